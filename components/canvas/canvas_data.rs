@@ -2,20 +2,38 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use app_units::Au;
 use azure::azure::AzFloat;
 use azure::azure_hl::{AntialiasMode, CapStyle, CompositionOp, JoinStyle};
 use azure::azure_hl::{BackendType, DrawOptions, DrawTarget, Pattern, StrokeOptions, SurfaceFormat};
-use azure::azure_hl::{Color, ColorPattern, DrawSurfaceOptions, Filter, PathBuilder};
+use azure::azure_hl::{Color, ColorPattern, DrawSurfaceOptions, Filter, Path, PathBuilder};
 use azure::azure_hl::{ExtendMode, GradientStop, LinearGradientPattern, RadialGradientPattern};
 use azure::azure_hl::SurfacePattern;
 use canvas_traits::canvas::*;
 use cssparser::RGBA;
 use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D};
+use filters::create_filters;
+use gfx::font::{FontDescriptor, FontGroup, FontRef, GlyphOutlineSegment, ShapingFlags, ShapingOptions};
+use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_context::FontContext;
+use gfx::font_template::FontTemplateDescriptor;
+use gfx::text::glyph::{ByteIndex, GlyphStore};
 use ipc_channel::ipc::IpcSender;
 use num_traits::ToPrimitive;
+use ordered_float::NotNan;
+use range::Range;
 use serde_bytes::ByteBuf;
 use std::mem;
+use std::rc::Rc;
 use std::sync::Arc;
+use style::Atom;
+use style::computed_values::font_variant_caps::T as FontVariantCaps;
+use style::values::computed::{Angle, Percentage};
+use style::values::computed::font::{FamilyName, FamilyNameSyntax, FontStretch, FontStyleAngle};
+use style::values::computed::font::{FontWeight, SingleFontFamily};
+use style::values::generics::NonNegative;
+use style::values::generics::font::FontStyle as GenericFontStyle;
+use unicode_script::{Script, get_script};
 use webrender_api;
 
 pub struct CanvasData<'a> {
@@ -69,20 +87,31 @@ impl<'a> CanvasData<'a> {
         // It discards the extra pixels (if any) that won't be painted
         let image_data = crop_image(image_data, image_size, source_rect);
 
+        // From spec https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
+        // When scaling up, if the imageSmoothingEnabled attribute is set to true, the user agent should attempt
+        // to apply a smoothing algorithm to the image data when it is scaled.
+        // Otherwise, the image must be rendered using nearest-neighbor interpolation.
+        let filter = match (smoothing_enabled, self.state.image_smoothing_quality) {
+            (false, _) => Filter::Point,
+            (true, ImageSmoothingQuality::High) => Filter::Good,
+            (true, _) => Filter::Linear,
+        };
         let writer = |draw_target: &DrawTarget| {
             write_image(&draw_target, image_data, source_rect.size, dest_rect,
-                        smoothing_enabled, self.state.draw_options.composition,
+                        filter, self.state.draw_options.composition,
                         self.state.draw_options.alpha);
         };
 
-        if self.need_to_draw_shadow() {
-            let rect = Rect::new(Point2D::new(dest_rect.origin.x as f32, dest_rect.origin.y as f32),
-                                 Size2D::new(dest_rect.size.width as f32, dest_rect.size.height as f32));
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            if self.need_to_draw_shadow() {
+                let rect = Rect::new(Point2D::new(dest_rect.origin.x as f32, dest_rect.origin.y as f32),
+                                     Size2D::new(dest_rect.size.width as f32, dest_rect.size.height as f32));
 
-            self.draw_with_shadow(&rect, writer);
-        } else {
-            writer(&self.drawtarget);
-        }
+                self.draw_with_shadow(draw_target, &rect, writer);
+            } else {
+                writer(draw_target);
+            }
+        });
     }
 
     pub fn draw_image_self(
@@ -112,8 +141,295 @@ impl<'a> CanvasData<'a> {
         }
     }
 
-    pub fn fill_text(&self, text: String, x: f64, y: f64, max_width: Option<f64>) {
-        error!("Unimplemented canvas2d.fillText. Values received: {}, {}, {}, {:?}.", text, x, y, max_width);
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-filltext
+    pub fn fill_text(
+        &self,
+        font_context: &mut FontContext<FontCacheThread>,
+        text: String,
+        x: f64,
+        y: f64,
+        max_width: Option<f64>,
+    ) {
+        if is_zero_size_gradient(&self.state.fill_style) {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        let layout = self.layout_text(font_context, &text);
+        let (path, bounds) = match self.text_path(&layout, x, y, max_width) {
+            Some(path) => path,
+            None => return,
+        };
+
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            if self.need_to_draw_shadow() {
+                self.draw_with_shadow(draw_target, &bounds, |new_draw_target: &DrawTarget| {
+                    new_draw_target.fill(&path,
+                                         self.state.fill_style.to_pattern_ref(),
+                                         &self.state.draw_options);
+                });
+            } else {
+                draw_target.fill(&path,
+                                 self.state.fill_style.to_pattern_ref(),
+                                 &self.state.draw_options);
+            }
+        });
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroketext
+    pub fn stroke_text(
+        &self,
+        font_context: &mut FontContext<FontCacheThread>,
+        text: String,
+        x: f64,
+        y: f64,
+        max_width: Option<f64>,
+    ) {
+        if is_zero_size_gradient(&self.state.stroke_style) {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        let layout = self.layout_text(font_context, &text);
+        let (path, bounds) = match self.text_path(&layout, x, y, max_width) {
+            Some(path) => path,
+            None => return,
+        };
+
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            if self.need_to_draw_shadow() {
+                self.draw_with_shadow(draw_target, &bounds, |new_draw_target: &DrawTarget| {
+                    new_draw_target.stroke(&path,
+                                           self.state.stroke_style.to_pattern_ref(),
+                                           &self.state.stroke_options(),
+                                           &self.state.draw_options);
+                });
+            } else {
+                draw_target.stroke(&path,
+                                   self.state.stroke_style.to_pattern_ref(),
+                                   &self.state.stroke_options(),
+                                   &self.state.draw_options);
+            }
+        });
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    pub fn measure_text(
+        &self,
+        font_context: &mut FontContext<FontCacheThread>,
+        text: String,
+        chan: IpcSender<TextMetrics>,
+    ) {
+        let layout = self.layout_text(font_context, &text);
+        let align_offset = self.text_align_offset(layout.width);
+        let baseline_offset = self.text_baseline_offset(&layout);
+
+        // Glyph outlines give us the ink bounds; text without any (e.g. only
+        // spaces) falls back to the advance width and the font's ascent.
+        let mut ink_bounds: Option<(Point2D<f64>, Point2D<f64>)> = None;
+        for (origin, outline) in self.glyph_outlines(&layout) {
+            for point in outline.iter().flat_map(outline_points) {
+                let point = Point2D::new(origin.x + point.x as f64, origin.y + point.y as f64);
+                ink_bounds = Some(match ink_bounds {
+                    Some((min, max)) => (min.min(point), max.max(point)),
+                    None => (point, point),
+                });
+            }
+        }
+        let (ink_min, ink_max) = ink_bounds.unwrap_or_else(|| {
+            (Point2D::new(0., -layout.ascent), Point2D::new(layout.width, layout.descent))
+        });
+
+        let em_scale = if layout.ascent + layout.descent > 0. {
+            layout.em_size / (layout.ascent + layout.descent)
+        } else {
+            0.
+        };
+        let metrics = TextMetrics {
+            width: layout.width,
+            actual_bounding_box_left: -(align_offset + ink_min.x),
+            actual_bounding_box_right: align_offset + ink_max.x,
+            font_bounding_box_ascent: layout.ascent - baseline_offset,
+            font_bounding_box_descent: layout.descent + baseline_offset,
+            actual_bounding_box_ascent: -(ink_min.y + baseline_offset),
+            actual_bounding_box_descent: ink_max.y + baseline_offset,
+            em_height_ascent: layout.ascent * em_scale - baseline_offset,
+            em_height_descent: layout.descent * em_scale + baseline_offset,
+            hanging_baseline: layout.hanging_baseline() - baseline_offset,
+            alphabetic_baseline: -baseline_offset,
+            ideographic_baseline: -(layout.descent + baseline_offset),
+        };
+        chan.send(metrics).unwrap();
+    }
+
+    /// Splits `text` into runs that can each be drawn with a single font and
+    /// script, and shapes them.
+    fn layout_text(&self, font_context: &mut FontContext<FontCacheThread>, text: &str) -> TextLayout {
+        let (descriptor, families) = font_descriptor(&self.state.font_style);
+        let mut font_group = FontGroup::from_families(descriptor, families.iter());
+
+        // https://html.spec.whatwg.org/multipage/#text-preparation-algorithm
+        // Step 2: Replace all ASCII whitespace with U+0020 SPACE characters.
+        let mut runs: Vec<(FontRef, Script, String)> = vec![];
+        for character in text.chars() {
+            let character = if character.is_ascii_whitespace() { ' ' } else { character };
+            let font = match font_group.find_by_codepoint(font_context, character) {
+                Some(font) => font,
+                None => continue,
+            };
+            let script = get_script(character);
+            if let Some(&mut (ref run_font, ref mut run_script, ref mut run_text)) = runs.last_mut() {
+                if Rc::ptr_eq(run_font, &font) && is_compatible_script(*run_script, script) {
+                    if !is_specific_script(*run_script) {
+                        *run_script = script;
+                    }
+                    run_text.push(character);
+                    continue;
+                }
+            }
+            runs.push((font, script, character.to_string()));
+        }
+
+        // FIXME: Mixed-direction text should be reordered with the bidi
+        // algorithm; for now every run is laid out in the context's direction.
+        let mut flags = ShapingFlags::empty();
+        if self.state.direction == Direction::Rtl {
+            flags.insert(ShapingFlags::RTL_FLAG);
+        }
+        let mut runs: Vec<ShapedRun> = runs.into_iter().map(|(font, script, text)| {
+            let options = ShapingOptions {
+                letter_spacing: None,
+                word_spacing: (Au(0), NotNan::new(0.).unwrap()),
+                script: script,
+                flags: flags,
+            };
+            let glyphs = font.borrow_mut().shape_text(&text, &options);
+            ShapedRun { font: font, glyphs: glyphs }
+        }).collect();
+        if self.state.direction == Direction::Rtl {
+            runs.reverse();
+        }
+
+        let width = runs.iter().map(|run| run.advance()).fold(Au(0), |sum, advance| sum + advance);
+        let (ascent, descent, em_size) = match font_group.first(font_context) {
+            Some(font) => {
+                let font = font.borrow();
+                let metrics = &font.metrics;
+                (metrics.ascent.to_f64_px(), metrics.descent.to_f64_px(), metrics.em_size.to_f64_px())
+            },
+            None => (0., 0., 0.),
+        };
+
+        TextLayout {
+            runs: runs,
+            width: width.to_f64_px(),
+            ascent: ascent,
+            descent: descent,
+            em_size: em_size,
+        }
+    }
+
+    /// The outline of every glyph in `layout`, together with its origin
+    /// relative to the start of the text on the alphabetic baseline.
+    fn glyph_outlines(&self, layout: &TextLayout) -> Vec<(Point2D<f64>, Vec<GlyphOutlineSegment>)> {
+        let mut outlines = vec![];
+        let mut pen = Au(0);
+        for run in &layout.runs {
+            let font = run.font.borrow();
+            let range = Range::new(ByteIndex(0), run.glyphs.len());
+            for glyph in run.glyphs.iter_glyphs_for_byte_range(&range) {
+                let offset = glyph.offset().unwrap_or(Point2D::zero());
+                if let Some(outline) = font.glyph_outline(glyph.id()) {
+                    let origin = Point2D::new((pen + offset.x).to_f64_px(), offset.y.to_f64_px());
+                    outlines.push((origin, outline));
+                }
+                pen += glyph.advance();
+            }
+        }
+        outlines
+    }
+
+    /// Builds the path of `layout` drawn at `(x, y)`, honouring the text
+    /// alignment, baseline and `maxWidth`, and returns it along with its
+    /// bounds for shadow drawing.
+    /// <https://html.spec.whatwg.org/multipage/#text-preparation-algorithm>
+    fn text_path(&self, layout: &TextLayout, x: f64, y: f64, max_width: Option<f64>) -> Option<(Path, Rect<f32>)> {
+        // Step 1.
+        if let Some(max_width) = max_width {
+            if !max_width.is_finite() || max_width <= 0. {
+                return None;
+            }
+        }
+        if layout.runs.is_empty() {
+            return None;
+        }
+
+        // Step 12: Squeeze the text horizontally if it's wider than maxWidth.
+        let scale = match max_width {
+            Some(max_width) if layout.width > max_width => max_width / layout.width,
+            _ => 1.,
+        };
+        let origin_x = x + self.text_align_offset(layout.width * scale);
+        let origin_y = y + self.text_baseline_offset(layout);
+        let to_canvas = |glyph_origin: Point2D<f64>, point: Point2D<f32>| {
+            Point2D::new((origin_x + (glyph_origin.x + point.x as f64) * scale) as AzFloat,
+                         (origin_y + glyph_origin.y + point.y as f64) as AzFloat)
+        };
+
+        let path_builder = self.drawtarget.create_path_builder();
+        for (glyph_origin, outline) in self.glyph_outlines(layout) {
+            for segment in outline {
+                match segment {
+                    GlyphOutlineSegment::MoveTo(point) => {
+                        path_builder.move_to(to_canvas(glyph_origin, point))
+                    },
+                    GlyphOutlineSegment::LineTo(point) => {
+                        path_builder.line_to(to_canvas(glyph_origin, point))
+                    },
+                    GlyphOutlineSegment::QuadTo(control, point) => {
+                        path_builder.quadratic_curve_to(&to_canvas(glyph_origin, control),
+                                                        &to_canvas(glyph_origin, point))
+                    },
+                    GlyphOutlineSegment::CubicTo(control1, control2, point) => {
+                        path_builder.bezier_curve_to(&to_canvas(glyph_origin, control1),
+                                                     &to_canvas(glyph_origin, control2),
+                                                     &to_canvas(glyph_origin, point))
+                    },
+                    GlyphOutlineSegment::Close => path_builder.close(),
+                }
+            }
+        }
+
+        let bounds = Rect::new(Point2D::new(origin_x, origin_y - layout.ascent),
+                               Size2D::new(layout.width * scale, layout.ascent + layout.descent));
+        Some((path_builder.finish(), bounds.to_f32()))
+    }
+
+    /// The horizontal offset from the anchor point to the start of the text.
+    /// <https://html.spec.whatwg.org/multipage/#text-preparation-algorithm>
+    /// Step 10.
+    fn text_align_offset(&self, width: f64) -> f64 {
+        match (self.state.text_align, self.state.direction) {
+            (TextAlign::Left, _) |
+            (TextAlign::Start, Direction::Ltr) |
+            (TextAlign::End, Direction::Rtl) => 0.,
+            (TextAlign::Right, _) |
+            (TextAlign::Start, Direction::Rtl) |
+            (TextAlign::End, Direction::Ltr) => -width,
+            (TextAlign::Center, _) => -width / 2.,
+        }
+    }
+
+    /// The vertical offset from the anchor point to the alphabetic baseline.
+    /// <https://html.spec.whatwg.org/multipage/#text-preparation-algorithm>
+    /// Step 11.
+    fn text_baseline_offset(&self, layout: &TextLayout) -> f64 {
+        match self.state.text_baseline {
+            TextBaseline::Top => layout.ascent,
+            TextBaseline::Hanging => layout.hanging_baseline(),
+            TextBaseline::Middle => (layout.ascent - layout.descent) / 2.,
+            TextBaseline::Alphabetic => 0.,
+            TextBaseline::Ideographic |
+            TextBaseline::Bottom => -layout.descent,
+        }
     }
 
     pub fn fill_rect(&self, rect: &Rect<f32>) {
@@ -136,15 +452,17 @@ impl<'a> CanvasData<'a> {
             }
         );
 
-        if self.need_to_draw_shadow() {
-            self.draw_with_shadow(&draw_rect, |new_draw_target: &DrawTarget| {
-                new_draw_target.fill_rect(&draw_rect, self.state.fill_style.to_pattern_ref(),
-                                          Some(&self.state.draw_options));
-            });
-        } else {
-            self.drawtarget.fill_rect(&draw_rect, self.state.fill_style.to_pattern_ref(),
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            if self.need_to_draw_shadow() {
+                self.draw_with_shadow(draw_target, &draw_rect, |new_draw_target: &DrawTarget| {
+                    new_draw_target.fill_rect(&draw_rect, self.state.fill_style.to_pattern_ref(),
+                                              Some(&self.state.draw_options));
+                });
+            } else {
+                draw_target.fill_rect(&draw_rect, self.state.fill_style.to_pattern_ref(),
                                       Some(&self.state.draw_options));
-        }
+            }
+        });
     }
 
     pub fn clear_rect(&self, rect: &Rect<f32>) {
@@ -156,30 +474,32 @@ impl<'a> CanvasData<'a> {
             return; // Paint nothing if gradient size is zero.
         }
 
-        if self.need_to_draw_shadow() {
-            self.draw_with_shadow(&rect, |new_draw_target: &DrawTarget| {
-                new_draw_target.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
-                                            &self.state.stroke_opts, &self.state.draw_options);
-            });
-        } else if rect.size.width == 0. || rect.size.height == 0. {
-            let cap = match self.state.stroke_opts.line_join {
-                JoinStyle::Round => CapStyle::Round,
-                _ => CapStyle::Butt
-            };
-
-            let stroke_opts =
-                StrokeOptions::new(self.state.stroke_opts.line_width,
-                                   self.state.stroke_opts.line_join,
-                                   cap,
-                                   self.state.stroke_opts.miter_limit,
-                                   self.state.stroke_opts.mDashPattern);
-            self.drawtarget.stroke_line(rect.origin, rect.bottom_right(),
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            if self.need_to_draw_shadow() {
+                self.draw_with_shadow(draw_target, &rect, |new_draw_target: &DrawTarget| {
+                    new_draw_target.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
+                                                &self.state.stroke_options(), &self.state.draw_options);
+                });
+            } else if rect.size.width == 0. || rect.size.height == 0. {
+                let cap = match self.state.stroke_opts.line_join {
+                    JoinStyle::Round => CapStyle::Round,
+                    _ => CapStyle::Butt
+                };
+
+                let stroke_opts =
+                    StrokeOptions::new(self.state.stroke_opts.line_width,
+                                       self.state.stroke_opts.line_join,
+                                       cap,
+                                       self.state.stroke_opts.miter_limit,
+                                       &self.state.dash_pattern);
+                draw_target.stroke_line(rect.origin, rect.bottom_right(),
                                         self.state.stroke_style.to_pattern_ref(),
                                         &stroke_opts, &self.state.draw_options);
-        } else {
-            self.drawtarget.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
-                                        &self.state.stroke_opts, &self.state.draw_options);
-        }
+            } else {
+                draw_target.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
+                                        &self.state.stroke_options(), &self.state.draw_options);
+            }
+        });
     }
 
     pub fn begin_path(&mut self) {
@@ -195,9 +515,11 @@ impl<'a> CanvasData<'a> {
             return; // Paint nothing if gradient size is zero.
         }
 
-        self.drawtarget.fill(&self.path_builder.finish(),
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            draw_target.fill(&self.path_builder.finish(),
                              self.state.fill_style.to_pattern_ref(),
                              &self.state.draw_options);
+        });
     }
 
     pub fn stroke(&self) {
@@ -205,16 +527,47 @@ impl<'a> CanvasData<'a> {
             return; // Paint nothing if gradient size is zero.
         }
 
-        self.drawtarget.stroke(&self.path_builder.finish(),
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            draw_target.stroke(&self.path_builder.finish(),
                                self.state.stroke_style.to_pattern_ref(),
-                               &self.state.stroke_opts,
+                               &self.state.stroke_options(),
                                &self.state.draw_options);
+        });
     }
 
     pub fn clip(&self) {
         self.drawtarget.push_clip(&self.path_builder.finish());
     }
 
+    pub fn fill_path(&self, segments: &[PathSegment], _fill_rule: FillRule) {
+        if is_zero_size_gradient(&self.state.fill_style) {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            draw_target.fill(&self.build_path(segments),
+                             self.state.fill_style.to_pattern_ref(),
+                             &self.state.draw_options);
+        });
+    }
+
+    pub fn stroke_path(&self, segments: &[PathSegment]) {
+        if is_zero_size_gradient(&self.state.stroke_style) {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        self.draw_filtered(|draw_target: &DrawTarget| {
+            draw_target.stroke(&self.build_path(segments),
+                               self.state.stroke_style.to_pattern_ref(),
+                               &self.state.stroke_options(),
+                               &self.state.draw_options);
+        });
+    }
+
+    pub fn clip_path(&self, segments: &[PathSegment], _fill_rule: FillRule) {
+        self.drawtarget.push_clip(&self.build_path(segments));
+    }
+
     pub fn is_point_in_path(
         &mut self,
         x: f64,
@@ -228,6 +581,83 @@ impl<'a> CanvasData<'a> {
         chan.send(result).unwrap();
     }
 
+    pub fn is_point_in_path_with_path(
+        &self,
+        segments: &[PathSegment],
+        x: f64,
+        y: f64,
+        _fill_rule: FillRule,
+        chan: IpcSender<bool>
+    ) {
+        let result = self.build_path(segments).contains_point(x, y, &self.state.transform);
+        chan.send(result).unwrap();
+    }
+
+    pub fn is_point_in_stroke(&mut self, x: f64, y: f64, chan: IpcSender<bool>) {
+        let path = self.path_builder.finish();
+        let result = self.stroke_contains_point(&path, x, y);
+        self.path_builder = path.copy_to_builder();
+        chan.send(result).unwrap();
+    }
+
+    pub fn is_point_in_stroke_with_path(
+        &self,
+        segments: &[PathSegment],
+        x: f64,
+        y: f64,
+        chan: IpcSender<bool>
+    ) {
+        let result = self.stroke_contains_point(&self.build_path(segments), x, y);
+        chan.send(result).unwrap();
+    }
+
+    /// Replays the segments of a `Path2D` into an Azure path.
+    fn build_path(&self, segments: &[PathSegment]) -> Path {
+        let path_builder = self.drawtarget.create_path_builder();
+        for segment in segments {
+            match *segment {
+                PathSegment::MoveTo(point) => path_builder.move_to(point),
+                PathSegment::LineTo(point) => path_builder.line_to(point),
+                PathSegment::QuadraticCurveTo(ref cp, ref point) => {
+                    path_builder.quadratic_curve_to(cp, point)
+                },
+                PathSegment::BezierCurveTo(ref cp1, ref cp2, ref point) => {
+                    path_builder.bezier_curve_to(cp1, cp2, point)
+                },
+                PathSegment::ClosePath => path_builder.close(),
+            }
+        }
+        path_builder.finish()
+    }
+
+    /// Azure can't hit-test strokes, so this strokes the path onto a single
+    /// pixel whose centre is the point being tested and checks whether
+    /// anything was painted there.
+    /// <https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinstroke>
+    fn stroke_contains_point(&self, path: &Path, x: f64, y: f64) -> bool {
+        if !x.is_finite() || !y.is_finite() {
+            return false;
+        }
+
+        let draw_target = self.drawtarget.create_similar_draw_target(&Size2D::new(1, 1),
+                                                                     self.drawtarget.get_format());
+        let matrix = Transform2D::identity()
+            .pre_translate(Vector2D::new(0.5 - x as f32, 0.5 - y as f32))
+            .pre_mul(&self.state.transform);
+        draw_target.set_transform(&matrix);
+        let pattern = Pattern::Color(ColorPattern::new(Color::black()));
+        draw_target.stroke(path,
+                           pattern.to_pattern_ref(),
+                           &self.state.stroke_options(),
+                           &DrawOptions::new(1.0, CompositionOp::Over, AntialiasMode::None));
+
+        let mut painted = false;
+        draw_target.snapshot().get_data_surface().with_data(|pixel| {
+            painted = pixel[3] != 0;
+        });
+        painted
+    }
+
     pub fn move_to(&self, point: &Point2D<AzFloat>) {
         self.path_builder.move_to(*point)
     }
@@ -371,11 +801,45 @@ impl<'a> CanvasData<'a> {
         self.state.stroke_opts.miter_limit = limit;
     }
 
+    pub fn set_line_dash(&mut self, segments: Vec<AzFloat>) {
+        self.state.line_dash = segments;
+        self.state.update_dash_pattern();
+    }
+
+    pub fn set_line_dash_offset(&mut self, offset: AzFloat) {
+        self.state.line_dash_offset = offset;
+        self.state.update_dash_pattern();
+    }
+
+    pub fn set_image_smoothing_quality(&mut self, quality: ImageSmoothingQuality) {
+        self.state.image_smoothing_quality = quality;
+    }
+
+    pub fn set_filter(&mut self, filters: Vec<FilterFunction>) {
+        self.state.filters = filters;
+    }
+
     pub fn set_transform(&mut self, transform: &Transform2D<f32>) {
         self.state.transform = transform.clone();
         self.drawtarget.set_transform(transform)
     }
 
+    pub fn set_font(&mut self, font_style: CanvasFontStyle) {
+        self.state.font_style = font_style;
+    }
+
+    pub fn set_text_align(&mut self, text_align: TextAlign) {
+        self.state.text_align = text_align;
+    }
+
+    pub fn set_text_baseline(&mut self, text_baseline: TextBaseline) {
+        self.state.text_baseline = text_baseline;
+    }
+
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.state.direction = direction;
+    }
+
     pub fn set_global_alpha(&mut self, alpha: f32) {
         self.state.draw_options.alpha = alpha;
     }
@@ -572,6 +1036,33 @@ impl<'a> CanvasData<'a> {
         self.state.shadow_color = value;
     }
 
+    /// Runs `draw` against the canvas or, when a filter is set, against a
+    /// transparent layer that is then filtered onto the canvas.
+    /// <https://html.spec.whatwg.org/multipage/#drawing-model>
+    fn draw_filtered<F>(&self, draw: F)
+        where F: FnOnce(&DrawTarget)
+    {
+        if self.state.filters.is_empty() {
+            return draw(&self.drawtarget);
+        }
+
+        let size = self.drawtarget.get_size();
+        let size = Size2D::new(size.width, size.height);
+        let layer = self.drawtarget.create_similar_draw_target(&size, self.drawtarget.get_format());
+        layer.set_transform(&self.state.transform);
+        draw(&layer);
+
+        let (filter, opacity) = create_filters(&self.drawtarget, &layer, &self.state.filters);
+        let bounds = Rect::new(Point2D::zero(), Size2D::new(size.width as AzFloat, size.height as AzFloat));
+        self.drawtarget.set_transform(&Transform2D::identity());
+        self.drawtarget.draw_filter(&filter,
+                                    &bounds,
+                                    &Point2D::zero(),
+                                    DrawOptions::new(opacity, self.state.draw_options.composition,
+                                                     AntialiasMode::None));
+        self.drawtarget.set_transform(&self.state.transform);
+    }
+
     // https://html.spec.whatwg.org/multipage/#when-shadows-are-drawn
     fn need_to_draw_shadow(&self) -> bool {
         self.state.shadow_color.a != 0.0f32 &&
@@ -591,20 +1082,20 @@ impl<'a> CanvasData<'a> {
         draw_target
     }
 
-    fn draw_with_shadow<F>(&self, rect: &Rect<f32>, draw_shadow_source: F)
+    fn draw_with_shadow<F>(&self, draw_target: &DrawTarget, rect: &Rect<f32>, draw_shadow_source: F)
         where F: FnOnce(&DrawTarget)
     {
         let shadow_src_rect = self.state.transform.transform_rect(rect);
         let new_draw_target = self.create_draw_target_for_shadow(&shadow_src_rect);
         draw_shadow_source(&new_draw_target);
-        self.drawtarget.draw_surface_with_shadow(new_draw_target.snapshot(),
-                                                 &Point2D::new(shadow_src_rect.origin.x as AzFloat,
-                                                               shadow_src_rect.origin.y as AzFloat),
-                                                 &self.state.shadow_color,
-                                                 &Vector2D::new(self.state.shadow_offset_x as AzFloat,
-                                                                self.state.shadow_offset_y as AzFloat),
-                                                 (self.state.shadow_blur / 2.0f64) as AzFloat,
-                                                 self.state.draw_options.composition);
+        draw_target.draw_surface_with_shadow(new_draw_target.snapshot(),
+                                             &Point2D::new(shadow_src_rect.origin.x as AzFloat,
+                                                           shadow_src_rect.origin.y as AzFloat),
+                                             &self.state.shadow_color,
+                                             &Vector2D::new(self.state.shadow_offset_x as AzFloat,
+                                                            self.state.shadow_offset_y as AzFloat),
+                                             (self.state.shadow_blur / 2.0f64) as AzFloat,
+                                             self.state.draw_options.composition);
     }
 
     /// It reads image data from the canvas
@@ -658,13 +1149,25 @@ struct CanvasPaintState<'a> {
     draw_options: DrawOptions,
     fill_style: Pattern,
     stroke_style: Pattern,
+    /// The stroke options other than the line dash, which is kept below.
     stroke_opts: StrokeOptions<'a>,
+    /// The dash list, as set by `setLineDash()`.
+    line_dash: Vec<AzFloat>,
+    line_dash_offset: AzFloat,
+    /// The dash list that is handed to Azure, with `line_dash_offset` applied.
+    dash_pattern: Vec<AzFloat>,
+    image_smoothing_quality: ImageSmoothingQuality,
+    filters: Vec<FilterFunction>,
     /// The current 2D transform matrix.
     transform: Transform2D<f32>,
     shadow_offset_x: f64,
     shadow_offset_y: f64,
     shadow_blur: f64,
     shadow_color: Color,
+    font_style: CanvasFontStyle,
+    text_align: TextAlign,
+    text_baseline: TextBaseline,
+    direction: Direction,
 }
 
 impl<'a> CanvasPaintState<'a> {
@@ -674,13 +1177,146 @@ impl<'a> CanvasPaintState<'a> {
             fill_style: Pattern::Color(ColorPattern::new(Color::black())),
             stroke_style: Pattern::Color(ColorPattern::new(Color::black())),
             stroke_opts: StrokeOptions::new(1.0, JoinStyle::MiterOrBevel, CapStyle::Butt, 10.0, &[]),
+            line_dash: vec![],
+            line_dash_offset: 0.0,
+            dash_pattern: vec![],
+            image_smoothing_quality: ImageSmoothingQuality::Low,
+            filters: vec![],
             transform: Transform2D::identity(),
             shadow_offset_x: 0.0,
             shadow_offset_y: 0.0,
             shadow_blur: 0.0,
             shadow_color: Color::transparent(),
+            font_style: CanvasFontStyle::default(),
+            text_align: TextAlign::Start,
+            text_baseline: TextBaseline::Alphabetic,
+            direction: Direction::Ltr,
         }
     }
+
+    fn stroke_options(&self) -> StrokeOptions {
+        StrokeOptions::new(self.stroke_opts.line_width,
+                           self.stroke_opts.line_join,
+                           self.stroke_opts.line_cap,
+                           self.stroke_opts.miter_limit,
+                           &self.dash_pattern)
+    }
+
+    /// Azure strokes dashes from the start of the pattern, so the dash offset
+    /// is applied by rotating the pattern instead. When the offset lands in a
+    /// gap, the rotated pattern has to start with a zero-length dash, which
+    /// shows up as a dot in that gap if the line has round or square caps.
+    /// <https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset>
+    fn update_dash_pattern(&mut self) {
+        let total: AzFloat = self.line_dash.iter().sum();
+        if total <= 0.0 || !total.is_finite() {
+            // A dash list that is empty or all zeros draws a solid line.
+            self.dash_pattern = vec![];
+            return;
+        }
+
+        let mut offset = self.line_dash_offset % total;
+        if offset < 0.0 {
+            offset += total;
+        }
+        let mut index = 0;
+        while index < self.line_dash.len() - 1 && offset >= self.line_dash[index] {
+            offset -= self.line_dash[index];
+            index += 1;
+        }
+
+        let in_gap = index % 2 == 1;
+        let mut pattern = vec![];
+        if in_gap {
+            pattern.push(0.0);
+        }
+        pattern.push(self.line_dash[index] - offset);
+        pattern.extend_from_slice(&self.line_dash[index + 1..]);
+        pattern.extend_from_slice(&self.line_dash[..index]);
+        if offset > 0.0 || in_gap {
+            // The part of the entry that the offset skipped, followed by an
+            // empty gap that joins a split dash back up.
+            pattern.push(offset);
+            if !in_gap {
+                pattern.push(0.0);
+            }
+        }
+        self.dash_pattern = pattern;
+    }
+}
+
+/// A piece of text shaped with a single font.
+struct ShapedRun {
+    font: FontRef,
+    glyphs: Arc<GlyphStore>,
+}
+
+impl ShapedRun {
+    fn advance(&self) -> Au {
+        self.glyphs.advance_for_byte_range(&Range::new(ByteIndex(0), self.glyphs.len()), Au(0))
+    }
+}
+
+/// Text shaped for drawing or measuring, in visual order. Metrics are in
+/// pixels and come from the first available font of the context's font.
+struct TextLayout {
+    runs: Vec<ShapedRun>,
+    width: f64,
+    ascent: f64,
+    descent: f64,
+    em_size: f64,
+}
+
+impl TextLayout {
+    /// How far above the alphabetic baseline the hanging baseline sits.
+    // FIXME: Read the hanging baseline from the font's BASE table.
+    fn hanging_baseline(&self) -> f64 {
+        self.ascent * 0.8
+    }
+}
+
+/// Converts the font sent by script into what the font context expects.
+fn font_descriptor(font_style: &CanvasFontStyle) -> (FontDescriptor, Vec<SingleFontFamily>) {
+    let style = match font_style.style {
+        CanvasFontSlant::Normal => GenericFontStyle::Normal,
+        CanvasFontSlant::Italic => GenericFontStyle::Italic,
+        CanvasFontSlant::Oblique(degrees) => GenericFontStyle::Oblique(FontStyleAngle(Angle::Deg(degrees))),
+    };
+    let stretch = FontStretch(NonNegative(Percentage(font_style.stretch / 100.)));
+    let descriptor = FontDescriptor {
+        template_descriptor: FontTemplateDescriptor::new(FontWeight(font_style.weight), stretch, style),
+        variant: if font_style.small_caps { FontVariantCaps::SmallCaps } else { FontVariantCaps::Normal },
+        pt_size: font_style.size,
+    };
+    let families = font_style.families.iter().map(|family| match *family {
+        CanvasFontFamily::Named(ref name) => SingleFontFamily::FamilyName(FamilyName {
+            name: Atom::from(&**name),
+            syntax: FamilyNameSyntax::Quoted,
+        }),
+        CanvasFontFamily::Generic(ref name) => SingleFontFamily::Generic(Atom::from(&**name)),
+    }).collect();
+    (descriptor, families)
+}
+
+/// Can a character with script `b` continue a text run with script `a`?
+fn is_compatible_script(a: Script, b: Script) -> bool {
+    a == b || !is_specific_script(a) || !is_specific_script(b)
+}
+
+/// Returns true if the script is not invalid or inherited.
+fn is_specific_script(script: Script) -> bool {
+    script != Script::Common && script != Script::Inherited
+}
+
+/// The points of an outline segment, for computing ink bounds.
+fn outline_points(segment: &GlyphOutlineSegment) -> Vec<Point2D<f32>> {
+    match *segment {
+        GlyphOutlineSegment::MoveTo(point) |
+        GlyphOutlineSegment::LineTo(point) => vec![point],
+        GlyphOutlineSegment::QuadTo(control, point) => vec![control, point],
+        GlyphOutlineSegment::CubicTo(control1, control2, point) => vec![control1, control2, point],
+        GlyphOutlineSegment::Close => vec![],
+    }
 }
 
 fn is_zero_size_gradient(pattern: &Pattern) -> bool {
@@ -730,13 +1366,13 @@ fn crop_image(
 /// image_data: Pixel information of the image to be written. It takes RGBA8
 /// image_size: The size of the image to be written
 /// dest_rect: Area of the destination target where the pixels will be copied
-/// smoothing_enabled: It determines if smoothing is applied to the image result
+/// filter: The filter used to sample the image when it is scaled
 fn write_image(
     draw_target: &DrawTarget,
     mut image_data: Vec<u8>,
     image_size: Size2D<f64>,
     dest_rect: Rect<f64>,
-    smoothing_enabled: bool,
+    filter: Filter,
     composition_op: CompositionOp,
     global_alpha: f32
 ) {
@@ -747,15 +1383,6 @@ fn write_image(
     // rgba -> bgra
     byte_swap(&mut image_data);
 
-    // From spec https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
-    // When scaling up, if the imageSmoothingEnabled attribute is set to true, the user agent should attempt
-    // to apply a smoothing algorithm to the image data when it is scaled.
-    // Otherwise, the image must be rendered using nearest-neighbor interpolation.
-    let filter = if smoothing_enabled {
-        Filter::Linear
-    } else {
-        Filter::Point
-    };
     // azure_hl operates with integers. We need to cast the image size
     let image_size = image_size.to_i32();
 
@@ -6,6 +6,8 @@ use azure::azure_hl::AntialiasMode;
 use canvas_data::*;
 use canvas_traits::canvas::*;
 use euclid::Size2D;
+use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_context::FontContext;
 use ipc_channel::ipc::{self, IpcSender};
 use std::borrow::ToOwned;
 use std::collections::HashMap;
@@ -15,22 +17,25 @@ use webrender_api;
 pub struct CanvasPaintThread <'a> {
     canvases: HashMap<CanvasId, CanvasData<'a>>,
     next_canvas_id: CanvasId,
+    /// The fonts used to draw text, shared by every canvas of this thread.
+    font_context: FontContext<FontCacheThread>,
 }
 
 impl<'a> CanvasPaintThread <'a> {
-    fn new() -> CanvasPaintThread <'a> {
+    fn new(font_cache_thread: FontCacheThread) -> CanvasPaintThread <'a> {
         CanvasPaintThread {
             canvases: HashMap::new(),
             next_canvas_id: CanvasId(0),
+            font_context: FontContext::new(font_cache_thread),
         }
     }
 
     /// Creates a new `CanvasPaintThread` and returns an `IpcSender` to
     /// communicate with it.
-    pub fn start() -> IpcSender<CanvasMsg> {
+    pub fn start(font_cache_thread: FontCacheThread) -> IpcSender<CanvasMsg> {
         let (sender, receiver) = ipc::channel::<CanvasMsg>().unwrap();
         thread::Builder::new().name("CanvasThread".to_owned()).spawn(move || {
-            let mut canvas_paint_thread = CanvasPaintThread::new();
+            let mut canvas_paint_thread = CanvasPaintThread::new(font_cache_thread);
             loop {
                 match receiver.recv() {
                     Ok(msg) => {
@@ -102,7 +107,16 @@ impl<'a> CanvasPaintThread <'a> {
     fn process_canvas_2d_message(&mut self, message: Canvas2dMsg, canvas_id: CanvasId) {
         match message {
             Canvas2dMsg::FillText(text, x, y, max_width) => {
-                self.canvas(canvas_id).fill_text(text, x, y, max_width)
+                let canvas = self.canvases.get_mut(&canvas_id).expect("Bogus canvas id");
+                canvas.fill_text(&mut self.font_context, text, x, y, max_width)
+            },
+            Canvas2dMsg::StrokeText(text, x, y, max_width) => {
+                let canvas = self.canvases.get_mut(&canvas_id).expect("Bogus canvas id");
+                canvas.stroke_text(&mut self.font_context, text, x, y, max_width)
+            },
+            Canvas2dMsg::MeasureText(text, chan) => {
+                let canvas = self.canvases.get_mut(&canvas_id).expect("Bogus canvas id");
+                canvas.measure_text(&mut self.font_context, text, chan)
             },
             Canvas2dMsg::FillRect(ref rect) => {
                 self.canvas(canvas_id).fill_rect(rect)
@@ -131,6 +145,24 @@ impl<'a> CanvasPaintThread <'a> {
             Canvas2dMsg::IsPointInPath(x, y, fill_rule, chan) => {
                 self.canvas(canvas_id).is_point_in_path(x, y, fill_rule, chan)
             },
+            Canvas2dMsg::FillPath(segments, fill_rule) => {
+                self.canvas(canvas_id).fill_path(&segments, fill_rule)
+            },
+            Canvas2dMsg::StrokePath(segments) => {
+                self.canvas(canvas_id).stroke_path(&segments)
+            },
+            Canvas2dMsg::ClipPath(segments, fill_rule) => {
+                self.canvas(canvas_id).clip_path(&segments, fill_rule)
+            },
+            Canvas2dMsg::IsPointInPathWithPath(segments, x, y, fill_rule, chan) => {
+                self.canvas(canvas_id).is_point_in_path_with_path(&segments, x, y, fill_rule, chan)
+            },
+            Canvas2dMsg::IsPointInStroke(x, y, chan) => {
+                self.canvas(canvas_id).is_point_in_stroke(x, y, chan)
+            },
+            Canvas2dMsg::IsPointInStrokeWithPath(segments, x, y, chan) => {
+                self.canvas(canvas_id).is_point_in_stroke_with_path(&segments, x, y, chan)
+            },
             Canvas2dMsg::DrawImage(
                 imagedata,
                 image_size,
@@ -235,9 +267,33 @@ impl<'a> CanvasPaintThread <'a> {
             Canvas2dMsg::SetMiterLimit(limit) => {
                 self.canvas(canvas_id).set_miter_limit(limit)
             },
+            Canvas2dMsg::SetLineDash(segments) => {
+                self.canvas(canvas_id).set_line_dash(segments)
+            },
+            Canvas2dMsg::SetLineDashOffset(offset) => {
+                self.canvas(canvas_id).set_line_dash_offset(offset)
+            },
+            Canvas2dMsg::SetImageSmoothingQuality(quality) => {
+                self.canvas(canvas_id).set_image_smoothing_quality(quality)
+            },
+            Canvas2dMsg::SetFilter(filters) => {
+                self.canvas(canvas_id).set_filter(filters)
+            },
             Canvas2dMsg::SetTransform(ref matrix) => {
                 self.canvas(canvas_id).set_transform(matrix)
             },
+            Canvas2dMsg::SetFont(font_style) => {
+                self.canvas(canvas_id).set_font(font_style)
+            },
+            Canvas2dMsg::SetTextAlign(text_align) => {
+                self.canvas(canvas_id).set_text_align(text_align)
+            },
+            Canvas2dMsg::SetTextBaseline(text_baseline) => {
+                self.canvas(canvas_id).set_text_baseline(text_baseline)
+            },
+            Canvas2dMsg::SetDirection(direction) => {
+                self.canvas(canvas_id).set_direction(direction)
+            },
             Canvas2dMsg::SetGlobalAlpha(alpha) => {
                 self.canvas(canvas_id).set_global_alpha(alpha)
             },
@@ -0,0 +1,151 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Support for the canvas `filter` attribute, built out of Azure filter nodes.
+
+use azure::azure::AzFloat;
+use azure::azure_hl::{ColorMatrixAttribute, ColorMatrixInput, CompositeInput, DrawTarget};
+use azure::azure_hl::{FilterNode, FilterType, LinearTransferAttribute, LinearTransferInput};
+use azure::azure_hl::{GaussianBlurAttribute, GaussianBlurInput, Matrix5x4};
+use azure::azure_hl::{TableTransferAttribute, TableTransferInput};
+use canvas_traits::canvas::FilterFunction;
+
+/// Creates a filter pipeline that applies `filters` to the contents of
+/// `layer`. Returns the end of the pipeline, along with the opacity it should
+/// be drawn with, since `opacity()` is cheaper to apply while compositing.
+pub fn create_filters(draw_target: &DrawTarget,
+                      layer: &DrawTarget,
+                      filters: &[FilterFunction])
+                      -> (FilterNode, AzFloat) {
+    let mut opacity = 1.0;
+    let mut filter = draw_target.create_filter(FilterType::Composite);
+    filter.set_input(CompositeInput, &layer.snapshot());
+    for function in filters {
+        match *function {
+            FilterFunction::Blur(radius) => {
+                let blur = draw_target.create_filter(FilterType::GaussianBlur);
+                blur.set_attribute(GaussianBlurAttribute::StdDeviation(radius));
+                blur.set_input(GaussianBlurInput, &filter);
+                filter = blur
+            },
+            FilterFunction::Brightness(amount) => {
+                filter = linear_transfer(draw_target, &filter, amount, 0.0)
+            },
+            FilterFunction::Contrast(amount) => {
+                filter = linear_transfer(draw_target, &filter, amount, -0.5 * amount + 0.5)
+            },
+            FilterFunction::Grayscale(amount) => {
+                filter = color_matrix(draw_target, &filter, grayscale(amount.min(1.0)))
+            },
+            FilterFunction::HueRotate(angle) => {
+                filter = color_matrix(draw_target, &filter, hue_rotate(angle))
+            },
+            FilterFunction::Invert(amount) => {
+                let amount = amount.min(1.0);
+                let table = [amount, 1.0 - amount];
+                let invert = draw_target.create_filter(FilterType::TableTransfer);
+                invert.set_attribute(TableTransferAttribute::DisableR(false));
+                invert.set_attribute(TableTransferAttribute::DisableG(false));
+                invert.set_attribute(TableTransferAttribute::DisableB(false));
+                invert.set_attribute(TableTransferAttribute::TableR(&table));
+                invert.set_attribute(TableTransferAttribute::TableG(&table));
+                invert.set_attribute(TableTransferAttribute::TableB(&table));
+                invert.set_input(TableTransferInput, &filter);
+                filter = invert
+            },
+            FilterFunction::Opacity(amount) => opacity *= amount.min(1.0),
+            FilterFunction::Saturate(amount) => {
+                filter = color_matrix(draw_target, &filter, saturate(amount))
+            },
+            FilterFunction::Sepia(amount) => {
+                filter = color_matrix(draw_target, &filter, sepia(amount.min(1.0)))
+            },
+        }
+    }
+    (filter, opacity)
+}
+
+fn color_matrix(draw_target: &DrawTarget, input: &FilterNode, matrix: Matrix5x4) -> FilterNode {
+    let filter = draw_target.create_filter(FilterType::ColorMatrix);
+    filter.set_attribute(ColorMatrixAttribute::Matrix(matrix));
+    filter.set_input(ColorMatrixInput, input);
+    filter
+}
+
+fn linear_transfer(draw_target: &DrawTarget,
+                   input: &FilterNode,
+                   slope: AzFloat,
+                   intercept: AzFloat)
+                   -> FilterNode {
+    let filter = draw_target.create_filter(FilterType::LinearTransfer);
+    filter.set_attribute(LinearTransferAttribute::DisableR(false));
+    filter.set_attribute(LinearTransferAttribute::DisableG(false));
+    filter.set_attribute(LinearTransferAttribute::DisableB(false));
+    filter.set_attribute(LinearTransferAttribute::SlopeR(slope));
+    filter.set_attribute(LinearTransferAttribute::SlopeG(slope));
+    filter.set_attribute(LinearTransferAttribute::SlopeB(slope));
+    filter.set_attribute(LinearTransferAttribute::InterceptR(intercept));
+    filter.set_attribute(LinearTransferAttribute::InterceptG(intercept));
+    filter.set_attribute(LinearTransferAttribute::InterceptB(intercept));
+    filter.set_input(LinearTransferInput, input);
+    filter
+}
+
+/// Builds a color matrix from the 3x3 matrix that CSS defines in terms of
+/// rows of `[r, g, b]` coefficients for each output channel.
+fn rgb_matrix(rows: [[AzFloat; 3]; 3]) -> Matrix5x4 {
+    Matrix5x4 {
+        m11: rows[0][0], m21: rows[0][1], m31: rows[0][2],
+        m12: rows[1][0], m22: rows[1][1], m32: rows[1][2],
+        m13: rows[2][0], m23: rows[2][1], m33: rows[2][2],
+        m14: 0.0, m24: 0.0, m34: 0.0, m44: 1.0,
+        m41: 0.0, m42: 0.0, m43: 0.0,
+        m51: 0.0, m52: 0.0, m53: 0.0, m54: 0.0,
+    }
+}
+
+// https://drafts.fxtf.org/filter-effects/#grayscaleEquivalent
+fn grayscale(amount: AzFloat) -> Matrix5x4 {
+    let amount = 1.0 - amount;
+    rgb_matrix([
+        [0.2126 + 0.7874 * amount, 0.7152 - 0.7152 * amount, 0.0722 - 0.0722 * amount],
+        [0.2126 - 0.2126 * amount, 0.7152 + 0.2848 * amount, 0.0722 - 0.0722 * amount],
+        [0.2126 - 0.2126 * amount, 0.7152 - 0.7152 * amount, 0.0722 + 0.9278 * amount],
+    ])
+}
+
+// https://drafts.fxtf.org/filter-effects/#sepiaEquivalent
+fn sepia(amount: AzFloat) -> Matrix5x4 {
+    let amount = 1.0 - amount;
+    rgb_matrix([
+        [0.393 + 0.607 * amount, 0.769 - 0.769 * amount, 0.189 - 0.189 * amount],
+        [0.349 - 0.349 * amount, 0.686 + 0.314 * amount, 0.168 - 0.168 * amount],
+        [0.272 - 0.272 * amount, 0.534 - 0.534 * amount, 0.131 + 0.869 * amount],
+    ])
+}
+
+// https://drafts.fxtf.org/filter-effects/#saturateEquivalent
+fn saturate(amount: AzFloat) -> Matrix5x4 {
+    rgb_matrix([
+        [0.213 + 0.787 * amount, 0.715 - 0.715 * amount, 0.072 - 0.072 * amount],
+        [0.213 - 0.213 * amount, 0.715 + 0.285 * amount, 0.072 - 0.072 * amount],
+        [0.213 - 0.213 * amount, 0.715 - 0.715 * amount, 0.072 + 0.928 * amount],
+    ])
+}
+
+// https://drafts.fxtf.org/filter-effects/#huerotateEquivalent
+fn hue_rotate(angle: AzFloat) -> Matrix5x4 {
+    let (sin, cos) = angle.sin_cos();
+    rgb_matrix([
+        [0.213 + cos * 0.787 - sin * 0.213,
+         0.715 - cos * 0.715 - sin * 0.715,
+         0.072 - cos * 0.072 + sin * 0.928],
+        [0.213 - cos * 0.213 + sin * 0.143,
+         0.715 + cos * 0.285 + sin * 0.140,
+         0.072 - cos * 0.072 - sin * 0.283],
+        [0.213 - cos * 0.213 - sin * 0.787,
+         0.715 - cos * 0.715 + sin * 0.715,
+         0.072 + cos * 0.928 + sin * 0.072],
+    ])
+}
@@ -4,24 +4,31 @@
 
 #![deny(unsafe_code)]
 
+extern crate app_units;
 extern crate azure;
 extern crate canvas_traits;
 extern crate compositing;
 extern crate cssparser;
 extern crate euclid;
 extern crate fnv;
+extern crate gfx;
 extern crate gleam;
 extern crate ipc_channel;
 #[macro_use] extern crate log;
 extern crate num_traits;
 extern crate offscreen_gl_context;
+extern crate ordered_float;
+extern crate range;
 extern crate serde_bytes;
 extern crate servo_config;
+extern crate style;
+extern crate unicode_script;
 extern crate webrender;
 extern crate webrender_api;
 
 pub mod canvas_data;
 pub mod canvas_paint_thread;
+mod filters;
 pub mod gl_context;
 mod webgl_mode;
 pub mod webgl_thread;
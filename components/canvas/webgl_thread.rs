@@ -647,7 +647,7 @@ impl WebGLImpl {
             WebGLCommand::DetachShader(program_id, shader_id) =>
                 ctx.gl().detach_shader(program_id.get(), shader_id.get()),
             WebGLCommand::BindAttribLocation(program_id, index, ref name) => {
-                ctx.gl().bind_attrib_location(program_id.get(), index, &to_name_in_compiled_shader(name))
+                ctx.gl().bind_attrib_location(program_id.get(), index, name)
             }
             WebGLCommand::BlendColor(r, g, b, a) =>
                 ctx.gl().blend_color(r, g, b, a),
@@ -743,6 +743,8 @@ impl WebGLImpl {
                 Self::uniform_location(ctx.gl(), program_id, &name, chan),
             WebGLCommand::GetShaderInfoLog(shader_id, ref chan) =>
                 Self::shader_info_log(ctx.gl(), shader_id, chan),
+            WebGLCommand::GetShaderCompileStatus(shader_id, ref chan) =>
+                Self::shader_compile_status(ctx.gl(), shader_id, chan),
             WebGLCommand::GetProgramInfoLog(program_id, ref chan) =>
                 Self::program_info_log(ctx.gl(), program_id, chan),
             WebGLCommand::CompileShader(shader_id, ref source) =>
@@ -1120,7 +1122,7 @@ impl WebGLImpl {
                 gl.get_attrib_location(program.get(), &name)
             };
             ActiveAttribInfo {
-                name: from_name_in_compiled_shader(&name),
+                name,
                 size,
                 type_,
                 location,
@@ -1142,7 +1144,7 @@ impl WebGLImpl {
                 name.truncate(len - 3);
             }
             ActiveUniformInfo {
-                base_name: from_name_in_compiled_shader(&name).into(),
+                base_name: name.into(),
                 size: if is_array { Some(size) } else { None },
                 type_,
             }
@@ -1215,7 +1217,7 @@ impl WebGLImpl {
         name: &str,
         chan: &WebGLSender<i32>,
     ) {
-        let location = gl.get_uniform_location(program_id.get(), &to_name_in_compiled_shader(name));
+        let location = gl.get_uniform_location(program_id.get(), name);
         assert!(location >= 0);
         chan.send(location).unwrap();
     }
@@ -1226,6 +1228,15 @@ impl WebGLImpl {
         chan.send(log).unwrap();
     }
 
+    #[allow(unsafe_code)]
+    fn shader_compile_status(gl: &gl::Gl, shader_id: WebGLShaderId, chan: &WebGLSender<bool>) {
+        let mut compiled = [0];
+        unsafe {
+            gl.get_shader_iv(shader_id.get(), gl::COMPILE_STATUS, &mut compiled);
+        }
+        chan.send(compiled[0] != 0).unwrap();
+    }
+
     fn program_info_log(gl: &gl::Gl, program_id: WebGLProgramId, chan: &WebGLSender<String>) {
         let log = gl.get_program_info_log(program_id.get());
         chan.send(log).unwrap();
@@ -1329,39 +1340,3 @@ impl WebGLImpl {
         gl.compile_shader(shader_id.get());
     }
 }
-
-/// ANGLE adds a `_u` prefix to variable names:
-///
-/// https://chromium.googlesource.com/angle/angle/+/855d964bd0d05f6b2cb303f625506cf53d37e94f
-///
-/// To avoid hard-coding this we would need to use the `sh::GetAttributes` and `sh::GetUniforms`
-/// API to look up the `x.name` and `x.mappedName` members.
-const ANGLE_NAME_PREFIX: &'static str = "_u";
-
-fn to_name_in_compiled_shader(s: &str) -> String {
-    map_dot_separated(s, |s, mapped| {
-        mapped.push_str(ANGLE_NAME_PREFIX);
-        mapped.push_str(s);
-    })
-}
-
-fn from_name_in_compiled_shader(s: &str) -> String {
-    map_dot_separated(s, |s, mapped| {
-        mapped.push_str(if s.starts_with(ANGLE_NAME_PREFIX) {
-            &s[ANGLE_NAME_PREFIX.len()..]
-        } else {
-            s
-        })
-    })
-}
-
-fn map_dot_separated<F: Fn(&str, &mut String)>(s: &str, f: F) -> String {
-    let mut iter = s.split('.');
-    let mut mapped = String::new();
-    f(iter.next().unwrap(), &mut mapped);
-    for s in iter {
-        mapped.push('.');
-        f(s, &mut mapped);
-    }
-    mapped
-}
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use app_units::Au;
 use cssparser::RGBA;
 use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D};
 use ipc_channel::ipc::IpcSender;
@@ -46,13 +47,19 @@ pub enum Canvas2dMsg {
     BezierCurveTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
     ClearRect(Rect<f32>),
     Clip,
+    ClipPath(Vec<PathSegment>, FillRule),
     ClosePath,
     Ellipse(Point2D<f32>, f32, f32, f32, f32, f32, bool),
     Fill,
+    FillPath(Vec<PathSegment>, FillRule),
     FillText(String, f64, f64, Option<f64>),
+    MeasureText(String, IpcSender<TextMetrics>),
     FillRect(Rect<f32>),
     GetImageData(Rect<i32>, Size2D<f64>, IpcSender<ByteBuf>),
     IsPointInPath(f64, f64, FillRule, IpcSender<bool>),
+    IsPointInPathWithPath(Vec<PathSegment>, f64, f64, FillRule, IpcSender<bool>),
+    IsPointInStroke(f64, f64, IpcSender<bool>),
+    IsPointInStrokeWithPath(Vec<PathSegment>, f64, f64, IpcSender<bool>),
     LineTo(Point2D<f32>),
     MoveTo(Point2D<f32>),
     PutImageData(ByteBuf, Vector2D<f64>, Size2D<f64>, Rect<f64>),
@@ -62,15 +69,25 @@ pub enum Canvas2dMsg {
     SaveContext,
     StrokeRect(Rect<f32>),
     Stroke,
+    StrokePath(Vec<PathSegment>),
+    StrokeText(String, f64, f64, Option<f64>),
     SetFillStyle(FillOrStrokeStyle),
     SetStrokeStyle(FillOrStrokeStyle),
     SetLineWidth(f32),
     SetLineCap(LineCapStyle),
     SetLineJoin(LineJoinStyle),
     SetMiterLimit(f32),
+    SetLineDash(Vec<f32>),
+    SetLineDashOffset(f32),
+    SetImageSmoothingQuality(ImageSmoothingQuality),
+    SetFilter(Vec<FilterFunction>),
     SetGlobalAlpha(f32),
     SetGlobalComposition(CompositionOrBlending),
     SetTransform(Transform2D<f32>),
+    SetFont(CanvasFontStyle),
+    SetTextAlign(TextAlign),
+    SetTextBaseline(TextBaseline),
+    SetDirection(Direction),
     SetShadowOffsetX(f64),
     SetShadowOffsetY(f64),
     SetShadowBlur(f64),
@@ -174,6 +191,146 @@ pub enum FillOrStrokeStyle {
     Surface(SurfaceStyle),
 }
 
+/// The parts of a computed `font` that the canvas thread needs to pick and
+/// size fonts for text drawing.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CanvasFontStyle {
+    pub families: Vec<CanvasFontFamily>,
+    pub size: Au,
+    pub style: CanvasFontSlant,
+    /// The numeric `font-weight`, from 1 to 1000.
+    pub weight: f32,
+    /// The `font-stretch` percentage.
+    pub stretch: f32,
+    pub small_caps: bool,
+}
+
+impl Default for CanvasFontStyle {
+    /// The initial `10px sans-serif` font of a 2D context.
+    fn default() -> CanvasFontStyle {
+        CanvasFontStyle {
+            families: vec![CanvasFontFamily::Generic("sans-serif".to_owned())],
+            size: Au::from_px(10),
+            style: CanvasFontSlant::Normal,
+            weight: 400.,
+            stretch: 100.,
+            small_caps: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum CanvasFontFamily {
+    Named(String),
+    /// A generic family keyword such as `serif`.
+    Generic(String),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum CanvasFontSlant {
+    Normal,
+    Italic,
+    /// An oblique angle in degrees.
+    Oblique(f32),
+}
+
+#[derive(Clone, Copy, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Clone, Copy, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum TextBaseline {
+    Top,
+    Hanging,
+    Middle,
+    Alphabetic,
+    Ideographic,
+    Bottom,
+}
+
+/// The resolved direction of canvas text; `inherit` is resolved against the
+/// canvas element before it gets here.
+#[derive(Clone, Copy, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+#[derive(Clone, Copy, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum ImageSmoothingQuality {
+    Low,
+    Medium,
+    High,
+}
+
+/// A computed CSS filter function, as set through the canvas `filter`
+/// attribute. Lengths are in pixels and angles in radians.
+/// <https://drafts.fxtf.org/filter-effects/#supported-filter-functions>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum FilterFunction {
+    Blur(f32),
+    Brightness(f32),
+    Contrast(f32),
+    Grayscale(f32),
+    HueRotate(f32),
+    Invert(f32),
+    Opacity(f32),
+    Saturate(f32),
+    Sepia(f32),
+}
+
+/// A segment of a `Path2D`. Arcs and ellipses are flattened into cubic Bézier
+/// curves when they are added, so that a path can be transformed exactly by
+/// `addPath` and replayed by the canvas thread.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum PathSegment {
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    QuadraticCurveTo(Point2D<f32>, Point2D<f32>),
+    BezierCurveTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    ClosePath,
+}
+
+impl PathSegment {
+    pub fn transform(&self, transform: &Transform2D<f32>) -> PathSegment {
+        match *self {
+            PathSegment::MoveTo(ref point) => PathSegment::MoveTo(transform.transform_point(point)),
+            PathSegment::LineTo(ref point) => PathSegment::LineTo(transform.transform_point(point)),
+            PathSegment::QuadraticCurveTo(ref cp, ref point) => {
+                PathSegment::QuadraticCurveTo(transform.transform_point(cp), transform.transform_point(point))
+            },
+            PathSegment::BezierCurveTo(ref cp1, ref cp2, ref point) => {
+                PathSegment::BezierCurveTo(transform.transform_point(cp1),
+                                           transform.transform_point(cp2),
+                                           transform.transform_point(point))
+            },
+            PathSegment::ClosePath => PathSegment::ClosePath,
+        }
+    }
+}
+
+/// <https://html.spec.whatwg.org/multipage/#textmetrics>
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct TextMetrics {
+    pub width: f64,
+    pub actual_bounding_box_left: f64,
+    pub actual_bounding_box_right: f64,
+    pub font_bounding_box_ascent: f64,
+    pub font_bounding_box_descent: f64,
+    pub actual_bounding_box_ascent: f64,
+    pub actual_bounding_box_descent: f64,
+    pub em_height_ascent: f64,
+    pub em_height_descent: f64,
+    pub hanging_baseline: f64,
+    pub alphabetic_baseline: f64,
+    pub ideographic_baseline: f64,
+}
+
 #[derive(Clone, Copy, Deserialize, MallocSizeOf, PartialEq, Serialize)]
 pub enum LineCapStyle {
     Butt = 0,
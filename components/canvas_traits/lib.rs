@@ -7,6 +7,7 @@
 
 #![deny(unsafe_code)]
 
+extern crate app_units;
 extern crate cssparser;
 extern crate euclid;
 extern crate gleam;
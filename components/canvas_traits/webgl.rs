@@ -208,6 +208,7 @@ pub enum WebGLCommand {
     GetShaderPrecisionFormat(u32, u32, WebGLSender<(i32, i32, i32)>),
     GetUniformLocation(WebGLProgramId, String, WebGLSender<i32>),
     GetShaderInfoLog(WebGLShaderId, WebGLSender<String>),
+    GetShaderCompileStatus(WebGLShaderId, WebGLSender<bool>),
     GetProgramInfoLog(WebGLProgramId, WebGLSender<String>),
     GetFramebufferAttachmentParameter(u32, u32, u32, WebGLSender<i32>),
     GetRenderbufferParameter(u32, u32, WebGLSender<i32>),
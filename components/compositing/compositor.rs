@@ -713,7 +713,11 @@ impl<Window: WindowMethods> IOCompositor<Window> {
         let results = self.hit_test_at_point(cursor);
         if let Some(item) = results.items.first() {
             let node_address = Some(UntrustedNodeAddress(item.tag.0 as *const c_void));
-            let event = MouseMoveEvent(Some(item.point_in_viewport.to_untyped()), node_address);
+            let event = MouseMoveEvent(
+                Some(item.point_in_viewport.to_untyped()),
+                node_address,
+                Some(item.point_relative_to_item.to_untyped()),
+            );
             let pipeline_id = PipelineId::from_webrender(item.pipeline);
             let msg = ConstellationMsg::ForwardEvent(pipeline_id, event);
             if let Err(e) = self.constellation_chan.send(msg) {
@@ -1249,13 +1253,10 @@ impl<Window: WindowMethods> IOCompositor<Window> {
             #[cfg(feature = "gleam")]
             CompositeTarget::WindowAndPng => {
                 let img = gl::draw_img(&*self.window.gl(), rt_info, width, height);
-                Some(Image {
-                    width: img.width(),
-                    height: img.height(),
-                    format: PixelFormat::RGB8,
-                    bytes: ipc::IpcSharedMemory::from_bytes(&*img),
-                    id: None,
-                })
+                Some(Image::new(img.width(),
+                                img.height(),
+                                PixelFormat::RGB8,
+                                ipc::IpcSharedMemory::from_bytes(&*img)))
             }
             #[cfg(feature = "gleam")]
             CompositeTarget::PngFile => {
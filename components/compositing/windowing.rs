@@ -83,6 +83,9 @@ pub enum WindowEvent {
     /// Make a top level browsing context visible, hiding the previous
     /// visible one.
     SelectBrowser(TopLevelBrowsingContextId),
+    /// Sent when the window is minimized or restored, or when the application
+    /// is suspended or resumed.
+    Visible(bool),
     /// Toggles a debug flag in WebRender
     ToggleWebRenderDebug(WebRenderDebugOption),
     /// Capture current WebRender
@@ -111,6 +114,7 @@ impl Debug for WindowEvent {
             WindowEvent::SendError(..) => write!(f, "SendError"),
             WindowEvent::CloseBrowser(..) => write!(f, "CloseBrowser"),
             WindowEvent::SelectBrowser(..) => write!(f, "SelectBrowser"),
+            WindowEvent::Visible(..) => write!(f, "Visible"),
             WindowEvent::ToggleWebRenderDebug(..) => write!(f, "ToggleWebRenderDebug"),
             WindowEvent::CaptureWebRender => write!(f, "CaptureWebRender"),
         }
@@ -123,12 +123,13 @@ use network_listener::NetworkListener;
 use pipeline::{InitialPipelineState, Pipeline};
 use profile_traits::mem;
 use profile_traits::time;
-use script_traits::{AnimationState, AnimationTickType, CompositorEvent};
+use script_traits::{AnimationState, AnimationTickType, BroadcastMsg, CompositorEvent};
 use script_traits::{ConstellationControlMsg, ConstellationMsg as FromCompositorMsg, DiscardBrowsingContext};
 use script_traits::{DocumentActivity, DocumentState, LayoutControlMsg, LoadData};
 use script_traits::{IFrameLoadInfo, IFrameLoadInfoWithData, IFrameSandboxState, TimerSchedulerMsg};
 use script_traits::{LayoutMsg as FromLayoutMsg, ScriptMsg as FromScriptMsg, ScriptThreadFactory};
 use script_traits::{LogEntry, ScriptToConstellationChan, ServiceWorkerMsg, webdriver_msg};
+use script_traits::{MessagePortId, MessagePortMsg, MessagePortRouterId, PortMessageTask, TransferredPort};
 use script_traits::{SWManagerMsg, ScopeThings, UpdatePipelineIdReason, WebDriverCommandMsg};
 use script_traits::{WindowSizeData, WindowSizeType};
 use script_traits::GuiApplication;
@@ -204,6 +205,9 @@ pub struct Constellation<Message, LTF, STF> {
     /// The last frame tree sent to WebRender.
     active_browser_id: Option<TopLevelBrowsingContextId>,
 
+    /// Whether the embedder's window is visible, i.e. not minimized.
+    window_visible: bool,
+
     /// Channels for the constellation to send messages to the public
     /// resource-related threads.  There are two groups of resource
     /// threads: one for public browsing, and one for private
@@ -338,6 +342,15 @@ pub struct Constellation<Message, LTF, STF> {
 
     /// Application which uses Servo as a GUI library.
     gui_application: Option<Box<GuiApplication>>,
+
+    /// The message ports known to the constellation, and where they currently live.
+    message_ports: HashMap<MessagePortId, MessagePortState>,
+
+    /// The message-port routers of the globals using message ports or broadcast channels.
+    message_port_routers: HashMap<MessagePortRouterId, MessagePortRouter>,
+
+    /// The routers listening to each broadcast channel, by origin and channel name.
+    broadcast_channels: HashMap<ImmutableOrigin, HashMap<String, Vec<MessagePortRouterId>>>,
 }
 
 /// State needed to construct a constellation.
@@ -392,6 +405,23 @@ pub struct InitialConstellationState {
     pub gui_application: Option<Box<GuiApplication>>,
 }
 
+/// Where a message port currently lives.
+enum MessagePortState {
+    /// The port is owned by the global behind the given router.
+    Owned(MessagePortRouterId),
+    /// The port is being transferred to another global. Messages for it are
+    /// buffered until its new owner claims it.
+    TransferInProgress(VecDeque<MessagePortMsg>),
+}
+
+/// The message-port router of a global.
+struct MessagePortRouter {
+    /// The pipeline the global belongs to.
+    pipeline_id: PipelineId,
+    /// The channel on which the global receives port messages and broadcasts.
+    sender: IpcSender<MessagePortMsg>,
+}
+
 /// Data needed for webdriver
 struct WebDriverData {
     load_channel: Option<(PipelineId, IpcSender<webdriver_msg::LoadStatus>)>,
@@ -592,6 +622,8 @@ where
 
                 PipelineNamespace::install(PipelineNamespaceId(0));
 
+                let canvas_chan = CanvasPaintThread::start(state.font_cache_thread.clone());
+
                 let mut constellation: Constellation<Message, LTF, STF> = Constellation {
                     script_sender: ipc_script_sender,
                     layout_sender: ipc_layout_sender,
@@ -603,6 +635,7 @@ where
                     embedder_proxy: state.embedder_proxy,
                     compositor_proxy: state.compositor_proxy,
                     active_browser_id: None,
+                    window_visible: true,
                     debugger_chan: state.debugger_chan,
                     devtools_chan: state.devtools_chan,
                     bluetooth_thread: state.bluetooth_thread,
@@ -661,8 +694,11 @@ where
                     ),
                     webgl_threads: state.webgl_threads,
                     webvr_chan: state.webvr_chan,
-                    canvas_chan: CanvasPaintThread::start(),
+                    canvas_chan: canvas_chan,
                     gui_application: state.gui_application,
+                    message_ports: HashMap::new(),
+                    message_port_routers: HashMap::new(),
+                    broadcast_channels: HashMap::new(),
                 };
 
                 constellation.run();
@@ -1060,7 +1096,21 @@ where
             },
             // Send frame tree to WebRender. Make it visible.
             FromCompositorMsg::SelectBrowser(top_level_browsing_context_id) => {
+                let previous_browser_id = self.active_browser_id;
                 self.send_frame_tree(top_level_browsing_context_id);
+                if previous_browser_id != Some(top_level_browsing_context_id) {
+                    if let Some(previous_browser_id) = previous_browser_id {
+                        self.set_browser_visibility(previous_browser_id, false);
+                    }
+                    let visible = self.window_visible;
+                    self.set_browser_visibility(top_level_browsing_context_id, visible);
+                }
+            },
+            FromCompositorMsg::WindowVisibility(visible) => {
+                self.window_visible = visible;
+                if let Some(active_browser_id) = self.active_browser_id {
+                    self.set_browser_visibility(active_browser_id, visible);
+                }
             },
             // Handle a forward or back request
             FromCompositorMsg::TraverseHistory(top_level_browsing_context_id, direction) => {
@@ -1179,8 +1229,38 @@ where
                     warn!("constellation got set final url message for dead pipeline");
                 }
             },
-            FromScriptMsg::PostMessage(browsing_context_id, origin, data) => {
-                self.handle_post_message_msg(browsing_context_id, origin, data);
+            FromScriptMsg::PostMessage(browsing_context_id, origin, data, ports) => {
+                self.handle_post_message_msg(browsing_context_id, origin, data, ports);
+            },
+            FromScriptMsg::NewMessagePortRouter(router_id, sender) => {
+                self.handle_new_message_port_router(source_pipeline_id, router_id, sender);
+            },
+            FromScriptMsg::RemoveMessagePortRouter(router_id) => {
+                self.handle_remove_message_port_router(router_id);
+            },
+            FromScriptMsg::NewMessagePort(router_id, port_id) => {
+                self.handle_new_message_port(router_id, port_id);
+            },
+            FromScriptMsg::MessagePortShipped(port_id) => {
+                self.handle_message_port_shipped(port_id);
+            },
+            FromScriptMsg::PostPortMessage(port_id, task) => {
+                self.handle_post_port_message(port_id, task);
+            },
+            FromScriptMsg::RemoveMessagePort(port_id) => {
+                self.handle_remove_message_port(port_id);
+            },
+            FromScriptMsg::DisentangleMessagePort(port_id) => {
+                self.route_message_port_msg(port_id, MessagePortMsg::Disentangle(port_id));
+            },
+            FromScriptMsg::NewBroadcastChannelNameInRouter(router_id, channel_name, origin) => {
+                self.handle_new_broadcast_channel_name_in_router(router_id, channel_name, origin);
+            },
+            FromScriptMsg::RemoveBroadcastChannelNameInRouter(router_id, channel_name, origin) => {
+                self.handle_remove_broadcast_channel_name_in_router(router_id, channel_name, origin);
+            },
+            FromScriptMsg::ScheduleBroadcast(router_id, message) => {
+                self.handle_schedule_broadcast(router_id, message);
             },
             FromScriptMsg::Focus => {
                 self.handle_focus_msg(source_pipeline_id);
@@ -1359,6 +1439,169 @@ where
         }
     }
 
+    fn handle_new_message_port_router(
+        &mut self,
+        pipeline_id: PipelineId,
+        router_id: MessagePortRouterId,
+        sender: IpcSender<MessagePortMsg>,
+    ) {
+        self.message_port_routers.insert(router_id, MessagePortRouter { pipeline_id, sender });
+    }
+
+    fn handle_remove_message_port_router(&mut self, router_id: MessagePortRouterId) {
+        self.message_port_routers.remove(&router_id);
+        // The ports owned by a router go away with it.
+        self.message_ports.retain(|_, state| match *state {
+            MessagePortState::Owned(owner) => owner != router_id,
+            MessagePortState::TransferInProgress(_) => true,
+        });
+        for channels in self.broadcast_channels.values_mut() {
+            for routers in channels.values_mut() {
+                routers.retain(|id| *id != router_id);
+            }
+        }
+    }
+
+    fn handle_new_message_port(&mut self, router_id: MessagePortRouterId, port_id: MessagePortId) {
+        let previous = self.message_ports.insert(port_id, MessagePortState::Owned(router_id));
+        // A port arriving at its new owner gets the messages posted to it while in transit.
+        if let Some(MessagePortState::TransferInProgress(buffer)) = previous {
+            for msg in buffer {
+                self.forward_message_port_msg(router_id, port_id, msg);
+            }
+        }
+    }
+
+    fn handle_remove_message_port(&mut self, port_id: MessagePortId) {
+        // The ports transferred by messages that never reached the port go
+        // away with it, along with their entanglement.
+        if let Some(MessagePortState::TransferInProgress(buffer)) = self.message_ports.remove(&port_id) {
+            for msg in buffer {
+                if let MessagePortMsg::NewTask(_, task) = msg {
+                    for port in task.ports {
+                        self.discard_transferred_port(port);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forget a port whose transfer was abandoned, and the ports transferred
+    /// by the messages it had queued.
+    fn discard_transferred_port(&mut self, port: TransferredPort) {
+        self.handle_remove_message_port(port.port_id);
+        if let Some(entangled_port) = port.entangled_port {
+            self.route_message_port_msg(entangled_port, MessagePortMsg::Disentangle(entangled_port));
+        }
+        for task in port.queued_messages {
+            for port in task.ports {
+                self.discard_transferred_port(port);
+            }
+        }
+    }
+
+    fn handle_message_port_shipped(&mut self, port_id: MessagePortId) {
+        match self.message_ports.get_mut(&port_id) {
+            Some(state) => {
+                if let MessagePortState::Owned(_) = *state {
+                    *state = MessagePortState::TransferInProgress(VecDeque::new());
+                }
+            },
+            None => warn!("Unknown message port {:?} shipped.", port_id),
+        }
+    }
+
+    fn handle_post_port_message(&mut self, port_id: MessagePortId, task: PortMessageTask) {
+        self.route_message_port_msg(port_id, MessagePortMsg::NewTask(port_id, task));
+    }
+
+    /// Send a message for a port to its owner, or buffer it while the port
+    /// is being transferred.
+    fn route_message_port_msg(&mut self, port_id: MessagePortId, msg: MessagePortMsg) {
+        let router_id = match self.message_ports.get_mut(&port_id) {
+            None => return warn!("Message for closed or unknown port {:?}.", port_id),
+            Some(&mut MessagePortState::TransferInProgress(ref mut buffer)) => {
+                return buffer.push_back(msg);
+            },
+            Some(&mut MessagePortState::Owned(router_id)) => router_id,
+        };
+        self.forward_message_port_msg(router_id, port_id, msg);
+    }
+
+    fn forward_message_port_msg(
+        &self,
+        router_id: MessagePortRouterId,
+        port_id: MessagePortId,
+        msg: MessagePortMsg,
+    ) {
+        match self.message_port_routers.get(&router_id) {
+            Some(router) => {
+                if let Err(e) = router.sender.send(msg) {
+                    warn!("Failed to forward message to port {:?} ({:?}).", port_id, e);
+                }
+            },
+            None => warn!("Message posted to port {:?} of a closed router.", port_id),
+        }
+    }
+
+    fn handle_new_broadcast_channel_name_in_router(
+        &mut self,
+        router_id: MessagePortRouterId,
+        channel_name: String,
+        origin: ImmutableOrigin,
+    ) {
+        let routers = self
+            .broadcast_channels
+            .entry(origin)
+            .or_insert_with(HashMap::new)
+            .entry(channel_name)
+            .or_insert_with(Vec::new);
+        if !routers.contains(&router_id) {
+            routers.push(router_id);
+        }
+    }
+
+    fn handle_remove_broadcast_channel_name_in_router(
+        &mut self,
+        router_id: MessagePortRouterId,
+        channel_name: String,
+        origin: ImmutableOrigin,
+    ) {
+        if let Some(channels) = self.broadcast_channels.get_mut(&origin) {
+            let now_empty = match channels.get_mut(&channel_name) {
+                Some(routers) => {
+                    routers.retain(|id| *id != router_id);
+                    routers.is_empty()
+                },
+                None => false,
+            };
+            if now_empty {
+                channels.remove(&channel_name);
+            }
+        }
+    }
+
+    fn handle_schedule_broadcast(&self, source_router_id: MessagePortRouterId, message: BroadcastMsg) {
+        let routers = match self
+            .broadcast_channels
+            .get(&message.origin)
+            .and_then(|channels| channels.get(&message.channel_name))
+        {
+            Some(routers) => routers,
+            None => return,
+        };
+        // The source global delivers the message to its own channels.
+        for router_id in routers.iter().filter(|id| **id != source_router_id) {
+            let router = match self.message_port_routers.get(router_id) {
+                Some(router) => router,
+                None => continue,
+            };
+            if let Err(e) = router.sender.send(MessagePortMsg::Broadcast(message.clone())) {
+                warn!("Failed to broadcast on channel {} ({:?}).", message.channel_name, e);
+            }
+        }
+    }
+
     fn handle_exit(&mut self) {
         // TODO: add a timer, which forces shutdown if threads aren't responsive.
         if self.shutting_down {
@@ -1507,6 +1750,18 @@ where
     fn handle_pipeline_exited(&mut self, pipeline_id: PipelineId) {
         debug!("Pipeline {:?} exited.", pipeline_id);
         self.pipelines.remove(&pipeline_id);
+
+        // Forget about the message-port routers of the pipeline's globals,
+        // including those of its workers.
+        let router_ids: Vec<MessagePortRouterId> = self
+            .message_port_routers
+            .iter()
+            .filter(|&(_, router)| router.pipeline_id == pipeline_id)
+            .map(|(router_id, _)| *router_id)
+            .collect();
+        for router_id in router_ids {
+            self.handle_remove_message_port_router(router_id);
+        }
     }
 
     fn handle_send_error(&mut self, pipeline_id: PipelineId, err: IpcError) {
@@ -2568,21 +2823,27 @@ where
         browsing_context_id: BrowsingContextId,
         origin: Option<ImmutableOrigin>,
         data: Vec<u8>,
+        ports: Vec<TransferredPort>,
     ) {
         let pipeline_id = match self.browsing_contexts.get(&browsing_context_id) {
             None => {
-                return warn!(
-                    "postMessage to closed browsing_context {}.",
-                    browsing_context_id
-                )
+                warn!("postMessage to closed browsing_context {}.", browsing_context_id);
+                for port in ports {
+                    self.discard_transferred_port(port);
+                }
+                return;
             },
             Some(browsing_context) => browsing_context.pipeline_id,
         };
-        let msg = ConstellationControlMsg::PostMessage(pipeline_id, origin, data);
-        let result = match self.pipelines.get(&pipeline_id) {
-            Some(pipeline) => pipeline.event_loop.send(msg),
-            None => return warn!("postMessage to closed pipeline {}.", pipeline_id),
-        };
+        if !self.pipelines.contains_key(&pipeline_id) {
+            warn!("postMessage to closed pipeline {}.", pipeline_id);
+            for port in ports {
+                self.discard_transferred_port(port);
+            }
+            return;
+        }
+        let msg = ConstellationControlMsg::PostMessage(pipeline_id, origin, data, ports);
+        let result = self.pipelines[&pipeline_id].event_loop.send(msg);
         if let Err(e) = result {
             self.handle_send_error(pipeline_id, e);
         }
@@ -2688,6 +2949,25 @@ where
         }
     }
 
+    /// Show or hide all the pipelines of a top-level browsing context.
+    fn set_browser_visibility(
+        &mut self,
+        top_level_browsing_context_id: TopLevelBrowsingContextId,
+        visible: bool,
+    ) {
+        let browsing_context_id = BrowsingContextId::from(top_level_browsing_context_id);
+        let pipeline_id = match self.browsing_contexts.get(&browsing_context_id) {
+            Some(browsing_context) => browsing_context.pipeline_id,
+            None => {
+                return warn!(
+                    "Visibility change for closed browsing context {:?}.",
+                    browsing_context_id
+                )
+            },
+        };
+        self.handle_set_visible_msg(pipeline_id, visible);
+    }
+
     fn handle_visibility_change_complete(&mut self, pipeline_id: PipelineId, visibility: bool) {
         let (browsing_context_id, parent_pipeline_info) = match self.pipelines.get(&pipeline_id) {
             None => return warn!("Visibity change for closed pipeline {:?}.", pipeline_id),
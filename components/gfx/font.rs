@@ -72,10 +72,25 @@ pub trait FontHandleMethods: Sized {
     fn metrics(&self) -> FontMetrics;
     fn table_for_tag(&self, FontTableTag) -> Option<FontTable>;
 
+    /// The outline of a glyph, or `None` if the platform can't provide one
+    /// (e.g. for bitmap-only fonts).
+    fn glyph_outline(&self, GlyphId) -> Option<Vec<GlyphOutlineSegment>>;
+
     /// A unique identifier for the font, allowing comparison.
     fn identifier(&self) -> Atom;
 }
 
+/// A segment of a glyph outline. Points are in pixels, relative to the glyph
+/// origin on the baseline, with the y axis pointing down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlyphOutlineSegment {
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    QuadTo(Point2D<f32>, Point2D<f32>),
+    CubicTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    Close,
+}
+
 // Used to abstract over the shaper's choice of fixed int representation.
 pub type FractionalPixel = f64;
 
@@ -312,6 +327,10 @@ impl Font {
         self.handle.glyph_h_kerning(first_glyph, second_glyph)
     }
 
+    pub fn glyph_outline(&self, glyph: GlyphId) -> Option<Vec<GlyphOutlineSegment>> {
+        self.handle.glyph_outline(glyph)
+    }
+
     pub fn glyph_h_advance(&self, glyph: GlyphId) -> FractionalPixel {
         *self.glyph_advance_cache.borrow_mut().entry(glyph).or_insert_with(|| {
             match self.handle.glyph_h_advance(glyph) {
@@ -336,12 +355,17 @@ pub struct FontGroup {
 
 impl FontGroup {
     pub fn new(style: &FontStyleStruct) -> FontGroup {
-        let descriptor = FontDescriptor::from(style);
+        FontGroup::from_families(FontDescriptor::from(style), style.font_family.0.iter())
+    }
 
+    /// Creates a group for an explicit list of families, for callers such as
+    /// the canvas thread which don't have a full `font` style struct around.
+    pub fn from_families<'a, I>(descriptor: FontDescriptor, families: I) -> FontGroup
+        where I: Iterator<Item = &'a SingleFontFamily>,
+    {
         let families =
-            style.font_family.0.iter()
-                .map(|family| FontGroupFamily::new(descriptor.clone(), &family))
-                .collect();
+            families.map(|family| FontGroupFamily::new(descriptor.clone(), family))
+                    .collect();
 
         FontGroup {
             descriptor,
@@ -3,7 +3,8 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use app_units::Au;
-use font::{FontHandleMethods, FontMetrics, FontTableMethods};
+use euclid::Point2D;
+use font::{FontHandleMethods, FontMetrics, FontTableMethods, GlyphOutlineSegment};
 use font::{FontTableTag, FractionalPixel, GPOS, GSUB, KERN};
 use freetype::freetype::{FT_Done_Face, FT_New_Face, FT_New_Memory_Face};
 use freetype::freetype::{FT_F26Dot6, FT_Face, FT_FaceRec};
@@ -11,7 +12,7 @@ use freetype::freetype::{FT_Get_Char_Index, FT_Get_Postscript_Name};
 use freetype::freetype::{FT_Get_Kerning, FT_Get_Sfnt_Table, FT_Load_Sfnt_Table};
 use freetype::freetype::{FT_GlyphSlot, FT_Library, FT_Long, FT_ULong};
 use freetype::freetype::{FT_Int32, FT_Kerning_Mode, FT_STYLE_FLAG_ITALIC};
-use freetype::freetype::{FT_Load_Glyph, FT_Set_Char_Size};
+use freetype::freetype::{FT_Load_Glyph, FT_Outline, FT_Set_Char_Size};
 use freetype::freetype::{FT_SizeRec, FT_Size_Metrics, FT_UInt, FT_Vector};
 use freetype::freetype::FT_Sfnt_Tag;
 use freetype::succeeded;
@@ -41,6 +42,14 @@ const FT_LOAD_TARGET_LIGHT: FT_Int32 = 1 << 16;
 // TODO(gw): Make this configurable.
 const GLYPH_LOAD_FLAGS: FT_Int32 = FT_LOAD_TARGET_LIGHT;
 
+// Like `FT_LOAD_TARGET_LIGHT`, this is a macro that bindgen doesn't see.
+const FT_LOAD_NO_BITMAP: FT_Int32 = 1 << 3;
+
+// The low bits of an outline point tag, which say whether the point is on the
+// curve or a conic or cubic control point.
+const FT_CURVE_TAG_ON: u8 = 1;
+const FT_CURVE_TAG_CUBIC: u8 = 2;
+
 fn fixed_to_float_ft(f: i32) -> f64 {
     fixed_to_float(6, f)
 }
@@ -327,11 +336,108 @@ impl FontHandleMethods for FontHandle {
         }
     }
 
+    fn glyph_outline(&self, glyph: GlyphId) -> Option<Vec<GlyphOutlineSegment>> {
+        assert!(!self.face.is_null());
+        unsafe {
+            let res = FT_Load_Glyph(self.face,
+                                    glyph as FT_UInt,
+                                    GLYPH_LOAD_FLAGS | FT_LOAD_NO_BITMAP);
+            if !succeeded(res) {
+                debug!("Unable to load outline for glyph {}. reason: {:?}", glyph, res);
+                return None;
+            }
+            let void_glyph = (*self.face).glyph;
+            let slot: FT_GlyphSlot = mem::transmute(void_glyph);
+            assert!(!slot.is_null());
+            Some(decompose_outline(&(*slot).outline))
+        }
+    }
+
     fn identifier(&self) -> Atom {
         self.font_data.identifier.clone()
     }
 }
 
+/// Converts a FreeType outline into path segments, following the rules of
+/// `FT_Outline_Decompose`: two consecutive conic control points imply an
+/// on-curve point halfway between them, and a contour may start off-curve.
+unsafe fn decompose_outline(outline: &FT_Outline) -> Vec<GlyphOutlineSegment> {
+    let mut segments = vec![];
+    if outline.n_points <= 0 || outline.n_contours <= 0 {
+        return segments;
+    }
+
+    let n_points = outline.n_points as usize;
+    let points = ::std::slice::from_raw_parts(outline.points, n_points);
+    let tags = ::std::slice::from_raw_parts(outline.tags, n_points);
+    let contours = ::std::slice::from_raw_parts(outline.contours, outline.n_contours as usize);
+
+    let mut first = 0;
+    for &last in contours {
+        let last = last as usize;
+        if last < first || last >= n_points {
+            break;
+        }
+        let contour: Vec<(Point2D<f32>, u8)> = (first..last + 1).map(|i| {
+            // FreeType's y axis points up, ours points down.
+            let point = Point2D::new(fixed_to_float_ft(points[i].x as i32) as f32,
+                                     -fixed_to_float_ft(points[i].y as i32) as f32);
+            (point, tags[i] as u8 & 3)
+        }).collect();
+        decompose_contour(&contour, &mut segments);
+        first = last + 1;
+    }
+    segments
+}
+
+fn decompose_contour(contour: &[(Point2D<f32>, u8)], segments: &mut Vec<GlyphOutlineSegment>) {
+    let midpoint = |a: Point2D<f32>, b: Point2D<f32>| a.lerp(b, 0.5);
+
+    // Start the contour on an on-curve point. If every point is a conic
+    // control point, start halfway between the last and the first one.
+    let (start, rest) = match contour.iter().position(|&(_, tag)| tag == FT_CURVE_TAG_ON) {
+        Some(index) => {
+            let rest: Vec<_> = contour[index + 1..].iter()
+                                                   .chain(contour[..index].iter())
+                                                   .cloned()
+                                                   .collect();
+            (contour[index].0, rest)
+        },
+        None => (midpoint(contour[contour.len() - 1].0, contour[0].0), contour.to_vec()),
+    };
+
+    segments.push(GlyphOutlineSegment::MoveTo(start));
+    let mut i = 0;
+    while i < rest.len() {
+        let (point, tag) = rest[i];
+        if tag == FT_CURVE_TAG_ON {
+            segments.push(GlyphOutlineSegment::LineTo(point));
+            i += 1;
+        } else if tag == FT_CURVE_TAG_CUBIC {
+            let control = rest.get(i + 1).map_or(start, |&(p, _)| p);
+            let end = rest.get(i + 2).map_or(start, |&(p, _)| p);
+            segments.push(GlyphOutlineSegment::CubicTo(point, control, end));
+            i += 3;
+        } else {
+            match rest.get(i + 1) {
+                Some(&(next, FT_CURVE_TAG_ON)) => {
+                    segments.push(GlyphOutlineSegment::QuadTo(point, next));
+                    i += 2;
+                },
+                Some(&(next, _)) => {
+                    segments.push(GlyphOutlineSegment::QuadTo(point, midpoint(point, next)));
+                    i += 1;
+                },
+                None => {
+                    segments.push(GlyphOutlineSegment::QuadTo(point, start));
+                    i += 1;
+                },
+            }
+        }
+    }
+    segments.push(GlyphOutlineSegment::Close);
+}
+
 impl<'a> FontHandle {
     fn set_char_size(face: FT_Face, pt_size: Au) -> Result<(), ()>{
         let char_size = pt_size.to_f64_px() * 64.0 + 0.5;
@@ -15,7 +15,7 @@ use core_text::font::CTFont;
 use core_text::font_descriptor::{SymbolicTraitAccessors, TraitAccessors};
 use core_text::font_descriptor::kCTFontDefaultOrientation;
 use font::{FontHandleMethods, FontMetrics, FontTableMethods, FontTableTag, FractionalPixel};
-use font::{GPOS, GSUB, KERN};
+use font::{GPOS, GSUB, GlyphOutlineSegment, KERN};
 use platform::font_template::FontTemplateData;
 use platform::macos::font_context::FontContextHandle;
 use servo_atoms::Atom;
@@ -318,6 +318,12 @@ impl FontHandleMethods for FontHandle {
         })
     }
 
+    fn glyph_outline(&self, _glyph: GlyphId) -> Option<Vec<GlyphOutlineSegment>> {
+        // FIXME: Glyph outlines are only implemented for FreeType so far, so
+        // canvas text draws nothing on this platform.
+        None
+    }
+
     fn identifier(&self) -> Atom {
         self.font_data.identifier.clone()
     }
@@ -11,7 +11,7 @@ use dwrote;
 use dwrote::{Font, FontFace, FontFile};
 use dwrote::{FontWeight, FontStretch, FontStyle};
 use font::{FontHandleMethods, FontMetrics, FontTableMethods};
-use font::{FontTableTag, FractionalPixel};
+use font::{FontTableTag, FractionalPixel, GlyphOutlineSegment};
 use platform::font_template::FontTemplateData;
 use platform::windows::font_context::FontContextHandle;
 use platform::windows::font_list::font_from_atom;
@@ -378,6 +378,12 @@ impl FontHandleMethods for FontHandle {
         self.face.get_font_table(tag).map(|bytes| FontTable { data: bytes })
     }
 
+    fn glyph_outline(&self, _glyph: GlyphId) -> Option<Vec<GlyphOutlineSegment>> {
+        // FIXME: Glyph outlines are only implemented for FreeType so far, so
+        // canvas text draws nothing on this platform.
+        None
+    }
+
     fn identifier(&self) -> Atom {
         self.font_data.identifier.clone()
     }
@@ -103,7 +103,72 @@ pub fn node_id_from_scroll_id(id: usize) -> Option<usize> {
     None
 }
 
+/// A rectangle in layer coordinates, as used by the metrics computed from display lists.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ContentRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ContentRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> ContentRect {
+        ContentRect { x, y, width, height }
+    }
+
+    pub fn max_x(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn max_y(&self) -> f32 {
+        self.y + self.height
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width * self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0. || self.height <= 0.
+    }
+
+    pub fn intersection(&self, other: &ContentRect) -> Option<ContentRect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let rect = ContentRect::new(x, y, self.max_x().min(other.max_x()) - x, self.max_y().min(other.max_y()) - y);
+        if rect.is_empty() {
+            None
+        } else {
+            Some(rect)
+        }
+    }
+
+    /// Returns the smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &ContentRect) -> ContentRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        ContentRect::new(x, y, self.max_x().max(other.max_x()) - x, self.max_y().max(other.max_y()) - y)
+    }
+}
+
+/// The content painted by a display list for a single DOM node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PaintedNode {
+    /// The opaque address of the DOM node.
+    pub node: usize,
+    /// The union of the bounds of all the display items of this node.
+    pub bounds: ContentRect,
+    /// The union of the visible bounds of the text and image display items
+    /// of this node, if it has any.
+    pub content_bounds: Option<ContentRect>,
+}
+
 pub trait DisplayList {
     /// Returns true if this display list contains meaningful content.
     fn is_contentful(&self) -> bool;
+
+    /// Returns the content painted for each DOM node by this display list, in
+    /// the order the nodes first appear in it.
+    fn painted_nodes(&self) -> Vec<PaintedNode>;
 }
@@ -7,18 +7,153 @@
 use context::LayoutContext;
 use display_list::items::OpaqueNode;
 use flow::{Flow, GetBaseFlow};
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
+use net_traits::image::base::{Image, ImageAnimationState};
 use opaque_node::OpaqueNodeMethods;
 use script_traits::{AnimationState, ConstellationControlMsg, LayoutMsg as ConstellationMsg};
 use script_traits::UntrustedNodeAddress;
+use std::mem;
+use std::sync::Arc;
 use std::sync::mpsc::Receiver;
 use style::animation::{Animation, update_style_for_animation};
 use style::dom::TElement;
 use style::font_metrics::ServoMetricsProvider;
 use style::selector_parser::RestyleDamage;
 use style::timer::Timer;
+use webrender_api::{ImageKey, RenderApi, Transaction};
+
+/// An image painted for a node. A node can paint several images, such as
+/// its content and background layers, so the address of the image is part
+/// of the key.
+type PaintedImage = (OpaqueNode, usize);
+
+/// The animated images painted in a document, with the frame each one shows.
+///
+/// Each painted image gets a WebRender image of its own, which is updated
+/// with the active frame, so advancing an animation doesn't need a new
+/// display list.
+pub struct ImageAnimations {
+    webrender_api: RenderApi,
+    animations: FxHashMap<PaintedImage, ImageAnimation>,
+    /// The images painted by the display list being built.
+    painted_images: FxHashSet<PaintedImage>,
+    /// The keys that the display list being built stopped using.
+    unused_keys: Vec<ImageKey>,
+    /// The keys that the last display list stopped using, which the display
+    /// list it replaces may still be showing.
+    retired_keys: Vec<ImageKey>,
+    /// Whether the document is hidden, in which case animations don't advance.
+    paused: bool,
+}
+
+/// The animation of a painted image, and the key it is painted with.
+struct ImageAnimation {
+    state: ImageAnimationState,
+    key: ImageKey,
+}
+
+impl ImageAnimations {
+    pub fn new(webrender_api: RenderApi) -> ImageAnimations {
+        ImageAnimations {
+            webrender_api: webrender_api,
+            animations: FxHashMap::default(),
+            painted_images: FxHashSet::default(),
+            unused_keys: vec![],
+            retired_keys: vec![],
+            paused: false,
+        }
+    }
+
+    /// Whether there are images to animate.
+    pub fn are_running(&self) -> bool {
+        !self.paused && !self.animations.is_empty()
+    }
+
+    /// Returns the key to paint the image of `node` with, starting its
+    /// animation if the image is animated.
+    pub fn active_image_key(&mut self, node: OpaqueNode, image: &Arc<Image>, now: f64) -> Option<ImageKey> {
+        if !image.should_animate() {
+            return image.id;
+        }
+        // The state keeps the image alive, so its address can't be reused
+        // by another image while the state exists.
+        let painted_image = (node, &**image as *const Image as usize);
+        self.painted_images.insert(painted_image);
+        if let Some(animation) = self.animations.get(&painted_image) {
+            return Some(animation.key);
+        }
+
+        let state = ImageAnimationState::new(image.clone(), now);
+        let (descriptor, data) = image.webrender_frame(state.active_frame);
+        let key = self.webrender_api.generate_image_key();
+        let mut txn = Transaction::new();
+        txn.add_image(key, descriptor, data, None);
+        self.webrender_api.update_resources(txn.resource_updates);
+        self.animations.insert(painted_image, ImageAnimation { state: state, key: key });
+        Some(key)
+    }
+
+    /// Called before building a display list.
+    pub fn start_display_list(&mut self) {
+        self.painted_images.clear();
+    }
+
+    /// Called after building a display list, to stop the animations of the
+    /// images that are no longer painted, and delete the images that no
+    /// display list being shown uses anymore.
+    pub fn finish_display_list(&mut self) {
+        let painted_images = &self.painted_images;
+        let unused_keys = &mut self.unused_keys;
+        self.animations.retain(|painted_image, animation| {
+            if painted_images.contains(painted_image) {
+                return true;
+            }
+            unused_keys.push(animation.key);
+            false
+        });
+
+        let retired_keys = mem::replace(&mut self.retired_keys, mem::replace(unused_keys, vec![]));
+        if retired_keys.is_empty() {
+            return;
+        }
+        let mut txn = Transaction::new();
+        for key in retired_keys {
+            txn.delete_image(key);
+        }
+        self.webrender_api.update_resources(txn.resource_updates);
+    }
+
+    /// Advances the animations to `now`, adding an update of the image of
+    /// each one that now shows a different frame to `txn`. Returns whether
+    /// there were any.
+    pub fn tick(&mut self, now: f64, txn: &mut Transaction) -> bool {
+        if self.paused {
+            return false;
+        }
+        let mut frames_changed = false;
+        for animation in self.animations.values_mut() {
+            if animation.state.update(now) {
+                let (descriptor, data) = animation.state.image.webrender_frame(animation.state.active_frame);
+                txn.update_image(animation.key, descriptor, data, None);
+                frames_changed = true;
+            }
+        }
+        frames_changed
+    }
+
+    /// Pauses the animations while the document is hidden, resuming them
+    /// from the frames they were showing once it is visible again.
+    pub fn set_paused(&mut self, paused: bool, now: f64) {
+        if self.paused && !paused {
+            for animation in self.animations.values_mut() {
+                animation.state.resume(now);
+            }
+        }
+        self.paused = paused;
+    }
+}
 
 /// Processes any new animations that were discovered after style recalculation.
 /// Also expire any old animations that have completed, inserting them into
@@ -32,6 +167,7 @@ pub fn update_animation_state<E>(
     new_animations_receiver: &Receiver<Animation>,
     pipeline_id: PipelineId,
     timer: &Timer,
+    image_animations_running: bool,
 )
 where
     E: TElement,
@@ -137,7 +273,7 @@ where
                           .push(new_running_animation)
     }
 
-    let animation_state = if running_animations.is_empty() {
+    let animation_state = if running_animations.is_empty() && !image_animations_running {
         AnimationState::NoAnimationsPresent
     } else {
         AnimationState::AnimationsPresent
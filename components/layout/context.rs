@@ -4,12 +4,14 @@
 
 //! Data needed by the layout thread.
 
+use animation::ImageAnimations;
 use display_list::items::{WebRenderImageInfo, OpaqueNode};
 use fnv::FnvHasher;
 use gfx::font_cache_thread::FontCacheThread;
 use gfx::font_context::FontContext;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use msg::constellation_msg::PipelineId;
+use net_traits::image::base::Image;
 use net_traits::image_cache::{CanRequestImages, ImageCache, ImageState};
 use net_traits::image_cache::{ImageOrMetadataAvailable, UsePlaceholder};
 use opaque_node::OpaqueNodeMethods;
@@ -26,6 +28,10 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use style::context::RegisteredSpeculativePainter;
 use style::context::SharedStyleContext;
+use style::properties::ComputedValues;
+use svg::{self, SvgRasterizations};
+use svg_renderer::SvgImage;
+use webrender_api::{ImageKey, LayoutSize};
 
 pub type LayoutFontContext = FontContext<FontCacheThread>;
 
@@ -73,6 +79,12 @@ pub struct LayoutContext<'a> {
                                                   WebRenderImageInfo,
                                                   BuildHasherDefault<FnvHasher>>>>,
 
+    /// The animated images of the document.
+    pub image_animations: Arc<Mutex<ImageAnimations>>,
+
+    /// The images of the inline `<svg>` elements of the document.
+    pub svg_rasterizations: Arc<Mutex<SvgRasterizations>>,
+
     /// Paint worklets
     pub registered_painters: &'a RegisteredPainters,
 
@@ -165,8 +177,12 @@ impl<'a> LayoutContext<'a> {
 
         match self.get_or_request_image_or_meta(node, url.clone(), use_placeholder) {
             Some(ImageOrMetadataAvailable::ImageAvailable(image, _)) => {
-                let image_info = WebRenderImageInfo::from_image(&*image);
-                if image_info.key.is_none() {
+                let mut image_info = WebRenderImageInfo::from_image(&*image);
+                if image.should_animate() {
+                    // Each node paints the image with a key of its own, so it can't be cached.
+                    image_info.key = self.active_image_key(node, &image);
+                    Some(image_info)
+                } else if image_info.key.is_none() {
                     Some(image_info)
                 } else {
                     let mut webrender_image_cache = self.webrender_image_cache.write();
@@ -178,6 +194,35 @@ impl<'a> LayoutContext<'a> {
             None | Some(ImageOrMetadataAvailable::MetadataAvailable(_)) => None,
         }
     }
+
+    /// The key to paint the image of `node` with.
+    pub fn active_image_key(&self, node: OpaqueNode, image: &Arc<Image>) -> Option<ImageKey> {
+        let now = self.style_context.timer.seconds();
+        self.image_animations.lock().unwrap().active_image_key(node, image, now)
+    }
+
+    /// The key of a rasterization of the vector image with key `key` at
+    /// `size`, for the current device pixel ratio. Falls back to `key` when
+    /// the image can't be rasterized at that size.
+    pub fn vector_image_key(&self, key: ImageKey, size: LayoutSize) -> ImageKey {
+        let device_pixel_ratio = self.style_context.device_pixel_ratio().get();
+        self.image_cache.rasterize_vector_image(key, size, device_pixel_ratio).unwrap_or(key)
+    }
+
+    /// The key of an image of the document of the inline `<svg>` element of
+    /// `node`, painted into a content box of `size` for the current device
+    /// pixel ratio.
+    pub fn svg_image_key(&self,
+                         node: OpaqueNode,
+                         document: &Arc<SvgImage>,
+                         size: LayoutSize,
+                         style: &ComputedValues)
+                         -> Option<ImageKey> {
+        let device_pixel_ratio = self.style_context.device_pixel_ratio().get();
+        svg::with_environment(self, style, |environment| {
+            self.svg_rasterizations.lock().unwrap().image_key(node, document, size, device_pixel_ratio, environment)
+        })
+    }
 }
 
 /// A registered painter
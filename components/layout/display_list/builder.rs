@@ -70,6 +70,7 @@ use style::values::generics::ui::Cursor;
 use style_traits::CSSPixel;
 use style_traits::ToCss;
 use style_traits::cursor::CursorKind;
+use svg::{PaintedSvg, PaintedSvgs};
 use table_cell::CollapsedBordersForCell;
 use webrender_api::{self, BorderRadius, BorderSide, BoxShadowClipMode, ColorF, ExternalScrollId};
 use webrender_api::{FilterOp, GlyphInstance, ImageRendering, LayoutRect, LayoutSize};
@@ -356,6 +357,9 @@ pub struct DisplayListBuildState<'a> {
 
     /// Stores text runs to answer text queries used to place a cursor inside text.
     pub indexable_text: IndexableText,
+
+    /// Stores inline `<svg>` elements to find the elements inside them that events target.
+    pub painted_svgs: PaintedSvgs,
 }
 
 impl<'a> DisplayListBuildState<'a> {
@@ -375,6 +379,7 @@ impl<'a> DisplayListBuildState<'a> {
                 ClippingAndScrolling::simple(ClipScrollNodeIndex::root_scroll_node()),
             iframe_sizes: Vec::new(),
             indexable_text: IndexableText::default(),
+            painted_svgs: PaintedSvgs::default(),
         }
     }
 
@@ -1030,6 +1035,11 @@ impl FragmentDisplayListBuilding for Fragment {
             index,
         );
 
+        let mut key = webrender_image.key.unwrap();
+        if webrender_image.is_vector {
+            key = state.layout_context.vector_image_key(key, placement.tile_size.to_layout());
+        }
+
         state.clipping_and_scrolling_scope(|state| {
             if !placement.clip_radii.is_zero() {
                 let clip_id =
@@ -1049,7 +1059,7 @@ impl FragmentDisplayListBuilding for Fragment {
             debug!("(building display list) adding background image.");
             state.add_display_item(DisplayItem::Image(Box::new(ImageDisplayItem {
                 base: base,
-                id: webrender_image.key.unwrap(),
+                id: key,
                 stretch_size: placement.tile_size.to_layout(),
                 tile_spacing: placement.tile_spacing.to_layout(),
                 image_rendering: style.get_inherited_box().image_rendering.to_layout(),
@@ -1101,6 +1111,7 @@ impl FragmentDisplayListBuilding for Fragment {
                 width: draw_result.width,
                 height: draw_result.height,
                 key: draw_result.image_key,
+                is_vector: false,
             };
 
             for url in draw_result.missing_image_urls.into_iter() {
@@ -1833,8 +1844,7 @@ impl FragmentDisplayListBuilding for Fragment {
             SpecificFragmentInfo::InlineBlock(_) |
             SpecificFragmentInfo::InlineAbsoluteHypothetical(_) |
             SpecificFragmentInfo::InlineAbsolute(_) |
-            SpecificFragmentInfo::TruncatedFragment(_) |
-            SpecificFragmentInfo::Svg(_) => {
+            SpecificFragmentInfo::TruncatedFragment(_) => {
                 if opts::get().show_debug_fragment_borders {
                     self.build_debug_borders_around_fragment(
                         state,
@@ -1871,7 +1881,15 @@ impl FragmentDisplayListBuilding for Fragment {
             SpecificFragmentInfo::Image(ref image_fragment) => {
                 // Place the image into the display list.
                 if let Some(ref image) = image_fragment.image {
-                    if let Some(id) = image.id {
+                    let key = if image.is_vector {
+                        image.id.map(|key| {
+                            let size = stacking_relative_content_box.size.to_layout();
+                            state.layout_context.vector_image_key(key, size)
+                        })
+                    } else {
+                        state.layout_context.active_image_key(self.node, image)
+                    };
+                    if let Some(id) = key {
                         let base = create_base_display_item(state);
                         state.add_display_item(DisplayItem::Image(Box::new(ImageDisplayItem {
                             base,
@@ -1886,6 +1904,30 @@ impl FragmentDisplayListBuilding for Fragment {
                     }
                 }
             },
+            SpecificFragmentInfo::Svg(ref svg_fragment_info) => {
+                let document = match svg_fragment_info.document {
+                    Some(ref document) => document,
+                    None => return,
+                };
+                let size = stacking_relative_content_box.size.to_layout();
+                let key = state.layout_context.svg_image_key(self.node, document, size, &self.style);
+                if let Some(id) = key {
+                    let base = create_base_display_item(state);
+                    state.add_display_item(DisplayItem::Image(Box::new(ImageDisplayItem {
+                        base,
+                        id,
+                        stretch_size: size,
+                        tile_spacing: LayoutSize::zero(),
+                        image_rendering: ImageRendering::Auto,
+                    })));
+                }
+                state.painted_svgs.insert(self.node, PaintedSvg {
+                    document: document.clone(),
+                    nodes: svg_fragment_info.nodes.clone(),
+                    size: Size2D::new(size.width, size.height),
+                    style: self.style.clone(),
+                });
+            },
             SpecificFragmentInfo::Canvas(ref canvas_fragment_info) => {
                 let image_key = match canvas_fragment_info.source {
                     CanvasFragmentSource::WebGL(image_key) => image_key,
@@ -3191,16 +3233,45 @@ impl IndexableText {
         self.inner.get(&node).map(|x| x.as_slice())
     }
 
-    // Returns the text index within a node for the point of interest.
-    pub fn text_index(&self, node: OpaqueNode, point_in_item: Point2D<Au>) -> Option<usize> {
-        let item = self.inner.get(&node)?;
-        // TODO(#20020): access all elements
-        let point = point_in_item + item[0].origin.to_vector();
-        let offset = point - item[0].baseline_origin;
+    // Returns the text index within a node for the point of interest. `point_in_item` is
+    // relative to the text item that was hit, and `point_in_page` tells which of the node's
+    // items, i.e. which of its line boxes, that was.
+    pub fn text_index(
+        &self,
+        node: OpaqueNode,
+        point_in_item: Point2D<Au>,
+        point_in_page: Point2D<Au>,
+    ) -> Option<usize> {
+        let items = self.inner.get(&node)?;
+        let item_origin = point_in_page - point_in_item.to_vector();
+        let (index, item) = items.iter().enumerate().min_by_key(|&(_, item)| {
+            let distance = item.origin - item_origin;
+            distance.x.0.abs() + distance.y.0.abs()
+        })?;
+        let point = point_in_item + item.origin.to_vector();
+        let offset = point - item.baseline_origin;
         Some(
-            item[0]
-                .text_run
-                .range_index_of_advance(&item[0].range, offset.x),
+            IndexableText::chars_before(items, index) +
+                item.text_run.range_index_of_advance(&item.range, offset.x),
         )
     }
+
+    // Returns the number of characters of the node that come before its item at `index`.
+    fn chars_before(items: &[IndexableTextItem], index: usize) -> usize {
+        let first = &items[0];
+        let item = &items[index];
+        if Arc::ptr_eq(&first.text_run, &item.text_run) {
+            // Count whitespace dropped at line breaks too.
+            let start = first.range.begin().to_usize();
+            let end = item.range.begin().to_usize();
+            return first.text_run.text[start..end].chars().count();
+        }
+        items[..index]
+            .iter()
+            .map(|item| {
+                let range = item.range.begin().to_usize()..item.range.end().to_usize();
+                item.text_run.text[range].chars().count()
+            })
+            .sum()
+    }
 }
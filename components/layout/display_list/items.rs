@@ -150,6 +150,54 @@ impl gfx_traits::DisplayList for DisplayList {
 
         false
     }
+
+    /// Gather the geometry of the content of each node, which is used for the
+    /// largest contentful paint and layout shift metrics.
+    fn painted_nodes(&self) -> Vec<gfx_traits::PaintedNode> {
+        fn to_content_rect(rect: &LayoutRect) -> gfx_traits::ContentRect {
+            gfx_traits::ContentRect::new(rect.origin.x, rect.origin.y, rect.size.width, rect.size.height)
+        }
+
+        let mut nodes: Vec<gfx_traits::PaintedNode> = vec![];
+        let mut indices = HashMap::new();
+        for item in &self.list {
+            let contentful = match *item {
+                DisplayItem::Text(_) | DisplayItem::Image(_) => true,
+                DisplayItem::PushTextShadow(_) |
+                DisplayItem::PopAllTextShadows(_) |
+                DisplayItem::PushStackingContext(_) |
+                DisplayItem::PopStackingContext(_) |
+                DisplayItem::DefineClipScrollNode(_) => continue,
+                _ => false,
+            };
+            let base = item.base();
+            let bounds = to_content_rect(&base.bounds);
+            let content_bounds = if contentful {
+                bounds.intersection(&to_content_rect(&base.clip_rect))
+            } else {
+                None
+            };
+
+            let node = base.metadata.node.id();
+            let index = *indices.entry(node).or_insert_with(|| {
+                nodes.push(gfx_traits::PaintedNode {
+                    node: node,
+                    bounds: bounds,
+                    content_bounds: None,
+                });
+                nodes.len() - 1
+            });
+            let painted_node = &mut nodes[index];
+            painted_node.bounds = painted_node.bounds.union(&bounds);
+            if let Some(content_bounds) = content_bounds {
+                painted_node.content_bounds = Some(match painted_node.content_bounds {
+                    Some(ref previous) => previous.union(&content_bounds),
+                    None => content_bounds,
+                });
+            }
+        }
+        nodes
+    }
 }
 
 /// Display list sections that make up a stacking context. Each section  here refers
@@ -1008,6 +1056,8 @@ pub struct WebRenderImageInfo {
     pub width: u32,
     pub height: u32,
     pub key: Option<ImageKey>,
+    /// Whether the image can be rasterized again at the size it is painted.
+    pub is_vector: bool,
 }
 
 impl WebRenderImageInfo {
@@ -1017,6 +1067,7 @@ impl WebRenderImageInfo {
             width: image.width,
             height: image.height,
             key: image.id,
+            is_vector: image.is_vector,
         }
     }
 }
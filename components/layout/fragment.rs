@@ -63,6 +63,7 @@ use style::values::computed::{Length, LengthOrPercentage, LengthOrPercentageOrAu
 use style::values::computed::counters::ContentItem;
 use style::values::generics::box_::{Perspective, VerticalAlign};
 use style::values::generics::transform;
+use svg_renderer::SvgImage;
 use text;
 use text::TextRunScanner;
 use webrender_api::{self, LayoutTransform};
@@ -377,6 +378,11 @@ impl CanvasFragmentInfo {
 pub struct SvgFragmentInfo {
     pub dom_width: Au,
     pub dom_height: Au,
+    /// The document made of the element and its SVG descendants, which is
+    /// painted into the content box.
+    pub document: Option<Arc<SvgImage>>,
+    /// The DOM node of each node of the document, for hit-testing.
+    pub nodes: Arc<Vec<OpaqueNode>>,
 }
 
 impl SvgFragmentInfo {
@@ -384,6 +390,8 @@ impl SvgFragmentInfo {
         SvgFragmentInfo {
             dom_width: Au::from_px(data.width as i32),
             dom_height: Au::from_px(data.height as i32),
+            document: data.document,
+            nodes: data.nodes,
         }
     }
 }
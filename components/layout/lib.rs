@@ -9,6 +9,7 @@ extern crate atomic_refcell;
 #[macro_use]
 extern crate bitflags;
 extern crate canvas_traits;
+extern crate cssparser;
 extern crate euclid;
 extern crate fnv;
 extern crate fxhash;
@@ -40,6 +41,7 @@ extern crate servo_url;
 extern crate smallvec;
 extern crate style;
 extern crate style_traits;
+extern crate svg_renderer;
 extern crate unicode_bidi;
 extern crate unicode_script;
 extern crate webrender_api;
@@ -72,6 +74,7 @@ pub mod parallel;
 mod persistent_list;
 pub mod query;
 pub mod sequential;
+pub mod svg;
 mod table;
 mod table_caption;
 mod table_cell;
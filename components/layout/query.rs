@@ -18,14 +18,17 @@ use msg::constellation_msg::PipelineId;
 use opaque_node::OpaqueNodeMethods;
 use script_layout_interface::{LayoutElementType, LayoutNodeType};
 use script_layout_interface::StyleData;
-use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
+use script_layout_interface::rpc::{BoxSizes, BoxSizesResponse, ContentBoxResponse, ContentBoxesResponse};
+use script_layout_interface::rpc::LayoutRPC;
 use script_layout_interface::rpc::{NodeGeometryResponse, NodeScrollIdResponse};
 use script_layout_interface::rpc::{OffsetParentResponse, ResolvedStyleResponse, StyleResponse};
-use script_layout_interface::rpc::TextIndexResponse;
+use script_layout_interface::rpc::{SvgHitTestResponse, TextIndexResponse};
 use script_layout_interface::wrapper_traits::{LayoutNode, ThreadSafeLayoutElement, ThreadSafeLayoutNode};
 use script_traits::LayoutMsg as ConstellationMsg;
 use script_traits::UntrustedNodeAddress;
 use sequential;
+use servo_arc::Arc as ServoArc;
+use servo_url::ServoUrl;
 use std::cmp::{min, max};
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
@@ -35,9 +38,13 @@ use style::computed_values::visibility::T as Visibility;
 use style::context::{StyleContext, ThreadLocalStyleContext};
 use style::dom::TElement;
 use style::logical_geometry::{WritingMode, BlockFlowDirection, InlineBaseDirection};
-use style::properties::{style_structs, PropertyId, PropertyDeclarationId, LonghandId};
+use style::properties::{ComputedValues, Importance, PropertyDeclarationBlock, SourcePropertyDeclaration};
+use style::properties::{parse_one_declaration_into, style_structs, PropertyId, PropertyDeclarationId};
+use style::properties::{LonghandId, ShorthandId};
 use style::selector_parser::PseudoElement;
-use style_traits::ToCss;
+use style::shared_lock::SharedRwLock;
+use style_traits::{ParsingMode, ToCss};
+use svg::PaintedSvgs;
 use webrender_api::ExternalScrollId;
 use wrapper::LayoutNodeLayoutData;
 
@@ -53,12 +60,18 @@ pub struct LayoutThreadData {
 
     pub indexable_text: IndexableText,
 
+    /// The inline `<svg>` elements painted by the display list.
+    pub painted_svgs: PaintedSvgs,
+
     /// A queued response for the union of the content boxes of a node.
     pub content_box_response: Option<Rect<Au>>,
 
     /// A queued response for the content boxes of a node.
     pub content_boxes_response: Vec<Rect<Au>>,
 
+    /// A queued response for the box sizes of a node.
+    pub box_sizes_response: Option<BoxSizes>,
+
     /// A queued response for the client {top, left, width, height} of a node in pixels.
     pub client_rect_response: Rect<i32>,
 
@@ -71,6 +84,9 @@ pub struct LayoutThreadData {
     /// A queued response for the resolved style property of an element.
     pub resolved_style_response: String,
 
+    /// A queued response for a `font` shorthand value resolved against an element.
+    pub resolved_font_style_response: Option<ServoArc<style_structs::Font>>,
+
     /// A queued response for the offset parent/rect of a node.
     pub offset_parent_response: OffsetParentResponse,
 
@@ -83,6 +99,9 @@ pub struct LayoutThreadData {
     /// Index in a text fragment. We need this do determine the insertion point.
     pub text_index_response: TextIndexResponse,
 
+    /// A queued response for the element of an inline `<svg>` element at a point.
+    pub svg_hit_test_response: SvgHitTestResponse,
+
     /// A queued response for the list of nodes at a given point.
     pub nodes_from_point_response: Vec<UntrustedNodeAddress>,
 
@@ -120,6 +139,12 @@ impl LayoutRPC for LayoutRPCImpl {
         ContentBoxesResponse(rw_data.content_boxes_response.clone())
     }
 
+    fn box_sizes(&self) -> BoxSizesResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        BoxSizesResponse(rw_data.box_sizes_response)
+    }
+
     fn nodes_from_point_response(&self) -> Vec<UntrustedNodeAddress> {
         let &LayoutRPCImpl(ref rw_data) = self;
         let rw_data = rw_data.lock().unwrap();
@@ -153,6 +178,13 @@ impl LayoutRPC for LayoutRPCImpl {
         ResolvedStyleResponse(rw_data.resolved_style_response.clone())
     }
 
+    /// Retrieves the computed font of a resolved `font` shorthand value.
+    fn resolved_font_style(&self) -> Option<ServoArc<style_structs::Font>> {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        rw_data.resolved_font_style_response.clone()
+    }
+
     fn offset_parent(&self) -> OffsetParentResponse {
         let &LayoutRPCImpl(ref rw_data) = self;
         let rw_data = rw_data.lock().unwrap();
@@ -171,6 +203,12 @@ impl LayoutRPC for LayoutRPCImpl {
         rw_data.text_index_response.clone()
     }
 
+    fn svg_hit_test(&self) -> SvgHitTestResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        rw_data.svg_hit_test_response.clone()
+    }
+
     fn element_inner_text(&self) -> String {
         let &LayoutRPCImpl(ref rw_data) = self;
         let rw_data = rw_data.lock().unwrap();
@@ -233,6 +271,42 @@ impl FragmentBorderBoxIterator for CollectingFragmentBorderBoxIterator {
     }
 }
 
+struct BoxSizesFragmentBorderBoxIterator {
+    node_address: OpaqueNode,
+    result: Option<BoxSizes>,
+}
+
+impl BoxSizesFragmentBorderBoxIterator {
+    fn new(node_address: OpaqueNode) -> BoxSizesFragmentBorderBoxIterator {
+        BoxSizesFragmentBorderBoxIterator {
+            node_address: node_address,
+            result: None,
+        }
+    }
+}
+
+impl FragmentBorderBoxIterator for BoxSizesFragmentBorderBoxIterator {
+    fn process(&mut self, fragment: &Fragment, _: i32, border_box: &Rect<Au>) {
+        let writing_mode = fragment.style.writing_mode;
+        let border = fragment.border_width().to_physical(writing_mode);
+        let border_padding = fragment.border_padding.to_physical(writing_mode);
+        let content_box = Rect::new(
+            Point2D::new(border_padding.left - border.left, border_padding.top - border.top),
+            Size2D::new(border_box.size.width - border_padding.horizontal(),
+                        border_box.size.height - border_padding.vertical()));
+        self.result = Some(BoxSizes {
+            border_box: border_box.size,
+            content_box: content_box,
+            vertical: writing_mode.is_vertical(),
+        });
+    }
+
+    fn should_process(&mut self, fragment: &Fragment) -> bool {
+        // Only the first fragment of the node counts.
+        self.result.is_none() && fragment.contains_node(self.node_address)
+    }
+}
+
 enum Side {
     Left,
     Right,
@@ -353,6 +427,13 @@ pub fn process_content_box_request<N: LayoutNode>(
     iterator.rect
 }
 
+pub fn process_box_sizes_request<N: LayoutNode>(requested_node: N, layout_root: &mut Flow)
+        -> Option<BoxSizes> {
+    let mut iterator = BoxSizesFragmentBorderBoxIterator::new(requested_node.opaque());
+    sequential::iterate_through_flow_tree_fragment_border_boxes(layout_root, &mut iterator);
+    iterator.result
+}
+
 pub fn process_content_boxes_request<N: LayoutNode>(requested_node: N, layout_root: &mut Flow)
         -> Vec<Rect<Au>> {
     // FIXME(pcwalton): This has not been updated to handle the stacking context relative
@@ -666,6 +747,70 @@ pub fn process_node_scroll_area_request< N: LayoutNode>(requested_node: N, layou
     }
 }
 
+/// Parses `value` as a `font` shorthand and computes it against `parent_style`.
+fn compute_font_declaration<E>(context: &LayoutContext,
+                               parent_style: &ComputedValues,
+                               value: &str,
+                               url_data: &ServoUrl,
+                               shared_lock: &SharedRwLock)
+                               -> Option<ServoArc<ComputedValues>>
+    where E: TElement,
+{
+    let mut declarations = SourcePropertyDeclaration::new();
+    let result = parse_one_declaration_into(&mut declarations,
+                                            PropertyId::Shorthand(ShorthandId::Font),
+                                            value,
+                                            url_data,
+                                            None,
+                                            ParsingMode::DEFAULT,
+                                            context.style_context.quirks_mode());
+    if result.is_err() {
+        return None;
+    }
+
+    let mut block = PropertyDeclarationBlock::new();
+    block.extend(declarations.drain(), Importance::Normal);
+
+    let stylist = &context.style_context.stylist;
+    Some(stylist.compute_for_declarations::<E>(&context.style_context.guards,
+                                               parent_style,
+                                               ServoArc::new(shared_lock.wrap(block))))
+}
+
+/// Return the computed font of a `font` shorthand value, resolved as the
+/// canvas `font` attribute would be against the given element.
+/// <https://html.spec.whatwg.org/multipage/#dom-context-2d-font>
+pub fn process_resolved_font_style_request<'a, N>(context: &LayoutContext,
+                                                  node: N,
+                                                  value: &str,
+                                                  url_data: ServoUrl,
+                                                  shared_lock: &SharedRwLock)
+                                                  -> Option<ServoArc<style_structs::Font>>
+    where N: LayoutNode,
+{
+    let element = node.as_element().unwrap();
+
+    // Relative keywords and lengths are computed relative to the element's
+    // font, or to the default `10px sans-serif` when it has no style.
+    let parent_style = match element.borrow_data() {
+        Some(ref data) if data.has_styles() => data.styles.primary().clone(),
+        _ => {
+            compute_font_declaration::<N::ConcreteElement>(context,
+                                                           ComputedValues::initial_values(),
+                                                           "10px sans-serif",
+                                                           &url_data,
+                                                           shared_lock)?
+        },
+    };
+
+    let style = compute_font_declaration::<N::ConcreteElement>(context,
+                                                               &parent_style,
+                                                               value,
+                                                               &url_data,
+                                                               shared_lock)?;
+    Some(style.clone_font())
+}
+
 /// Return the resolved value of property for a given (pseudo)element.
 /// <https://drafts.csswg.org/cssom/#resolved-value>
 pub fn process_resolved_style_request<'a, N>(context: &LayoutContext,
@@ -0,0 +1,284 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Inline `<svg>` elements, which are replaced elements whose content is
+//! rendered into an image rather than laid out into boxes.
+
+use ServoArc;
+use app_units::Au;
+use context::{LayoutContext, with_thread_local_font_context};
+use cssparser::{Parser, ParserInput, RGBA};
+use display_list::items::OpaqueNode;
+use euclid::{Point2D, Size2D, Vector2D};
+use fnv::{FnvHashMap, FnvHashSet};
+use gfx::font::{FontDescriptor, FontGroup, FontRef, GlyphOutlineSegment, ShapingFlags, ShapingOptions};
+use gfx::font_template::FontTemplateDescriptor;
+use gfx::text::glyph::ByteIndex;
+use ordered_float::NotNan;
+use range::Range;
+use servo_atoms::Atom;
+use std::mem;
+use std::rc::Rc;
+use std::sync::Arc;
+use style::properties::ComputedValues;
+use style::properties::style_structs::Font as FontStyleStruct;
+use style::values::computed::font::{FontStyle, FontWeight, SingleFontFamily};
+use style::values::generics::font::FontStyle as GenericFontStyle;
+use style_traits::ToCss;
+use svg_renderer::{Environment, Font, Segment, SvgImage, TextOutliner};
+use unicode_script::{Script, get_script};
+use webrender_api::{DeviceUintSize, ImageData, ImageDescriptor, ImageFormat, ImageKey};
+use webrender_api::{LayoutSize, RenderApi, Transaction};
+
+/// The rasterizations of the inline `<svg>` elements painted in a document.
+pub struct SvgRasterizations {
+    webrender_api: RenderApi,
+    rasterizations: FnvHashMap<OpaqueNode, Rasterization>,
+    /// The nodes whose rasterization was painted by the display list being
+    /// built.
+    painted_nodes: FnvHashSet<OpaqueNode>,
+    /// The keys that the display list being built stopped using.
+    unused_keys: Vec<ImageKey>,
+    /// The keys that the last display list stopped using, which the display
+    /// list it replaces may still be showing.
+    retired_keys: Vec<ImageKey>,
+}
+
+/// An image of a document, and what it was painted with.
+struct Rasterization {
+    document: Arc<SvgImage>,
+    size: DeviceUintSize,
+    environment: (String, f32, RGBA),
+    key: ImageKey,
+}
+
+impl SvgRasterizations {
+    pub fn new(webrender_api: RenderApi) -> SvgRasterizations {
+        SvgRasterizations {
+            webrender_api: webrender_api,
+            rasterizations: FnvHashMap::default(),
+            painted_nodes: FnvHashSet::default(),
+            unused_keys: vec![],
+            retired_keys: vec![],
+        }
+    }
+
+    /// Returns the key of an image of the document of the `<svg>` element
+    /// of `node`, painted into a viewport of `size` CSS pixels, rasterizing
+    /// it again if the document or anything it was painted with changed.
+    pub fn image_key(&mut self,
+                     node: OpaqueNode,
+                     document: &Arc<SvgImage>,
+                     size: LayoutSize,
+                     device_pixel_ratio: f32,
+                     environment: &Environment)
+                     -> Option<ImageKey> {
+        let device_size = DeviceUintSize::new((size.width * device_pixel_ratio).ceil() as u32,
+                                              (size.height * device_pixel_ratio).ceil() as u32);
+        if device_size.width == 0 || device_size.height == 0 {
+            return None;
+        }
+        self.painted_nodes.insert(node);
+        let environment_key = (environment.font_family.to_owned(), environment.font_size, environment.color);
+        if let Some(rasterization) = self.rasterizations.get(&node) {
+            if Arc::ptr_eq(&rasterization.document, document) && rasterization.size == device_size &&
+               rasterization.environment == environment_key {
+                return Some(rasterization.key);
+            }
+        }
+
+        let raster = document.paint(size.to_untyped(), device_pixel_ratio, environment)?;
+        let descriptor = ImageDescriptor {
+            size: DeviceUintSize::new(raster.width, raster.height),
+            stride: None,
+            format: ImageFormat::BGRA8,
+            offset: 0,
+            is_opaque: false,
+            allow_mipmaps: true,
+        };
+        let key = self.webrender_api.generate_image_key();
+        let mut txn = Transaction::new();
+        txn.add_image(key, descriptor, ImageData::new(raster.bytes), None);
+        self.webrender_api.update_resources(txn.resource_updates);
+
+        let rasterization = Rasterization {
+            document: document.clone(),
+            size: device_size,
+            environment: environment_key,
+            key: key,
+        };
+        if let Some(replaced) = self.rasterizations.insert(node, rasterization) {
+            self.unused_keys.push(replaced.key);
+        }
+        Some(key)
+    }
+
+    /// Called before building a display list.
+    pub fn start_display_list(&mut self) {
+        self.painted_nodes.clear();
+    }
+
+    /// Called after building a display list, to forget the rasterizations
+    /// it doesn't paint, and delete the images that no display list being
+    /// shown uses anymore.
+    pub fn finish_display_list(&mut self) {
+        let painted_nodes = &self.painted_nodes;
+        let unused_keys = &mut self.unused_keys;
+        self.rasterizations.retain(|node, rasterization| {
+            if painted_nodes.contains(node) {
+                return true;
+            }
+            unused_keys.push(rasterization.key);
+            false
+        });
+
+        let retired_keys = mem::replace(&mut self.retired_keys, mem::replace(unused_keys, vec![]));
+        if retired_keys.is_empty() {
+            return;
+        }
+        let mut txn = Transaction::new();
+        for key in retired_keys {
+            txn.delete_image(key);
+        }
+        self.webrender_api.update_resources(txn.resource_updates);
+    }
+}
+
+/// An inline `<svg>` element painted by a display list.
+pub struct PaintedSvg {
+    pub document: Arc<SvgImage>,
+    /// The DOM node of each node of the document.
+    pub nodes: Arc<Vec<OpaqueNode>>,
+    /// The size of the content box, in CSS pixels.
+    pub size: Size2D<f32>,
+    pub style: ServoArc<ComputedValues>,
+}
+
+/// The inline `<svg>` elements painted by a display list, for hit-testing
+/// their content.
+#[derive(Default)]
+pub struct PaintedSvgs {
+    inner: FnvHashMap<OpaqueNode, PaintedSvg>,
+}
+
+impl PaintedSvgs {
+    pub fn insert(&mut self, node: OpaqueNode, svg: PaintedSvg) {
+        self.inner.insert(node, svg);
+    }
+
+    /// The node of the element painted topmost at a point of the content box
+    /// of the `<svg>` element of `node`, if any.
+    pub fn hit_test(&self, layout_context: &LayoutContext, node: OpaqueNode, point: Point2D<f32>)
+                    -> Option<OpaqueNode> {
+        let svg = self.inner.get(&node)?;
+        let hit = with_environment(layout_context, &svg.style, |environment| {
+            svg.document.hit_test(svg.size, point, environment)
+        })?;
+        svg.nodes.get(hit).cloned()
+    }
+}
+
+/// Calls `f` with what the document of an `<svg>` element with the computed
+/// style `style` inherits from the page.
+pub fn with_environment<F, R>(layout_context: &LayoutContext, style: &ComputedValues, f: F) -> R
+    where F: FnOnce(&Environment) -> R
+{
+    let font_style = style.get_font();
+    let font_family = font_style.font_family.to_css_string();
+    let outliner = FontOutliner {
+        layout_context: layout_context,
+        font_style: font_style,
+    };
+    f(&Environment {
+        color: style.get_color().color,
+        font_family: &font_family,
+        font_size: font_style.font_size.size().to_f32_px(),
+        text_outliner: Some(&outliner),
+    })
+}
+
+/// Lays out the text of inline SVG with the fonts of the page.
+struct FontOutliner<'a, 'b: 'a> {
+    layout_context: &'a LayoutContext<'b>,
+    /// The font of the `<svg>` element, whose stretch and variant its text
+    /// inherits.
+    font_style: &'a FontStyleStruct,
+}
+
+impl<'a, 'b> TextOutliner for FontOutliner<'a, 'b> {
+    fn outline(&self, text: &str, font: &Font) -> (Vec<Segment>, f32) {
+        let weight = if font.bold { FontWeight::bold() } else { FontWeight::normal() };
+        let style = if font.italic { GenericFontStyle::Italic } else { FontStyle::normal() };
+        let descriptor = FontDescriptor {
+            template_descriptor: FontTemplateDescriptor::new(weight, self.font_style.font_stretch, style),
+            variant: self.font_style.font_variant_caps,
+            pt_size: Au::from_f32_px(font.size),
+        };
+        let families = parse_font_families(font.family);
+        let mut font_group = FontGroup::from_families(descriptor, families.iter());
+
+        with_thread_local_font_context(self.layout_context, |font_context| {
+            // Text is shaped in runs of characters with the same font.
+            let mut runs: Vec<(FontRef, String)> = vec![];
+            for character in text.chars() {
+                let font = match font_group.find_by_codepoint(font_context, character) {
+                    Some(font) => font,
+                    None => continue,
+                };
+                if let Some(&mut (ref run_font, ref mut run_text)) = runs.last_mut() {
+                    if Rc::ptr_eq(run_font, &font) {
+                        run_text.push(character);
+                        continue;
+                    }
+                }
+                runs.push((font, character.to_string()));
+            }
+
+            let mut segments = vec![];
+            let mut pen = Au(0);
+            for (font, text) in runs {
+                let script = text.chars()
+                    .map(get_script)
+                    .find(|&script| script != Script::Common && script != Script::Inherited)
+                    .unwrap_or(Script::Common);
+                let options = ShapingOptions {
+                    letter_spacing: None,
+                    word_spacing: (Au(0), NotNan::new(0.).unwrap()),
+                    script: script,
+                    flags: ShapingFlags::empty(),
+                };
+                let mut font = font.borrow_mut();
+                let glyphs = font.shape_text(&text, &options);
+                let range = Range::new(ByteIndex(0), glyphs.len());
+                for glyph in glyphs.iter_glyphs_for_byte_range(&range) {
+                    let offset = glyph.offset().unwrap_or(Point2D::zero());
+                    let origin = Vector2D::new((pen + offset.x).to_f32_px(), offset.y.to_f32_px());
+                    for segment in font.glyph_outline(glyph.id()).unwrap_or_default() {
+                        segments.push(to_segment(segment).translate(origin));
+                    }
+                    pen += glyph.advance();
+                }
+            }
+            (segments, pen.to_f32_px())
+        })
+    }
+}
+
+/// The families of a `font-family` value, or `serif` if it is invalid.
+fn parse_font_families(value: &str) -> Vec<SingleFontFamily> {
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    parser.parse_entirely(|parser| parser.parse_comma_separated(SingleFontFamily::parse))
+        .unwrap_or_else(|_| vec![SingleFontFamily::Generic(Atom::from("serif"))])
+}
+
+fn to_segment(segment: GlyphOutlineSegment) -> Segment {
+    match segment {
+        GlyphOutlineSegment::MoveTo(point) => Segment::MoveTo(point),
+        GlyphOutlineSegment::LineTo(point) => Segment::LineTo(point),
+        GlyphOutlineSegment::QuadTo(control, point) => Segment::QuadTo(control, point),
+        GlyphOutlineSegment::CubicTo(control1, control2, point) => Segment::CubicTo(control1, control2, point),
+        GlyphOutlineSegment::Close => Segment::Close,
+    }
+}
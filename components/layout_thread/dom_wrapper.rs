@@ -38,9 +38,10 @@ use layout::wrapper::GetRawData;
 use msg::constellation_msg::{BrowsingContextId, PipelineId};
 use range::Range;
 use script::layout_exports::{CharacterDataTypeId, ElementTypeId, HTMLElementTypeId, NodeTypeId};
-use script::layout_exports::{Document, Element, Node, Text};
-use script::layout_exports::{LayoutCharacterDataHelpers, LayoutDocumentHelpers};
+use script::layout_exports::{Document, Element, HTMLSlotElement, Node, ShadowRoot, Text};
+use script::layout_exports::{LayoutCharacterDataHelpers, LayoutDocumentHelpers, LayoutHTMLSlotElementHelpers};
 use script::layout_exports::{LayoutElementHelpers, LayoutNodeHelpers, LayoutDom, RawLayoutElementHelpers};
+use script::layout_exports::LayoutShadowRootHelpers;
 use script::layout_exports::NodeFlags;
 use script::layout_exports::PendingRestyle;
 use script_layout_interface::{HTMLCanvasData, LayoutNodeType, SVGSVGData, TrustedNodeAddress};
@@ -63,16 +64,17 @@ use std::sync::atomic::Ordering;
 use style::CaseSensitivityExt;
 use style::applicable_declarations::ApplicableDeclarationBlock;
 use style::attr::AttrValue;
-use style::context::SharedStyleContext;
+use style::context::{QuirksMode as StyleQuirksMode, SharedStyleContext};
 use style::data::ElementData;
-use style::dom::{DomChildren, LayoutIterator, NodeInfo, OpaqueNode};
+use style::dom::{LayoutIterator, NodeInfo, OpaqueNode};
 use style::dom::{TDocument, TElement, TNode, TShadowRoot};
 use style::element_state::*;
 use style::font_metrics::ServoMetricsProvider;
+use style::media_queries::Device;
 use style::properties::{ComputedValues, PropertyDeclarationBlock};
 use style::selector_parser::{AttrValue as SelectorAttrValue, NonTSPseudoClass, PseudoClassStringArg};
 use style::selector_parser::{PseudoElement, SelectorImpl, extended_filtering};
-use style::shared_lock::{SharedRwLock as StyleSharedRwLock, Locked as StyleLocked};
+use style::shared_lock::{SharedRwLock as StyleSharedRwLock, SharedRwLockReadGuard, Locked as StyleLocked};
 use style::str::is_whitespace;
 use style::stylist::CascadeData;
 
@@ -151,35 +153,64 @@ impl<'ln> NodeInfo for ServoLayoutNode<'ln> {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum Impossible { }
+/// A wrapper around shadow roots that ensures layout can only ever access
+/// safe properties.
+#[derive(Clone, Copy)]
+pub struct ServoShadowRoot<'lr> {
+    shadow_root: LayoutDom<ShadowRoot>,
+    chain: PhantomData<&'lr ()>,
+}
 
-#[derive(Clone, Copy, PartialEq)]
-pub struct ShadowRoot<'lr>(Impossible, PhantomData<&'lr ()>);
+impl<'lr> PartialEq for ServoShadowRoot<'lr> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.shadow_root == other.shadow_root
+    }
+}
 
-impl<'lr> TShadowRoot for ShadowRoot<'lr> {
+impl<'lr> TShadowRoot for ServoShadowRoot<'lr> {
     type ConcreteNode = ServoLayoutNode<'lr>;
 
     fn as_node(&self) -> Self::ConcreteNode {
-        match self.0 { }
+        ServoLayoutNode::from_layout_js(self.shadow_root.upcast())
     }
 
     fn host(&self) -> ServoLayoutElement<'lr> {
-        match self.0 { }
+        ServoLayoutElement::from_layout_js(unsafe { self.shadow_root.get_host_for_layout() })
     }
 
     fn style_data<'a>(&self) -> &'a CascadeData
     where
         Self: 'a,
     {
-        match self.0 { }
+        unsafe { self.shadow_root.get_style_data_for_layout() }
+    }
+}
+
+impl<'lr> ServoShadowRoot<'lr> {
+    fn from_layout_js(shadow_root: LayoutDom<ShadowRoot>) -> ServoShadowRoot<'lr> {
+        ServoShadowRoot {
+            shadow_root,
+            chain: PhantomData,
+        }
+    }
+
+    /// Rebuilds the cascade data of this shadow tree if its stylesheets
+    /// changed since the last reflow.
+    pub fn flush_stylesheets(&self,
+                             device: &Device,
+                             quirks_mode: StyleQuirksMode,
+                             guard: &SharedRwLockReadGuard) {
+        unsafe {
+            self.shadow_root.flush_stylesheets_for_layout::<ServoLayoutElement>(device, quirks_mode, guard)
+        }
     }
 }
 
 impl<'ln> TNode for ServoLayoutNode<'ln> {
     type ConcreteDocument = ServoLayoutDocument<'ln>;
     type ConcreteElement = ServoLayoutElement<'ln>;
-    type ConcreteShadowRoot = ShadowRoot<'ln>;
+    type ConcreteShadowRoot = ServoShadowRoot<'ln>;
 
     fn parent_node(&self) -> Option<Self> {
         unsafe {
@@ -216,7 +247,14 @@ impl<'ln> TNode for ServoLayoutNode<'ln> {
     }
 
     fn traversal_parent(&self) -> Option<ServoLayoutElement<'ln>> {
-        self.parent_element()
+        if let Some(slot) = unsafe { self.node.assigned_slot_ref() } {
+            return Some(ServoLayoutElement::from_layout_js(slot.upcast()));
+        }
+        let parent = self.parent_node()?;
+        if let Some(shadow) = parent.as_shadow_root() {
+            return Some(shadow.host());
+        }
+        parent.as_element()
     }
 
     fn opaque(&self) -> OpaqueNode {
@@ -235,8 +273,8 @@ impl<'ln> TNode for ServoLayoutNode<'ln> {
         self.node.downcast().map(ServoLayoutDocument::from_layout_js)
     }
 
-    fn as_shadow_root(&self) -> Option<ShadowRoot<'ln>> {
-        None
+    fn as_shadow_root(&self) -> Option<ServoShadowRoot<'ln>> {
+        self.node.downcast().map(ServoShadowRoot::from_layout_js)
     }
 
     fn is_in_document(&self) -> bool {
@@ -307,6 +345,78 @@ impl<'ln> ServoLayoutNode<'ln> {
     pub unsafe fn get_jsmanaged(&self) -> &LayoutDom<Node> {
         &self.node
     }
+
+    /// The first child of this node in the flat tree, that is, the first
+    /// child of its shadow root if this is a shadow host, and the first node
+    /// assigned to it if this is a slot with assigned nodes.
+    fn flat_tree_first_child(&self) -> Option<Self> {
+        if let Some(element) = self.as_element() {
+            if let Some(shadow) = element.shadow_root() {
+                return shadow.as_node().first_child();
+            }
+            if let Some(slot) = element.as_slot() {
+                if let Some(node) = unsafe { slot.assigned_node_for_layout(0) } {
+                    return Some(unsafe { self.new_with_this_lifetime(&node) });
+                }
+            }
+        }
+        self.first_child()
+    }
+
+    /// The next sibling of this node in the flat tree, which for a node
+    /// assigned to a slot is the next node assigned to the same slot.
+    fn flat_tree_next_sibling(&self) -> Option<Self> {
+        let slot = match unsafe { self.node.assigned_slot_ref() } {
+            Some(slot) => slot,
+            None => return self.next_sibling(),
+        };
+        // Slottables are assigned in tree order, so the next node assigned to
+        // the same slot is the first of the following siblings assigned to it.
+        let mut next = self.next_sibling();
+        while let Some(node) = next {
+            if unsafe { node.node.assigned_slot_ref() } == Some(slot) {
+                return Some(node);
+            }
+            next = node.next_sibling();
+        }
+        None
+    }
+}
+
+/// An iterator over the children of a node in the flat tree.
+pub struct ServoFlatTreeChildren<'ln> {
+    /// The slot whose assigned nodes are iterated, if any.
+    slot: Option<LayoutDom<HTMLSlotElement>>,
+    /// The index of the next assigned node of the slot.
+    index: usize,
+    /// The next child, if the node isn't a slot with assigned nodes.
+    current: Option<ServoLayoutNode<'ln>>,
+}
+
+impl<'ln> ServoFlatTreeChildren<'ln> {
+    fn new(parent: ServoLayoutElement<'ln>) -> Self {
+        let slot = parent.as_slot().filter(|slot| unsafe { slot.assigned_node_for_layout(0).is_some() });
+        ServoFlatTreeChildren {
+            slot,
+            index: 0,
+            current: if slot.is_some() { None } else { parent.as_node().flat_tree_first_child() },
+        }
+    }
+}
+
+impl<'ln> Iterator for ServoFlatTreeChildren<'ln> {
+    type Item = ServoLayoutNode<'ln>;
+
+    fn next(&mut self) -> Option<ServoLayoutNode<'ln>> {
+        if let Some(slot) = self.slot {
+            let node = unsafe { slot.assigned_node_for_layout(self.index)? };
+            self.index += 1;
+            return Some(ServoLayoutNode::from_layout_js(node));
+        }
+        let node = self.current.take()?;
+        self.current = node.flat_tree_next_sibling();
+        Some(node)
+    }
 }
 
 // A wrapper around documents that ensures ayout can only ever access safe properties.
@@ -354,6 +464,12 @@ impl<'ld> ServoLayoutDocument<'ld> {
         unsafe { self.document.style_shared_lock() }
     }
 
+    pub fn shadow_roots(&self) -> Vec<ServoShadowRoot<'ld>> {
+        unsafe {
+            self.document.shadow_roots().into_iter().map(ServoShadowRoot::from_layout_js).collect()
+        }
+    }
+
     pub fn from_layout_js(doc: LayoutDom<Document>) -> ServoLayoutDocument<'ld> {
         ServoLayoutDocument {
             document: doc,
@@ -381,7 +497,7 @@ impl<'le> fmt::Debug for ServoLayoutElement<'le> {
 
 impl<'le> TElement for ServoLayoutElement<'le> {
     type ConcreteNode = ServoLayoutNode<'le>;
-    type TraversalChildrenIterator = DomChildren<Self::ConcreteNode>;
+    type TraversalChildrenIterator = ServoFlatTreeChildren<'le>;
 
     type FontMetricsProvider = ServoMetricsProvider;
 
@@ -390,7 +506,12 @@ impl<'le> TElement for ServoLayoutElement<'le> {
     }
 
     fn traversal_children(&self) -> LayoutIterator<Self::TraversalChildrenIterator> {
-        LayoutIterator(self.as_node().dom_children())
+        LayoutIterator(ServoFlatTreeChildren::new(*self))
+    }
+
+    fn inheritance_parent(&self) -> Option<Self> {
+        // Inherit along the flat tree, from slots and shadow hosts.
+        self.traversal_parent()
     }
 
     fn is_html_element(&self) -> bool {
@@ -575,12 +696,25 @@ impl<'le> TElement for ServoLayoutElement<'le> {
         }
     }
 
-    fn shadow_root(&self) -> Option<ShadowRoot<'le>> {
-        None
+    fn shadow_root(&self) -> Option<ServoShadowRoot<'le>> {
+        unsafe {
+            self.element.get_shadow_root_for_layout().map(ServoShadowRoot::from_layout_js)
+        }
     }
 
-    fn containing_shadow(&self) -> Option<ShadowRoot<'le>> {
-        None
+    fn containing_shadow(&self) -> Option<ServoShadowRoot<'le>> {
+        if !unsafe { self.as_node().node.get_flag(NodeFlags::IS_IN_SHADOW_TREE) } {
+            return None;
+        }
+        let mut current = self.as_node();
+        while let Some(parent) = current.parent_node() {
+            current = parent;
+        }
+        current.as_shadow_root()
+    }
+
+    fn has_part_attr(&self) -> bool {
+        unsafe { self.element.has_part_attr_for_layout() }
     }
 }
 
@@ -606,6 +740,10 @@ impl<'le> ServoLayoutElement<'le> {
         }
     }
 
+    fn as_slot(&self) -> Option<LayoutDom<HTMLSlotElement>> {
+        self.element.downcast()
+    }
+
     #[inline]
     fn get_attr_enum(&self, namespace: &Namespace, name: &LocalName) -> Option<&AttrValue> {
         unsafe {
@@ -645,7 +783,7 @@ impl<'le> ServoLayoutElement<'le> {
             // we get that wrong.  I have in-flight patches to fix all this
             // stuff up, so we just always propagate this bit for now.
             el.set_dirty_descendants();
-            current = el.parent_element();
+            current = el.traversal_parent();
         }
     }
 }
@@ -668,11 +806,18 @@ impl<'le> ::selectors::Element for ServoLayoutElement<'le> {
     }
 
     fn parent_node_is_shadow_root(&self) -> bool {
-        false
+        self.as_node().parent_node().map_or(false, |parent| parent.as_shadow_root().is_some())
     }
 
     fn containing_shadow_host(&self) -> Option<Self> {
-        None
+        self.containing_shadow().map(|shadow| shadow.host())
+    }
+
+    fn assigned_slot(&self) -> Option<Self> {
+        unsafe {
+            self.as_node().node.assigned_slot_ref()
+                .map(|slot| ServoLayoutElement::from_layout_js(slot.upcast()))
+        }
     }
 
     fn prev_sibling_element(&self) -> Option<ServoLayoutElement<'le>> {
@@ -838,6 +983,11 @@ impl<'le> ::selectors::Element for ServoLayoutElement<'le> {
         }
     }
 
+    #[inline]
+    fn is_part(&self, name: &Atom) -> bool {
+        unsafe { self.element.has_part_for_layout(name) }
+    }
+
     fn is_html_element_in_html_document(&self) -> bool {
         unsafe {
             if !self.element.is_html_element() {
@@ -868,12 +1018,12 @@ impl<'a> PartialEq for ServoThreadSafeLayoutNode<'a> {
 
 impl<'ln> DangerousThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
     unsafe fn dangerous_first_child(&self) -> Option<Self> {
-            self.get_jsmanaged().first_child_ref()
-                .map(|node| self.new_with_this_lifetime(&node))
+            self.node.flat_tree_first_child()
+                .map(|node| ServoThreadSafeLayoutNode::new(&node))
     }
     unsafe fn dangerous_next_sibling(&self) -> Option<Self> {
-            self.get_jsmanaged().next_sibling_ref()
-                .map(|node| self.new_with_this_lifetime(&node))
+            self.node.flat_tree_next_sibling()
+                .map(|node| ServoThreadSafeLayoutNode::new(&node))
     }
 }
 
@@ -930,11 +1080,20 @@ impl<'ln> ThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
     }
 
     fn parent_style(&self) -> Arc<ComputedValues> {
-        let parent = self.node.parent_node().unwrap().as_element().unwrap();
+        let parent = self.node.traversal_parent().unwrap();
         let parent_data = parent.get_data().unwrap().borrow();
         parent_data.styles.primary().clone()
     }
 
+    fn parent_selected_style(&self) -> Arc<ComputedValues> {
+        let parent = self.node.traversal_parent().unwrap();
+        let parent_data = parent.get_data().unwrap().borrow();
+        parent_data.styles.pseudos
+            .get(&PseudoElement::Selection)
+            .unwrap_or(parent_data.styles.primary())
+            .clone()
+    }
+
     fn debug_id(self) -> usize {
         self.node.debug_id()
     }
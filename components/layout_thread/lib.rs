@@ -59,16 +59,16 @@ use dom_wrapper::{ServoLayoutElement, ServoLayoutDocument, ServoLayoutNode};
 use dom_wrapper::drop_style_and_layout_data;
 use embedder_traits::resources::{self, Resource};
 use euclid::{Point2D, Rect, Size2D, TypedScale, TypedSize2D};
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use fxhash::FxHashMap;
 use gfx::font;
 use gfx::font_cache_thread::FontCacheThread;
 use gfx::font_context;
-use gfx_traits::{Epoch, node_id_from_scroll_id};
+use gfx_traits::{ContentRect, Epoch, node_id_from_scroll_id};
 use histogram::Histogram;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
-use layout::animation;
+use layout::animation::{self, ImageAnimations};
 use layout::construct::ConstructionResult;
 use layout::context::LayoutContext;
 use layout::context::RegisteredPainter;
@@ -80,12 +80,16 @@ use layout::flow::{Flow, GetBaseFlow, ImmutableFlowUtils, MutableOwnedFlowUtils}
 use layout::flow_ref::FlowRef;
 use layout::incremental::{LayoutDamageComputation, RelayoutMode, SpecialRestyleDamage};
 use layout::layout_debug;
+use layout::opaque_node::OpaqueNodeMethods;
 use layout::parallel;
-use layout::query::{LayoutRPCImpl, LayoutThreadData, process_content_box_request, process_content_boxes_request};
+use layout::query::{LayoutRPCImpl, LayoutThreadData, process_box_sizes_request, process_content_box_request};
+use layout::query::process_content_boxes_request;
 use layout::query::{process_element_inner_text_query, process_node_geometry_request};
 use layout::query::{process_node_scroll_area_request, process_node_scroll_id_request};
 use layout::query::{process_offset_parent_query, process_resolved_style_request, process_style_query};
+use layout::query::process_resolved_font_style_request;
 use layout::sequential;
+use layout::svg::{PaintedSvgs, SvgRasterizations};
 use layout::traversal::{ComputeStackingRelativePositions, PreorderFlowTraversal, RecalcStyleAndConstructFlows};
 use layout::wrapper::LayoutNodeLayoutData;
 use layout_traits::LayoutThreadFactory;
@@ -102,9 +106,9 @@ use profile_traits::time::{TimerMetadataFrameType, TimerMetadataReflowType};
 use script_layout_interface::message::{Msg, NewLayoutThreadInfo, NodesFromPointQueryType, Reflow};
 use script_layout_interface::message::{ReflowComplete, QueryMsg, ReflowGoal, ScriptReflow};
 use script_layout_interface::rpc::{LayoutRPC, StyleResponse, OffsetParentResponse};
-use script_layout_interface::rpc::TextIndexResponse;
+use script_layout_interface::rpc::{SvgHitTestResponse, TextIndexResponse};
 use script_layout_interface::wrapper_traits::LayoutNode;
-use script_traits::{ConstellationControlMsg, LayoutControlMsg, LayoutMsg as ConstellationMsg};
+use script_traits::{AnimationState, ConstellationControlMsg, LayoutControlMsg, LayoutMsg as ConstellationMsg};
 use script_traits::{DrawAPaintImageResult, PaintWorkletError};
 use script_traits::{ScrollState, UntrustedNodeAddress};
 use script_traits::Painter;
@@ -246,6 +250,15 @@ pub struct LayoutThread {
     webrender_image_cache: Arc<RwLock<FnvHashMap<(ServoUrl, UsePlaceholder),
                                                  WebRenderImageInfo>>>,
 
+    /// The animated images of the document.
+    image_animations: Arc<Mutex<ImageAnimations>>,
+
+    /// Whether the compositor was last told that images are animating.
+    image_animations_were_running: Cell<bool>,
+
+    /// The rasterizations of the inline `<svg>` elements of the document.
+    svg_rasterizations: Arc<Mutex<SvgRasterizations>>,
+
     /// The executors for paint worklets.
     registered_painters: RegisteredPaintersImpl,
 
@@ -531,21 +544,28 @@ impl LayoutThread {
                     constellation_chan: constellation_chan,
                     display_list: None,
                     indexable_text: IndexableText::default(),
+                    painted_svgs: PaintedSvgs::default(),
                     content_box_response: None,
                     content_boxes_response: Vec::new(),
+                    box_sizes_response: None,
                     client_rect_response: Rect::zero(),
                     scroll_id_response: None,
                     scroll_area_response: Rect::zero(),
                     resolved_style_response: String::new(),
+                    resolved_font_style_response: None,
                     offset_parent_response: OffsetParentResponse::empty(),
                     style_response: StyleResponse(None),
                     scroll_offsets: HashMap::new(),
                     text_index_response: TextIndexResponse(None),
+                    svg_hit_test_response: SvgHitTestResponse(None),
                     nodes_from_point_response: vec![],
                     element_inner_text_response: String::new(),
                 })),
             webrender_image_cache:
                 Arc::new(RwLock::new(FnvHashMap::default())),
+            image_animations: Arc::new(Mutex::new(ImageAnimations::new(webrender_api_sender.create_api()))),
+            image_animations_were_running: Cell::new(false),
+            svg_rasterizations: Arc::new(Mutex::new(SvgRasterizations::new(webrender_api_sender.create_api()))),
             timer:
                 if PREFS.get("layout.animations.test.enabled")
                            .as_boolean().unwrap_or(false) {
@@ -599,6 +619,8 @@ impl LayoutThread {
             image_cache: self.image_cache.clone(),
             font_cache_thread: Mutex::new(self.font_cache_thread.clone()),
             webrender_image_cache: self.webrender_image_cache.clone(),
+            image_animations: self.image_animations.clone(),
+            svg_rasterizations: self.svg_rasterizations.clone(),
             pending_images: if script_initiated_layout { Some(Mutex::new(vec![])) } else { None },
             newly_transitioning_nodes: if script_initiated_layout { Some(Mutex::new(vec![])) } else { None },
             registered_painters: &self.registered_painters,
@@ -784,11 +806,35 @@ impl LayoutThread {
             Msg::SetNavigationStart(time) => {
                 self.paint_time_metrics.set_navigation_start(time);
             },
+            Msg::SetDocumentVisibility(visible) => {
+                let now = self.timer.seconds();
+                self.image_animations.lock().unwrap().set_paused(!visible, now);
+                self.update_image_animation_state();
+            },
         }
 
         true
     }
 
+    /// Tells the compositor whether it needs to keep ticking this pipeline
+    /// for its animated images, if that changed.
+    fn update_image_animation_state(&self) {
+        let running = self.image_animations.lock().unwrap().are_running();
+        if running == self.image_animations_were_running.get() {
+            return;
+        }
+        self.image_animations_were_running.set(running);
+        let animation_state = if running || !self.running_animations.read().is_empty() {
+            AnimationState::AnimationsPresent
+        } else {
+            AnimationState::NoAnimationsPresent
+        };
+        let msg = ConstellationMsg::ChangeRunningAnimationsState(self.id, animation_state);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            warn!("Sending animation state to constellation failed ({}).", e);
+        }
+    }
+
     fn collect_reports<'a, 'b>(&self,
                                reports_chan: ReportsChan,
                                possibly_locked_rw_data: &mut RwData<'a, 'b>) {
@@ -1003,8 +1049,14 @@ impl LayoutThread {
             if layout_root.base().restyle_damage.contains(ServoRestyleDamage::REPAINT) ||
                     rw_data.display_list.is_none() {
                 if reflow_goal.needs_display_list() {
+                    self.image_animations.lock().unwrap().start_display_list();
+                    self.svg_rasterizations.lock().unwrap().start_display_list();
                     let mut build_state =
                         sequential::build_display_list_for_subtree(layout_root, layout_context);
+                    self.image_animations.lock().unwrap().finish_display_list();
+                    self.svg_rasterizations.lock().unwrap().finish_display_list();
+                    self.image_cache.finish_display_list();
+                    self.update_image_animation_state();
 
                     debug!("Done building display list.");
 
@@ -1034,6 +1086,9 @@ impl LayoutThread {
                     rw_data.indexable_text = std::mem::replace(
                         &mut build_state.indexable_text,
                         IndexableText::default());
+                    rw_data.painted_svgs = std::mem::replace(
+                        &mut build_state.painted_svgs,
+                        PaintedSvgs::default());
                     rw_data.display_list = Some(Arc::new(build_state.to_display_list()));
                 }
             }
@@ -1070,12 +1125,13 @@ impl LayoutThread {
             epoch.next();
             self.epoch.set(epoch);
 
+            let viewport = ContentRect::new(0., 0., viewport_size.width, viewport_size.height);
             let viewport_size = webrender_api::LayoutSize::from_untyped(&viewport_size);
 
             // Observe notifications about rendered frames if needed right before
             // sending the display list to WebRender in order to set time related
             // Progressive Web Metrics.
-            self.paint_time_metrics.maybe_observe_paint_time(self, epoch, &*display_list);
+            self.paint_time_metrics.maybe_observe_paint_time(self, epoch, &*display_list, &viewport);
 
             let mut txn = webrender_api::Transaction::new();
             txn.set_display_list(
@@ -1123,6 +1179,9 @@ impl LayoutThread {
                         &QueryMsg::ContentBoxesQuery(_) => {
                             rw_data.content_boxes_response = Vec::new();
                         },
+                        &QueryMsg::BoxSizesQuery(_) => {
+                            rw_data.box_sizes_response = None;
+                        },
                         &QueryMsg::NodesFromPointQuery(..) => {
                             rw_data.nodes_from_point_response = Vec::new();
                         },
@@ -1138,6 +1197,9 @@ impl LayoutThread {
                         &QueryMsg::ResolvedStyleQuery(_, _, _) => {
                             rw_data.resolved_style_response = String::new();
                         },
+                        &QueryMsg::ResolvedFontStyleQuery(..) => {
+                            rw_data.resolved_font_style_response = None;
+                        },
                         &QueryMsg::OffsetParentQuery(_) => {
                             rw_data.offset_parent_response = OffsetParentResponse::empty();
                         },
@@ -1147,6 +1209,9 @@ impl LayoutThread {
                         &QueryMsg::TextIndexQuery(..) => {
                             rw_data.text_index_response = TextIndexResponse(None);
                         }
+                        &QueryMsg::SvgHitTestQuery(..) => {
+                            rw_data.svg_hit_test_response = SvgHitTestResponse(None);
+                        },
                         &QueryMsg::ElementInnerTextQuery(_) => {
                             rw_data.element_inner_text_response = String::new();
                         },
@@ -1258,7 +1323,7 @@ impl LayoutThread {
             // Propagate the descendant bit up the ancestors. Do this before
             // the restyle calculation so that we can also do it for new
             // unstyled nodes, which the descendants bit helps us find.
-            if let Some(parent) = el.parent_element() {
+            if let Some(parent) = el.traversal_parent() {
                 unsafe { parent.note_dirty_descendant() };
             }
 
@@ -1287,6 +1352,12 @@ impl LayoutThread {
 
         self.stylist.flush(&guards, Some(element), Some(&map));
 
+        // Shadow trees keep their author styles apart from the stylist, so
+        // they need to be flushed on their own before styling.
+        for shadow_root in document.shadow_roots() {
+            shadow_root.flush_stylesheets(self.stylist.device(), self.stylist.quirks_mode(), &author_guard);
+        }
+
         // Create a layout context for use throughout the following passes.
         let mut layout_context =
             self.build_layout_context(guards.clone(), true, &map);
@@ -1399,17 +1470,31 @@ impl LayoutThread {
                     let node = unsafe { ServoLayoutNode::new(&node) };
                     rw_data.content_boxes_response = process_content_boxes_request(node, root_flow);
                 },
-                &QueryMsg::TextIndexQuery(node, point_in_node) => {
+                &QueryMsg::BoxSizesQuery(node) => {
+                    let node = unsafe { ServoLayoutNode::new(&node) };
+                    rw_data.box_sizes_response = process_box_sizes_request(node, root_flow);
+                },
+                &QueryMsg::TextIndexQuery(node, point_in_node, point_in_page) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
                     let opaque_node = node.opaque();
                     let point_in_node = Point2D::new(
                         Au::from_f32_px(point_in_node.x),
                         Au::from_f32_px(point_in_node.y)
                     );
+                    let point_in_page = Point2D::new(
+                        Au::from_f32_px(point_in_page.x),
+                        Au::from_f32_px(point_in_page.y)
+                    );
                     rw_data.text_index_response = TextIndexResponse(
-                        rw_data.indexable_text.text_index(opaque_node, point_in_node)
+                        rw_data.indexable_text.text_index(opaque_node, point_in_node, point_in_page)
                     );
                 },
+                &QueryMsg::SvgHitTestQuery(node, point_in_node) => {
+                    let node = unsafe { ServoLayoutNode::new(&node) };
+                    let hit = rw_data.painted_svgs.hit_test(context, node.opaque(), point_in_node);
+                    rw_data.svg_hit_test_response =
+                        SvgHitTestResponse(hit.map(|node| node.to_untrusted_node_address()));
+                },
                 &QueryMsg::NodeGeometryQuery(node) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
                     rw_data.client_rect_response = process_node_geometry_request(node, root_flow);
@@ -1431,6 +1516,17 @@ impl LayoutThread {
                                                        property,
                                                        root_flow);
                 },
+                &QueryMsg::ResolvedFontStyleQuery(node, ref value) => {
+                    let node = unsafe { ServoLayoutNode::new(&node) };
+                    let url = self.url.clone();
+                    let shared_lock = self.document_shared_lock.as_ref().unwrap();
+                    rw_data.resolved_font_style_response =
+                        process_resolved_font_style_request(context,
+                                                            node,
+                                                            value,
+                                                            url,
+                                                            shared_lock);
+                },
                 &QueryMsg::OffsetParentQuery(node) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
                     rw_data.offset_parent_response = process_offset_parent_query(node, root_flow);
@@ -1457,9 +1553,24 @@ impl LayoutThread {
                         flags
                     );
 
-                    rw_data.nodes_from_point_response = results.items.iter()
-                       .map(|item| UntrustedNodeAddress(item.tag.0 as *const c_void))
-                       .collect()
+                    // Inline `<svg>` elements are painted as single images, so the
+                    // element painted at the point inside them is looked up
+                    // separately. Their image is above their background and
+                    // border, so only their topmost item is relative to their
+                    // content box.
+                    let mut hit_svgs = FnvHashSet::default();
+                    let mut nodes = vec![];
+                    for item in &results.items {
+                        let node = OpaqueNode(item.tag.0 as usize);
+                        if hit_svgs.insert(node) {
+                            let point = item.point_relative_to_item.to_untyped();
+                            if let Some(hit) = rw_data.painted_svgs.hit_test(context, node, point) {
+                                nodes.push(hit.to_untrusted_node_address());
+                            }
+                        }
+                        nodes.push(UntrustedNodeAddress(item.tag.0 as *const c_void));
+                    }
+                    rw_data.nodes_from_point_response = nodes;
                 },
                 &QueryMsg::ElementInnerTextQuery(node) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
@@ -1521,6 +1632,15 @@ impl LayoutThread {
                                                                false,
                                                                &snapshots);
 
+            // Show the next frame of the animated images that are due for it,
+            // by updating their images in WebRender rather than repainting.
+            let now = self.timer.seconds();
+            let mut txn = webrender_api::Transaction::new();
+            if self.image_animations.lock().unwrap().tick(now, &mut txn) {
+                txn.generate_frame();
+                self.webrender_api.send_transaction(self.webrender_document, txn);
+            }
+
             {
                 // Perform an abbreviated style recalc that operates without access to the DOM.
                 let animations = self.running_animations.read();
@@ -1571,6 +1691,7 @@ impl LayoutThread {
                 &self.new_animations_receiver,
                 self.id,
                 &self.timer,
+                self.image_animations.lock().unwrap().are_running(),
             );
         }
 
@@ -16,7 +16,7 @@ extern crate servo_config;
 extern crate servo_url;
 extern crate time;
 
-use gfx_traits::{Epoch, DisplayList};
+use gfx_traits::{ContentRect, DisplayList, Epoch, PaintedNode};
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
 use profile_traits::time::{ProfilerChan, ProfilerCategory, send_profile_data};
@@ -27,6 +27,7 @@ use servo_url::ServoUrl;
 use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::mem;
 use time::precise_time_ns;
 
 pub trait ProfilerMetadataFactory {
@@ -260,12 +261,75 @@ impl ProgressiveWebMetric for InteractiveMetrics {
     }
 }
 
+/// Returns the visible area of the largest text or image content of the painted nodes.
+fn largest_content_size(painted_nodes: &[PaintedNode], viewport: &ContentRect) -> Option<f32> {
+    painted_nodes.iter()
+        .filter_map(|node| node.content_bounds)
+        .filter_map(|bounds| bounds.intersection(viewport))
+        .map(|visible| visible.area())
+        .fold(None, |largest, area| match largest {
+            Some(largest) if largest >= area => Some(largest),
+            _ => Some(area),
+        })
+}
+
+/// Returns the area of the union of the given rectangles.
+fn region_area(rects: &[ContentRect]) -> f32 {
+    let mut edges: Vec<f32> = rects.iter().flat_map(|rect| vec![rect.x, rect.max_x()]).collect();
+    edges.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    edges.dedup();
+
+    let mut area = 0.;
+    for slab in edges.windows(2) {
+        let (left, right) = (slab[0], slab[1]);
+        let mut spans: Vec<(f32, f32)> = rects.iter()
+            .filter(|rect| rect.x <= left && rect.max_x() >= right)
+            .map(|rect| (rect.y, rect.max_y()))
+            .collect();
+        spans.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        let mut covered = 0.;
+        let mut current: Option<(f32, f32)> = None;
+        for (top, bottom) in spans {
+            current = match current {
+                Some((start, end)) if top <= end => Some((start, end.max(bottom))),
+                Some((start, end)) => {
+                    covered += end - start;
+                    Some((top, bottom))
+                },
+                None => Some((top, bottom)),
+            };
+        }
+        if let Some((start, end)) = current {
+            covered += end - start;
+        }
+        area += covered * (right - left);
+    }
+    area
+}
+
+struct PendingPaintMetric {
+    profiler_metadata: Option<TimerMetadata>,
+    is_contentful: bool,
+    /// The size of the new largest contentful paint candidate of the frame, if any.
+    largest_contentful_size: Option<f32>,
+}
+
 // https://w3c.github.io/paint-timing/
+// https://wicg.github.io/largest-contentful-paint/
+// https://wicg.github.io/layout-instability/
 pub struct PaintTimeMetrics {
-    pending_metrics: RefCell<HashMap<Epoch, (Option<TimerMetadata>, bool)>>,
+    pending_metrics: RefCell<HashMap<Epoch, PendingPaintMetric>>,
     navigation_start: Option<u64>,
     first_paint: Cell<Option<u64>>,
     first_contentful_paint: Cell<Option<u64>>,
+    largest_contentful_paint: Cell<Option<u64>>,
+    /// The size of the largest content observed so far, including the one
+    /// of frames which have not been painted yet.
+    largest_contentful_size: Cell<f32>,
+    /// The bounds of the nodes painted by the last observed display list.
+    painted_node_bounds: RefCell<HashMap<usize, ContentRect>>,
+    cumulative_layout_shift: Cell<f64>,
     pipeline_id: PipelineId,
     time_profiler_chan: ProfilerChan,
     constellation_chan: IpcSender<LayoutMsg>,
@@ -286,6 +350,10 @@ impl PaintTimeMetrics {
             navigation_start: None,
             first_paint: Cell::new(None),
             first_contentful_paint: Cell::new(None),
+            largest_contentful_paint: Cell::new(None),
+            largest_contentful_size: Cell::new(0.),
+            painted_node_bounds: RefCell::new(HashMap::new()),
+            cumulative_layout_shift: Cell::new(0.),
             pipeline_id,
             time_profiler_chan,
             constellation_chan,
@@ -317,17 +385,41 @@ impl PaintTimeMetrics {
         &self,
         profiler_metadata_factory: &T,
         epoch: Epoch,
-        display_list: &DisplayList)
+        display_list: &DisplayList,
+        viewport: &ContentRect)
     where T: ProfilerMetadataFactory {
-        if self.first_paint.get().is_some() && self.first_contentful_paint.get().is_some() {
-            // If we already set all paint metrics, we just bail out.
+        // Largest contentful paint and layout shift are both reported relative
+        // to the navigation start, so there is no point in gathering the
+        // painted nodes before it is set.
+        let largest_contentful_size = if self.navigation_start.is_some() {
+            let painted_nodes = display_list.painted_nodes();
+            self.observe_layout_shift(profiler_metadata_factory, &painted_nodes, viewport);
+
+            // A frame is a new largest contentful paint candidate if it paints
+            // larger content than any of the previous ones.
+            match largest_content_size(&painted_nodes, viewport) {
+                Some(size) if size > self.largest_contentful_size.get() => {
+                    self.largest_contentful_size.set(size);
+                    Some(size)
+                },
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        if self.first_paint.get().is_some() && self.first_contentful_paint.get().is_some() &&
+            largest_contentful_size.is_none() {
+            // If we already set all paint metrics and there is no new largest
+            // contentful paint candidate, we just bail out.
             return;
         }
 
-        self.pending_metrics.borrow_mut().insert(epoch, (
-            profiler_metadata_factory.new_metadata(),
-            display_list.is_contentful(),
-        ));
+        self.pending_metrics.borrow_mut().insert(epoch, PendingPaintMetric {
+            profiler_metadata: profiler_metadata_factory.new_metadata(),
+            is_contentful: display_list.is_contentful(),
+            largest_contentful_size,
+        });
 
         // Send the pending metric information to the compositor thread.
         // The compositor will record the current time after painting the
@@ -339,29 +431,29 @@ impl PaintTimeMetrics {
     }
 
     pub fn maybe_set_metric(&self, epoch: Epoch, paint_time: u64) {
-        if self.first_paint.get().is_some() && self.first_contentful_paint.get().is_some() ||
-            self.navigation_start.is_none() {
-            // If we already set all paint metrics or we have not set navigation start yet,
-            // we just bail out.
+        if self.navigation_start.is_none() {
+            // If we have not set navigation start yet, we just bail out.
             return;
         }
 
         if let Some(pending_metric) = self.pending_metrics.borrow_mut().remove(&epoch) {
-            let profiler_metadata = pending_metric.0;
-            set_metric(
-                self,
-                profiler_metadata.clone(),
-                ProgressiveWebMetricType::FirstPaint,
-                ProfilerCategory::TimeToFirstPaint,
-                &self.first_paint,
-                Some(paint_time),
-                &self.url,
-            );
-
-            if pending_metric.1 {
+            let profiler_metadata = pending_metric.profiler_metadata;
+            if self.first_paint.get().is_none() {
                 set_metric(
                     self,
-                    profiler_metadata,
+                    profiler_metadata.clone(),
+                    ProgressiveWebMetricType::FirstPaint,
+                    ProfilerCategory::TimeToFirstPaint,
+                    &self.first_paint,
+                    Some(paint_time),
+                    &self.url,
+                );
+            }
+
+            if pending_metric.is_contentful && self.first_contentful_paint.get().is_none() {
+                set_metric(
+                    self,
+                    profiler_metadata.clone(),
                     ProgressiveWebMetricType::FirstContentfulPaint,
                     ProfilerCategory::TimeToFirstContentfulPaint,
                     &self.first_contentful_paint,
@@ -369,6 +461,88 @@ impl PaintTimeMetrics {
                     &self.url,
                 );
             }
+
+            if let Some(size) = pending_metric.largest_contentful_size {
+                set_metric(
+                    self,
+                    profiler_metadata,
+                    ProgressiveWebMetricType::LargestContentfulPaint(size as u64),
+                    ProfilerCategory::TimeToLargestContentfulPaint,
+                    &self.largest_contentful_paint,
+                    Some(paint_time),
+                    &self.url,
+                );
+            }
+        }
+    }
+
+    /// Compare the bounds of the nodes painted by a new display list with the
+    /// ones painted by the previous one, and report the layout shift
+    /// between both, if any.
+    ///
+    /// <https://wicg.github.io/layout-instability/#sec-layout-shift>
+    fn observe_layout_shift<T>(&self,
+                               profiler_metadata_factory: &T,
+                               painted_nodes: &[PaintedNode],
+                               viewport: &ContentRect)
+    where T: ProfilerMetadataFactory {
+        let previous_bounds = mem::replace(
+            &mut *self.painted_node_bounds.borrow_mut(),
+            painted_nodes.iter().map(|node| (node.node, node.bounds)).collect(),
+        );
+
+        // Nodes which existed in the previous frame and whose starting point
+        // moved are unstable, and the area they covered in both frames makes
+        // up the impact region.
+        let mut impact_region = vec![];
+        let mut move_distance = 0f32;
+        for node in painted_nodes {
+            let previous = match previous_bounds.get(&node.node) {
+                Some(previous) => previous,
+                None => continue,
+            };
+            let (dx, dy) = (node.bounds.x - previous.x, node.bounds.y - previous.y);
+            if dx == 0. && dy == 0. {
+                continue;
+            }
+            let previous_visible = previous.intersection(viewport);
+            let visible = node.bounds.intersection(viewport);
+            if previous_visible.is_none() && visible.is_none() {
+                continue;
+            }
+            impact_region.extend(previous_visible);
+            impact_region.extend(visible);
+            move_distance = move_distance.max(dx.abs()).max(dy.abs());
+        }
+
+        if impact_region.is_empty() || viewport.is_empty() {
+            return;
+        }
+
+        let impact_fraction = region_area(&impact_region) / viewport.area();
+        let distance_fraction = move_distance / viewport.width.max(viewport.height);
+        let score = (impact_fraction.min(1.) * distance_fraction.min(1.)) as f64;
+        let cumulative_layout_shift = self.cumulative_layout_shift.get() + score;
+        self.cumulative_layout_shift.set(cumulative_layout_shift);
+
+        // Queue performance observer notification.
+        let time = precise_time_ns() - self.navigation_start.unwrap();
+        self.send_queued_constellation_msg(ProgressiveWebMetricType::LayoutShift(score), time);
+
+        // The time profiler only deals with durations, so the score is sent as
+        // a duration whose length in milliseconds is the score itself.
+        send_profile_data(
+            ProfilerCategory::CumulativeLayoutShift,
+            profiler_metadata_factory.new_metadata(),
+            &self.time_profiler_chan,
+            0,
+            (cumulative_layout_shift * 1000000.) as u64,
+            0,
+            0,
+        );
+
+        if opts::get().print_pwm {
+            println!("{:?} layout shift {} (cumulative {})", self.url, score, cumulative_layout_shift);
         }
     }
 
@@ -379,6 +553,14 @@ impl PaintTimeMetrics {
     pub fn get_first_contentful_paint(&self) -> Option<u64> {
         self.first_contentful_paint.get()
     }
+
+    pub fn get_largest_contentful_paint(&self) -> Option<u64> {
+        self.largest_contentful_paint.get()
+    }
+
+    pub fn get_cumulative_layout_shift(&self) -> f64 {
+        self.cumulative_layout_shift.get()
+    }
 }
 
 impl ProgressiveWebMetric for PaintTimeMetrics {
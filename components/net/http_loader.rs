@@ -33,7 +33,7 @@ use hyper_openssl::OpensslClient;
 use hyper_serde::Serde;
 use log;
 use msg::constellation_msg::{HistoryStateId, PipelineId};
-use net_traits::{CookieSource, FetchMetadata, NetworkError, ReferrerPolicy};
+use net_traits::{CookieSource, FetchMetadata, NetworkError, ReferrerPolicy, ResourceFetchTiming};
 use net_traits::request::{CacheMode, CredentialsMode, Destination, Origin};
 use net_traits::request::{RedirectMode, Referrer, Request, RequestMode};
 use net_traits::request::{ResponseTainting, ServiceWorkersMode};
@@ -92,10 +92,6 @@ impl HttpState {
     }
 }
 
-fn precise_time_ms() -> u64 {
-    time::precise_time_ns() / (1000 * 1000)
-}
-
 // Step 3 of https://fetch.spec.whatwg.org/#concept-fetch.
 pub fn set_default_accept(destination: Destination, headers: &mut Headers) {
     if headers.has::<Accept>() {
@@ -381,7 +377,8 @@ fn obtain_response(connector: &Pool<Connector>,
                    iters: u32,
                    request_id: Option<&str>,
                    is_xhr: bool)
-                   -> Result<(HyperResponse, Option<ChromeToDevtoolsControlMsg>), NetworkError> {
+                   -> Result<(HyperResponse, Option<ChromeToDevtoolsControlMsg>, ResourceFetchTiming),
+                             NetworkError> {
     let null_data = None;
 
     // loop trying connections in connection pool
@@ -420,7 +417,7 @@ fn obtain_response(connector: &Pool<Connector>,
             info!("{:?}", data);
         }
 
-        let connect_start = precise_time_ms();
+        let connect_start = time::precise_time_ns();
 
         let request = HyperRequest::with_connector(method.clone(),
                                                    url.clone().into_url(),
@@ -431,9 +428,9 @@ fn obtain_response(connector: &Pool<Connector>,
         };
         *request.headers_mut() = headers.clone();
 
-        let connect_end = precise_time_ms();
+        let connect_end = time::precise_time_ns();
 
-        let send_start = precise_time_ms();
+        let send_start = time::precise_time_ns();
 
         let mut request_writer = match request.start() {
             Ok(streaming) => streaming,
@@ -457,7 +454,15 @@ fn obtain_response(connector: &Pool<Connector>,
             Err(e) => return Err(NetworkError::Internal(e.description().to_owned())),
         };
 
-        let send_end = precise_time_ms();
+        let send_end = time::precise_time_ns();
+
+        let timing = ResourceFetchTiming {
+            connect_start: connect_start,
+            connect_end: connect_end,
+            request_start: send_start,
+            response_start: send_end,
+            ..ResourceFetchTiming::default()
+        };
 
         let msg = if let Some(request_id) = request_id {
             if let Some(pipeline_id) = *pipeline_id {
@@ -465,7 +470,8 @@ fn obtain_response(connector: &Pool<Connector>,
                     request_id.into(),
                     url.clone(), method.clone(), headers,
                     request_body.clone(), pipeline_id, time::now(),
-                    connect_end - connect_start, send_end - send_start, is_xhr))
+                    (connect_end - connect_start) / (1000 * 1000),
+                    (send_end - send_start) / (1000 * 1000), is_xhr))
             } else {
                 debug!("Not notifying devtools (no pipeline_id)");
                 None
@@ -474,7 +480,7 @@ fn obtain_response(connector: &Pool<Connector>,
             debug!("Not notifying devtools (no request_id)");
             None
         };
-        return Ok((response, msg));
+        return Ok((response, msg, timing));
     }
 }
 
@@ -1071,7 +1077,7 @@ fn http_network_fetch(request: &Request,
                                            request_id.as_ref().map(Deref::deref), is_xhr);
 
     let pipeline_id = request.pipeline_id;
-    let (res, msg) = match wrapped_response {
+    let (res, msg, mut timing) = match wrapped_response {
         Ok(wrapped_response) => wrapped_response,
         Err(error) => return Response::network_error(error),
     };
@@ -1091,6 +1097,16 @@ fn http_network_fetch(request: &Request,
     response.referrer = request.referrer.to_url().cloned();
     response.referrer_policy = request.referrer_policy.clone();
 
+    // The body sizes can only be told from the headers at this point, since the
+    // response metadata is sent before the body has been read.
+    let encoded_body_size = res.headers.get::<ContentLength>().map_or(0, |&ContentLength(len)| len);
+    let header_size = res.headers.iter().map(|header| {
+        (header.name().len() + header.value_string().len() + ": \r\n".len()) as u64
+    }).sum::<u64>();
+    timing.transfer_size = header_size + encoded_body_size;
+    timing.encoded_body_size = encoded_body_size;
+    response.resource_timing = Some(timing);
+
     let res_body = response.body.clone();
 
     // We're about to spawn a thread to be waited on here
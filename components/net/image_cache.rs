@@ -4,6 +4,7 @@
 
 use embedder_traits::resources::{self, Resource};
 use immeta::load_from_buf;
+use ipc_channel::ipc::IpcSharedMemory;
 use net_traits::{FetchMetadata, FetchResponseMsg, NetworkError};
 use net_traits::image::base::{Image, ImageMetadata, PixelFormat, load_from_memory};
 use net_traits::image_cache::{CanRequestImages, ImageCache, ImageResponder};
@@ -16,6 +17,7 @@ use std::io;
 use std::mem;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use svg_renderer::{self, Raster, SvgImage};
 use webrender_api;
 
 ///
@@ -32,14 +34,40 @@ use webrender_api;
 // Helper functions.
 // ======================================================================
 
+/// How many rasterizations of a vector image are kept, besides the one at its
+/// default size. Once there are more, the least recently used is dropped.
+const MAX_VECTOR_RASTERIZATIONS: usize = 8;
+
 fn decode_bytes_sync(key: LoadKey, bytes: &[u8]) -> DecoderMsg {
+    if svg_renderer::is_svg(bytes) {
+        let document = SvgImage::parse(bytes);
+        let image = document.as_ref().and_then(|document| {
+            document.rasterize(document.default_size(), 1.).map(vector_image_from_raster)
+        });
+        return DecoderMsg {
+            key: key,
+            vector_document: image.as_ref().and(document).map(Arc::new),
+            image: image,
+        };
+    }
+
     let image = load_from_memory(bytes);
     DecoderMsg {
         key: key,
-        image: image
+        image: image,
+        vector_document: None,
     }
 }
 
+fn vector_image_from_raster(raster: Raster) -> Image {
+    let mut image = Image::new(raster.width,
+                               raster.height,
+                               PixelFormat::BGRA8,
+                               IpcSharedMemory::from_bytes(&raster.bytes));
+    image.is_vector = true;
+    image
+}
+
 fn get_placeholder_image(webrender_api: &webrender_api::RenderApi, data: &[u8]) -> io::Result<Arc<Image>> {
     let mut image = load_from_memory(&data).unwrap();
     set_webrender_image_key(webrender_api, &mut image);
@@ -48,37 +76,9 @@ fn get_placeholder_image(webrender_api: &webrender_api::RenderApi, data: &[u8])
 
 fn set_webrender_image_key(webrender_api: &webrender_api::RenderApi, image: &mut Image) {
     if image.id.is_some() { return; }
-    let mut bytes = Vec::new();
-    let is_opaque = match image.format {
-        PixelFormat::BGRA8 => {
-            bytes.extend_from_slice(&*image.bytes);
-            premultiply(bytes.as_mut_slice())
-        }
-        PixelFormat::RGB8 => {
-            for bgr in image.bytes.chunks(3) {
-                bytes.extend_from_slice(&[
-                    bgr[2],
-                    bgr[1],
-                    bgr[0],
-                    0xff
-                ]);
-            }
-
-            true
-        }
-        PixelFormat::K8 | PixelFormat::KA8 => {
-            panic!("Not support by webrender yet");
-        }
-    };
-    let descriptor = webrender_api::ImageDescriptor {
-        size: webrender_api::DeviceUintSize::new(image.width, image.height),
-        stride: None,
-        format: webrender_api::ImageFormat::BGRA8,
-        offset: 0,
-        is_opaque,
-        allow_mipmaps: true,
-    };
-    let data = webrender_api::ImageData::new(bytes);
+    // Layout uploads the other frames of animated images itself, under keys
+    // of its own that it updates as the animation runs.
+    let (descriptor, data) = image.webrender_frame(0);
     let image_key = webrender_api.generate_image_key();
     let mut txn = webrender_api::Transaction::new();
     txn.add_image(image_key, descriptor, data, None);
@@ -86,30 +86,6 @@ fn set_webrender_image_key(webrender_api: &webrender_api::RenderApi, image: &mut
     image.id = Some(image_key);
 }
 
-// Returns true if the image was found to be
-// completely opaque.
-fn premultiply(data: &mut [u8]) -> bool {
-    let mut is_opaque = true;
-    let length = data.len();
-
-    let mut i = 0;
-    while i < length {
-        let b = data[i + 0] as u32;
-        let g = data[i + 1] as u32;
-        let r = data[i + 2] as u32;
-        let a = data[i + 3] as u32;
-
-        data[i + 0] = (b * a / 255) as u8;
-        data[i + 1] = (g * a / 255) as u8;
-        data[i + 2] = (r * a / 255) as u8;
-
-        i += 4;
-        is_opaque = is_opaque && a == 255;
-    }
-
-    is_opaque
-}
-
 // ======================================================================
 // Aux structs and enums.
 // ======================================================================
@@ -209,6 +185,16 @@ impl CompletedLoad {
 struct DecoderMsg {
     key: LoadKey,
     image: Option<Image>,
+    /// The parsed document, when the image is an SVG one.
+    vector_document: Option<Arc<SvgImage>>,
+}
+
+/// A loaded vector image, and the rasterizations of it that were asked for.
+struct VectorImage {
+    document: Arc<SvgImage>,
+    /// Keys of rasterizations, with their size in device pixels. The most
+    /// recently used comes last.
+    rasterizations: Vec<(webrender_api::DeviceUintSize, webrender_api::ImageKey)>,
 }
 
 enum ImageBytes {
@@ -266,6 +252,7 @@ impl LoadKeyGenerator {
 
 enum LoadResult {
     Loaded(Image),
+    VectorLoaded(Image, Arc<SvgImage>),
     PlaceholderLoaded(Arc<Image>),
     None
 }
@@ -324,6 +311,17 @@ struct ImageCacheStore {
     // The URL used for the placeholder image
     placeholder_url: ServoUrl,
 
+    // Loaded vector images, indexed by the key of their default rasterization.
+    vector_images: HashMap<webrender_api::ImageKey, VectorImage>,
+
+    // Keys of the rasterizations evicted while building the current display
+    // list, which may already be painting them.
+    unused_vector_keys: Vec<webrender_api::ImageKey>,
+
+    // Keys of the rasterizations evicted while building the last display
+    // list, which the display list it replaces may still be showing.
+    retired_vector_keys: Vec<webrender_api::ImageKey>,
+
     // Webrender API instance.
     webrender_api: webrender_api::RenderApi,
 }
@@ -338,12 +336,20 @@ impl ImageCacheStore {
 
         match load_result {
             LoadResult::Loaded(ref mut image) => set_webrender_image_key(&self.webrender_api, image),
+            LoadResult::VectorLoaded(ref mut image, ref document) => {
+                set_webrender_image_key(&self.webrender_api, image);
+                self.vector_images.insert(image.id.unwrap(), VectorImage {
+                    document: document.clone(),
+                    rasterizations: vec![],
+                });
+            }
             LoadResult::PlaceholderLoaded(..) | LoadResult::None => {}
         }
 
         let url = pending_load.final_url.clone();
         let image_response = match load_result {
-            LoadResult::Loaded(image) => ImageResponse::Loaded(Arc::new(image), url.unwrap()),
+            LoadResult::Loaded(image) |
+            LoadResult::VectorLoaded(image, _) => ImageResponse::Loaded(Arc::new(image), url.unwrap()),
             LoadResult::PlaceholderLoaded(image) =>
                 ImageResponse::PlaceholderLoaded(image, self.placeholder_url.clone()),
             LoadResult::None => ImageResponse::None,
@@ -381,9 +387,10 @@ impl ImageCacheStore {
     /// Handle a message from one of the decoder worker threads or from a sync
     /// decoding operation.
     fn handle_decoder(&mut self, msg: DecoderMsg) {
-        let image = match msg.image {
-            None => LoadResult::None,
-            Some(image) => LoadResult::Loaded(image),
+        let image = match (msg.image, msg.vector_document) {
+            (None, _) => LoadResult::None,
+            (Some(image), None) => LoadResult::Loaded(image),
+            (Some(image), Some(document)) => LoadResult::VectorLoaded(image, document),
         };
         self.complete_load(msg.key, image);
     }
@@ -405,6 +412,9 @@ impl ImageCache for ImageCacheImpl {
                 completed_loads: HashMap::new(),
                 placeholder_image: get_placeholder_image(&webrender_api, &rippy_data).ok(),
                 placeholder_url: ServoUrl::parse("chrome://resources/rippy.png").unwrap(),
+                vector_images: HashMap::new(),
+                unused_vector_keys: vec![],
+                retired_vector_keys: vec![],
                 webrender_api: webrender_api,
             }))
         }
@@ -560,4 +570,66 @@ impl ImageCache for ImageCacheImpl {
     fn set_webrender_image_key(&self, image: &mut Image) {
         set_webrender_image_key(&self.store.lock().unwrap().webrender_api, image);
     }
+
+    fn rasterize_vector_image(&self,
+                              image_key: webrender_api::ImageKey,
+                              size: webrender_api::LayoutSize,
+                              device_pixel_ratio: f32)
+                              -> Option<webrender_api::ImageKey> {
+        let device_size = webrender_api::DeviceUintSize::new(
+            (size.width * device_pixel_ratio).ceil() as u32,
+            (size.height * device_pixel_ratio).ceil() as u32);
+        if device_size.width == 0 || device_size.height == 0 {
+            return None;
+        }
+
+        let document = {
+            let mut store = self.store.lock().unwrap();
+            let vector_image = store.vector_images.get_mut(&image_key)?;
+            let cached = vector_image.rasterizations.iter().position(|&(size, _)| size == device_size);
+            if let Some(index) = cached {
+                let rasterization = vector_image.rasterizations.remove(index);
+                vector_image.rasterizations.push(rasterization);
+                return Some(rasterization.1);
+            }
+            vector_image.document.clone()
+        };
+
+        // The store is not locked while rasterizing, which can take a while.
+        let raster = document.rasterize(size.to_untyped(), device_pixel_ratio)?;
+        let mut image = vector_image_from_raster(raster);
+        let mut store = self.store.lock().unwrap();
+        let store = &mut *store;
+        let vector_image = store.vector_images.get_mut(&image_key)?;
+        let cached = vector_image.rasterizations.iter().find(|&&(size, _)| size == device_size).cloned();
+        if let Some((_, key)) = cached {
+            // Another thread rasterized the image at that size meanwhile.
+            return Some(key);
+        }
+        set_webrender_image_key(&store.webrender_api, &mut image);
+        let key = image.id.unwrap();
+        vector_image.rasterizations.push((device_size, key));
+        if vector_image.rasterizations.len() > MAX_VECTOR_RASTERIZATIONS {
+            // The evicted rasterization is deleted once no display list
+            // being shown can use it.
+            let (_, evicted) = vector_image.rasterizations.remove(0);
+            store.unused_vector_keys.push(evicted);
+        }
+        Some(key)
+    }
+
+    fn finish_display_list(&self) {
+        let mut store = self.store.lock().unwrap();
+        let store = &mut *store;
+        let unused_keys = mem::replace(&mut store.unused_vector_keys, vec![]);
+        let retired_keys = mem::replace(&mut store.retired_vector_keys, unused_keys);
+        if retired_keys.is_empty() {
+            return;
+        }
+        let mut txn = webrender_api::Transaction::new();
+        for key in retired_keys {
+            txn.delete_image(key);
+        }
+        store.webrender_api.update_resources(txn.resource_updates);
+    }
 }
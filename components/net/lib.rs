@@ -35,6 +35,7 @@ extern crate servo_allocator;
 extern crate servo_arc;
 extern crate servo_config;
 extern crate servo_url;
+extern crate svg_renderer;
 extern crate time;
 extern crate unicase;
 extern crate url;
@@ -0,0 +1,293 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Animated PNG decoding.
+//!
+//! The PNG decoder only knows about the default image of an APNG, so every
+//! frame is repackaged as a standalone PNG, decoded, and composited onto the
+//! previous frames as the `fcTL` chunks say.
+//!
+//! https://wiki.mozilla.org/APNG_Specification
+
+use piston_image::{self, ImageFormat};
+use std::time::Duration;
+use super::base::{FrameRect, animation_frame_len, max_animation_frames};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// An APNG decoded into frames of RGBA pixels of the full image size.
+pub struct DecodedApng {
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<(Vec<u8>, Duration)>,
+}
+
+struct Chunk<'a> {
+    kind: &'a [u8],
+    data: &'a [u8],
+}
+
+/// Iterates over the chunks of a PNG, stopping at the first truncated one.
+fn chunks(buffer: &[u8]) -> ChunkIter {
+    ChunkIter { buffer: buffer.get(PNG_SIGNATURE.len()..).unwrap_or(&[]) }
+}
+
+struct ChunkIter<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> Iterator for ChunkIter<'a> {
+    type Item = Chunk<'a>;
+
+    fn next(&mut self) -> Option<Chunk<'a>> {
+        if self.buffer.len() < 12 {
+            return None;
+        }
+        let length = read_u32(&self.buffer[0..4]) as usize;
+        if self.buffer.len() - 12 < length {
+            return None;
+        }
+        let chunk = Chunk {
+            kind: &self.buffer[4..8],
+            data: &self.buffer[8..8 + length],
+        };
+        self.buffer = &self.buffer[12 + length..];
+        Some(chunk)
+    }
+}
+
+/// Whether the PNG has an animation control chunk, which has to come before
+/// the image data.
+pub fn is_animated(buffer: &[u8]) -> bool {
+    chunks(buffer)
+        .take_while(|chunk| chunk.kind != b"IDAT")
+        .any(|chunk| chunk.kind == b"acTL")
+}
+
+/// The frame control chunk of a frame.
+struct FrameControl {
+    width: u32,
+    height: u32,
+    x_offset: u32,
+    y_offset: u32,
+    delay: Duration,
+    dispose_op: u8,
+    blend_op: u8,
+}
+
+const APNG_DISPOSE_OP_BACKGROUND: u8 = 1;
+const APNG_DISPOSE_OP_PREVIOUS: u8 = 2;
+const APNG_BLEND_OP_OVER: u8 = 1;
+
+impl FrameControl {
+    fn parse(data: &[u8]) -> Option<FrameControl> {
+        if data.len() < 26 {
+            return None;
+        }
+        let delay_num = read_u16(&data[20..22]) as u64;
+        // A zero denominator means hundredths of a second.
+        let delay_den = match read_u16(&data[22..24]) as u64 {
+            0 => 100,
+            den => den,
+        };
+        Some(FrameControl {
+            width: read_u32(&data[4..8]),
+            height: read_u32(&data[8..12]),
+            x_offset: read_u32(&data[12..16]),
+            y_offset: read_u32(&data[16..20]),
+            delay: Duration::from_millis(delay_num * 1000 / delay_den),
+            dispose_op: data[24],
+            blend_op: data[25],
+        })
+    }
+}
+
+/// A frame being reassembled from its control and data chunks.
+struct RawFrame {
+    control: FrameControl,
+    data: Vec<u8>,
+}
+
+pub fn decode(buffer: &[u8]) -> Option<DecodedApng> {
+    if !buffer.starts_with(&PNG_SIGNATURE) {
+        return None;
+    }
+
+    let mut header = None;
+    // The chunks before the image data that every frame shares, such as
+    // the palette and its transparency.
+    let mut shared_chunks = vec![];
+    let mut frames: Vec<RawFrame> = vec![];
+    let mut pending_control = None;
+    let mut seen_image_data = false;
+    for chunk in chunks(buffer) {
+        match chunk.kind {
+            b"IHDR" => header = Some(chunk.data),
+            b"acTL" | b"IEND" => {},
+            b"fcTL" => {
+                let control = FrameControl::parse(chunk.data)?;
+                pending_control = Some(control);
+            },
+            b"IDAT" => {
+                // The default image is only the first frame of the animation
+                // if a frame control chunk precedes it.
+                if !seen_image_data {
+                    seen_image_data = true;
+                    if let Some(control) = pending_control.take() {
+                        frames.push(RawFrame { control: control, data: vec![] });
+                    }
+                }
+                if let Some(frame) = frames.last_mut() {
+                    frame.data.extend_from_slice(chunk.data);
+                }
+            },
+            b"fdAT" => {
+                if chunk.data.len() < 4 {
+                    return None;
+                }
+                if let Some(control) = pending_control.take() {
+                    frames.push(RawFrame { control: control, data: vec![] });
+                }
+                // Skip the sequence number.
+                frames.last_mut()?.data.extend_from_slice(&chunk.data[4..]);
+            },
+            _ => {
+                if !seen_image_data {
+                    shared_chunks.push(chunk);
+                }
+            },
+        }
+    }
+
+    let header = header?;
+    if header.len() != 13 {
+        return None;
+    }
+    let width = read_u32(&header[0..4]);
+    let height = read_u32(&header[4..8]);
+
+    let frame_len = animation_frame_len(width, height)?;
+    if frames.len() > max_animation_frames(frame_len) {
+        debug!("APNG has too many frames, keeping the first {}", max_animation_frames(frame_len));
+    }
+    let mut canvas = vec![0; frame_len];
+    let mut decoded_frames = Vec::with_capacity(frames.len().min(max_animation_frames(frame_len)));
+    for (index, frame) in frames.iter().take(max_animation_frames(frame_len)).enumerate() {
+        let control = &frame.control;
+        // Frames have to fit in the image, which also bounds what decoding
+        // them allocates.
+        let fits = |offset: u32, size: u32, image_size: u32| {
+            offset.checked_add(size).map_or(false, |end| end <= image_size)
+        };
+        if !fits(control.x_offset, control.width, width) || !fits(control.y_offset, control.height, height) {
+            debug!("APNG frame {} is outside the image", index);
+            return None;
+        }
+        let mut frame_header = header.to_vec();
+        frame_header[0..4].copy_from_slice(&u32_bytes(control.width));
+        frame_header[4..8].copy_from_slice(&u32_bytes(control.height));
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", &frame_header);
+        for chunk in &shared_chunks {
+            write_chunk(&mut png, chunk.kind, chunk.data);
+        }
+        write_chunk(&mut png, b"IDAT", &frame.data);
+        write_chunk(&mut png, b"IEND", &[]);
+
+        let pixels = match piston_image::load_from_memory_with_format(&png, ImageFormat::PNG) {
+            Ok(image) => image.to_rgba().into_raw(),
+            Err(e) => {
+                debug!("APNG frame {} decoding error: {:?}", index, e);
+                break;
+            },
+        };
+
+        let rect = FrameRect::new(control.x_offset, control.y_offset,
+                                  control.width, control.height,
+                                  width, height);
+        // The first frame can't restore a previous one, and clears instead.
+        let previous = if control.dispose_op == APNG_DISPOSE_OP_PREVIOUS && index > 0 {
+            Some(canvas.clone())
+        } else {
+            None
+        };
+        rect.for_each_pixel(control.width, |src, dst| {
+            let source = &pixels[src..src + 4];
+            if control.blend_op == APNG_BLEND_OP_OVER {
+                blend_over(&mut canvas[dst..dst + 4], source);
+            } else {
+                canvas[dst..dst + 4].copy_from_slice(source);
+            }
+        });
+
+        decoded_frames.push((canvas.clone(), control.delay));
+
+        match (control.dispose_op, previous) {
+            (_, Some(previous)) => canvas = previous,
+            (APNG_DISPOSE_OP_BACKGROUND, _) |
+            (APNG_DISPOSE_OP_PREVIOUS, _) => rect.clear(&mut canvas),
+            _ => {},
+        }
+    }
+
+    if decoded_frames.is_empty() {
+        return None;
+    }
+    Some(DecodedApng {
+        width: width,
+        height: height,
+        frames: decoded_frames,
+    })
+}
+
+/// Composites a non-premultiplied RGBA pixel over another.
+fn blend_over(destination: &mut [u8], source: &[u8]) {
+    let source_alpha = source[3] as u32;
+    match source_alpha {
+        0 => return,
+        255 => return destination.copy_from_slice(source),
+        _ => {},
+    }
+    let destination_alpha = destination[3] as u32 * (255 - source_alpha) / 255;
+    let alpha = source_alpha + destination_alpha;
+    for channel in 0..3 {
+        destination[channel] = ((source[channel] as u32 * source_alpha +
+                                 destination[channel] as u32 * destination_alpha) / alpha) as u8;
+    }
+    destination[3] = alpha as u8;
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | bytes[3] as u32
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    (bytes[0] as u16) << 8 | bytes[1] as u16
+}
+
+fn u32_bytes(value: u32) -> [u8; 4] {
+    [(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+    png.extend_from_slice(&u32_bytes(data.len() as u32));
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&u32_bytes(crc));
+}
+
+/// The CRC-32 that PNG chunks end with.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xEDB88320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -2,9 +2,13 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use gif::{self, SetParameter};
 use ipc_channel::ipc::IpcSharedMemory;
 use piston_image::{self, DynamicImage, ImageFormat};
 use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use super::{apng, webp};
 use webrender_api;
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
@@ -24,16 +28,131 @@ pub struct Image {
     pub width: u32,
     pub height: u32,
     pub format: PixelFormat,
+    /// The pixels of every frame, one frame after the other.
     #[ignore_malloc_size_of = "Defined in ipc-channel"]
     pub bytes: IpcSharedMemory,
+    /// The WebRender key of the first frame.
     #[ignore_malloc_size_of = "Defined in webrender_api"]
     pub id: Option<webrender_api::ImageKey>,
+    /// The frames of the image. Still images have a single one.
+    pub frames: Vec<ImageFrame>,
+    /// Whether the image is a rasterization of a vector image, at its
+    /// default size. The image cache rasterizes it again at any other size.
+    pub is_vector: bool,
+}
+
+/// A frame of an image, composited onto the full image size.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct ImageFrame {
+    /// How long the frame is shown before the next one, for animated images.
+    pub delay: Option<Duration>,
+}
+
+impl Image {
+    /// Creates a still image from its pixels.
+    pub fn new(width: u32, height: u32, format: PixelFormat, bytes: IpcSharedMemory) -> Image {
+        Image {
+            width: width,
+            height: height,
+            format: format,
+            bytes: bytes,
+            id: None,
+            frames: vec![ImageFrame { delay: None }],
+            is_vector: false,
+        }
+    }
+
+    /// Whether the image has several frames to cycle through.
+    pub fn should_animate(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// The pixels of the frame at `index`.
+    pub fn frame_bytes(&self, index: usize) -> &[u8] {
+        let frame_len = self.bytes.len() / self.frames.len();
+        &self.bytes[index * frame_len..(index + 1) * frame_len]
+    }
+
+    /// The pixels of the first frame, which is what still renderings of an
+    /// animated image (canvas, WebGL, screenshots...) use.
+    pub fn first_frame_bytes(&self) -> &[u8] {
+        self.frame_bytes(0)
+    }
+
+    /// The frame at `index`, in the premultiplied BGRA that WebRender takes.
+    pub fn webrender_frame(&self, index: usize) -> (webrender_api::ImageDescriptor, webrender_api::ImageData) {
+        let mut bytes = Vec::new();
+        let is_opaque = match self.format {
+            PixelFormat::BGRA8 => {
+                bytes.extend_from_slice(self.frame_bytes(index));
+                premultiply(bytes.as_mut_slice())
+            }
+            PixelFormat::RGB8 => {
+                for bgr in self.frame_bytes(index).chunks(3) {
+                    bytes.extend_from_slice(&[
+                        bgr[2],
+                        bgr[1],
+                        bgr[0],
+                        0xff
+                    ]);
+                }
+
+                true
+            }
+            PixelFormat::K8 => {
+                for &k in self.frame_bytes(index) {
+                    bytes.extend_from_slice(&[k, k, k, 0xff]);
+                }
+
+                true
+            }
+            PixelFormat::KA8 => {
+                for ka in self.frame_bytes(index).chunks(2) {
+                    bytes.extend_from_slice(&[ka[0], ka[0], ka[0], ka[1]]);
+                }
+                premultiply(bytes.as_mut_slice())
+            }
+        };
+        let descriptor = webrender_api::ImageDescriptor {
+            size: webrender_api::DeviceUintSize::new(self.width, self.height),
+            stride: None,
+            format: webrender_api::ImageFormat::BGRA8,
+            offset: 0,
+            is_opaque,
+            allow_mipmaps: true,
+        };
+        (descriptor, webrender_api::ImageData::new(bytes))
+    }
+}
+
+// Returns true if the image was found to be
+// completely opaque.
+fn premultiply(data: &mut [u8]) -> bool {
+    let mut is_opaque = true;
+    let length = data.len();
+
+    let mut i = 0;
+    while i < length {
+        let b = data[i + 0] as u32;
+        let g = data[i + 1] as u32;
+        let r = data[i + 2] as u32;
+        let a = data[i + 3] as u32;
+
+        data[i + 0] = (b * a / 255) as u8;
+        data[i + 1] = (g * a / 255) as u8;
+        data[i + 2] = (r * a / 255) as u8;
+
+        i += 4;
+        is_opaque = is_opaque && a == 255;
+    }
+
+    is_opaque
 }
 
 impl fmt::Debug for Image {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Image {{ width: {}, height: {}, format: {:?}, ..., id: {:?} }}",
-               self.width, self.height, self.format, self.id)
+        write!(f, "Image {{ width: {}, height: {}, format: {:?}, frames: {}, ..., id: {:?} }}",
+               self.width, self.height, self.format, self.frames.len(), self.id)
     }
 }
 
@@ -75,31 +194,278 @@ pub fn load_from_memory(buffer: &[u8]) -> Option<Image> {
             debug!("{}", msg);
             None
         },
-        Ok(_) => {
-            match piston_image::load_from_memory(buffer) {
-                Ok(image) => {
-                    let mut rgba = match image {
-                        DynamicImage::ImageRgba8(rgba) => rgba,
-                        image => image.to_rgba(),
-                    };
-                    byte_swap_and_premultiply(&mut *rgba);
-                    Some(Image {
-                        width: rgba.width(),
-                        height: rgba.height(),
-                        format: PixelFormat::BGRA8,
-                        bytes: IpcSharedMemory::from_bytes(&*rgba),
-                        id: None,
-                    })
-                },
-                Err(e) => {
-                    debug!("Image decoding error: {:?}", e);
-                    None
+        Ok(ImageFormat::GIF) => load_gif(buffer),
+        Ok(ImageFormat::WEBP) => {
+            let webp = webp::decode(buffer)?;
+            Some(image_from_frames(webp.width, webp.height, webp.frames))
+        },
+        Ok(ImageFormat::PNG) if apng::is_animated(buffer) => {
+            match apng::decode(buffer) {
+                Some(apng) => {
+                    let frames = apng.frames.into_iter().map(|(mut rgba, delay)| {
+                        byte_swap_and_premultiply(&mut rgba);
+                        (rgba, delay)
+                    }).collect();
+                    Some(image_from_frames(apng.width, apng.height, frames))
                 },
+                // Fall back to the default image, which every APNG has.
+                None => load_still_image(buffer),
             }
         },
+        Ok(_) => load_still_image(buffer),
+    }
+}
+
+fn load_still_image(buffer: &[u8]) -> Option<Image> {
+    match piston_image::load_from_memory(buffer) {
+        Ok(image) => {
+            let mut rgba = match image {
+                DynamicImage::ImageRgba8(rgba) => rgba,
+                image => image.to_rgba(),
+            };
+            byte_swap_and_premultiply(&mut *rgba);
+            Some(Image::new(rgba.width(), rgba.height(), PixelFormat::BGRA8,
+                            IpcSharedMemory::from_bytes(&*rgba)))
+        },
+        Err(e) => {
+            debug!("Image decoding error: {:?}", e);
+            None
+        },
+    }
+}
+
+/// Decodes every frame of a GIF, applying the disposal method of each frame
+/// so that the frames can be shown as is.
+fn load_gif(buffer: &[u8]) -> Option<Image> {
+    let mut decoder = gif::Decoder::new(buffer);
+    decoder.set(gif::ColorOutput::RGBA);
+    let mut reader = match decoder.read_info() {
+        Ok(reader) => reader,
+        Err(e) => {
+            debug!("GIF decoding error: {:?}", e);
+            return None;
+        },
+    };
+    let width = reader.width() as u32;
+    let height = reader.height() as u32;
+
+    let frame_len = match animation_frame_len(width, height) {
+        Some(frame_len) => frame_len,
+        None => {
+            debug!("GIF too large to animate, decoding its first frame");
+            return load_still_image(buffer);
+        },
+    };
+    let mut canvas = vec![0; frame_len];
+    let mut frames = vec![];
+    loop {
+        if frames.len() == max_animation_frames(frame_len) {
+            debug!("GIF has too many frames, stopping after {}", frames.len());
+            break;
+        }
+        let frame = match reader.read_next_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(e) => {
+                // Keep the frames decoded so far, like other browsers do
+                // with truncated files.
+                debug!("GIF decoding error: {:?}", e);
+                break;
+            },
+        };
+
+        let rect = FrameRect::new(frame.left as u32, frame.top as u32,
+                                  frame.width as u32, frame.height as u32,
+                                  width, height);
+        let previous = match frame.dispose {
+            gif::DisposalMethod::Previous => Some(canvas.clone()),
+            _ => None,
+        };
+        // GIF pixels are either opaque or fully transparent, so blending
+        // is a matter of skipping the transparent ones.
+        rect.for_each_pixel(frame.width as u32, |src, dst| {
+            let pixel = &frame.buffer[src..src + 4];
+            if pixel[3] != 0 {
+                canvas[dst..dst + 4].copy_from_slice(pixel);
+            }
+        });
+
+        let mut bytes = canvas.clone();
+        byte_swap_and_premultiply(&mut bytes);
+        frames.push((bytes, Duration::from_millis(frame.delay as u64 * 10)));
+
+        match frame.dispose {
+            gif::DisposalMethod::Background => rect.clear(&mut canvas),
+            gif::DisposalMethod::Previous => canvas = previous.unwrap(),
+            gif::DisposalMethod::Any | gif::DisposalMethod::Keep => {},
+        }
+    }
+
+    if frames.is_empty() {
+        return None;
+    }
+    Some(image_from_frames(width, height, frames))
+}
+
+/// The part of the image covered by a frame, clipped to the image.
+pub(super) struct FrameRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    image_width: u32,
+}
+
+impl FrameRect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32,
+               image_width: u32, image_height: u32) -> FrameRect {
+        let x = x.min(image_width);
+        let y = y.min(image_height);
+        FrameRect {
+            x: x,
+            y: y,
+            width: width.min(image_width - x),
+            height: height.min(image_height - y),
+            image_width: image_width,
+        }
+    }
+
+    /// Calls `f` with the byte offsets of each pixel of the rect in a frame
+    /// buffer of the given width and in the image.
+    pub fn for_each_pixel<F: FnMut(usize, usize)>(&self, frame_width: u32, mut f: F) {
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let src = (row * frame_width + column) as usize * 4;
+                let dst = ((self.y + row) * self.image_width + self.x + column) as usize * 4;
+                f(src, dst)
+            }
+        }
+    }
+
+    /// Makes the rect transparent black in `canvas`.
+    pub fn clear(&self, canvas: &mut [u8]) {
+        for row in self.y..self.y + self.height {
+            let start = (row * self.image_width + self.x) as usize * 4;
+            let end = start + self.width as usize * 4;
+            for byte in &mut canvas[start..end] {
+                *byte = 0;
+            }
+        }
+    }
+}
+
+/// The delay, in milliseconds, of frames that ask to be shown for 10ms or
+/// less. Like other browsers, we slow those down, as many GIFs rely on it.
+const MINIMUM_FRAME_DELAY_MS: u64 = 100;
+
+/// The largest width or height of an animated image, in pixels.
+const MAX_ANIMATION_DIMENSION: u32 = 16384;
+
+/// The most memory the decoded frames of an animated image may take, in
+/// bytes. Every frame is a full copy of the image, so an animation with more
+/// frames than fit is cut short.
+const MAX_ANIMATION_BYTES: usize = 256 * 1024 * 1024;
+
+/// The length of a frame of four bytes per pixel of an animated image, or
+/// `None` if the image is too large to animate.
+pub(super) fn animation_frame_len(width: u32, height: u32) -> Option<usize> {
+    if width > MAX_ANIMATION_DIMENSION || height > MAX_ANIMATION_DIMENSION {
+        return None;
+    }
+    (width as usize).checked_mul(height as usize)?.checked_mul(4).filter(|&len| len <= MAX_ANIMATION_BYTES)
+}
+
+/// How many frames of `frame_len` bytes an animated image may have.
+pub(super) fn max_animation_frames(frame_len: usize) -> usize {
+    MAX_ANIMATION_BYTES / frame_len.max(1)
+}
+
+/// Builds an image from frames of BGRA pixels of the full image size.
+fn image_from_frames(width: u32, height: u32, frames: Vec<(Vec<u8>, Duration)>) -> Image {
+    let animated = frames.len() > 1;
+    let mut bytes = Vec::with_capacity(frames.iter().map(|&(ref bytes, _)| bytes.len()).sum());
+    let frames = frames.into_iter().map(|(frame_bytes, delay)| {
+        bytes.extend_from_slice(&frame_bytes);
+        let delay = if !animated {
+            None
+        } else if delay <= Duration::from_millis(10) {
+            Some(Duration::from_millis(MINIMUM_FRAME_DELAY_MS))
+        } else {
+            Some(delay)
+        };
+        ImageFrame { delay: delay }
+    }).collect();
+
+    Image {
+        width: width,
+        height: height,
+        format: PixelFormat::BGRA8,
+        bytes: IpcSharedMemory::from_bytes(&bytes),
+        id: None,
+        frames: frames,
+        is_vector: false,
     }
 }
 
+/// The frame of an animated image to show at a given time.
+///
+/// Animations only advance when `update` is called, and `resume` restarts the
+/// timeline from the current frame, so that pausing the animation (for
+/// instance while its document is hidden) does not make it skip frames.
+#[derive(Clone, Debug)]
+pub struct ImageAnimationState {
+    pub image: Arc<Image>,
+    /// The index of the frame being shown.
+    pub active_frame: usize,
+    /// When the active frame started being shown, in seconds.
+    last_update_time: f64,
+}
+
+impl ImageAnimationState {
+    pub fn new(image: Arc<Image>, now: f64) -> ImageAnimationState {
+        ImageAnimationState {
+            image: image,
+            active_frame: 0,
+            last_update_time: now,
+        }
+    }
+
+    /// Restarts the timeline of the active frame at `now`.
+    pub fn resume(&mut self, now: f64) {
+        self.last_update_time = now;
+    }
+
+    /// Advances to the frame that should be shown at `now`. Returns whether
+    /// the active frame changed.
+    pub fn update(&mut self, now: f64) -> bool {
+        if !self.image.should_animate() {
+            return false;
+        }
+
+        let frame_duration = |frame: &ImageFrame| {
+            let delay = frame.delay.unwrap_or(Duration::from_millis(MINIMUM_FRAME_DELAY_MS));
+            delay.as_secs() as f64 + delay.subsec_nanos() as f64 / 1e9
+        };
+
+        // Skip whole loops at once rather than frame by frame.
+        let loop_duration: f64 = self.image.frames.iter().map(&frame_duration).sum();
+        let elapsed = now - self.last_update_time;
+        if elapsed > loop_duration {
+            self.last_update_time += (elapsed / loop_duration).floor() * loop_duration;
+        }
+
+        let previous_frame = self.active_frame;
+        loop {
+            let duration = frame_duration(&self.image.frames[self.active_frame]);
+            if now - self.last_update_time < duration {
+                break;
+            }
+            self.last_update_time += duration;
+            self.active_frame = (self.active_frame + 1) % self.image.frames.len();
+        }
+        self.active_frame != previous_frame
+    }
+}
 
 // https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img
 pub fn detect_image_format(buffer: &[u8]) -> Result<ImageFormat, &str> {
@@ -113,6 +479,8 @@ pub fn detect_image_format(buffer: &[u8]) -> Result<ImageFormat, &str> {
         Ok(ImageFormat::BMP)
     } else if is_ico(buffer) {
         Ok(ImageFormat::ICO)
+    } else if is_webp(buffer) {
+        Ok(ImageFormat::WEBP)
     } else {
         Err("Image Format Not Supported")
     }
@@ -137,3 +505,7 @@ fn is_bmp(buffer: &[u8]) -> bool {
 fn is_ico(buffer: &[u8]) -> bool {
     buffer.starts_with(&[0x00, 0x00, 0x01, 0x00])
 }
+
+fn is_webp(buffer: &[u8]) -> bool {
+    buffer.len() >= 12 && buffer.starts_with(b"RIFF") && &buffer[8..12] == b"WEBP"
+}
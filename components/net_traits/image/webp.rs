@@ -0,0 +1,99 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! WebP decoding through the system libwebp.
+//!
+//! The animation decoder of libwebpdemux handles still and animated files
+//! alike, and composites every frame onto the full canvas, applying the
+//! blending and disposal methods of the file. Builds without the `webp`
+//! feature decode no WebP images.
+
+#![allow(unsafe_code)]
+
+#[cfg(feature = "webp")]
+use std::{ptr, slice};
+use std::time::Duration;
+#[cfg(feature = "webp")]
+use super::base::{animation_frame_len, max_animation_frames};
+#[cfg(feature = "webp")]
+use webp_sys::{WebPAnimDecoder, servo_webp_decoder_delete, servo_webp_decoder_new, servo_webp_decoder_next_frame};
+
+/// A WebP file decoded into canvas-sized BGRA frames.
+pub struct DecodedWebP {
+    pub width: u32,
+    pub height: u32,
+    /// The pixels of each frame, with the time it is shown for.
+    pub frames: Vec<(Vec<u8>, Duration)>,
+}
+
+/// Frees the decoder, and with it the frame buffer it hands out.
+#[cfg(feature = "webp")]
+struct DecoderGuard(*mut WebPAnimDecoder);
+
+#[cfg(feature = "webp")]
+impl Drop for DecoderGuard {
+    fn drop(&mut self) {
+        unsafe { servo_webp_decoder_delete(self.0) }
+    }
+}
+
+#[cfg(not(feature = "webp"))]
+pub fn decode(_buffer: &[u8]) -> Option<DecodedWebP> {
+    debug!("Servo was built without WebP support");
+    None
+}
+
+#[cfg(feature = "webp")]
+pub fn decode(buffer: &[u8]) -> Option<DecodedWebP> {
+    let (mut width, mut height, mut frame_count) = (0, 0, 0);
+    let decoder = unsafe {
+        servo_webp_decoder_new(buffer.as_ptr(), buffer.len(), &mut width, &mut height, &mut frame_count)
+    };
+    if decoder.is_null() {
+        debug!("libwebp could not parse the image");
+        return None;
+    }
+    let decoder = DecoderGuard(decoder);
+    let frame_len = match animation_frame_len(width, height) {
+        Some(frame_len) => frame_len,
+        None => {
+            debug!("WebP image too large to decode");
+            return None;
+        },
+    };
+
+    let mut frames = Vec::with_capacity((frame_count as usize).min(max_animation_frames(frame_len)));
+    let mut previous_timestamp = 0;
+    loop {
+        if frames.len() == max_animation_frames(frame_len) {
+            debug!("WebP image has too many frames, stopping after {}", frames.len());
+            break;
+        }
+        let mut pixels = ptr::null_mut();
+        let mut timestamp = 0;
+        match unsafe { servo_webp_decoder_next_frame(decoder.0, &mut pixels, &mut timestamp) } {
+            0 => break,
+            1 => {},
+            _ => {
+                debug!("libwebp failed to decode frame {}", frames.len());
+                break;
+            },
+        }
+        // The buffer is owned by the decoder and reused for the next frame.
+        let bytes = unsafe { slice::from_raw_parts(pixels, frame_len) }.to_vec();
+        // Timestamps are the end time of each frame, in milliseconds.
+        let duration = (timestamp - previous_timestamp).max(0) as u64;
+        previous_timestamp = timestamp;
+        frames.push((bytes, Duration::from_millis(duration)));
+    }
+
+    if frames.is_empty() {
+        return None;
+    }
+    Some(DecodedWebP {
+        width: width,
+        height: height,
+        frames: frames,
+    })
+}
@@ -121,4 +121,18 @@ pub trait ImageCache: Sync + Send {
 
     /// Ensure an image has a webrender key.
     fn set_webrender_image_key(&self, image: &mut Image);
+
+    /// Returns the key of a rasterization of the vector image whose default
+    /// rasterization has the key `image_key`, at `size` CSS pixels and
+    /// `device_pixel_ratio` device pixels per CSS pixel. Returns `None` if
+    /// the image is not a vector image or cannot be rasterized at that size.
+    fn rasterize_vector_image(&self,
+                              image_key: webrender_api::ImageKey,
+                              size: webrender_api::LayoutSize,
+                              device_pixel_ratio: f32)
+                              -> Option<webrender_api::ImageKey>;
+
+    /// Called by layout after building a display list, to delete the vector
+    /// image rasterizations that no display list being shown uses anymore.
+    fn finish_display_list(&self);
 }
@@ -7,6 +7,7 @@
 
 extern crate cookie as cookie_rs;
 extern crate embedder_traits;
+extern crate gif;
 extern crate hyper;
 extern crate hyper_serde;
 extern crate image as piston_image;
@@ -22,6 +23,7 @@ extern crate servo_arc;
 extern crate servo_url;
 extern crate url;
 extern crate uuid;
+#[cfg(feature = "webp")] extern crate webp_sys;
 extern crate webrender_api;
 
 use cookie_rs::Cookie;
@@ -56,7 +58,9 @@ pub mod storage_thread;
 /// However, image handling is generally very integrated with the network stack (especially where
 /// caching is involved) and as a result it must live in here.
 pub mod image {
+    mod apng;
     pub mod base;
+    mod webp;
 }
 
 /// A loading context, for context-specific sniffing, as defined in
@@ -400,6 +404,36 @@ pub struct ResourceCorsData {
     pub origin: ServoUrl,
 }
 
+/// Timing information about a fetch, used to populate
+/// [resource timing](https://w3c.github.io/resource-timing/) entries.
+///
+/// All timestamps are `time::precise_time_ns` values, with `0` meaning that the
+/// corresponding phase did not happen (e.g. for responses served from the cache).
+/// The network stack fills in the connection, request and response start times
+/// and the sizes it can tell from the response headers; the fetching document
+/// records `fetch_start`, `response_end` and `decoded_body_size` itself.
+#[derive(Clone, Copy, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct ResourceFetchTiming {
+    /// Time at which the fetch was started.
+    pub fetch_start: u64,
+    /// Time immediately before the connection to the server was established.
+    pub connect_start: u64,
+    /// Time immediately after the connection to the server was established.
+    pub connect_end: u64,
+    /// Time immediately before the request was sent.
+    pub request_start: u64,
+    /// Time immediately after the response headers were received.
+    pub response_start: u64,
+    /// Time immediately after the last byte of the response body was received.
+    pub response_end: u64,
+    /// Size in bytes of the response header fields and payload body.
+    pub transfer_size: u64,
+    /// Size in bytes of the payload body, before removing any content codings.
+    pub encoded_body_size: u64,
+    /// Size in bytes of the payload body, after removing any content codings.
+    pub decoded_body_size: u64,
+}
+
 /// Metadata about a loaded resource, such as is obtained from HTTP headers.
 #[derive(Clone, Deserialize, MallocSizeOf, Serialize, Debug)]
 pub struct Metadata {
@@ -431,6 +465,9 @@ pub struct Metadata {
 
     /// Referrer Policy of the Request used to obtain Response
     pub referrer_policy: Option<ReferrerPolicy>,
+
+    /// Timing information, if the resource was obtained from the network
+    pub timing: Option<ResourceFetchTiming>,
 }
 
 impl Metadata {
@@ -447,6 +484,7 @@ impl Metadata {
             https_state: HttpsState::None,
             referrer: None,
             referrer_policy: None,
+            timing: None,
         }
     }
 
@@ -4,7 +4,7 @@
 
 //! The [Response](https://fetch.spec.whatwg.org/#responses) object
 //! resulting from a [fetch operation](https://fetch.spec.whatwg.org/#concept-fetch)
-use {FetchMetadata, FilteredMetadata, Metadata, NetworkError, ReferrerPolicy};
+use {FetchMetadata, FilteredMetadata, Metadata, NetworkError, ReferrerPolicy, ResourceFetchTiming};
 use hyper::header::{AccessControlExposeHeaders, ContentType, Headers};
 use hyper::status::StatusCode;
 use hyper_serde::Serde;
@@ -117,6 +117,8 @@ pub struct Response {
     /// https://fetch.spec.whatwg.org/#concept-response-aborted
     #[ignore_malloc_size_of = "AtomicBool heap size undefined"]
     pub aborted: Arc<AtomicBool>,
+    /// Timing information about the network fetch that produced this response
+    pub resource_timing: Option<ResourceFetchTiming>,
 }
 
 impl Response {
@@ -139,6 +141,7 @@ impl Response {
             internal_response: None,
             return_internal: true,
             aborted: Arc::new(AtomicBool::new(false)),
+            resource_timing: None,
         }
     }
 
@@ -169,6 +172,7 @@ impl Response {
             internal_response: None,
             return_internal: true,
             aborted: Arc::new(AtomicBool::new(false)),
+            resource_timing: None,
         }
     }
 
@@ -301,6 +305,7 @@ impl Response {
             metadata.https_state = response.https_state;
             metadata.referrer = response.referrer.clone();
             metadata.referrer_policy = response.referrer_policy.clone();
+            metadata.timing = response.resource_timing;
             metadata
         };
 
@@ -2,9 +2,16 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+extern crate gif;
+extern crate ipc_channel;
 extern crate net_traits;
+extern crate webrender_api;
 
-use net_traits::image::base::detect_image_format;
+use ipc_channel::ipc::IpcSharedMemory;
+use net_traits::image::base::{Image, ImageAnimationState, PixelFormat, detect_image_format, load_from_memory};
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[test]
 fn test_supported_images() {
@@ -14,6 +21,8 @@ fn test_supported_images() {
     let png = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
     let bmp = [0x42, 0x4D];
     let ico = [0x00, 0x00, 0x01, 0x00];
+    let webp = [b'R', b'I', b'F', b'F', 0x1A, 0x00, 0x00, 0x00, b'W', b'E', b'B', b'P'];
+    let riff = [b'R', b'I', b'F', b'F', 0x1A, 0x00, 0x00, 0x00, b'W', b'A', b'V', b'E'];
     let junk_format = [0x01, 0x02, 0x03, 0x04, 0x05];
 
     assert!(detect_image_format(&gif1).is_ok());
@@ -22,5 +31,206 @@ fn test_supported_images() {
     assert!(detect_image_format(&png).is_ok());
     assert!(detect_image_format(&bmp).is_ok());
     assert!(detect_image_format(&ico).is_ok());
+    assert!(detect_image_format(&webp).is_ok());
+    assert!(detect_image_format(&riff).is_err());
     assert!(detect_image_format(&junk_format).is_err());
 }
+
+/// A 2x1 GIF whose second frame only covers its right pixel.
+fn animated_gif() -> Vec<u8> {
+    let palette = [0xff, 0x00, 0x00, 0x00, 0x00, 0xff];
+    let mut data = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut data, 2, 1, &palette).unwrap();
+        encoder.write_frame(&gif::Frame {
+            width: 2,
+            height: 1,
+            delay: 5,
+            buffer: Cow::Borrowed(&[0, 0]),
+            ..gif::Frame::default()
+        }).unwrap();
+        encoder.write_frame(&gif::Frame {
+            left: 1,
+            width: 1,
+            height: 1,
+            delay: 5,
+            buffer: Cow::Borrowed(&[1]),
+            ..gif::Frame::default()
+        }).unwrap();
+    }
+    data
+}
+
+#[test]
+fn test_animated_gif_frames() {
+    let image = load_from_memory(&animated_gif()).unwrap();
+    assert_eq!((image.width, image.height), (2, 1));
+    assert!(image.should_animate());
+    assert_eq!(image.frames.len(), 2);
+    assert_eq!(image.frames[0].delay, Some(Duration::from_millis(50)));
+
+    // Frames are BGRA and composited onto the previous ones.
+    assert_eq!(image.frame_bytes(0), &[0, 0, 255, 255, 0, 0, 255, 255][..]);
+    assert_eq!(image.frame_bytes(1), &[0, 0, 255, 255, 255, 0, 0, 255][..]);
+    assert_eq!(image.first_frame_bytes(), image.frame_bytes(0));
+}
+
+#[test]
+fn test_huge_gif_does_not_animate() {
+    // Too wide to keep a copy of the image for every frame, so only the
+    // first frame is decoded.
+    let palette = [0xff, 0x00, 0x00, 0x00, 0x00, 0xff];
+    let mut data = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut data, 20000, 1, &palette).unwrap();
+        for index in 0..2 {
+            encoder.write_frame(&gif::Frame {
+                width: 1,
+                height: 1,
+                delay: 5,
+                buffer: Cow::Borrowed(&[index]),
+                ..gif::Frame::default()
+            }).unwrap();
+        }
+    }
+    let image = load_from_memory(&data).unwrap();
+    assert_eq!((image.width, image.height), (20000, 1));
+    assert!(!image.should_animate());
+}
+
+#[test]
+fn test_still_gif_does_not_animate() {
+    let palette = [0xff, 0x00, 0x00, 0x00, 0x00, 0xff];
+    let mut data = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut data, 1, 1, &palette).unwrap();
+        encoder.write_frame(&gif::Frame {
+            width: 1,
+            height: 1,
+            delay: 5,
+            buffer: Cow::Borrowed(&[1]),
+            ..gif::Frame::default()
+        }).unwrap();
+    }
+    let image = load_from_memory(&data).unwrap();
+    assert!(!image.should_animate());
+    assert_eq!(image.frames[0].delay, None);
+    assert_eq!(image.first_frame_bytes(), &[255, 0, 0, 255][..]);
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xEDB88320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn be32(value: u32) -> [u8; 4] {
+    [(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+    png.extend_from_slice(&be32(data.len() as u32));
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&be32(crc));
+}
+
+/// A zlib stream storing a single scanline of one RGBA pixel uncompressed.
+fn stored_pixel(rgba: [u8; 4]) -> Vec<u8> {
+    let scanline = [0, rgba[0], rgba[1], rgba[2], rgba[3]];
+    let mut data = vec![0x78, 0x01, 0x01, 5, 0, !5, !0];
+    data.extend_from_slice(&scanline);
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in &scanline {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    data.extend_from_slice(&be32(b << 16 | a));
+    data
+}
+
+fn frame_control(sequence: u32, delay_num: u8, blend_op: u8) -> Vec<u8> {
+    let mut data = vec![];
+    data.extend_from_slice(&be32(sequence));
+    data.extend_from_slice(&be32(1));
+    data.extend_from_slice(&be32(1));
+    data.extend_from_slice(&be32(0));
+    data.extend_from_slice(&be32(0));
+    data.extend_from_slice(&[0, delay_num, 0, 10, 0, blend_op]);
+    data
+}
+
+/// A 1x1 APNG showing a red frame, then a half-transparent blue one blended
+/// over it.
+fn animated_png() -> Vec<u8> {
+    let mut png = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+    push_chunk(&mut png, b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+    push_chunk(&mut png, b"acTL", &[0, 0, 0, 2, 0, 0, 0, 0]);
+    push_chunk(&mut png, b"fcTL", &frame_control(0, 1, 0));
+    push_chunk(&mut png, b"IDAT", &stored_pixel([255, 0, 0, 255]));
+    push_chunk(&mut png, b"fcTL", &frame_control(1, 2, 1));
+    let mut frame_data = be32(2).to_vec();
+    frame_data.extend_from_slice(&stored_pixel([0, 0, 255, 0]));
+    push_chunk(&mut png, b"fdAT", &frame_data);
+    push_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[test]
+fn test_animated_png_frames() {
+    let image = load_from_memory(&animated_png()).unwrap();
+    assert_eq!((image.width, image.height), (1, 1));
+    assert_eq!(image.frames.len(), 2);
+    assert_eq!(image.frames[0].delay, Some(Duration::from_millis(100)));
+    assert_eq!(image.frames[1].delay, Some(Duration::from_millis(200)));
+    assert_eq!(image.frame_bytes(0), &[0, 0, 255, 255][..]);
+    // A transparent pixel blended over the previous frame leaves it as is.
+    assert_eq!(image.frame_bytes(1), &[0, 0, 255, 255][..]);
+}
+
+#[test]
+fn test_image_animation_state() {
+    let image = Arc::new(load_from_memory(&animated_gif()).unwrap());
+    let mut state = ImageAnimationState::new(image, 0.);
+    assert_eq!(state.active_frame, 0);
+
+    assert!(!state.update(0.01));
+    assert!(state.update(0.06));
+    assert_eq!(state.active_frame, 1);
+    assert!(state.update(0.11));
+    assert_eq!(state.active_frame, 0);
+
+    // Time spent paused doesn't advance the animation.
+    state.resume(10.);
+    assert!(!state.update(10.01));
+    assert_eq!(state.active_frame, 0);
+}
+
+#[test]
+fn test_grayscale_webrender_frame() {
+    let image = Image::new(2, 1, PixelFormat::K8, IpcSharedMemory::from_bytes(&[0x10, 0x80]));
+    let (descriptor, data) = image.webrender_frame(0);
+    assert!(descriptor.is_opaque);
+    match data {
+        webrender_api::ImageData::Raw(bytes) => {
+            assert_eq!(&bytes[..], &[0x10, 0x10, 0x10, 0xff, 0x80, 0x80, 0x80, 0xff][..]);
+        },
+        _ => panic!("expected raw image data"),
+    }
+
+    // Luminance is premultiplied by alpha like the color channels of BGRA.
+    let image = Image::new(1, 1, PixelFormat::KA8, IpcSharedMemory::from_bytes(&[0xff, 0x80]));
+    let (descriptor, data) = image.webrender_frame(0);
+    assert!(!descriptor.is_opaque);
+    match data {
+        webrender_api::ImageData::Raw(bytes) => assert_eq!(&bytes[..], &[0x80, 0x80, 0x80, 0x80][..]),
+        _ => panic!("expected raw image data"),
+    }
+}
@@ -155,9 +155,14 @@ impl Formattable for ProfilerCategory {
             ProfilerCategory::ScriptWebVREvent => "Script WebVR Event",
             ProfilerCategory::ScriptWorkletEvent => "Script Worklet Event",
             ProfilerCategory::ScriptPerformanceEvent => "Script Performance Event",
+            ProfilerCategory::ScriptPortMessage => "Script Port Message",
+            ProfilerCategory::ScriptIntersectionObserver => "Script Intersection Observer",
+            ProfilerCategory::ScriptIdleCallback => "Script Idle Callback",
             ProfilerCategory::TimeToFirstPaint => "Time To First Paint",
             ProfilerCategory::TimeToFirstContentfulPaint => "Time To First Contentful Paint",
             ProfilerCategory::TimeToInteractive => "Time to Interactive",
+            ProfilerCategory::TimeToLargestContentfulPaint => "Time To Largest Contentful Paint",
+            ProfilerCategory::CumulativeLayoutShift => "Cumulative Layout Shift",
             ProfilerCategory::IpcReceiver => "Blocked at IPC Receive",
             ProfilerCategory::ApplicationHeartbeat => "Application Heartbeat",
         };
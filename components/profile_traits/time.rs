@@ -99,10 +99,15 @@ pub enum ProfilerCategory {
     ScriptWebVREvent = 0x79,
     ScriptWorkletEvent = 0x7a,
     ScriptPerformanceEvent = 0x7b,
+    ScriptPortMessage = 0x7c,
+    ScriptIntersectionObserver = 0x7d,
+    ScriptIdleCallback = 0x7e,
     TimeToFirstPaint = 0x80,
     TimeToFirstContentfulPaint = 0x81,
     TimeToInteractive = 0x82,
     IpcReceiver = 0x83,
+    TimeToLargestContentfulPaint = 0x84,
+    CumulativeLayoutShift = 0x85,
     ApplicationHeartbeat = 0x90,
 }
 
@@ -0,0 +1,168 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::AbortSignalBinding::{self, AbortSignalMethods};
+use dom::bindings::codegen::Bindings::EventListenerBinding::EventListener;
+use dom::bindings::codegen::Bindings::EventTargetBinding::EventListenerOptions;
+use dom::bindings::error::{Error, ErrorResult};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::domexception::{DOMErrorName, DOMException};
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use fetch::FetchCanceller;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::{Heap, JSContext};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use js::rust::wrappers::JS_SetPendingException;
+use std::rc::Rc;
+
+/// An algorithm to run when an `AbortSignal` is aborted.
+///
+/// <https://dom.spec.whatwg.org/#abortsignal-abort-algorithms>
+#[derive(JSTraceable)]
+#[must_root]
+pub enum AbortAlgorithm {
+    /// Remove an event listener that was added with this signal.
+    RemoveEventListener(Dom<EventTarget>, DOMString, Rc<EventListener>, bool),
+    /// Cancel an ongoing fetch.
+    Fetch(FetchCanceller),
+}
+
+impl AbortAlgorithm {
+    fn exec(&mut self) {
+        match *self {
+            AbortAlgorithm::RemoveEventListener(ref target, ref ty, ref listener, capture) => {
+                target.remove_event_listener(
+                    ty.clone(),
+                    Some(listener.clone()),
+                    EventListenerOptions { capture },
+                );
+            },
+            AbortAlgorithm::Fetch(ref mut canceller) => canceller.cancel(),
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#abortsignal
+#[dom_struct]
+pub struct AbortSignal {
+    eventtarget: EventTarget,
+    /// <https://dom.spec.whatwg.org/#abortsignal-abort-reason>
+    abort_reason: Heap<JSVal>,
+    #[ignore_malloc_size_of = "Rc"]
+    abort_algorithms: DomRefCell<Vec<AbortAlgorithm>>,
+}
+
+impl AbortSignal {
+    fn new_inherited() -> AbortSignal {
+        AbortSignal {
+            eventtarget: EventTarget::new_inherited(),
+            abort_reason: Heap::default(),
+            abort_algorithms: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<AbortSignal> {
+        let signal = reflect_dom_object(Box::new(AbortSignal::new_inherited()),
+                                        global,
+                                        AbortSignalBinding::Wrap);
+        signal.abort_reason.set(UndefinedValue());
+        signal
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-aborted>
+    pub fn aborted(&self) -> bool {
+        !self.abort_reason.get().is_undefined()
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-add>
+    ///
+    /// Does nothing if the signal is already aborted, so callers must check
+    /// `aborted` first, e.g. to reject a fetch right away.
+    pub fn add_abort_algorithm(&self, algorithm: AbortAlgorithm) {
+        if self.aborted() {
+            return;
+        }
+        self.abort_algorithms.borrow_mut().push(algorithm);
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-signal-abort>
+    #[allow(unsafe_code)]
+    pub fn signal_abort(&self, reason: HandleValue) {
+        // Step 1.
+        if self.aborted() {
+            return;
+        }
+
+        // Step 2.
+        if reason.is_undefined() {
+            let global = self.global();
+            let cx = global.get_cx();
+            rooted!(in(cx) let mut error = UndefinedValue());
+            let exception = DOMException::new(&global, DOMErrorName::AbortError);
+            unsafe { exception.to_jsval(cx, error.handle_mut()) };
+            self.abort_reason.set(error.get());
+        } else {
+            self.abort_reason.set(reason.get());
+        }
+
+        // Step 3.
+        for algorithm in self.abort_algorithms.borrow_mut().iter_mut() {
+            algorithm.exec();
+        }
+
+        // Step 4.
+        self.abort_algorithms.borrow_mut().clear();
+
+        // Step 5.
+        self.upcast::<EventTarget>().fire_event(atom!("abort"));
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-abort
+    #[allow(unsafe_code)]
+    pub unsafe fn Abort(_cx: *mut JSContext, global: &GlobalScope, reason: HandleValue) -> DomRoot<AbortSignal> {
+        // Step 1.
+        let signal = AbortSignal::new(global);
+
+        // Step 2.
+        signal.signal_abort(reason);
+
+        // Step 3.
+        signal
+    }
+}
+
+impl AbortSignalMethods for AbortSignal {
+    // https://dom.spec.whatwg.org/#dom-abortsignal-aborted
+    fn Aborted(&self) -> bool {
+        self.aborted()
+    }
+
+    #[allow(unsafe_code)]
+    // https://dom.spec.whatwg.org/#dom-abortsignal-reason
+    unsafe fn Reason(&self, _cx: *mut JSContext) -> JSVal {
+        self.abort_reason.get()
+    }
+
+    #[allow(unsafe_code)]
+    // https://dom.spec.whatwg.org/#dom-abortsignal-throwifaborted
+    fn ThrowIfAborted(&self) -> ErrorResult {
+        if !self.aborted() {
+            return Ok(());
+        }
+        let cx = self.global().get_cx();
+        rooted!(in(cx) let reason = self.abort_reason.get());
+        unsafe { JS_SetPendingException(cx, reason.handle()) };
+        Err(Error::JSFailed)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+}
@@ -8,13 +8,14 @@ use dom::bindings::structuredclone::StructuredCloneData;
 use js::jsapi::{JSRuntime, JS_RequestInterruptCallback};
 use js::rust::Runtime;
 use script_runtime::CommonScriptMsg;
+use script_traits::TransferredPort;
 
 /// Messages used to control the worker event loops
 pub enum WorkerScriptMsg {
     /// Common variants associated with the script messages
     Common(CommonScriptMsg),
-    /// Message sent through Worker.postMessage
-    DOMMessage(StructuredCloneData)
+    /// Message sent through Worker.postMessage, along with the transferred ports
+    DOMMessage(StructuredCloneData, Vec<TransferredPort>)
 }
 
 pub struct SimpleWorkerErrorHandler<T: DomObject> {
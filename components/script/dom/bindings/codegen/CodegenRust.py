@@ -344,12 +344,18 @@ class CGMethodCall(CGThing):
 
             distinguishingIndex = method.distinguishingIndexForArgCount(argCount)
 
-            # We can't handle unions at the distinguishing index.
+            # We can't handle unions of non-object values at the distinguishing
+            # index.
             for (returnType, args) in possibleSignatures:
-                if args[distinguishingIndex].type.isUnion():
-                    raise TypeError("No support for unions as distinguishing "
-                                    "arguments yet: %s",
-                                    args[distinguishingIndex].location)
+                type = args[distinguishingIndex].type
+                if type.isUnion():
+                    if type.nullable():
+                        type = type.inner
+                    for memberType in type.flatMemberTypes:
+                        if not (memberType.isObject() or memberType.isNonCallbackInterface()):
+                            raise TypeError("No support for unions with non-object variants "
+                                            "as distinguishing arguments yet: %s",
+                                            args[distinguishingIndex].location)
 
             # Convert all our arguments up to the distinguishing index.
             # Doesn't matter which of the possible signatures we use, since
@@ -388,6 +394,7 @@ class CGMethodCall(CGThing):
             interfacesSigs = [
                 s for s in possibleSignatures
                 if (s[1][distinguishingIndex].type.isObject() or
+                    s[1][distinguishingIndex].type.isUnion() or
                     s[1][distinguishingIndex].type.isNonCallbackInterface())]
             # There might be more than one of these; we need to check
             # which ones we unwrap to.
@@ -1495,7 +1502,7 @@ def getRetvalDeclarationForType(returnType, descriptorProvider):
                     returnType)
 
 
-def MemberCondition(pref, func):
+def MemberCondition(pref, func, secureContext):
     """
     A string representing the condition for a member to actually be exposed.
     Any of the arguments can be None. If not None, they should have the
@@ -1503,14 +1510,17 @@ def MemberCondition(pref, func):
 
     pref: The name of the preference.
     func: The name of the function.
+    secureContext: Whether the member is only exposed in secure contexts.
     """
     assert pref is None or isinstance(pref, str)
     assert func is None or isinstance(func, str)
-    assert func is None or pref is None
+    assert len([c for c in [pref, func, secureContext] if c]) <= 1
     if pref:
         return 'Condition::Pref("%s")' % pref
     if func:
         return 'Condition::Func(%s)' % func
+    if secureContext:
+        return "Condition::SecureContext"
     return "Condition::Satisfied"
 
 
@@ -1553,7 +1563,8 @@ class PropertyDefiner:
             PropertyDefiner.getStringAttr(interfaceMember,
                                           "Pref"),
             PropertyDefiner.getStringAttr(interfaceMember,
-                                          "Func"))
+                                          "Func"),
+            bool(interfaceMember.getExtendedAttribute("SecureContext")))
 
     def generateGuardedArray(self, array, name, specTemplate, specTerminator,
                              specType, getCondition, getDataTuple):
@@ -2187,8 +2198,8 @@ class CGAssertInheritance(CGThing):
 
         selfName = self.descriptor.interface.identifier.name
 
-        if selfName == "PaintRenderingContext2D":
-            # PaintRenderingContext2D embeds a CanvasRenderingContext2D
+        if selfName in ("OffscreenCanvasRenderingContext2D", "PaintRenderingContext2D"):
+            # These embed a CanvasRenderingContext2D
             # instead of a Reflector as an optimization,
             # but this is fine since CanvasRenderingContext2D
             # also has a reflector
@@ -2572,6 +2583,9 @@ class CGConstructorEnabled(CGAbstractMethod):
             assert isinstance(func, list) and len(func) == 1
             conditions.append("%s(aCx, aObj)" % func[0])
 
+        if iface.getExtendedAttribute("SecureContext"):
+            conditions.append("is_secure_context(aCx, aObj)")
+
         return CGList((CGGeneric(cond) for cond in conditions), " &&\n")
 
 
@@ -4409,7 +4423,9 @@ class CGUnionConversionStruct(CGThing):
             conversions.append(CGIfWrapper("value.get().is_object()", templateBody))
 
         if dictionaryObject:
-            assert not hasObjectTypes
+            # Objects which aren't one of the interface types are converted to
+            # the dictionary.
+            assert not (object or arrayObject or dateObject or mozMapObject)
             conversions.append(dictionaryObject)
 
         stringTypes = [t for t in memberTypes if t.isString() or t.isEnum()]
@@ -5920,6 +5936,7 @@ def generate_imports(config, cgthings, descriptors, callbacks=None, dictionaries
         'dom::bindings::error::throw_dom_exception',
         'dom::bindings::guard::Condition',
         'dom::bindings::guard::Guard',
+        'dom::bindings::guard::is_secure_context',
         'dom::bindings::inheritance::Castable',
         'dom::bindings::proxyhandler',
         'dom::bindings::proxyhandler::ensure_expando_object',
@@ -4,6 +4,7 @@
 
 //! Machinery to conditionally expose things.
 
+use dom::globalscope::GlobalScope;
 use js::jsapi::JSContext;
 use js::rust::HandleObject;
 use servo_config::prefs::PREFS;
@@ -41,6 +42,8 @@ pub enum Condition {
     Func(unsafe fn(*mut JSContext, HandleObject) -> bool),
     /// The condition is satisfied if the preference is set.
     Pref(&'static str),
+    /// The condition is satisfied if the global is a secure context.
+    SecureContext,
     /// The condition is always satisfied.
     Satisfied,
 }
@@ -50,7 +53,14 @@ impl Condition {
         match *self {
             Condition::Pref(name) => PREFS.get(name).as_boolean().unwrap_or(false),
             Condition::Func(f) => f(cx, obj),
+            Condition::SecureContext => is_secure_context(cx, obj),
             Condition::Satisfied => true,
         }
     }
 }
+
+/// Whether the global of `obj` is a
+/// [secure context](https://html.spec.whatwg.org/multipage/#secure-context).
+pub unsafe fn is_secure_context(_: *mut JSContext, obj: HandleObject) -> bool {
+    GlobalScope::from_object(obj.get()).is_secure_context()
+}
@@ -5,12 +5,17 @@
 //! This module implements structured cloning, as defined by [HTML]
 //! (https://html.spec.whatwg.org/multipage/#safe-passing-of-structured-data).
 
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::ImageBitmapMethods;
+use dom::bindings::codegen::Bindings::MessagePortBinding::Transferable;
 use dom::bindings::conversions::root_from_handleobject;
 use dom::bindings::error::{Error, Fallible};
 use dom::bindings::reflector::DomObject;
 use dom::bindings::root::DomRoot;
 use dom::blob::{Blob, BlobImpl};
 use dom::globalscope::GlobalScope;
+use dom::imagebitmap::ImageBitmap;
+use dom::messageport::MessagePort;
+use euclid::Size2D;
 use js::jsapi::{JSAutoCompartment, JSContext};
 use js::jsapi::{JSStructuredCloneCallbacks, JSStructuredCloneReader, JSStructuredCloneWriter};
 use js::jsapi::{JS_ClearPendingException, JSObject};
@@ -23,6 +28,8 @@ use js::jsapi::TransferableOwnership;
 use js::rust::{Handle, HandleValue, MutableHandleValue};
 use js::rust::wrappers::{JS_WriteStructuredClone, JS_ReadStructuredClone};
 use libc::size_t;
+use script_traits::TransferredPort;
+use std::collections::HashSet;
 use std::os::raw;
 use std::ptr;
 use std::slice;
@@ -36,6 +43,8 @@ enum StructuredCloneTags {
     /// To support additional types, add new tags with values incremented from the last one before Max.
     Min = 0xFFFF8000,
     DomBlob = 0xFFFF8001,
+    MessagePort = 0xFFFF8002,
+    ImageBitmap = 0xFFFF8003,
     Max = 0xFFFFFFFF,
 }
 
@@ -128,10 +137,69 @@ unsafe fn write_blob(blob: DomRoot<Blob>,
     return Ok(())
 }
 
+/// Ports are not serialized themselves: only their index in the transfer
+/// list is, the ports being sent alongside the clone.
+fn read_port(index: u32, sc_holder: &StructuredCloneHolder) -> *mut JSObject {
+    match sc_holder.ports.get(index as usize) {
+        Some(port) => port.reflector().get_jsobject().get(),
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe fn write_port(port: DomRoot<MessagePort>,
+                     w: *mut JSStructuredCloneWriter,
+                     sc_holder: &StructuredCloneHolder)
+                     -> Result<(), ()> {
+    // https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer
+    // Only transferred ports can be cloned.
+    let index = sc_holder.ports.iter().position(|transferred| *transferred == port).ok_or(())?;
+    assert!(JS_WriteUint32Pair(w, StructuredCloneTags::MessagePort as u32, index as u32));
+    Ok(())
+}
+
+unsafe fn read_image_bitmap(cx: *mut JSContext,
+                            r: *mut JSStructuredCloneReader,
+                            premultiplied: u32,
+                            sc_holder: &mut StructuredCloneHolder)
+                            -> *mut JSObject {
+    let structured_reader = StructuredCloneReader { r: r };
+    let mut width = 0;
+    let mut height = 0;
+    assert!(JS_ReadUint32Pair(r, &mut width as *mut u32, &mut height as *mut u32));
+    let bitmap_data = structured_reader.read_bytes();
+    let target_global = GlobalScope::from_context(cx);
+    let bitmap = ImageBitmap::new(&target_global,
+                                  Size2D::new(width, height),
+                                  bitmap_data,
+                                  premultiplied != 0,
+                                  true);
+    let js_object = bitmap.reflector().get_jsobject().get();
+    sc_holder.image_bitmaps.push(bitmap);
+    js_object
+}
+
+/// Bitmaps are copied whether they are transferred or not, the transferred
+/// ones being closed once the whole message was serialized.
+unsafe fn write_image_bitmap(bitmap: DomRoot<ImageBitmap>,
+                             w: *mut JSStructuredCloneWriter)
+                             -> Result<(), ()> {
+    // https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:serialization-steps
+    if !bitmap.origin_is_clean() {
+        return Err(());
+    }
+    let (bitmap_data, size) = bitmap.bitmap_data().ok_or(())?;
+    let structured_writer = StructuredCloneWriter { w: w };
+    let premultiplied = bitmap.is_alpha_premultiplied() as u32;
+    assert!(JS_WriteUint32Pair(w, StructuredCloneTags::ImageBitmap as u32, premultiplied));
+    assert!(JS_WriteUint32Pair(w, size.width, size.height));
+    structured_writer.write_slice(&bitmap_data);
+    Ok(())
+}
+
 unsafe extern "C" fn read_callback(cx: *mut JSContext,
                                    r: *mut JSStructuredCloneReader,
                                    tag: u32,
-                                   _data: u32,
+                                   data: u32,
                                    closure: *mut raw::c_void)
                                    -> *mut JSObject {
     assert!(tag < StructuredCloneTags::Max as u32, "tag should be lower than StructuredCloneTags::Max");
@@ -139,17 +207,29 @@ unsafe extern "C" fn read_callback(cx: *mut JSContext,
     if tag == StructuredCloneTags::DomBlob as u32 {
         return read_blob(cx, r, &mut *(closure as *mut StructuredCloneHolder))
     }
+    if tag == StructuredCloneTags::MessagePort as u32 {
+        return read_port(data, &*(closure as *mut StructuredCloneHolder))
+    }
+    if tag == StructuredCloneTags::ImageBitmap as u32 {
+        return read_image_bitmap(cx, r, data, &mut *(closure as *mut StructuredCloneHolder))
+    }
     return ptr::null_mut()
 }
 
 unsafe extern "C" fn write_callback(_cx: *mut JSContext,
                                     w: *mut JSStructuredCloneWriter,
                                     obj: RawHandleObject,
-                                    _closure: *mut raw::c_void)
+                                    closure: *mut raw::c_void)
                                     -> bool {
     if let Ok(blob) = root_from_handleobject::<Blob>(Handle::from_raw(obj)) {
         return write_blob(blob, w).is_ok()
     }
+    if let Ok(port) = root_from_handleobject::<MessagePort>(Handle::from_raw(obj)) {
+        return write_port(port, w, &*(closure as *mut StructuredCloneHolder)).is_ok()
+    }
+    if let Ok(bitmap) = root_from_handleobject::<ImageBitmap>(Handle::from_raw(obj)) {
+        return write_image_bitmap(bitmap, w).is_ok()
+    }
     return false
 }
 
@@ -195,7 +275,12 @@ static STRUCTURED_CLONE_CALLBACKS: JSStructuredCloneCallbacks = JSStructuredClon
 };
 
 struct StructuredCloneHolder {
-    blob: Option<DomRoot<Blob>>
+    blob: Option<DomRoot<Blob>>,
+    /// The ports transferred along with the clone: the transfer list when
+    /// writing, and the newly created ports when reading.
+    ports: Vec<DomRoot<MessagePort>>,
+    /// The bitmaps created while reading, kept rooted until the clone is read.
+    image_bitmaps: Vec<DomRoot<ImageBitmap>>,
 }
 
 /// A buffer for a structured clone.
@@ -209,6 +294,42 @@ pub enum StructuredCloneData {
 impl StructuredCloneData {
     /// Writes a structured clone. Returns a `DataClone` error if that fails.
     pub fn write(cx: *mut JSContext, message: HandleValue) -> Fallible<StructuredCloneData> {
+        StructuredCloneData::write_with_transfer(cx, message, &[]).map(|(data, _)| data)
+    }
+
+    /// Writes a structured clone, transferring the given ports and bitmaps
+    /// along with it. Returns a `DataClone` error if that fails, in which case
+    /// none of the ports are detached and none of the bitmaps are closed.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer>
+    pub fn write_with_transfer(cx: *mut JSContext,
+                               message: HandleValue,
+                               transfer: &[Transferable])
+                               -> Fallible<(StructuredCloneData, Vec<TransferredPort>)> {
+        let mut ports = vec![];
+        let mut image_bitmaps: Vec<DomRoot<ImageBitmap>> = vec![];
+        for transferable in transfer {
+            match *transferable {
+                Transferable::MessagePort(ref port) => ports.push(port.clone()),
+                Transferable::ImageBitmap(ref bitmap) => image_bitmaps.push(bitmap.clone()),
+            }
+        }
+
+        // Step 4.
+        let mut seen = HashSet::new();
+        for port in &ports {
+            if !seen.insert(port.message_port_id()) || port.detached() {
+                return Err(Error::DataClone);
+            }
+        }
+        for (index, bitmap) in image_bitmaps.iter().enumerate() {
+            if image_bitmaps[..index].contains(bitmap) || bitmap.is_closed() {
+                return Err(Error::DataClone);
+            }
+        }
+
+        let mut sc_holder = StructuredCloneHolder { blob: None, ports: ports, image_bitmaps: vec![] };
+        let sc_holder_ptr = &mut sc_holder as *mut _;
         let mut data = ptr::null_mut();
         let mut nbytes = 0;
         let result = unsafe {
@@ -217,7 +338,7 @@ impl StructuredCloneData {
                                     &mut data,
                                     &mut nbytes,
                                     &STRUCTURED_CLONE_CALLBACKS,
-                                    ptr::null_mut(),
+                                    sc_holder_ptr as *mut raw::c_void,
                                     HandleValue::undefined())
         };
         if !result {
@@ -226,7 +347,13 @@ impl StructuredCloneData {
             }
             return Err(Error::DataClone);
         }
-        Ok(StructuredCloneData::Struct(data, nbytes))
+
+        // Step 8, only once serialization succeeded.
+        let transferred = sc_holder.ports.iter().map(|port| port.transfer()).collect();
+        for bitmap in image_bitmaps {
+            bitmap.Close();
+        }
+        Ok((StructuredCloneData::Struct(data, nbytes), transferred))
     }
 
     /// Converts a StructuredCloneData to Vec<u8> for inter-thread sharing
@@ -247,11 +374,12 @@ impl StructuredCloneData {
     fn read_clone(global: &GlobalScope,
                   data: *mut u64,
                   nbytes: size_t,
+                  ports: &[DomRoot<MessagePort>],
                   rval: MutableHandleValue) {
         let cx = global.get_cx();
         let globalhandle = global.reflector().get_jsobject();
         let _ac = JSAutoCompartment::new(cx, globalhandle.get());
-        let mut sc_holder = StructuredCloneHolder { blob: None };
+        let mut sc_holder = StructuredCloneHolder { blob: None, ports: ports.to_vec(), image_bitmaps: vec![] };
         let sc_holder_ptr = &mut sc_holder as *mut _;
         unsafe {
             assert!(JS_ReadStructuredClone(cx,
@@ -266,14 +394,32 @@ impl StructuredCloneData {
 
     /// Thunk for the actual `read_clone` method. Resolves proper variant for read_clone.
     pub fn read(self, global: &GlobalScope, rval: MutableHandleValue) {
+        self.read_with_ports(global, vec![], rval);
+    }
+
+    /// Reads a structured clone, creating the ports that were transferred
+    /// along with it in the given global. Returns the new ports.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#structureddeserializewithtransfer>
+    pub fn read_with_ports(self,
+                           global: &GlobalScope,
+                           ports: Vec<TransferredPort>,
+                           rval: MutableHandleValue)
+                           -> Vec<DomRoot<MessagePort>> {
+        let new_ports: Vec<_> = ports.into_iter()
+            .map(|transferred| MessagePort::new_transferred(global, transferred))
+            .collect();
         match self {
             StructuredCloneData::Vector(mut vec_msg) => {
                 let nbytes = vec_msg.len();
                 let data = vec_msg.as_mut_ptr() as *mut u64;
-                StructuredCloneData::read_clone(global, data, nbytes, rval);
+                StructuredCloneData::read_clone(global, data, nbytes, &new_ports, rval);
+            }
+            StructuredCloneData::Struct(data, nbytes) => {
+                StructuredCloneData::read_clone(global, data, nbytes, &new_ports, rval)
             }
-            StructuredCloneData::Struct(data, nbytes) => StructuredCloneData::read_clone(global, data, nbytes, rval)
         }
+        new_ports
     }
 }
 
@@ -2,19 +2,26 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use canvas_traits::canvas::{Canvas2dMsg, CanvasMsg, CanvasId};
-use canvas_traits::canvas::{CompositionOrBlending, FillOrStrokeStyle, FillRule};
+use canvas_traits::canvas::{Canvas2dMsg, CanvasFontFamily, CanvasFontSlant, CanvasFontStyle, CanvasMsg, CanvasId};
+use canvas_traits::canvas::{CompositionOrBlending, Direction, FillOrStrokeStyle, FillRule};
+use canvas_traits::canvas::{FilterFunction, ImageSmoothingQuality};
 use canvas_traits::canvas::{LineCapStyle, LineJoinStyle, LinearGradientStyle};
-use canvas_traits::canvas::{RadialGradientStyle, RepetitionStyle, byte_swap_and_premultiply};
+use canvas_traits::canvas::{RadialGradientStyle, RepetitionStyle, TextAlign, TextBaseline};
+use canvas_traits::canvas::{byte_swap, byte_swap_and_premultiply};
 use cssparser::{Parser, ParserInput, RGBA};
 use cssparser::Color as CSSColor;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasDirection;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasFillRule;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasImageSource;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineCap;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineJoin;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextAlign;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextBaseline;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::ImageSmoothingQuality as SmoothingQuality;
+use dom::bindings::codegen::Bindings::DOMMatrixBinding::DOMMatrixInit;
 use dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
 use dom::bindings::codegen::UnionTypes::StringOrCanvasGradientOrCanvasPattern;
 use dom::bindings::error::{Error, ErrorResult, Fallible};
@@ -25,13 +32,19 @@ use dom::bindings::root::{Dom, DomRoot, LayoutDom};
 use dom::bindings::str::DOMString;
 use dom::canvasgradient::{CanvasGradient, CanvasGradientStyle, ToFillOrStrokeStyle};
 use dom::canvaspattern::CanvasPattern;
+use dom::dommatrix::DOMMatrix;
+use dom::dommatrixreadonly::dommatrixinit_to_matrix;
 use dom::element::Element;
 use dom::globalscope::GlobalScope;
 use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::imagebitmap::ImageBitmap;
 use dom::imagedata::ImageData;
-use dom::node::{Node, NodeDamage, window_from_node};
+use dom::node::{Node, NodeDamage, document_from_node, window_from_node};
+use dom::offscreencanvas::OffscreenCanvas;
+use dom::path2d::Path2D;
+use dom::textmetrics::TextMetrics;
 use dom_struct::dom_struct;
-use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D, vec2};
+use euclid::{Transform2D, Transform3D, TypedScale, TypedSize2D, Point2D, Vector2D, Rect, Size2D, vec2};
 use ipc_channel::ipc::IpcSender;
 use net_traits::image::base::PixelFormat;
 use net_traits::image_cache::CanRequestImages;
@@ -48,6 +61,21 @@ use std::{cmp, fmt, mem};
 use std::cell::Cell;
 use std::str::FromStr;
 use std::sync::Arc;
+use style::computed_values::font_variant_caps::T as FontVariantCaps;
+use style::context::QuirksMode;
+use style::media_queries::{Device, MediaType};
+use style::parser::{Parse, ParserContext};
+use style::properties::{ComputedValues, LonghandId, PropertyId};
+use style::properties::shorthands::font;
+use style::properties::style_structs::Font;
+use style::stylesheets::CssRuleType;
+use style::values::computed::{Context, ToComputedValue};
+use style::values::computed::font::{FontStretch, FontStyle, FontWeight, SingleFontFamily};
+use style::values::generics::effects::Filter as GenericFilter;
+use style::values::generics::font::FontStyle as GenericFontStyle;
+use style::values::specified::effects::Filter;
+use style::values::specified::font::FontStretchKeyword;
+use style_traits::{ParsingMode, ToCss};
 use unpremultiplytable::UNPREMULTIPLY_TABLE;
 
 #[must_root]
@@ -65,11 +93,13 @@ pub struct CanvasRenderingContext2D {
     reflector_: Reflector,
     #[ignore_malloc_size_of = "Defined in ipc-channel"]
     ipc_renderer: IpcSender<CanvasMsg>,
-    /// For rendering contexts created by an HTML canvas element, this is Some,
-    /// for ones created by a paint worklet, this is None.
+    /// For rendering contexts created by an HTML canvas element, or by an
+    /// offscreen canvas that has a placeholder element, this is Some,
+    /// for ones created by a paint worklet or in a worker, this is None.
     canvas: Option<Dom<HTMLCanvasElement>>,
+    /// Workers have no image cache, so images can't be drawn there.
     #[ignore_malloc_size_of = "Arc"]
-    image_cache: Arc<ImageCache>,
+    image_cache: Option<Arc<ImageCache>>,
     /// Any missing image URLs.
     missing_image_urls: DomRefCell<Vec<ServoUrl>>,
     /// The base URL for resolving CSS image URL values.
@@ -79,6 +109,8 @@ pub struct CanvasRenderingContext2D {
     saved_states: DomRefCell<Vec<CanvasContextState>>,
     origin_clean: Cell<bool>,
     canvas_id: CanvasId,
+    /// The size of the bitmap.
+    size: Cell<Size2D<i32>>,
 }
 
 #[must_root]
@@ -87,17 +119,25 @@ struct CanvasContextState {
     global_alpha: f64,
     global_composition: CompositionOrBlending,
     image_smoothing_enabled: bool,
+    image_smoothing_quality: ImageSmoothingQuality,
     fill_style: CanvasFillOrStrokeStyle,
     stroke_style: CanvasFillOrStrokeStyle,
     line_width: f64,
     line_cap: LineCapStyle,
     line_join: LineJoinStyle,
     miter_limit: f64,
+    line_dash: Vec<f64>,
+    line_dash_offset: f64,
     transform: Transform2D<f32>,
     shadow_offset_x: f64,
     shadow_offset_y: f64,
     shadow_blur: f64,
     shadow_color: RGBA,
+    font: DOMString,
+    text_align: TextAlign,
+    text_baseline: TextBaseline,
+    direction: CanvasDirection,
+    filter: DOMString,
 }
 
 impl CanvasContextState {
@@ -107,17 +147,25 @@ impl CanvasContextState {
             global_alpha: 1.0,
             global_composition: CompositionOrBlending::default(),
             image_smoothing_enabled: true,
+            image_smoothing_quality: ImageSmoothingQuality::Low,
             fill_style: CanvasFillOrStrokeStyle::Color(black),
             stroke_style: CanvasFillOrStrokeStyle::Color(black),
             line_width: 1.0,
             line_cap: LineCapStyle::Butt,
             line_join: LineJoinStyle::Miter,
             miter_limit: 10.0,
+            line_dash: vec![],
+            line_dash_offset: 0.0,
             transform: Transform2D::identity(),
             shadow_offset_x: 0.0,
             shadow_offset_y: 0.0,
             shadow_blur: 0.0,
             shadow_color: RGBA::transparent(),
+            font: DOMString::from("10px sans-serif"),
+            text_align: TextAlign::Start,
+            text_baseline: TextBaseline::Alphabetic,
+            direction: CanvasDirection::Inherit,
+            filter: DOMString::from("none"),
         }
     }
 }
@@ -125,7 +173,7 @@ impl CanvasContextState {
 impl CanvasRenderingContext2D {
     pub fn new_inherited(global: &GlobalScope,
                          canvas: Option<&HTMLCanvasElement>,
-                         image_cache: Arc<ImageCache>,
+                         image_cache: Option<Arc<ImageCache>>,
                          base_url: ServoUrl,
                          size: Size2D<i32>)
                          -> CanvasRenderingContext2D {
@@ -147,6 +195,7 @@ impl CanvasRenderingContext2D {
             saved_states: DomRefCell::new(Vec::new()),
             origin_clean: Cell::new(true),
             canvas_id: canvas_id,
+            size: Cell::new(size),
         }
     }
 
@@ -158,7 +207,7 @@ impl CanvasRenderingContext2D {
         let image_cache = window.image_cache();
         let base_url = window.get_url();
         let boxed = Box::new(CanvasRenderingContext2D::new_inherited(
-            global, Some(canvas), image_cache, base_url, size
+            global, Some(canvas), Some(image_cache), base_url, size
         ));
         reflect_dom_object(boxed, global, CanvasRenderingContext2DBinding::Wrap)
     }
@@ -166,11 +215,25 @@ impl CanvasRenderingContext2D {
     // https://html.spec.whatwg.org/multipage/#concept-canvas-set-bitmap-dimensions
     pub fn set_bitmap_dimensions(&self, size: Size2D<i32>) {
         self.reset_to_initial_state();
+        self.size.set(size);
         self.ipc_renderer
             .send(CanvasMsg::Recreate(size, self.get_canvas_id()))
             .unwrap();
     }
 
+    /// Makes the whole bitmap transparent black, without touching the drawing
+    /// state. Putting image data ignores the transform and the clipping region.
+    pub fn clear_bitmap(&self) {
+        let size = self.size.get();
+        let data = vec![0; size.width as usize * size.height as usize * 4];
+        let size = Size2D::new(size.width as f64, size.height as f64);
+        self.send_canvas_2d_msg(Canvas2dMsg::PutImageData(data.into(),
+                                                          Vector2D::zero(),
+                                                          size,
+                                                          Rect::new(Point2D::zero(), size)));
+        self.mark_as_dirty();
+    }
+
     // https://html.spec.whatwg.org/multipage/#reset-the-rendering-context-to-its-default-state
     fn reset_to_initial_state(&self) {
         self.saved_states.borrow_mut().clear();
@@ -248,6 +311,8 @@ impl CanvasRenderingContext2D {
             }
             CanvasImageSource::CanvasRenderingContext2D(image) =>
                 image.origin_is_clean(),
+            CanvasImageSource::OffscreenCanvas(canvas) => canvas.origin_is_clean(),
+            CanvasImageSource::ImageBitmap(bitmap) => bitmap.origin_is_clean(),
             CanvasImageSource::HTMLImageElement(image) => {
                 let image_origin = image.get_origin().expect("Image's origin is missing");
                 image_origin.same_origin(GlobalScope::entry().origin())
@@ -299,6 +364,16 @@ impl CanvasRenderingContext2D {
                                               sx, sy, sw, sh,
                                               dx, dy, dw, dh)
             }
+            CanvasImageSource::OffscreenCanvas(ref canvas) => {
+                self.draw_offscreen_canvas(&canvas,
+                                           sx, sy, sw, sh,
+                                           dx, dy, dw, dh)
+            }
+            CanvasImageSource::ImageBitmap(ref bitmap) => {
+                self.draw_image_bitmap(&bitmap,
+                                       sx, sy, sw, sh,
+                                       dx, dy, dw, dh)
+            }
             CanvasImageSource::HTMLImageElement(ref image) => {
                 // https://html.spec.whatwg.org/multipage/#img-error
                 // If the image argument is an HTMLImageElement object that is in the broken state,
@@ -338,7 +413,96 @@ impl CanvasRenderingContext2D {
             return Err(Error::InvalidState);
         }
 
-        let canvas_size = canvas.get_size();
+        let source = if self.canvas.as_ref().map_or(false, |c| &**c == canvas) {
+            None
+        } else {
+            match canvas.get_or_init_2d_context() {
+                Some(context) => Some((context.get_ipc_renderer(), context.get_canvas_id())),
+                None => {
+                    // Canvases without a 2D renderer, like WebGL ones, are
+                    // drawn from a readback of their pixels.
+                    let (mut image_data, image_size) = canvas.fetch_all_data().ok_or(Error::InvalidState)?;
+                    byte_swap(&mut image_data);
+                    let image_size = Size2D::new(image_size.width as f64, image_size.height as f64);
+                    let dw = dw.unwrap_or(image_size.width);
+                    let dh = dh.unwrap_or(image_size.height);
+                    let sw = sw.unwrap_or(image_size.width);
+                    let sh = sh.unwrap_or(image_size.height);
+                    return self.draw_image_data(image_data, image_size, sx, sy, sw, sh, dx, dy, dw, dh);
+                },
+            }
+        };
+
+        self.draw_canvas_bitmap(source, canvas.get_size(), sx, sy, sw, sh, dx, dy, dw, dh)
+    }
+
+    fn draw_offscreen_canvas(&self,
+                             canvas: &OffscreenCanvas,
+                             sx: f64,
+                             sy: f64,
+                             sw: Option<f64>,
+                             sh: Option<f64>,
+                             dx: f64,
+                             dy: f64,
+                             dw: Option<f64>,
+                             dh: Option<f64>)
+                             -> ErrorResult {
+        // 1. Check the usability of the image argument
+        if !canvas.is_valid() {
+            return Err(Error::InvalidState);
+        }
+
+        let context = canvas.get_or_init_2d_context();
+        let source = if context.get_canvas_id() == self.get_canvas_id() {
+            None
+        } else {
+            Some((context.get_ipc_renderer(), context.get_canvas_id()))
+        };
+
+        self.draw_canvas_bitmap(source, canvas.get_size(), sx, sy, sw, sh, dx, dy, dw, dh)
+    }
+
+    fn draw_image_bitmap(&self,
+                         bitmap: &ImageBitmap,
+                         sx: f64,
+                         sy: f64,
+                         sw: Option<f64>,
+                         sh: Option<f64>,
+                         dx: f64,
+                         dy: f64,
+                         dw: Option<f64>,
+                         dh: Option<f64>)
+                         -> ErrorResult {
+        // 1. Check the usability of the image argument
+        let (mut image_data, image_size) = bitmap.premultiplied_data().ok_or(Error::InvalidState)?;
+
+        // Bitmaps are stored in BGRA order and drawImage expects RGBA.
+        byte_swap(&mut image_data);
+        let image_size = Size2D::new(image_size.width as f64, image_size.height as f64);
+        let dw = dw.unwrap_or(image_size.width);
+        let dh = dh.unwrap_or(image_size.height);
+        let sw = sw.unwrap_or(image_size.width);
+        let sh = sh.unwrap_or(image_size.height);
+        self.draw_image_data(image_data,
+                             image_size,
+                             sx, sy, sw, sh,
+                             dx, dy, dw, dh)
+    }
+
+    /// Draws the bitmap of another canvas, identified by its renderer and id,
+    /// or of this one if `source` is `None`.
+    fn draw_canvas_bitmap(&self,
+                          source: Option<(IpcSender<CanvasMsg>, CanvasId)>,
+                          canvas_size: Size2D<i32>,
+                          sx: f64,
+                          sy: f64,
+                          sw: Option<f64>,
+                          sh: Option<f64>,
+                          dx: f64,
+                          dy: f64,
+                          dw: Option<f64>,
+                          dh: Option<f64>)
+                          -> ErrorResult {
         let dw = dw.unwrap_or(canvas_size.width as f64);
         let dh = dh.unwrap_or(canvas_size.height as f64);
         let sw = sw.unwrap_or(canvas_size.width as f64);
@@ -362,29 +526,26 @@ impl CanvasRenderingContext2D {
 
         let smoothing_enabled = self.state.borrow().image_smoothing_enabled;
 
-        if self.canvas.as_ref().map_or(false, |c| &**c == canvas) {
-            self.send_canvas_2d_msg(Canvas2dMsg::DrawImageSelf(
-                image_size, dest_rect, source_rect, smoothing_enabled));
-        } else {
-            let context = match canvas.get_or_init_2d_context() {
-                Some(context) => context,
-                None => return Err(Error::InvalidState),
-            };
-
-            let msg = CanvasMsg::Canvas2d(
-                Canvas2dMsg::DrawImageInOther(
-                    self.get_canvas_id(),
-                    image_size,
-                    dest_rect,
-                    source_rect,
-                    smoothing_enabled
-                ),
-                context.get_canvas_id()
-            );
-
-            let renderer = context.get_ipc_renderer();
-            renderer.send(msg).unwrap();
-        };
+        match source {
+            None => {
+                self.send_canvas_2d_msg(Canvas2dMsg::DrawImageSelf(
+                    image_size, dest_rect, source_rect, smoothing_enabled));
+            },
+            Some((renderer, canvas_id)) => {
+                let msg = CanvasMsg::Canvas2d(
+                    Canvas2dMsg::DrawImageInOther(
+                        self.get_canvas_id(),
+                        image_size,
+                        dest_rect,
+                        source_rect,
+                        smoothing_enabled
+                    ),
+                    canvas_id
+                );
+
+                renderer.send(msg).unwrap();
+            },
+        }
 
         self.mark_as_dirty();
         Ok(())
@@ -476,7 +637,7 @@ impl CanvasRenderingContext2D {
 
         let image_size = Size2D::new(img.width as i32, img.height as i32);
         let image_data = match img.format {
-            PixelFormat::BGRA8 => img.bytes.to_vec(),
+            PixelFormat::BGRA8 => img.first_frame_bytes().to_vec(),
             PixelFormat::K8 => panic!("K8 color type not supported"),
             PixelFormat::RGB8 => panic!("RGB8 color type not supported"),
             PixelFormat::KA8 => panic!("KA8 color type not supported"),
@@ -487,7 +648,11 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     fn request_image_from_cache(&self, url: ServoUrl) -> ImageResponse {
-        let response = self.image_cache
+        let image_cache = match self.image_cache {
+            Some(ref image_cache) => image_cache,
+            None => return ImageResponse::None,
+        };
+        let response = image_cache
             .find_image_or_metadata(url.clone(),
                                     UsePlaceholder::No,
                                     CanRequestImages::No);
@@ -557,6 +722,86 @@ impl CanvasRenderingContext2D {
         }
     }
 
+    /// Parses a value of the `filter` attribute into filter functions for the
+    /// paint thread, or returns `None` if the value should be ignored.
+    fn parse_filter(&self, value: &str) -> Option<Vec<FilterFunction>> {
+        let mut input = ParserInput::new(value);
+        let mut parser = Parser::new(&mut input);
+        if parser.try(|p| p.expect_ident_matching("none")).is_ok() {
+            return if parser.is_exhausted() { Some(vec![]) } else { None };
+        }
+
+        let context = self.css_parser_context();
+        let mut filters = vec![];
+        while !parser.is_exhausted() {
+            filters.push(Filter::parse(&context, &mut parser).ok()?);
+        }
+        if filters.is_empty() {
+            return None;
+        }
+
+        let device = self.css_device();
+        let filters = Context::for_media_query_evaluation(&device, QuirksMode::NoQuirks, |context| {
+            filters.iter().map(|filter| filter.to_computed_value(context)).collect::<Vec<_>>()
+        });
+        Some(filters.into_iter().map(|filter| match filter {
+            GenericFilter::Blur(length) => FilterFunction::Blur(length.px()),
+            GenericFilter::Brightness(amount) => FilterFunction::Brightness(amount.0),
+            GenericFilter::Contrast(amount) => FilterFunction::Contrast(amount.0),
+            GenericFilter::Grayscale(amount) => FilterFunction::Grayscale(amount.0),
+            GenericFilter::HueRotate(angle) => FilterFunction::HueRotate(angle.radians()),
+            GenericFilter::Invert(amount) => FilterFunction::Invert(amount.0),
+            GenericFilter::Opacity(amount) => FilterFunction::Opacity(amount.0),
+            GenericFilter::Saturate(amount) => FilterFunction::Saturate(amount.0),
+            GenericFilter::Sepia(amount) => FilterFunction::Sepia(amount.0),
+            GenericFilter::DropShadow(impossible) |
+            GenericFilter::Url(impossible) => match impossible {},
+        }).collect())
+    }
+
+    /// Parses a value of the `font` attribute and computes it against the
+    /// initial style, for contexts that have no canvas element to resolve it
+    /// against.
+    fn compute_font_without_element(&self, value: &str) -> Option<Font> {
+        let mut input = ParserInput::new(value);
+        let mut parser = Parser::new(&mut input);
+        let context = self.css_parser_context();
+        let longhands = parser.parse_entirely(|input| font::parse_value(&context, input)).ok()?;
+
+        let device = self.css_device();
+        let mut font = ComputedValues::initial_values().get_font().clone();
+        Context::for_media_query_evaluation(&device, QuirksMode::NoQuirks, |context| {
+            font.font_style = longhands.font_style.to_computed_value(context);
+            font.font_variant_caps = longhands.font_variant_caps.to_computed_value(context);
+            font.font_weight = longhands.font_weight.to_computed_value(context);
+            font.font_stretch = longhands.font_stretch.to_computed_value(context);
+            font.font_size = longhands.font_size.to_computed_value(context);
+            font.font_family = longhands.font_family.to_computed_value(context);
+        });
+        font.compute_font_hash();
+        Some(font)
+    }
+
+    fn css_parser_context(&self) -> ParserContext {
+        ParserContext::new_for_cssom(
+            &self.base_url,
+            Some(CssRuleType::Style),
+            ParsingMode::DEFAULT,
+            QuirksMode::NoQuirks,
+            None,
+        )
+    }
+
+    /// The device to compute CSS values against. Paint worklets and workers
+    /// have no viewport, so viewport-relative lengths resolve to zero there.
+    fn css_device(&self) -> Device {
+        self.canvas.as_ref()
+            .and_then(|canvas| document_from_node(&**canvas).device())
+            .unwrap_or_else(|| {
+                Device::new(MediaType::screen(), TypedSize2D::new(0.0, 0.0), TypedScale::new(1.0))
+            })
+    }
+
     pub fn get_canvas_id(&self) -> CanvasId {
         self.canvas_id.clone()
     }
@@ -576,6 +821,46 @@ impl CanvasRenderingContext2D {
     fn set_origin_unclean(&self) {
         self.origin_clean.set(false)
     }
+
+    /// Resolves a `direction` of `inherit` against the canvas element and
+    /// tells the canvas thread about it, ahead of drawing or measuring text.
+    fn update_inherited_direction(&self) {
+        if self.state.borrow().direction != CanvasDirection::Inherit {
+            return;
+        }
+
+        // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+        let direction = match self.canvas {
+            Some(ref canvas) => {
+                let window = window_from_node(&**canvas);
+                let node = canvas.upcast::<Node>().to_trusted_node_address();
+                let property = PropertyId::Longhand(LonghandId::Direction);
+                match &*window.resolved_style_query(node, None, property) {
+                    "rtl" => Direction::Rtl,
+                    _ => Direction::Ltr,
+                }
+            },
+            None => Direction::Ltr,
+        };
+        self.send_canvas_2d_msg(Canvas2dMsg::SetDirection(direction));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#text-preparation-algorithm
+    fn draw_text(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>, stroke: bool) {
+        if !x.is_finite() || !y.is_finite() || max_width.map_or(false, |width| !width.is_finite()) {
+            return;
+        }
+
+        self.update_inherited_direction();
+        let text: String = text.into();
+        let msg = if stroke {
+            Canvas2dMsg::StrokeText(text, x, y, max_width)
+        } else {
+            Canvas2dMsg::FillText(text, x, y, max_width)
+        };
+        self.send_canvas_2d_msg(msg);
+        self.mark_as_dirty();
+    }
 }
 
 pub trait LayoutCanvasRenderingContext2DHelpers {
@@ -609,7 +894,7 @@ impl LayoutCanvasRenderingContext2DHelpers for LayoutDom<CanvasRenderingContext2
 impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-canvas
     fn Canvas(&self) -> DomRoot<HTMLCanvasElement> {
-        // This method is not called from a paint worklet rendering context,
+        // This method is not called from a paint worklet or offscreen rendering context,
         // so it's OK to panic if self.canvas is None.
         DomRoot::from_ref(self.canvas.as_ref().expect("No canvas."))
     }
@@ -680,16 +965,47 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.update_transform()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-gettransform
+    fn GetTransform(&self) -> DomRoot<DOMMatrix> {
+        let transform = self.state.borrow().transform.cast::<f64>();
+        let matrix = Transform3D::row_major(transform.m11, transform.m12, 0.0, 0.0,
+                                            transform.m21, transform.m22, 0.0, 0.0,
+                                            0.0, 0.0, 1.0, 0.0,
+                                            transform.m31, transform.m32, 0.0, 1.0);
+        DOMMatrix::new(&self.global(), true, matrix)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-settransform
-    fn SetTransform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+    fn SetTransform(&self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> ErrorResult {
         if !(a.is_finite() && b.is_finite() && c.is_finite() &&
              d.is_finite() && e.is_finite() && f.is_finite()) {
-            return;
+            return Ok(());
         }
 
         self.state.borrow_mut().transform =
             Transform2D::row_major(a as f32, b as f32, c as f32, d as f32, e as f32, f as f32);
-        self.update_transform()
+        self.update_transform();
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-settransform-matrix
+    fn SetTransform_(&self, transform: &DOMMatrixInit) -> ErrorResult {
+        // Step 1.
+        let (_, matrix) = dommatrixinit_to_matrix(transform)?;
+
+        // Step 2.
+        if ![matrix.m11, matrix.m12, matrix.m21, matrix.m22, matrix.m41, matrix.m42]
+            .iter().all(|value| value.is_finite()) {
+            return Ok(());
+        }
+
+        // Step 3.
+        self.state.borrow_mut().transform =
+            Transform2D::row_major(matrix.m11, matrix.m12,
+                                   matrix.m21, matrix.m22,
+                                   matrix.m41, matrix.m42).cast();
+        self.update_transform();
+        Ok(())
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-resettransform
@@ -772,34 +1088,81 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    fn Fill_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.send_canvas_2d_msg(Canvas2dMsg::FillPath(path.segments(), to_fill_rule(fill_rule)));
+        self.mark_as_dirty();
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
     fn Stroke(&self) {
         self.send_canvas_2d_msg(Canvas2dMsg::Stroke);
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    fn Stroke_(&self, path: &Path2D) {
+        self.send_canvas_2d_msg(Canvas2dMsg::StrokePath(path.segments()));
+        self.mark_as_dirty();
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
     fn Clip(&self, _: CanvasFillRule) {
         // TODO: Process fill rule
         self.send_canvas_2d_msg(Canvas2dMsg::Clip);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    fn Clip_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.send_canvas_2d_msg(Canvas2dMsg::ClipPath(path.segments(), to_fill_rule(fill_rule)));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
     fn IsPointInPath(&self, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
-        let fill_rule = match fill_rule {
-            CanvasFillRule::Nonzero => FillRule::Nonzero,
-            CanvasFillRule::Evenodd => FillRule::Evenodd,
-        };
         let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
-        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPath(x, y, fill_rule, sender));
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPath(x, y, to_fill_rule(fill_rule), sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    fn IsPointInPath_(&self, path: &Path2D, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
+        let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
+        let fill_rule = to_fill_rule(fill_rule);
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPathWithPath(path.segments(), x, y, fill_rule, sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinstroke
+    fn IsPointInStroke(&self, x: f64, y: f64) -> bool {
+        let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInStroke(x, y, sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinstroke
+    fn IsPointInStroke_(&self, path: &Path2D, x: f64, y: f64) -> bool {
+        let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInStrokeWithPath(path.segments(), x, y, sender));
         receiver.recv().unwrap()
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-filltext
     fn FillText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
-        let parsed_text: String = text.into();
-        self.send_canvas_2d_msg(Canvas2dMsg::FillText(parsed_text, x, y, max_width));
-        self.mark_as_dirty();
+        self.draw_text(text, x, y, max_width, false)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroketext
+    fn StrokeText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
+        self.draw_text(text, x, y, max_width, true)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    fn MeasureText(&self, text: DOMString) -> DomRoot<TextMetrics> {
+        self.update_inherited_direction();
+        let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::MeasureText(text.into(), sender));
+        let metrics = receiver.recv().unwrap();
+        TextMetrics::new(&self.global(), metrics)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
@@ -970,6 +1333,41 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.state.borrow_mut().image_smoothing_enabled = value;
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-imagesmoothingquality
+    fn ImageSmoothingQuality(&self) -> SmoothingQuality {
+        match self.state.borrow().image_smoothing_quality {
+            ImageSmoothingQuality::Low => SmoothingQuality::Low,
+            ImageSmoothingQuality::Medium => SmoothingQuality::Medium,
+            ImageSmoothingQuality::High => SmoothingQuality::High,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-imagesmoothingquality
+    fn SetImageSmoothingQuality(&self, value: SmoothingQuality) {
+        let quality = match value {
+            SmoothingQuality::Low => ImageSmoothingQuality::Low,
+            SmoothingQuality::Medium => ImageSmoothingQuality::Medium,
+            SmoothingQuality::High => ImageSmoothingQuality::High,
+        };
+        self.state.borrow_mut().image_smoothing_quality = quality;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetImageSmoothingQuality(quality))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-filter
+    fn Filter(&self) -> DOMString {
+        self.state.borrow().filter.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-filter
+    fn SetFilter(&self, value: DOMString) {
+        let filters = match self.parse_filter(&value) {
+            Some(filters) => filters,
+            None => return,
+        };
+        self.state.borrow_mut().filter = value;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetFilter(filters))
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-strokestyle
     fn StrokeStyle(&self) -> StringOrCanvasGradientOrCanvasPattern {
         match self.state.borrow().stroke_style {
@@ -1110,7 +1508,7 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
         let dest_rect = Rect::new(Point2D::new(sx.to_i32().unwrap(), sy.to_i32().unwrap()),
                                   Size2D::new(sw as i32, sh as i32));
-        let canvas_size = self.canvas.as_ref().map(|c| c.get_size()).unwrap_or(Size2D::zero());
+        let canvas_size = self.size.get();
         let canvas_size = Size2D::new(canvas_size.width as f64, canvas_size.height as f64);
         self.send_canvas_2d_msg(Canvas2dMsg::GetImageData(dest_rect, canvas_size, sender));
         let mut data = receiver.recv().unwrap();
@@ -1222,6 +1620,13 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
 
                 canvas.fetch_all_data().ok_or(Error::InvalidState)?
             }
+            CanvasImageSource::OffscreenCanvas(ref canvas) => {
+                canvas.fetch_all_data().ok_or(Error::InvalidState)?
+            }
+            CanvasImageSource::ImageBitmap(ref bitmap) => {
+                let (data, size) = bitmap.premultiplied_data().ok_or(Error::InvalidState)?;
+                (data, size.to_i32())
+            }
             CanvasImageSource::CSSStyleValue(ref value) => {
                 value.get_url(self.base_url.clone())
                     .and_then(|url| self.fetch_image_data(url))
@@ -1316,6 +1721,46 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.send_canvas_2d_msg(Canvas2dMsg::SetMiterLimit(limit as f32))
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-setlinedash
+    fn SetLineDash(&self, segments: Vec<f64>) {
+        // Step 1.
+        if segments.iter().any(|segment| !segment.is_finite() || *segment < 0.0) {
+            return;
+        }
+
+        // Step 2.
+        let mut segments = segments;
+        if segments.len() % 2 == 1 {
+            let copy = segments.clone();
+            segments.extend(copy);
+        }
+
+        // Step 3.
+        let dash = segments.iter().map(|segment| *segment as f32).collect();
+        self.state.borrow_mut().line_dash = segments;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetLineDash(dash))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-getlinedash
+    fn GetLineDash(&self) -> Vec<f64> {
+        self.state.borrow().line_dash.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset
+    fn LineDashOffset(&self) -> f64 {
+        self.state.borrow().line_dash_offset
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-linedashoffset
+    fn SetLineDashOffset(&self, offset: f64) {
+        if !offset.is_finite() {
+            return;
+        }
+
+        self.state.borrow_mut().line_dash_offset = offset;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetLineDashOffset(offset as f32))
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowoffsetx
     fn ShadowOffsetX(&self) -> f64 {
         self.state.borrow().shadow_offset_x
@@ -1372,6 +1817,100 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
             self.send_canvas_2d_msg(Canvas2dMsg::SetShadowColor(color))
         }
     }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn Font(&self) -> DOMString {
+        self.state.borrow().font.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn SetFont(&self, value: DOMString) {
+        // CSS-wide keywords are ignored.
+        match_ignore_ascii_case! { value.trim(),
+            "inherit" | "initial" | "unset" => return,
+            _ => {},
+        }
+
+        let font = match self.canvas {
+            Some(ref canvas) => {
+                let window = window_from_node(&**canvas);
+                window.resolved_font_style_query(canvas.upcast(), value.into()).map(|font| (*font).clone())
+            },
+            None => self.compute_font_without_element(&value),
+        };
+        let font = match font {
+            Some(font) => font,
+            None => return,
+        };
+        self.state.borrow_mut().font = serialize_font(&font);
+        self.send_canvas_2d_msg(Canvas2dMsg::SetFont(canvas_font_style(&font)))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn TextAlign(&self) -> CanvasTextAlign {
+        match self.state.borrow().text_align {
+            TextAlign::Start => CanvasTextAlign::Start,
+            TextAlign::End => CanvasTextAlign::End,
+            TextAlign::Left => CanvasTextAlign::Left,
+            TextAlign::Right => CanvasTextAlign::Right,
+            TextAlign::Center => CanvasTextAlign::Center,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn SetTextAlign(&self, value: CanvasTextAlign) {
+        let text_align = match value {
+            CanvasTextAlign::Start => TextAlign::Start,
+            CanvasTextAlign::End => TextAlign::End,
+            CanvasTextAlign::Left => TextAlign::Left,
+            CanvasTextAlign::Right => TextAlign::Right,
+            CanvasTextAlign::Center => TextAlign::Center,
+        };
+        self.state.borrow_mut().text_align = text_align;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTextAlign(text_align))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn TextBaseline(&self) -> CanvasTextBaseline {
+        match self.state.borrow().text_baseline {
+            TextBaseline::Top => CanvasTextBaseline::Top,
+            TextBaseline::Hanging => CanvasTextBaseline::Hanging,
+            TextBaseline::Middle => CanvasTextBaseline::Middle,
+            TextBaseline::Alphabetic => CanvasTextBaseline::Alphabetic,
+            TextBaseline::Ideographic => CanvasTextBaseline::Ideographic,
+            TextBaseline::Bottom => CanvasTextBaseline::Bottom,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn SetTextBaseline(&self, value: CanvasTextBaseline) {
+        let text_baseline = match value {
+            CanvasTextBaseline::Top => TextBaseline::Top,
+            CanvasTextBaseline::Hanging => TextBaseline::Hanging,
+            CanvasTextBaseline::Middle => TextBaseline::Middle,
+            CanvasTextBaseline::Alphabetic => TextBaseline::Alphabetic,
+            CanvasTextBaseline::Ideographic => TextBaseline::Ideographic,
+            CanvasTextBaseline::Bottom => TextBaseline::Bottom,
+        };
+        self.state.borrow_mut().text_baseline = text_baseline;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTextBaseline(text_baseline))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn Direction(&self) -> CanvasDirection {
+        self.state.borrow().direction
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn SetDirection(&self, value: CanvasDirection) {
+        self.state.borrow_mut().direction = value;
+        match value {
+            CanvasDirection::Ltr => self.send_canvas_2d_msg(Canvas2dMsg::SetDirection(Direction::Ltr)),
+            CanvasDirection::Rtl => self.send_canvas_2d_msg(Canvas2dMsg::SetDirection(Direction::Rtl)),
+            // Resolved whenever text is drawn or measured.
+            CanvasDirection::Inherit => {},
+        }
+    }
 }
 
 impl Drop for CanvasRenderingContext2D {
@@ -1397,6 +1936,59 @@ pub fn parse_color(string: &str) -> Result<RGBA, ()> {
     }
 }
 
+fn to_fill_rule(fill_rule: CanvasFillRule) -> FillRule {
+    match fill_rule {
+        CanvasFillRule::Nonzero => FillRule::Nonzero,
+        CanvasFillRule::Evenodd => FillRule::Evenodd,
+    }
+}
+
+/// Serializes a computed font the way the `font` attribute returns it: as a
+/// `font` shorthand with the size in pixels and no line height.
+/// <https://html.spec.whatwg.org/multipage/#dom-context-2d-font>
+fn serialize_font(font: &Font) -> DOMString {
+    let mut result = String::new();
+    if font.font_style != FontStyle::normal() {
+        result.push_str(&font.font_style.to_css_string());
+        result.push(' ');
+    }
+    if font.font_variant_caps == FontVariantCaps::SmallCaps {
+        result.push_str("small-caps ");
+    }
+    if font.font_weight != FontWeight::normal() {
+        result.push_str(&font.font_weight.to_css_string());
+        result.push(' ');
+    }
+    if font.font_stretch != FontStretch::hundred() {
+        if let Some(keyword) = FontStretchKeyword::from_percentage(font.font_stretch.value()) {
+            result.push_str(&keyword.to_css_string());
+            result.push(' ');
+        }
+    }
+    result.push_str(&format!("{}px ", font.font_size.size().to_f64_px()));
+    result.push_str(&font.font_family.to_css_string());
+    DOMString::from(result)
+}
+
+/// Reduces a computed font to what the canvas thread needs to draw with it.
+fn canvas_font_style(font: &Font) -> CanvasFontStyle {
+    CanvasFontStyle {
+        families: font.font_family.0.iter().map(|family| match *family {
+            SingleFontFamily::FamilyName(ref name) => CanvasFontFamily::Named(name.name.to_string()),
+            SingleFontFamily::Generic(ref name) => CanvasFontFamily::Generic(name.to_string()),
+        }).collect(),
+        size: font.font_size.size(),
+        style: match font.font_style {
+            GenericFontStyle::Normal => CanvasFontSlant::Normal,
+            GenericFontStyle::Italic => CanvasFontSlant::Italic,
+            GenericFontStyle::Oblique(ref angle) => CanvasFontSlant::Oblique(angle.0.degrees()),
+        },
+        weight: font.font_weight.0,
+        stretch: font.font_stretch.value() * 100.,
+        small_caps: font.font_variant_caps == FontVariantCaps::SmallCaps,
+    }
+}
+
 // Used by drawImage to determine if a source or destination rectangle is valid
 // Origin coordinates and size cannot be negative. Size has to be greater than zero
 fn is_rect_valid(rect: Rect<f64>) -> bool {
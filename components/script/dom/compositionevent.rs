@@ -5,9 +5,11 @@
 use dom::bindings::codegen::Bindings::CompositionEventBinding::{self, CompositionEventMethods};
 use dom::bindings::codegen::Bindings::UIEventBinding::UIEventBinding::UIEventMethods;
 use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::reflect_dom_object;
 use dom::bindings::root::{DomRoot, RootedReference};
 use dom::bindings::str::DOMString;
+use dom::event::Event;
 use dom::uievent::UIEvent;
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -47,6 +49,7 @@ impl CompositionEvent {
                                     init.parent.view.r(),
                                     init.parent.detail,
                                     init.data.clone());
+        event.upcast::<Event>().set_composed(init.parent.parent.composed);
         Ok(event)
     }
 }
@@ -60,6 +60,7 @@ use dom::htmlprogresselement::HTMLProgressElement;
 use dom::htmlquoteelement::HTMLQuoteElement;
 use dom::htmlscriptelement::HTMLScriptElement;
 use dom::htmlselectelement::HTMLSelectElement;
+use dom::htmlslotelement::HTMLSlotElement;
 use dom::htmlsourceelement::HTMLSourceElement;
 use dom::htmlspanelement::HTMLSpanElement;
 use dom::htmlstyleelement::HTMLStyleElement;
@@ -188,6 +189,12 @@ fn create_html_element(name: QualName,
         result.set_custom_element_state(CustomElementState::Undefined);
     }
 
+    // Step 7.2 sets the is value, so that a later definition of a customized
+    // built-in element can find and upgrade this element.
+    if let Some(is) = is {
+        result.set_is(is);
+    }
+
     result
 }
 
@@ -323,6 +330,7 @@ pub fn create_native_html_element(
         local_name!("script")     => make!(HTMLScriptElement, creator),
         local_name!("section")    => make!(HTMLElement),
         local_name!("select")     => make!(HTMLSelectElement),
+        local_name!("slot")       => make!(HTMLSlotElement),
         local_name!("small")      => make!(HTMLElement),
         local_name!("source")     => make!(HTMLSourceElement),
         // https://html.spec.whatwg.org/multipage/#other-elements,-attributes-and-apis:spacer
@@ -0,0 +1,79 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::CryptoBinding::{self, CryptoMethods};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::globalscope::GlobalScope;
+use dom::subtlecrypto::SubtleCrypto;
+use dom_struct::dom_struct;
+use js::jsapi::{JSContext, JSObject, Type};
+use js::rust::CustomAutoRooterGuard;
+use js::typedarray::ArrayBufferView;
+use servo_rand::{self, Rng};
+use std::ptr::NonNull;
+
+/// The largest number of bytes `getRandomValues()` fills in one call.
+/// <https://w3c.github.io/webcrypto/#Crypto-method-getRandomValues>
+const MAX_RANDOM_VALUES_LENGTH: usize = 65536;
+
+// https://w3c.github.io/webcrypto/#crypto-interface
+#[dom_struct]
+pub struct Crypto {
+    reflector_: Reflector,
+    subtle: MutNullableDom<SubtleCrypto>,
+}
+
+impl Crypto {
+    fn new_inherited() -> Crypto {
+        Crypto {
+            reflector_: Reflector::new(),
+            subtle: Default::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<Crypto> {
+        reflect_dom_object(Box::new(Crypto::new_inherited()),
+                           global,
+                           CryptoBinding::Wrap)
+    }
+}
+
+impl CryptoMethods for Crypto {
+    // https://w3c.github.io/webcrypto/#dom-crypto-subtle
+    fn Subtle(&self) -> DomRoot<SubtleCrypto> {
+        self.subtle.or_init(|| SubtleCrypto::new(&self.global()))
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dom-crypto-getrandomvalues
+    unsafe fn GetRandomValues(&self,
+                              _cx: *mut JSContext,
+                              mut array: CustomAutoRooterGuard<ArrayBufferView>)
+                              -> Fallible<NonNull<JSObject>> {
+        // Step 1.
+        match array.get_array_type() {
+            Type::Int8 | Type::Uint8 | Type::Uint8Clamped |
+            Type::Int16 | Type::Uint16 | Type::Int32 | Type::Uint32 => {},
+            _ => return Err(Error::TypeMismatch),
+        }
+
+        // Step 2.
+        let bytes = array.as_mut_slice();
+        if bytes.len() > MAX_RANDOM_VALUES_LENGTH {
+            return Err(Error::QuotaExceeded);
+        }
+
+        // Steps 3-4.
+        servo_rand::thread_rng().fill_bytes(bytes);
+        Ok(NonNull::new_unchecked(*array.underlying_object()))
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-crypto-randomuuid
+    fn RandomUUID(&self) -> DOMString {
+        DOMString::from(servo_rand::random_uuid().hyphenated().to_string())
+    }
+}
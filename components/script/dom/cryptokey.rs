@@ -0,0 +1,231 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::CryptoKeyBinding::{self, CryptoKeyMethods, KeyType, KeyUsage};
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::bindings::utils::set_dictionary_property;
+use dom::globalscope::GlobalScope;
+use dom::subtlecrypto::{AlgorithmName, DigestAlgorithm, NamedCurve};
+use dom_struct::dom_struct;
+use js::jsapi::{JSContext, JSObject, JS_NewObject};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::HandleObject;
+use js::typedarray::{CreateWith, Uint8Array};
+use openssl::pkey::PKey;
+use std::ptr::{self, NonNull};
+use std::sync::Arc;
+
+/// The key material backing a `CryptoKey`.
+/// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-handle>
+#[derive(JSTraceable)]
+pub enum Handle {
+    /// The raw bytes of an AES, HMAC, PBKDF2 or HKDF key.
+    Secret(Vec<u8>),
+    /// The public half of an RSA or elliptic curve key pair.
+    Public(PKey),
+    /// An RSA or elliptic curve private key, which also carries its public half.
+    Private(PKey),
+}
+
+impl Handle {
+    pub fn key_type(&self) -> KeyType {
+        match *self {
+            Handle::Secret(_) => KeyType::Secret,
+            Handle::Public(_) => KeyType::Public,
+            Handle::Private(_) => KeyType::Private,
+        }
+    }
+}
+
+/// The `[[algorithm]]` internal slot of a key, which is exposed to script as
+/// one of the `KeyAlgorithm` dictionaries.
+/// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-algorithm>
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+pub enum KeyAlgorithm {
+    /// <https://w3c.github.io/webcrypto/#dfn-KeyAlgorithm>
+    Plain(AlgorithmName),
+    /// <https://w3c.github.io/webcrypto/#dfn-AesKeyAlgorithm>
+    Aes {
+        name: AlgorithmName,
+        length: u16,
+    },
+    /// <https://w3c.github.io/webcrypto/#dfn-HmacKeyAlgorithm>
+    Hmac {
+        hash: DigestAlgorithm,
+        length: u32,
+    },
+    /// <https://w3c.github.io/webcrypto/#dfn-EcKeyAlgorithm>
+    Ec {
+        name: AlgorithmName,
+        named_curve: NamedCurve,
+    },
+    /// <https://w3c.github.io/webcrypto/#dfn-RsaHashedKeyAlgorithm>
+    RsaHashed {
+        name: AlgorithmName,
+        modulus_length: u32,
+        public_exponent: Vec<u8>,
+        hash: DigestAlgorithm,
+    },
+}
+
+impl KeyAlgorithm {
+    pub fn name(&self) -> AlgorithmName {
+        match *self {
+            KeyAlgorithm::Plain(name) => name,
+            KeyAlgorithm::Aes { name, .. } => name,
+            KeyAlgorithm::Hmac { .. } => AlgorithmName::Hmac,
+            KeyAlgorithm::Ec { name, .. } => name,
+            KeyAlgorithm::RsaHashed { name, .. } => name,
+        }
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn set_member<T: ToJSValConvertible>(cx: *mut JSContext,
+                                                object: HandleObject,
+                                                member: &str,
+                                                value: &T) {
+        rooted!(in(cx) let mut js_value = UndefinedValue());
+        value.to_jsval(cx, js_value.handle_mut());
+        set_dictionary_property(cx, object, member, js_value.handle())
+            .expect("Failed to set key algorithm member");
+    }
+
+    /// Creates a new object holding the `{ name }` dictionary of `name`.
+    #[allow(unsafe_code)]
+    unsafe fn named_object(cx: *mut JSContext, name: &str) -> *mut JSObject {
+        rooted!(in(cx) let object = JS_NewObject(cx, ptr::null()));
+        KeyAlgorithm::set_member(cx, object.handle(), "name", &DOMString::from(name));
+        object.get()
+    }
+
+    /// Creates the script-visible dictionary for this algorithm.
+    #[allow(unsafe_code)]
+    pub unsafe fn to_object(&self, cx: *mut JSContext) -> NonNull<JSObject> {
+        rooted!(in(cx) let object = KeyAlgorithm::named_object(cx, self.name().as_str()));
+        match *self {
+            KeyAlgorithm::Plain(_) => {},
+            KeyAlgorithm::Aes { length, .. } => {
+                KeyAlgorithm::set_member(cx, object.handle(), "length", &length);
+            },
+            KeyAlgorithm::Hmac { hash, length } => {
+                rooted!(in(cx) let hash = KeyAlgorithm::named_object(cx, hash.as_str()));
+                KeyAlgorithm::set_member(cx, object.handle(), "hash", &ObjectValue(hash.get()));
+                KeyAlgorithm::set_member(cx, object.handle(), "length", &length);
+            },
+            KeyAlgorithm::Ec { named_curve, .. } => {
+                KeyAlgorithm::set_member(cx, object.handle(), "namedCurve",
+                                         &DOMString::from(named_curve.as_str()));
+            },
+            KeyAlgorithm::RsaHashed { modulus_length, ref public_exponent, hash, .. } => {
+                KeyAlgorithm::set_member(cx, object.handle(), "modulusLength", &modulus_length);
+                rooted!(in(cx) let mut exponent = ptr::null_mut::<JSObject>());
+                assert!(Uint8Array::create(cx, CreateWith::Slice(public_exponent), exponent.handle_mut()).is_ok());
+                KeyAlgorithm::set_member(cx, object.handle(), "publicExponent", &ObjectValue(exponent.get()));
+                rooted!(in(cx) let hash = KeyAlgorithm::named_object(cx, hash.as_str()));
+                KeyAlgorithm::set_member(cx, object.handle(), "hash", &ObjectValue(hash.get()));
+            },
+        }
+        NonNull::new_unchecked(object.get())
+    }
+}
+
+// https://w3c.github.io/webcrypto/#cryptokey-interface
+#[dom_struct]
+pub struct CryptoKey {
+    reflector_: Reflector,
+    key_type: KeyType,
+    extractable: bool,
+    algorithm: KeyAlgorithm,
+    usages: Vec<KeyUsage>,
+    #[ignore_malloc_size_of = "Defined in openssl"]
+    handle: Arc<Handle>,
+}
+
+impl CryptoKey {
+    fn new_inherited(key_type: KeyType,
+                     extractable: bool,
+                     algorithm: KeyAlgorithm,
+                     usages: Vec<KeyUsage>,
+                     handle: Handle)
+                     -> CryptoKey {
+        CryptoKey {
+            reflector_: Reflector::new(),
+            key_type: key_type,
+            extractable: extractable,
+            algorithm: algorithm,
+            usages: usages,
+            handle: Arc::new(handle),
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               key_type: KeyType,
+               extractable: bool,
+               algorithm: KeyAlgorithm,
+               usages: Vec<KeyUsage>,
+               handle: Handle)
+               -> DomRoot<CryptoKey> {
+        reflect_dom_object(Box::new(CryptoKey::new_inherited(key_type, extractable, algorithm, usages, handle)),
+                           global,
+                           CryptoKeyBinding::Wrap)
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    pub fn extractable(&self) -> bool {
+        self.extractable
+    }
+
+    pub fn algorithm(&self) -> &KeyAlgorithm {
+        &self.algorithm
+    }
+
+    pub fn usages(&self) -> &[KeyUsage] {
+        &self.usages
+    }
+
+    pub fn has_usage(&self, usage: KeyUsage) -> bool {
+        self.usages.contains(&usage)
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// The key material, shared with the operations running in parallel.
+    pub fn shared_handle(&self) -> Arc<Handle> {
+        self.handle.clone()
+    }
+}
+
+impl CryptoKeyMethods for CryptoKey {
+    // https://w3c.github.io/webcrypto/#dom-cryptokey-type
+    fn Type(&self) -> KeyType {
+        self.key_type
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-cryptokey-extractable
+    fn Extractable(&self) -> bool {
+        self.extractable
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dom-cryptokey-algorithm
+    unsafe fn Algorithm(&self, cx: *mut JSContext) -> NonNull<JSObject> {
+        self.algorithm.to_object(cx)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dom-cryptokey-usages
+    unsafe fn Usages(&self, cx: *mut JSContext) -> NonNull<JSObject> {
+        rooted!(in(cx) let mut usages = UndefinedValue());
+        self.usages.to_jsval(cx, usages.handle_mut());
+        NonNull::new_unchecked(usages.to_object())
+    }
+}
@@ -22,6 +22,7 @@ use dom::domexception::{DOMErrorName, DOMException};
 use dom::element::{CustomElementState, Element};
 use dom::globalscope::GlobalScope;
 use dom::htmlelement::HTMLElement;
+use dom::htmlformelement::{FormControl, HTMLFormElement};
 use dom::node::{document_from_node, Node, window_from_node};
 use dom::promise::Promise;
 use dom::window::Window;
@@ -31,7 +32,7 @@ use js::conversions::ToJSValConvertible;
 use js::glue::UnwrapObject;
 use js::jsapi::{Heap, IsCallable, IsConstructor, HandleValueArray};
 use js::jsapi::{JSAutoCompartment, JSContext, JSObject};
-use js::jsval::{JSVal, NullValue, ObjectValue, UndefinedValue};
+use js::jsval::{BooleanValue, JSVal, NullValue, ObjectValue, UndefinedValue};
 use js::rust::{HandleObject, HandleValue, MutableHandleValue};
 use js::rust::wrappers::{JS_GetProperty, Construct1, JS_SameValue};
 use microtask::Microtask;
@@ -134,9 +135,28 @@ impl CustomElementRegistry {
             disconnected_callback: get_callback(cx, prototype, b"disconnectedCallback\0")?,
             adopted_callback: get_callback(cx, prototype, b"adoptedCallback\0")?,
             attribute_changed_callback: get_callback(cx, prototype, b"attributeChangedCallback\0")?,
+            form_associated_callback: None,
+            form_reset_callback: None,
+            form_disabled_callback: None,
         })
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#dom-customelementregistry-define>
+    /// Step 10.10
+    #[allow(unsafe_code)]
+    unsafe fn add_form_associated_callbacks(&self,
+                                            prototype: HandleObject,
+                                            callbacks: &mut LifecycleCallbacks)
+                                            -> ErrorResult {
+        let cx = self.window.get_cx();
+
+        callbacks.form_associated_callback = get_callback(cx, prototype, b"formAssociatedCallback\0")?;
+        callbacks.form_reset_callback = get_callback(cx, prototype, b"formResetCallback\0")?;
+        callbacks.form_disabled_callback = get_callback(cx, prototype, b"formDisabledCallback\0")?;
+
+        Ok(())
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#dom-customelementregistry-define>
     /// Step 10.6
     #[allow(unsafe_code)]
@@ -163,6 +183,60 @@ impl CustomElementRegistry {
             _ => Err(Error::JSFailed),
         }
     }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-customelementregistry-define>
+    /// Step 10.7
+    #[allow(unsafe_code)]
+    fn get_disabled_features(&self, constructor: HandleObject) -> Fallible<Vec<DOMString>> {
+        let cx = self.window.get_cx();
+        rooted!(in(cx) let mut disabled_features = UndefinedValue());
+        if unsafe { !JS_GetProperty(cx,
+                                    constructor,
+                                    b"disabledFeatures\0".as_ptr() as *const _,
+                                    disabled_features.handle_mut()) } {
+            return Err(Error::JSFailed);
+        }
+
+        if disabled_features.is_undefined() {
+            return Ok(Vec::new());
+        }
+
+        let conversion = unsafe {
+            FromJSValConvertible::from_jsval(cx, disabled_features.handle(), StringificationBehavior::Default)
+        };
+        match conversion {
+            Ok(ConversionResult::Success(features)) => Ok(features),
+            Ok(ConversionResult::Failure(error)) => Err(Error::Type(error.into())),
+            _ => Err(Error::JSFailed),
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-customelementregistry-define>
+    /// Step 10.8
+    #[allow(unsafe_code)]
+    fn get_form_associated_value(&self, constructor: HandleObject) -> Fallible<bool> {
+        let cx = self.window.get_cx();
+        rooted!(in(cx) let mut form_associated_value = UndefinedValue());
+        if unsafe { !JS_GetProperty(cx,
+                                    constructor,
+                                    b"formAssociated\0".as_ptr() as *const _,
+                                    form_associated_value.handle_mut()) } {
+            return Err(Error::JSFailed);
+        }
+
+        if form_associated_value.is_undefined() {
+            return Ok(false);
+        }
+
+        let conversion = unsafe {
+            FromJSValConvertible::from_jsval(cx, form_associated_value.handle(), ())
+        };
+        match conversion {
+            Ok(ConversionResult::Success(form_associated)) => Ok(form_associated),
+            Ok(ConversionResult::Failure(error)) => Err(Error::Type(error.into())),
+            _ => Err(Error::JSFailed),
+        }
+    }
 }
 
 /// <https://html.spec.whatwg.org/multipage/#dom-customelementregistry-define>
@@ -272,7 +346,7 @@ impl CustomElementRegistryMethods for CustomElementRegistry {
 
         // Steps 10.3 - 10.4
         rooted!(in(cx) let proto_object = prototype.to_object());
-        let callbacks = {
+        let mut callbacks = {
             let _ac = JSAutoCompartment::new(cx, proto_object.get());
             match unsafe { self.get_callbacks(proto_object.handle()) } {
                 Ok(callbacks) => callbacks,
@@ -297,6 +371,41 @@ impl CustomElementRegistryMethods for CustomElementRegistry {
             Vec::new()
         };
 
+        // Step 10.7
+        let disabled_features = {
+            let _ac = JSAutoCompartment::new(cx, constructor.get());
+            match self.get_disabled_features(constructor.handle()) {
+                Ok(features) => features,
+                Err(error) => {
+                    self.element_definition_is_running.set(false);
+                    return Err(error);
+                },
+            }
+        };
+        let disable_internals = disabled_features.iter().any(|feature| *feature == "internals");
+        let disable_shadow = disabled_features.iter().any(|feature| *feature == "shadow");
+
+        // Step 10.8
+        let form_associated = {
+            let _ac = JSAutoCompartment::new(cx, constructor.get());
+            match self.get_form_associated_value(constructor.handle()) {
+                Ok(form_associated) => form_associated,
+                Err(error) => {
+                    self.element_definition_is_running.set(false);
+                    return Err(error);
+                },
+            }
+        };
+
+        // Step 10.9
+        if form_associated {
+            let _ac = JSAutoCompartment::new(cx, proto_object.get());
+            if let Err(error) = unsafe { self.add_form_associated_callbacks(proto_object.handle(), &mut callbacks) } {
+                self.element_definition_is_running.set(false);
+                return Err(error);
+            }
+        }
+
         self.element_definition_is_running.set(false);
 
         // Step 11
@@ -304,7 +413,10 @@ impl CustomElementRegistryMethods for CustomElementRegistry {
                                                               local_name.clone(),
                                                               constructor_,
                                                               observed_attributes,
-                                                              callbacks));
+                                                              callbacks,
+                                                              form_associated,
+                                                              disable_internals,
+                                                              disable_shadow));
 
         // Step 12
         self.definitions.borrow_mut().insert(name.clone(), definition.clone());
@@ -391,6 +503,15 @@ pub struct LifecycleCallbacks {
 
     #[ignore_malloc_size_of = "Rc"]
     attribute_changed_callback: Option<Rc<Function>>,
+
+    #[ignore_malloc_size_of = "Rc"]
+    form_associated_callback: Option<Rc<Function>>,
+
+    #[ignore_malloc_size_of = "Rc"]
+    form_reset_callback: Option<Rc<Function>>,
+
+    #[ignore_malloc_size_of = "Rc"]
+    form_disabled_callback: Option<Rc<Function>>,
 }
 
 #[derive(Clone, JSTraceable, MallocSizeOf)]
@@ -414,6 +535,15 @@ pub struct CustomElementDefinition {
     pub callbacks: LifecycleCallbacks,
 
     pub construction_stack: DomRefCell<Vec<ConstructionStackEntry>>,
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-custom-element-definition-form-associated>
+    pub form_associated: bool,
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-custom-element-definition-disable-internals>
+    pub disable_internals: bool,
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-custom-element-definition-disable-shadow>
+    pub disable_shadow: bool,
 }
 
 impl CustomElementDefinition {
@@ -421,7 +551,10 @@ impl CustomElementDefinition {
            local_name: LocalName,
            constructor: Rc<CustomElementConstructor>,
            observed_attributes: Vec<DOMString>,
-           callbacks: LifecycleCallbacks)
+           callbacks: LifecycleCallbacks,
+           form_associated: bool,
+           disable_internals: bool,
+           disable_shadow: bool)
            -> CustomElementDefinition {
         CustomElementDefinition {
             name: name,
@@ -430,6 +563,9 @@ impl CustomElementDefinition {
             observed_attributes: observed_attributes,
             callbacks: callbacks,
             construction_stack: Default::default(),
+            form_associated: form_associated,
+            disable_internals: disable_internals,
+            disable_shadow: disable_shadow,
         }
     }
 
@@ -543,6 +679,15 @@ pub fn upgrade_element(definition: Rc<CustomElementDefinition>, element: &Elemen
 
     // Step 9
     element.set_custom_element_definition(definition);
+
+    // Step 10
+    // Resetting the form owner enqueues the formAssociatedCallback reaction
+    // if the element ends up associated with a form.
+    if let Some(html_element) = element.downcast::<HTMLElement>() {
+        if html_element.is_form_associated_custom_element() {
+            html_element.reset_form_owner();
+        }
+    }
 }
 
 /// <https://html.spec.whatwg.org/multipage/#concept-upgrade-an-element>
@@ -624,6 +769,9 @@ pub enum CallbackReaction {
     Disconnected,
     Adopted(DomRoot<Document>, DomRoot<Document>),
     AttributeChanged(LocalName, Option<DOMString>, Option<DOMString>, Namespace),
+    FormAssociated(Option<DomRoot<HTMLFormElement>>),
+    FormDisabled(bool),
+    FormReset,
 }
 
 /// <https://html.spec.whatwg.org/multipage/#processing-the-backup-element-queue>
@@ -758,6 +906,20 @@ impl CustomElementReactionStack {
 
                 (definition.callbacks.attribute_changed_callback.clone(), args)
             },
+            CallbackReaction::FormAssociated(form) => {
+                let args = vec![Heap::default()];
+                match form {
+                    Some(form) => args[0].set(ObjectValue(form.reflector().get_jsobject().get())),
+                    None => args[0].set(NullValue()),
+                }
+                (definition.callbacks.form_associated_callback.clone(), args)
+            },
+            CallbackReaction::FormDisabled(disabled) => {
+                let args = vec![Heap::default()];
+                args[0].set(BooleanValue(disabled));
+                (definition.callbacks.form_disabled_callback.clone(), args)
+            },
+            CallbackReaction::FormReset => (definition.callbacks.form_reset_callback.clone(), Vec::new()),
         };
 
         // Step 3
@@ -56,11 +56,13 @@ impl CustomEvent {
                        type_: DOMString,
                        init: RootedTraceableBox<CustomEventBinding::CustomEventInit>)
                        -> Fallible<DomRoot<CustomEvent>> {
-        Ok(CustomEvent::new(global,
-                            Atom::from(type_),
-                            init.parent.bubbles,
-                            init.parent.cancelable,
-                            init.detail.handle()))
+        let event = CustomEvent::new(global,
+                                     Atom::from(type_),
+                                     init.parent.bubbles,
+                                     init.parent.cancelable,
+                                     init.detail.handle());
+        event.upcast::<Event>().set_composed(init.parent.composed);
+        Ok(event)
     }
 
     fn init_custom_event(&self,
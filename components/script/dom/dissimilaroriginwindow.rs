@@ -4,6 +4,7 @@
 
 use dom::bindings::codegen::Bindings::DissimilarOriginWindowBinding;
 use dom::bindings::codegen::Bindings::DissimilarOriginWindowBinding::DissimilarOriginWindowMethods;
+use dom::bindings::codegen::Bindings::MessagePortBinding::Transferable;
 use dom::bindings::error::{Error, ErrorResult};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
@@ -18,7 +19,7 @@ use js::jsapi::JSContext;
 use js::jsval::{JSVal, UndefinedValue};
 use js::rust::HandleValue;
 use msg::constellation_msg::PipelineId;
-use script_traits::ScriptMsg;
+use script_traits::{ScriptMsg, TransferredPort};
 use servo_url::ImmutableOrigin;
 use servo_url::MutableOrigin;
 use servo_url::ServoUrl;
@@ -142,7 +143,12 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
 
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage
-    unsafe fn PostMessage(&self, cx: *mut JSContext, message: HandleValue, origin: DOMString) -> ErrorResult {
+    unsafe fn PostMessage(&self,
+                          cx: *mut JSContext,
+                          message: HandleValue,
+                          origin: DOMString,
+                          transfer: Option<Vec<Transferable>>)
+                          -> ErrorResult {
         // Step 3-5.
         let origin = match &origin[..] {
             "*" => None,
@@ -157,11 +163,11 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
         };
 
         // Step 1-2, 6-8.
-        // TODO(#12717): Should implement the `transfer` argument.
-        let data = StructuredCloneData::write(cx, message)?;
+        let transfer = transfer.unwrap_or_default();
+        let (data, ports) = StructuredCloneData::write_with_transfer(cx, message, &transfer)?;
 
         // Step 9.
-        self.post_message(origin, data);
+        self.post_message(origin, data, ports);
         Ok(())
     }
 
@@ -195,14 +201,18 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
 }
 
 impl DissimilarOriginWindow {
-    pub fn post_message(&self, origin: Option<ImmutableOrigin>, data: StructuredCloneData) {
+    pub fn post_message(&self,
+                        origin: Option<ImmutableOrigin>,
+                        data: StructuredCloneData,
+                        ports: Vec<TransferredPort>) {
         let incumbent = match GlobalScope::incumbent() {
             None => return warn!("postMessage called with no incumbent global"),
             Some(incumbent) => incumbent,
         };
         let msg = ScriptMsg::PostMessage(self.window_proxy.browsing_context_id(),
                                                 origin,
-                                                data.move_to_arraybuffer());
+                                                data.move_to_arraybuffer(),
+                                                ports);
         let _ = incumbent.script_to_constellation_chan().send(msg);
     }
 }
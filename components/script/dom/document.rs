@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use clipboard_provider::ClipboardProvider;
 use cookie_rs;
 use devtools_traits::ScriptToDevtoolsControlMsg;
 use document_loader::{DocumentLoader, LoadType};
@@ -10,15 +11,17 @@ use dom::attr::Attr;
 use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::DocumentBinding;
-use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState, ElementCreationOptions};
+use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState, DocumentVisibilityState};
+use dom::bindings::codegen::Bindings::DocumentBinding::ElementCreationOptions;
 use dom::bindings::codegen::Bindings::HTMLIFrameElementBinding::HTMLIFrameElementBinding::HTMLIFrameElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::codegen::Bindings::NodeFilterBinding::NodeFilter;
 use dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
 use dom::bindings::codegen::Bindings::TouchBinding::TouchMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::{FrameRequestCallback, ScrollBehavior, WindowMethods};
+use dom::bindings::codegen::Bindings::XPathNSResolverBinding::XPathNSResolver;
 use dom::bindings::codegen::UnionTypes::NodeOrString;
-use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::error::{Error, ErrorInfo, ErrorResult, Fallible};
 use dom::bindings::inheritance::{Castable, ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use dom::bindings::num::Finite;
 use dom::bindings::refcounted::{Trusted, TrustedPromise};
@@ -27,6 +30,7 @@ use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom, RootedReferen
 use dom::bindings::str::{DOMString, USVString};
 use dom::bindings::xmlname::{namespace_from_domstring, validate_and_extract, xml_name_type};
 use dom::bindings::xmlname::XMLName::InvalidXMLName;
+use dom::characterdata::CharacterData;
 use dom::closeevent::CloseEvent;
 use dom::comment::Comment;
 use dom::cssstylesheet::CSSStyleSheet;
@@ -35,6 +39,7 @@ use dom::customevent::CustomEvent;
 use dom::documentfragment::DocumentFragment;
 use dom::documenttype::DocumentType;
 use dom::domimplementation::DOMImplementation;
+use dom::editing::{self, EditingHistory};
 use dom::element::{Element, ElementCreator, ElementPerformFullscreenEnter, ElementPerformFullscreenExit};
 use dom::element::CustomElementCreationMode;
 use dom::errorevent::ErrorEvent;
@@ -55,9 +60,12 @@ use dom::htmlheadelement::HTMLHeadElement;
 use dom::htmlhtmlelement::HTMLHtmlElement;
 use dom::htmliframeelement::HTMLIFrameElement;
 use dom::htmlimageelement::HTMLImageElement;
+use dom::htmlinputelement::HTMLInputElement;
 use dom::htmlmetaelement::HTMLMetaElement;
 use dom::htmlscriptelement::{HTMLScriptElement, ScriptResult};
+use dom::htmltextareaelement::HTMLTextAreaElement;
 use dom::htmltitleelement::HTMLTitleElement;
+use dom::intersectionobserver::IntersectionObserver;
 use dom::keyboardevent::KeyboardEvent;
 use dom::location::Location;
 use dom::messageevent::MessageEvent;
@@ -68,13 +76,19 @@ use dom::node::VecPreOrderInsertionHelper;
 use dom::nodeiterator::NodeIterator;
 use dom::nodelist::NodeList;
 use dom::pagetransitionevent::PageTransitionEvent;
+use dom::performanceentry::PerformanceEntry;
+use dom::performancenavigationtiming::PerformanceNavigationTiming;
 use dom::popstateevent::PopStateEvent;
 use dom::processinginstruction::ProcessingInstruction;
 use dom::promise::Promise;
 use dom::range::Range;
+use dom::resizeobserver::ResizeObserver;
+use dom::selection::Selection;
 use dom::servoparser::ServoParser;
+use dom::shadowroot::ShadowRoot;
 use dom::storageevent::StorageEvent;
 use dom::stylesheetlist::StyleSheetList;
+use dom::svgsvgelement::SVGSVGElement;
 use dom::text::Text;
 use dom::touch::Touch;
 use dom::touchevent::TouchEvent;
@@ -84,6 +98,9 @@ use dom::uievent::UIEvent;
 use dom::virtualmethods::vtable_for;
 use dom::window::{ReflowReason, Window};
 use dom::windowproxy::WindowProxy;
+use dom::xpathevaluator::XPathEvaluator;
+use dom::xpathexpression::XPathExpression;
+use dom::xpathresult::XPathResult;
 use dom_struct::dom_struct;
 use embedder_traits::EmbedderMsg;
 use encoding_rs::{Encoding, UTF_8};
@@ -95,10 +112,11 @@ use hyper_serde::Serde;
 use ipc_channel::ipc::{IpcSender};
 use js::jsapi::{JSContext, JSObject, JSRuntime};
 use js::jsapi::JS_GetRuntime;
+use js::jsval::NullValue;
 use metrics::{InteractiveFlag, InteractiveMetrics, InteractiveWindow, ProfilerMetadataFactory, ProgressiveWebMetric};
 use mime::{Mime, TopLevel, SubLevel};
 use msg::constellation_msg::{BrowsingContextId, Key, KeyModifiers, KeyState};
-use net_traits::{FetchResponseMsg, IpcSend, ReferrerPolicy};
+use net_traits::{FetchResponseMsg, IpcSend, ReferrerPolicy, ResourceFetchTiming};
 use net_traits::CookieSource::NonHTTP;
 use net_traits::CoreResourceMsg::{GetCookiesForUrl, SetCookiesForUrl};
 use net_traits::pub_domains::is_pub_domain;
@@ -109,6 +127,7 @@ use profile_traits::ipc as profile_ipc;
 use profile_traits::time::{TimerMetadata, TimerMetadataFrameType, TimerMetadataReflowType};
 use ref_slice::ref_slice;
 use script_layout_interface::message::{Msg, NodesFromPointQueryType, QueryMsg, ReflowGoal};
+use script_layout_interface::rpc::{SvgHitTestResponse, TextIndexResponse};
 use script_runtime::{CommonScriptMsg, ScriptThreadEventCategory};
 use script_thread::{MainThreadScriptMsg, ScriptThread};
 use script_traits::{AnimationState, DocumentActivity, MouseButton, MouseEventType};
@@ -119,6 +138,7 @@ use servo_config::prefs::PREFS;
 use servo_url::{ImmutableOrigin, MutableOrigin, ServoUrl};
 use std::borrow::ToOwned;
 use std::cell::{Cell, Ref, RefMut};
+use std::cmp;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::default::Default;
@@ -130,6 +150,7 @@ use std::time::{Duration, Instant};
 use style::attr::AttrValue;
 use style::context::QuirksMode;
 use style::invalidation::element::restyle_hints::RestyleHint;
+use style::invalidation::media_queries::{MediaListKey, ToMediaListKey};
 use style::media_queries::{Device, MediaList, MediaType};
 use style::selector_parser::{RestyleDamage, Snapshot};
 use style::shared_lock::{SharedRwLock as StyleSharedRwLock, SharedRwLockReadGuard};
@@ -137,6 +158,7 @@ use style::str::{split_html_space_chars, str_join};
 use style::stylesheet_set::DocumentStylesheetSet;
 use style::stylesheets::{CssRule, Stylesheet, Origin, OriginSet};
 use task_source::{TaskSource, TaskSourceName};
+use textinput::is_control_key;
 use time;
 use timers::OneshotTimerCallback;
 use url::Host;
@@ -151,6 +173,9 @@ const SPURIOUS_ANIMATION_FRAME_THRESHOLD: u8 = 5;
 /// The amount of time between fake `requestAnimationFrame()`s.
 const FAKE_REQUEST_ANIMATION_FRAME_DELAY: u64 = 16;
 
+/// The expected amount of time between two animation frames, in milliseconds.
+const ANIMATION_FRAME_DURATION: f64 = 1000. / 60.;
+
 pub enum TouchEventResult {
     Processed(bool),
     Forwarded,
@@ -201,12 +226,14 @@ impl PendingRestyle {
     }
 }
 
+/// A stylesheet together with the element that owns it, as stored in the
+/// stylesheet sets of documents and shadow roots.
 #[derive(Clone, JSTraceable, MallocSizeOf)]
 #[must_root]
-struct StyleSheetInDocument {
+pub struct StyleSheetInDocument {
     #[ignore_malloc_size_of = "Arc"]
-    sheet: Arc<Stylesheet>,
-    owner: Dom<Element>,
+    pub sheet: Arc<Stylesheet>,
+    pub owner: Dom<Element>,
 }
 
 impl fmt::Debug for StyleSheetInDocument {
@@ -221,6 +248,12 @@ impl PartialEq for StyleSheetInDocument {
     }
 }
 
+impl ToMediaListKey for StyleSheetInDocument {
+    fn to_media_list_key(&self) -> MediaListKey {
+        self.sheet.to_media_list_key()
+    }
+}
+
 impl ::style::stylesheets::StylesheetInDocument for StyleSheetInDocument {
     fn origin(&self, guard: &SharedRwLockReadGuard) -> Origin {
         self.sheet.origin(guard)
@@ -243,6 +276,29 @@ impl ::style::stylesheets::StylesheetInDocument for StyleSheetInDocument {
     }
 }
 
+/// The document or shadow root whose list of stylesheets the stylesheets of
+/// a given style, link or meta element belong to.
+pub enum StyleSheetListOwner {
+    Document(DomRoot<Document>),
+    ShadowRoot(DomRoot<ShadowRoot>),
+}
+
+impl StyleSheetListOwner {
+    pub fn add_stylesheet(&self, owner: &Element, sheet: Arc<Stylesheet>) {
+        match *self {
+            StyleSheetListOwner::Document(ref document) => document.add_stylesheet(owner, sheet),
+            StyleSheetListOwner::ShadowRoot(ref shadow_root) => shadow_root.add_stylesheet(owner, sheet),
+        }
+    }
+
+    pub fn remove_stylesheet(&self, owner: &Element, sheet: &Arc<Stylesheet>) {
+        match *self {
+            StyleSheetListOwner::Document(ref document) => document.remove_stylesheet(owner, sheet),
+            StyleSheetListOwner::ShadowRoot(ref shadow_root) => shadow_root.remove_stylesheet(owner, sheet),
+        }
+    }
+}
+
 /// <https://dom.spec.whatwg.org/#document>
 #[dom_struct]
 pub struct Document {
@@ -277,7 +333,12 @@ pub struct Document {
     /// List of stylesheets associated with nodes in this document. |None| if the list needs to be refreshed.
     stylesheets: DomRefCell<DocumentStylesheetSet<StyleSheetInDocument>>,
     stylesheet_list: MutNullableDom<StyleSheetList>,
+    /// The shadow roots whose hosts are connected to this document, whose
+    /// stylesheets layout needs to flush before restyling.
+    shadow_roots: DomRefCell<Vec<Dom<ShadowRoot>>>,
     ready_state: Cell<DocumentReadyState>,
+    /// <https://html.spec.whatwg.org/multipage/#visibility-state>
+    visibility_state: Cell<DocumentVisibilityState>,
     /// Whether the DOMContentLoaded event has already been dispatched.
     domcontentloaded_dispatched: Cell<bool>,
     /// The element that has most recently requested focus for itself.
@@ -305,11 +366,27 @@ pub struct Document {
     /// <https://html.spec.whatwg.org/multipage/#list-of-animation-frame-callbacks>
     /// List of animation frame callbacks
     animation_frame_list: DomRefCell<Vec<(u32, Option<AnimationFrameCallback>)>>,
+    /// When the animation frame callbacks last ran, in the time base of `performance.now()`.
+    last_animation_frame_time: Cell<f64>,
     /// Whether we're in the process of running animation callbacks.
     ///
     /// Tracking this is not necessary for correctness. Instead, it is an optimization to avoid
     /// sending needless `ChangeRunningAnimationsState` messages to the compositor.
     running_animation_callbacks: Cell<bool>,
+    /// The intersection observers created in the window of this document.
+    intersection_observers: DomRefCell<Vec<Dom<IntersectionObserver>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#document-intersectionobservertaskqueued>
+    intersection_observer_task_queued: Cell<bool>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-document-resizeobservers-slot>
+    resize_observers: DomRefCell<Vec<Dom<ResizeObserver>>>,
+    /// <https://w3c.github.io/selection-api/#dfn-selection>
+    selection: MutNullableDom<Selection>,
+    /// Whether the user is currently selecting text by dragging the mouse.
+    selecting_with_mouse: Cell<bool>,
+    /// <https://html.spec.whatwg.org/multipage/#designMode>
+    design_mode: Cell<bool>,
+    /// The undo and redo history of editing commands.
+    editing_history: EditingHistory,
     /// Tracks all outstanding loads related to this document.
     loader: DomRefCell<DocumentLoader>,
     /// The current active HTML parser, to allow resuming after interruptions.
@@ -339,6 +416,8 @@ pub struct Document {
     top_level_dom_complete: Cell<u64>,
     load_event_start: Cell<u64>,
     load_event_end: Cell<u64>,
+    /// The timing of the fetch of this document, once it has been received.
+    navigation_fetch_timing: Cell<Option<ResourceFetchTiming>>,
     /// <https://html.spec.whatwg.org/multipage/#concept-document-https-state>
     https_state: Cell<HttpsState>,
     /// The document's origin.
@@ -822,6 +901,53 @@ impl Document {
         self.upcast::<EventTarget>().fire_event(atom!("readystatechange"));
     }
 
+    /// Set the visibility state of a document that was just created.
+    pub fn set_initial_visibility_state(&self, visibility_state: DocumentVisibilityState) {
+        self.visibility_state.set(visibility_state);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#update-the-visibility-state>
+    pub fn update_visibility_state(&self, visibility_state: DocumentVisibilityState) {
+        // Step 1.
+        if self.visibility_state.get() == visibility_state {
+            return;
+        }
+
+        // Step 2.
+        self.visibility_state.set(visibility_state);
+
+        // Hidden documents don't run animation frame callbacks, so stop
+        // ticking them until the document is visible again.
+        if !self.animation_frame_list.borrow().is_empty() {
+            match visibility_state {
+                DocumentVisibilityState::Visible if self.is_faking_animation_frames() => {
+                    let callback = FakeRequestAnimationFrameCallback {
+                        document: Trusted::new(self),
+                    };
+                    self.global()
+                        .schedule_callback(OneshotTimerCallback::FakeRequestAnimationFrame(callback),
+                                           MsDuration::new(FAKE_REQUEST_ANIMATION_FRAME_DELAY));
+                },
+                DocumentVisibilityState::Visible => {
+                    let event = ScriptMsg::ChangeRunningAnimationsState(AnimationState::AnimationCallbacksPresent);
+                    self.window().send_to_constellation(event);
+                },
+                DocumentVisibilityState::Hidden if !self.is_faking_animation_frames() => {
+                    let event = ScriptMsg::ChangeRunningAnimationsState(AnimationState::NoAnimationCallbacksPresent);
+                    self.window().send_to_constellation(event);
+                },
+                DocumentVisibilityState::Hidden => {},
+            }
+        }
+
+        // Step 4.
+        self.upcast::<EventTarget>().fire_bubbling_event(atom!("visibilitychange"));
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.visibility_state.get() == DocumentVisibilityState::Hidden
+    }
+
     /// Return whether scripting is enabled or not
     pub fn is_scripting_enabled(&self) -> bool {
         self.scripting_enabled
@@ -839,6 +965,15 @@ impl Document {
         self.possibly_focused.set(None);
     }
 
+    /// Whether the document is in design mode, making all of it editable.
+    pub fn design_mode(&self) -> bool {
+        self.design_mode.get()
+    }
+
+    pub fn editing_history(&self) -> &EditingHistory {
+        &self.editing_history
+    }
+
     /// Request that the given element receive focus once the current transaction is complete.
     pub fn request_focus(&self, elem: &Element) {
         if elem.is_focusable_area() {
@@ -882,6 +1017,11 @@ impl Document {
                 self.send_to_embedder(EmbedderMsg::ShowIME(kind));
             }
         }
+
+        // The caret is only drawn in focused editing hosts.
+        if let Some(selection) = self.selection.get() {
+            selection.update_highlighted_text();
+        }
     }
 
     /// Handles any updates when the document's title has changed.
@@ -916,7 +1056,7 @@ impl Document {
     pub fn handle_mouse_event(
         &self,
         js_runtime: *mut JSRuntime,
-        _button: MouseButton,
+        button: MouseButton,
         client_point: Point2D<f32>,
         mouse_event_type: MouseEventType,
         node_address: Option<UntrustedNodeAddress>,
@@ -929,8 +1069,8 @@ impl Document {
         };
         debug!("{}: at {:?}", mouse_event_type_string, client_point);
 
-        let el = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+        let hit_node = self.hit_node(js_runtime, node_address, point_in_node);
+        let el = hit_node.as_ref().and_then(|node| {
             node.inclusive_ancestors()
                 .filter_map(DomRoot::downcast::<Element>)
                 .next()
@@ -981,7 +1121,13 @@ impl Document {
         // https://html.spec.whatwg.org/multipage/#run-authentic-click-activation-steps
         let activatable = el.as_maybe_activatable();
         match mouse_event_type {
-            MouseEventType::Click => el.authentic_click_activation(event),
+            MouseEventType::Click => {
+                // Clicking in editable content focuses its editing host.
+                if let Some(host) = editing::editing_host_of(node) {
+                    self.request_focus(&host);
+                }
+                el.authentic_click_activation(event)
+            },
             MouseEventType::MouseDown => {
                 if let Some(a) = activatable {
                     a.enter_formal_activation_state();
@@ -989,6 +1135,12 @@ impl Document {
 
                 let target = node.upcast();
                 event.fire(target);
+
+                if let (MouseButton::Left, Some(ref hit_node)) = (button, hit_node.as_ref()) {
+                    if event.get_cancel_state() != EventDefault::Prevented {
+                        self.start_selection_with_mouse(hit_node, client_point, point_in_node);
+                    }
+                }
             },
             MouseEventType::MouseUp => {
                 if let Some(a) = activatable {
@@ -997,6 +1149,8 @@ impl Document {
 
                 let target = node.upcast();
                 event.fire(target);
+
+                self.selecting_with_mouse.set(false);
             },
         }
 
@@ -1008,6 +1162,111 @@ impl Document {
         self.window.reflow(ReflowGoal::Full, ReflowReason::MouseEvent);
     }
 
+    /// The node under the mouse. Layout paints the content of an `<svg>`
+    /// element as a single image, so it is asked which shape is under the
+    /// mouse in it.
+    #[allow(unsafe_code)]
+    fn hit_node(
+        &self,
+        js_runtime: *mut JSRuntime,
+        node_address: Option<UntrustedNodeAddress>,
+        point_in_node: Option<Point2D<f32>>
+    ) -> Option<DomRoot<Node>> {
+        let node = unsafe { node::from_untrusted_node_address(js_runtime, node_address?) };
+        let point_in_node = match point_in_node {
+            Some(point_in_node) if node.is::<SVGSVGElement>() => point_in_node,
+            _ => return Some(node),
+        };
+        let SvgHitTestResponse(shape) = self.window.svg_hit_test_query(
+            node.to_trusted_node_address(),
+            point_in_node,
+        );
+        match shape {
+            Some(address) => Some(unsafe { node::from_untrusted_node_address(js_runtime, address) }),
+            None => Some(node),
+        }
+    }
+
+    /// The offset in `node` under the mouse that a selection can start or end
+    /// at, if `node` is a text node.
+    fn text_offset_from_point(
+        &self,
+        node: &Node,
+        client_point: Point2D<f32>,
+        point_in_node: Option<Point2D<f32>>,
+    ) -> Option<u32> {
+        if !node.is::<Text>() {
+            return None;
+        }
+        let point_in_node = match point_in_node {
+            Some(point_in_node) => point_in_node,
+            None => return Some(0),
+        };
+        let TextIndexResponse(index) = self.window.text_index_query(
+            node.to_trusted_node_address(),
+            point_in_node,
+            client_point,
+        );
+        // The text index counts characters, but offsets in the DOM are in UTF-16 code units.
+        let data = node.downcast::<CharacterData>().unwrap().data();
+        let offset = data.chars().take(index.unwrap_or(0)).map(char::len_utf16).sum::<usize>();
+        Some(offset as u32)
+    }
+
+    /// Start a new selection where the user pressed the mouse button.
+    fn start_selection_with_mouse(
+        &self,
+        node: &Node,
+        client_point: Point2D<f32>,
+        point_in_node: Option<Point2D<f32>>,
+    ) {
+        // Text controls maintain their own selection.
+        if node.inclusive_ancestors().any(|ancestor| {
+            ancestor.is::<HTMLInputElement>() || ancestor.is::<HTMLTextAreaElement>()
+        }) {
+            return;
+        }
+
+        let selection = match self.GetSelection() {
+            Some(selection) => selection,
+            None => return,
+        };
+        let offset = match self.text_offset_from_point(node, client_point, point_in_node) {
+            Some(offset) => offset,
+            // Clicking next to the text of editable content puts the caret at its end.
+            None if editing::editing_host_of(node).is_some() => node.children_count(),
+            None => return selection.RemoveAllRanges(),
+        };
+
+        // https://w3c.github.io/selection-api/#selectstart-event
+        let event = node.upcast::<EventTarget>().fire_bubbling_cancelable_event(atom!("selectstart"));
+        if event.get_cancel_state() == EventDefault::Prevented {
+            return;
+        }
+
+        selection.collapse_at(node, offset);
+        self.selecting_with_mouse.set(true);
+    }
+
+    /// Extend the selection to where the user dragged the mouse.
+    fn extend_selection_with_mouse(
+        &self,
+        node: &Node,
+        client_point: Point2D<f32>,
+        point_in_node: Option<Point2D<f32>>,
+    ) {
+        if !self.selecting_with_mouse.get() {
+            return;
+        }
+        let offset = match self.text_offset_from_point(node, client_point, point_in_node) {
+            Some(offset) => offset,
+            None => return,
+        };
+        if let Some(selection) = self.GetSelection() {
+            selection.extend_to(node, offset);
+        }
+    }
+
     fn maybe_fire_dblclick(&self, click_pos: Point2D<f32>, target: &Node) {
         // https://w3c.github.io/uievents/#event-type-dblclick
         let now = Instant::now();
@@ -1094,7 +1353,8 @@ impl Document {
         js_runtime: *mut JSRuntime,
         client_point: Option<Point2D<f32>>,
         prev_mouse_over_target: &MutNullableDom<Element>,
-        node_address: Option<UntrustedNodeAddress>
+        node_address: Option<UntrustedNodeAddress>,
+        point_in_node: Option<Point2D<f32>>
     ) {
         let client_point = match client_point {
             None => {
@@ -1106,8 +1366,8 @@ impl Document {
             Some(client_point) => client_point,
         };
 
-        let maybe_new_target = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+        let hit_node = self.hit_node(js_runtime, node_address, point_in_node);
+        let maybe_new_target = hit_node.as_ref().and_then(|node| {
             node.inclusive_ancestors()
                 .filter_map(DomRoot::downcast::<Element>)
                 .next()
@@ -1122,6 +1382,10 @@ impl Document {
 
         self.fire_mouse_event(client_point, new_target.upcast(), FireMouseEventType::Move);
 
+        if let Some(ref hit_node) = hit_node {
+            self.extend_selection_with_mouse(hit_node, client_point, point_in_node);
+        }
+
         // Nothing more to do here, mousemove is sent,
         // and the element under the mouse hasn't changed.
         if maybe_new_target == prev_mouse_over_target.get() {
@@ -1368,6 +1632,10 @@ impl Document {
             let msg = EmbedderMsg::KeyEvent(ch, key, state, modifiers);
             self.send_to_embedder(msg);
 
+            if state != KeyState::Released && !editing::handle_key(self, ch, key, modifiers) {
+                self.handle_selection_key(key, modifiers);
+            }
+
             // This behavior is unspecced
             // We are supposed to dispatch synthetic click activation for Space and/or Return,
             // however *when* we do it is up to us.
@@ -1400,6 +1668,32 @@ impl Document {
         self.window.reflow(ReflowGoal::Full, ReflowReason::KeyEvent);
     }
 
+    /// Handle the keyboard shortcuts that act on the document's selection,
+    /// unless a text control is focused, which handles them itself.
+    fn handle_selection_key(&self, key: Key, modifiers: KeyModifiers) {
+        if let Some(focused) = self.get_focused_element() {
+            if focused.is::<HTMLInputElement>() || focused.is::<HTMLTextAreaElement>() {
+                return;
+            }
+        }
+        let selection = match self.GetSelection() {
+            Some(selection) => selection,
+            None => return,
+        };
+        match key {
+            Key::Left if modifiers == KeyModifiers::SHIFT => selection.extend_by_character(false),
+            Key::Right if modifiers == KeyModifiers::SHIFT => selection.extend_by_character(true),
+            Key::C if is_control_key(modifiers) => {
+                let text = selection.Stringifier();
+                if !text.is_empty() {
+                    let mut clipboard = self.window.upcast::<GlobalScope>().script_to_constellation_chan().clone();
+                    clipboard.set_clipboard_contents(String::from(text));
+                }
+            },
+            _ => {},
+        }
+    }
+
     // https://dom.spec.whatwg.org/#converting-nodes-into-a-node
     pub fn node_from_nodes_and_strings(&self,
                                        mut nodes: Vec<NodeOrString>)
@@ -1473,6 +1767,13 @@ impl Document {
         if let Some(element) = self.GetDocumentElement() {
             element.upcast::<Node>().dirty(NodeDamage::NodeStyleDamaged);
         }
+
+        let shadow_roots: Vec<_> = self.shadow_roots.borrow().iter()
+            .map(|shadow_root| DomRoot::from_ref(&**shadow_root))
+            .collect();
+        for shadow_root in shadow_roots {
+            shadow_root.invalidate_stylesheets();
+        }
     }
 
     /// <https://html.spec.whatwg.org/multipage/#dom-window-requestanimationframe>
@@ -1482,7 +1783,10 @@ impl Document {
         self.animation_frame_ident.set(ident);
         self.animation_frame_list.borrow_mut().push((ident, Some(callback)));
 
-        // TODO: Should tick animation only when document is visible
+        // Hidden documents keep their callbacks until they become visible again.
+        if self.is_hidden() {
+            return ident;
+        }
 
         // If we are running 'fake' animation frames, we unconditionally
         // set up a one-shot timer for script to execute the rAF callbacks.
@@ -1507,6 +1811,17 @@ impl Document {
         ident
     }
 
+    /// When the next animation frame callbacks are expected to run, in the
+    /// time base of `performance.now()`, if the compositor is ticking them.
+    pub fn next_animation_frame_time(&self) -> Option<f64> {
+        if self.animation_frame_list.borrow().is_empty() ||
+           self.is_hidden() ||
+           self.is_faking_animation_frames() {
+            return None;
+        }
+        Some(self.last_animation_frame_time.get() + ANIMATION_FRAME_DURATION)
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#dom-window-cancelanimationframe>
     pub fn cancel_animation_frame(&self, ident: u32) {
         let mut list = self.animation_frame_list.borrow_mut();
@@ -1517,6 +1832,10 @@ impl Document {
 
     /// <https://html.spec.whatwg.org/multipage/#run-the-animation-frame-callbacks>
     pub fn run_the_animation_frame_callbacks(&self) {
+        if self.is_hidden() {
+            return;
+        }
+
         rooted_vec!(let mut animation_frame_list);
         mem::swap(
             &mut *animation_frame_list,
@@ -1525,6 +1844,7 @@ impl Document {
         self.running_animation_callbacks.set(true);
         let was_faking_animation_frames = self.is_faking_animation_frames();
         let timing = self.global().performance().Now();
+        self.last_animation_frame_time.set(*timing);
 
         for (_, callback) in animation_frame_list.drain(..) {
             if let Some(callback) = callback {
@@ -1585,6 +1905,153 @@ impl Document {
         }
     }
 
+    pub fn add_resize_observer(&self, observer: &ResizeObserver) {
+        self.resize_observers.borrow_mut().push(Dom::from_ref(observer));
+    }
+
+    fn resize_observers(&self) -> Vec<DomRoot<ResizeObserver>> {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .map(|observer| DomRoot::from_ref(&**observer))
+            .collect()
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#html-event-loop>
+    pub fn run_the_resize_observers(&self) {
+        if self.resize_observers.borrow().is_empty() {
+            return;
+        }
+
+        // Step 1.
+        let mut depth = 0;
+
+        // Step 2.
+        self.gather_active_resize_observations_at_depth(depth);
+
+        // Step 3.
+        while self.has_active_resize_observations() {
+            // Step 3.1 happens as part of the layout queries of the next steps.
+            // Step 3.2.
+            depth = self.broadcast_active_resize_observations();
+            // Step 3.3.
+            self.gather_active_resize_observations_at_depth(depth);
+        }
+
+        // Step 4.
+        if self.has_skipped_resize_observations() {
+            self.deliver_resize_loop_error_notification();
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    fn gather_active_resize_observations_at_depth(&self, depth: usize) {
+        for observer in &self.resize_observers() {
+            observer.gather_active_observations_at_depth(depth);
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-active-observations-h>
+    fn has_active_resize_observations(&self) -> bool {
+        self.resize_observers().iter().any(|observer| observer.has_active_observations())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-skipped-observations-h>
+    fn has_skipped_resize_observations(&self) -> bool {
+        self.resize_observers().iter().any(|observer| observer.has_skipped_observations())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#broadcast-active-observations>
+    fn broadcast_active_resize_observations(&self) -> usize {
+        // Step 1.
+        let mut shallowest_target_depth = usize::max_value();
+
+        // Step 2.
+        for observer in &self.resize_observers() {
+            shallowest_target_depth = cmp::min(shallowest_target_depth,
+                                               observer.broadcast_active_observations());
+        }
+
+        // Step 3.
+        shallowest_target_depth
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#deliver-resize-error>
+    #[allow(unsafe_code)]
+    fn deliver_resize_loop_error_notification(&self) {
+        let global = self.window.upcast::<GlobalScope>();
+        let error_info = ErrorInfo {
+            message: "ResizeObserver loop completed with undelivered notifications.".to_owned(),
+            filename: String::new(),
+            lineno: 0,
+            column: 0,
+        };
+        rooted!(in(global.get_cx()) let error = NullValue());
+        global.report_an_error(error_info, error.handle());
+    }
+
+    pub fn add_intersection_observer(&self, observer: &IntersectionObserver) {
+        self.intersection_observers.borrow_mut().push(Dom::from_ref(observer));
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#run-the-update-intersection-observations-steps>
+    pub fn update_intersection_observations(&self) {
+        if self.intersection_observers.borrow().is_empty() {
+            return;
+        }
+
+        // Step 1.
+        let observers: Vec<DomRoot<IntersectionObserver>> = self.intersection_observers
+            .borrow()
+            .iter()
+            .map(|observer| DomRoot::from_ref(&**observer))
+            .collect();
+
+        // Step 2.
+        let time = *self.window.Performance().Now();
+        let mut queued = false;
+        for observer in &observers {
+            queued |= observer.update_intersection_observations(time);
+        }
+        if queued {
+            self.queue_intersection_observer_task();
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#queue-an-intersection-observer-task>
+    fn queue_intersection_observer_task(&self) {
+        // Step 2.
+        if self.intersection_observer_task_queued.get() {
+            return;
+        }
+        // Step 3.
+        self.intersection_observer_task_queued.set(true);
+        // Step 4.
+        let document = Trusted::new(self);
+        let _ = self.window.intersection_observer_task_source().queue(
+            task!(notify_intersection_observers: move || {
+                document.root().notify_intersection_observers();
+            }),
+            self.window.upcast(),
+        );
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#notify-intersection-observers-algo>
+    fn notify_intersection_observers(&self) {
+        // Step 1.
+        self.intersection_observer_task_queued.set(false);
+        // Step 2.
+        let notify_list: Vec<DomRoot<IntersectionObserver>> = self.intersection_observers
+            .borrow()
+            .iter()
+            .map(|observer| DomRoot::from_ref(&**observer))
+            .collect();
+        // Step 3.
+        for observer in &notify_list {
+            observer.invoke_callback();
+        }
+    }
+
     pub fn fetch_async(&self, load: LoadType,
                        request: RequestInit,
                        fetch_target: IpcSender<FetchResponseMsg>) {
@@ -1794,6 +2261,7 @@ impl Document {
 
                 // http://w3c.github.io/navigation-timing/#widl-PerformanceNavigationTiming-loadEventEnd
                 update_with_current_time_ms(&document.load_event_end);
+                document.queue_navigation_timing_entry();
 
                 window.reflow(ReflowGoal::Full, ReflowReason::DocumentLoaded);
 
@@ -2102,6 +2570,21 @@ impl Document {
         self.load_event_end.get()
     }
 
+    pub fn set_navigation_fetch_timing(&self, timing: ResourceFetchTiming) {
+        self.navigation_fetch_timing.set(Some(timing));
+    }
+
+    /// <https://w3c.github.io/navigation-timing/#dfn-queue-the-navigation-timing-entry>
+    fn queue_navigation_timing_entry(&self) {
+        let timing = match self.navigation_fetch_timing.get() {
+            Some(timing) => timing,
+            None => return,
+        };
+        let entry = PerformanceNavigationTiming::new(&self.window, self, &timing);
+        self.window.Performance().queue_entry(entry.upcast::<PerformanceEntry>(),
+                                              true /* buffer performance entry */);
+    }
+
     pub fn start_tti(&self) {
         if self.get_interactive_metrics().needs_tti() {
             self.tti_window.borrow_mut().start_window();
@@ -2205,6 +2688,7 @@ pub trait LayoutDocumentHelpers {
     unsafe fn will_paint(&self);
     unsafe fn quirks_mode(&self) -> QuirksMode;
     unsafe fn style_shared_lock(&self) -> &StyleSharedRwLock;
+    unsafe fn shadow_roots(&self) -> Vec<LayoutDom<ShadowRoot>>;
 }
 
 #[allow(unsafe_code)]
@@ -2218,11 +2702,11 @@ impl LayoutDocumentHelpers for LayoutDom<Document> {
     #[allow(unrooted_must_root)]
     unsafe fn drain_pending_restyles(&self) -> Vec<(LayoutDom<Element>, PendingRestyle)> {
         let mut elements = (*self.unsafe_get()).pending_restyles.borrow_mut_for_layout();
-        // Elements were in a document when they were adding to this list, but that
+        // Elements were connected when they were adding to this list, but that
         // may no longer be true when the next layout occurs.
         let result = elements.drain()
             .map(|(k, v)| (k.to_layout(), v))
-            .filter(|&(ref k, _)| k.upcast::<Node>().get_flag(NodeFlags::IS_IN_DOC))
+            .filter(|&(ref k, _)| k.upcast::<Node>().get_flag(NodeFlags::IS_CONNECTED))
             .collect();
         result
     }
@@ -2246,6 +2730,15 @@ impl LayoutDocumentHelpers for LayoutDom<Document> {
     unsafe fn style_shared_lock(&self) -> &StyleSharedRwLock {
         (*self.unsafe_get()).style_shared_lock()
     }
+
+    #[inline]
+    #[allow(unrooted_must_root)]
+    unsafe fn shadow_roots(&self) -> Vec<LayoutDom<ShadowRoot>> {
+        (*self.unsafe_get()).shadow_roots.borrow_for_layout()
+            .iter()
+            .map(|shadow_root| shadow_root.to_layout())
+            .collect()
+    }
 }
 
 // https://html.spec.whatwg.org/multipage/#is-a-registrable-domain-suffix-of-or-is-equal-to
@@ -2385,7 +2878,9 @@ impl Document {
             },
             stylesheets: DomRefCell::new(DocumentStylesheetSet::new()),
             stylesheet_list: MutNullableDom::new(None),
+            shadow_roots: DomRefCell::new(vec![]),
             ready_state: Cell::new(ready_state),
+            visibility_state: Cell::new(DocumentVisibilityState::Visible),
             domcontentloaded_dispatched: Cell::new(domcontentloaded_dispatched),
             possibly_focused: Default::default(),
             focused: Default::default(),
@@ -2397,8 +2892,16 @@ impl Document {
             asap_scripts_set: Default::default(),
             scripting_enabled: has_browsing_context == HasBrowsingContext::Yes,
             animation_frame_ident: Cell::new(0),
+            last_animation_frame_time: Cell::new(0.),
             animation_frame_list: DomRefCell::new(vec![]),
             running_animation_callbacks: Cell::new(false),
+            intersection_observers: DomRefCell::new(vec![]),
+            intersection_observer_task_queued: Cell::new(false),
+            resize_observers: DomRefCell::new(vec![]),
+            selection: Default::default(),
+            selecting_with_mouse: Cell::new(false),
+            design_mode: Cell::new(false),
+            editing_history: Default::default(),
             loader: DomRefCell::new(doc_loader),
             current_parser: Default::default(),
             reflow_timeout: Cell::new(None),
@@ -2415,6 +2918,7 @@ impl Document {
             top_level_dom_complete: Cell::new(Default::default()),
             load_event_start: Cell::new(Default::default()),
             load_event_end: Cell::new(Default::default()),
+            navigation_fetch_timing: Cell::new(None),
             https_state: Cell::new(HttpsState::None),
             origin: origin,
             referrer: referrer,
@@ -2608,6 +3112,21 @@ impl Document {
         }
     }
 
+    /// Starts tracking the stylesheets of `shadow_root`, whose host just got
+    /// connected to this document.
+    pub fn register_shadow_root(&self, shadow_root: &ShadowRoot) {
+        let mut shadow_roots = self.shadow_roots.borrow_mut();
+        if !shadow_roots.iter().any(|s| &**s == shadow_root) {
+            shadow_roots.push(Dom::from_ref(shadow_root));
+        }
+    }
+
+    /// Stops tracking the stylesheets of `shadow_root`, whose host just got
+    /// disconnected from this document.
+    pub fn unregister_shadow_root(&self, shadow_root: &ShadowRoot) {
+        self.shadow_roots.borrow_mut().retain(|s| &**s != shadow_root);
+    }
+
     /// Returns the number of document stylesheets.
     pub fn stylesheet_count(&self) -> usize {
         self.stylesheets.borrow().len()
@@ -2945,6 +3464,74 @@ impl DocumentMethods for Document {
         false
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-document-designmode
+    fn DesignMode(&self) -> DOMString {
+        DOMString::from(if self.design_mode.get() { "on" } else { "off" })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-document-designmode
+    fn SetDesignMode(&self, value: DOMString) {
+        let design_mode = match &*value.to_ascii_lowercase() {
+            "on" => true,
+            "off" => false,
+            _ => return,
+        };
+        if design_mode == self.design_mode.get() {
+            return;
+        }
+        self.design_mode.set(design_mode);
+        if design_mode {
+            // Put the caret at the start of the body.
+            let root = self.GetBody().map(DomRoot::upcast::<Element>).or_else(|| self.GetDocumentElement());
+            if let (Some(root), Some(selection)) = (root, self.GetSelection()) {
+                selection.collapse_at(root.upcast(), 0);
+            }
+        } else if let Some(selection) = self.selection.get() {
+            selection.update_highlighted_text();
+        }
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#execcommand()
+    fn ExecCommand(&self, command_id: DOMString, _show_ui: bool, value: DOMString) -> bool {
+        match editing::Command::from_id(&command_id) {
+            Some(command) => editing::exec_command(self, command, &value),
+            None => false,
+        }
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandenabled()
+    fn QueryCommandEnabled(&self, command_id: DOMString) -> bool {
+        editing::Command::from_id(&command_id).map_or(false, |command| {
+            editing::query_command_enabled(self, command)
+        })
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandindeterm()
+    fn QueryCommandIndeterm(&self, command_id: DOMString) -> bool {
+        editing::Command::from_id(&command_id).map_or(false, |command| {
+            editing::query_command_indeterm(self, command)
+        })
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandstate()
+    fn QueryCommandState(&self, command_id: DOMString) -> bool {
+        editing::Command::from_id(&command_id).map_or(false, |command| {
+            editing::query_command_state(self, command)
+        })
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandsupported()
+    fn QueryCommandSupported(&self, command_id: DOMString) -> bool {
+        editing::Command::from_id(&command_id).is_some()
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandvalue()
+    fn QueryCommandValue(&self, command_id: DOMString) -> DOMString {
+        editing::Command::from_id(&command_id).map_or(DOMString::new(), |command| {
+            editing::query_command_value(self, command)
+        })
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-document-domain
     fn Domain(&self) -> DOMString {
         // Step 1.
@@ -3335,6 +3922,30 @@ impl DocumentMethods for Document {
         TreeWalker::new(self, root, what_to_show, filter)
     }
 
+    // https://dom.spec.whatwg.org/#dom-xpathevaluatorbase-createexpression
+    fn CreateExpression(&self,
+                        expression: DOMString,
+                        resolver: Option<Rc<XPathNSResolver>>)
+                        -> Fallible<DomRoot<XPathExpression>> {
+        XPathExpression::create(&self.window, expression, resolver)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathevaluatorbase-creatensresolver
+    fn CreateNSResolver(&self, node_resolver: &Node) -> DomRoot<Node> {
+        DomRoot::from_ref(node_resolver)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathevaluatorbase-evaluate
+    fn Evaluate(&self,
+                expression: DOMString,
+                context_node: &Node,
+                resolver: Option<Rc<XPathNSResolver>>,
+                type_: u16,
+                _result: Option<&XPathResult>)
+                -> Fallible<DomRoot<XPathResult>> {
+        XPathEvaluator::evaluate(&self.window, expression, context_node, resolver, type_)
+    }
+
     // https://html.spec.whatwg.org/multipage/#document.title
     fn Title(&self) -> DOMString {
         let title = self.GetDocumentElement().and_then(|root| {
@@ -3865,6 +4476,14 @@ impl DocumentMethods for Document {
         elements
     }
 
+    // https://w3c.github.io/selection-api/#dom-document-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        if !self.has_browsing_context {
+            return None;
+        }
+        Some(self.selection.or_init(|| Selection::new(self)))
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-document-open
     fn Open(&self, _type: Option<DOMString>, replace: DOMString) -> Fallible<DomRoot<Document>> {
         if !self.is_html_document() {
@@ -4084,6 +4703,19 @@ impl DocumentMethods for Document {
     // https://html.spec.whatwg.org/multipage/#documentandelementeventhandlers
     document_and_element_event_handlers!();
 
+    // https://w3c.github.io/page-visibility/#dom-document-hidden
+    fn Hidden(&self) -> bool {
+        self.is_hidden()
+    }
+
+    // https://w3c.github.io/page-visibility/#dom-document-visibilitystate
+    fn VisibilityState(&self) -> DocumentVisibilityState {
+        self.visibility_state.get()
+    }
+
+    // https://w3c.github.io/page-visibility/#dom-document-onvisibilitychange
+    event_handler!(visibilitychange, GetOnvisibilitychange, SetOnvisibilitychange);
+
     // https://fullscreen.spec.whatwg.org/#handler-document-onfullscreenerror
     event_handler!(fullscreenerror, GetOnfullscreenerror, SetOnfullscreenerror);
 
@@ -36,6 +36,8 @@ pub enum DOMErrorName {
     InvalidNodeTypeError = DOMExceptionConstants::INVALID_NODE_TYPE_ERR,
     DataCloneError = DOMExceptionConstants::DATA_CLONE_ERR,
     NotReadableError = DOMExceptionConstants::NOT_READABLE_ERR,
+    DataError = DOMExceptionConstants::DATA_ERR,
+    OperationError = DOMExceptionConstants::OPERATION_ERR,
 }
 
 #[dom_struct]
@@ -95,7 +97,9 @@ impl DOMExceptionMethods for DOMException {
             DOMErrorName::InvalidNodeTypeError =>
                 "The supplied node is incorrect or has an incorrect ancestor for this operation.",
             DOMErrorName::DataCloneError => "The object can not be cloned.",
-            DOMErrorName::NotReadableError => "The I/O read operation failed."
+            DOMErrorName::NotReadableError => "The I/O read operation failed.",
+            DOMErrorName::DataError => "The provided data is inadequate.",
+            DOMErrorName::OperationError => "The operation failed for an operation-specific reason."
         };
 
         DOMString::from(message)
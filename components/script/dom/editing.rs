@@ -0,0 +1,1025 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Editing of `contenteditable` elements and of documents in design mode.
+//!
+//! <https://w3c.github.io/editing/docs/execCommand/>
+
+use clipboard_provider::ClipboardProvider;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
+use dom::bindings::codegen::Bindings::TextBinding::TextMethods;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::root::{Dom, DomRoot, RootedReference};
+use dom::bindings::str::DOMString;
+use dom::characterdata::CharacterData;
+use dom::document::Document;
+use dom::element::{CustomElementCreationMode, Element, ElementCreator};
+use dom::event::{Event, EventDefault};
+use dom::globalscope::GlobalScope;
+use dom::htmlbrelement::HTMLBRElement;
+use dom::htmlelement::HTMLElement;
+use dom::htmlhrelement::HTMLHRElement;
+use dom::htmlimageelement::HTMLImageElement;
+use dom::inputevent::InputEvent;
+use dom::mutationobserver::Mutation;
+use dom::node::Node;
+use dom::range::Range;
+use dom::selection::Selection;
+use dom::text::Text;
+use html5ever::{LocalName, Namespace, QualName};
+use msg::constellation_msg::{Key, KeyModifiers};
+use std::iter;
+use textinput::is_control_key;
+
+/// How many steps the undo history of a document remembers.
+const MAX_UNDO_STEPS: usize = 100;
+
+/// A command that edits the content of an editing host.
+///
+/// <https://w3c.github.io/editing/docs/execCommand/#commands>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Bold,
+    CreateLink,
+    Delete,
+    ForwardDelete,
+    InsertLineBreak,
+    InsertParagraph,
+    InsertText,
+    Italic,
+    Redo,
+    SelectAll,
+    StrikeThrough,
+    Underline,
+    Undo,
+    /// Cutting the selection to the clipboard, only available to the user.
+    Cut,
+    /// Pasting the clipboard, only available to the user.
+    Paste,
+}
+
+impl Command {
+    /// The command with the given command id, if it is supported.
+    pub fn from_id(id: &str) -> Option<Command> {
+        Some(match &*id.to_ascii_lowercase() {
+            "bold" => Command::Bold,
+            "createlink" => Command::CreateLink,
+            "delete" => Command::Delete,
+            "forwarddelete" => Command::ForwardDelete,
+            "insertlinebreak" => Command::InsertLineBreak,
+            "insertparagraph" => Command::InsertParagraph,
+            "inserttext" => Command::InsertText,
+            "italic" => Command::Italic,
+            "redo" => Command::Redo,
+            "selectall" => Command::SelectAll,
+            "strikethrough" => Command::StrikeThrough,
+            "underline" => Command::Underline,
+            "undo" => Command::Undo,
+            _ => return None,
+        })
+    }
+
+    /// <https://w3c.github.io/input-events/#interface-InputEvent-Attributes>
+    fn input_type(&self) -> &'static str {
+        match *self {
+            Command::Bold => "formatBold",
+            Command::CreateLink => "insertLink",
+            Command::Cut => "deleteByCut",
+            Command::Delete => "deleteContentBackward",
+            Command::ForwardDelete => "deleteContentForward",
+            Command::InsertLineBreak => "insertLineBreak",
+            Command::InsertParagraph => "insertParagraph",
+            Command::InsertText => "insertText",
+            Command::Italic => "formatItalic",
+            Command::Paste => "insertFromPaste",
+            Command::Redo => "historyRedo",
+            Command::SelectAll => "",
+            Command::StrikeThrough => "formatStrikeThrough",
+            Command::Underline => "formatUnderline",
+            Command::Undo => "historyUndo",
+        }
+    }
+
+    /// Whether this command toggles an inline style.
+    fn is_format(&self) -> bool {
+        match *self {
+            Command::Bold | Command::Italic | Command::StrikeThrough | Command::Underline => true,
+            _ => false,
+        }
+    }
+
+    /// The element this command wraps the selected text in.
+    fn format_element_name(&self) -> LocalName {
+        match *self {
+            Command::Bold => local_name!("b"),
+            Command::CreateLink => local_name!("a"),
+            Command::Italic => local_name!("i"),
+            Command::StrikeThrough => local_name!("s"),
+            Command::Underline => local_name!("u"),
+            _ => unreachable!("{:?} doesn't wrap text", self),
+        }
+    }
+
+    /// Whether `element` applies the style of this command.
+    fn is_format_element(&self, element: &Element) -> bool {
+        if *element.namespace() != ns!(html) {
+            return false;
+        }
+        let name = element.local_name();
+        match *self {
+            Command::Bold => *name == local_name!("b") || *name == local_name!("strong"),
+            Command::CreateLink => *name == local_name!("a"),
+            Command::Italic => *name == local_name!("i") || *name == local_name!("em"),
+            Command::StrikeThrough => *name == local_name!("s") || *name == local_name!("strike"),
+            Command::Underline => *name == local_name!("u"),
+            _ => false,
+        }
+    }
+}
+
+/// The state of the `contenteditable` attribute of an element.
+///
+/// <https://html.spec.whatwg.org/multipage/#attr-contenteditable>
+#[derive(Clone, Copy, PartialEq)]
+pub enum ContentEditableState {
+    True,
+    False,
+    Inherit,
+}
+
+pub fn content_editable_state(element: &Element) -> ContentEditableState {
+    if !element.is::<HTMLElement>() {
+        return ContentEditableState::Inherit;
+    }
+    match element.get_attribute(&ns!(), &local_name!("contenteditable")) {
+        Some(attr) => match &*attr.value().to_ascii_lowercase() {
+            "" | "true" => ContentEditableState::True,
+            "false" => ContentEditableState::False,
+            _ => ContentEditableState::Inherit,
+        },
+        None => ContentEditableState::Inherit,
+    }
+}
+
+/// The editing host `node` is in, if it is editable or is an editing host itself.
+///
+/// <https://html.spec.whatwg.org/multipage/#editing-host>
+pub fn editing_host_of(node: &Node) -> Option<DomRoot<Element>> {
+    let mut host = None;
+    for ancestor in node.inclusive_ancestors() {
+        if let Some(element) = ancestor.downcast::<Element>() {
+            match content_editable_state(element) {
+                ContentEditableState::True => host = Some(DomRoot::from_ref(element)),
+                ContentEditableState::False => return host,
+                ContentEditableState::Inherit => {},
+            }
+        }
+    }
+    let document = node.owner_doc();
+    if document.design_mode() && node.is_in_doc() {
+        return document.GetDocumentElement();
+    }
+    host
+}
+
+/// <https://html.spec.whatwg.org/multipage/#editing-host>
+pub fn is_editing_host(element: &Element) -> bool {
+    editing_host_of(element.upcast()).map_or(false, |host| &*host == element)
+}
+
+/// Whether the caret should be drawn in `node` when the selection is collapsed in it.
+pub fn shows_caret(document: &Document, node: &Node) -> bool {
+    editing_host_of(node).map_or(false, |host| document.design_mode() || host.focus_state())
+}
+
+/// The selection and the editing host it is in, if both ends of the
+/// selection are in the same editing host.
+fn editing_context(document: &Document) -> Option<(DomRoot<Selection>, DomRoot<Range>, DomRoot<Element>)> {
+    let selection = document.GetSelection()?;
+    let range = selection.GetRangeAt(0).ok()?;
+    let host = editing_host_of(&range.StartContainer())?;
+    match editing_host_of(&range.EndContainer()) {
+        Some(ref end_host) if *end_host == host => {},
+        _ => return None,
+    }
+    Some((selection, range, host))
+}
+
+/// A change a command made to the DOM, recorded from the mutation record it
+/// queued.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+enum Operation {
+    /// `added` were inserted into `parent` and `removed` were removed from
+    /// it, right before `next`.
+    ChildList {
+        parent: Dom<Node>,
+        added: Vec<Dom<Node>>,
+        removed: Vec<Dom<Node>>,
+        next: Option<Dom<Node>>,
+    },
+    CharacterData {
+        target: Dom<Node>,
+        old_value: DOMString,
+    },
+    Attribute {
+        target: Dom<Node>,
+        name: LocalName,
+        namespace: Namespace,
+        old_value: Option<DOMString>,
+    },
+}
+
+impl Operation {
+    #[allow(unrooted_must_root)]
+    fn new(target: &Node, mutation: &Mutation) -> Operation {
+        match *mutation {
+            Mutation::ChildList { added, removed, next, .. } => {
+                let nodes = |nodes: Option<&[&Node]>| {
+                    nodes.unwrap_or(&[]).iter().map(|node| Dom::from_ref(*node)).collect()
+                };
+                Operation::ChildList {
+                    parent: Dom::from_ref(target),
+                    added: nodes(added),
+                    removed: nodes(removed),
+                    next: next.map(Dom::from_ref),
+                }
+            },
+            Mutation::CharacterData { ref old_value } => Operation::CharacterData {
+                target: Dom::from_ref(target),
+                old_value: old_value.clone(),
+            },
+            Mutation::Attribute { ref name, ref namespace, ref old_value } => Operation::Attribute {
+                target: Dom::from_ref(target),
+                name: name.clone(),
+                namespace: namespace.clone(),
+                old_value: old_value.clone(),
+            },
+        }
+    }
+
+    /// Undo this change, which has to be the last one made to the nodes it touched.
+    fn revert(&self) -> ErrorResult {
+        match *self {
+            Operation::ChildList { ref parent, ref added, ref removed, ref next } => {
+                for node in added {
+                    parent.RemoveChild(node)?;
+                }
+                for node in removed {
+                    parent.InsertBefore(node, next.as_ref().map(|next| &**next))?;
+                }
+            },
+            Operation::CharacterData { ref target, ref old_value } => {
+                let data = target.downcast::<CharacterData>().ok_or(Error::InvalidNodeType)?;
+                data.SetData(old_value.clone());
+            },
+            Operation::Attribute { ref target, ref name, ref namespace, ref old_value } => {
+                let element = target.downcast::<Element>().ok_or(Error::InvalidNodeType)?;
+                let value = match *old_value {
+                    Some(ref value) => element.parse_attribute(namespace, name, value.clone()),
+                    None => {
+                        element.remove_attribute(namespace, name);
+                        return Ok(());
+                    },
+                };
+                match element.get_attribute(namespace, name) {
+                    Some(attr) => attr.set_value(value, element),
+                    None => element.push_new_attribute(name.clone(), value, name.clone(), namespace.clone(), None),
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+/// The anchor and focus of a selection.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct SelectionState {
+    anchor: Dom<Node>,
+    anchor_offset: u32,
+    focus: Dom<Node>,
+    focus_offset: u32,
+}
+
+impl SelectionState {
+    #[allow(unrooted_must_root)]
+    fn new(selection: &Selection) -> Option<SelectionState> {
+        Some(SelectionState {
+            anchor: Dom::from_ref(&*selection.GetAnchorNode()?),
+            anchor_offset: selection.AnchorOffset(),
+            focus: Dom::from_ref(&*selection.GetFocusNode()?),
+            focus_offset: selection.FocusOffset(),
+        })
+    }
+}
+
+/// The changes a command made to an editing host, and its selection before
+/// and after them.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct UndoStep {
+    host: Dom<Element>,
+    operations: Vec<Operation>,
+    selection_before: Option<SelectionState>,
+    selection_after: Option<SelectionState>,
+}
+
+/// The undo and redo stacks of a document.
+#[derive(Default, JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct EditingHistory {
+    undo: DomRefCell<Vec<UndoStep>>,
+    redo: DomRefCell<Vec<UndoStep>>,
+    /// The changes made by the command that is running, if any.
+    recording: DomRefCell<Option<Vec<Operation>>>,
+}
+
+impl EditingHistory {
+    fn start_recording(&self) {
+        *self.recording.borrow_mut() = Some(vec![]);
+    }
+
+    #[allow(unrooted_must_root)]
+    fn stop_recording(&self) -> Vec<Operation> {
+        self.recording.borrow_mut().take().unwrap_or_default()
+    }
+
+    #[allow(unrooted_must_root)]
+    fn push_undo_step(&self, step: UndoStep) {
+        let mut undo = self.undo.borrow_mut();
+        undo.push(step);
+        if undo.len() > MAX_UNDO_STEPS {
+            undo.remove(0);
+        }
+    }
+}
+
+/// Record a change to the DOM if a command of the document of `target` is
+/// running, so that the command can be undone.
+#[allow(unrooted_must_root)]
+pub fn record_mutation(target: &Node, mutation: &Mutation) {
+    let document = target.owner_doc();
+    if let Some(ref mut operations) = *document.editing_history().recording.borrow_mut() {
+        operations.push(Operation::new(target, mutation));
+    }
+}
+
+/// Run `command`, recording the changes it makes as a new undo step.
+#[allow(unrooted_must_root)]
+fn record_undo_step(document: &Document,
+                    selection: &Selection,
+                    range: &Range,
+                    host: &Element,
+                    command: Command,
+                    value: &str) -> ErrorResult {
+    let history = document.editing_history();
+    let selection_before = SelectionState::new(selection);
+    history.start_recording();
+    let result = perform(document, selection, range, host, command, value);
+    let operations = history.stop_recording();
+    // Keep what a failing command changed undoable too.
+    if !operations.is_empty() {
+        history.redo.borrow_mut().clear();
+        history.push_undo_step(UndoStep {
+            host: Dom::from_ref(host),
+            operations,
+            selection_before,
+            selection_after: SelectionState::new(selection),
+        });
+    }
+    result
+}
+
+/// Revert the last step of the undo stack, or of the redo stack when `redo`
+/// is true, and record the reverting changes as a step on the other one.
+#[allow(unrooted_must_root)]
+fn traverse_history(history: &EditingHistory, selection: &Selection, redo: bool) -> bool {
+    let (from, to) = if redo { (&history.redo, &history.undo) } else { (&history.undo, &history.redo) };
+    history.start_recording();
+    // The step stays on its stack while it is reverted, so that the nodes it
+    // holds on to are traced.
+    let result = match from.borrow().last() {
+        Some(step) => step.operations.iter().rev().map(Operation::revert).collect::<ErrorResult>(),
+        None => {
+            history.stop_recording();
+            return false;
+        },
+    };
+    let operations = history.stop_recording();
+    if result.is_err() {
+        // The content was changed by something else than a command since,
+        // so the history no longer applies to it.
+        history.undo.borrow_mut().clear();
+        history.redo.borrow_mut().clear();
+        return false;
+    }
+
+    let step = match from.borrow_mut().pop() {
+        Some(step) => step,
+        None => return false,
+    };
+    let restored = step.selection_before.as_ref().map_or(false, |state| {
+        selection.SetBaseAndExtent(&state.anchor, state.anchor_offset, &state.focus, state.focus_offset).is_ok()
+    });
+    if !restored {
+        selection.collapse_at(step.host.upcast(), 0);
+    }
+    to.borrow_mut().push(UndoStep {
+        host: step.host,
+        operations,
+        selection_before: step.selection_after,
+        selection_after: step.selection_before,
+    });
+    true
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#execcommand()>
+pub fn exec_command(document: &Document, command: Command, value: &str) -> bool {
+    if !query_command_enabled(document, command) {
+        return false;
+    }
+    if command == Command::CreateLink && value.is_empty() {
+        return false;
+    }
+    match editing_context(document) {
+        Some((selection, range, host)) => run(document, &selection, &range, &host, command, value),
+        None => false,
+    }
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandenabled()>
+pub fn query_command_enabled(document: &Document, command: Command) -> bool {
+    if editing_context(document).is_none() {
+        return false;
+    }
+    let history = document.editing_history();
+    match command {
+        Command::Undo => !history.undo.borrow().is_empty(),
+        Command::Redo => !history.redo.borrow().is_empty(),
+        _ => true,
+    }
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandindeterm()>
+pub fn query_command_indeterm(document: &Document, command: Command) -> bool {
+    format_state(document, command) == FormatState::Some
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandstate()>
+pub fn query_command_state(document: &Document, command: Command) -> bool {
+    format_state(document, command) == FormatState::All
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandvalue()>
+pub fn query_command_value(document: &Document, command: Command) -> DOMString {
+    if !command.is_format() {
+        return DOMString::new();
+    }
+    DOMString::from(if query_command_state(document, command) { "true" } else { "false" })
+}
+
+/// Handle a key pressed while the selection is in editable content.
+/// Returns whether the key was used for editing.
+pub fn handle_key(document: &Document, ch: Option<char>, key: Key, modifiers: KeyModifiers) -> bool {
+    let (selection, range, host) = match editing_context(document) {
+        Some(context) => context,
+        None => return false,
+    };
+    if !document.design_mode() && !host.focus_state() {
+        return false;
+    }
+
+    let control = is_control_key(modifiers);
+    let shift = modifiers.contains(KeyModifiers::SHIFT);
+    let (command, value) = match key {
+        Key::Backspace => (Command::Delete, String::new()),
+        Key::Delete => (Command::ForwardDelete, String::new()),
+        Key::Enter if shift => (Command::InsertLineBreak, String::new()),
+        Key::Enter => (Command::InsertParagraph, String::new()),
+        Key::Left | Key::Right if modifiers.is_empty() => {
+            move_caret(&selection, &range, key == Key::Right);
+            return true;
+        },
+        Key::A if control => (Command::SelectAll, String::new()),
+        Key::B if control => (Command::Bold, String::new()),
+        Key::I if control => (Command::Italic, String::new()),
+        Key::U if control => (Command::Underline, String::new()),
+        Key::Y if control => (Command::Redo, String::new()),
+        Key::Z if control && shift => (Command::Redo, String::new()),
+        Key::Z if control => (Command::Undo, String::new()),
+        Key::X if control => {
+            if range.Collapsed() {
+                return true;
+            }
+            let mut clipboard = document.window().upcast::<GlobalScope>().script_to_constellation_chan().clone();
+            clipboard.set_clipboard_contents(String::from(selection.Stringifier()));
+            (Command::Cut, String::new())
+        },
+        Key::V if control => {
+            let mut clipboard = document.window().upcast::<GlobalScope>().script_to_constellation_chan().clone();
+            (Command::Paste, clipboard.clipboard_contents())
+        },
+        _ => match ch {
+            Some(ch) if !ch.is_control() && !control && !modifiers.contains(KeyModifiers::ALT) => {
+                (Command::InsertText, ch.to_string())
+            },
+            _ => return false,
+        },
+    };
+
+    // https://w3c.github.io/input-events/#event-type-beforeinput
+    if command != Command::SelectAll &&
+       !fire_input_event(document, &host, "beforeinput", true, command, &value) {
+        return true;
+    }
+    run(document, &selection, &range, &host, command, &value);
+    true
+}
+
+/// Run `command` in `host` and fire the `input` event that follows.
+fn run(document: &Document,
+       selection: &Selection,
+       range: &Range,
+       host: &Element,
+       command: Command,
+       value: &str) -> bool {
+    let history = document.editing_history();
+    let done = match command {
+        Command::SelectAll => return selection.SelectAllChildren(host.upcast()).is_ok(),
+        Command::Undo => traverse_history(history, selection, false),
+        Command::Redo => traverse_history(history, selection, true),
+        _ => record_undo_step(document, selection, range, host, command, value).is_ok(),
+    };
+    if done {
+        // https://w3c.github.io/input-events/#event-type-input
+        fire_input_event(document, host, "input", false, command, value);
+    }
+    done
+}
+
+fn perform(document: &Document,
+           selection: &Selection,
+           range: &Range,
+           host: &Element,
+           command: Command,
+           value: &str) -> ErrorResult {
+    let host = host.upcast::<Node>();
+    match command {
+        Command::InsertText | Command::Paste => insert_text(document, selection, range, value),
+        Command::Cut | Command::Delete => delete(selection, range, host, false),
+        Command::ForwardDelete => delete(selection, range, host, true),
+        Command::InsertLineBreak => insert_line_break(document, selection, range),
+        Command::InsertParagraph => insert_paragraph(document, selection, range, host),
+        Command::Bold | Command::Italic | Command::StrikeThrough | Command::Underline => {
+            toggle_format(document, selection, range, host, command)
+        },
+        Command::CreateLink => create_link(document, selection, range, host, value),
+        Command::Redo | Command::SelectAll | Command::Undo => unreachable!(),
+    }
+}
+
+/// Fire an `InputEvent` at the editing host, returning whether it wasn't canceled.
+fn fire_input_event(document: &Document,
+                    host: &Element,
+                    type_: &str,
+                    cancelable: bool,
+                    command: Command,
+                    value: &str) -> bool {
+    let data = match command {
+        Command::InsertText | Command::Paste => Some(DOMString::from(value)),
+        _ => None,
+    };
+    let window = document.window();
+    let event = InputEvent::new(window,
+                                DOMString::from(type_),
+                                true,
+                                cancelable,
+                                Some(window),
+                                0,
+                                data,
+                                false,
+                                DOMString::from(command.input_type()));
+    let event = event.upcast::<Event>();
+    event.fire(host.upcast());
+    event.get_cancel_state() != EventDefault::Prevented
+}
+
+fn move_caret(selection: &Selection, range: &Range, forward: bool) {
+    if !range.Collapsed() {
+        let (node, offset) = if forward {
+            (range.EndContainer(), range.EndOffset())
+        } else {
+            (range.StartContainer(), range.StartOffset())
+        };
+        return selection.collapse_at(&node, offset);
+    }
+    selection.extend_by_character(forward);
+    if let Some(focus) = selection.GetFocusNode() {
+        selection.collapse_at(&focus, selection.FocusOffset());
+    }
+}
+
+fn insert_text(document: &Document, selection: &Selection, range: &Range, text: &str) -> ErrorResult {
+    if !range.Collapsed() {
+        range.DeleteContents()?;
+    }
+    let (node, offset) = (range.StartContainer(), range.StartOffset());
+    let length = text.encode_utf16().count() as u32;
+    match node.downcast::<CharacterData>() {
+        Some(data) if node.is::<Text>() => {
+            data.InsertData(offset, DOMString::from(text))?;
+            selection.collapse_at(&node, offset + length);
+        },
+        _ => {
+            let text_node = document.CreateTextNode(DOMString::from(text));
+            range.InsertNode(text_node.upcast())?;
+            selection.collapse_at(text_node.upcast(), length);
+        },
+    }
+    Ok(())
+}
+
+/// The length in UTF-16 code units of the character after `offset` in
+/// `data`, or before it when `forward` is false.
+fn char_length_at(data: &str, offset: u32, forward: bool) -> u32 {
+    let mut position = 0;
+    for c in data.chars() {
+        let length = c.len_utf16() as u32;
+        if forward && position >= offset {
+            return length;
+        }
+        position += length;
+        if !forward && position >= offset {
+            return length;
+        }
+    }
+    1
+}
+
+/// Whether `node` is something the caret can move over or delete.
+fn is_leaf(node: &Node) -> bool {
+    if node.is::<Text>() {
+        return node.len() > 0;
+    }
+    node.is::<HTMLBRElement>() || node.is::<HTMLHRElement>() || node.is::<HTMLImageElement>()
+}
+
+fn leaf_before(node: &Node, offset: u32, host: &Node) -> Option<DomRoot<Node>> {
+    let child = match offset.checked_sub(1).and_then(|index| node.children().nth(index as usize)) {
+        Some(child) => child,
+        None => return node.preceding_nodes(host).find(|node| is_leaf(node)),
+    };
+    let start = child.descending_last_children().last().unwrap_or(child);
+    iter::once(start.clone()).chain(start.preceding_nodes(host)).find(|node| is_leaf(node))
+}
+
+fn leaf_after(node: &Node, offset: u32, host: &Node) -> Option<DomRoot<Node>> {
+    match node.children().nth(offset as usize) {
+        Some(child) => iter::once(child.clone()).chain(child.following_nodes(host)).find(|node| is_leaf(node)),
+        None => {
+            let mut following = node.following_nodes(host);
+            let next = following.next_skipping_children();
+            next.into_iter().chain(following).find(|node| is_leaf(node))
+        },
+    }
+}
+
+fn is_block(element: &Element) -> bool {
+    if *element.namespace() != ns!(html) {
+        return false;
+    }
+    match *element.local_name() {
+        local_name!("address") | local_name!("blockquote") | local_name!("div") |
+        local_name!("h1") | local_name!("h2") | local_name!("h3") |
+        local_name!("h4") | local_name!("h5") | local_name!("h6") |
+        local_name!("li") | local_name!("p") | local_name!("pre") => true,
+        _ => false,
+    }
+}
+
+/// The innermost block `node` is in, below the editing host.
+fn enclosing_block(node: &Node, host: &Node) -> Option<DomRoot<Element>> {
+    node.inclusive_ancestors()
+        .take_while(|ancestor| &**ancestor != host)
+        .filter_map(DomRoot::downcast::<Element>)
+        .find(|element| is_block(element))
+}
+
+fn delete(selection: &Selection, range: &Range, host: &Node, forward: bool) -> ErrorResult {
+    if !range.Collapsed() {
+        return range.DeleteContents();
+    }
+
+    let (node, offset) = (range.StartContainer(), range.StartOffset());
+    if let Some(data) = node.downcast::<CharacterData>() {
+        if forward && offset < node.len() {
+            let length = char_length_at(&data.data(), offset, true);
+            data.DeleteData(offset, length)?;
+            selection.collapse_at(&node, offset);
+            return Ok(());
+        }
+        if !forward && offset > 0 {
+            let length = char_length_at(&data.data(), offset, false);
+            data.DeleteData(offset - length, length)?;
+            selection.collapse_at(&node, offset - length);
+            return Ok(());
+        }
+    }
+
+    let leaf = if forward { leaf_after(&node, offset, host) } else { leaf_before(&node, offset, host) };
+    let leaf = match leaf {
+        Some(leaf) => leaf,
+        None => return Ok(()),
+    };
+
+    // Deleting at the edge of a block merges it with its neighbour.
+    let block = enclosing_block(&node, host);
+    let leaf_block = enclosing_block(&leaf, host);
+    if block != leaf_block {
+        return if forward {
+            join_blocks(selection, block, leaf_block)
+        } else {
+            join_blocks(selection, leaf_block, block)
+        };
+    }
+
+    match leaf.downcast::<CharacterData>() {
+        Some(data) => {
+            let length = leaf.len();
+            if forward {
+                data.DeleteData(0, char_length_at(&data.data(), 0, true))?;
+                selection.collapse_at(&leaf, 0);
+            } else {
+                let char_length = char_length_at(&data.data(), length, false);
+                data.DeleteData(length - char_length, char_length)?;
+                selection.collapse_at(&leaf, length - char_length);
+            }
+        },
+        None => {
+            let parent = leaf.GetParentNode().ok_or(Error::NotFound)?;
+            let index = leaf.index();
+            leaf.remove_self();
+            selection.collapse_at(&parent, index);
+        },
+    }
+    Ok(())
+}
+
+/// Move the content of `second` to the end of `first`. A missing block
+/// stands for the inline content right before or after the other one.
+fn join_blocks(selection: &Selection,
+               first: Option<DomRoot<Element>>,
+               second: Option<DomRoot<Element>>) -> ErrorResult {
+    match (first, second) {
+        (Some(first), Some(second)) => {
+            let first = first.upcast::<Node>();
+            if let Some(last) = first.GetLastChild() {
+                if last.is::<HTMLBRElement>() {
+                    last.remove_self();
+                }
+            }
+            let offset = first.children_count();
+            let second = second.upcast::<Node>();
+            while let Some(child) = second.GetFirstChild() {
+                first.AppendChild(&child)?;
+            }
+            second.remove_self();
+            selection.collapse_at(first, offset);
+        },
+        (None, Some(second)) => {
+            let second = second.upcast::<Node>();
+            let parent = second.GetParentNode().ok_or(Error::NotFound)?;
+            let index = second.index();
+            while let Some(child) = second.GetFirstChild() {
+                parent.InsertBefore(&child, Some(second))?;
+            }
+            second.remove_self();
+            selection.collapse_at(&parent, index);
+        },
+        (Some(first), None) => {
+            let first = first.upcast::<Node>();
+            let offset = first.children_count();
+            while let Some(next) = first.GetNextSibling() {
+                if next.downcast::<Element>().map_or(false, is_block) {
+                    break;
+                }
+                if next.is::<HTMLBRElement>() {
+                    next.remove_self();
+                    break;
+                }
+                first.AppendChild(&next)?;
+            }
+            selection.collapse_at(first, offset);
+        },
+        (None, None) => {},
+    }
+    Ok(())
+}
+
+fn create_html_element(document: &Document, name: LocalName) -> DomRoot<Element> {
+    Element::create(QualName::new(None, ns!(html), name),
+                    None,
+                    document,
+                    ElementCreator::ScriptCreated,
+                    CustomElementCreationMode::Synchronous)
+}
+
+fn insert_line_break(document: &Document, selection: &Selection, range: &Range) -> ErrorResult {
+    if !range.Collapsed() {
+        range.DeleteContents()?;
+    }
+    let br = HTMLBRElement::new(local_name!("br"), None, document);
+    let br = br.upcast::<Node>();
+    range.InsertNode(br)?;
+    // A line break at the end of a block doesn't start a new line on its
+    // own, so it needs another one after it. Inserting it at the end of a
+    // text node leaves an empty one behind, which doesn't count.
+    let parent = br.GetParentNode().ok_or(Error::NotFound)?;
+    if !br.following_siblings().any(|sibling| sibling.traverse_preorder().any(|node| is_leaf(&node))) {
+        let extra = HTMLBRElement::new(local_name!("br"), None, document);
+        parent.AppendChild(extra.upcast())?;
+    }
+    selection.collapse_at(&parent, br.index() + 1);
+    Ok(())
+}
+
+fn insert_paragraph(document: &Document, selection: &Selection, range: &Range, host: &Node) -> ErrorResult {
+    if !range.Collapsed() {
+        range.DeleteContents()?;
+    }
+    let (node, offset) = (range.StartContainer(), range.StartOffset());
+    let block = match enclosing_block(&node, host) {
+        Some(block) => block,
+        None => return insert_line_break(document, selection, range),
+    };
+
+    // Move everything after the caret to a new block of the same kind.
+    let block_node = block.upcast::<Node>();
+    let tail = Range::new(document, &node, offset, block_node, block_node.len());
+    let contents = tail.ExtractContents()?;
+    let new_block = create_html_element(document, block.local_name().clone());
+    let new_block = new_block.upcast::<Node>();
+    new_block.AppendChild(contents.upcast())?;
+    let parent = block_node.GetParentNode().ok_or(Error::NotFound)?;
+    parent.InsertBefore(new_block, block_node.GetNextSibling().r())?;
+
+    // Empty blocks have no height, so give them a line break.
+    for block in &[block_node, new_block] {
+        if !block.traverse_preorder().any(|node| is_leaf(&node)) {
+            let br = HTMLBRElement::new(local_name!("br"), None, document);
+            block.AppendChild(br.upcast())?;
+        }
+    }
+
+    match new_block.traverse_preorder().find(|node| node.is::<Text>()) {
+        Some(text) => selection.collapse_at(&text, 0),
+        None => selection.collapse_at(new_block, 0),
+    }
+    Ok(())
+}
+
+/// Split the text nodes at the boundaries of `range` and return the ones it selects.
+fn split_selected_text(range: &Range) -> Fallible<Vec<DomRoot<Text>>> {
+    let mut result = vec![];
+    for (text, start, end) in range.text_nodes() {
+        if end < text.upcast::<Node>().len() {
+            text.SplitText(end)?;
+        }
+        result.push(if start > 0 { text.SplitText(start)? } else { text });
+    }
+    Ok(result)
+}
+
+fn select_texts(selection: &Selection, texts: &[DomRoot<Text>]) -> ErrorResult {
+    if let (Some(first), Some(last)) = (texts.first(), texts.last()) {
+        let last = last.upcast::<Node>();
+        selection.SetBaseAndExtent(first.upcast(), 0, last, last.len())?;
+    }
+    Ok(())
+}
+
+/// The elements between `node` and the editing host that apply the style of `command`.
+fn format_ancestors(node: &Node, host: &Node, command: Command) -> Vec<DomRoot<Element>> {
+    node.inclusive_ancestors()
+        .take_while(|ancestor| &**ancestor != host)
+        .filter_map(DomRoot::downcast::<Element>)
+        .filter(|element| command.is_format_element(element))
+        .collect()
+}
+
+#[derive(PartialEq)]
+enum FormatState {
+    None,
+    Some,
+    All,
+}
+
+fn format_state(document: &Document, command: Command) -> FormatState {
+    if !command.is_format() {
+        return FormatState::None;
+    }
+    let (_, range, host) = match editing_context(document) {
+        Some(context) => context,
+        None => return FormatState::None,
+    };
+    let host = host.upcast::<Node>();
+    if range.Collapsed() {
+        return if format_ancestors(&range.StartContainer(), host, command).is_empty() {
+            FormatState::None
+        } else {
+            FormatState::All
+        };
+    }
+    let texts = range.text_nodes();
+    let formatted = texts.iter().filter(|&&(ref text, _, _)| {
+        !format_ancestors(text.upcast(), host, command).is_empty()
+    }).count();
+    match formatted {
+        0 => FormatState::None,
+        count if count == texts.len() => FormatState::All,
+        _ => FormatState::Some,
+    }
+}
+
+fn wrap(node: &Node, wrapper: &Element) -> ErrorResult {
+    let parent = node.GetParentNode().ok_or(Error::NotFound)?;
+    parent.InsertBefore(wrapper.upcast(), Some(node))?;
+    wrapper.upcast::<Node>().AppendChild(node)?;
+    Ok(())
+}
+
+fn unwrap(element: &Element) -> ErrorResult {
+    let node = element.upcast::<Node>();
+    let parent = match node.GetParentNode() {
+        Some(parent) => parent,
+        None => return Ok(()),
+    };
+    while let Some(child) = node.GetFirstChild() {
+        parent.InsertBefore(&child, Some(node))?;
+    }
+    parent.RemoveChild(node)?;
+    Ok(())
+}
+
+fn toggle_format(document: &Document,
+                 selection: &Selection,
+                 range: &Range,
+                 host: &Node,
+                 command: Command) -> ErrorResult {
+    if range.Collapsed() {
+        return Ok(());
+    }
+    let texts = split_selected_text(range)?;
+    let all_formatted = texts.iter().all(|text| !format_ancestors(text.upcast(), host, command).is_empty());
+    for text in &texts {
+        let text = text.upcast::<Node>();
+        if all_formatted {
+            for element in format_ancestors(text, host, command) {
+                unwrap(&element)?;
+            }
+        } else if format_ancestors(text, host, command).is_empty() {
+            wrap(text, &create_html_element(document, command.format_element_name()))?;
+        }
+    }
+    select_texts(selection, &texts)
+}
+
+fn create_link(document: &Document,
+               selection: &Selection,
+               range: &Range,
+               host: &Node,
+               url: &str) -> ErrorResult {
+    let create_anchor = || {
+        let anchor = create_html_element(document, local_name!("a"));
+        anchor.set_string_attribute(&local_name!("href"), DOMString::from(url));
+        anchor
+    };
+
+    if range.Collapsed() {
+        let anchor = create_anchor();
+        let anchor_node = anchor.upcast::<Node>();
+        anchor_node.AppendChild(document.CreateTextNode(DOMString::from(url)).upcast())?;
+        range.InsertNode(anchor_node)?;
+        let parent = anchor_node.GetParentNode().ok_or(Error::NotFound)?;
+        selection.collapse_at(&parent, anchor_node.index() + 1);
+        return Ok(());
+    }
+
+    let texts = split_selected_text(range)?;
+    for text in &texts {
+        let text = text.upcast::<Node>();
+        match format_ancestors(text, host, Command::CreateLink).first() {
+            Some(anchor) => anchor.set_string_attribute(&local_name!("href"), DOMString::from(url)),
+            None => wrap(text, &create_anchor())?,
+        }
+    }
+    select_texts(selection, &texts)
+}
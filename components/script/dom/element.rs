@@ -11,11 +11,12 @@ use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::AttrBinding::AttrMethods;
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::ElementBinding;
-use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
+use dom::bindings::codegen::Bindings::ElementBinding::{ElementMethods, ShadowRootInit};
 use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use dom::bindings::codegen::Bindings::HTMLTemplateElementBinding::HTMLTemplateElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMode;
 use dom::bindings::codegen::Bindings::WindowBinding::{ScrollBehavior, ScrollToOptions};
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::UnionTypes::NodeOrString;
@@ -31,10 +32,12 @@ use dom::bindings::xmlname::XMLName::InvalidXMLName;
 use dom::characterdata::CharacterData;
 use dom::create::create_element;
 use dom::customelementregistry::{CallbackReaction, CustomElementDefinition, CustomElementReaction};
+use dom::customelementregistry::is_valid_custom_element_name;
 use dom::document::{Document, LayoutDocumentHelpers};
 use dom::documentfragment::DocumentFragment;
 use dom::domrect::DOMRect;
 use dom::domtokenlist::DOMTokenList;
+use dom::editing;
 use dom::event::Event;
 use dom::eventtarget::EventTarget;
 use dom::htmlanchorelement::HTMLAnchorElement;
@@ -56,6 +59,7 @@ use dom::htmllinkelement::HTMLLinkElement;
 use dom::htmlobjectelement::HTMLObjectElement;
 use dom::htmloptgroupelement::HTMLOptGroupElement;
 use dom::htmlselectelement::HTMLSelectElement;
+use dom::htmlslotelement::HTMLSlotElement;
 use dom::htmlstyleelement::HTMLStyleElement;
 use dom::htmltablecellelement::{HTMLTableCellElement, HTMLTableCellElementLayoutHelpers};
 use dom::htmltableelement::{HTMLTableElement, HTMLTableElementLayoutHelpers};
@@ -70,7 +74,9 @@ use dom::node::{NodeDamage, NodeFlags, UnbindContext};
 use dom::node::{document_from_node, window_from_node};
 use dom::nodelist::NodeList;
 use dom::promise::Promise;
-use dom::servoparser::ServoParser;
+use dom::servoparser::{ServoParser, xml};
+use dom::shadowroot::ShadowRoot;
+use dom::svgsvgelement::dirty_outermost_svg_element;
 use dom::text::Text;
 use dom::validation::Validatable;
 use dom::virtualmethods::{VirtualMethods, vtable_for};
@@ -121,8 +127,6 @@ use style::values::{CSSFloat, Either};
 use style::values::{specified, computed};
 use stylesheet_loader::StylesheetOwner;
 use task::TaskOnce;
-use xml5ever::serialize as xmlSerialize;
-use xml5ever::serialize::SerializeOpts as XmlSerializeOpts;
 use xml5ever::serialize::TraversalScope as XmlTraversalScope;
 use xml5ever::serialize::TraversalScope::ChildrenOnly as XmlChildrenOnly;
 use xml5ever::serialize::TraversalScope::IncludeNode as XmlIncludeNode;
@@ -404,6 +408,17 @@ impl Element {
                 box_.clone_overflow_y() == overflow_y::computed_value::T::Hidden
         })
     }
+
+    /// Computed value of overflow-x or overflow-y is anything but "visible",
+    /// i.e. the element clips its content.
+    pub fn has_overflow_clip(&self) -> bool {
+        self.style().map_or(false, |s| {
+            let box_ = s.get_box();
+
+            box_.clone_overflow_x() != overflow_x::computed_value::T::Visible ||
+                box_.clone_overflow_y() != overflow_y::computed_value::T::Visible
+        })
+    }
 }
 
 #[allow(unsafe_code)]
@@ -413,6 +428,7 @@ pub trait RawLayoutElementHelpers {
     unsafe fn get_attr_val_for_layout<'a>(&'a self, namespace: &Namespace, name: &LocalName)
                                       -> Option<&'a str>;
     unsafe fn get_attr_vals_for_layout<'a>(&'a self, name: &LocalName) -> Vec<&'a AttrValue>;
+    unsafe fn get_attrs_for_layout<'a>(&'a self) -> Vec<(&'a Namespace, LocalName, &'a str)>;
 }
 
 #[inline]
@@ -458,6 +474,15 @@ impl RawLayoutElementHelpers for Element {
             }
         }).collect()
     }
+
+    #[inline]
+    unsafe fn get_attrs_for_layout<'a>(&'a self) -> Vec<(&'a Namespace, LocalName, &'a str)> {
+        let attrs = self.attrs.borrow_for_layout();
+        attrs.iter().map(|attr| {
+            let attr = attr.to_layout();
+            ((*attr.unsafe_get()).namespace(), attr.local_name_atom_forever(), attr.value_ref_forever())
+        }).collect()
+    }
 }
 
 pub trait LayoutElementHelpers {
@@ -465,6 +490,12 @@ pub trait LayoutElementHelpers {
     unsafe fn has_class_for_layout(&self, name: &Atom, case_sensitivity: CaseSensitivity) -> bool;
     #[allow(unsafe_code)]
     unsafe fn get_classes_for_layout(&self) -> Option<&'static [Atom]>;
+    #[allow(unsafe_code)]
+    unsafe fn has_part_for_layout(&self, name: &Atom) -> bool;
+    #[allow(unsafe_code)]
+    unsafe fn has_part_attr_for_layout(&self) -> bool;
+    #[allow(unsafe_code)]
+    unsafe fn get_shadow_root_for_layout(&self) -> Option<LayoutDom<ShadowRoot>>;
 
     #[allow(unsafe_code)]
     unsafe fn synthesize_presentational_hints_for_legacy_attributes<V>(&self, &mut V)
@@ -503,6 +534,29 @@ impl LayoutElementHelpers for LayoutDom<Element> {
             .map(|attr| attr.value_tokens_forever().unwrap())
     }
 
+    #[allow(unsafe_code)]
+    #[inline]
+    unsafe fn has_part_for_layout(&self, name: &Atom) -> bool {
+        get_attr_for_layout(&*self.unsafe_get(), &ns!(), &LocalName::from("part")).map_or(false, |attr| {
+            attr.value_tokens_forever().unwrap().iter().any(|atom| atom == name)
+        })
+    }
+
+    #[allow(unsafe_code)]
+    #[inline]
+    unsafe fn has_part_attr_for_layout(&self) -> bool {
+        get_attr_for_layout(&*self.unsafe_get(), &ns!(), &LocalName::from("part")).map_or(false, |attr| {
+            !attr.value_tokens_forever().unwrap().is_empty()
+        })
+    }
+
+    #[allow(unsafe_code)]
+    #[inline]
+    unsafe fn get_shadow_root_for_layout(&self) -> Option<LayoutDom<ShadowRoot>> {
+        let rare_data = (*self.unsafe_get()).upcast::<Node>().rare_data().get();
+        rare_data.and_then(|data| data.shadow_root.get_inner_as_layout())
+    }
+
     #[allow(unsafe_code)]
     unsafe fn synthesize_presentational_hints_for_legacy_attributes<V>(&self, hints: &mut V)
         where V: Push<ApplicableDeclarationBlock>
@@ -1038,17 +1092,9 @@ impl Element {
         }
     }
 
+    /// <https://w3c.github.io/DOM-Parsing/#dfn-fragment-serializing-algorithm>
     pub fn xmlSerialize(&self, traversal_scope: XmlTraversalScope) -> Fallible<DOMString> {
-        let mut writer = vec![];
-        match xmlSerialize::serialize(&mut writer,
-                        &self.upcast::<Node>(),
-                        XmlSerializeOpts {
-                            traversal_scope: traversal_scope,
-                            ..Default::default()
-                        }) {
-            Ok(()) => Ok(DOMString::from(String::from_utf8(writer).unwrap())),
-            Err(_) => panic!("Cannot serialize element"),
-        }
+        xml::serialize(self.upcast(), traversal_scope, true)
     }
 
     pub fn root_element(&self) -> DomRoot<Element> {
@@ -1121,7 +1167,7 @@ impl Element {
             NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLTextAreaElement)) => {
                 true
             }
-            _ => false,
+            _ => editing::is_editing_host(self),
         }
     }
 
@@ -1535,13 +1581,17 @@ impl Element {
     pub fn parse_fragment(&self, markup: DOMString) -> Fallible<DomRoot<DocumentFragment>> {
         // Steps 1-2.
         let context_document = document_from_node(self);
-        // TODO(#11995): XML case.
-        let new_children = ServoParser::parse_html_fragment(self, markup);
         // Step 3.
         let fragment = DocumentFragment::new(&context_document);
         // Step 4.
-        for child in new_children {
-            fragment.upcast::<Node>().AppendChild(&child).unwrap();
+        if context_document.is_html_document() {
+            for child in ServoParser::parse_html_fragment(self, markup) {
+                fragment.upcast::<Node>().AppendChild(&child).unwrap();
+            }
+        } else {
+            for child in ServoParser::parse_xml_fragment(self, markup)? {
+                fragment.upcast::<Node>().AppendChild(&child).unwrap();
+            }
         }
         // Step 5.
         Ok(fragment)
@@ -1636,6 +1686,23 @@ impl ElementMethods for Element {
         self.class_list.or_init(|| DOMTokenList::new(self, &local_name!("class")))
     }
 
+    // https://dom.spec.whatwg.org/#dom-element-slot
+    make_getter!(Slot, "slot");
+
+    // https://dom.spec.whatwg.org/#dom-element-slot
+    make_setter!(SetSlot, "slot");
+
+    // https://drafts.csswg.org/css-shadow-parts/#dom-element-part
+    fn Part(&self) -> DomRoot<DOMTokenList> {
+        let rare_data = self.upcast::<Node>().rare_data().ensure();
+        rare_data.part_list.or_init(|| DOMTokenList::new(self, &LocalName::from("part")))
+    }
+
+    // https://dom.spec.whatwg.org/#dom-slotable-assignedslot
+    fn GetAssignedSlot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        HTMLSlotElement::find_a_slot(self.upcast(), true)
+    }
+
     // https://dom.spec.whatwg.org/#dom-element-attributes
     fn Attributes(&self) -> DomRoot<NamedNodeMap> {
         self.attr_list.or_init(|| NamedNodeMap::new(&window_from_node(self), self))
@@ -2213,7 +2280,7 @@ impl ElementMethods for Element {
             NodeTypeId::Document(_) => return Err(Error::NoModificationAllowed),
 
             // Step 4.
-            NodeTypeId::DocumentFragment => {
+            NodeTypeId::DocumentFragment(_) => {
                 let body_elem = Element::create(QualName::new(None, ns!(html), local_name!("body")),
                                                 None,
                                                 &context_document,
@@ -2390,6 +2457,52 @@ impl ElementMethods for Element {
         self.insert_adjacent(position, fragment.upcast()).map(|_| ())
     }
 
+    // https://dom.spec.whatwg.org/#dom-element-attachshadow
+    fn AttachShadow(&self, init: &ShadowRootInit) -> Fallible<DomRoot<ShadowRoot>> {
+        // Steps 1-3.
+        if !self.can_attach_shadow() {
+            return Err(Error::NotSupported);
+        }
+        let is = self.get_is();
+        if is_valid_custom_element_name(&self.local_name) || is.is_some() {
+            let definition = document_from_node(self)
+                .lookup_custom_element_definition(&self.namespace, &self.local_name, is.as_ref());
+            if definition.map_or(false, |definition| definition.disable_shadow) {
+                return Err(Error::NotSupported);
+            }
+        }
+
+        // Step 4.
+        if self.is_shadow_host() {
+            return Err(Error::InvalidState);
+        }
+
+        // Steps 5-7.
+        let shadow_root = ShadowRoot::new(self, init.mode);
+        self.upcast::<Node>().rare_data().ensure().shadow_root.set(Some(&shadow_root));
+        if self.is_connected() {
+            document_from_node(self).register_shadow_root(&shadow_root);
+        }
+
+        // The children of this element stop being part of the flat tree until
+        // they get assigned to a slot.
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        for child in self.upcast::<Node>().children() {
+            if child.is::<Element>() {
+                child.dirty(NodeDamage::OtherNodeDamage);
+            }
+        }
+
+        // Step 8.
+        Ok(shadow_root)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-element-shadowroot
+    fn GetShadowRoot(&self) -> Option<DomRoot<ShadowRoot>> {
+        // Steps 1-3.
+        self.shadow_root().filter(|shadow_root| shadow_root.mode() == ShadowRootMode::Open)
+    }
+
     // check-tidy: no specs after this line
     fn EnterFormalActivationState(&self) -> ErrorResult {
         match self.as_maybe_activatable() {
@@ -2509,6 +2622,13 @@ impl VirtualMethods for Element {
                     }
                 }
             },
+            &local_name!("slot") if attr.namespace() == &ns!() => {
+                // https://dom.spec.whatwg.org/#shadow-tree-slots
+                if let Some(slot) = node.assigned_slot() {
+                    slot.assign_slottables();
+                }
+                HTMLSlotElement::assign_a_slot(node);
+            },
             _ => {
                 // FIXME(emilio): This is pretty dubious, and should be done in
                 // the relevant super-classes.
@@ -2519,6 +2639,11 @@ impl VirtualMethods for Element {
             },
         };
 
+        // Inline SVG is rendered from the attributes of all of its elements.
+        if *self.namespace() == ns!(svg) {
+            dirty_outermost_svg_element(node);
+        }
+
         // Make sure we rev the version even if we didn't dirty the node. If we
         // don't do this, various attribute-dependent htmlcollections (like those
         // generated by getElementsByClassName) might become stale.
@@ -2529,6 +2654,7 @@ impl VirtualMethods for Element {
         match name {
             &local_name!("id") => AttrValue::from_atomic(value.into()),
             &local_name!("class") => AttrValue::from_serialized_tokenlist(value.into()),
+            _ if &**name == "part" => AttrValue::from_serialized_tokenlist(value.into()),
             _ => self.super_type().unwrap().parse_plain_attribute(name, value),
         }
     }
@@ -2542,6 +2668,12 @@ impl VirtualMethods for Element {
             f.bind_form_control_to_tree();
         }
 
+        if let Some(shadow_root) = self.shadow_root() {
+            if self.is_connected() {
+                shadow_root.set_connected(true);
+            }
+        }
+
         if !tree_in_doc {
             return;
         }
@@ -2561,6 +2693,12 @@ impl VirtualMethods for Element {
             f.unbind_form_control_from_tree();
         }
 
+        if let Some(shadow_root) = self.shadow_root() {
+            if context.tree_connected {
+                shadow_root.set_connected(false);
+            }
+        }
+
         if !context.tree_in_doc {
             return;
         }
@@ -2602,6 +2740,11 @@ impl VirtualMethods for Element {
                 }
             }
         }
+
+        // Inline SVG is rendered from its whole subtree, text included.
+        if *self.namespace() == ns!(svg) {
+            dirty_outermost_svg_element(self.upcast::<Node>());
+        }
     }
 
     fn adopting_steps(&self, old_doc: &Document) {
@@ -2844,6 +2987,14 @@ impl Element {
                 let element = self.downcast::<HTMLTextAreaElement>().unwrap();
                 Some(element as &Validatable)
             },
+            NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLElement)) => {
+                let element = self.downcast::<HTMLElement>().unwrap();
+                if element.is_form_associated_custom_element() {
+                    Some(element as &Validatable)
+                } else {
+                    None
+                }
+            },
             _ => {
                 None
             }
@@ -3038,9 +3189,34 @@ impl Element {
 
     /// <https://dom.spec.whatwg.org/#connected>
     pub fn is_connected(&self) -> bool {
-        let node = self.upcast::<Node>();
-        let root = node.GetRootNode();
-        root.is::<Document>()
+        self.upcast::<Node>().is_connected()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-element-shadow-root>
+    pub fn shadow_root(&self) -> Option<DomRoot<ShadowRoot>> {
+        self.upcast::<Node>().rare_data().get().and_then(|data| data.shadow_root.get())
+    }
+
+    /// <https://dom.spec.whatwg.org/#element-shadow-host>
+    pub fn is_shadow_host(&self) -> bool {
+        self.shadow_root().is_some()
+    }
+
+    /// Whether `attachShadow` may be called on this element, per step 2 and
+    /// 3 of <https://dom.spec.whatwg.org/#dom-element-attachshadow>.
+    fn can_attach_shadow(&self) -> bool {
+        if self.namespace != ns!(html) {
+            return false;
+        }
+        match self.local_name {
+            local_name!("article") | local_name!("aside") | local_name!("blockquote") |
+            local_name!("body") | local_name!("div") | local_name!("footer") |
+            local_name!("h1") | local_name!("h2") | local_name!("h3") |
+            local_name!("h4") | local_name!("h5") | local_name!("h6") |
+            local_name!("header") | local_name!("main") | local_name!("nav") |
+            local_name!("p") | local_name!("section") | local_name!("span") => true,
+            ref name => is_valid_custom_element_name(name),
+        }
     }
 }
 
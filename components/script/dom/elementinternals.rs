@@ -0,0 +1,236 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ElementInternalsBinding::{self, ElementInternalsMethods};
+use dom::bindings::codegen::Bindings::ElementInternalsBinding::ValidityStateFlags;
+use dom::bindings::codegen::UnionTypes::FileOrUSVString;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::element::Element;
+use dom::eventtarget::EventTarget;
+use dom::file::File;
+use dom::htmldatalistelement::HTMLDataListElement;
+use dom::htmlelement::HTMLElement;
+use dom::htmlformelement::{FormDatum, FormDatumValue, HTMLFormElement};
+use dom::node::{Node, window_from_node};
+use dom::nodelist::NodeList;
+use dom::validitystate::{ValidationFlags, ValidityState};
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+/// The value a form-associated custom element contributes to its form's
+/// data set.
+/// <https://html.spec.whatwg.org/multipage/#face-submission-value>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+enum SubmissionValue {
+    None,
+    File(Dom<File>),
+    USVString(String),
+}
+
+// https://html.spec.whatwg.org/multipage/#elementinternals
+#[dom_struct]
+pub struct ElementInternals {
+    reflector_: Reflector,
+    /// <https://html.spec.whatwg.org/multipage/#internals-target>
+    target_element: Dom<HTMLElement>,
+    form_owner: MutNullableDom<HTMLFormElement>,
+    submission_value: DomRefCell<SubmissionValue>,
+    validity_flags: Cell<ValidationFlags>,
+    validation_message: DomRefCell<DOMString>,
+    validation_anchor: MutNullableDom<HTMLElement>,
+}
+
+impl ElementInternals {
+    fn new_inherited(target_element: &HTMLElement) -> ElementInternals {
+        ElementInternals {
+            reflector_: Reflector::new(),
+            target_element: Dom::from_ref(target_element),
+            form_owner: MutNullableDom::new(None),
+            submission_value: DomRefCell::new(SubmissionValue::None),
+            validity_flags: Cell::new(ValidationFlags::empty()),
+            validation_message: DomRefCell::new(DOMString::new()),
+            validation_anchor: MutNullableDom::new(None),
+        }
+    }
+
+    pub fn new(target_element: &HTMLElement) -> DomRoot<ElementInternals> {
+        let window = window_from_node(target_element);
+        reflect_dom_object(Box::new(ElementInternals::new_inherited(target_element)),
+                           &window,
+                           ElementInternalsBinding::Wrap)
+    }
+
+    fn check_form_associated(&self) -> ErrorResult {
+        if self.target_element.is_form_associated_custom_element() {
+            Ok(())
+        } else {
+            Err(Error::NotSupported)
+        }
+    }
+
+    pub fn form_owner(&self) -> Option<DomRoot<HTMLFormElement>> {
+        self.form_owner.get()
+    }
+
+    pub fn set_form_owner(&self, form: Option<&HTMLFormElement>) {
+        self.form_owner.set(form);
+    }
+
+    /// Whether the target element satisfies every constraint in `flags`.
+    pub fn satisfies_constraints(&self, flags: ValidationFlags) -> bool {
+        !self.validity_flags.get().intersects(flags)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#candidate-for-constraint-validation>
+    pub fn is_candidate_for_constraint_validation(&self) -> bool {
+        let element = self.target_element.upcast::<Element>();
+        !element.disabled_state() &&
+            !element.upcast::<Node>().ancestors().any(|a| a.is::<HTMLDataListElement>())
+    }
+
+    /// Appends the entry for the target element to a form data set.
+    /// <https://html.spec.whatwg.org/multipage/#constructing-the-form-data-set>
+    /// Step 5.12
+    pub fn push_form_data(&self, data_set: &mut Vec<FormDatum>) {
+        let name = self.target_element.upcast::<Element>().get_string_attribute(&local_name!("name"));
+        if name.is_empty() {
+            return;
+        }
+        let value = match *self.submission_value.borrow() {
+            SubmissionValue::None => return,
+            SubmissionValue::File(ref file) => FormDatumValue::File(DomRoot::from_ref(&**file)),
+            SubmissionValue::USVString(ref string) => FormDatumValue::String(DOMString::from(string.clone())),
+        };
+        data_set.push(FormDatum {
+            ty: DOMString::from(self.target_element.upcast::<Element>().local_name().to_string()),
+            name: name,
+            value: value,
+        });
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#check-validity-steps>
+    fn check_validity(&self) -> bool {
+        if !self.is_candidate_for_constraint_validation() ||
+           self.satisfies_constraints(ValidationFlags::all()) {
+            return true;
+        }
+        self.target_element.upcast::<EventTarget>().fire_cancelable_event(atom!("invalid"));
+        false
+    }
+}
+
+impl ElementInternalsMethods for ElementInternals {
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-setformvalue
+    fn SetFormValue(&self, value: Option<FileOrUSVString>) -> ErrorResult {
+        // Step 2.
+        self.check_form_associated()?;
+
+        // Step 3.
+        *self.submission_value.borrow_mut() = match value {
+            None => SubmissionValue::None,
+            Some(FileOrUSVString::File(file)) => SubmissionValue::File(Dom::from_ref(&*file)),
+            Some(FileOrUSVString::USVString(string)) => SubmissionValue::USVString(string.0),
+        };
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-form
+    fn GetForm(&self) -> Fallible<Option<DomRoot<HTMLFormElement>>> {
+        self.check_form_associated()?;
+        Ok(self.form_owner())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-setvalidity
+    fn SetValidity(&self,
+                   flags: &ValidityStateFlags,
+                   message: Option<DOMString>,
+                   anchor: Option<&HTMLElement>) -> ErrorResult {
+        // Step 2.
+        self.check_form_associated()?;
+
+        let mut validity_flags = ValidationFlags::empty();
+        validity_flags.set(ValidationFlags::VALUE_MISSING, flags.valueMissing);
+        validity_flags.set(ValidationFlags::TYPE_MISMATCH, flags.typeMismatch);
+        validity_flags.set(ValidationFlags::PATTERN_MISMATCH, flags.patternMismatch);
+        validity_flags.set(ValidationFlags::TOO_LONG, flags.tooLong);
+        validity_flags.set(ValidationFlags::TOO_SHORT, flags.tooShort);
+        validity_flags.set(ValidationFlags::RANGE_UNDERFLOW, flags.rangeUnderflow);
+        validity_flags.set(ValidationFlags::RANGE_OVERFLOW, flags.rangeOverflow);
+        validity_flags.set(ValidationFlags::STEP_MISMATCH, flags.stepMismatch);
+        validity_flags.set(ValidationFlags::BAD_INPUT, flags.badInput);
+        validity_flags.set(ValidationFlags::CUSTOM_ERROR, flags.customError);
+
+        // Step 3.
+        let message = message.unwrap_or_default();
+        if !validity_flags.is_empty() && message.is_empty() {
+            return Err(Error::Type("A validation message is required when a flag is set".to_owned()));
+        }
+
+        // Step 4.
+        if let Some(anchor) = anchor {
+            let target = self.target_element.upcast::<Node>();
+            if anchor == &*self.target_element ||
+               !target.is_shadow_including_inclusive_ancestor_of(anchor.upcast()) {
+                return Err(Error::NotFound);
+            }
+        }
+
+        // Steps 5-6.
+        self.validity_flags.set(validity_flags);
+        *self.validation_message.borrow_mut() = if validity_flags.is_empty() {
+            DOMString::new()
+        } else {
+            message
+        };
+
+        // Step 7.
+        self.validation_anchor.set(anchor);
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-willvalidate
+    fn WillValidate(&self) -> Fallible<bool> {
+        self.check_form_associated()?;
+        Ok(self.is_candidate_for_constraint_validation())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-validity
+    fn Validity(&self) -> Fallible<DomRoot<ValidityState>> {
+        self.check_form_associated()?;
+        let window = window_from_node(&*self.target_element);
+        Ok(ValidityState::new(&window, self.target_element.upcast()))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-validationmessage
+    fn ValidationMessage(&self) -> Fallible<DOMString> {
+        self.check_form_associated()?;
+        Ok(self.validation_message.borrow().clone())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-checkvalidity
+    fn CheckValidity(&self) -> Fallible<bool> {
+        self.check_form_associated()?;
+        Ok(self.check_validity())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-reportvalidity
+    fn ReportValidity(&self) -> Fallible<bool> {
+        self.check_form_associated()?;
+        // TODO: Report the problem to the user, focusing the validation
+        // anchor if one was provided.
+        Ok(self.check_validity())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-elementinternals-labels
+    fn Labels(&self) -> Fallible<DomRoot<NodeList>> {
+        self.check_form_associated()?;
+        Ok(self.target_element.labels())
+    }
+}
@@ -7,16 +7,19 @@ use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::EventBinding;
 use dom::bindings::codegen::Bindings::EventBinding::{EventConstants, EventMethods};
+use dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMode;
 use dom::bindings::error::Fallible;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
-use dom::bindings::root::{DomRoot, MutNullableDom, RootedReference};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::document::Document;
+use dom::element::Element;
 use dom::eventtarget::{CompiledEventListener, EventTarget, ListenerPhase};
 use dom::globalscope::GlobalScope;
 use dom::node::Node;
+use dom::shadowroot::ShadowRoot;
 use dom::virtualmethods::vtable_for;
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -38,9 +41,16 @@ pub struct Event {
     stop_immediate: Cell<bool>,
     cancelable: Cell<bool>,
     bubbles: Cell<bool>,
+    /// <https://dom.spec.whatwg.org/#composed-flag>
+    composed: Cell<bool>,
     trusted: Cell<bool>,
     dispatching: Cell<bool>,
     initialized: Cell<bool>,
+    /// The invocation targets of the event while it is being dispatched,
+    /// starting with its target.
+    ///
+    /// <https://dom.spec.whatwg.org/#event-path>
+    path: DomRefCell<Vec<Dom<EventTarget>>>,
     timestamp: u64,
 }
 
@@ -57,9 +67,11 @@ impl Event {
             stop_immediate: Cell::new(false),
             cancelable: Cell::new(false),
             bubbles: Cell::new(false),
+            composed: Cell::new(false),
             trusted: Cell::new(false),
             dispatching: Cell::new(false),
             initialized: Cell::new(false),
+            path: DomRefCell::new(vec![]),
             timestamp: time::get_time().sec as u64,
         }
     }
@@ -84,7 +96,9 @@ impl Event {
                        init: &EventBinding::EventInit) -> Fallible<DomRoot<Event>> {
         let bubbles = EventBubbles::from(init.bubbles);
         let cancelable = EventCancelable::from(init.cancelable);
-        Ok(Event::new(global, Atom::from(type_), bubbles, cancelable))
+        let event = Event::new(global, Atom::from(type_), bubbles, cancelable);
+        event.set_composed(init.composed);
+        Ok(event)
     }
 
     pub fn init_event(&self, type_: Atom, bubbles: bool, cancelable: bool) {
@@ -119,8 +133,11 @@ impl Event {
         // The "invoke" algorithm is only used on `target` separately,
         // so we don't put it in the path.
         if let Some(target_node) = target.downcast::<Node>() {
-            for ancestor in target_node.ancestors() {
-                event_path.push(DomRoot::from_ref(ancestor.upcast::<EventTarget>()));
+            let target_root = target_node.root_node();
+            let mut parent = self.get_the_parent(target_node, &target_root);
+            while let Some(node) = parent {
+                event_path.push(DomRoot::from_ref(node.upcast::<EventTarget>()));
+                parent = self.get_the_parent(&node, &target_root);
             }
             let top_most_ancestor_or_target =
                 event_path.last().cloned().unwrap_or(DomRoot::from_ref(target));
@@ -133,6 +150,23 @@ impl Event {
         event_path
     }
 
+    /// The get the parent algorithm of nodes and shadow roots, where
+    /// `target_root` is the root of the target of this event.
+    ///
+    /// <https://dom.spec.whatwg.org/#get-the-parent>
+    fn get_the_parent(&self, node: &Node, target_root: &Node) -> Option<DomRoot<Node>> {
+        if let Some(shadow_root) = node.downcast::<ShadowRoot>() {
+            if !self.composed.get() && node == target_root {
+                return None;
+            }
+            return Some(DomRoot::upcast(shadow_root.host()));
+        }
+        if let Some(slot) = node.assigned_slot() {
+            return Some(DomRoot::upcast(slot));
+        }
+        node.GetParentNode()
+    }
+
     // https://dom.spec.whatwg.org/#concept-event-dispatch
     pub fn dispatch(&self,
                     target: &EventTarget,
@@ -163,20 +197,31 @@ impl Event {
         // Step 3-4.
         let path = self.construct_event_path(&target);
         rooted_vec!(let event_path <- path.into_iter());
+        {
+            let mut full_path = self.path.borrow_mut();
+            full_path.push(Dom::from_ref(target));
+            full_path.extend(event_path.iter().map(|item| Dom::from_ref(&**item)));
+        }
         // Steps 5-9. In a separate function to short-circuit various things easily.
-        dispatch_to_listeners(self, target, event_path.r());
-
-        // Default action.
-        if let Some(target) = self.GetTarget() {
-            if let Some(node) = target.downcast::<Node>() {
-                let vtable = vtable_for(&node);
-                vtable.handle_event(self);
-            }
+        dispatch_to_listeners(self, target, target_override.is_none(), event_path.r());
+
+        // Default action. This runs on the original target, which may be
+        // inside a shadow tree.
+        let original_target = target_override.unwrap_or(target);
+        self.target.set(Some(original_target));
+        if let Some(node) = original_target.downcast::<Node>() {
+            let vtable = vtable_for(&node);
+            vtable.handle_event(self);
         }
 
         // Step 10-12.
         self.clear_dispatching_flags();
 
+        // Step 13. Don't leak nodes in shadow trees through the target.
+        if target.downcast::<Node>().map_or(false, |node| node.is_in_shadow_tree()) {
+            self.target.set(None);
+        }
+
         // Step 14.
         self.status()
     }
@@ -203,6 +248,7 @@ impl Event {
         self.stop_immediate.set(false);
         self.phase.set(EventPhase::None);
         self.current_target.set(None);
+        self.path.borrow_mut().clear();
     }
 
     #[inline]
@@ -229,6 +275,10 @@ impl Event {
         self.trusted.set(trusted);
     }
 
+    pub fn set_composed(&self, composed: bool) {
+        self.composed.set(composed);
+    }
+
     // https://html.spec.whatwg.org/multipage/#fire-a-simple-event
     pub fn fire(&self, target: &EventTarget) -> EventStatus {
         self.set_trusted(true);
@@ -307,6 +357,66 @@ impl EventMethods for Event {
     fn IsTrusted(&self) -> bool {
         self.trusted.get()
     }
+
+    // https://dom.spec.whatwg.org/#dom-event-composed
+    fn Composed(&self) -> bool {
+        self.composed.get()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-composedpath
+    fn ComposedPath(&self) -> Vec<DomRoot<EventTarget>> {
+        // Steps 1-3.
+        let current_target = match self.current_target.get() {
+            Some(current_target) => current_target,
+            None => return vec![],
+        };
+
+        // Steps 4-12, without keeping track of the hidden subtree levels, by
+        // checking every item against the current target instead.
+        self.path.borrow()
+            .iter()
+            .filter(|item| !is_closed_shadow_hidden(item, &current_target))
+            .map(|item| DomRoot::from_ref(&**item))
+            .collect()
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#concept-closed-shadow-hidden>
+fn is_closed_shadow_hidden(a: &EventTarget, b: &EventTarget) -> bool {
+    let a = match a.downcast::<Node>() {
+        Some(a) => a,
+        None => return false,
+    };
+    let root = a.root_node();
+    let shadow_root = match root.downcast::<ShadowRoot>() {
+        Some(shadow_root) => shadow_root,
+        None => return false,
+    };
+    if b.downcast::<Node>().map_or(false, |b| root.is_shadow_including_inclusive_ancestor_of(b)) {
+        return false;
+    }
+    shadow_root.mode() == ShadowRootMode::Closed ||
+        is_closed_shadow_hidden(shadow_root.host().upcast(), b)
+}
+
+/// <https://dom.spec.whatwg.org/#retarget>
+fn retarget(a: &EventTarget, b: &EventTarget) -> DomRoot<EventTarget> {
+    let mut a = DomRoot::from_ref(a);
+    while let Some(host) = retargeting_host(&a, b) {
+        a = DomRoot::upcast(host);
+    }
+    a
+}
+
+/// The host of the shadow root of `a`, if `a` is in a shadow tree that is
+/// not a shadow-including inclusive ancestor of `b`.
+fn retargeting_host(a: &EventTarget, b: &EventTarget) -> Option<DomRoot<Element>> {
+    let root = a.downcast::<Node>()?.root_node();
+    let shadow_root = root.downcast::<ShadowRoot>()?;
+    if b.downcast::<Node>().map_or(false, |b| root.is_shadow_including_inclusive_ancestor_of(b)) {
+        return None;
+    }
+    Some(shadow_root.host())
 }
 
 #[derive(Clone, Copy, MallocSizeOf, PartialEq)]
@@ -427,7 +537,10 @@ impl TaskOnce for SimpleEventTask {
 
 // See dispatch_event.
 // https://dom.spec.whatwg.org/#concept-event-dispatch
-fn dispatch_to_listeners(event: &Event, target: &EventTarget, event_path: &[&EventTarget]) {
+fn dispatch_to_listeners(event: &Event,
+                         target: &EventTarget,
+                         retarget_path: bool,
+                         event_path: &[&EventTarget]) {
     assert!(!event.stop_propagation.get());
     assert!(!event.stop_immediate.get());
 
@@ -447,6 +560,9 @@ fn dispatch_to_listeners(event: &Event, target: &EventTarget, event_path: &[&Eve
 
     // Step 6.
     for object in event_path.iter().rev() {
+        if retarget_path {
+            event.target.set(Some(&retarget(target, object)));
+        }
         invoke(window.r(), object, event, Some(ListenerPhase::Capturing));
         if event.stop_propagation.get() {
             return;
@@ -459,6 +575,9 @@ fn dispatch_to_listeners(event: &Event, target: &EventTarget, event_path: &[&Eve
     event.phase.set(EventPhase::AtTarget);
 
     // Step 8.
+    if retarget_path {
+        event.target.set(Some(target));
+    }
     invoke(window.r(), target, event, None);
     if event.stop_propagation.get() {
         return;
@@ -475,6 +594,9 @@ fn dispatch_to_listeners(event: &Event, target: &EventTarget, event_path: &[&Eve
 
     // Step 9.2.
     for object in event_path {
+        if retarget_path {
+            event.target.set(Some(&retarget(target, object)));
+        }
         invoke(window.r(), object, event, Some(ListenerPhase::Bubbling));
         if event.stop_propagation.get() {
             return;
@@ -543,6 +665,7 @@ impl Default for EventBinding::EventInit {
         EventBinding::EventInit {
             bubbles: false,
             cancelable: false,
+            composed: false,
         }
     }
 }
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::abortsignal::AbortAlgorithm;
 use dom::bindings::callback::{CallbackContainer, ExceptionHandling, CallbackFunction};
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::ErrorEventBinding::ErrorEventMethods;
@@ -20,8 +21,9 @@ use dom::bindings::codegen::UnionTypes::EventOrString;
 use dom::bindings::error::{Error, Fallible, report_pending_exception};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
-use dom::bindings::root::DomRoot;
+use dom::bindings::root::{Dom, DomRoot};
 use dom::bindings::str::DOMString;
+use dom::broadcastchannel::BroadcastChannel;
 use dom::element::Element;
 use dom::errorevent::ErrorEvent;
 use dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
@@ -322,39 +324,51 @@ impl EventTarget {
 
     pub fn remove_all_listeners(&self) {
         *self.handlers.borrow_mut() = Default::default();
+        self.listeners_changed();
+    }
+
+    /// Let the targets whose lifetime depends on their listeners know that
+    /// these changed.
+    fn listeners_changed(&self) {
+        if let Some(channel) = self.downcast::<BroadcastChannel>() {
+            channel.listeners_changed();
+        }
     }
 
     /// <https://html.spec.whatwg.org/multipage/#event-handler-attributes:event-handlers-11>
     fn set_inline_event_listener(&self,
                                  ty: Atom,
                                  listener: Option<InlineEventListener>) {
-        let mut handlers = self.handlers.borrow_mut();
-        let entries = match handlers.entry(ty) {
-            Occupied(entry) => entry.into_mut(),
-            Vacant(entry) => entry.insert(EventListeners(vec!())),
-        };
+        {
+            let mut handlers = self.handlers.borrow_mut();
+            let entries = match handlers.entry(ty) {
+                Occupied(entry) => entry.into_mut(),
+                Vacant(entry) => entry.insert(EventListeners(vec!())),
+            };
 
-        let idx = entries.iter().position(|ref entry| {
-            match entry.listener {
-                EventListenerType::Inline(_) => true,
-                _ => false,
-            }
-        });
+            let idx = entries.iter().position(|ref entry| {
+                match entry.listener {
+                    EventListenerType::Inline(_) => true,
+                    _ => false,
+                }
+            });
 
-        match idx {
-            Some(idx) => {
-                entries[idx].listener =
-                    EventListenerType::Inline(listener.unwrap_or(InlineEventListener::Null));
-            }
-            None => {
-                if let Some(listener) = listener {
-                    entries.push(EventListenerEntry {
-                        phase: ListenerPhase::Bubbling,
-                        listener: EventListenerType::Inline(listener),
-                    });
+            match idx {
+                Some(idx) => {
+                    entries[idx].listener =
+                        EventListenerType::Inline(listener.unwrap_or(InlineEventListener::Null));
+                }
+                None => {
+                    if let Some(listener) = listener {
+                        entries.push(EventListenerEntry {
+                            phase: ListenerPhase::Bubbling,
+                            listener: EventListenerType::Inline(listener),
+                        });
+                    }
                 }
             }
         }
+        self.listeners_changed();
     }
 
     fn get_inline_event_listener(&self, ty: &Atom) -> Option<CommonEventHandler> {
@@ -569,24 +583,38 @@ impl EventTarget {
             Some(l) => l,
             None => return,
         };
-        let mut handlers = self.handlers.borrow_mut();
-        let entry = match handlers.entry(Atom::from(ty)) {
-            Occupied(entry) => entry.into_mut(),
-            Vacant(entry) => entry.insert(EventListeners(vec!())),
-        };
+        if let Some(ref signal) = options.signal {
+            if signal.aborted() {
+                return;
+            }
+            signal.add_abort_algorithm(AbortAlgorithm::RemoveEventListener(
+                Dom::from_ref(self),
+                ty.clone(),
+                listener.clone(),
+                options.parent.capture,
+            ));
+        }
+        {
+            let mut handlers = self.handlers.borrow_mut();
+            let entry = match handlers.entry(Atom::from(ty)) {
+                Occupied(entry) => entry.into_mut(),
+                Vacant(entry) => entry.insert(EventListeners(vec!())),
+            };
 
-        let phase = if options.parent.capture {
-            ListenerPhase::Capturing
-        } else {
-            ListenerPhase::Bubbling
-        };
-        let new_entry = EventListenerEntry {
-            phase: phase,
-            listener: EventListenerType::Additive(listener)
-        };
-        if !entry.contains(&new_entry) {
-            entry.push(new_entry);
+            let phase = if options.parent.capture {
+                ListenerPhase::Capturing
+            } else {
+                ListenerPhase::Bubbling
+            };
+            let new_entry = EventListenerEntry {
+                phase: phase,
+                listener: EventListenerType::Additive(listener)
+            };
+            if !entry.contains(&new_entry) {
+                entry.push(new_entry);
+            }
         }
+        self.listeners_changed();
     }
 
     // https://dom.spec.whatwg.org/#dom-eventtarget-removeeventlistener
@@ -600,22 +628,25 @@ impl EventTarget {
             Some(l) => l,
             None => return,
         };
-        let mut handlers = self.handlers.borrow_mut();
-        let entry = handlers.get_mut(&Atom::from(ty));
-        for entry in entry {
-            let phase = if options.capture {
-                ListenerPhase::Capturing
-            } else {
-                ListenerPhase::Bubbling
-            };
-            let old_entry = EventListenerEntry {
-                phase: phase,
-                listener: EventListenerType::Additive(listener.clone())
-            };
-            if let Some(position) = entry.iter().position(|e| *e == old_entry) {
-                entry.remove(position);
+        {
+            let mut handlers = self.handlers.borrow_mut();
+            let entry = handlers.get_mut(&Atom::from(ty));
+            for entry in entry {
+                let phase = if options.capture {
+                    ListenerPhase::Capturing
+                } else {
+                    ListenerPhase::Bubbling
+                };
+                let old_entry = EventListenerEntry {
+                    phase: phase,
+                    listener: EventListenerType::Additive(listener.clone())
+                };
+                if let Some(position) = entry.iter().position(|e| *e == old_entry) {
+                    entry.remove(position);
+                }
             }
         }
+        self.listeners_changed();
     }
 }
 
@@ -666,7 +697,7 @@ impl From<AddEventListenerOptionsOrBoolean> for AddEventListenerOptions {
                 options
             },
             AddEventListenerOptionsOrBoolean::Boolean(capture) => {
-                Self { parent: EventListenerOptions { capture } }
+                Self { parent: EventListenerOptions { capture }, signal: None }
             },
         }
     }
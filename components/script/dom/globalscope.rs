@@ -9,20 +9,25 @@ use dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScop
 use dom::bindings::conversions::root_from_object;
 use dom::bindings::error::{ErrorInfo, report_pending_exception};
 use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::DomObject;
-use dom::bindings::root::DomRoot;
+use dom::bindings::root::{Dom, DomRoot};
 use dom::bindings::settings_stack::{AutoEntryScript, entry_global, incumbent_global};
 use dom::bindings::str::DOMString;
+use dom::bindings::weakref::WeakRef;
+use dom::broadcastchannel::BroadcastChannel;
 use dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use dom::errorevent::ErrorEvent;
 use dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use dom::eventtarget::EventTarget;
+use dom::messageport::MessagePort;
 use dom::performance::Performance;
 use dom::window::Window;
 use dom::workerglobalscope::WorkerGlobalScope;
 use dom::workletglobalscope::WorkletGlobalScope;
 use dom_struct::dom_struct;
-use ipc_channel::ipc::IpcSender;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
 use js::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL};
 use js::glue::{IsWrapper, UnwrapObject};
 use js::jsapi::{CurrentGlobalOrNull, GetGlobalForObjectCrossCompartment};
@@ -40,20 +45,22 @@ use net_traits::{CoreResourceThread, ResourceThreads, IpcSend};
 use profile_traits::{mem, time};
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptPort};
 use script_thread::{MainThreadScriptChan, ScriptThread};
-use script_traits::{MsDuration, ScriptToConstellationChan, TimerEvent};
-use script_traits::{TimerEventId, TimerSchedulerMsg, TimerSource};
+use script_traits::{BroadcastMsg, MessagePortId, MessagePortMsg, MessagePortRouterId};
+use script_traits::{MsDuration, PortMessageTask, ScriptMsg, ScriptToConstellationChan, TimerEvent};
+use script_traits::{TimerEventId, TimerSchedulerMsg, TimerSource, TransferredPort};
 use servo_url::{MutableOrigin, ServoUrl};
 use std::cell::Cell;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::collections::hash_map::Entry;
 use std::ffi::CString;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use task::TaskCanceller;
-use task_source::TaskSourceName;
+use task_source::{TaskSource, TaskSourceName};
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
+use task_source::port_message::PortMessageQueue;
 use task_source::remote_event::RemoteEventTaskSource;
 use time::{Timespec, get_time};
 use timers::{IsInterval, OneshotTimerCallback, OneshotTimerHandle};
@@ -70,6 +77,63 @@ impl Drop for AutoCloseWorker {
     }
 }
 
+/// A `MessagePort` owned by a global, along with its port message queue.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct ManagedMessagePort {
+    /// The port, held weakly so that ports script can no longer reach are
+    /// collected.
+    dom_port: WeakRef<MessagePort>,
+    /// A strong reference to the port while it is entangled with a port of
+    /// another global, or has messages waiting in its enabled port message
+    /// queue.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#ports-and-garbage-collection>
+    keep_alive: Option<Dom<MessagePort>>,
+    /// Messages received by the port and not dispatched yet.
+    message_queue: VecDeque<PortMessageTask>,
+    /// Whether the [port message queue](https://html.spec.whatwg.org/multipage/#port-message-queue)
+    /// is enabled.
+    enabled: bool,
+}
+
+impl ManagedMessagePort {
+    /// Hold the port strongly while the garbage collection rules for ports
+    /// require it, and weakly otherwise.
+    #[allow(unrooted_must_root)]
+    fn update_keep_alive(&mut self) {
+        let port = self.dom_port.root();
+        let keep_alive = port.as_ref().map_or(false, |port| {
+            port.entangled_with_other_global() || (self.enabled && !self.message_queue.is_empty())
+        });
+        self.keep_alive = if keep_alive { port.map(|port| Dom::from_ref(&*port)) } else { None };
+    }
+}
+
+/// A `BroadcastChannel` of a global.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct ManagedBroadcastChannel {
+    /// The channel, held weakly so that channels script can no longer reach
+    /// are collected.
+    dom_channel: WeakRef<BroadcastChannel>,
+    /// A strong reference to the channel while it has `message` listeners.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#broadcasting-to-other-browsing-contexts>
+    keep_alive: Option<Dom<BroadcastChannel>>,
+}
+
+/// The message ports and broadcast channels of a global, through which the
+/// constellation delivers messages to that global.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct MessagePortRouter {
+    router_id: MessagePortRouterId,
+    ports: HashMap<MessagePortId, ManagedMessagePort>,
+    /// The open broadcast channels of this global, by name, in creation order.
+    broadcast_channels: HashMap<DOMString, Vec<ManagedBroadcastChannel>>,
+}
+
 #[dom_struct]
 pub struct GlobalScope {
     eventtarget: EventTarget,
@@ -128,6 +192,9 @@ pub struct GlobalScope {
     /// Vector storing closing references of all workers
     #[ignore_malloc_size_of = "Arc"]
     list_auto_close_worker: DomRefCell<Vec<AutoCloseWorker>>,
+
+    /// The message-port router of this global, created on first use.
+    message_port_router: DomRefCell<Option<MessagePortRouter>>,
 }
 
 impl GlobalScope {
@@ -160,6 +227,7 @@ impl GlobalScope {
             origin,
             microtask_queue,
             list_auto_close_worker: Default::default(),
+            message_port_router: DomRefCell::new(None),
         }
     }
 
@@ -167,6 +235,376 @@ impl GlobalScope {
        self.list_auto_close_worker.borrow_mut().push(AutoCloseWorker(closing_worker));
     }
 
+    /// Returns the id of the message-port router of this global, creating it
+    /// and registering it with the constellation if needed.
+    fn message_port_router_id(&self) -> MessagePortRouterId {
+        if let Some(ref router) = *self.message_port_router.borrow() {
+            return router.router_id;
+        }
+
+        let router_id = MessagePortRouterId::new();
+        let (router_sender, router_receiver) = ipc::channel().unwrap();
+        let this = Trusted::new(self);
+        let task_source = self.port_message_queue();
+        let canceller = self.task_canceller(TaskSourceName::PortMessage);
+        ROUTER.add_route(router_receiver.to_opaque(), Box::new(move |message| {
+            let msg: MessagePortMsg = message.to().unwrap();
+            let this = this.clone();
+            let _ = task_source.queue_with_canceller(
+                task!(route_message_port_msg: move || {
+                    this.root().route_message_port_msg(msg);
+                }),
+                &canceller,
+            );
+        }));
+        let _ = self.script_to_constellation_chan()
+            .send(ScriptMsg::NewMessagePortRouter(router_id, router_sender));
+
+        *self.message_port_router.borrow_mut() = Some(MessagePortRouter {
+            router_id: router_id,
+            ports: HashMap::new(),
+            broadcast_channels: HashMap::new(),
+        });
+        router_id
+    }
+
+    /// Handle a message sent by the constellation to the message-port router.
+    fn route_message_port_msg(&self, msg: MessagePortMsg) {
+        match msg {
+            MessagePortMsg::NewTask(port_id, task) => self.route_task_to_port(port_id, task),
+            MessagePortMsg::Broadcast(msg) => self.dispatch_broadcast(&msg, None),
+            MessagePortMsg::Disentangle(port_id) => self.disentangle_message_port(port_id),
+        }
+    }
+
+    /// Start managing a port created in, or transferred to, this global.
+    pub fn track_message_port(&self, port: &MessagePort, queued_messages: Vec<PortMessageTask>) {
+        let router_id = self.message_port_router_id();
+        let port_id = port.message_port_id();
+        let local_entangled_port = match *self.message_port_router.borrow_mut() {
+            Some(ref mut router) => {
+                // Forget the ports that were collected.
+                router.ports.retain(|_, managed| managed.dom_port.is_alive());
+                router.ports.insert(port_id, ManagedMessagePort {
+                    dom_port: WeakRef::new(port),
+                    keep_alive: None,
+                    message_queue: queued_messages.into_iter().collect(),
+                    enabled: false,
+                });
+                port.entangled_port()
+                    .and_then(|entangled_port| router.ports.get(&entangled_port))
+                    .and_then(|managed| managed.dom_port.root())
+            },
+            None => None,
+        };
+        // A port transferred back to the global of its entangled port.
+        match local_entangled_port {
+            Some(entangled_port) => port.entangle(&entangled_port),
+            None => self.update_message_port_keep_alive(&port_id),
+        }
+        let _ = self.script_to_constellation_chan()
+            .send(ScriptMsg::NewMessagePort(router_id, port_id));
+    }
+
+    /// Hold a port strongly while the garbage collection rules for ports
+    /// require it, after its entanglement changed.
+    pub fn update_message_port_keep_alive(&self, port_id: &MessagePortId) {
+        let mut router = self.message_port_router.borrow_mut();
+        if let Some(managed) = router.as_mut().and_then(|router| router.ports.get_mut(port_id)) {
+            managed.update_keep_alive();
+        }
+    }
+
+    /// Enable the port message queue of a port, dispatching the messages it
+    /// received so far.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-messageport-start>
+    pub fn start_message_port(&self, port_id: &MessagePortId) {
+        let queued = {
+            let mut router = self.message_port_router.borrow_mut();
+            match router.as_mut().and_then(|router| router.ports.get_mut(port_id)) {
+                Some(managed) => {
+                    if managed.enabled {
+                        return;
+                    }
+                    managed.enabled = true;
+                    managed.update_keep_alive();
+                    managed.message_queue.len()
+                },
+                None => return,
+            }
+        };
+        // Each task dispatches the oldest queued message, so that ordering
+        // is preserved with messages received in the meantime.
+        let task_source = self.port_message_queue();
+        for _ in 0..queued {
+            let this = Trusted::new(self);
+            let port_id = *port_id;
+            let _ = task_source.queue(task!(dispatch_queued_port_message: move || {
+                this.root().dispatch_next_port_message(&port_id);
+            }), self);
+        }
+    }
+
+    /// Stop managing a closed port, and disentangle the port it was
+    /// entangled with, wherever it lives.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-messageport-close>
+    pub fn close_message_port(&self, port_id: &MessagePortId, entangled_port: Option<MessagePortId>) {
+        if let Some(ref mut router) = *self.message_port_router.borrow_mut() {
+            router.ports.remove(port_id);
+        }
+        let chan = self.script_to_constellation_chan();
+        let _ = chan.send(ScriptMsg::RemoveMessagePort(*port_id));
+        if let Some(entangled_port) = entangled_port {
+            let _ = chan.send(ScriptMsg::DisentangleMessagePort(entangled_port));
+        }
+    }
+
+    /// Close the ports transferred with a message that was dropped before
+    /// reaching its target, and the ports transferred with the messages they
+    /// had queued, so that they don't stay in transit forever.
+    pub fn discard_transferred_ports(&self, ports: Vec<TransferredPort>) {
+        for port in ports {
+            self.close_message_port(&port.port_id, port.entangled_port);
+            for task in port.queued_messages {
+                self.discard_transferred_ports(task.ports);
+            }
+        }
+    }
+
+    /// Disentangle a port of this global after its entangled port was closed.
+    fn disentangle_message_port(&self, port_id: MessagePortId) {
+        let port = match *self.message_port_router.borrow() {
+            Some(ref router) => router.ports.get(&port_id).map(|managed| managed.dom_port.root()),
+            None => None,
+        };
+        match port {
+            Some(Some(port)) => {
+                port.disentangle();
+                self.update_message_port_keep_alive(&port_id);
+            },
+            // The port was collected.
+            Some(None) => {},
+            None => {
+                // The port was shipped elsewhere in the meantime.
+                let _ = self.script_to_constellation_chan()
+                    .send(ScriptMsg::DisentangleMessagePort(port_id));
+            },
+        }
+    }
+
+    /// Stop managing a port that is being transferred to another global,
+    /// returning the messages it received but did not dispatch.
+    pub fn ship_message_port(&self, port_id: &MessagePortId) -> Vec<PortMessageTask> {
+        let queued = match *self.message_port_router.borrow_mut() {
+            Some(ref mut router) => {
+                let queued = match router.ports.get_mut(port_id) {
+                    Some(managed) => managed.message_queue.drain(..).collect(),
+                    None => vec![],
+                };
+                router.ports.remove(port_id);
+                queued
+            },
+            None => vec![],
+        };
+        let _ = self.script_to_constellation_chan()
+            .send(ScriptMsg::MessagePortShipped(*port_id));
+        queued
+    }
+
+    /// Post a message to a port, wherever it lives.
+    pub fn post_messageport_msg(&self, port_id: MessagePortId, task: PortMessageTask) {
+        let _ = self.script_to_constellation_chan()
+            .send(ScriptMsg::PostPortMessage(port_id, task));
+    }
+
+    /// Add a message received for one of the ports of this global to its port
+    /// message queue, dispatching it right away if the queue is enabled.
+    fn route_task_to_port(&self, port_id: MessagePortId, task: PortMessageTask) {
+        let queued = {
+            let mut router = self.message_port_router.borrow_mut();
+            match router.as_mut().and_then(|router| router.ports.get_mut(&port_id)) {
+                Some(managed) => {
+                    if managed.dom_port.is_alive() {
+                        managed.message_queue.push_back(task);
+                        managed.update_keep_alive();
+                        Ok(managed.enabled)
+                    } else {
+                        Err(task)
+                    }
+                },
+                None => {
+                    // The port was shipped elsewhere in the meantime.
+                    let _ = self.script_to_constellation_chan()
+                        .send(ScriptMsg::PostPortMessage(port_id, task));
+                    return;
+                },
+            }
+        };
+        match queued {
+            Ok(true) => self.dispatch_next_port_message(&port_id),
+            Ok(false) => {},
+            // The port was collected, nothing can receive the message.
+            Err(task) => self.discard_transferred_ports(task.ports),
+        }
+    }
+
+    /// Dispatch the oldest message in the port message queue of a port.
+    fn dispatch_next_port_message(&self, port_id: &MessagePortId) {
+        let (port, task) = {
+            let mut router = self.message_port_router.borrow_mut();
+            let managed = match router.as_mut().and_then(|router| router.ports.get_mut(port_id)) {
+                Some(managed) => managed,
+                None => return,
+            };
+            let port = match managed.dom_port.root() {
+                Some(port) => port,
+                None => return,
+            };
+            let task = match managed.message_queue.pop_front() {
+                Some(task) => task,
+                None => return,
+            };
+            managed.update_keep_alive();
+            (port, task)
+        };
+        port.dispatch_message(task);
+    }
+
+    /// Start listening to broadcasts for the name of the given channel.
+    pub fn track_broadcast_channel(&self, channel: &BroadcastChannel) {
+        let router_id = self.message_port_router_id();
+        let first_with_name = match *self.message_port_router.borrow_mut() {
+            Some(ref mut router) => {
+                let channels = router.broadcast_channels
+                    .entry(channel.name().clone())
+                    .or_insert_with(Vec::new);
+                let first_with_name = channels.is_empty();
+                channels.retain(|other| other.dom_channel.is_alive());
+                channels.push(ManagedBroadcastChannel {
+                    dom_channel: WeakRef::new(channel),
+                    keep_alive: None,
+                });
+                first_with_name
+            },
+            None => return,
+        };
+        if first_with_name {
+            let _ = self.script_to_constellation_chan().send(ScriptMsg::NewBroadcastChannelNameInRouter(
+                router_id,
+                channel.name().to_string(),
+                self.origin().immutable().clone(),
+            ));
+        }
+    }
+
+    /// Hold a channel strongly while it has `message` listeners, and weakly
+    /// otherwise.
+    pub fn update_broadcast_channel_keep_alive(&self, channel: &BroadcastChannel) {
+        let keep_alive = channel.upcast::<EventTarget>().has_listeners_for(&atom!("message"));
+        if let Some(ref mut router) = *self.message_port_router.borrow_mut() {
+            let managed = router.broadcast_channels.get_mut(channel.name())
+                .and_then(|channels| channels.iter_mut().find(|managed| managed.dom_channel == *channel));
+            if let Some(managed) = managed {
+                managed.keep_alive = if keep_alive { Some(Dom::from_ref(channel)) } else { None };
+            }
+        }
+    }
+
+    /// Stop listening to broadcasts for a closed channel.
+    pub fn untrack_broadcast_channel(&self, channel: &BroadcastChannel) {
+        self.forget_broadcast_channels(channel.name(), Some(channel));
+    }
+
+    /// Forget the given channel, if any, and the collected channels with the
+    /// given name. Stop listening to broadcasts for that name if no channel
+    /// is left.
+    fn forget_broadcast_channels(&self, name: &DOMString, channel: Option<&BroadcastChannel>) {
+        let (router_id, last_with_name) = match *self.message_port_router.borrow_mut() {
+            Some(ref mut router) => {
+                let last_with_name = match router.broadcast_channels.entry(name.clone()) {
+                    Entry::Occupied(mut entry) => {
+                        entry.get_mut().retain(|other| {
+                            other.dom_channel.is_alive() &&
+                                channel.map_or(true, |channel| other.dom_channel != *channel)
+                        });
+                        if entry.get().is_empty() {
+                            entry.remove();
+                            true
+                        } else {
+                            false
+                        }
+                    },
+                    Entry::Vacant(_) => false,
+                };
+                (router.router_id, last_with_name)
+            },
+            None => return,
+        };
+        if last_with_name {
+            let _ = self.script_to_constellation_chan().send(ScriptMsg::RemoveBroadcastChannelNameInRouter(
+                router_id,
+                name.to_string(),
+                self.origin().immutable().clone(),
+            ));
+        }
+    }
+
+    /// Broadcast a message to every other channel with the same name and
+    /// origin, in this global and elsewhere.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage>
+    pub fn schedule_broadcast(&self, source: &BroadcastChannel, msg: BroadcastMsg) {
+        let router_id = self.message_port_router_id();
+        let this = Trusted::new(self);
+        let source = Trusted::new(source);
+        let local_msg = msg.clone();
+        let _ = self.port_message_queue().queue(task!(broadcast_message_locally: move || {
+            this.root().dispatch_broadcast(&local_msg, Some(&source.root()));
+        }), self);
+        let _ = self.script_to_constellation_chan()
+            .send(ScriptMsg::ScheduleBroadcast(router_id, msg));
+    }
+
+    /// Fire a `message` event on the channels of this global with the name
+    /// of the broadcast, in creation order.
+    fn dispatch_broadcast(&self, msg: &BroadcastMsg, source: Option<&BroadcastChannel>) {
+        let (channels, collected): (Vec<DomRoot<BroadcastChannel>>, bool) = match *self.message_port_router.borrow() {
+            Some(ref router) => {
+                match router.broadcast_channels.get(&*msg.channel_name) {
+                    Some(channels) => {
+                        let collected = channels.iter().any(|managed| !managed.dom_channel.is_alive());
+                        let channels = channels.iter()
+                            .filter(|managed| source.map_or(true, |source| managed.dom_channel != *source))
+                            .filter_map(|managed| managed.dom_channel.root())
+                            .collect();
+                        (channels, collected)
+                    },
+                    None => return,
+                }
+            },
+            None => return,
+        };
+        if collected {
+            self.forget_broadcast_channels(&DOMString::from(msg.channel_name.clone()), None);
+        }
+        for channel in channels {
+            channel.dispatch_message(msg);
+        }
+    }
+
+    /// Tell the constellation this global's ports and broadcast channels are
+    /// going away.
+    pub fn remove_message_port_router(&self) {
+        let router_id = self.message_port_router.borrow().as_ref().map(|router| router.router_id);
+        if let Some(router_id) = router_id {
+            *self.message_port_router.borrow_mut() = None;
+            let _ = self.script_to_constellation_chan()
+                .send(ScriptMsg::RemoveMessagePortRouter(router_id));
+        }
+    }
+
     /// Returns the global scope of the realm that the given DOM object's reflector
     /// was created in.
     #[allow(unsafe_code)]
@@ -316,6 +754,13 @@ impl GlobalScope {
         unreachable!();
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#secure-context>
+    pub fn is_secure_context(&self) -> bool {
+        // FIXME: Nested browsing contexts and workers should also check
+        // whether their ancestors and owners are secure contexts.
+        self.get_url().is_potentially_trustworthy()
+    }
+
     /// Extract a `Window`, panic if the global object is not a `Window`.
     pub fn as_window(&self) -> &Window {
         self.downcast::<Window>().expect("expected a Window scope")
@@ -406,6 +851,17 @@ impl GlobalScope {
         unreachable!();
     }
 
+    /// The port message queue of this global scope.
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.port_message_queue();
+        }
+        if let Some(worker) = self.downcast::<WorkerGlobalScope>() {
+            return worker.port_message_queue();
+        }
+        unreachable!();
+    }
+
     /// Evaluate JS code on this global scope.
     pub fn evaluate_js_on_global_with_result(
             &self, code: &str, rval: MutableHandleValue) -> bool {
@@ -467,6 +923,10 @@ impl GlobalScope {
         self.timers.unschedule_callback(handle);
     }
 
+    pub fn time_until_next_timer(&self) -> Option<MsDuration> {
+        self.timers.time_until_next_timer()
+    }
+
     pub fn set_timeout_or_interval(
             &self,
             callback: TimerCallback,
@@ -4,35 +4,47 @@
 
 use base64;
 use canvas_traits::canvas::{CanvasMsg, CanvasId, FromScriptMsg};
+use canvas_traits::webgl::WebGLVersion;
 use dom::attr::Attr;
+use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
-use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
 use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding;
-use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::{HTMLCanvasElementMethods, RenderingContext};
-use dom::bindings::error::{Error, Fallible};
+use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::{BlobCallback, HTMLCanvasElementMethods};
+use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::RenderingContext;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLContextAttributes;
+use dom::bindings::conversions::ConversionResult;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::Castable;
-use dom::bindings::num::Finite;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::DomObject;
 use dom::bindings::root::{Dom, DomRoot, LayoutDom};
 use dom::bindings::str::DOMString;
+use dom::blob::{Blob, BlobImpl};
 use dom::canvasrenderingcontext2d::{CanvasRenderingContext2D, LayoutCanvasRenderingContext2DHelpers};
 use dom::document::Document;
 use dom::element::{AttributeMutation, Element, RawLayoutElementHelpers};
 use dom::globalscope::GlobalScope;
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, window_from_node};
+use dom::node::{Node, NodeDamage, window_from_node};
+use dom::offscreencanvas::{LayoutOffscreenCanvasHelpers, OffscreenCanvas};
 use dom::virtualmethods::VirtualMethods;
+use dom::webglrenderingcontext::{LayoutCanvasWebGLRenderingContextHelpers, WebGLRenderingContext};
 use dom_struct::dom_struct;
 use euclid::Size2D;
 use html5ever::{LocalName, Prefix};
-use image::ColorType;
-use image::png::PNGEncoder;
+use image_encoder::ImageType;
 use js::jsapi::JSContext;
 use js::rust::HandleValue;
+use offscreen_gl_context::GLContextAttributes;
 use profile_traits::ipc;
 use script_layout_interface::{HTMLCanvasData, HTMLCanvasDataSource};
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::iter::repeat;
+use std::rc::Rc;
+use std::thread;
 use style::attr::{AttrValue, LengthOrPercentageOrAuto};
+use task_source::{TaskSource, TaskSourceName};
 
 const DEFAULT_WIDTH: u32 = 300;
 const DEFAULT_HEIGHT: u32 = 150;
@@ -41,12 +53,20 @@ const DEFAULT_HEIGHT: u32 = 150;
 #[derive(Clone, JSTraceable, MallocSizeOf)]
 pub enum CanvasContext {
     Context2d(Dom<CanvasRenderingContext2D>),
+    WebGL(Dom<WebGLRenderingContext>),
+    /// The canvas was transferred to an offscreen canvas, and displays
+    /// whatever is drawn on it.
+    Placeholder(Dom<OffscreenCanvas>),
 }
 
 #[dom_struct]
 pub struct HTMLCanvasElement {
     htmlelement: HTMLElement,
     context: DomRefCell<Option<CanvasContext>>,
+    /// The callbacks of pending `toBlob` calls, by id.
+    #[ignore_malloc_size_of = "Rc"]
+    blob_callbacks: DomRefCell<HashMap<u32, Rc<BlobCallback>>>,
+    next_blob_callback_id: Cell<u32>,
 }
 
 impl HTMLCanvasElement {
@@ -56,6 +76,8 @@ impl HTMLCanvasElement {
         HTMLCanvasElement {
             htmlelement: HTMLElement::new_inherited(local_name, prefix, document),
             context: DomRefCell::new(None),
+            blob_callbacks: DomRefCell::new(HashMap::new()),
+            next_blob_callback_id: Cell::new(0),
         }
     }
 
@@ -73,20 +95,45 @@ impl HTMLCanvasElement {
         if let Some(ref context) = *self.context.borrow() {
             match *context {
                 CanvasContext::Context2d(ref context) => context.set_bitmap_dimensions(size),
+                CanvasContext::WebGL(ref context) => context.recreate(size),
+                // The offscreen canvas decides its own size.
+                CanvasContext::Placeholder(_) => {},
             }
         }
     }
 
     pub fn get_size(&self) -> Size2D<i32> {
-        Size2D::new(self.Width() as i32, self.Height() as i32)
+        match *self.context.borrow() {
+            Some(CanvasContext::Placeholder(ref offscreen_canvas)) => offscreen_canvas.get_size(),
+            _ => Size2D::new(self.Width() as i32, self.Height() as i32),
+        }
+    }
+
+    /// Returns the bitmap as premultiplied BGRA pixels to be serialized to an
+    /// image file, a canvas without a context being transparent black.
+    fn get_bitmap_for_serialization(&self) -> Vec<u8> {
+        let size = self.get_size();
+        let data = match *self.context.borrow() {
+            Some(_) => self.fetch_all_data().map(|(data, _)| data),
+            None => None,
+        };
+        data.unwrap_or_else(|| vec![0; size.width as usize * size.height as usize * 4])
     }
 
     pub fn origin_is_clean(&self) -> bool {
         match *self.context.borrow() {
             Some(CanvasContext::Context2d(ref context)) => context.origin_is_clean(),
-            _ => true,
+            Some(CanvasContext::Placeholder(ref canvas)) => canvas.origin_is_clean(),
+            // Cross-origin images can't be uploaded to WebGL textures.
+            Some(CanvasContext::WebGL(_)) | None => true,
         }
     }
+
+    /// Called when the offscreen canvas this is a placeholder for changes
+    /// size, so that it gets laid out again.
+    pub fn offscreen_canvas_resized(&self) {
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
 }
 
 pub trait LayoutHTMLCanvasElementHelpers {
@@ -105,17 +152,31 @@ impl LayoutHTMLCanvasElementHelpers for LayoutDom<HTMLCanvasElement> {
                 Some(&CanvasContext::Context2d(ref context)) => {
                     HTMLCanvasDataSource::Image(Some(context.to_layout().get_ipc_renderer()))
                 },
+                Some(&CanvasContext::WebGL(ref context)) => {
+                    context.to_layout().canvas_data_source()
+                },
+                Some(&CanvasContext::Placeholder(ref offscreen_canvas)) => {
+                    let renderer = offscreen_canvas.to_layout().get_canvas_renderer_for_layout();
+                    HTMLCanvasDataSource::Image(renderer.map(|(renderer, _)| renderer))
+                },
                 None => {
                     HTMLCanvasDataSource::Image(None)
                 }
             };
 
-            let width_attr = canvas.upcast::<Element>().get_attr_for_layout(&ns!(), &local_name!("width"));
-            let height_attr = canvas.upcast::<Element>().get_attr_for_layout(&ns!(), &local_name!("height"));
+            let size = match canvas.context.borrow_for_layout().as_ref() {
+                Some(&CanvasContext::Placeholder(ref offscreen_canvas)) => offscreen_canvas.to_layout().get_size(),
+                _ => {
+                    let width_attr = canvas.upcast::<Element>().get_attr_for_layout(&ns!(), &local_name!("width"));
+                    let height_attr = canvas.upcast::<Element>().get_attr_for_layout(&ns!(), &local_name!("height"));
+                    Size2D::new(width_attr.map_or(DEFAULT_WIDTH, |val| val.as_uint()),
+                                height_attr.map_or(DEFAULT_HEIGHT, |val| val.as_uint()))
+                },
+            };
             HTMLCanvasData {
                 source: source,
-                width: width_attr.map_or(DEFAULT_WIDTH, |val| val.as_uint()),
-                height: height_attr.map_or(DEFAULT_HEIGHT, |val| val.as_uint()),
+                width: size.width,
+                height: size.height,
                 canvas_id: self.get_canvas_id_for_layout(),
             }
         }
@@ -145,10 +206,15 @@ impl LayoutHTMLCanvasElementHelpers for LayoutDom<HTMLCanvasElement> {
     fn get_canvas_id_for_layout(&self) -> CanvasId {
         unsafe {
             let canvas = &*self.unsafe_get();
-            if let &Some(CanvasContext::Context2d(ref context)) = canvas.context.borrow_for_layout() {
-                context.to_layout().get_canvas_id()
-            } else {
-                CanvasId(0)
+            match *canvas.context.borrow_for_layout() {
+                Some(CanvasContext::Context2d(ref context)) => context.to_layout().get_canvas_id(),
+                Some(CanvasContext::WebGL(_)) => CanvasId(0),
+                Some(CanvasContext::Placeholder(ref offscreen_canvas)) => {
+                    offscreen_canvas.to_layout()
+                        .get_canvas_renderer_for_layout()
+                        .map_or(CanvasId(0), |(_, canvas_id)| canvas_id)
+                },
+                None => CanvasId(0),
             }
         }
     }
@@ -166,12 +232,47 @@ impl HTMLCanvasElement {
 
         match *self.context.borrow().as_ref().unwrap() {
             CanvasContext::Context2d(ref context) => Some(DomRoot::from_ref(&*context)),
-            //_   => None,
+            CanvasContext::WebGL(_) | CanvasContext::Placeholder(_) => None,
+        }
+    }
+
+    pub fn get_or_init_webgl_context(&self,
+                                     cx: *mut JSContext,
+                                     attrs: Option<HandleValue>)
+                                     -> Fallible<Option<DomRoot<WebGLRenderingContext>>> {
+        if self.context.borrow().is_none() {
+            let window = window_from_node(self);
+            let size = self.get_size();
+            let attrs = Self::get_gl_attributes(cx, attrs)?;
+            let maybe_ctx = WebGLRenderingContext::new(&window, self, WebGLVersion::WebGL1, size, attrs);
+
+            *self.context.borrow_mut() = maybe_ctx.map(|ctx| CanvasContext::WebGL(Dom::from_ref(&*ctx)));
+        }
+
+        Ok(match self.context.borrow().as_ref() {
+            Some(&CanvasContext::WebGL(ref context)) => Some(DomRoot::from_ref(&*context)),
+            _ => None,
+        })
+    }
+
+    #[allow(unsafe_code)]
+    fn get_gl_attributes(cx: *mut JSContext, attrs: Option<HandleValue>) -> Fallible<GLContextAttributes> {
+        let webgl_attributes = match attrs {
+            Some(attrs) => unsafe { WebGLContextAttributes::new(cx, attrs) },
+            None => return Ok(GLContextAttributes::from(&WebGLContextAttributes::empty())),
+        };
+
+        match webgl_attributes {
+            Ok(ConversionResult::Success(ref attrs)) => Ok(From::from(attrs)),
+            Ok(ConversionResult::Failure(error)) => Err(Error::Type(error.into_owned())),
+            // The exception thrown while converting is still pending.
+            Err(()) => Err(Error::JSFailed),
         }
     }
 
     pub fn is_valid(&self) -> bool {
-        self.Height() != 0 && self.Width() != 0
+        let size = self.get_size();
+        size.width != 0 && size.height != 0
     }
 
     pub fn fetch_all_data(&self) -> Option<(Vec<u8>, Size2D<i32>)> {
@@ -189,6 +290,8 @@ impl HTMLCanvasElement {
 
                 receiver.recv().unwrap()?.into()
             },
+            Some(&CanvasContext::WebGL(ref context)) => context.get_image_data(size),
+            Some(&CanvasContext::Placeholder(ref offscreen_canvas)) => return offscreen_canvas.fetch_all_data(),
             None => {
                 repeat(0xffu8).take((size.height as usize) * (size.width as usize) * 4).collect()
             }
@@ -214,65 +317,131 @@ impl HTMLCanvasElementMethods for HTMLCanvasElement {
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-canvas-getcontext
     unsafe fn GetContext(&self,
-                  _cx: *mut JSContext,
+                  cx: *mut JSContext,
                   id: DOMString,
-                  _attributes: Vec<HandleValue>)
-        -> Option<RenderingContext> {
-        match &*id {
+                  attributes: Vec<HandleValue>)
+        -> Fallible<Option<RenderingContext>> {
+        // A canvas whose control was transferred has no context of its own.
+        if let Some(CanvasContext::Placeholder(_)) = *self.context.borrow() {
+            return Err(Error::InvalidState);
+        }
+
+        Ok(match &*id {
             "2d" => {
                 self.get_or_init_2d_context()
-                  /*  .map(RenderingContext::CanvasRenderingContext2D) */
+                    .map(RenderingContext::CanvasRenderingContext2D)
+            }
+            "webgl" | "experimental-webgl" => {
+                self.get_or_init_webgl_context(cx, attributes.get(0).cloned())?
+                    .map(RenderingContext::WebGLRenderingContext)
             }
             _ => None
+        })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-canvas-transfercontroltooffscreen
+    fn TransferControlToOffscreen(&self) -> Fallible<DomRoot<OffscreenCanvas>> {
+        // Step 1.
+        if self.context.borrow().is_some() {
+            return Err(Error::InvalidState);
         }
+
+        // Step 2-3.
+        let offscreen_canvas = OffscreenCanvas::new(&self.global(),
+                                                    self.Width() as u64,
+                                                    self.Height() as u64,
+                                                    Some(self));
+
+        // Step 4.
+        *self.context.borrow_mut() = Some(CanvasContext::Placeholder(Dom::from_ref(&*offscreen_canvas)));
+
+        // Step 5.
+        Ok(offscreen_canvas)
     }
 
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-canvas-todataurl
     unsafe fn ToDataURL(&self,
                  _context: *mut JSContext,
-                 _mime_type: Option<DOMString>,
-                 _arguments: Vec<HandleValue>) -> Fallible<DOMString> {
+                 mime_type: Option<DOMString>,
+                 arguments: Vec<HandleValue>) -> Fallible<DOMString> {
         // Step 1.
-        if let Some(CanvasContext::Context2d(ref context)) = *self.context.borrow() {
-            if !context.origin_is_clean() {
-                return Err(Error::Security);
-            }
+        if !self.origin_is_clean() {
+            return Err(Error::Security);
         }
 
         // Step 2.
-        if self.Width() == 0 || self.Height() == 0 {
+        let size = self.get_size();
+        if size.width == 0 || size.height == 0 {
             return Ok(DOMString::from("data:,"));
         }
 
         // Step 3.
-        let raw_data = match *self.context.borrow() {
-            Some(CanvasContext::Context2d(ref context)) => {
-                let image_data = context.GetImageData(Finite::wrap(0f64), Finite::wrap(0f64),
-                                                           Finite::wrap(self.Width() as f64),
-                                                           Finite::wrap(self.Height() as f64))?;
-                image_data.get_data_array()
-            }
-            None => {
-                // Each pixel is fully-transparent black.
-                vec![0; (self.Width() * self.Height() * 4) as usize]
-            }
-        };
-
-        // Only handle image/png for now.
-        let mime_type = "image/png";
+        let image_type = ImageType::new(mime_type.as_ref().map_or("", |mime_type| &**mime_type), size.to_u32());
+        let encoded = image_type.encode(self.get_bitmap_for_serialization(), size.to_u32(), quality(&arguments));
+        let encoded = base64::encode(&encoded);
+        Ok(DOMString::from(format!("data:{};base64,{}", image_type.mime_type(), encoded)))
+    }
 
-        let mut encoded = Vec::new();
-        {
-            let encoder: PNGEncoder<&mut Vec<u8>> = PNGEncoder::new(&mut encoded);
-            encoder.encode(&raw_data, self.Width(), self.Height(), ColorType::RGBA(8)).unwrap();
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-canvas-toblob
+    unsafe fn ToBlob(&self,
+                     _context: *mut JSContext,
+                     callback: Rc<BlobCallback>,
+                     mime_type: Option<DOMString>,
+                     arguments: Vec<HandleValue>) -> ErrorResult {
+        // Step 1.
+        if !self.origin_is_clean() {
+            return Err(Error::Security);
         }
 
-        let encoded = base64::encode(&encoded);
-        Ok(DOMString::from(format!("data:{};base64,{}", mime_type, encoded)))
+        // Step 2.
+        let size = self.get_size().to_u32();
+        let bitmap = if size.width == 0 || size.height == 0 {
+            None
+        } else {
+            Some(self.get_bitmap_for_serialization())
+        };
+
+        let callback_id = self.next_blob_callback_id.get();
+        self.next_blob_callback_id.set(callback_id.wrapping_add(1));
+        self.blob_callbacks.borrow_mut().insert(callback_id, callback);
+
+        // Step 3, the encoding happening in parallel.
+        let image_type = ImageType::new(mime_type.as_ref().map_or("", |mime_type| &**mime_type), size);
+        let quality = quality(&arguments);
+        let this = Trusted::new(self);
+        let window = window_from_node(self);
+        let task_source = window.dom_manipulation_task_source();
+        let canceller = window.task_canceller(TaskSourceName::DOMManipulation);
+        thread::Builder::new().name("CanvasBlobEncoder".to_owned()).spawn(move || {
+            let encoded = bitmap.map(|bitmap| image_type.encode(bitmap, size, quality));
+            let _ = task_source.queue_with_canceller(
+                task!(invoke_blob_callback: move || {
+                    let this = this.root();
+                    let callback = match this.blob_callbacks.borrow_mut().remove(&callback_id) {
+                        Some(callback) => callback,
+                        None => return,
+                    };
+                    let blob = encoded.map(|encoded| {
+                        Blob::new(&this.global(),
+                                  BlobImpl::new_from_bytes(encoded),
+                                  image_type.mime_type().to_owned())
+                    });
+                    let _ = callback.Call__(blob.as_ref().map(|blob| &**blob), ExceptionHandling::Report);
+                }),
+                &canceller,
+            );
+        }).expect("Thread spawning failed");
+        Ok(())
     }
 }
 
+/// Returns the quality argument of `toDataURL` and `toBlob`, if it is a number.
+fn quality(arguments: &[HandleValue]) -> Option<f64> {
+    arguments.get(0).filter(|quality| quality.is_number()).map(|quality| quality.to_number())
+}
+
 impl VirtualMethods for HTMLCanvasElement {
     fn super_type(&self) -> Option<&VirtualMethods> {
         Some(self.upcast::<HTMLElement>() as &VirtualMethods)
@@ -294,3 +463,16 @@ impl VirtualMethods for HTMLCanvasElement {
         }
     }
 }
+
+impl<'a> From<&'a WebGLContextAttributes> for GLContextAttributes {
+    fn from(attrs: &'a WebGLContextAttributes) -> GLContextAttributes {
+        GLContextAttributes {
+            alpha: attrs.alpha,
+            depth: attrs.depth,
+            stencil: attrs.stencil,
+            antialias: attrs.antialias,
+            premultiplied_alpha: attrs.premultipliedAlpha,
+            preserve_drawing_buffer: attrs.preserveDrawingBuffer,
+        }
+    }
+}
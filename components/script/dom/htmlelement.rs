@@ -10,31 +10,38 @@ use dom::bindings::codegen::Bindings::HTMLElementBinding;
 use dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeBinding::NodeMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
-use dom::bindings::error::{Error, ErrorResult};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::{ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::root::{Dom, DomRoot, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::cssstyledeclaration::{CSSModificationAccess, CSSStyleDeclaration, CSSStyleOwner};
+use dom::customelementregistry::CallbackReaction;
 use dom::document::{Document, FocusType};
 use dom::documentfragment::DocumentFragment;
 use dom::domstringmap::DOMStringMap;
-use dom::element::{AttributeMutation, Element};
+use dom::editing::{self, ContentEditableState};
+use dom::element::{AttributeMutation, CustomElementState, Element};
+use dom::elementinternals::ElementInternals;
 use dom::eventtarget::EventTarget;
 use dom::htmlbodyelement::HTMLBodyElement;
 use dom::htmlbrelement::HTMLBRElement;
+use dom::htmlformelement::{FormControl, FormDatum, HTMLFormElement};
 use dom::htmlframesetelement::HTMLFrameSetElement;
 use dom::htmlhtmlelement::HTMLHtmlElement;
 use dom::htmlinputelement::{HTMLInputElement, InputType};
 use dom::htmllabelelement::HTMLLabelElement;
-use dom::node::{Node, NodeFlags};
+use dom::node::{Node, NodeFlags, UnbindContext};
 use dom::node::{document_from_node, window_from_node};
 use dom::nodelist::NodeList;
 use dom::text::Text;
+use dom::validation::Validatable;
+use dom::validitystate::ValidationFlags;
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
 use script_layout_interface::message::QueryMsg;
+use script_thread::ScriptThread;
 use std::collections::HashSet;
 use std::default::Default;
 use std::rc::Rc;
@@ -469,6 +476,71 @@ impl HTMLElementMethods for HTMLElement {
         // Step 7.
         Node::replace_all(Some(fragment.upcast()), self.upcast::<Node>());
     }
+
+    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    fn ContentEditable(&self) -> DOMString {
+        DOMString::from(match editing::content_editable_state(self.upcast()) {
+            ContentEditableState::True => "true",
+            ContentEditableState::False => "false",
+            ContentEditableState::Inherit => "inherit",
+        })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    fn SetContentEditable(&self, value: DOMString) -> ErrorResult {
+        let element = self.upcast::<Element>();
+        match &*value.to_ascii_lowercase() {
+            "inherit" => {
+                element.remove_attribute(&ns!(), &local_name!("contenteditable"));
+            },
+            value @ "true" | value @ "false" => {
+                element.set_string_attribute(&local_name!("contenteditable"), DOMString::from(value));
+            },
+            _ => return Err(Error::Syntax),
+        }
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-iscontenteditable
+    fn IsContentEditable(&self) -> bool {
+        editing::editing_host_of(self.upcast()).is_some()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-attachinternals
+    fn AttachInternals(&self) -> Fallible<DomRoot<ElementInternals>> {
+        let element = self.upcast::<Element>();
+
+        // Step 1.
+        if element.get_is().is_some() {
+            return Err(Error::NotSupported);
+        }
+
+        // Steps 2-3.
+        let definition = document_from_node(self).lookup_custom_element_definition(
+            element.namespace(),
+            element.local_name(),
+            None,
+        );
+        let definition = match definition {
+            Some(definition) => definition,
+            None => return Err(Error::NotSupported),
+        };
+
+        // Step 4.
+        if definition.disable_internals {
+            return Err(Error::NotSupported);
+        }
+
+        // Step 5.
+        if self.element_internals().is_some() {
+            return Err(Error::NotSupported);
+        }
+
+        // Steps 6-7.
+        let internals = ElementInternals::new(self);
+        self.upcast::<Node>().rare_data().ensure().element_internals.set(Some(&internals));
+        Ok(internals)
+    }
 }
 
 fn append_text_node_to_fragment(
@@ -545,6 +617,34 @@ fn to_camel_case(name: &str) -> Option<DOMString> {
 }
 
 impl HTMLElement {
+    /// <https://html.spec.whatwg.org/multipage/#form-associated-custom-element>
+    pub fn is_form_associated_custom_element(&self) -> bool {
+        let element = self.upcast::<Element>();
+        element.get_custom_element_state() == CustomElementState::Custom &&
+            element.get_custom_element_definition().map_or(false, |definition| definition.form_associated)
+    }
+
+    /// The internals holding this element's form-associated state. Form
+    /// association happens whether or not script has called
+    /// `attachInternals()`, so they are created on demand.
+    fn ensure_element_internals(&self) -> DomRoot<ElementInternals> {
+        let rare_data = self.upcast::<Node>().rare_data().ensure();
+        rare_data.element_internals.or_init(|| ElementInternals::new(self))
+    }
+
+    /// The internals of this element, if they were created.
+    fn element_internals(&self) -> Option<DomRoot<ElementInternals>> {
+        self.upcast::<Node>().rare_data().get().and_then(|data| data.element_internals.get())
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#constructing-the-form-data-set>
+    /// Step 5.12
+    pub fn push_form_data(&self, data_set: &mut Vec<FormDatum>) {
+        if let Some(internals) = self.element_internals() {
+            internals.push_form_data(data_set);
+        }
+    }
+
     pub fn set_custom_attr(&self, name: DOMString, value: DOMString) -> ErrorResult {
         if name.chars()
                .skip_while(|&ch| ch != '\u{2d}')
@@ -576,6 +676,7 @@ impl HTMLElement {
                 match type_id {
                     HTMLElementTypeId::HTMLInputElement =>
                         self.downcast::<HTMLInputElement>().unwrap().input_type() != InputType::Hidden,
+                    HTMLElementTypeId::HTMLElement => self.is_form_associated_custom_element(),
                     HTMLElementTypeId::HTMLButtonElement |
                         HTMLElementTypeId::HTMLMeterElement |
                         HTMLElementTypeId::HTMLOutputElement |
@@ -599,6 +700,7 @@ impl HTMLElement {
         match self.upcast::<Node>().type_id() {
             NodeTypeId::Element(ElementTypeId::HTMLElement(type_id)) =>
                 match type_id {
+                    HTMLElementTypeId::HTMLElement => self.is_form_associated_custom_element(),
                     HTMLElementTypeId::HTMLButtonElement |
                         HTMLElementTypeId::HTMLFieldSetElement |
                         HTMLElementTypeId::HTMLInputElement |
@@ -677,6 +779,26 @@ impl VirtualMethods for HTMLElement {
                                                       // FIXME(ajeffrey): Convert directly from AttrValue to DOMString
                                                       DOMString::from(&**attr.value()));
             },
+            (&local_name!("form"), _) if self.is_form_associated_custom_element() => {
+                self.form_attribute_mutated(mutation);
+            },
+            (&local_name!("disabled"), _) if self.is_form_associated_custom_element() => {
+                let element = self.upcast::<Element>();
+                let disabled = match mutation {
+                    AttributeMutation::Set(_) => true,
+                    AttributeMutation::Removed => false,
+                };
+                if element.disabled_state() != disabled {
+                    element.set_disabled_state(disabled);
+                    element.set_enabled_state(!disabled);
+                    if !disabled {
+                        element.check_ancestors_disabled_state_for_form_control();
+                    }
+                    ScriptThread::enqueue_callback_reaction(element,
+                                                            CallbackReaction::FormDisabled(disabled),
+                                                            None);
+                }
+            },
             _ => {}
         }
     }
@@ -686,6 +808,20 @@ impl VirtualMethods for HTMLElement {
             s.bind_to_tree(tree_in_doc);
         }
         self.update_sequentially_focusable_status();
+
+        if self.is_form_associated_custom_element() {
+            self.bind_form_control_to_tree();
+        }
+    }
+
+    fn unbind_from_tree(&self, context: &UnbindContext) {
+        if let Some(ref s) = self.super_type() {
+            s.unbind_from_tree(context);
+        }
+
+        if self.is_form_associated_custom_element() {
+            self.unbind_form_control_from_tree();
+        }
     }
 
     fn parse_plain_attribute(&self, name: &LocalName, value: DOMString) -> AttrValue {
@@ -701,3 +837,41 @@ impl VirtualMethods for HTMLElement {
         }
     }
 }
+
+impl FormControl for HTMLElement {
+    fn form_owner(&self) -> Option<DomRoot<HTMLFormElement>> {
+        self.element_internals().and_then(|internals| internals.form_owner())
+    }
+
+    fn set_form_owner(&self, form: Option<&HTMLFormElement>) {
+        let internals = self.ensure_element_internals();
+        let old_owner = internals.form_owner();
+        if old_owner.r() == form {
+            return;
+        }
+        internals.set_form_owner(form);
+
+        // https://html.spec.whatwg.org/multipage/#reset-the-form-owner
+        // Step 5.
+        if self.is_form_associated_custom_element() {
+            ScriptThread::enqueue_callback_reaction(self.upcast(),
+                                                    CallbackReaction::FormAssociated(form.map(DomRoot::from_ref)),
+                                                    None);
+        }
+    }
+
+    fn to_element<'a>(&'a self) -> &'a Element {
+        self.upcast::<Element>()
+    }
+}
+
+impl Validatable for HTMLElement {
+    fn is_instance_validatable(&self) -> bool {
+        self.is_form_associated_custom_element() &&
+            self.element_internals().map_or(true, |internals| internals.is_candidate_for_constraint_validation())
+    }
+
+    fn validate(&self, validate_flags: ValidationFlags) -> bool {
+        self.element_internals().map_or(true, |internals| internals.satisfies_constraints(validate_flags))
+    }
+}
@@ -18,6 +18,7 @@ use dom::bindings::reflector::DomObject;
 use dom::bindings::root::{Dom, DomOnceCell, DomRoot, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::blob::Blob;
+use dom::customelementregistry::CallbackReaction;
 use dom::document::Document;
 use dom::element::{AttributeMutation, Element};
 use dom::eventtarget::EventTarget;
@@ -46,7 +47,7 @@ use encoding_rs::{Encoding, UTF_8};
 use html5ever::{LocalName, Prefix};
 use hyper::header::{Charset, ContentDisposition, ContentType, DispositionParam, DispositionType};
 use hyper::method::Method;
-use script_thread::MainThreadScriptMsg;
+use script_thread::{MainThreadScriptMsg, ScriptThread};
 use script_traits::LoadData;
 use servo_rand::random;
 use std::borrow::ToOwned;
@@ -178,6 +179,13 @@ impl HTMLFormElementMethods for HTMLFormElement {
                             HTMLElementTypeId::HTMLButtonElement => {
                                 elem.downcast::<HTMLButtonElement>().unwrap().form_owner()
                             }
+                            HTMLElementTypeId::HTMLElement => {
+                                let html_elem = elem.downcast::<HTMLElement>().unwrap();
+                                if !html_elem.is_form_associated_custom_element() {
+                                    return false;
+                                }
+                                html_elem.form_owner()
+                            }
                             HTMLElementTypeId::HTMLFieldSetElement => {
                                 elem.downcast::<HTMLFieldSetElement>().unwrap().form_owner()
                             }
@@ -487,7 +495,7 @@ impl HTMLFormElement {
                     };
                     if !validatable.is_instance_validatable() {
                         None
-                    } else if validatable.validate(ValidationFlags::empty()) {
+                    } else if validatable.validate(ValidationFlags::all()) {
                         None
                     } else {
                         Some(FormSubmittableElement::from_element(&el))
@@ -540,6 +548,12 @@ impl HTMLFormElement {
                             data_set.push(datum);
                         }
                     }
+                    HTMLElementTypeId::HTMLElement => {
+                        let html_element = child.downcast::<HTMLElement>().unwrap();
+                        if html_element.is_form_associated_custom_element() {
+                            html_element.push_form_data(&mut data_set);
+                        }
+                    }
                     HTMLElementTypeId::HTMLObjectElement => {
                         // Unimplemented
                         ()
@@ -656,6 +670,14 @@ impl HTMLFormElement {
                 NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLOutputElement)) => {
                     // Unimplemented
                 }
+                NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLElement)) => {
+                    let html_element = child.downcast::<HTMLElement>().unwrap();
+                    if html_element.is_form_associated_custom_element() {
+                        ScriptThread::enqueue_callback_reaction(html_element.upcast(),
+                                                                CallbackReaction::FormReset,
+                                                                None);
+                    }
+                }
                 _ => {}
             }
         }
@@ -723,6 +745,7 @@ pub enum FormMethod {
 #[allow(dead_code)]
 pub enum FormSubmittableElement {
     ButtonElement(DomRoot<HTMLButtonElement>),
+    CustomElement(DomRoot<HTMLElement>),
     InputElement(DomRoot<HTMLInputElement>),
     // TODO: HTMLKeygenElement unimplemented
     // KeygenElement(&'a HTMLKeygenElement),
@@ -735,6 +758,7 @@ impl FormSubmittableElement {
     fn as_event_target(&self) -> &EventTarget {
         match *self {
             FormSubmittableElement::ButtonElement(ref button) => button.upcast(),
+            FormSubmittableElement::CustomElement(ref element) => element.upcast(),
             FormSubmittableElement::InputElement(ref input) => input.upcast(),
             FormSubmittableElement::ObjectElement(ref object) => object.upcast(),
             FormSubmittableElement::SelectElement(ref select) => select.upcast(),
@@ -757,6 +781,9 @@ impl FormSubmittableElement {
         }
         else if let Some(input) = element.downcast::<HTMLTextAreaElement>() {
             FormSubmittableElement::TextAreaElement(DomRoot::from_ref(&input))
+        }
+        else if let Some(input) = element.downcast::<HTMLElement>() {
+            FormSubmittableElement::CustomElement(DomRoot::from_ref(&input))
         } else {
             unreachable!()
         }
@@ -1077,6 +1104,14 @@ impl FormControlElementHelpers for Element {
             NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLButtonElement)) => {
                 Some(self.downcast::<HTMLButtonElement>().unwrap() as &FormControl)
             },
+            NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLElement)) => {
+                let element = self.downcast::<HTMLElement>().unwrap();
+                if element.is_form_associated_custom_element() {
+                    Some(element as &FormControl)
+                } else {
+                    None
+                }
+            },
             NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLFieldSetElement)) => {
                 Some(self.downcast::<HTMLFieldSetElement>().unwrap() as &FormControl)
             },
@@ -34,6 +34,7 @@ use dom::htmlpictureelement::HTMLPictureElement;
 use dom::htmlsourceelement::HTMLSourceElement;
 use dom::mouseevent::MouseEvent;
 use dom::node::{Node, NodeDamage, document_from_node, window_from_node};
+use dom::performanceresourcetiming::InitiatorType;
 use dom::values::UNSIGNED_LONG_MAX;
 use dom::virtualmethods::VirtualMethods;
 use dom::window::Window;
@@ -50,7 +51,7 @@ use net_traits::image_cache::{CanRequestImages, ImageCache, ImageOrMetadataAvail
 use net_traits::image_cache::{ImageResponder, ImageResponse, ImageState, PendingImageId};
 use net_traits::image_cache::UsePlaceholder;
 use net_traits::request::RequestInit;
-use network_listener::{NetworkListener, PreInvoke};
+use network_listener::{NetworkListener, PreInvoke, ResourceTimingRecorder};
 use num_traits::ToPrimitive;
 use script_thread::ScriptThread;
 use servo_url::ServoUrl;
@@ -149,6 +150,15 @@ impl HTMLImageElement {
     pub fn get_url(&self) -> Option<ServoUrl> {
         self.current_request.borrow().parsed_url.clone()
     }
+
+    /// Returns the decoded image, if it is completely available.
+    pub fn get_image(&self) -> Option<Arc<Image>> {
+        let request = self.current_request.borrow();
+        match request.state {
+            State::CompletelyAvailable => request.image.clone(),
+            _ => None,
+        }
+    }
 }
 
 /// The context required for asynchronously loading an external image.
@@ -159,6 +169,10 @@ struct ImageContext {
     status: Result<(), NetworkError>,
     /// The cache ID for this request.
     id: PendingImageId,
+    /// The document that requested the image.
+    document: Trusted<Document>,
+    /// The timing of the fetch, reported once the image has been received.
+    resource_timing: ResourceTimingRecorder,
 }
 
 impl FetchResponseListener for ImageContext {
@@ -166,6 +180,7 @@ impl FetchResponseListener for ImageContext {
     fn process_request_eof(&mut self) {}
 
     fn process_response(&mut self, metadata: Result<FetchMetadata, NetworkError>) {
+        self.resource_timing.record_response(&metadata);
         self.image_cache.notify_pending_response(
             self.id,
             FetchResponseMsg::ProcessResponse(metadata.clone()));
@@ -189,6 +204,7 @@ impl FetchResponseListener for ImageContext {
     }
 
     fn process_response_chunk(&mut self, payload: Vec<u8>) {
+        self.resource_timing.record_chunk(&payload);
         if self.status.is_ok() {
             self.image_cache.notify_pending_response(
                 self.id,
@@ -197,6 +213,7 @@ impl FetchResponseListener for ImageContext {
     }
 
     fn process_response_eof(&mut self, response: Result<(), NetworkError>) {
+        self.resource_timing.submit(self.document.root().window().upcast());
         self.image_cache.notify_pending_response(
             self.id,
             FetchResponseMsg::ProcessResponseEOF(response));
@@ -278,6 +295,8 @@ impl HTMLImageElement {
             image_cache: window.image_cache(),
             status: Ok(()),
             id: id,
+            document: Trusted::new(&*document),
+            resource_timing: ResourceTimingRecorder::new(img_url.clone(), InitiatorType::Img),
         }));
 
         let (action_sender, action_receiver) = ipc::channel().unwrap();
@@ -12,6 +12,7 @@ use dom::bindings::codegen::Bindings::HTMLFormElementBinding::SelectionMode;
 use dom::bindings::codegen::Bindings::HTMLInputElementBinding;
 use dom::bindings::codegen::Bindings::HTMLInputElementBinding::HTMLInputElementMethods;
 use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventMethods;
+use dom::bindings::codegen::Bindings::MouseEventBinding::MouseEventMethods;
 use dom::bindings::error::{Error, ErrorResult};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::DomObject;
@@ -39,6 +40,7 @@ use dom::validitystate::ValidationFlags;
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use embedder_traits::FilterPattern;
+use euclid::Point2D;
 use html5ever::{LocalName, Prefix};
 use mime_guess;
 use msg::constellation_msg::InputMethodType;
@@ -1366,9 +1368,14 @@ impl VirtualMethods for HTMLInputElement {
                         // now.
                         if let Some(point_in_target) = mouse_event.point_in_target() {
                             let window = window_from_node(self);
+                            let client_point = Point2D::new(
+                                mouse_event.ClientX() as f32,
+                                mouse_event.ClientY() as f32,
+                            );
                             let TextIndexResponse(index) = window.text_index_query(
                                 self.upcast::<Node>().to_trusted_node_address(),
-                                point_in_target
+                                point_in_target,
+                                client_point
                             );
                             if let Some(i) = index {
                                 self.textinput.borrow_mut().set_edit_point_index(i as usize);
@@ -2,6 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use base64;
+use canvas_traits::canvas::byte_swap;
 use cssparser::{Parser as CssParser, ParserInput};
 use dom::attr::Attr;
 use dom::bindings::cell::DomRefCell;
@@ -9,6 +11,7 @@ use dom::bindings::codegen::Bindings::DOMTokenListBinding::DOMTokenListBinding::
 use dom::bindings::codegen::Bindings::HTMLLinkElementBinding;
 use dom::bindings::codegen::Bindings::HTMLLinkElementBinding::HTMLLinkElementMethods;
 use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::root::{DomRoot, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::cssstylesheet::CSSStyleSheet;
@@ -16,18 +19,30 @@ use dom::document::Document;
 use dom::domtokenlist::DOMTokenList;
 use dom::element::{AttributeMutation, Element, ElementCreator};
 use dom::element::{cors_setting_for_element, reflect_cross_origin_attribute, set_cross_origin_attribute};
+use dom::globalscope::GlobalScope;
 use dom::htmlelement::HTMLElement;
 use dom::node::{Node, UnbindContext, document_from_node, window_from_node};
+use dom::performanceresourcetiming::InitiatorType;
 use dom::stylesheet::StyleSheet as DOMStyleSheet;
 use dom::virtualmethods::VirtualMethods;
+use dom::window::Window;
 use dom_struct::dom_struct;
 use embedder_traits::EmbedderMsg;
+use euclid::Size2D;
 use html5ever::{LocalName, Prefix};
-use net_traits::ReferrerPolicy;
+use image::ColorType;
+use image::png::PNGEncoder;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use net_traits::{FetchMetadata, FetchResponseListener, NetworkError, ReferrerPolicy};
+use net_traits::request::{Destination, RequestInit};
+use network_listener::{NetworkListener, PreInvoke, ResourceTimingRecorder};
 use servo_arc::Arc;
+use servo_url::ServoUrl;
 use std::borrow::ToOwned;
 use std::cell::Cell;
 use std::default::Default;
+use std::sync::Mutex;
 use style::attr::AttrValue;
 use style::media_queries::MediaList;
 use style::parser::ParserContext as CssParserContext;
@@ -35,6 +50,8 @@ use style::str::HTML_SPACE_CHARACTERS;
 use style::stylesheets::{CssRuleType, Stylesheet};
 use style_traits::ParsingMode;
 use stylesheet_loader::{StylesheetLoader, StylesheetContextSource, StylesheetOwner};
+use svg_renderer::{Raster, SvgImage};
+use task_source::TaskSourceName;
 
 #[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
 pub struct RequestGenerationId(u32);
@@ -96,13 +113,13 @@ impl HTMLLinkElement {
     // FIXME(emilio): These methods are duplicated with
     // HTMLStyleElement::set_stylesheet.
     pub fn set_stylesheet(&self, s: Arc<Stylesheet>) {
-        let doc = document_from_node(self);
+        let stylesheets_owner = self.upcast::<Node>().stylesheet_list_owner();
         if let Some(ref s) = *self.stylesheet.borrow() {
-            doc.remove_stylesheet(self.upcast(), s)
+            stylesheets_owner.remove_stylesheet(self.upcast(), s)
         }
         *self.stylesheet.borrow_mut() = Some(s.clone());
         self.cssom_stylesheet.set(None);
-        doc.add_stylesheet(self.upcast(), s);
+        stylesheets_owner.add_stylesheet(self.upcast(), s);
     }
 
     pub fn get_stylesheet(&self) -> Option<Arc<Stylesheet>> {
@@ -209,7 +226,7 @@ impl VirtualMethods for HTMLLinkElement {
             s.bind_to_tree(tree_in_doc);
         }
 
-        if tree_in_doc {
+        if tree_in_doc || self.upcast::<Node>().is_in_shadow_tree() {
             let element = self.upcast();
 
             let rel = get_attr(element, &local_name!("rel"));
@@ -220,7 +237,8 @@ impl VirtualMethods for HTMLLinkElement {
                 Some(ref href) if string_is_stylesheet(&rel) => {
                     self.handle_stylesheet_url(href);
                 }
-                Some(ref href) if is_favicon(&rel) => {
+                // Only links in the document tree can change the favicon.
+                Some(ref href) if tree_in_doc && is_favicon(&rel) => {
                     self.handle_favicon_url(rel.as_ref().unwrap(), href, &sizes);
                 }
                 _ => {}
@@ -234,7 +252,7 @@ impl VirtualMethods for HTMLLinkElement {
         }
 
         if let Some(s) = self.stylesheet.borrow_mut().take() {
-            document_from_node(self).remove_stylesheet(self.upcast(), &s);
+            context.parent.stylesheet_list_owner().remove_stylesheet(self.upcast(), &s);
         }
     }
 }
@@ -307,22 +325,156 @@ impl HTMLLinkElement {
         }, link_url, cors_setting, integrity_metadata.to_owned());
     }
 
-    fn handle_favicon_url(&self, _rel: &str, href: &str, _sizes: &Option<String>) {
+    fn handle_favicon_url(&self, _rel: &str, href: &str, sizes: &Option<String>) {
         let document = document_from_node(self);
         match document.base_url().join(href) {
             Ok(url) => {
                 let window = document.window();
-                if window.is_top_level() {
-                    let msg = EmbedderMsg::NewFavicon(url.clone());
-                    window.send_to_embedder(msg);
+                if !window.is_top_level() {
+                    return;
+                }
+                // Embedders can't render SVG icons, so those are sent to them
+                // rasterized instead.
+                let type_ = get_attr(self.upcast(), &local_name!("type"));
+                let is_svg = type_.map_or(false, |type_| type_.trim().eq_ignore_ascii_case("image/svg+xml")) ||
+                    url.path().to_ascii_lowercase().ends_with(".svg");
+                if is_svg {
+                    self.fetch_svg_favicon(url, favicon_size(sizes));
+                } else {
+                    window.send_to_embedder(EmbedderMsg::NewFavicon(url));
                 }
-
             }
             Err(e) => debug!("Parsing url {} failed: {}", href, e)
         }
     }
+
+    fn fetch_svg_favicon(&self, url: ServoUrl, size: f32) {
+        let document = document_from_node(self);
+        let window = document.window();
+
+        let context = ::std::sync::Arc::new(Mutex::new(SvgFaviconContext {
+            window: Trusted::new(window),
+            url: url.clone(),
+            size: size,
+            data: vec![],
+            status: Ok(()),
+            resource_timing: ResourceTimingRecorder::new(url.clone(), InitiatorType::Link),
+        }));
+
+        let (action_sender, action_receiver) = ipc::channel().unwrap();
+        let listener = NetworkListener {
+            context: context,
+            task_source: window.networking_task_source(),
+            canceller: Some(window.task_canceller(TaskSourceName::Networking)),
+        };
+        ROUTER.add_route(action_receiver.to_opaque(), Box::new(move |message| {
+            listener.notify_fetch(message.to().unwrap());
+        }));
+
+        let request = RequestInit {
+            url: url,
+            destination: Destination::Image,
+            origin: document.origin().immutable().clone(),
+            pipeline_id: Some(window.upcast::<GlobalScope>().pipeline_id()),
+            referrer_url: Some(document.url()),
+            referrer_policy: document.get_referrer_policy(),
+            .. RequestInit::default()
+        };
+        document.loader_mut().fetch_async_background(request, action_sender);
+    }
+}
+
+/// The size, in CSS pixels, of SVG favicons whose link doesn't give any.
+const DEFAULT_FAVICON_SIZE: f32 = 32.;
+
+/// The size to rasterize an SVG favicon at: the largest height in the `sizes`
+/// of its link.
+/// <https://html.spec.whatwg.org/multipage/#attr-link-sizes>
+fn favicon_size(sizes: &Option<String>) -> f32 {
+    let sizes = match *sizes {
+        Some(ref sizes) => sizes,
+        None => return DEFAULT_FAVICON_SIZE,
+    };
+    sizes.split(HTML_SPACE_CHARACTERS)
+        .filter_map(|size| {
+            let mut dimensions = size.splitn(2, |c| c == 'x' || c == 'X');
+            let _width = dimensions.next()?.parse::<u32>().ok()?;
+            dimensions.next()?.parse::<u32>().ok()
+        })
+        .max()
+        .map_or(DEFAULT_FAVICON_SIZE, |height| height as f32)
+}
+
+/// Encodes a rasterized favicon into a PNG `data:` URL.
+fn favicon_data_url(raster: Raster) -> ServoUrl {
+    let mut data = raster.bytes;
+    byte_swap(&mut data);
+    let mut encoded = vec![];
+    PNGEncoder::new(&mut encoded)
+        .encode(&data, raster.width, raster.height, ColorType::RGBA(8))
+        .unwrap();
+    ServoUrl::parse(&format!("data:image/png;base64,{}", base64::encode(&encoded))).unwrap()
+}
+
+/// The context required for fetching and rasterizing an SVG favicon.
+struct SvgFaviconContext {
+    /// The window whose embedder is told about the favicon.
+    window: Trusted<Window>,
+    /// The URL of the favicon.
+    url: ServoUrl,
+    /// The width and height to rasterize the favicon at, in CSS pixels.
+    size: f32,
+    /// The response body received to date.
+    data: Vec<u8>,
+    /// Indicates whether the request failed, and why.
+    status: Result<(), NetworkError>,
+    /// The timing of the fetch, reported once the favicon has been received.
+    resource_timing: ResourceTimingRecorder,
+}
+
+impl FetchResponseListener for SvgFaviconContext {
+    fn process_request_body(&mut self) {}
+    fn process_request_eof(&mut self) {}
+
+    fn process_response(&mut self, metadata: Result<FetchMetadata, NetworkError>) {
+        self.resource_timing.record_response(&metadata);
+        let metadata = metadata.ok().map(|meta| match meta {
+            FetchMetadata::Unfiltered(m) => m,
+            FetchMetadata::Filtered { unsafe_, .. } => unsafe_
+        });
+        let status_code = metadata.and_then(|m| m.status.map(|(code, _)| code)).unwrap_or(0);
+
+        self.status = match status_code {
+            0 => Err(NetworkError::Internal("No http status code received".to_owned())),
+            200...299 => Ok(()), // HTTP ok status codes
+            _ => Err(NetworkError::Internal(format!("HTTP error code {}", status_code)))
+        };
+    }
+
+    fn process_response_chunk(&mut self, mut chunk: Vec<u8>) {
+        self.resource_timing.record_chunk(&chunk);
+        if self.status.is_ok() {
+            self.data.append(&mut chunk);
+        }
+    }
+
+    fn process_response_eof(&mut self, response: Result<(), NetworkError>) {
+        let window = self.window.root();
+        self.resource_timing.submit(window.upcast());
+
+        // When the icon can't be rasterized, the embedder still gets its URL.
+        let size = Size2D::new(self.size, self.size);
+        let device_pixel_ratio = window.device_pixel_ratio().get();
+        let favicon = response.and(self.status.clone()).ok()
+            .and_then(|_| SvgImage::parse(&self.data))
+            .and_then(|document| document.rasterize(size, device_pixel_ratio))
+            .map_or_else(|| self.url.clone(), favicon_data_url);
+        window.send_to_embedder(EmbedderMsg::NewFavicon(favicon));
+    }
 }
 
+impl PreInvoke for SvgFaviconContext {}
+
 impl StylesheetOwner for HTMLLinkElement {
     fn increment_pending_loads_count(&self) {
         self.pending_loads.set(self.pending_loads.get() + 1)
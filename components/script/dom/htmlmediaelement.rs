@@ -28,6 +28,7 @@ use dom::htmlelement::HTMLElement;
 use dom::htmlsourceelement::HTMLSourceElement;
 use dom::mediaerror::MediaError;
 use dom::node::{window_from_node, document_from_node, Node, UnbindContext};
+use dom::performanceresourcetiming::InitiatorType;
 use dom::promise::Promise;
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
@@ -38,7 +39,7 @@ use microtask::{Microtask, MicrotaskRunnable};
 use mime::{Mime, SubLevel, TopLevel};
 use net_traits::{FetchResponseListener, FetchMetadata, Metadata, NetworkError};
 use net_traits::request::{CredentialsMode, Destination, RequestInit};
-use network_listener::{NetworkListener, PreInvoke};
+use network_listener::{NetworkListener, PreInvoke, ResourceTimingRecorder};
 use script_thread::ScriptThread;
 use servo_url::ServoUrl;
 use std::cell::Cell;
@@ -602,7 +603,7 @@ impl HTMLMediaElement {
                     .. RequestInit::default()
                 };
 
-                let context = Arc::new(Mutex::new(HTMLMediaElementContext::new(self)));
+                let context = Arc::new(Mutex::new(HTMLMediaElementContext::new(self, request.url.clone())));
                 let (action_sender, action_receiver) = ipc::channel().unwrap();
                 let window = window_from_node(self);
                 let listener = NetworkListener {
@@ -988,6 +989,8 @@ struct HTMLMediaElementContext {
     have_metadata: bool,
     /// True if this response is invalid and should be ignored.
     ignore_response: bool,
+    /// The timing of the fetch, reported once the media resource has been received.
+    resource_timing: ResourceTimingRecorder,
 }
 
 // https://html.spec.whatwg.org/multipage/#media-data-processing-steps-list
@@ -997,6 +1000,7 @@ impl FetchResponseListener for HTMLMediaElementContext {
     fn process_request_eof(&mut self) {}
 
     fn process_response(&mut self, metadata: Result<FetchMetadata, NetworkError>) {
+        self.resource_timing.record_response(&metadata);
         self.metadata = metadata.ok().map(|m| {
             match m {
                 FetchMetadata::Unfiltered(m) => m,
@@ -1018,6 +1022,7 @@ impl FetchResponseListener for HTMLMediaElementContext {
     }
 
     fn process_response_chunk(&mut self, mut payload: Vec<u8>) {
+        self.resource_timing.record_chunk(&payload);
         if self.ignore_response {
             // An error was received previously, skip processing the payload.
             return;
@@ -1050,11 +1055,13 @@ impl FetchResponseListener for HTMLMediaElementContext {
 
     // https://html.spec.whatwg.org/multipage/#media-data-processing-steps-list
     fn process_response_eof(&mut self, status: Result<(), NetworkError>) {
+        let elem = self.elem.root();
+        self.resource_timing.submit(&elem.global());
+
         if self.ignore_response {
             // An error was received previously, skip processing the payload.
             return;
         }
-        let elem = self.elem.root();
 
         // => "If the media data can be fetched but is found by inspection to be in an unsupported
         //     format, or can otherwise not be rendered at all"
@@ -1100,7 +1107,11 @@ impl PreInvoke for HTMLMediaElementContext {
 }
 
 impl HTMLMediaElementContext {
-    fn new(elem: &HTMLMediaElement) -> HTMLMediaElementContext {
+    fn new(elem: &HTMLMediaElement, url: ServoUrl) -> HTMLMediaElementContext {
+        let initiator_type = match elem.media_type_id() {
+            HTMLMediaElementTypeId::HTMLAudioElement => InitiatorType::Audio,
+            HTMLMediaElementTypeId::HTMLVideoElement => InitiatorType::Video,
+        };
         HTMLMediaElementContext {
             elem: Trusted::new(elem),
             data: vec![],
@@ -1109,6 +1120,7 @@ impl HTMLMediaElementContext {
             next_progress_event: time::get_time() + Duration::milliseconds(350),
             have_metadata: false,
             ignore_response: false,
+            resource_timing: ResourceTimingRecorder::new(url, initiator_type),
         }
     }
 
@@ -21,6 +21,7 @@ use dom::globalscope::GlobalScope;
 use dom::htmlelement::HTMLElement;
 use dom::node::{ChildrenMutation, CloneChildrenFlag, Node};
 use dom::node::{document_from_node, window_from_node};
+use dom::performanceresourcetiming::InitiatorType;
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use encoding_rs::Encoding;
@@ -30,7 +31,7 @@ use ipc_channel::router::ROUTER;
 use js::jsval::UndefinedValue;
 use net_traits::{FetchMetadata, FetchResponseListener, Metadata, NetworkError};
 use net_traits::request::{CorsSettings, CredentialsMode, Destination, RequestInit, RequestMode};
-use network_listener::{NetworkListener, PreInvoke};
+use network_listener::{NetworkListener, PreInvoke, ResourceTimingRecorder};
 use servo_atoms::Atom;
 use servo_config::opts;
 use servo_url::ServoUrl;
@@ -154,7 +155,9 @@ struct ScriptContext {
     /// The initial URL requested.
     url: ServoUrl,
     /// Indicates whether the request failed, and why
-    status: Result<(), NetworkError>
+    status: Result<(), NetworkError>,
+    /// The timing of the fetch, reported once the script has been received.
+    resource_timing: ResourceTimingRecorder,
 }
 
 impl FetchResponseListener for ScriptContext {
@@ -164,6 +167,7 @@ impl FetchResponseListener for ScriptContext {
 
     fn process_response(&mut self,
                         metadata: Result<FetchMetadata, NetworkError>) {
+        self.resource_timing.record_response(&metadata);
         self.metadata = metadata.ok().map(|meta| match meta {
             FetchMetadata::Unfiltered(m) => m,
             FetchMetadata::Filtered { unsafe_, .. } => unsafe_
@@ -184,6 +188,7 @@ impl FetchResponseListener for ScriptContext {
     }
 
     fn process_response_chunk(&mut self, mut chunk: Vec<u8>) {
+        self.resource_timing.record_chunk(&chunk);
         if self.status.is_ok() {
             self.data.append(&mut chunk);
         }
@@ -211,6 +216,7 @@ impl FetchResponseListener for ScriptContext {
         // Step 18.6 (When the chosen algorithm asynchronously completes).
         let elem = self.elem.root();
         let document = document_from_node(&*elem);
+        self.resource_timing.submit(document.window().upcast());
 
         match self.kind {
             ExternalScriptKind::Asap => document.asap_script_loaded(&elem, load),
@@ -267,7 +273,8 @@ fn fetch_a_classic_script(script: &HTMLScriptElement,
         data: vec!(),
         metadata: None,
         url: url.clone(),
-        status: Ok(())
+        status: Ok(()),
+        resource_timing: ResourceTimingRecorder::new(url.clone(), InitiatorType::Script),
     }));
 
     let (action_sender, action_receiver) = ipc::channel().unwrap();
@@ -0,0 +1,286 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::attr::Attr;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::HTMLSlotElementBinding;
+use dom::bindings::codegen::Bindings::HTMLSlotElementBinding::{AssignedNodesOptions, HTMLSlotElementMethods};
+use dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMode;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::bindings::str::DOMString;
+use dom::document::Document;
+use dom::element::{AttributeMutation, Element};
+use dom::htmlelement::HTMLElement;
+use dom::mutationobserver::MutationObserver;
+use dom::node::{Node, NodeDamage};
+use dom::shadowroot::ShadowRoot;
+use dom::text::Text;
+use dom::virtualmethods::VirtualMethods;
+use dom_struct::dom_struct;
+use html5ever::{LocalName, Prefix};
+use script_thread::ScriptThread;
+
+/// <https://html.spec.whatwg.org/multipage/#the-slot-element>
+#[dom_struct]
+pub struct HTMLSlotElement {
+    htmlelement: HTMLElement,
+    /// <https://dom.spec.whatwg.org/#slot-assigned-nodes>
+    assigned_nodes: DomRefCell<Vec<Dom<Node>>>,
+}
+
+impl HTMLSlotElement {
+    fn new_inherited(local_name: LocalName,
+                     prefix: Option<Prefix>,
+                     document: &Document) -> HTMLSlotElement {
+        HTMLSlotElement {
+            htmlelement: HTMLElement::new_inherited(local_name, prefix, document),
+            assigned_nodes: DomRefCell::new(vec![]),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(local_name: LocalName,
+               prefix: Option<Prefix>,
+               document: &Document) -> DomRoot<HTMLSlotElement> {
+        Node::reflect_node(Box::new(HTMLSlotElement::new_inherited(local_name, prefix, document)),
+                           document,
+                           HTMLSlotElementBinding::Wrap)
+    }
+
+    /// <https://dom.spec.whatwg.org/#slot-name>
+    fn name(&self) -> DOMString {
+        self.upcast::<Element>().get_string_attribute(&local_name!("name"))
+    }
+
+    /// <https://dom.spec.whatwg.org/#slot-assigned-nodes>
+    pub fn assigned_nodes(&self) -> Vec<DomRoot<Node>> {
+        self.assigned_nodes.borrow().iter().map(|node| DomRoot::from_ref(&**node)).collect()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-slotable>
+    pub fn is_slottable(node: &Node) -> bool {
+        node.is::<Element>() || node.is::<Text>()
+    }
+
+    /// <https://dom.spec.whatwg.org/#slotable-name>
+    fn slottable_name(slottable: &Node) -> DOMString {
+        match slottable.downcast::<Element>() {
+            Some(element) => element.get_string_attribute(&local_name!("slot")),
+            None => DOMString::new(),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-a-slot>
+    pub fn find_a_slot(slottable: &Node, open: bool) -> Option<DomRoot<HTMLSlotElement>> {
+        // Step 1.
+        let parent = slottable.GetParentNode()?;
+
+        // Step 2.
+        let shadow = parent.downcast::<Element>()?.shadow_root()?;
+
+        // Step 3.
+        if open && shadow.mode() != ShadowRootMode::Open {
+            return None;
+        }
+
+        // Step 4.
+        let name = HTMLSlotElement::slottable_name(slottable);
+        shadow.upcast::<Node>()
+            .traverse_preorder()
+            .filter_map(DomRoot::downcast::<HTMLSlotElement>)
+            .find(|slot| slot.name() == name)
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-slotables>
+    fn find_slottables(&self) -> Vec<DomRoot<Node>> {
+        // Step 1.
+        let mut result = vec![];
+
+        // Steps 2-3.
+        let root = self.upcast::<Node>().root_node();
+        let shadow = match root.downcast::<ShadowRoot>() {
+            Some(shadow) => shadow,
+            None => return result,
+        };
+        let host = shadow.host();
+
+        // Step 4.
+        for slottable in host.upcast::<Node>().children() {
+            if !HTMLSlotElement::is_slottable(&slottable) {
+                continue;
+            }
+            let found = HTMLSlotElement::find_a_slot(&slottable, false);
+            if found.map_or(false, |slot| &*slot == self) {
+                result.push(slottable);
+            }
+        }
+
+        // Step 5.
+        result
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-flattened-slotables>
+    fn find_flattened_slottables(&self) -> Vec<DomRoot<Node>> {
+        // Step 1.
+        let mut result = vec![];
+
+        // Step 2.
+        if !self.upcast::<Node>().root_node().is::<ShadowRoot>() {
+            return result;
+        }
+
+        // Step 3.
+        let mut slottables = self.assigned_nodes();
+
+        // Step 4.
+        if slottables.is_empty() {
+            slottables = self.upcast::<Node>()
+                .children()
+                .filter(|child| HTMLSlotElement::is_slottable(child))
+                .collect();
+        }
+
+        // Step 5.
+        for node in slottables {
+            match node.downcast::<HTMLSlotElement>() {
+                Some(slot) if node.root_node().is::<ShadowRoot>() => {
+                    result.extend(slot.find_flattened_slottables());
+                },
+                _ => result.push(node),
+            }
+        }
+
+        // Step 6.
+        result
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-slotables>
+    pub fn assign_slottables(&self) {
+        // Step 1.
+        let slottables = self.find_slottables();
+
+        // Step 2.
+        let unchanged = {
+            let assigned = self.assigned_nodes.borrow();
+            assigned.len() == slottables.len() &&
+                assigned.iter().zip(slottables.iter()).all(|(a, b)| &**a == &**b)
+        };
+        if unchanged {
+            return;
+        }
+        self.signal_a_slot_change();
+
+        // Step 3.
+        let old = self.assigned_nodes();
+        for node in &old {
+            if node.assigned_slot().map_or(false, |slot| &*slot == self) {
+                node.set_assigned_slot(None);
+            }
+        }
+        *self.assigned_nodes.borrow_mut() =
+            slottables.iter().map(|node| Dom::from_ref(&**node)).collect();
+
+        // Step 4.
+        for slottable in &slottables {
+            slottable.set_assigned_slot(Some(self));
+        }
+
+        // The flat tree changed, so both the slot and whatever moved in or out
+        // of it need new boxes.
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        for node in old.iter().chain(slottables.iter()) {
+            if node.is::<Element>() {
+                node.dirty(NodeDamage::OtherNodeDamage);
+            }
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-slotables-for-a-tree>
+    pub fn assign_slottables_for_a_tree(root: &Node) {
+        let slots = root.traverse_preorder().filter_map(DomRoot::downcast::<HTMLSlotElement>);
+        for slot in slots {
+            slot.assign_slottables();
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-a-slot>
+    pub fn assign_a_slot(slottable: &Node) {
+        // Step 1.
+        let slot = HTMLSlotElement::find_a_slot(slottable, false);
+
+        // Step 2.
+        if let Some(slot) = slot {
+            slot.assign_slottables();
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#signal-a-slot-change>
+    pub fn signal_a_slot_change(&self) {
+        // Step 1.
+        ScriptThread::add_signal_slot(self);
+
+        // Step 2.
+        MutationObserver::queue_mutation_observer_compound_microtask();
+    }
+}
+
+impl HTMLSlotElementMethods for HTMLSlotElement {
+    // https://html.spec.whatwg.org/multipage/#dom-slot-name
+    make_getter!(Name, "name");
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-name
+    make_setter!(SetName, "name");
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-assignednodes
+    fn AssignedNodes(&self, options: &AssignedNodesOptions) -> Vec<DomRoot<Node>> {
+        if options.flatten {
+            self.find_flattened_slottables()
+        } else {
+            self.assigned_nodes()
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-assignedelements
+    fn AssignedElements(&self, options: &AssignedNodesOptions) -> Vec<DomRoot<Element>> {
+        self.AssignedNodes(options)
+            .into_iter()
+            .filter_map(DomRoot::downcast::<Element>)
+            .collect()
+    }
+}
+
+impl VirtualMethods for HTMLSlotElement {
+    fn super_type(&self) -> Option<&VirtualMethods> {
+        Some(self.upcast::<HTMLElement>() as &VirtualMethods)
+    }
+
+    fn attribute_mutated(&self, attr: &Attr, mutation: AttributeMutation) {
+        self.super_type().unwrap().attribute_mutated(attr, mutation);
+
+        // https://dom.spec.whatwg.org/#shadow-tree-slots
+        if attr.local_name() == &local_name!("name") && attr.namespace() == &ns!() {
+            let root = self.upcast::<Node>().root_node();
+            if root.is::<ShadowRoot>() {
+                HTMLSlotElement::assign_slottables_for_a_tree(&root);
+            }
+        }
+    }
+}
+
+pub trait LayoutHTMLSlotElementHelpers {
+    #[allow(unsafe_code)]
+    unsafe fn assigned_node_for_layout(&self, index: usize) -> Option<LayoutDom<Node>>;
+}
+
+impl LayoutHTMLSlotElementHelpers for LayoutDom<HTMLSlotElement> {
+    #[allow(unrooted_must_root)]
+    #[allow(unsafe_code)]
+    unsafe fn assigned_node_for_layout(&self, index: usize) -> Option<LayoutDom<Node>> {
+        (*self.unsafe_get()).assigned_nodes
+            .borrow_for_layout()
+            .get(index)
+            .map(|node| node.to_layout())
+    }
+}
@@ -124,13 +124,13 @@ impl HTMLStyleElement {
 
     // FIXME(emilio): This is duplicated with HTMLLinkElement::set_stylesheet.
     pub fn set_stylesheet(&self, s: Arc<Stylesheet>) {
-        let doc = document_from_node(self);
+        let stylesheets_owner = self.upcast::<Node>().stylesheet_list_owner();
         if let Some(ref s) = *self.stylesheet.borrow() {
-            doc.remove_stylesheet(self.upcast(), s)
+            stylesheets_owner.remove_stylesheet(self.upcast(), s)
         }
         *self.stylesheet.borrow_mut() = Some(s.clone());
         self.cssom_stylesheet.set(None);
-        doc.add_stylesheet(self.upcast(), s);
+        stylesheets_owner.add_stylesheet(self.upcast(), s);
     }
 
     pub fn get_stylesheet(&self) -> Option<Arc<Stylesheet>> {
@@ -164,7 +164,8 @@ impl VirtualMethods for HTMLStyleElement {
         // "The element is not on the stack of open elements of an HTML parser or XML parser,
         // and one of its child nodes is modified by a script."
         // TODO: Handle Text child contents being mutated.
-        if self.upcast::<Node>().is_in_doc() && !self.in_stack_of_open_elements.get() {
+        let node = self.upcast::<Node>();
+        if (node.is_in_doc() || node.is_in_shadow_tree()) && !self.in_stack_of_open_elements.get() {
             self.parse_own_css();
         }
     }
@@ -176,7 +177,11 @@ impl VirtualMethods for HTMLStyleElement {
         // Handles the case when:
         // "The element is not on the stack of open elements of an HTML parser or XML parser,
         // and it becomes connected or disconnected."
-        if tree_in_doc && !self.in_stack_of_open_elements.get() {
+        //
+        // Style elements in shadow trees own their sheets for as long as
+        // they're in it, whether the shadow host is connected or not.
+        let in_shadow_tree = self.upcast::<Node>().is_in_shadow_tree();
+        if (tree_in_doc || in_shadow_tree) && !self.in_stack_of_open_elements.get() {
             self.parse_own_css();
         }
     }
@@ -188,7 +193,8 @@ impl VirtualMethods for HTMLStyleElement {
         // Handles the case when:
         // "The element is popped off the stack of open elements of an HTML parser or XML parser."
         self.in_stack_of_open_elements.set(false);
-        if self.upcast::<Node>().is_in_doc() {
+        let node = self.upcast::<Node>();
+        if node.is_in_doc() || node.is_in_shadow_tree() {
             self.parse_own_css();
         }
     }
@@ -198,9 +204,9 @@ impl VirtualMethods for HTMLStyleElement {
             s.unbind_from_tree(context);
         }
 
-        if context.tree_in_doc {
+        if context.tree_in_doc || context.parent.is_in_shadow_tree() {
             if let Some(s) = self.stylesheet.borrow_mut().take() {
-                document_from_node(self).remove_stylesheet(self.upcast(), &s)
+                context.parent.stylesheet_list_owner().remove_stylesheet(self.upcast(), &s)
             }
         }
     }
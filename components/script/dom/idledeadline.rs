@@ -0,0 +1,53 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::IdleDeadlineBinding::{self, IdleDeadlineMethods};
+use dom::bindings::codegen::Bindings::PerformanceBinding::DOMHighResTimeStamp;
+use dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+/// <https://w3c.github.io/requestidlecallback/#the-idledeadline-interface>
+#[dom_struct]
+pub struct IdleDeadline {
+    reflector_: Reflector,
+    window: Dom<Window>,
+    /// The end of the idle period, in the same time base as `performance.now()`.
+    deadline: f64,
+    did_timeout: bool,
+}
+
+impl IdleDeadline {
+    fn new_inherited(window: &Window, deadline: f64, did_timeout: bool) -> IdleDeadline {
+        IdleDeadline {
+            reflector_: Reflector::new(),
+            window: Dom::from_ref(window),
+            deadline,
+            did_timeout,
+        }
+    }
+
+    pub fn new(window: &Window, deadline: f64, did_timeout: bool) -> DomRoot<IdleDeadline> {
+        reflect_dom_object(Box::new(IdleDeadline::new_inherited(window, deadline, did_timeout)),
+                           window,
+                           IdleDeadlineBinding::Wrap)
+    }
+}
+
+impl IdleDeadlineMethods for IdleDeadline {
+    // https://w3c.github.io/requestidlecallback/#dom-idledeadline-timeremaining
+    fn TimeRemaining(&self) -> DOMHighResTimeStamp {
+        let now = *self.window.Performance().Now();
+        Finite::wrap((self.deadline - now).max(0.))
+    }
+
+    // https://w3c.github.io/requestidlecallback/#dom-idledeadline-didtimeout
+    fn DidTimeout(&self) -> bool {
+        self.did_timeout
+    }
+}
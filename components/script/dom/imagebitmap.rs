@@ -0,0 +1,372 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::{byte_swap, multiply_u8_pixel};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{self, ImageBitmapMethods, ImageBitmapOptions};
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapSource, ImageOrientation};
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{PremultiplyAlpha, ResizeQuality};
+use dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
+use dom::bindings::error::Error;
+use dom::bindings::refcounted::TrustedPromise;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom_struct::dom_struct;
+use euclid::{Point2D, Rect, Size2D};
+use image::{FilterType, ImageBuffer, Rgba};
+use image::imageops;
+use net_traits::image::base::{PixelFormat, load_from_memory};
+use std::rc::Rc;
+use std::thread;
+use task_source::{TaskSource, TaskSourceName};
+use unpremultiplytable::UNPREMULTIPLY_TABLE;
+
+// https://html.spec.whatwg.org/multipage/#imagebitmap
+#[dom_struct]
+pub struct ImageBitmap {
+    reflector_: Reflector,
+    width: u32,
+    height: u32,
+    /// The bitmap, as BGRA pixels, or `None` once it has been closed.
+    bitmap_data: DomRefCell<Option<Vec<u8>>>,
+    /// Whether the color channels of `bitmap_data` are premultiplied by alpha,
+    /// which is not the case when created with `premultiplyAlpha: "none"`.
+    alpha_premultiplied: bool,
+    origin_clean: bool,
+}
+
+impl ImageBitmap {
+    fn new_inherited(size: Size2D<u32>,
+                     bitmap_data: Vec<u8>,
+                     alpha_premultiplied: bool,
+                     origin_clean: bool)
+                     -> ImageBitmap {
+        ImageBitmap {
+            reflector_: Reflector::new(),
+            width: size.width,
+            height: size.height,
+            bitmap_data: DomRefCell::new(Some(bitmap_data)),
+            alpha_premultiplied: alpha_premultiplied,
+            origin_clean: origin_clean,
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               size: Size2D<u32>,
+               bitmap_data: Vec<u8>,
+               alpha_premultiplied: bool,
+               origin_clean: bool)
+               -> DomRoot<ImageBitmap> {
+        let bitmap = ImageBitmap::new_inherited(size, bitmap_data, alpha_premultiplied, origin_clean);
+        reflect_dom_object(Box::new(bitmap),
+                           global,
+                           ImageBitmapBinding::Wrap)
+    }
+
+    /// Returns the pixels of the bitmap as they are stored, along with its
+    /// size, or `None` if it was closed.
+    pub fn bitmap_data(&self) -> Option<(Vec<u8>, Size2D<u32>)> {
+        self.bitmap_data.borrow().as_ref().map(|data| {
+            (data.clone(), Size2D::new(self.width, self.height))
+        })
+    }
+
+    /// Returns the pixels of the bitmap as premultiplied BGRA, which is what
+    /// canvases draw, or `None` if it was closed.
+    pub fn premultiplied_data(&self) -> Option<(Vec<u8>, Size2D<u32>)> {
+        self.bitmap_data().map(|(mut data, size)| {
+            if !self.alpha_premultiplied {
+                premultiply(&mut data);
+            }
+            (data, size)
+        })
+    }
+
+    pub fn is_alpha_premultiplied(&self) -> bool {
+        self.alpha_premultiplied
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.bitmap_data.borrow().is_none()
+    }
+
+    pub fn origin_is_clean(&self) -> bool {
+        self.origin_clean
+    }
+
+    /// Creates a bitmap out of `image`, cropped to `crop` if given.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-createimagebitmap>
+    #[allow(unrooted_must_root)]
+    pub fn create(global: &GlobalScope,
+                  image: ImageBitmapSource,
+                  crop: Option<Rect<i32>>,
+                  options: &ImageBitmapOptions)
+                  -> Rc<Promise> {
+        let promise = Promise::new(global);
+
+        // Step 2.
+        if crop.map_or(false, |crop| crop.size.width == 0 || crop.size.height == 0) {
+            promise.reject_error(Error::Range("The source width and height must not be 0".to_owned()));
+            return promise;
+        }
+
+        // Step 3.
+        if options.resizeWidth == Some(0) || options.resizeHeight == Some(0) {
+            promise.reject_error(Error::InvalidState);
+            return promise;
+        }
+
+        let format = BitmapFormat {
+            crop: crop,
+            resize_width: options.resizeWidth,
+            resize_height: options.resizeHeight,
+            resize_quality: options.resizeQuality,
+            flip_y: options.imageOrientation == ImageOrientation::FlipY,
+            premultiply: options.premultiplyAlpha != PremultiplyAlpha::None,
+        };
+
+        // Step 4-6, the pixels being BGRA, premultiplied for everything but
+        // decoded images and ImageData.
+        let (data, size, premultiplied, origin_clean) = match image {
+            ImageBitmapSource::HTMLImageElement(ref image) => {
+                let bitmap = match image.get_image() {
+                    Some(ref bitmap) if bitmap.format == PixelFormat::BGRA8 => bitmap.clone(),
+                    _ => {
+                        promise.reject_error(Error::InvalidState);
+                        return promise;
+                    },
+                };
+                let origin_clean = image.get_origin()
+                    .map_or(false, |origin| origin.same_origin(GlobalScope::entry().origin()));
+                (bitmap.first_frame_bytes().to_vec(), Size2D::new(bitmap.width, bitmap.height), false, origin_clean)
+            },
+            ImageBitmapSource::HTMLCanvasElement(ref canvas) => {
+                let _ = canvas.get_or_init_2d_context();
+                match canvas.fetch_all_data() {
+                    Some((data, size)) => (data, size.to_u32(), true, canvas.origin_is_clean()),
+                    None => {
+                        promise.reject_error(Error::InvalidState);
+                        return promise;
+                    },
+                }
+            },
+            ImageBitmapSource::CanvasRenderingContext2D(ref context) => {
+                let canvas = context.Canvas();
+                match canvas.fetch_all_data() {
+                    Some((data, size)) => (data, size.to_u32(), true, canvas.origin_is_clean()),
+                    None => {
+                        promise.reject_error(Error::InvalidState);
+                        return promise;
+                    },
+                }
+            },
+            ImageBitmapSource::OffscreenCanvas(ref canvas) => {
+                match canvas.fetch_all_data() {
+                    Some((data, size)) => (data, size.to_u32(), true, canvas.origin_is_clean()),
+                    None => {
+                        promise.reject_error(Error::InvalidState);
+                        return promise;
+                    },
+                }
+            },
+            ImageBitmapSource::ImageBitmap(ref bitmap) => {
+                match bitmap.bitmap_data() {
+                    Some((data, size)) => (data, size, bitmap.alpha_premultiplied, bitmap.origin_clean),
+                    None => {
+                        promise.reject_error(Error::InvalidState);
+                        return promise;
+                    },
+                }
+            },
+            ImageBitmapSource::ImageData(ref image_data) => {
+                let mut data = image_data.get_data_array();
+                byte_swap(&mut data);
+                (data, Size2D::new(image_data.Width(), image_data.Height()), false, true)
+            },
+            ImageBitmapSource::Blob(ref blob) => {
+                match blob.get_bytes() {
+                    Ok(bytes) => ImageBitmap::decode_blob(global, &promise, bytes, format),
+                    Err(()) => promise.reject_error(Error::InvalidState),
+                }
+                return promise;
+            },
+            ImageBitmapSource::CSSStyleValue(_) => {
+                promise.reject_error(Error::InvalidState);
+                return promise;
+            },
+        };
+
+        if size.width == 0 || size.height == 0 {
+            promise.reject_error(Error::InvalidState);
+            return promise;
+        }
+
+        let (data, size, premultiplied) = format.apply(data, size, premultiplied);
+        promise.resolve_native(&ImageBitmap::new(global, size, data, premultiplied, origin_clean));
+        promise
+    }
+
+    /// Decodes the image held by a blob in parallel, resolving `promise` with
+    /// the resulting bitmap once done.
+    fn decode_blob(global: &GlobalScope, promise: &Rc<Promise>, bytes: Vec<u8>, format: BitmapFormat) {
+        let trusted_promise = TrustedPromise::new(promise.clone());
+        let task_source = global.networking_task_source();
+        let canceller = global.task_canceller(TaskSourceName::Networking);
+        thread::Builder::new().name("ImageBitmapDecoder".to_owned()).spawn(move || {
+            let image = load_from_memory(&bytes);
+            let _ = task_source.queue_with_canceller(
+                task!(resolve_image_bitmap: move || {
+                    let promise = trusted_promise.root();
+                    let image = match image {
+                        Some(image) => image,
+                        None => return promise.reject_error(Error::InvalidState),
+                    };
+                    let size = Size2D::new(image.width, image.height);
+                    let (data, size, premultiplied) = format.apply(image.first_frame_bytes().to_vec(), size, false);
+                    let bitmap = ImageBitmap::new(&promise.global(), size, data, premultiplied, true);
+                    promise.resolve_native(&bitmap);
+                }),
+                &canceller,
+            );
+        }).expect("Thread spawning failed");
+    }
+}
+
+/// How to crop, resize and orient the source of a new bitmap.
+#[derive(Clone, Copy)]
+struct BitmapFormat {
+    crop: Option<Rect<i32>>,
+    resize_width: Option<u32>,
+    resize_height: Option<u32>,
+    resize_quality: ResizeQuality,
+    flip_y: bool,
+    premultiply: bool,
+}
+
+impl BitmapFormat {
+    /// Converts BGRA pixels of the given size to this format, returning the
+    /// new pixels, their size, and whether they are premultiplied.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#cropped-to-the-source-rectangle-with-formatting>
+    fn apply(&self, data: Vec<u8>, size: Size2D<u32>, premultiplied: bool) -> (Vec<u8>, Size2D<u32>, bool) {
+        // Step 1-3.
+        let source_rect = match self.crop {
+            Some(crop) => {
+                let mut origin = crop.origin;
+                let mut size = crop.size;
+                if size.width < 0 {
+                    origin.x += size.width;
+                    size.width = -size.width;
+                }
+                if size.height < 0 {
+                    origin.y += size.height;
+                    size.height = -size.height;
+                }
+                Rect::new(origin, size)
+            },
+            None => Rect::new(Point2D::zero(), size.to_i32()),
+        };
+        let mut data = crop_pixels(&data, size, source_rect);
+        let source_size = source_rect.size.to_u32();
+
+        // Step 4-7.
+        let output_size = Size2D::new(
+            self.resize_width.unwrap_or_else(|| match self.resize_height {
+                Some(height) => (source_size.width as f64 * height as f64 / source_size.height as f64).ceil() as u32,
+                None => source_size.width,
+            }),
+            self.resize_height.unwrap_or_else(|| match self.resize_width {
+                Some(width) => (source_size.height as f64 * width as f64 / source_size.width as f64).ceil() as u32,
+                None => source_size.height,
+            }),
+        );
+
+        // Step 10.
+        if output_size != source_size {
+            let filter = match self.resize_quality {
+                ResizeQuality::Pixelated => FilterType::Nearest,
+                ResizeQuality::Low => FilterType::Triangle,
+                ResizeQuality::Medium => FilterType::CatmullRom,
+                ResizeQuality::High => FilterType::Lanczos3,
+            };
+            let buffer: ImageBuffer<Rgba<u8>, _> =
+                ImageBuffer::from_raw(source_size.width, source_size.height, data).unwrap();
+            data = imageops::resize(&buffer, output_size.width, output_size.height, filter).into_raw();
+        }
+
+        // Step 11.
+        if self.flip_y {
+            let buffer: ImageBuffer<Rgba<u8>, _> =
+                ImageBuffer::from_raw(output_size.width, output_size.height, data).unwrap();
+            data = imageops::flip_vertical(&buffer).into_raw();
+        }
+
+        // Step 12.
+        if self.premultiply && !premultiplied {
+            premultiply(&mut data);
+        } else if !self.premultiply && premultiplied {
+            unpremultiply(&mut data);
+        }
+
+        (data, output_size, self.premultiply)
+    }
+}
+
+/// Copies the pixels of `rect` out of an image of the given size, the parts
+/// of `rect` outside of it being transparent black.
+fn crop_pixels(data: &[u8], size: Size2D<u32>, rect: Rect<i32>) -> Vec<u8> {
+    let mut cropped = vec![0; rect.size.width as usize * rect.size.height as usize * 4];
+    let image_rect = Rect::new(Point2D::zero(), size.to_i32());
+    let visible = match image_rect.intersection(&rect) {
+        Some(visible) => visible,
+        None => return cropped,
+    };
+    let row_length = visible.size.width as usize * 4;
+    for y in visible.origin.y..visible.max_y() {
+        let src = ((y as usize * size.width as usize) + visible.origin.x as usize) * 4;
+        let dest = (((y - rect.origin.y) as usize * rect.size.width as usize) +
+                    (visible.origin.x - rect.origin.x) as usize) * 4;
+        cropped[dest..dest + row_length].copy_from_slice(&data[src..src + row_length]);
+    }
+    cropped
+}
+
+pub fn premultiply(data: &mut [u8]) {
+    for chunk in data.chunks_mut(4) {
+        let alpha = chunk[3];
+        chunk[0] = multiply_u8_pixel(chunk[0], alpha);
+        chunk[1] = multiply_u8_pixel(chunk[1], alpha);
+        chunk[2] = multiply_u8_pixel(chunk[2], alpha);
+    }
+}
+
+pub fn unpremultiply(data: &mut [u8]) {
+    for chunk in data.chunks_mut(4) {
+        let alpha = chunk[3] as usize;
+        chunk[0] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[0] as usize];
+        chunk[1] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[1] as usize];
+        chunk[2] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[2] as usize];
+    }
+}
+
+impl ImageBitmapMethods for ImageBitmap {
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-width
+    fn Width(&self) -> u32 {
+        if self.is_closed() { 0 } else { self.width }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-height
+    fn Height(&self) -> u32 {
+        if self.is_closed() { 0 } else { self.height }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-close
+    fn Close(&self) {
+        *self.bitmap_data.borrow_mut() = None;
+    }
+}
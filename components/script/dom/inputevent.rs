@@ -5,9 +5,11 @@
 use dom::bindings::codegen::Bindings::InputEventBinding::{self, InputEventMethods};
 use dom::bindings::codegen::Bindings::UIEventBinding::UIEventBinding::UIEventMethods;
 use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::reflect_dom_object;
 use dom::bindings::root::{DomRoot, RootedReference};
 use dom::bindings::str::DOMString;
+use dom::event::Event;
 use dom::uievent::UIEvent;
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -17,6 +19,7 @@ pub struct InputEvent {
     uievent: UIEvent,
     data: Option<DOMString>,
     is_composing: bool,
+    input_type: DOMString,
 }
 
 impl InputEvent {
@@ -27,11 +30,13 @@ impl InputEvent {
                view: Option<&Window>,
                detail: i32,
                data: Option<DOMString>,
-               is_composing: bool) -> DomRoot<InputEvent> {
+               is_composing: bool,
+               input_type: DOMString) -> DomRoot<InputEvent> {
         let ev = reflect_dom_object(Box::new(InputEvent {
                                         uievent: UIEvent::new_inherited(),
                                         data: data,
                                         is_composing: is_composing,
+                                        input_type: input_type,
                                     }),
                                     window,
                                     InputEventBinding::Wrap);
@@ -50,7 +55,9 @@ impl InputEvent {
                                     init.parent.view.r(),
                                     init.parent.detail,
                                     init.data.clone(),
-                                    init.isComposing);
+                                    init.isComposing,
+                                    init.inputType.clone());
+        event.upcast::<Event>().set_composed(init.parent.parent.composed);
         Ok(event)
     }
 }
@@ -66,6 +73,11 @@ impl InputEventMethods for InputEvent {
         self.is_composing
     }
 
+    // https://w3c.github.io/input-events/#dom-inputevent-inputtype
+    fn InputType(&self) -> DOMString {
+        self.input_type.clone()
+    }
+
     // https://dom.spec.whatwg.org/#dom-event-istrusted
     fn IsTrusted(&self) -> bool {
         self.uievent.IsTrusted()
@@ -0,0 +1,577 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use dom::bindings::callback::ExceptionHandling;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::IntersectionObserverBinding;
+use dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverCallback;
+use dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverInit;
+use dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::codegen::UnionTypes::{DoubleOrDoubleSequence, ElementOrDocument};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, RootedReference};
+use dom::bindings::str::DOMString;
+use dom::document::Document;
+use dom::domrectreadonly::DOMRectReadOnly;
+use dom::element::Element;
+use dom::intersectionobserverentry::IntersectionObserverEntry;
+use dom::node::Node;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::{Point2D, Rect, Size2D, Vector2D};
+use js::conversions::ToJSValConvertible;
+use js::jsapi::JSContext;
+use js::jsval::{JSVal, UndefinedValue};
+use std::cell::Cell;
+use std::cmp::{max, min};
+use std::rc::Rc;
+use style::str::split_html_space_chars;
+
+/// A length of the root margin, either in pixels or as a percentage of the
+/// size of the root intersection rectangle.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum RootMarginLength {
+    Pixels(f32),
+    Percentage(f32),
+}
+
+impl RootMarginLength {
+    fn parse(value: &str) -> Option<RootMarginLength> {
+        let length = if value == "0" {
+            Some(RootMarginLength::Pixels(0.))
+        } else if value.ends_with("px") {
+            value[..value.len() - 2].parse().ok().map(RootMarginLength::Pixels)
+        } else if value.ends_with('%') {
+            value[..value.len() - 1].parse().ok().map(RootMarginLength::Percentage)
+        } else {
+            None
+        };
+        length.filter(|length| match *length {
+            RootMarginLength::Pixels(value) |
+            RootMarginLength::Percentage(value) => value.is_finite(),
+        })
+    }
+
+    fn resolve(&self, basis: Au) -> Au {
+        match *self {
+            RootMarginLength::Pixels(value) => Au::from_f32_px(value),
+            RootMarginLength::Percentage(value) => basis.scale_by(value / 100.),
+        }
+    }
+
+    fn serialize(&self) -> String {
+        match *self {
+            RootMarginLength::Pixels(value) => format!("{}px", value),
+            RootMarginLength::Percentage(value) => format!("{}%", value),
+        }
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-rootmargin-slot>
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct RootMargin {
+    top: RootMarginLength,
+    right: RootMarginLength,
+    bottom: RootMarginLength,
+    left: RootMarginLength,
+}
+
+impl RootMargin {
+    /// <https://w3c.github.io/IntersectionObserver/#parse-a-root-margin>
+    pub fn parse(value: &str) -> Fallible<RootMargin> {
+        let lengths = split_html_space_chars(value)
+            .map(RootMarginLength::parse)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::Syntax)?;
+        let (top, right, bottom, left) = match lengths[..] {
+            [] => {
+                let zero = RootMarginLength::Pixels(0.);
+                (zero, zero, zero, zero)
+            },
+            [all] => (all, all, all, all),
+            [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+            [top, horizontal, bottom] => (top, horizontal, bottom, horizontal),
+            [top, right, bottom, left] => (top, right, bottom, left),
+            _ => return Err(Error::Syntax),
+        };
+        Ok(RootMargin { top, right, bottom, left })
+    }
+
+    /// Grow `rect` by this margin, resolving percentages against its size.
+    pub fn apply(&self, rect: Rect<Au>) -> Rect<Au> {
+        let top = self.top.resolve(rect.size.height);
+        let right = self.right.resolve(rect.size.width);
+        let bottom = self.bottom.resolve(rect.size.height);
+        let left = self.left.resolve(rect.size.width);
+        Rect::new(
+            Point2D::new(rect.origin.x - left, rect.origin.y - top),
+            Size2D::new(
+                max(Au(0), rect.size.width + left + right),
+                max(Au(0), rect.size.height + top + bottom),
+            ),
+        )
+    }
+
+    pub fn serialize(&self) -> String {
+        format!("{} {} {} {}",
+                self.top.serialize(),
+                self.right.serialize(),
+                self.bottom.serialize(),
+                self.left.serialize())
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#intersectionobserverregistration>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct IntersectionObserverRegistration {
+    target: Dom<Element>,
+    previous_threshold_index: Cell<i32>,
+    previous_is_intersecting: Cell<bool>,
+}
+
+// https://w3c.github.io/IntersectionObserver/#intersectionobserver
+#[dom_struct]
+pub struct IntersectionObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<IntersectionObserverCallback>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-root>
+    /// An element or a document, or `None` for the implicit root.
+    root: Option<Dom<Node>>,
+    root_margin: RootMargin,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-thresholds>
+    thresholds: Vec<f64>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-queuedentries-slot>
+    queued_entries: DomRefCell<Vec<Dom<IntersectionObserverEntry>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-observationtargets-slot>
+    observation_targets: DomRefCell<Vec<IntersectionObserverRegistration>>,
+}
+
+impl IntersectionObserver {
+    fn new_inherited(callback: Rc<IntersectionObserverCallback>,
+                     root: Option<&Node>,
+                     root_margin: RootMargin,
+                     thresholds: Vec<f64>)
+                     -> IntersectionObserver {
+        IntersectionObserver {
+            reflector_: Reflector::new(),
+            callback: callback,
+            root: root.map(Dom::from_ref),
+            root_margin: root_margin,
+            thresholds: thresholds,
+            queued_entries: DomRefCell::new(vec![]),
+            observation_targets: DomRefCell::new(vec![]),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn new(window: &Window,
+           callback: Rc<IntersectionObserverCallback>,
+           root: Option<&Node>,
+           root_margin: RootMargin,
+           thresholds: Vec<f64>)
+           -> DomRoot<IntersectionObserver> {
+        let observer = IntersectionObserver::new_inherited(callback, root, root_margin, thresholds);
+        reflect_dom_object(Box::new(observer), window, IntersectionObserverBinding::Wrap)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-intersectionobserver
+    pub fn Constructor(window: &Window,
+                       callback: Rc<IntersectionObserverCallback>,
+                       options: &IntersectionObserverInit)
+                       -> Fallible<DomRoot<IntersectionObserver>> {
+        // Step 1-2.
+        let root = options.root.as_ref().map(|root| match *root {
+            ElementOrDocument::Element(ref element) => DomRoot::from_ref(element.upcast::<Node>()),
+            ElementOrDocument::Document(ref document) => DomRoot::from_ref(document.upcast::<Node>()),
+        });
+
+        // Step 3-4.
+        let root_margin = RootMargin::parse(&options.rootMargin)?;
+
+        // Step 5-7.
+        let mut thresholds = match options.threshold {
+            None => vec![],
+            Some(DoubleOrDoubleSequence::Double(ref threshold)) => vec![**threshold],
+            Some(DoubleOrDoubleSequence::DoubleSequence(ref thresholds)) => {
+                thresholds.iter().map(|threshold| **threshold).collect()
+            },
+        };
+        if thresholds.iter().any(|threshold| *threshold < 0. || *threshold > 1.) {
+            return Err(Error::Range("Threshold values must be between 0 and 1".to_owned()));
+        }
+        thresholds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if thresholds.is_empty() {
+            thresholds.push(0.);
+        }
+
+        // Step 8-9.
+        let observer = IntersectionObserver::new(window,
+                                                 callback,
+                                                 root.r(),
+                                                 root_margin,
+                                                 thresholds);
+        window.Document().add_intersection_observer(&observer);
+        Ok(observer)
+    }
+
+    fn root_element(&self) -> Option<&Element> {
+        self.root.as_ref().and_then(|root| root.downcast::<Element>())
+    }
+
+    /// The document of the intersection root. For the implicit root, this is
+    /// the document of the top-level browsing context, or of the topmost
+    /// ancestor browsing context this script thread has access to.
+    fn intersection_root_document(&self) -> DomRoot<Document> {
+        if let Some(ref root) = self.root {
+            return root.owner_doc();
+        }
+        let mut document = self.global().as_window().Document();
+        loop {
+            let parent = document.window().undiscarded_window_proxy().and_then(|proxy| {
+                proxy.frame_element().map(|frame| frame.owner_doc())
+            });
+            match parent {
+                Some(parent) => document = parent,
+                None => return document,
+            }
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#intersectionobserver-root-intersection-rectangle>
+    ///
+    /// In the coordinate space of the layout of `root_document`.
+    fn root_intersection_rectangle(&self, root_document: &Document) -> Option<Rect<Au>> {
+        let rect = match self.root_element() {
+            Some(element) if element.has_overflow_clip() => padding_box(element)?,
+            Some(element) => element.upcast::<Node>().bounding_content_box()?,
+            None => viewport(root_document.window()),
+        };
+        Some(self.root_margin.apply(rect))
+    }
+
+    /// Whether `target` is in the containing block chain of the root, which
+    /// we approximate with the DOM ancestors of `target`.
+    fn is_targeted(&self, target: &Element, root_document: &Document) -> bool {
+        match self.root {
+            Some(ref root) if root.is::<Element>() => root.is_ancestor_of(target.upcast()),
+            Some(_) => &*target.owner_doc() == root_document,
+            None => true,
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#compute-the-intersection>
+    ///
+    /// Returns the intersection of `target_rect` with `root_bounds`, in the
+    /// coordinate space of `root_document`, along with the offset that maps
+    /// the coordinate space of the target's document to that one. Returns
+    /// `None` if they don't intersect.
+    fn compute_intersection(&self,
+                            target: &Element,
+                            target_rect: Rect<Au>,
+                            root_document: &Document,
+                            root_bounds: &Rect<Au>)
+                            -> Option<(Rect<Au>, Vector2D<Au>)> {
+        // Step 1.
+        let mut intersection_rect = target_rect;
+        let mut offset = Vector2D::zero();
+        let mut node = DomRoot::from_ref(target.upcast::<Node>());
+        loop {
+            // Step 2-3.
+            for container in node.ancestors().filter_map(DomRoot::downcast::<Element>) {
+                let is_root = self.root.as_ref().map_or(false, |root| **root == *container.upcast::<Node>());
+                if !is_root && !container.has_overflow_clip() {
+                    continue;
+                }
+                // Layout positions ignore scrolling, so move the content of
+                // the scroll container by its scroll offset.
+                let scroll_offset = scroll_offset(&container);
+                intersection_rect = intersection_rect.translate(&-scroll_offset);
+                offset = offset - scroll_offset;
+                if is_root {
+                    // Step 4-5.
+                    return edge_inclusive_intersection(&intersection_rect, root_bounds)
+                        .map(|rect| (rect, offset));
+                }
+                intersection_rect = edge_inclusive_intersection(&intersection_rect, &padding_box(&container)?)?;
+            }
+
+            let document = node.owner_doc();
+            if &*document == root_document {
+                // Step 4-5.
+                return edge_inclusive_intersection(&intersection_rect, root_bounds)
+                    .map(|rect| (rect, offset));
+            }
+
+            // Clip to the viewport of this nested browsing context, then move
+            // on to the coordinate space of the parent document.
+            let window = document.window();
+            let viewport = viewport(window);
+            intersection_rect = edge_inclusive_intersection(&intersection_rect, &viewport)?;
+            let frame = window.undiscarded_window_proxy()
+                .and_then(|proxy| proxy.frame_element().map(DomRoot::from_ref))?;
+            // FIXME: this should be the content box of the frame, but it
+            // rarely has any padding.
+            let frame_offset = padding_box(&frame)?.origin - viewport.origin;
+            intersection_rect = intersection_rect.translate(&frame_offset);
+            offset = offset + frame_offset;
+            node = DomRoot::upcast(frame);
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#update-intersection-observations-algo>
+    /// step 2, for this observer. Returns whether any entry was queued.
+    pub fn update_intersection_observations(&self, time: f64) -> bool {
+        let root_document = self.intersection_root_document();
+        let root_bounds = self.root_intersection_rectangle(&root_document);
+        let root_client_offset = match self.root_element() {
+            Some(element) => client_offset(element),
+            None => -viewport(root_document.window()).origin.to_vector(),
+        };
+        // We can only tell the bounds of the implicit root if we reached the
+        // top-level browsing context.
+        let exposed_root_bounds = match root_bounds {
+            Some(_) if self.root.is_none() && !root_document.window().is_top_level() => None,
+            Some(bounds) => Some(bounds.translate(&root_client_offset)),
+            None => None,
+        };
+
+        let mut queued = false;
+        for registration in self.observation_targets.borrow().iter() {
+            let target = &*registration.target;
+
+            // Step 2.2.1.
+            let mut threshold_index = 0;
+            let mut is_intersecting = false;
+            let mut target_rect = Rect::zero();
+            let mut intersection_rect = Rect::zero();
+            let mut intersection_ratio = 0.;
+
+            // Step 2.2.2-2.2.3.
+            let bounding_box = target.upcast::<Node>().bounding_content_box();
+            if let (Some(bounding_box), Some(root_bounds)) = (bounding_box, root_bounds) {
+                if self.is_targeted(target, &root_document) {
+                    // Step 2.2.4.
+                    let target_client_offset = client_offset(target);
+                    target_rect = bounding_box.translate(&target_client_offset);
+
+                    // Step 2.2.5.
+                    let intersection = self.compute_intersection(target,
+                                                                 bounding_box,
+                                                                 &root_document,
+                                                                 &root_bounds);
+                    if let Some((rect, offset)) = intersection {
+                        // Step 2.2.8.
+                        is_intersecting = true;
+                        intersection_rect = rect.translate(&(target_client_offset - offset));
+                    }
+
+                    // Step 2.2.6-2.2.7, 2.2.9.
+                    let target_area = area(&target_rect);
+                    intersection_ratio = if target_area > 0. {
+                        area(&intersection_rect) / target_area
+                    } else if is_intersecting {
+                        1.
+                    } else {
+                        0.
+                    };
+
+                    // Step 2.2.10.
+                    if is_intersecting {
+                        threshold_index = self.thresholds
+                            .iter()
+                            .position(|threshold| *threshold > intersection_ratio)
+                            .unwrap_or(self.thresholds.len()) as i32;
+                    }
+                }
+            }
+
+            // Step 2.2.11-2.2.12.
+            if threshold_index != registration.previous_threshold_index.get() ||
+               is_intersecting != registration.previous_is_intersecting.get() {
+                self.queue_entry(time,
+                                 exposed_root_bounds,
+                                 target_rect,
+                                 intersection_rect,
+                                 is_intersecting,
+                                 intersection_ratio,
+                                 target);
+                queued = true;
+            }
+
+            // Step 2.2.13-2.2.14.
+            registration.previous_threshold_index.set(threshold_index);
+            registration.previous_is_intersecting.set(is_intersecting);
+        }
+        queued
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#queue-an-intersectionobserverentry>
+    /// steps 1-2. The caller is responsible for queueing the notification task.
+    fn queue_entry(&self,
+                   time: f64,
+                   root_bounds: Option<Rect<Au>>,
+                   bounding_client_rect: Rect<Au>,
+                   intersection_rect: Rect<Au>,
+                   is_intersecting: bool,
+                   intersection_ratio: f64,
+                   target: &Element) {
+        let window = self.global();
+        let window = window.as_window();
+        let root_bounds = root_bounds.map(|rect| dom_rect(window, &rect));
+        let entry = IntersectionObserverEntry::new(window,
+                                                   time,
+                                                   root_bounds.r(),
+                                                   &dom_rect(window, &bounding_client_rect),
+                                                   &dom_rect(window, &intersection_rect),
+                                                   is_intersecting,
+                                                   intersection_ratio,
+                                                   target);
+        self.queued_entries.borrow_mut().push(Dom::from_ref(&*entry));
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#notify-intersection-observers-algo>
+    /// step 3, for this observer.
+    pub fn invoke_callback(&self) {
+        // Step 3.2-3.3.
+        let queue = self.TakeRecords();
+        // Step 3.1.
+        if queue.is_empty() {
+            return;
+        }
+        // Step 3.4.
+        let _ = self.callback.Call_(self, queue, self, ExceptionHandling::Report);
+    }
+}
+
+impl IntersectionObserverMethods for IntersectionObserver {
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-root
+    fn GetRoot(&self) -> Option<ElementOrDocument> {
+        self.root.as_ref().map(|root| match root.downcast::<Document>() {
+            Some(document) => ElementOrDocument::Document(DomRoot::from_ref(document)),
+            None => ElementOrDocument::Element(DomRoot::from_ref(root.downcast::<Element>().unwrap())),
+        })
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-rootmargin
+    fn RootMargin(&self) -> DOMString {
+        DOMString::from(self.root_margin.serialize())
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-thresholds
+    unsafe fn Thresholds(&self, cx: *mut JSContext) -> JSVal {
+        rooted!(in(cx) let mut thresholds = UndefinedValue());
+        self.thresholds.to_jsval(cx, thresholds.handle_mut());
+        thresholds.get()
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-observe
+    fn Observe(&self, target: &Element) {
+        // Step 1.
+        let mut targets = self.observation_targets.borrow_mut();
+        if targets.iter().any(|registration| &*registration.target == target) {
+            return;
+        }
+        // Step 2-4.
+        targets.push(IntersectionObserverRegistration {
+            target: Dom::from_ref(target),
+            previous_threshold_index: Cell::new(-1),
+            previous_is_intersecting: Cell::new(false),
+        });
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-unobserve
+    fn Unobserve(&self, target: &Element) {
+        self.observation_targets.borrow_mut().retain(|registration| &*registration.target != target);
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-disconnect
+    fn Disconnect(&self) {
+        self.observation_targets.borrow_mut().clear();
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-takerecords
+    fn TakeRecords(&self) -> Vec<DomRoot<IntersectionObserverEntry>> {
+        let records = self.queued_entries.borrow().iter().map(|entry| DomRoot::from_ref(&**entry)).collect();
+        self.queued_entries.borrow_mut().clear();
+        records
+    }
+}
+
+/// The intersection of two rectangles, which is empty rather than `None`
+/// when they are only edge-adjacent.
+fn edge_inclusive_intersection(a: &Rect<Au>, b: &Rect<Au>) -> Option<Rect<Au>> {
+    let min_x = max(a.min_x(), b.min_x());
+    let min_y = max(a.min_y(), b.min_y());
+    let max_x = min(a.max_x(), b.max_x());
+    let max_y = min(a.max_y(), b.max_y());
+    if max_x < min_x || max_y < min_y {
+        return None;
+    }
+    Some(Rect::new(Point2D::new(min_x, min_y), Size2D::new(max_x - min_x, max_y - min_y)))
+}
+
+fn area(rect: &Rect<Au>) -> f64 {
+    rect.size.width.to_f64_px() * rect.size.height.to_f64_px()
+}
+
+fn dom_rect(window: &Window, rect: &Rect<Au>) -> DomRoot<DOMRectReadOnly> {
+    DOMRectReadOnly::new(window.upcast(),
+                         rect.origin.x.to_f64_px(),
+                         rect.origin.y.to_f64_px(),
+                         rect.size.width.to_f64_px(),
+                         rect.size.height.to_f64_px())
+}
+
+/// The viewport of `window`, in the coordinate space of the layout of its
+/// document.
+fn viewport(window: &Window) -> Rect<Au> {
+    let viewport = window.current_viewport();
+    if !viewport.size.is_empty_or_negative() {
+        return viewport;
+    }
+    // We haven't been told about the viewport yet, use the initial one.
+    let size = window.window_size().map_or(Size2D::zero(), |size| {
+        Size2D::new(Au::from_f32_px(size.initial_viewport.width),
+                    Au::from_f32_px(size.initial_viewport.height))
+    });
+    Rect::new(viewport.origin, size)
+}
+
+/// The padding box of `element`, in the coordinate space of the layout of
+/// its document. This is the clip of its content if it has an overflow clip.
+fn padding_box(element: &Element) -> Option<Rect<Au>> {
+    let node = element.upcast::<Node>();
+    let border_box = node.bounding_content_box()?;
+    let client_rect = node.client_rect();
+    Some(Rect::new(
+        border_box.origin + Vector2D::new(Au::from_px(client_rect.origin.x), Au::from_px(client_rect.origin.y)),
+        Size2D::new(Au::from_px(client_rect.size.width), Au::from_px(client_rect.size.height)),
+    ))
+}
+
+fn scroll_offset(element: &Element) -> Vector2D<Au> {
+    let scroll_offset = element.upcast::<Node>().scroll_offset();
+    Vector2D::new(Au::from_f32_px(scroll_offset.x), Au::from_f32_px(scroll_offset.y))
+}
+
+/// The offset that maps the layout position of `element` to its position
+/// relative to the viewport, taking the scroll offsets of its ancestors into
+/// account.
+fn client_offset(element: &Element) -> Vector2D<Au> {
+    let node = element.upcast::<Node>();
+    let mut offset = -viewport(node.owner_doc().window()).origin.to_vector();
+    for ancestor in node.ancestors().filter_map(DomRoot::downcast::<Element>) {
+        if ancestor.has_overflow_clip() {
+            offset = offset - scroll_offset(&ancestor);
+        }
+    }
+    offset
+}
@@ -0,0 +1,128 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::DOMRectReadOnlyBinding::DOMRectInit;
+use dom::bindings::codegen::Bindings::IntersectionObserverEntryBinding;
+use dom::bindings::codegen::Bindings::IntersectionObserverEntryBinding::IntersectionObserverEntryInit;
+use dom::bindings::codegen::Bindings::IntersectionObserverEntryBinding::IntersectionObserverEntryMethods;
+use dom::bindings::codegen::Bindings::PerformanceBinding::DOMHighResTimeStamp;
+use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::domrectreadonly::DOMRectReadOnly;
+use dom::element::Element;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+// https://w3c.github.io/IntersectionObserver/#intersectionobserverentry
+#[dom_struct]
+pub struct IntersectionObserverEntry {
+    reflector_: Reflector,
+    time: f64,
+    root_bounds: Option<Dom<DOMRectReadOnly>>,
+    bounding_client_rect: Dom<DOMRectReadOnly>,
+    intersection_rect: Dom<DOMRectReadOnly>,
+    is_intersecting: bool,
+    intersection_ratio: f64,
+    target: Dom<Element>,
+}
+
+impl IntersectionObserverEntry {
+    fn new_inherited(time: f64,
+                     root_bounds: Option<&DOMRectReadOnly>,
+                     bounding_client_rect: &DOMRectReadOnly,
+                     intersection_rect: &DOMRectReadOnly,
+                     is_intersecting: bool,
+                     intersection_ratio: f64,
+                     target: &Element)
+                     -> IntersectionObserverEntry {
+        IntersectionObserverEntry {
+            reflector_: Reflector::new(),
+            time: time,
+            root_bounds: root_bounds.map(Dom::from_ref),
+            bounding_client_rect: Dom::from_ref(bounding_client_rect),
+            intersection_rect: Dom::from_ref(intersection_rect),
+            is_intersecting: is_intersecting,
+            intersection_ratio: intersection_ratio,
+            target: Dom::from_ref(target),
+        }
+    }
+
+    pub fn new(window: &Window,
+               time: f64,
+               root_bounds: Option<&DOMRectReadOnly>,
+               bounding_client_rect: &DOMRectReadOnly,
+               intersection_rect: &DOMRectReadOnly,
+               is_intersecting: bool,
+               intersection_ratio: f64,
+               target: &Element)
+               -> DomRoot<IntersectionObserverEntry> {
+        let entry = IntersectionObserverEntry::new_inherited(time,
+                                                             root_bounds,
+                                                             bounding_client_rect,
+                                                             intersection_rect,
+                                                             is_intersecting,
+                                                             intersection_ratio,
+                                                             target);
+        reflect_dom_object(Box::new(entry), window, IntersectionObserverEntryBinding::Wrap)
+    }
+
+    pub fn Constructor(window: &Window,
+                       init: &IntersectionObserverEntryInit)
+                       -> Fallible<DomRoot<IntersectionObserverEntry>> {
+        let rect_from_init = |init: &DOMRectInit| {
+            DOMRectReadOnly::new(window.upcast(), init.x, init.y, init.width, init.height)
+        };
+        let root_bounds = rect_from_init(&init.rootBounds);
+        let bounding_client_rect = rect_from_init(&init.boundingClientRect);
+        let intersection_rect = rect_from_init(&init.intersectionRect);
+        Ok(IntersectionObserverEntry::new(window,
+                                          *init.time,
+                                          Some(&root_bounds),
+                                          &bounding_client_rect,
+                                          &intersection_rect,
+                                          init.isIntersecting,
+                                          *init.intersectionRatio,
+                                          &init.target))
+    }
+}
+
+impl IntersectionObserverEntryMethods for IntersectionObserverEntry {
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-time
+    fn Time(&self) -> DOMHighResTimeStamp {
+        Finite::wrap(self.time)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-rootbounds
+    fn GetRootBounds(&self) -> Option<DomRoot<DOMRectReadOnly>> {
+        self.root_bounds.as_ref().map(|rect| DomRoot::from_ref(&**rect))
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-boundingclientrect
+    fn BoundingClientRect(&self) -> DomRoot<DOMRectReadOnly> {
+        DomRoot::from_ref(&*self.bounding_client_rect)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-intersectionrect
+    fn IntersectionRect(&self) -> DomRoot<DOMRectReadOnly> {
+        DomRoot::from_ref(&*self.intersection_rect)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-isintersecting
+    fn IsIntersecting(&self) -> bool {
+        self.is_intersecting
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-intersectionratio
+    fn IntersectionRatio(&self) -> Finite<f64> {
+        Finite::wrap(self.intersection_ratio)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserverentry-target
+    fn Target(&self) -> DomRoot<Element> {
+        DomRoot::from_ref(&*self.target)
+    }
+}
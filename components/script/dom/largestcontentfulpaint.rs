@@ -0,0 +1,57 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::LargestContentfulPaintBinding;
+use dom::bindings::codegen::Bindings::LargestContentfulPaintBinding::LargestContentfulPaintMethods;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::performanceentry::PerformanceEntry;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+#[dom_struct]
+pub struct LargestContentfulPaint {
+    entry: PerformanceEntry,
+    render_time: f64,
+    size: u32,
+}
+
+impl LargestContentfulPaint {
+    fn new_inherited(render_time: f64, size: u32) -> LargestContentfulPaint {
+        LargestContentfulPaint {
+            entry: PerformanceEntry::new_inherited(DOMString::from(""),
+                                                   DOMString::from("largest-contentful-paint"),
+                                                   render_time,
+                                                   0.),
+            render_time,
+            size,
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(window: &Window, render_time: f64, size: u32) -> DomRoot<LargestContentfulPaint> {
+        let entry = LargestContentfulPaint::new_inherited(render_time, size);
+        reflect_dom_object(Box::new(entry), window, LargestContentfulPaintBinding::Wrap)
+    }
+}
+
+impl LargestContentfulPaintMethods for LargestContentfulPaint {
+    // https://wicg.github.io/largest-contentful-paint/#dom-largestcontentfulpaint-rendertime
+    fn RenderTime(&self) -> Finite<f64> {
+        Finite::wrap(self.render_time)
+    }
+
+    // https://wicg.github.io/largest-contentful-paint/#dom-largestcontentfulpaint-loadtime
+    fn LoadTime(&self) -> Finite<f64> {
+        // TODO: report the time at which image candidates finished loading.
+        Finite::wrap(0.)
+    }
+
+    // https://wicg.github.io/largest-contentful-paint/#dom-largestcontentfulpaint-size
+    fn Size(&self) -> u32 {
+        self.size
+    }
+}
@@ -0,0 +1,55 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::LayoutShiftBinding;
+use dom::bindings::codegen::Bindings::LayoutShiftBinding::LayoutShiftMethods;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::performanceentry::PerformanceEntry;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+#[dom_struct]
+pub struct LayoutShift {
+    entry: PerformanceEntry,
+    value: f64,
+}
+
+impl LayoutShift {
+    fn new_inherited(start_time: f64, value: f64) -> LayoutShift {
+        LayoutShift {
+            entry: PerformanceEntry::new_inherited(DOMString::from(""),
+                                                   DOMString::from("layout-shift"),
+                                                   start_time,
+                                                   0.),
+            value,
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(window: &Window, start_time: f64, value: f64) -> DomRoot<LayoutShift> {
+        let entry = LayoutShift::new_inherited(start_time, value);
+        reflect_dom_object(Box::new(entry), window, LayoutShiftBinding::Wrap)
+    }
+}
+
+impl LayoutShiftMethods for LayoutShift {
+    // https://wicg.github.io/layout-instability/#dom-layoutshift-value
+    fn Value(&self) -> Finite<f64> {
+        Finite::wrap(self.value)
+    }
+
+    // https://wicg.github.io/layout-instability/#dom-layoutshift-hadrecentinput
+    fn HadRecentInput(&self) -> bool {
+        // TODO: layout shifts are not yet excluded after user input.
+        false
+    }
+
+    // https://wicg.github.io/layout-instability/#dom-layoutshift-lastinputtime
+    fn LastInputTime(&self) -> Finite<f64> {
+        Finite::wrap(0.)
+    }
+}
@@ -8,15 +8,17 @@ use dom::bindings::codegen::Bindings::MessageEventBinding::MessageEventMethods;
 use dom::bindings::error::Fallible;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::reflect_dom_object;
-use dom::bindings::root::DomRoot;
+use dom::bindings::root::{Dom, DomRoot};
 use dom::bindings::str::DOMString;
 use dom::bindings::trace::RootedTraceableBox;
 use dom::event::Event;
 use dom::eventtarget::EventTarget;
 use dom::globalscope::GlobalScope;
+use dom::messageport::MessagePort;
 use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
 use js::jsapi::{Heap, JSContext};
-use js::jsval::JSVal;
+use js::jsval::{JSVal, UndefinedValue};
 use js::rust::HandleValue;
 use servo_atoms::Atom;
 
@@ -26,6 +28,7 @@ pub struct MessageEvent {
     data: Heap<JSVal>,
     origin: DOMString,
     lastEventId: DOMString,
+    ports: Vec<Dom<MessagePort>>,
 }
 
 impl MessageEvent {
@@ -33,18 +36,21 @@ impl MessageEvent {
         MessageEvent::new_initialized(global,
                                       HandleValue::undefined(),
                                       DOMString::new(),
-                                      DOMString::new())
+                                      DOMString::new(),
+                                      vec![])
     }
 
     pub fn new_initialized(global: &GlobalScope,
                            data: HandleValue,
                            origin: DOMString,
-                           lastEventId: DOMString) -> DomRoot<MessageEvent> {
+                           lastEventId: DOMString,
+                           ports: Vec<DomRoot<MessagePort>>) -> DomRoot<MessageEvent> {
         let ev = Box::new(MessageEvent {
             event: Event::new_inherited(),
             data: Heap::default(),
             origin: origin,
             lastEventId: lastEventId,
+            ports: ports.iter().map(|port| Dom::from_ref(&**port)).collect(),
         });
         let ev = reflect_dom_object(ev, global, MessageEventBinding::Wrap);
         ev.data.set(data.get());
@@ -54,9 +60,10 @@ impl MessageEvent {
 
     pub fn new(global: &GlobalScope, type_: Atom,
                bubbles: bool, cancelable: bool,
-               data: HandleValue, origin: DOMString, lastEventId: DOMString)
+               data: HandleValue, origin: DOMString, lastEventId: DOMString,
+               ports: Vec<DomRoot<MessagePort>>)
                -> DomRoot<MessageEvent> {
-        let ev = MessageEvent::new_initialized(global, data, origin, lastEventId);
+        let ev = MessageEvent::new_initialized(global, data, origin, lastEventId, ports);
         {
             let event = ev.upcast::<Event>();
             event.init_event(type_, bubbles, cancelable);
@@ -74,7 +81,8 @@ impl MessageEvent {
                                    init.parent.cancelable,
                                    init.data.handle(),
                                    init.origin.clone(),
-                                   init.lastEventId.clone());
+                                   init.lastEventId.clone(),
+                                   init.ports.clone().unwrap_or_default());
         Ok(ev)
     }
 }
@@ -82,15 +90,18 @@ impl MessageEvent {
 impl MessageEvent {
     pub fn dispatch_jsval(target: &EventTarget,
                           scope: &GlobalScope,
-                          message: HandleValue) {
+                          message: HandleValue,
+                          origin: Option<&str>,
+                          ports: Vec<DomRoot<MessagePort>>) {
         let messageevent = MessageEvent::new(
             scope,
             atom!("message"),
             false,
             false,
             message,
+            DOMString::from(origin.unwrap_or("")),
             DOMString::new(),
-            DOMString::new());
+            ports);
         messageevent.upcast::<Event>().fire(target);
     }
 }
@@ -112,6 +123,16 @@ impl MessageEventMethods for MessageEvent {
         self.lastEventId.clone()
     }
 
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-messageevent-ports
+    unsafe fn Ports(&self, cx: *mut JSContext) -> JSVal {
+        let ports: Vec<DomRoot<MessagePort>> =
+            self.ports.iter().map(|port| DomRoot::from_ref(&**port)).collect();
+        rooted!(in(cx) let mut ports_value = UndefinedValue());
+        ports.to_jsval(cx, ports_value.handle_mut());
+        ports_value.get()
+    }
+
     // https://dom.spec.whatwg.org/#dom-event-istrusted
     fn IsTrusted(&self) -> bool {
         self.event.IsTrusted()
@@ -0,0 +1,237 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use dom::bindings::codegen::Bindings::MessagePortBinding::{self, MessagePortMethods, Transferable};
+use dom::bindings::error::{Error, ErrorResult};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::bindings::structuredclone::StructuredCloneData;
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::messageevent::MessageEvent;
+use dom_struct::dom_struct;
+use js::jsapi::{JSAutoCompartment, JSContext};
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use script_traits::{MessagePortId, PortMessageTask, ScriptMsg, ScriptToConstellationChan, TransferredPort};
+use std::cell::Cell;
+use std::rc::Rc;
+
+// https://html.spec.whatwg.org/multipage/#messageport
+#[dom_struct]
+pub struct MessagePort {
+    eventtarget: EventTarget,
+    message_port_id: MessagePortId,
+    /// The port this one is entangled with, which may live in another global.
+    entangled_port: Cell<Option<MessagePortId>>,
+    /// The entangled port when it lives in the same global. The two ports
+    /// hold each other, so that a channel script no longer references is
+    /// collected as a whole.
+    local_entangled_port: MutNullableDom<MessagePort>,
+    /// <https://html.spec.whatwg.org/multipage/#detached>
+    detached: Cell<bool>,
+    /// For forgetting the port once it is collected.
+    #[ignore_malloc_size_of = "channels are hard"]
+    script_to_constellation_chan: ScriptToConstellationChan,
+}
+
+impl MessagePort {
+    fn new_inherited(message_port_id: MessagePortId,
+                     entangled_port: Option<MessagePortId>,
+                     script_to_constellation_chan: ScriptToConstellationChan) -> MessagePort {
+        MessagePort {
+            eventtarget: EventTarget::new_inherited(),
+            message_port_id: message_port_id,
+            entangled_port: Cell::new(entangled_port),
+            local_entangled_port: MutNullableDom::new(None),
+            detached: Cell::new(false),
+            script_to_constellation_chan: script_to_constellation_chan,
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#create-a-new-messageport-object>
+    pub fn new(owner: &GlobalScope) -> DomRoot<MessagePort> {
+        let chan = owner.script_to_constellation_chan().clone();
+        let port = reflect_dom_object(Box::new(MessagePort::new_inherited(MessagePortId::new(), None, chan)),
+                                      owner,
+                                      MessagePortBinding::Wrap);
+        owner.track_message_port(&port, vec![]);
+        port
+    }
+
+    /// Create the port on the receiving side of a transfer, along with the
+    /// messages it had received but not dispatched in its previous global.
+    pub fn new_transferred(owner: &GlobalScope, transferred: TransferredPort) -> DomRoot<MessagePort> {
+        let TransferredPort { port_id, entangled_port, queued_messages } = transferred;
+        let chan = owner.script_to_constellation_chan().clone();
+        let port = reflect_dom_object(Box::new(MessagePort::new_inherited(port_id, entangled_port, chan)),
+                                      owner,
+                                      MessagePortBinding::Wrap);
+        owner.track_message_port(&port, queued_messages);
+        port
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#entangle>
+    pub fn entangle(&self, other: &MessagePort) {
+        self.entangled_port.set(Some(other.message_port_id));
+        other.entangled_port.set(Some(self.message_port_id));
+        self.local_entangled_port.set(Some(other));
+        other.local_entangled_port.set(Some(self));
+        let global = self.global();
+        global.update_message_port_keep_alive(&self.message_port_id);
+        global.update_message_port_keep_alive(&other.message_port_id);
+    }
+
+    /// Forget the entangled port, after it was closed.
+    pub fn disentangle(&self) {
+        self.entangled_port.set(None);
+        self.local_entangled_port.set(None);
+    }
+
+    pub fn message_port_id(&self) -> MessagePortId {
+        self.message_port_id
+    }
+
+    pub fn entangled_port(&self) -> Option<MessagePortId> {
+        self.entangled_port.get()
+    }
+
+    /// Whether this port is entangled with a port of another global, or with
+    /// a port being transferred.
+    pub fn entangled_with_other_global(&self) -> bool {
+        self.entangled_port.get().is_some() && self.local_entangled_port.get().is_none()
+    }
+
+    pub fn detached(&self) -> bool {
+        self.detached.get()
+    }
+
+    /// The transfer steps of a `MessagePort`: detach this port and take it
+    /// out of its global, so that another global can claim it.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#message-ports:transfer-steps>
+    pub fn transfer(&self) -> TransferredPort {
+        self.detached.set(true);
+        let global = self.global();
+        let queued_messages = global.ship_message_port(&self.message_port_id);
+        if let Some(other) = self.local_entangled_port.take() {
+            other.local_entangled_port.set(None);
+            global.update_message_port_keep_alive(&other.message_port_id);
+        }
+        TransferredPort {
+            port_id: self.message_port_id,
+            entangled_port: self.entangled_port.take(),
+            queued_messages: queued_messages,
+        }
+    }
+
+    /// Fire a `message` event for a message received by this port.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage> step 7.
+    #[allow(unsafe_code)]
+    pub fn dispatch_message(&self, task: PortMessageTask) {
+        let PortMessageTask { data, ports } = task;
+        let global = self.global();
+        let cx = global.get_cx();
+        let _ac = JSAutoCompartment::new(cx, self.reflector().get_jsobject().get());
+        rooted!(in(cx) let mut message_clone = UndefinedValue());
+        let new_ports = StructuredCloneData::Vector(data)
+            .read_with_ports(&global, ports, message_clone.handle_mut());
+        MessageEvent::dispatch_jsval(self.upcast(), &global, message_clone.handle(), None, new_ports);
+    }
+}
+
+impl MessagePortMethods for MessagePort {
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage
+    unsafe fn PostMessage(&self,
+                          cx: *mut JSContext,
+                          message: HandleValue,
+                          transfer: Option<Vec<Transferable>>)
+                          -> ErrorResult {
+        let transfer = transfer.unwrap_or_default();
+
+        // Step 1.
+        let target_port = self.entangled_port.get();
+
+        let transferred_ports: Vec<_> = transfer.iter().filter_map(|transferable| match *transferable {
+            Transferable::MessagePort(ref port) => Some(port.message_port_id),
+            Transferable::ImageBitmap(_) => None,
+        }).collect();
+
+        // Step 2.
+        if transferred_ports.contains(&self.message_port_id) {
+            return Err(Error::DataClone);
+        }
+
+        // Step 3-4.
+        let doomed = target_port.map_or(false, |target| transferred_ports.contains(&target));
+
+        // Step 5.
+        let (data, ports) = StructuredCloneData::write_with_transfer(cx, message, &transfer)?;
+
+        // Step 6.
+        let target_port = match target_port {
+            Some(target_port) if !doomed => target_port,
+            _ => return Ok(()),
+        };
+
+        // Step 7.
+        let task = PortMessageTask {
+            data: data.move_to_arraybuffer(),
+            ports: ports,
+        };
+        self.global().post_messageport_msg(target_port, task);
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-start
+    fn Start(&self) {
+        if self.detached.get() {
+            return;
+        }
+        self.global().start_message_port(&self.message_port_id);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-close
+    fn Close(&self) {
+        if self.detached.get() {
+            return;
+        }
+        // Step 1.
+        self.detached.set(true);
+        // Step 2.
+        let entangled_port = self.entangled_port.take();
+        self.local_entangled_port.set(None);
+        self.global().close_message_port(&self.message_port_id, entangled_port);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessage
+    fn GetOnmessage(&self) -> Option<Rc<EventHandlerNonNull>> {
+        self.upcast::<EventTarget>().get_event_handler_common("message")
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessage
+    fn SetOnmessage(&self, listener: Option<Rc<EventHandlerNonNull>>) {
+        self.upcast::<EventTarget>().set_event_handler_common("message", listener);
+        // Setting onmessage implicitly enables the port message queue.
+        self.Start();
+    }
+}
+
+impl Drop for MessagePort {
+    fn drop(&mut self) {
+        // A port collected before being closed or transferred is gone for
+        // good: forget it, and disentangle the port it was entangled with.
+        if self.detached.get() {
+            return;
+        }
+        let _ = self.script_to_constellation_chan.send(ScriptMsg::RemoveMessagePort(self.message_port_id));
+        if let Some(entangled_port) = self.entangled_port.get() {
+            let _ = self.script_to_constellation_chan.send(ScriptMsg::DisentangleMessagePort(entangled_port));
+        }
+    }
+}
@@ -212,12 +212,15 @@ pub mod types {
     include!(concat!(env!("OUT_DIR"), "/build/InterfaceTypes.rs"));
 }
 
+pub mod abortcontroller;
+pub mod abortsignal;
 pub mod abstractworker;
 pub mod abstractworkerglobalscope;
 pub mod activation;
 pub mod attr;
 pub mod bindings;
 pub mod blob;
+pub mod broadcastchannel;
 pub mod canvasgradient;
 pub mod canvaspattern;
 pub mod canvasrenderingcontext2d;
@@ -226,6 +229,8 @@ pub mod closeevent;
 pub mod comment;
 pub mod compositionevent;
 mod create;
+pub mod crypto;
+pub mod cryptokey;
 pub mod css;
 pub mod cssconditionrule;
 pub mod cssfontfacerule;
@@ -263,7 +268,9 @@ pub mod domrect;
 pub mod domrectreadonly;
 pub mod domstringmap;
 pub mod domtokenlist;
+pub mod editing;
 pub mod element;
+pub mod elementinternals;
 pub mod errorevent;
 pub mod event;
 pub mod eventtarget;
@@ -327,6 +334,7 @@ pub mod htmlprogresselement;
 pub mod htmlquoteelement;
 pub mod htmlscriptelement;
 pub mod htmlselectelement;
+pub mod htmlslotelement;
 pub mod htmlsourceelement;
 pub mod htmlspanelement;
 pub mod htmlstyleelement;
@@ -346,15 +354,23 @@ pub mod htmltrackelement;
 pub mod htmlulistelement;
 pub mod htmlunknownelement;
 pub mod htmlvideoelement;
+pub mod idledeadline;
+pub mod imagebitmap;
 pub mod imagedata;
 pub mod inputevent;
+pub mod intersectionobserver;
+pub mod intersectionobserverentry;
 pub mod keyboardevent;
+pub mod largestcontentfulpaint;
+pub mod layoutshift;
 pub mod location;
 pub mod mediaerror;
 pub mod medialist;
 pub mod mediaquerylist;
 pub mod mediaquerylistevent;
+pub mod messagechannel;
 pub mod messageevent;
+pub mod messageport;
 pub mod mimetype;
 pub mod mimetypearray;
 pub mod mouseevent;
@@ -364,17 +380,23 @@ pub mod namednodemap;
 pub mod node;
 pub mod nodeiterator;
 pub mod nodelist;
+pub mod offscreencanvas;
+pub mod offscreencanvasrenderingcontext2d;
 pub mod pagetransitionevent;
 pub mod paintrenderingcontext2d;
 pub mod paintsize;
 pub mod paintworkletglobalscope;
+pub mod path2d;
 pub mod performance;
 pub mod performanceentry;
+pub mod performancelongtasktiming;
 pub mod performancemark;
 pub mod performancemeasure;
+pub mod performancenavigationtiming;
 pub mod performanceobserver;
 pub mod performanceobserverentrylist;
 pub mod performancepainttiming;
+pub mod performanceresourcetiming;
 pub mod performancetiming;
 pub mod popstateevent;
 pub mod processinginstruction;
@@ -382,17 +404,25 @@ pub mod promise;
 pub mod promisenativehandler;
 pub mod radionodelist;
 pub mod range;
+pub mod raredata;
+pub mod resizeobserver;
+pub mod resizeobserverentry;
+pub mod resizeobserversize;
 pub mod screen;
+pub mod selection;
 pub mod serviceworkerglobalscope;
 pub mod servoparser;
+pub mod shadowroot;
 pub mod storage;
 pub mod storageevent;
 pub mod stylepropertymapreadonly;
 pub mod stylesheet;
 pub mod stylesheetlist;
+pub mod subtlecrypto;
 pub mod svgelement;
 pub mod svggraphicselement;
 pub mod svgsvgelement;
+pub mod taskattributiontiming;
 pub mod testbinding;
 pub mod testbindingiterable;
 pub mod testbindingpairiterable;
@@ -403,6 +433,7 @@ pub mod text;
 pub mod textcontrol;
 pub mod textdecoder;
 pub mod textencoder;
+pub mod textmetrics;
 pub mod touch;
 pub mod touchevent;
 pub mod touchlist;
@@ -417,6 +448,20 @@ pub mod validation;
 pub mod validitystate;
 pub mod values;
 pub mod virtualmethods;
+pub mod webgl_extensions;
+pub mod webgl_validations;
+pub mod webglactiveinfo;
+pub mod webglbuffer;
+pub mod webglcontextevent;
+pub mod webglframebuffer;
+pub mod webglobject;
+pub mod webglprogram;
+pub mod webglrenderbuffer;
+pub mod webglrenderingcontext;
+pub mod webglshader;
+pub mod webglshaderprecisionformat;
+pub mod webgltexture;
+pub mod webgluniformlocation;
 pub mod window;
 pub mod windowproxy;
 pub mod worker;
@@ -425,3 +470,7 @@ pub mod workerlocation;
 pub mod worklet;
 pub mod workletglobalscope;
 pub mod xmldocument;
+pub mod xmlserializer;
+pub mod xpathevaluator;
+pub mod xpathexpression;
+pub mod xpathresult;
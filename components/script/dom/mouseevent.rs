@@ -108,6 +108,7 @@ impl MouseEvent {
             init.parent.altKey, init.parent.shiftKey, init.parent.metaKey,
             init.button, init.relatedTarget.r(), None
         );
+        event.upcast::<Event>().set_composed(init.parent.parent.parent.composed);
         Ok(event)
     }
 
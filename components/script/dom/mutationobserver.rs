@@ -9,9 +9,12 @@ use dom::bindings::codegen::Bindings::MutationObserverBinding::MutationCallback;
 use dom::bindings::codegen::Bindings::MutationObserverBinding::MutationObserverBinding::MutationObserverMethods;
 use dom::bindings::codegen::Bindings::MutationObserverBinding::MutationObserverInit;
 use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::{Reflector, reflect_dom_object, DomObject};
 use dom::bindings::root::DomRoot;
 use dom::bindings::str::DOMString;
+use dom::editing;
+use dom::eventtarget::EventTarget;
 use dom::mutationrecord::MutationRecord;
 use dom::node::Node;
 use dom::window::Window;
@@ -94,7 +97,8 @@ impl MutationObserver {
         ScriptThread::set_mutation_observer_compound_microtask_queued(false);
         // Step 2
         let notify_list = ScriptThread::get_mutation_observers();
-        // TODO: steps 3-4 (slots)
+        // Steps 3-4
+        let signal_list = ScriptThread::take_signal_slots();
         // Step 5
         for mo in &notify_list {
             let queue: Vec<DomRoot<MutationRecord>> = mo.record_queue.borrow().clone();
@@ -104,11 +108,15 @@ impl MutationObserver {
                 let _ = mo.callback.Call_(&**mo, queue, &**mo, ExceptionHandling::Report);
             }
         }
-        // TODO: Step 6 (slot signals)
+        // Step 6
+        for slot in &signal_list {
+            slot.upcast::<EventTarget>().fire_bubbling_event(atom!("slotchange"));
+        }
     }
 
     /// <https://dom.spec.whatwg.org/#queueing-a-mutation-record>
     pub fn queue_a_mutation_record(target: &Node, attr_type: Mutation) {
+        editing::record_mutation(target, &attr_type);
         if !target.global().as_window().get_exists_mut_observer() {
             return;
         }
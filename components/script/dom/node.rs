@@ -12,15 +12,15 @@ use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
 use dom::bindings::codegen::Bindings::HTMLCollectionBinding::HTMLCollectionMethods;
-use dom::bindings::codegen::Bindings::NodeBinding::{NodeConstants, NodeMethods};
+use dom::bindings::codegen::Bindings::NodeBinding::{GetRootNodeOptions, NodeConstants, NodeMethods};
 use dom::bindings::codegen::Bindings::NodeListBinding::NodeListMethods;
 use dom::bindings::codegen::Bindings::ProcessingInstructionBinding::ProcessingInstructionMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::UnionTypes::NodeOrString;
 use dom::bindings::conversions::{self, DerivedFrom};
 use dom::bindings::error::{Error, ErrorResult, Fallible};
-use dom::bindings::inheritance::{Castable, CharacterDataTypeId, ElementTypeId};
-use dom::bindings::inheritance::{EventTargetTypeId, HTMLElementTypeId, NodeTypeId};
+use dom::bindings::inheritance::{Castable, CharacterDataTypeId, DocumentFragmentTypeId};
+use dom::bindings::inheritance::{ElementTypeId, EventTargetTypeId, HTMLElementTypeId, NodeTypeId};
 use dom::bindings::inheritance::{SVGElementTypeId, SVGGraphicsElementTypeId};
 use dom::bindings::reflector::{DomObject, reflect_dom_object};
 use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom, RootedReference};
@@ -30,6 +30,7 @@ use dom::characterdata::{CharacterData, LayoutCharacterDataHelpers};
 use dom::cssstylesheet::CSSStyleSheet;
 use dom::customelementregistry::{CallbackReaction, try_upgrade_element};
 use dom::document::{Document, DocumentSource, HasBrowsingContext, IsHTMLDocument};
+use dom::document::StyleSheetListOwner;
 use dom::documentfragment::DocumentFragment;
 use dom::documenttype::DocumentType;
 use dom::element::{CustomElementCreationMode, Element, ElementCreator};
@@ -44,14 +45,17 @@ use dom::htmlimageelement::{HTMLImageElement, LayoutHTMLImageElementHelpers};
 use dom::htmlinputelement::{HTMLInputElement, LayoutHTMLInputElementHelpers};
 use dom::htmllinkelement::HTMLLinkElement;
 use dom::htmlmetaelement::HTMLMetaElement;
+use dom::htmlslotelement::HTMLSlotElement;
 use dom::htmlstyleelement::HTMLStyleElement;
 use dom::htmltextareaelement::{HTMLTextAreaElement, LayoutHTMLTextAreaElementHelpers};
 use dom::mutationobserver::{Mutation, MutationObserver, RegisteredObserver};
 use dom::nodelist::NodeList;
 use dom::processinginstruction::ProcessingInstruction;
 use dom::range::WeakRangeVec;
+use dom::raredata::RareData;
+use dom::shadowroot::ShadowRoot;
 use dom::svgsvgelement::{SVGSVGElement, LayoutSVGSVGElementHelpers};
-use dom::text::Text;
+use dom::text::{LayoutTextHelpers, Text};
 use dom::virtualmethods::{VirtualMethods, vtable_for};
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -142,6 +146,9 @@ pub struct Node {
     /// Registered observers for this node.
     mutation_observers: DomRefCell<Vec<RegisteredObserver>>,
 
+    /// The fields that few nodes use.
+    rare_data: RareData,
+
     unique_id: UniqueId,
 }
 
@@ -149,7 +156,8 @@ bitflags! {
     #[doc = "Flags for node items."]
     #[derive(JSTraceable, MallocSizeOf)]
     pub struct NodeFlags: u16 {
-        #[doc = "Specifies whether this node is in a document."]
+        #[doc = "Specifies whether this node is in a document tree, that is, its root \
+                 is a document."]
         const IS_IN_DOC = 1 << 0;
 
         #[doc = "Specifies whether this node needs style recalc on next reflow."]
@@ -164,7 +172,11 @@ bitflags! {
                  to be reachable with using sequential focus navigation."]
         const SEQUENTIALLY_FOCUSABLE = 1 << 3;
 
-        // There are two free bits here.
+        #[doc = "Specifies whether this node's root is a shadow root."]
+        const IS_IN_SHADOW_TREE = 1 << 4;
+
+        #[doc = "Specifies whether this node's shadow-including root is a document."]
+        const IS_CONNECTED = 1 << 5;
 
         #[doc = "Specifies whether the parser has set an associated form owner for \
                  this element. Only applicable for form-associatable elements."]
@@ -257,8 +269,12 @@ impl Node {
         self.children_count.set(self.children_count.get() + 1);
 
         let parent_in_doc = self.is_in_doc();
+        let parent_in_shadow_tree = self.is_in_shadow_tree();
+        let parent_is_connected = self.is_connected();
         for node in new_child.traverse_preorder() {
             node.set_flag(NodeFlags::IS_IN_DOC, parent_in_doc);
+            node.set_flag(NodeFlags::IS_IN_SHADOW_TREE, parent_in_shadow_tree);
+            node.set_flag(NodeFlags::IS_CONNECTED, parent_is_connected);
             // Out-of-document elements never have the descendants flag set.
             debug_assert!(!node.get_flag(NodeFlags::HAS_DIRTY_DESCENDANTS));
             vtable_for(&&*node).bind_to_tree(parent_in_doc);
@@ -298,7 +314,8 @@ impl Node {
 
         for node in child.traverse_preorder() {
             // Out-of-document elements never have the descendants flag set.
-            node.set_flag(NodeFlags::IS_IN_DOC | NodeFlags::HAS_DIRTY_DESCENDANTS |
+            node.set_flag(NodeFlags::IS_IN_DOC | NodeFlags::IS_IN_SHADOW_TREE |
+                          NodeFlags::IS_CONNECTED | NodeFlags::HAS_DIRTY_DESCENDANTS |
                           NodeFlags::HAS_SNAPSHOT | NodeFlags::HANDLED_SNAPSHOT,
                           false);
         }
@@ -371,6 +388,9 @@ impl<'a> Iterator for QuerySelectorIterator {
 impl Node {
     pub fn teardown(&self) {
         self.style_and_layout_data.get().map(|d| self.dispose(d));
+        if let Some(shadow_root) = self.downcast::<Element>().and_then(|e| e.shadow_root()) {
+            shadow_root.upcast::<Node>().teardown();
+        }
         for kid in self.children() {
             kid.teardown();
         }
@@ -429,6 +449,111 @@ impl Node {
         self.flags.get().contains(NodeFlags::IS_IN_DOC)
     }
 
+    /// <https://dom.spec.whatwg.org/#connected>
+    pub fn is_connected(&self) -> bool {
+        self.flags.get().contains(NodeFlags::IS_CONNECTED)
+    }
+
+    pub fn is_in_shadow_tree(&self) -> bool {
+        self.flags.get().contains(NodeFlags::IS_IN_SHADOW_TREE)
+    }
+
+    /// Returns the shadow root this node is in, if its root is one.
+    pub fn containing_shadow_root(&self) -> Option<DomRoot<ShadowRoot>> {
+        if !self.is_in_shadow_tree() {
+            return None;
+        }
+        self.inclusive_ancestors().last().and_then(DomRoot::downcast::<ShadowRoot>)
+    }
+
+    /// Returns where the stylesheets owned by elements in this node's tree
+    /// go: its shadow root if it is in a shadow tree, its document otherwise.
+    pub fn stylesheet_list_owner(&self) -> StyleSheetListOwner {
+        match self.containing_shadow_root() {
+            Some(shadow_root) => StyleSheetListOwner::ShadowRoot(shadow_root),
+            None => StyleSheetListOwner::Document(self.owner_doc()),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-root>
+    pub fn root_node(&self) -> DomRoot<Node> {
+        self.inclusive_ancestors().last().unwrap()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-shadow-including-root>
+    pub fn shadow_including_root(&self) -> DomRoot<Node> {
+        let root = self.root_node();
+        match root.downcast::<ShadowRoot>() {
+            Some(shadow_root) => shadow_root.host().upcast::<Node>().shadow_including_root(),
+            None => root,
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-shadow-including-inclusive-ancestor>
+    pub fn is_shadow_including_inclusive_ancestor_of(&self, node: &Node) -> bool {
+        let mut current = DomRoot::from_ref(node);
+        loop {
+            if current.inclusive_ancestors().any(|ancestor| &*ancestor == self) {
+                return true;
+            }
+            let root = current.root_node();
+            current = match root.downcast::<ShadowRoot>() {
+                Some(shadow_root) => DomRoot::upcast(shadow_root.host()),
+                None => return false,
+            };
+        }
+    }
+
+    /// Returns this node and all its shadow-including descendants, in
+    /// shadow-including tree order.
+    ///
+    /// <https://dom.spec.whatwg.org/#concept-shadow-including-tree-order>
+    pub fn shadow_including_inclusive_descendants(&self) -> Vec<DomRoot<Node>> {
+        let mut nodes = vec![];
+        for node in self.traverse_preorder() {
+            let shadow_root = node.downcast::<Element>().and_then(|e| e.shadow_root());
+            nodes.push(node);
+            if let Some(shadow_root) = shadow_root {
+                nodes.extend(shadow_root.upcast::<Node>().shadow_including_inclusive_descendants());
+            }
+        }
+        nodes
+    }
+
+    /// <https://dom.spec.whatwg.org/#slotable-assigned-slot>
+    pub fn assigned_slot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        self.rare_data.get().and_then(|data| data.assigned_slot.get())
+    }
+
+    pub fn set_assigned_slot(&self, slot: Option<&HTMLSlotElement>) {
+        match self.rare_data.get() {
+            Some(data) => data.assigned_slot.set(slot),
+            None if slot.is_some() => self.rare_data.ensure().assigned_slot.set(slot),
+            None => {},
+        }
+    }
+
+    /// The fields that few nodes use.
+    pub fn rare_data(&self) -> &RareData {
+        &self.rare_data
+    }
+
+    /// Returns the parent of this node in the flat tree, that is, its
+    /// assigned slot if it has one, the shadow host if this is a child of a
+    /// shadow root, or its parent otherwise.
+    ///
+    /// <https://drafts.csswg.org/css-scoping/#flat-tree>
+    pub fn flat_tree_parent(&self) -> Option<DomRoot<Node>> {
+        if let Some(slot) = self.assigned_slot() {
+            return Some(DomRoot::upcast(slot));
+        }
+        let parent = self.GetParentNode()?;
+        match parent.downcast::<ShadowRoot>() {
+            Some(shadow_root) => Some(DomRoot::upcast(shadow_root.host())),
+            None => Some(parent),
+        }
+    }
+
     /// Returns the type ID of this node.
     pub fn type_id(&self) -> NodeTypeId {
         match *self.eventtarget.type_id() {
@@ -489,14 +614,16 @@ impl Node {
 
     // FIXME(emilio): This and the function below should move to Element.
     pub fn note_dirty_descendants(&self) {
-        debug_assert!(self.is_in_doc());
+        debug_assert!(self.is_connected());
 
-        for ancestor in self.inclusive_ancestors() {
+        let mut current = Some(DomRoot::from_ref(self));
+        while let Some(ancestor) = current {
             if ancestor.get_flag(NodeFlags::HAS_DIRTY_DESCENDANTS) {
                 return;
             }
 
             ancestor.set_flag(NodeFlags::HAS_DIRTY_DESCENDANTS, true);
+            current = ancestor.flat_tree_parent();
         }
     }
 
@@ -519,15 +646,19 @@ impl Node {
 
     pub fn dirty(&self, damage: NodeDamage) {
         self.rev_version();
-        if !self.is_in_doc() {
+        if !self.is_connected() {
             return;
         }
 
         match self.type_id() {
-            NodeTypeId::CharacterData(CharacterDataTypeId::Text) =>
-                self.parent_node.get().unwrap().downcast::<Element>().unwrap().restyle(damage),
+            NodeTypeId::CharacterData(CharacterDataTypeId::Text) => {
+                let parent = self.flat_tree_parent().unwrap();
+                parent.downcast::<Element>().unwrap().restyle(damage)
+            },
             NodeTypeId::Element(_) =>
                 self.downcast::<Element>().unwrap().restyle(damage),
+            NodeTypeId::DocumentFragment(DocumentFragmentTypeId::ShadowRoot) =>
+                self.downcast::<ShadowRoot>().unwrap().host().restyle(damage),
             _ => {},
         };
     }
@@ -1014,6 +1145,7 @@ pub trait LayoutNodeHelpers {
     unsafe fn last_child_ref(&self) -> Option<LayoutDom<Node>>;
     unsafe fn prev_sibling_ref(&self) -> Option<LayoutDom<Node>>;
     unsafe fn next_sibling_ref(&self) -> Option<LayoutDom<Node>>;
+    unsafe fn assigned_slot_ref(&self) -> Option<LayoutDom<HTMLSlotElement>>;
 
     unsafe fn owner_doc_for_layout(&self) -> LayoutDom<Document>;
 
@@ -1080,6 +1212,12 @@ impl LayoutNodeHelpers for LayoutDom<Node> {
         (*self.unsafe_get()).next_sibling.get_inner_as_layout()
     }
 
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn assigned_slot_ref(&self) -> Option<LayoutDom<HTMLSlotElement>> {
+        (*self.unsafe_get()).rare_data.get().and_then(|data| data.assigned_slot.get_inner_as_layout())
+    }
+
     #[inline]
     #[allow(unsafe_code)]
     unsafe fn owner_doc_for_layout(&self) -> LayoutDom<Document> {
@@ -1153,6 +1291,10 @@ impl LayoutNodeHelpers for LayoutDom<Node> {
 
     #[allow(unsafe_code)]
     fn selection(&self) -> Option<Range<usize>> {
+        if let Some(text) = self.downcast::<Text>() {
+            return unsafe { text.selection_for_layout() };
+        }
+
         if let Some(area) = self.downcast::<HTMLTextAreaElement>() {
             return unsafe { area.selection_for_layout() };
         }
@@ -1388,7 +1530,7 @@ impl Node {
 
     #[allow(unrooted_must_root)]
     pub fn new_document_node() -> Node {
-        Node::new_(NodeFlags::new() | NodeFlags::IS_IN_DOC, None)
+        Node::new_(NodeFlags::new() | NodeFlags::IS_IN_DOC | NodeFlags::IS_CONNECTED, None)
     }
 
     #[allow(unrooted_must_root)]
@@ -1412,6 +1554,8 @@ impl Node {
 
             mutation_observers: Default::default(),
 
+            rare_data: RareData::new(),
+
             unique_id: UniqueId::new(),
         }
     }
@@ -1447,7 +1591,7 @@ impl Node {
         // Step 1.
         match parent.type_id() {
             NodeTypeId::Document(_) |
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(..) => (),
             _ => return Err(Error::HierarchyRequest)
         }
@@ -1476,7 +1620,7 @@ impl Node {
                     return Err(Error::HierarchyRequest);
                 }
             },
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(_) |
             NodeTypeId::CharacterData(CharacterDataTypeId::ProcessingInstruction) |
             NodeTypeId::CharacterData(CharacterDataTypeId::Comment) => (),
@@ -1487,7 +1631,7 @@ impl Node {
         if parent.is::<Document>() {
             match node.type_id() {
                 // Step 6.1
-                NodeTypeId::DocumentFragment => {
+                NodeTypeId::DocumentFragment(_) => {
                     // Step 6.1.1(b)
                     if node.children()
                            .any(|c| c.is::<Text>())
@@ -1604,7 +1748,7 @@ impl Node {
             }
         }
         rooted_vec!(let mut new_nodes);
-        let new_nodes = if let NodeTypeId::DocumentFragment = node.type_id() {
+        let new_nodes = if let NodeTypeId::DocumentFragment(_) = node.type_id() {
             // Step 3.
             new_nodes.extend(node.children().map(|kid| Dom::from_ref(&*kid)));
             // Step 4.
@@ -1641,8 +1785,28 @@ impl Node {
         for kid in new_nodes {
             // Step 7.1.
             parent.add_child(*kid, child);
+            // Step 7.2.
+            let parent_is_shadow_host =
+                parent.downcast::<Element>().map_or(false, |e| e.shadow_root().is_some());
+            if parent_is_shadow_host && HTMLSlotElement::is_slottable(kid) {
+                HTMLSlotElement::assign_a_slot(kid);
+            }
+            // Step 7.3.
+            if parent.is_in_shadow_tree() {
+                if let Some(slot) = parent.downcast::<HTMLSlotElement>() {
+                    if slot.assigned_nodes().is_empty() {
+                        slot.signal_a_slot_change();
+                    }
+                }
+            }
+            // Step 7.4. Only slots in the inserted subtree can change the
+            // assignments of its root.
+            if kid.is_in_shadow_tree() && kid.traverse_preorder().any(|n| n.is::<HTMLSlotElement>()) {
+                HTMLSlotElement::assign_slottables_for_a_tree(&kid.root_node());
+            }
             // Step 7.7.
-            for descendant in kid.traverse_preorder().filter_map(DomRoot::downcast::<Element>) {
+            let descendants = kid.shadow_including_inclusive_descendants();
+            for descendant in descendants.into_iter().filter_map(DomRoot::downcast::<Element>) {
                 // Step 7.7.2.
                 if descendant.is_connected() {
                     if descendant.get_custom_element_definition().is_some() {
@@ -1680,7 +1844,7 @@ impl Node {
         // Step 3.
         rooted_vec!(let mut added_nodes);
         let added_nodes = if let Some(node) = node.as_ref() {
-            if let NodeTypeId::DocumentFragment = node.type_id() {
+            if let NodeTypeId::DocumentFragment(_) = node.type_id() {
                 added_nodes.extend(node.children().map(|child| Dom::from_ref(&*child)));
                 added_nodes.r()
             } else {
@@ -1752,7 +1916,26 @@ impl Node {
         // Step 8.
         let old_next_sibling = node.GetNextSibling();
         // Steps 9-10 are handled in unbind_from_tree.
+        let parent_in_shadow_tree = parent.is_in_shadow_tree();
         parent.remove_child(node, cached_index);
+        // The slot steps, which come right after removing node from its
+        // parent in the current spec.
+        if let Some(slot) = node.assigned_slot() {
+            slot.assign_slottables();
+        }
+        if parent_in_shadow_tree {
+            if let Some(slot) = parent.downcast::<HTMLSlotElement>() {
+                if slot.assigned_nodes().is_empty() {
+                    slot.signal_a_slot_change();
+                }
+            }
+        }
+        if node.traverse_preorder().any(|n| n.is::<HTMLSlotElement>()) {
+            if parent_in_shadow_tree {
+                HTMLSlotElement::assign_slottables_for_a_tree(&parent.root_node());
+            }
+            HTMLSlotElement::assign_slottables_for_a_tree(node);
+        }
         // Step 11. transient registered observers
         // Step 12.
         if let SuppressObserver::Unsuppressed = suppress_observers {
@@ -1792,7 +1975,7 @@ impl Node {
                                                 &document);
                 DomRoot::upcast::<Node>(doctype)
             },
-            NodeTypeId::DocumentFragment => {
+            NodeTypeId::DocumentFragment(_) => {
                 let doc_fragment = DocumentFragment::new(&document);
                 DomRoot::upcast::<Node>(doc_fragment)
             },
@@ -1915,7 +2098,7 @@ impl Node {
                     .GetDocumentElement().as_ref()
                     .map_or(ns!(), |elem| elem.locate_namespace(prefix))
             },
-            NodeTypeId::DocumentType | NodeTypeId::DocumentFragment => ns!(),
+            NodeTypeId::DocumentType | NodeTypeId::DocumentFragment(_) => ns!(),
             _ => {
                 node.GetParentElement().as_ref()
                     .map_or(ns!(), |elem| elem.locate_namespace(prefix))
@@ -1938,7 +2121,7 @@ impl NodeMethods for Node {
                 NodeConstants::DOCUMENT_NODE,
             NodeTypeId::DocumentType =>
                 NodeConstants::DOCUMENT_TYPE_NODE,
-            NodeTypeId::DocumentFragment =>
+            NodeTypeId::DocumentFragment(_) =>
                 NodeConstants::DOCUMENT_FRAGMENT_NODE,
             NodeTypeId::Element(_) =>
                 NodeConstants::ELEMENT_NODE,
@@ -1959,7 +2142,7 @@ impl NodeMethods for Node {
             NodeTypeId::DocumentType => {
                 self.downcast::<DocumentType>().unwrap().name().clone()
             },
-            NodeTypeId::DocumentFragment => DOMString::from("#document-fragment"),
+            NodeTypeId::DocumentFragment(_) => DOMString::from("#document-fragment"),
             NodeTypeId::Document(_) => DOMString::from("#document")
         }
     }
@@ -1975,14 +2158,23 @@ impl NodeMethods for Node {
             NodeTypeId::CharacterData(..) |
             NodeTypeId::Element(..) |
             NodeTypeId::DocumentType |
-            NodeTypeId::DocumentFragment => Some(self.owner_doc()),
+            NodeTypeId::DocumentFragment(_) => Some(self.owner_doc()),
             NodeTypeId::Document(_) => None
         }
     }
 
     // https://dom.spec.whatwg.org/#dom-node-getrootnode
-    fn GetRootNode(&self) -> DomRoot<Node> {
-        self.inclusive_ancestors().last().unwrap()
+    fn GetRootNode(&self, options: &GetRootNodeOptions) -> DomRoot<Node> {
+        if options.composed {
+            self.shadow_including_root()
+        } else {
+            self.inclusive_ancestors().last().unwrap()
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#dom-node-isconnected
+    fn IsConnected(&self) -> bool {
+        self.is_connected()
     }
 
     // https://dom.spec.whatwg.org/#dom-node-parentnode
@@ -2044,7 +2236,7 @@ impl NodeMethods for Node {
     // https://dom.spec.whatwg.org/#dom-node-textcontent
     fn GetTextContent(&self) -> Option<DOMString> {
         match self.type_id() {
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(..) => {
                 let content = Node::collect_text_contents(self.traverse_preorder());
                 Some(content)
@@ -2064,7 +2256,7 @@ impl NodeMethods for Node {
     fn SetTextContent(&self, value: Option<DOMString>) {
         let value = value.unwrap_or_default();
         match self.type_id() {
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(..) => {
                 // Step 1-2.
                 let node = if value.is_empty() {
@@ -2100,7 +2292,7 @@ impl NodeMethods for Node {
         // Step 1.
         match self.type_id() {
             NodeTypeId::Document(_) |
-            NodeTypeId::DocumentFragment |
+            NodeTypeId::DocumentFragment(_) |
             NodeTypeId::Element(..) => (),
             _ => return Err(Error::HierarchyRequest)
         }
@@ -2128,7 +2320,7 @@ impl NodeMethods for Node {
         if self.is::<Document>() {
             match node.type_id() {
                 // Step 6.1
-                NodeTypeId::DocumentFragment => {
+                NodeTypeId::DocumentFragment(_) => {
                     // Step 6.1.1(b)
                     if node.children()
                            .any(|c| c.is::<Text>())
@@ -2209,7 +2401,7 @@ impl NodeMethods for Node {
 
         // Step 12.
         rooted_vec!(let mut nodes);
-        let nodes = if node.type_id() == NodeTypeId::DocumentFragment {
+        let nodes = if node.is::<DocumentFragment>() {
             nodes.extend(node.children().map(|node| Dom::from_ref(&*node)));
             nodes.r()
         } else {
@@ -2270,12 +2462,18 @@ impl NodeMethods for Node {
     }
 
     // https://dom.spec.whatwg.org/#dom-node-clonenode
-    fn CloneNode(&self, deep: bool) -> DomRoot<Node> {
-        Node::clone(self, None, if deep {
+    fn CloneNode(&self, deep: bool) -> Fallible<DomRoot<Node>> {
+        // Step 1.
+        if self.is::<ShadowRoot>() {
+            return Err(Error::NotSupported);
+        }
+
+        // Step 2.
+        Ok(Node::clone(self, None, if deep {
             CloneChildrenFlag::CloneChildren
         } else {
             CloneChildrenFlag::DoNotCloneChildren
-        })
+        }))
     }
 
     // https://dom.spec.whatwg.org/#dom-node-isequalnode
@@ -2463,7 +2661,7 @@ impl NodeMethods for Node {
                     element.lookup_prefix(namespace)
                 })
             },
-            NodeTypeId::DocumentType | NodeTypeId::DocumentFragment => None,
+            NodeTypeId::DocumentType | NodeTypeId::DocumentFragment(_) => None,
             _ => {
                 self.GetParentElement().and_then(|element| {
                     element.lookup_prefix(namespace)
@@ -2665,6 +2863,9 @@ pub struct UnbindContext<'a> {
     prev_sibling: Option<&'a Node>,
     /// Whether the tree is in a document.
     pub tree_in_doc: bool,
+    /// Whether the tree is connected, that is, its shadow-including root is
+    /// a document.
+    pub tree_connected: bool,
 }
 
 impl<'a> UnbindContext<'a> {
@@ -2677,6 +2878,7 @@ impl<'a> UnbindContext<'a> {
             parent: parent,
             prev_sibling: prev_sibling,
             tree_in_doc: parent.is_in_doc(),
+            tree_connected: parent.is_connected(),
         }
     }
 
@@ -0,0 +1,251 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::{CanvasMsg, CanvasId, FromScriptMsg};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::OffscreenCanvasBinding::{self, ImageEncodeOptions};
+use dom::bindings::codegen::Bindings::OffscreenCanvasBinding::{OffscreenCanvasMethods, OffscreenRenderingContext};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::bindings::str::DOMString;
+use dom::blob::{Blob, BlobImpl};
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::imagebitmap::ImageBitmap;
+use dom::offscreencanvasrenderingcontext2d::OffscreenCanvasRenderingContext2D;
+use dom::promise::Promise;
+use dom_struct::dom_struct;
+use euclid::Size2D;
+use image_encoder::ImageType;
+use ipc_channel::ipc::IpcSender;
+use js::jsapi::JSContext;
+use js::rust::HandleValue;
+use profile_traits::ipc;
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[must_root]
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+pub enum OffscreenCanvasContext {
+    OffscreenContext2d(Dom<OffscreenCanvasRenderingContext2D>),
+}
+
+// https://html.spec.whatwg.org/multipage/#offscreencanvas
+#[dom_struct]
+pub struct OffscreenCanvas {
+    eventtarget: EventTarget,
+    width: Cell<u64>,
+    height: Cell<u64>,
+    context: DomRefCell<Option<OffscreenCanvasContext>>,
+    /// The canvas element this was transferred from, which displays what is
+    /// drawn on it.
+    placeholder: Option<Dom<HTMLCanvasElement>>,
+}
+
+impl OffscreenCanvas {
+    fn new_inherited(width: u64, height: u64, placeholder: Option<&HTMLCanvasElement>) -> OffscreenCanvas {
+        OffscreenCanvas {
+            eventtarget: EventTarget::new_inherited(),
+            width: Cell::new(width),
+            height: Cell::new(height),
+            context: DomRefCell::new(None),
+            placeholder: placeholder.map(Dom::from_ref),
+        }
+    }
+
+    pub fn new(global: &GlobalScope,
+               width: u64,
+               height: u64,
+               placeholder: Option<&HTMLCanvasElement>)
+               -> DomRoot<OffscreenCanvas> {
+        reflect_dom_object(Box::new(OffscreenCanvas::new_inherited(width, height, placeholder)),
+                           global,
+                           OffscreenCanvasBinding::Wrap)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas
+    pub fn Constructor(global: &GlobalScope, width: u64, height: u64) -> Fallible<DomRoot<OffscreenCanvas>> {
+        Ok(OffscreenCanvas::new(global, width, height, None))
+    }
+
+    pub fn get_size(&self) -> Size2D<i32> {
+        Size2D::new(self.width.get() as i32, self.height.get() as i32)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.width.get() != 0 && self.height.get() != 0
+    }
+
+    pub fn origin_is_clean(&self) -> bool {
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::OffscreenContext2d(ref context)) => context.origin_is_clean(),
+            None => true,
+        }
+    }
+
+    pub fn context(&self) -> Option<DomRoot<OffscreenCanvasRenderingContext2D>> {
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::OffscreenContext2d(ref context)) => Some(DomRoot::from_ref(&**context)),
+            None => None,
+        }
+    }
+
+    pub fn get_or_init_2d_context(&self) -> DomRoot<OffscreenCanvasRenderingContext2D> {
+        if let Some(context) = self.context() {
+            return context;
+        }
+        let context = OffscreenCanvasRenderingContext2D::new(&self.global(),
+                                                             self,
+                                                             self.get_size(),
+                                                             self.placeholder.as_ref().map(|p| &**p));
+        *self.context.borrow_mut() = Some(OffscreenCanvasContext::OffscreenContext2d(Dom::from_ref(&*context)));
+        context
+    }
+
+    /// Returns the bitmap as premultiplied BGRA pixels, or `None` if it is
+    /// empty. A canvas without a context is transparent black.
+    pub fn fetch_all_data(&self) -> Option<(Vec<u8>, Size2D<i32>)> {
+        let size = self.get_size();
+        if size.width == 0 || size.height == 0 {
+            return None;
+        }
+
+        let data = match self.context() {
+            Some(context) => {
+                let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
+                let msg = CanvasMsg::FromScript(FromScriptMsg::SendPixels(sender), context.get_canvas_id());
+                context.get_ipc_renderer().send(msg).unwrap();
+                receiver.recv().unwrap()?.into()
+            },
+            None => vec![0; size.width as usize * size.height as usize * 4],
+        };
+        Some((data, size))
+    }
+
+    fn set_dimensions(&self, width: u64, height: u64) {
+        self.width.set(width);
+        self.height.set(height);
+        if let Some(context) = self.context() {
+            context.set_bitmap_dimensions(self.get_size());
+        }
+        if let Some(ref placeholder) = self.placeholder {
+            placeholder.offscreen_canvas_resized();
+        }
+    }
+}
+
+pub trait LayoutOffscreenCanvasHelpers {
+    fn get_size(&self) -> Size2D<u32>;
+    fn get_canvas_renderer_for_layout(&self) -> Option<(IpcSender<CanvasMsg>, CanvasId)>;
+}
+
+impl LayoutOffscreenCanvasHelpers for LayoutDom<OffscreenCanvas> {
+    #[allow(unsafe_code)]
+    fn get_size(&self) -> Size2D<u32> {
+        unsafe {
+            let canvas = &*self.unsafe_get();
+            Size2D::new(canvas.width.get() as u32, canvas.height.get() as u32)
+        }
+    }
+
+    #[allow(unsafe_code)]
+    fn get_canvas_renderer_for_layout(&self) -> Option<(IpcSender<CanvasMsg>, CanvasId)> {
+        unsafe {
+            let canvas = &*self.unsafe_get();
+            match *canvas.context.borrow_for_layout() {
+                Some(OffscreenCanvasContext::OffscreenContext2d(ref context)) => {
+                    let context = &*context.to_layout().unsafe_get();
+                    Some((context.get_ipc_renderer(), context.get_canvas_id()))
+                },
+                None => None,
+            }
+        }
+    }
+}
+
+impl OffscreenCanvasMethods for OffscreenCanvas {
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-width
+    fn Width(&self) -> u64 {
+        self.width.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-width
+    fn SetWidth(&self, value: u64) {
+        self.set_dimensions(value, self.height.get())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-height
+    fn Height(&self) -> u64 {
+        self.height.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-height
+    fn SetHeight(&self, value: u64) {
+        self.set_dimensions(self.width.get(), value)
+    }
+
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-getcontext
+    unsafe fn GetContext(&self,
+                         _cx: *mut JSContext,
+                         id: DOMString,
+                         _options: HandleValue)
+                         -> Option<OffscreenRenderingContext> {
+        match &*id {
+            "2d" => Some(self.get_or_init_2d_context()),
+            _ => None,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-transfertoimagebitmap
+    fn TransferToImageBitmap(&self) -> Fallible<DomRoot<ImageBitmap>> {
+        // Step 2.
+        let context = match self.context() {
+            Some(context) => context,
+            None => return Err(Error::InvalidState),
+        };
+
+        // Step 3.
+        let (data, size) = match self.fetch_all_data() {
+            Some(result) => result,
+            None => (vec![], Size2D::zero()),
+        };
+        let bitmap = ImageBitmap::new(&self.global(), size.to_u32(), data, true, context.origin_is_clean());
+
+        // Step 4.
+        context.clear_bitmap();
+
+        // Step 5.
+        Ok(bitmap)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-converttoblob
+    fn ConvertToBlob(&self, options: &ImageEncodeOptions) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+
+        // Step 2.
+        if !self.origin_is_clean() {
+            promise.reject_error(Error::Security);
+            return promise;
+        }
+
+        // Step 3.
+        if !self.is_valid() {
+            promise.reject_error(Error::IndexSize);
+            return promise;
+        }
+
+        // Step 4-6.
+        let size = self.get_size().to_u32();
+        let data = self.fetch_all_data().map(|(data, _)| data).unwrap_or_default();
+        let image_type = ImageType::new(&options.type_, size);
+        let encoded = image_type.encode(data, size, options.quality);
+        let blob = Blob::new(&self.global(), BlobImpl::new_from_bytes(encoded), image_type.mime_type().to_owned());
+        promise.resolve_native(&blob);
+        promise
+    }
+}
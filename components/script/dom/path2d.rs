@@ -0,0 +1,688 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::PathSegment;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::DOMMatrixBinding::DOMMatrixInit;
+use dom::bindings::codegen::Bindings::Path2DBinding::{self, Path2DMethods};
+use dom::bindings::codegen::UnionTypes::Path2DOrString;
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::dommatrixreadonly::dommatrixinit_to_matrix;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use euclid::{Point2D, Transform2D};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+// https://html.spec.whatwg.org/multipage/#path2d-objects
+#[dom_struct]
+pub struct Path2D {
+    reflector_: Reflector,
+    path: DomRefCell<PathData>,
+}
+
+impl Path2D {
+    fn new_inherited(path: PathData) -> Path2D {
+        Path2D {
+            reflector_: Reflector::new(),
+            path: DomRefCell::new(path),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, path: PathData) -> DomRoot<Path2D> {
+        reflect_dom_object(Box::new(Path2D::new_inherited(path)),
+                           global,
+                           Path2DBinding::Wrap)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-path2d
+    pub fn Constructor(global: &GlobalScope, path: Option<Path2DOrString>) -> Fallible<DomRoot<Path2D>> {
+        let path = match path {
+            Some(Path2DOrString::Path2D(path)) => path.path.borrow().clone(),
+            Some(Path2DOrString::String(data)) => PathData::parse_svg_path_data(&data),
+            None => PathData::default(),
+        };
+        Ok(Path2D::new(global, path))
+    }
+
+    /// The segments to hand to the canvas thread when this path is drawn.
+    pub fn segments(&self) -> Vec<PathSegment> {
+        self.path.borrow().segments.clone()
+    }
+}
+
+impl Path2DMethods for Path2D {
+    // https://html.spec.whatwg.org/multipage/#dom-path2d-addpath
+    fn AddPath(&self, path: &Path2D, transform: &DOMMatrixInit) -> ErrorResult {
+        let (_, matrix) = dommatrixinit_to_matrix(transform)?;
+        let transform = Transform2D::row_major(matrix.m11, matrix.m12,
+                                               matrix.m21, matrix.m22,
+                                               matrix.m41, matrix.m42);
+        if ![transform.m11, transform.m12, transform.m21, transform.m22, transform.m31, transform.m32]
+            .iter().all(|value| value.is_finite()) {
+            return Ok(());
+        }
+
+        // Clone first, since `path` may be this very object.
+        let other = path.path.borrow().clone();
+        self.path.borrow_mut().add_path(&other, &transform.cast());
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-closepath
+    fn ClosePath(&self) {
+        self.path.borrow_mut().close_path()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-moveto
+    fn MoveTo(&self, x: f64, y: f64) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.path.borrow_mut().move_to(Point2D::new(x, y))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-lineto
+    fn LineTo(&self, x: f64, y: f64) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.path.borrow_mut().line_to(Point2D::new(x, y))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-quadraticcurveto
+    fn QuadraticCurveTo(&self, cpx: f64, cpy: f64, x: f64, y: f64) {
+        if !([cpx, cpy, x, y].iter().all(|value| value.is_finite())) {
+            return;
+        }
+        self.path.borrow_mut().quadratic_curve_to(Point2D::new(cpx, cpy), Point2D::new(x, y))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-beziercurveto
+    fn BezierCurveTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        if !([cp1x, cp1y, cp2x, cp2y, x, y].iter().all(|value| value.is_finite())) {
+            return;
+        }
+        self.path.borrow_mut().bezier_curve_to(Point2D::new(cp1x, cp1y),
+                                               Point2D::new(cp2x, cp2y),
+                                               Point2D::new(x, y))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arcto
+    fn ArcTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, r: f64) -> ErrorResult {
+        if !([cp1x, cp1y, cp2x, cp2y, r].iter().all(|value| value.is_finite())) {
+            return Ok(());
+        }
+        if r < 0.0 {
+            return Err(Error::IndexSize);
+        }
+        self.path.borrow_mut().arc_to(Point2D::new(cp1x, cp1y), Point2D::new(cp2x, cp2y), r);
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-rect
+    fn Rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        if !([x, y, width, height].iter().all(|value| value.is_finite())) {
+            return;
+        }
+        self.path.borrow_mut().rect(x, y, width, height)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arc
+    fn Arc(&self, x: f64, y: f64, r: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        if !([x, y, r, start, end].iter().all(|value| value.is_finite())) {
+            return Ok(());
+        }
+        if r < 0.0 {
+            return Err(Error::IndexSize);
+        }
+        self.path.borrow_mut().ellipse(Point2D::new(x, y), r, r, 0.0, start, end, ccw);
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ellipse
+    fn Ellipse(&self, x: f64, y: f64, rx: f64, ry: f64, rotation: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        if !([x, y, rx, ry, rotation, start, end].iter().all(|value| value.is_finite())) {
+            return Ok(());
+        }
+        if rx < 0.0 || ry < 0.0 {
+            return Err(Error::IndexSize);
+        }
+        self.path.borrow_mut().ellipse(Point2D::new(x, y), rx, ry, rotation, start, end, ccw);
+        Ok(())
+    }
+}
+
+/// The list of subpaths of a `Path2D`, along with the bookkeeping needed to
+/// keep appending to it.
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+pub struct PathData {
+    segments: Vec<PathSegment>,
+    /// The last point of the last subpath, if there is a subpath.
+    current_point: Option<Point2D<f32>>,
+    /// The first point of the last subpath, which closePath() returns to.
+    subpath_start: Point2D<f32>,
+}
+
+impl Default for PathData {
+    fn default() -> PathData {
+        PathData {
+            segments: vec![],
+            current_point: None,
+            subpath_start: Point2D::zero(),
+        }
+    }
+}
+
+impl PathData {
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    fn current_point(&self) -> Option<Point2D<f64>> {
+        self.current_point.map(|point| point.cast::<f64>())
+    }
+
+    fn push(&mut self, segment: PathSegment, end: Point2D<f64>) {
+        self.segments.push(segment);
+        self.current_point = Some(end.cast::<f32>());
+    }
+
+    // https://html.spec.whatwg.org/multipage/#ensure-there-is-a-subpath
+    fn ensure_subpath(&mut self, point: Point2D<f64>) {
+        if self.current_point.is_none() {
+            self.move_to(point);
+        }
+    }
+
+    pub fn move_to(&mut self, point: Point2D<f64>) {
+        self.push(PathSegment::MoveTo(point.cast::<f32>()), point);
+        self.subpath_start = point.cast::<f32>();
+    }
+
+    pub fn line_to(&mut self, point: Point2D<f64>) {
+        if self.current_point.is_none() {
+            return self.move_to(point);
+        }
+        self.push(PathSegment::LineTo(point.cast::<f32>()), point);
+    }
+
+    pub fn quadratic_curve_to(&mut self, cp: Point2D<f64>, point: Point2D<f64>) {
+        self.ensure_subpath(cp);
+        self.push(PathSegment::QuadraticCurveTo(cp.cast::<f32>(), point.cast::<f32>()), point);
+    }
+
+    pub fn bezier_curve_to(&mut self, cp1: Point2D<f64>, cp2: Point2D<f64>, point: Point2D<f64>) {
+        self.ensure_subpath(cp1);
+        self.push(PathSegment::BezierCurveTo(cp1.cast::<f32>(), cp2.cast::<f32>(), point.cast::<f32>()), point);
+    }
+
+    pub fn close_path(&mut self) {
+        if self.current_point.is_none() {
+            return;
+        }
+        self.segments.push(PathSegment::ClosePath);
+        self.current_point = Some(self.subpath_start);
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.move_to(Point2D::new(x, y));
+        self.line_to(Point2D::new(x + width, y));
+        self.line_to(Point2D::new(x + width, y + height));
+        self.line_to(Point2D::new(x, y + height));
+        self.close_path();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arcto
+    pub fn arc_to(&mut self, cp1: Point2D<f64>, cp2: Point2D<f64>, radius: f64) {
+        self.ensure_subpath(cp1);
+        let cp0 = self.current_point().unwrap();
+
+        if cp0 == cp1 || cp1 == cp2 || radius == 0.0 {
+            return self.line_to(cp1);
+        }
+
+        // If all three control points lie on a single straight line, connect
+        // the first two by a straight line.
+        let direction = (cp2.x - cp1.x) * (cp0.y - cp1.y) + (cp2.y - cp1.y) * (cp1.x - cp0.x);
+        if direction == 0.0 {
+            return self.line_to(cp1);
+        }
+
+        let a2 = (cp0.x - cp1.x).powi(2) + (cp0.y - cp1.y).powi(2);
+        let b2 = (cp1.x - cp2.x).powi(2) + (cp1.y - cp2.y).powi(2);
+        let d = {
+            let c2 = (cp0.x - cp2.x).powi(2) + (cp0.y - cp2.y).powi(2);
+            let cosx = (a2 + b2 - c2) / (2.0 * (a2 * b2).sqrt());
+            let sinx = (1.0 - cosx.powi(2)).sqrt();
+            radius / ((1.0 - cosx) / sinx)
+        };
+
+        // The two tangent points.
+        let anx = (cp1.x - cp0.x) / a2.sqrt();
+        let any = (cp1.y - cp0.y) / a2.sqrt();
+        let tp1 = Point2D::new(cp1.x - anx * d, cp1.y - any * d);
+        let bnx = (cp1.x - cp2.x) / b2.sqrt();
+        let bny = (cp1.y - cp2.y) / b2.sqrt();
+        let tp2 = Point2D::new(cp1.x - bnx * d, cp1.y - bny * d);
+
+        let anticlockwise = direction < 0.0;
+        let sign = if anticlockwise { 1.0 } else { -1.0 };
+        let center = Point2D::new(tp1.x + any * radius * sign, tp1.y - anx * radius * sign);
+        let start = (tp1.y - center.y).atan2(tp1.x - center.x);
+        let end = (tp2.y - center.y).atan2(tp2.x - center.x);
+
+        self.line_to(tp1);
+        if [center.x, center.y, start, end].iter().all(|value| value.is_finite()) {
+            self.ellipse(center, radius, radius, 0.0, start, end, anticlockwise);
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ellipse
+    pub fn ellipse(
+        &mut self,
+        center: Point2D<f64>,
+        radius_x: f64,
+        radius_y: f64,
+        rotation: f64,
+        start: f64,
+        end: f64,
+        anticlockwise: bool,
+    ) {
+        let two_pi = 2.0 * PI;
+        let sweep = if !anticlockwise {
+            if end - start >= two_pi {
+                two_pi
+            } else {
+                let sweep = (end - start) % two_pi;
+                if sweep < 0.0 { sweep + two_pi } else { sweep }
+            }
+        } else {
+            if start - end >= two_pi {
+                -two_pi
+            } else {
+                let sweep = (end - start) % two_pi;
+                if sweep > 0.0 { sweep - two_pi } else { sweep }
+            }
+        };
+
+        let ellipse = EllipseArc { center, radius_x, radius_y, rotation };
+        self.line_to(ellipse.point_at(start));
+        self.append_arc(&ellipse, start, sweep);
+    }
+
+    /// Appends an arc of `ellipse` from the current point, which must already
+    /// be at the `start` angle, approximated by one cubic Bézier curve per
+    /// quarter turn.
+    fn append_arc(&mut self, ellipse: &EllipseArc, start: f64, sweep: f64) {
+        if sweep == 0.0 {
+            return;
+        }
+
+        let count = (sweep.abs() / FRAC_PI_2).ceil();
+        let step = sweep / count;
+        let k = 4.0 / 3.0 * (step / 4.0).tan();
+        let mut angle = start;
+        for _ in 0..count as u32 {
+            let next = angle + step;
+            let (sin_start, cos_start) = angle.sin_cos();
+            let (sin_end, cos_end) = next.sin_cos();
+            let cp1 = ellipse.map(cos_start - k * sin_start, sin_start + k * cos_start);
+            let cp2 = ellipse.map(cos_end + k * sin_end, sin_end - k * cos_end);
+            self.bezier_curve_to(cp1, cp2, ellipse.point_at(next));
+            angle = next;
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-path2d-addpath
+    pub fn add_path(&mut self, other: &PathData, transform: &Transform2D<f32>) {
+        if other.segments.is_empty() {
+            return;
+        }
+        self.segments.extend(other.segments.iter().map(|segment| segment.transform(transform)));
+        self.current_point = other.current_point.map(|point| transform.transform_point(&point));
+        self.subpath_start = transform.transform_point(&other.subpath_start);
+    }
+
+    /// Builds a path from SVG path data. As in SVG, everything up to the first
+    /// error in the data is kept.
+    /// <https://www.w3.org/TR/SVG11/paths.html#PathData>
+    pub fn parse_svg_path_data(data: &str) -> PathData {
+        let mut path = PathData::default();
+        SvgPathParser::new(data, &mut path).parse();
+        path
+    }
+}
+
+/// A possibly rotated ellipse, as taken by `ellipse()`.
+struct EllipseArc {
+    center: Point2D<f64>,
+    radius_x: f64,
+    radius_y: f64,
+    rotation: f64,
+}
+
+impl EllipseArc {
+    /// Maps a point of the unit circle onto the ellipse.
+    fn map(&self, x: f64, y: f64) -> Point2D<f64> {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, y) = (x * self.radius_x, y * self.radius_y);
+        Point2D::new(self.center.x + x * cos - y * sin, self.center.y + x * sin + y * cos)
+    }
+
+    fn point_at(&self, angle: f64) -> Point2D<f64> {
+        let (sin, cos) = angle.sin_cos();
+        self.map(cos, sin)
+    }
+}
+
+/// The last control point of the previous command, which the smooth curve
+/// commands reflect.
+enum LastControlPoint {
+    None,
+    Cubic(Point2D<f64>),
+    Quadratic(Point2D<f64>),
+}
+
+struct SvgPathParser<'a, 'b> {
+    input: &'a [u8],
+    position: usize,
+    path: &'b mut PathData,
+    last_control_point: LastControlPoint,
+}
+
+impl<'a, 'b> SvgPathParser<'a, 'b> {
+    fn new(input: &'a str, path: &'b mut PathData) -> SvgPathParser<'a, 'b> {
+        SvgPathParser {
+            input: input.as_bytes(),
+            position: 0,
+            path: path,
+            last_control_point: LastControlPoint::None,
+        }
+    }
+
+    fn parse(&mut self) {
+        self.skip_whitespace();
+        // Path data has to start with a moveto.
+        match self.peek() {
+            Some(b'M') | Some(b'm') => {},
+            _ => return,
+        }
+
+        while let Some(command) = self.peek() {
+            self.position += 1;
+            if self.parse_command(command).is_none() {
+                return;
+            }
+            self.skip_whitespace();
+        }
+    }
+
+    /// Parses the arguments of a command, and of any implicit repetitions of
+    /// it, appending the resulting segments to the path.
+    fn parse_command(&mut self, command: u8) -> Option<()> {
+        let relative = command.is_ascii_lowercase();
+        match command.to_ascii_uppercase() {
+            b'Z' => {
+                self.path.close_path();
+                self.last_control_point = LastControlPoint::None;
+                return Some(());
+            },
+            b'M' => {
+                let point = self.parse_point(relative)?;
+                self.path.move_to(point);
+                self.last_control_point = LastControlPoint::None;
+                // Further coordinate pairs are implicit lineto commands.
+                while self.has_more_arguments() {
+                    let point = self.parse_point(relative)?;
+                    self.path.line_to(point);
+                }
+                return Some(());
+            },
+            b'L' | b'H' | b'V' | b'C' | b'S' | b'Q' | b'T' | b'A' => {},
+            _ => return None,
+        }
+
+        loop {
+            self.parse_drawing_command(command.to_ascii_uppercase(), relative)?;
+            if !self.has_more_arguments() {
+                return Some(());
+            }
+        }
+    }
+
+    fn parse_drawing_command(&mut self, command: u8, relative: bool) -> Option<()> {
+        self.skip_comma_whitespace();
+        let current = self.current_point();
+        match command {
+            b'L' => {
+                let point = self.parse_point(relative)?;
+                self.path.line_to(point);
+                self.last_control_point = LastControlPoint::None;
+            },
+            b'H' => {
+                let mut x = self.parse_number()?;
+                if relative {
+                    x += current.x;
+                }
+                self.path.line_to(Point2D::new(x, current.y));
+                self.last_control_point = LastControlPoint::None;
+            },
+            b'V' => {
+                let mut y = self.parse_number()?;
+                if relative {
+                    y += current.y;
+                }
+                self.path.line_to(Point2D::new(current.x, y));
+                self.last_control_point = LastControlPoint::None;
+            },
+            b'C' => {
+                let cp1 = self.parse_point(relative)?;
+                let cp2 = self.parse_point(relative)?;
+                let point = self.parse_point(relative)?;
+                self.path.bezier_curve_to(cp1, cp2, point);
+                self.last_control_point = LastControlPoint::Cubic(cp2);
+            },
+            b'S' => {
+                let cp1 = match self.last_control_point {
+                    LastControlPoint::Cubic(cp) => reflect(cp, current),
+                    _ => current,
+                };
+                let cp2 = self.parse_point(relative)?;
+                let point = self.parse_point(relative)?;
+                self.path.bezier_curve_to(cp1, cp2, point);
+                self.last_control_point = LastControlPoint::Cubic(cp2);
+            },
+            b'Q' => {
+                let cp = self.parse_point(relative)?;
+                let point = self.parse_point(relative)?;
+                self.path.quadratic_curve_to(cp, point);
+                self.last_control_point = LastControlPoint::Quadratic(cp);
+            },
+            b'T' => {
+                let cp = match self.last_control_point {
+                    LastControlPoint::Quadratic(cp) => reflect(cp, current),
+                    _ => current,
+                };
+                let point = self.parse_point(relative)?;
+                self.path.quadratic_curve_to(cp, point);
+                self.last_control_point = LastControlPoint::Quadratic(cp);
+            },
+            b'A' => {
+                let radius_x = self.parse_number()?;
+                self.skip_comma_whitespace();
+                let radius_y = self.parse_number()?;
+                self.skip_comma_whitespace();
+                let rotation = self.parse_number()?;
+                self.skip_comma_whitespace();
+                let large_arc = self.parse_flag()?;
+                self.skip_comma_whitespace();
+                let sweep = self.parse_flag()?;
+                self.skip_comma_whitespace();
+                let point = self.parse_point(relative)?;
+                self.arc(current, radius_x, radius_y, rotation, large_arc, sweep, point);
+                self.last_control_point = LastControlPoint::None;
+            },
+            _ => unreachable!(),
+        }
+        Some(())
+    }
+
+    /// Converts an arc from the endpoint parameterization of path data to the
+    /// center parameterization that `PathData` uses.
+    /// <https://www.w3.org/TR/SVG11/implnote.html#ArcConversionEndpointToCenter>
+    fn arc(
+        &mut self,
+        from: Point2D<f64>,
+        radius_x: f64,
+        radius_y: f64,
+        rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        to: Point2D<f64>,
+    ) {
+        if from == to {
+            return;
+        }
+        let (mut radius_x, mut radius_y) = (radius_x.abs(), radius_y.abs());
+        if radius_x == 0.0 || radius_y == 0.0 {
+            return self.path.line_to(to);
+        }
+
+        let rotation = rotation.to_radians();
+        let (sin, cos) = rotation.sin_cos();
+        let (half_dx, half_dy) = ((from.x - to.x) / 2.0, (from.y - to.y) / 2.0);
+        let x1 = cos * half_dx + sin * half_dy;
+        let y1 = -sin * half_dx + cos * half_dy;
+
+        // Scale radii that are too small to span the endpoints.
+        let lambda = (x1 * x1) / (radius_x * radius_x) + (y1 * y1) / (radius_y * radius_y);
+        if lambda > 1.0 {
+            radius_x *= lambda.sqrt();
+            radius_y *= lambda.sqrt();
+        }
+
+        let (rx2, ry2) = (radius_x * radius_x, radius_y * radius_y);
+        let numerator = rx2 * ry2 - rx2 * y1 * y1 - ry2 * x1 * x1;
+        let denominator = rx2 * y1 * y1 + ry2 * x1 * x1;
+        let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let cx1 = coefficient * radius_x * y1 / radius_y;
+        let cy1 = -coefficient * radius_y * x1 / radius_x;
+        let center = Point2D::new(cos * cx1 - sin * cy1 + (from.x + to.x) / 2.0,
+                                  sin * cx1 + cos * cy1 + (from.y + to.y) / 2.0);
+
+        let start = ((y1 - cy1) / radius_y).atan2((x1 - cx1) / radius_x);
+        let end = ((-y1 - cy1) / radius_y).atan2((-x1 - cx1) / radius_x);
+        let mut delta = end - start;
+        if !sweep && delta > 0.0 {
+            delta -= 2.0 * PI;
+        } else if sweep && delta < 0.0 {
+            delta += 2.0 * PI;
+        }
+
+        let ellipse = EllipseArc { center, radius_x, radius_y, rotation };
+        self.path.append_arc(&ellipse, start, delta);
+    }
+
+    fn current_point(&self) -> Point2D<f64> {
+        self.path.current_point().unwrap_or(Point2D::zero())
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\x0C') | Some(b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn skip_comma_whitespace(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.position += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    /// Whether another set of arguments follows, repeating the last command.
+    fn has_more_arguments(&mut self) -> bool {
+        self.skip_comma_whitespace();
+        match self.peek() {
+            Some(b'0'...b'9') | Some(b'+') | Some(b'-') | Some(b'.') => true,
+            _ => false,
+        }
+    }
+
+    fn parse_point(&mut self, relative: bool) -> Option<Point2D<f64>> {
+        self.skip_comma_whitespace();
+        let x = self.parse_number()?;
+        self.skip_comma_whitespace();
+        let y = self.parse_number()?;
+        let point = Point2D::new(x, y);
+        if relative {
+            let current = self.current_point();
+            return Some(Point2D::new(current.x + point.x, current.y + point.y));
+        }
+        Some(point)
+    }
+
+    fn parse_flag(&mut self) -> Option<bool> {
+        let flag = match self.peek()? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(flag)
+    }
+
+    fn parse_number(&mut self) -> Option<f64> {
+        let start = self.position;
+        if let Some(b'+') | Some(b'-') = self.peek() {
+            self.position += 1;
+        }
+        let integer_digits = self.skip_digits();
+        let mut fraction_digits = 0;
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            fraction_digits = self.skip_digits();
+        }
+        if integer_digits == 0 && fraction_digits == 0 {
+            return None;
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.position += 1;
+            }
+            if self.skip_digits() == 0 {
+                // Not an exponent after all, e.g. the "e" of a command.
+                self.position = mantissa_end;
+            }
+        }
+
+        let number = ::std::str::from_utf8(&self.input[start..self.position]).ok()?;
+        number.parse::<f64>().ok().filter(|number| number.is_finite())
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.position;
+        while let Some(b'0'...b'9') = self.peek() {
+            self.position += 1;
+        }
+        self.position - start
+    }
+}
+
+/// Reflects `point` about `center`.
+fn reflect(point: Point2D<f64>, center: Point2D<f64>) -> Point2D<f64> {
+    Point2D::new(2.0 * center.x - point.x, 2.0 * center.y - point.y)
+}
@@ -14,6 +14,7 @@ use dom::bindings::root::{Dom, DomRoot};
 use dom::bindings::str::DOMString;
 use dom::globalscope::GlobalScope;
 use dom::performanceentry::PerformanceEntry;
+use dom::performancelongtasktiming::PerformanceLongTaskTiming;
 use dom::performancemark::PerformanceMark;
 use dom::performancemeasure::PerformanceMeasure;
 use dom::performanceobserver::PerformanceObserver as DOMPerformanceObserver;
@@ -223,6 +224,27 @@ impl Performance {
         task_source.queue_notification(&self.global());
     }
 
+    /// Report a task of the event loop that took longer than 50ms to run,
+    /// given as `time::precise_time_ns` timestamps.
+    ///
+    /// Algorithm spec:
+    /// <https://w3c.github.io/longtasks/#report-long-tasks>
+    pub fn queue_long_task_entry(&self, task_start: u64, task_end: u64) {
+        // Long task entries are never buffered, so only bother creating one
+        // when somebody is observing them.
+        let observed = self.observers.borrow().iter().any(|o| {
+            o.entry_types.iter().any(|entry_type| &**entry_type == "longtask")
+        });
+        if !observed {
+            return;
+        }
+        let entry = PerformanceLongTaskTiming::new(self.global().as_window(),
+                                                   self.to_dom_high_res_time_stamp(task_start),
+                                                   (task_end - task_start).to_ms());
+        self.queue_entry(entry.upcast::<PerformanceEntry>(),
+                         false /* buffer performance entry */);
+    }
+
     /// Observers notifications task.
     ///
     /// Algorithm spec (step 7):
@@ -248,12 +270,24 @@ impl Performance {
         }
     }
 
-    fn now(&self) -> f64 {
-        let nav_start = match self.timing {
+    fn time_origin(&self) -> u64 {
+        match self.timing {
             Some(ref timing) => timing.navigation_start_precise(),
             None => self.navigation_start_precise,
-        };
-        (time::precise_time_ns() - nav_start).to_ms()
+        }
+    }
+
+    fn now(&self) -> f64 {
+        (time::precise_time_ns() - self.time_origin()).to_ms()
+    }
+
+    /// Convert a `time::precise_time_ns` timestamp into a `DOMHighResTimeStamp`
+    /// relative to the time origin. Unset (zero) timestamps stay zero.
+    pub fn to_dom_high_res_time_stamp(&self, time: u64) -> f64 {
+        if time == 0 {
+            return 0.;
+        }
+        time.saturating_sub(self.time_origin()).to_ms()
     }
 }
 
@@ -0,0 +1,54 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::PerformanceLongTaskTimingBinding;
+use dom::bindings::codegen::Bindings::PerformanceLongTaskTimingBinding::PerformanceLongTaskTimingMethods;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::performanceentry::PerformanceEntry;
+use dom::taskattributiontiming::TaskAttributionTiming;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::JSContext;
+use js::jsval::{JSVal, UndefinedValue};
+
+#[dom_struct]
+pub struct PerformanceLongTaskTiming {
+    entry: PerformanceEntry,
+    attribution: Dom<TaskAttributionTiming>,
+}
+
+impl PerformanceLongTaskTiming {
+    fn new_inherited(start_time: f64,
+                     duration: f64,
+                     attribution: &TaskAttributionTiming) -> PerformanceLongTaskTiming {
+        PerformanceLongTaskTiming {
+            // Tasks are only attributed to the document whose event loop ran them.
+            entry: PerformanceEntry::new_inherited(DOMString::from("self"),
+                                                   DOMString::from("longtask"),
+                                                   start_time,
+                                                   duration),
+            attribution: Dom::from_ref(attribution),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(window: &Window, start_time: f64, duration: f64) -> DomRoot<PerformanceLongTaskTiming> {
+        let attribution = TaskAttributionTiming::new(window, DOMString::from("window"));
+        let entry = PerformanceLongTaskTiming::new_inherited(start_time, duration, &attribution);
+        reflect_dom_object(Box::new(entry), window, PerformanceLongTaskTimingBinding::Wrap)
+    }
+}
+
+impl PerformanceLongTaskTimingMethods for PerformanceLongTaskTiming {
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/longtasks/#dom-performancelongtasktiming-attribution
+    unsafe fn Attribution(&self, cx: *mut JSContext) -> JSVal {
+        rooted!(in(cx) let mut attribution = UndefinedValue());
+        vec![DomRoot::from_ref(&*self.attribution)].to_jsval(cx, attribution.handle_mut());
+        attribution.get()
+    }
+}
@@ -0,0 +1,115 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
+use dom::bindings::codegen::Bindings::PerformanceNavigationTimingBinding;
+use dom::bindings::codegen::Bindings::PerformanceNavigationTimingBinding::NavigationType;
+use dom::bindings::codegen::Bindings::PerformanceNavigationTimingBinding::PerformanceNavigationTimingMethods;
+use dom::bindings::codegen::Bindings::PerformanceTimingBinding::PerformanceTimingMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::document::Document;
+use dom::performanceresourcetiming::{InitiatorType, PerformanceResourceTiming};
+use dom::window::Window;
+use dom_struct::dom_struct;
+use net_traits::ResourceFetchTiming;
+
+#[dom_struct]
+pub struct PerformanceNavigationTiming {
+    resource_timing: PerformanceResourceTiming,
+    /// The navigation start time, in milliseconds since the epoch, which the
+    /// document's milestones are measured against.
+    navigation_start: u64,
+    document: Dom<Document>,
+}
+
+impl PerformanceNavigationTiming {
+    fn new_inherited(window: &Window,
+                     document: &Document,
+                     timing: &ResourceFetchTiming) -> PerformanceNavigationTiming {
+        let performance = window.Performance();
+        PerformanceNavigationTiming {
+            resource_timing: PerformanceResourceTiming::new_inherited(&document.url(),
+                                                                      DOMString::from("navigation"),
+                                                                      InitiatorType::Navigation,
+                                                                      &performance,
+                                                                      timing),
+            navigation_start: performance.Timing().NavigationStart(),
+            document: Dom::from_ref(document),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(window: &Window,
+               document: &Document,
+               timing: &ResourceFetchTiming) -> DomRoot<PerformanceNavigationTiming> {
+        let entry = PerformanceNavigationTiming::new_inherited(window, document, timing);
+        reflect_dom_object(Box::new(entry), window, PerformanceNavigationTimingBinding::Wrap)
+    }
+
+    /// Convert one of the document's milestones into a `DOMHighResTimeStamp`.
+    fn relative_to_navigation_start(&self, time: u64) -> Finite<f64> {
+        if time == 0 {
+            return Finite::wrap(0.);
+        }
+        Finite::wrap(time.saturating_sub(self.navigation_start) as f64)
+    }
+}
+
+impl PerformanceNavigationTimingMethods for PerformanceNavigationTiming {
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-unloadeventstart
+    fn UnloadEventStart(&self) -> Finite<f64> {
+        // TODO: the unload of the previous document is not timed yet.
+        Finite::wrap(0.)
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-unloadeventend
+    fn UnloadEventEnd(&self) -> Finite<f64> {
+        Finite::wrap(0.)
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-dominteractive
+    fn DomInteractive(&self) -> Finite<f64> {
+        self.relative_to_navigation_start(self.document.get_dom_interactive())
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-domcontentloadedeventstart
+    fn DomContentLoadedEventStart(&self) -> Finite<f64> {
+        self.relative_to_navigation_start(self.document.get_dom_content_loaded_event_start())
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-domcontentloadedeventend
+    fn DomContentLoadedEventEnd(&self) -> Finite<f64> {
+        self.relative_to_navigation_start(self.document.get_dom_content_loaded_event_end())
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-domcomplete
+    fn DomComplete(&self) -> Finite<f64> {
+        self.relative_to_navigation_start(self.document.get_dom_complete())
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-loadeventstart
+    fn LoadEventStart(&self) -> Finite<f64> {
+        self.relative_to_navigation_start(self.document.get_load_event_start())
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-loadeventend
+    fn LoadEventEnd(&self) -> Finite<f64> {
+        self.relative_to_navigation_start(self.document.get_load_event_end())
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-type
+    fn Type(&self) -> NavigationType {
+        // TODO: reloads and history traversals are not told apart yet.
+        NavigationType::Navigate
+    }
+
+    // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming-redirectcount
+    fn RedirectCount(&self) -> u16 {
+        0
+    }
+}
@@ -24,9 +24,13 @@ use std::rc::Rc;
 const VALID_ENTRY_TYPES: &'static [&'static str] = &[
     "mark", // User Timing API
     "measure", // User Timing API
-    // "resource", XXX Resource Timing API
+    "resource", // Resource Timing API
+    "navigation", // Navigation Timing API
+    "longtask", // Long Tasks API
     // "server", XXX Server Timing API
     "paint", // Paint Timing API
+    "largest-contentful-paint", // Largest Contentful Paint API
+    "layout-shift", // Layout Instability API
 ];
 
 #[dom_struct]
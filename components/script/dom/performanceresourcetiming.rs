@@ -0,0 +1,209 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::PerformanceResourceTimingBinding;
+use dom::bindings::codegen::Bindings::PerformanceResourceTimingBinding::PerformanceResourceTimingMethods;
+use dom::bindings::num::Finite;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::globalscope::GlobalScope;
+use dom::performance::Performance;
+use dom::performanceentry::PerformanceEntry;
+use dom_struct::dom_struct;
+use net_traits::ResourceFetchTiming;
+use servo_url::ServoUrl;
+
+/// The kind of content that initiated a fetch.
+/// <https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-initiatortype>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum InitiatorType {
+    Audio,
+    Css,
+    Img,
+    Link,
+    Navigation,
+    Other,
+    Script,
+    Video,
+}
+
+impl InitiatorType {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            InitiatorType::Audio => "audio",
+            InitiatorType::Css => "css",
+            InitiatorType::Img => "img",
+            InitiatorType::Link => "link",
+            InitiatorType::Navigation => "navigation",
+            InitiatorType::Other => "other",
+            InitiatorType::Script => "script",
+            InitiatorType::Video => "video",
+        }
+    }
+}
+
+#[dom_struct]
+pub struct PerformanceResourceTiming {
+    entry: PerformanceEntry,
+    initiator_type: InitiatorType,
+    next_hop_protocol: DOMString,
+    fetch_start: f64,
+    connect_start: f64,
+    connect_end: f64,
+    request_start: f64,
+    response_start: f64,
+    response_end: f64,
+    transfer_size: u64,
+    encoded_body_size: u64,
+    decoded_body_size: u64,
+}
+
+impl PerformanceResourceTiming {
+    pub fn new_inherited(url: &ServoUrl,
+                         entry_type: DOMString,
+                         initiator_type: InitiatorType,
+                         performance: &Performance,
+                         timing: &ResourceFetchTiming) -> PerformanceResourceTiming {
+        let fetch_start = performance.to_dom_high_res_time_stamp(timing.fetch_start);
+        let response_end = performance.to_dom_high_res_time_stamp(timing.response_end);
+        // https://w3c.github.io/navigation-timing/#dom-performancenavigationtiming
+        // The navigation entry always starts at the time origin.
+        let start_time = if initiator_type == InitiatorType::Navigation {
+            0.
+        } else {
+            fetch_start
+        };
+        let duration = if response_end > start_time {
+            response_end - start_time
+        } else {
+            0.
+        };
+        // Only responses that came from the network carry connection timings,
+        // and our network stack only speaks HTTP/1.1.
+        let next_hop_protocol = if timing.connect_start != 0 {
+            DOMString::from("http/1.1")
+        } else {
+            DOMString::new()
+        };
+        PerformanceResourceTiming {
+            entry: PerformanceEntry::new_inherited(DOMString::from(url.as_str()),
+                                                   entry_type,
+                                                   start_time,
+                                                   duration),
+            initiator_type,
+            next_hop_protocol,
+            fetch_start,
+            connect_start: performance.to_dom_high_res_time_stamp(timing.connect_start),
+            connect_end: performance.to_dom_high_res_time_stamp(timing.connect_end),
+            request_start: performance.to_dom_high_res_time_stamp(timing.request_start),
+            response_start: performance.to_dom_high_res_time_stamp(timing.response_start),
+            response_end,
+            transfer_size: timing.transfer_size,
+            encoded_body_size: timing.encoded_body_size,
+            decoded_body_size: timing.decoded_body_size,
+        }
+    }
+
+    /// Create a resource entry from the given timings, which must already have
+    /// had the attributes that failed the timing allow check zeroed out; zero
+    /// timings are exposed as `0`, and hide the next hop protocol.
+    #[allow(unrooted_must_root)]
+    pub fn new(global: &GlobalScope,
+               url: &ServoUrl,
+               initiator_type: InitiatorType,
+               timing: &ResourceFetchTiming) -> DomRoot<PerformanceResourceTiming> {
+        let entry = PerformanceResourceTiming::new_inherited(url,
+                                                             DOMString::from("resource"),
+                                                             initiator_type,
+                                                             &global.performance(),
+                                                             timing);
+        reflect_dom_object(Box::new(entry), global, PerformanceResourceTimingBinding::Wrap)
+    }
+}
+
+impl PerformanceResourceTimingMethods for PerformanceResourceTiming {
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-initiatortype
+    fn InitiatorType(&self) -> DOMString {
+        DOMString::from(self.initiator_type.as_str())
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-nexthopprotocol
+    fn NextHopProtocol(&self) -> DOMString {
+        self.next_hop_protocol.clone()
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-redirectstart
+    fn RedirectStart(&self) -> Finite<f64> {
+        // TODO: redirects are not reported by the network stack yet.
+        Finite::wrap(0.)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-redirectend
+    fn RedirectEnd(&self) -> Finite<f64> {
+        Finite::wrap(0.)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-fetchstart
+    fn FetchStart(&self) -> Finite<f64> {
+        Finite::wrap(self.fetch_start)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-domainlookupstart
+    fn DomainLookupStart(&self) -> Finite<f64> {
+        // Name resolution happens as part of establishing the connection.
+        Finite::wrap(self.connect_start)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-domainlookupend
+    fn DomainLookupEnd(&self) -> Finite<f64> {
+        Finite::wrap(self.connect_start)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-connectstart
+    fn ConnectStart(&self) -> Finite<f64> {
+        Finite::wrap(self.connect_start)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-connectend
+    fn ConnectEnd(&self) -> Finite<f64> {
+        Finite::wrap(self.connect_end)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-secureconnectionstart
+    fn SecureConnectionStart(&self) -> Finite<f64> {
+        // TODO: the network stack does not tell secure connections apart yet.
+        Finite::wrap(0.)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-requeststart
+    fn RequestStart(&self) -> Finite<f64> {
+        Finite::wrap(self.request_start)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-responsestart
+    fn ResponseStart(&self) -> Finite<f64> {
+        Finite::wrap(self.response_start)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-responseend
+    fn ResponseEnd(&self) -> Finite<f64> {
+        Finite::wrap(self.response_end)
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-transfersize
+    fn TransferSize(&self) -> u64 {
+        self.transfer_size
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-encodedbodysize
+    fn EncodedBodySize(&self) -> u64 {
+        self.encoded_body_size
+    }
+
+    // https://w3c.github.io/resource-timing/#dom-performanceresourcetiming-decodedbodysize
+    fn DecodedBodySize(&self) -> u64 {
+        self.decoded_body_size
+    }
+}
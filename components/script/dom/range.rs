@@ -14,7 +14,7 @@ use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::{CharacterDataTypeId, NodeTypeId};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::{Reflector, reflect_dom_object};
-use dom::bindings::root::{Dom, DomRoot, MutDom, RootedReference};
+use dom::bindings::root::{Dom, DomRoot, MutDom, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::bindings::trace::JSTraceable;
 use dom::bindings::weakref::{WeakRef, WeakRefVec};
@@ -24,6 +24,7 @@ use dom::documentfragment::DocumentFragment;
 use dom::element::Element;
 use dom::htmlscriptelement::HTMLScriptElement;
 use dom::node::{Node, UnbindContext};
+use dom::selection::Selection;
 use dom::text::Text;
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -37,6 +38,8 @@ pub struct Range {
     reflector_: Reflector,
     start: BoundaryPoint,
     end: BoundaryPoint,
+    /// The selection this range is associated with, if any.
+    associated_selection: MutNullableDom<Selection>,
 }
 
 impl Range {
@@ -46,6 +49,7 @@ impl Range {
             reflector_: Reflector::new(),
             start: BoundaryPoint::new(start_container, start_offset),
             end: BoundaryPoint::new(end_container, end_offset),
+            associated_selection: Default::default(),
         }
     }
 
@@ -79,7 +83,7 @@ impl Range {
     }
 
     // https://dom.spec.whatwg.org/#contained
-    fn contains(&self, node: &Node) -> bool {
+    pub fn contains(&self, node: &Node) -> bool {
         match (bp_position(node, 0, &self.StartContainer(), self.StartOffset()),
                bp_position(node, node.len(), &self.EndContainer(), self.EndOffset())) {
             (Some(Ordering::Greater), Some(Ordering::Less)) => true,
@@ -146,6 +150,7 @@ impl Range {
             }
         }
         self.start.set(node, offset);
+        self.notify_selection();
     }
 
     // https://dom.spec.whatwg.org/#concept-range-bp-set
@@ -160,6 +165,58 @@ impl Range {
             }
         }
         self.end.set(node, offset);
+        self.notify_selection();
+    }
+
+    /// The text nodes that are in this range, in tree order, with the start and
+    /// end offsets of the part of each one that is in it.
+    pub fn text_nodes(&self) -> Vec<(DomRoot<Text>, u32, u32)> {
+        let (start_node, start_offset) = (self.StartContainer(), self.StartOffset());
+        let (end_node, end_offset) = (self.EndContainer(), self.EndOffset());
+
+        // The text nodes in a range are contiguous in tree order, so we can stop
+        // at the first one past its end.
+        let mut text_nodes = vec![];
+        let mut found_start = false;
+        for node in self.CommonAncestorContainer().traverse_preorder() {
+            if !node.is::<Text>() {
+                continue;
+            }
+            let is_start = node == start_node;
+            let is_end = node == end_node;
+            if !is_start && !is_end && !self.contains(&node) {
+                if found_start {
+                    break;
+                }
+                continue;
+            }
+            found_start = true;
+            let start = if is_start { start_offset } else { 0 };
+            let end = if is_end { end_offset } else { node.len() };
+            if start < end {
+                text_nodes.push((DomRoot::downcast::<Text>(node).unwrap(), start, end));
+            }
+            if is_end {
+                break;
+            }
+        }
+        text_nodes
+    }
+
+    /// <https://w3c.github.io/selection-api/#dfn-associated>
+    pub fn associated_selection(&self) -> Option<DomRoot<Selection>> {
+        self.associated_selection.get()
+    }
+
+    pub fn set_associated_selection(&self, selection: Option<&Selection>) {
+        self.associated_selection.set(selection);
+    }
+
+    /// Let the associated selection know that its range has changed.
+    fn notify_selection(&self) {
+        if let Some(selection) = self.associated_selection.get() {
+            selection.range_changed();
+        }
     }
 
     // https://dom.spec.whatwg.org/#dom-range-comparepointnode-offset
@@ -470,7 +527,7 @@ impl RangeMethods for Range {
                 fragment.upcast::<Node>().AppendChild(&clone)?;
             } else {
                 // Step 14.1.
-                let clone = child.CloneNode(false);
+                let clone = child.CloneNode(false)?;
                 // Step 14.2.
                 fragment.upcast::<Node>().AppendChild(&clone)?;
                 // Step 14.3.
@@ -489,7 +546,7 @@ impl RangeMethods for Range {
         // Step 15.
         for child in contained_children {
             // Step 15.1.
-            let clone = child.CloneNode(true);
+            let clone = child.CloneNode(true)?;
             // Step 15.2.
             fragment.upcast::<Node>().AppendChild(&clone)?;
         }
@@ -505,7 +562,7 @@ impl RangeMethods for Range {
                 fragment.upcast::<Node>().AppendChild(&clone)?;
             } else {
                 // Step 17.1.
-                let clone = child.CloneNode(false);
+                let clone = child.CloneNode(false)?;
                 // Step 17.2.
                 fragment.upcast::<Node>().AppendChild(&clone)?;
                 // Step 17.3.
@@ -545,7 +602,7 @@ impl RangeMethods for Range {
         if end_node == start_node {
             if let Some(end_data) = end_node.downcast::<CharacterData>() {
                 // Step 4.1.
-                let clone = end_node.CloneNode(true);
+                let clone = end_node.CloneNode(true)?;
                 // Step 4.2.
                 let text = end_data.SubstringData(start_offset, end_offset - start_offset);
                 clone.downcast::<CharacterData>().unwrap().SetData(text.unwrap());
@@ -581,7 +638,7 @@ impl RangeMethods for Range {
             if let Some(start_data) = child.downcast::<CharacterData>() {
                 assert!(child == start_node);
                 // Step 15.1.
-                let clone = start_node.CloneNode(true);
+                let clone = start_node.CloneNode(true)?;
                 // Step 15.2.
                 let text = start_data.SubstringData(start_offset,
                                                     start_node.len() - start_offset);
@@ -594,7 +651,7 @@ impl RangeMethods for Range {
                                             DOMString::new())?;
             } else {
                 // Step 16.1.
-                let clone = child.CloneNode(false);
+                let clone = child.CloneNode(false)?;
                 // Step 16.2.
                 fragment.upcast::<Node>().AppendChild(&clone)?;
                 // Step 16.3.
@@ -619,7 +676,7 @@ impl RangeMethods for Range {
             if let Some(end_data) = child.downcast::<CharacterData>() {
                 assert!(child == end_node);
                 // Step 18.1.
-                let clone = end_node.CloneNode(true);
+                let clone = end_node.CloneNode(true)?;
                 // Step 18.2.
                 let text = end_data.SubstringData(0, end_offset);
                 clone.downcast::<CharacterData>().unwrap().SetData(text.unwrap());
@@ -629,7 +686,7 @@ impl RangeMethods for Range {
                 end_data.ReplaceData(0, end_offset, DOMString::new())?;
             } else {
                 // Step 19.1.
-                let clone = child.CloneNode(false);
+                let clone = child.CloneNode(false)?;
                 // Step 19.2.
                 fragment.upcast::<Node>().AppendChild(&clone)?;
                 // Step 19.3.
@@ -725,7 +782,7 @@ impl RangeMethods for Range {
             reference_node.r().map_or(parent.len(), |node| node.index());
 
         // Step 11
-        let new_offset = new_offset + if node.type_id() == NodeTypeId::DocumentFragment {
+        let new_offset = new_offset + if node.is::<DocumentFragment>() {
             node.len()
         } else {
             1
@@ -837,7 +894,7 @@ impl RangeMethods for Range {
         match new_parent.type_id() {
             NodeTypeId::Document(_) |
             NodeTypeId::DocumentType |
-            NodeTypeId::DocumentFragment => return Err(Error::InvalidNodeType),
+            NodeTypeId::DocumentFragment(_) => return Err(Error::InvalidNodeType),
             _ => ()
         }
 
@@ -906,7 +963,7 @@ impl RangeMethods for Range {
         let node = self.StartContainer();
         let owner_doc = node.owner_doc();
         let element = match node.type_id() {
-            NodeTypeId::Document(_) | NodeTypeId::DocumentFragment => None,
+            NodeTypeId::Document(_) | NodeTypeId::DocumentFragment(_) => None,
             NodeTypeId::Element(_) => Some(DomRoot::downcast::<Element>(node).unwrap()),
             NodeTypeId::CharacterData(CharacterDataTypeId::Comment) |
             NodeTypeId::CharacterData(CharacterDataTypeId::Text) => node.GetParentElement(),
@@ -0,0 +1,81 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Node fields that few nodes use, kept out of the node structs so that
+//! they don't make every node bigger.
+
+use dom::bindings::root::MutNullableDom;
+use dom::bindings::trace::JSTraceable;
+use dom::domtokenlist::DOMTokenList;
+use dom::elementinternals::ElementInternals;
+use dom::htmlslotelement::HTMLSlotElement;
+use dom::shadowroot::ShadowRoot;
+use js::jsapi::JSTracer;
+use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+use std::cell::UnsafeCell;
+
+/// The rare fields of a node, and those of the element it may be.
+#[derive(Default, JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct NodeRareData {
+    /// The slot this node is assigned to, if any. Only elements and text
+    /// nodes are ever assigned.
+    ///
+    /// <https://dom.spec.whatwg.org/#slotable-assigned-slot>
+    pub assigned_slot: MutNullableDom<HTMLSlotElement>,
+    /// The token list of the `part` attribute of an element.
+    pub part_list: MutNullableDom<DOMTokenList>,
+    /// <https://dom.spec.whatwg.org/#concept-element-shadow-root>
+    pub shadow_root: MutNullableDom<ShadowRoot>,
+    /// The internals of an HTML element, created by `attachInternals()` or
+    /// when the element gets a form owner.
+    pub element_internals: MutNullableDom<ElementInternals>,
+}
+
+/// The rare data of a node, allocated the first time one of its fields is
+/// set. It is never freed before the node, and its fields are only ever
+/// mutated through shared references.
+#[must_root]
+pub struct RareData {
+    cell: UnsafeCell<Option<Box<NodeRareData>>>,
+}
+
+#[allow(unsafe_code)]
+impl RareData {
+    pub fn new() -> RareData {
+        RareData { cell: UnsafeCell::new(None) }
+    }
+
+    /// The rare data, if it was allocated.
+    pub fn get(&self) -> Option<&NodeRareData> {
+        unsafe { (*self.cell.get()).as_ref().map(|data| &**data) }
+    }
+
+    /// The rare data, allocating it first if needed.
+    pub fn ensure(&self) -> &NodeRareData {
+        unsafe {
+            let ptr = self.cell.get();
+            if (*ptr).is_none() {
+                *ptr = Some(Box::new(NodeRareData::default()));
+            }
+            (*ptr).as_ref().unwrap()
+        }
+    }
+}
+
+#[allow(unsafe_code)]
+impl MallocSizeOf for RareData {
+    fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+        self.get().map_or(0, |data| unsafe { ops.malloc_size_of(data) } + data.size_of(ops))
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe impl JSTraceable for RareData {
+    unsafe fn trace(&self, trc: *mut JSTracer) {
+        if let Some(data) = self.get() {
+            data.trace(trc);
+        }
+    }
+}
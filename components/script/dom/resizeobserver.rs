@@ -0,0 +1,242 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use dom::bindings::callback::ExceptionHandling;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ResizeObserverBinding;
+use dom::bindings::codegen::Bindings::ResizeObserverBinding::{ResizeObserverBoxOptions, ResizeObserverCallback};
+use dom::bindings::codegen::Bindings::ResizeObserverBinding::{ResizeObserverMethods, ResizeObserverOptions};
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::domrectreadonly::DOMRectReadOnly;
+use dom::element::Element;
+use dom::node::{Node, window_from_node};
+use dom::resizeobserverentry::ResizeObserverEntry;
+use dom::resizeobserversize::ResizeObserverSize;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::{Rect, Size2D};
+use script_layout_interface::rpc::BoxSizes;
+use std::cell::Cell;
+use std::cmp::min;
+use std::rc::Rc;
+
+/// The depth of a node in the tree, used to avoid infinite resize loops.
+///
+/// <https://drafts.csswg.org/resize-observer/#calculate-depth-for-node>
+pub fn depth_for_node(node: &Node) -> usize {
+    node.ancestors().count()
+}
+
+/// The inline and block sizes of a box.
+type LogicalSize = (f64, f64);
+
+/// <https://drafts.csswg.org/resize-observer/#resize-observation-interface>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct ResizeObservation {
+    target: Dom<Element>,
+    observed_box: ResizeObserverBoxOptions,
+    last_reported_size: Cell<LogicalSize>,
+    /// Whether this observation is in the active targets of its observer.
+    active: Cell<bool>,
+    /// Whether this observation is in the skipped targets of its observer.
+    skipped: Cell<bool>,
+}
+
+impl ResizeObservation {
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobservation-isactive>
+    fn is_active(&self) -> bool {
+        let sizes = box_sizes(&self.target);
+        calculate_box_size(&self.target, sizes.as_ref(), self.observed_box) != self.last_reported_size.get()
+    }
+}
+
+// https://drafts.csswg.org/resize-observer/#resize-observer-interface
+#[dom_struct]
+pub struct ResizeObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<ResizeObserverCallback>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-observationtargets-slot>
+    observation_targets: DomRefCell<Vec<ResizeObservation>>,
+}
+
+impl ResizeObserver {
+    fn new_inherited(callback: Rc<ResizeObserverCallback>) -> ResizeObserver {
+        ResizeObserver {
+            reflector_: Reflector::new(),
+            callback: callback,
+            observation_targets: DomRefCell::new(vec![]),
+        }
+    }
+
+    fn new(window: &Window, callback: Rc<ResizeObserverCallback>) -> DomRoot<ResizeObserver> {
+        reflect_dom_object(Box::new(ResizeObserver::new_inherited(callback)),
+                           window,
+                           ResizeObserverBinding::Wrap)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-resizeobserver
+    pub fn Constructor(window: &Window, callback: Rc<ResizeObserverCallback>)
+                       -> Fallible<DomRoot<ResizeObserver>> {
+        let observer = ResizeObserver::new(window, callback);
+        window.Document().add_resize_observer(&observer);
+        Ok(observer)
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    /// step 2, for this observer.
+    pub fn gather_active_observations_at_depth(&self, depth: usize) {
+        for observation in self.observation_targets.borrow().iter() {
+            observation.active.set(false);
+            observation.skipped.set(false);
+            if !observation.is_active() {
+                continue;
+            }
+            if depth_for_node(observation.target.upcast()) > depth {
+                observation.active.set(true);
+            } else {
+                observation.skipped.set(true);
+            }
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-active-observations-h>
+    pub fn has_active_observations(&self) -> bool {
+        self.observation_targets.borrow().iter().any(|observation| observation.active.get())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-skipped-observations-h>
+    pub fn has_skipped_observations(&self) -> bool {
+        self.observation_targets.borrow().iter().any(|observation| observation.skipped.get())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#broadcast-active-observations>
+    /// step 2, for this observer. Returns the depth of the shallowest target
+    /// that was reported.
+    pub fn broadcast_active_observations(&self) -> usize {
+        let mut shallowest_target_depth = usize::max_value();
+
+        // Step 2.1.
+        if !self.has_active_observations() {
+            return shallowest_target_depth;
+        }
+
+        // Step 2.2-2.3.
+        let window = self.global();
+        let window = window.as_window();
+        let mut entries = vec![];
+        for observation in self.observation_targets.borrow().iter() {
+            if !observation.active.get() {
+                continue;
+            }
+            let target = &*observation.target;
+            let sizes = box_sizes(target);
+            let entry = create_and_populate_entry(window, target, sizes.as_ref());
+            entries.push(entry);
+            observation.last_reported_size.set(calculate_box_size(target, sizes.as_ref(), observation.observed_box));
+            shallowest_target_depth = min(shallowest_target_depth, depth_for_node(target.upcast()));
+        }
+
+        // Step 2.4.
+        let _ = self.callback.Call_(self, entries, self, ExceptionHandling::Report);
+
+        // Step 2.5.
+        for observation in self.observation_targets.borrow().iter() {
+            observation.active.set(false);
+        }
+
+        shallowest_target_depth
+    }
+}
+
+impl ResizeObserverMethods for ResizeObserver {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-observe
+    fn Observe(&self, target: &Element, options: &ResizeObserverOptions) {
+        // Step 1-2.
+        self.Unobserve(target);
+        // Step 3-4.
+        self.observation_targets.borrow_mut().push(ResizeObservation {
+            target: Dom::from_ref(target),
+            observed_box: options.box_,
+            last_reported_size: Cell::new((0., 0.)),
+            active: Cell::new(false),
+            skipped: Cell::new(false),
+        });
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-unobserve
+    fn Unobserve(&self, target: &Element) {
+        self.observation_targets.borrow_mut().retain(|observation| &*observation.target != target);
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-disconnect
+    fn Disconnect(&self) {
+        self.observation_targets.borrow_mut().clear();
+    }
+}
+
+fn box_sizes(target: &Element) -> Option<BoxSizes> {
+    window_from_node(target).box_sizes_query(target.upcast::<Node>().to_trusted_node_address())
+}
+
+fn logical_size(size: &Size2D<Au>, vertical: bool) -> LogicalSize {
+    let (width, height) = (size.width.to_f64_px(), size.height.to_f64_px());
+    if vertical {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+/// <https://drafts.csswg.org/resize-observer/#calculate-box-size>
+fn calculate_box_size(target: &Element,
+                      sizes: Option<&BoxSizes>,
+                      observed_box: ResizeObserverBoxOptions)
+                      -> LogicalSize {
+    let sizes = match sizes {
+        Some(sizes) => sizes,
+        None => return (0., 0.),
+    };
+    match observed_box {
+        ResizeObserverBoxOptions::Border_box => logical_size(&sizes.border_box, sizes.vertical),
+        ResizeObserverBoxOptions::Content_box => logical_size(&sizes.content_box.size, sizes.vertical),
+        ResizeObserverBoxOptions::Device_pixel_content_box => {
+            let (inline_size, block_size) = logical_size(&sizes.content_box.size, sizes.vertical);
+            let ratio = window_from_node(target).device_pixel_ratio().get() as f64;
+            ((inline_size * ratio).round(), (block_size * ratio).round())
+        },
+    }
+}
+
+/// <https://drafts.csswg.org/resize-observer/#create-and-populate-resizeobserverentry-h>
+fn create_and_populate_entry(window: &Window,
+                             target: &Element,
+                             sizes: Option<&BoxSizes>)
+                             -> DomRoot<ResizeObserverEntry> {
+    let new_size = |observed_box| {
+        let (inline_size, block_size) = calculate_box_size(target, sizes, observed_box);
+        ResizeObserverSize::new(window, inline_size, block_size)
+    };
+    let border_box_size = new_size(ResizeObserverBoxOptions::Border_box);
+    let content_box_size = new_size(ResizeObserverBoxOptions::Content_box);
+    let device_pixel_content_box_size = new_size(ResizeObserverBoxOptions::Device_pixel_content_box);
+    let content_rect = sizes.map_or(Rect::zero(), |sizes| sizes.content_box);
+    let content_rect = DOMRectReadOnly::new(window.upcast(),
+                                            content_rect.origin.x.to_f64_px(),
+                                            content_rect.origin.y.to_f64_px(),
+                                            content_rect.size.width.to_f64_px(),
+                                            content_rect.size.height.to_f64_px());
+    ResizeObserverEntry::new(window,
+                             target,
+                             &content_rect,
+                             &border_box_size,
+                             &content_box_size,
+                             &device_pixel_content_box_size)
+}
@@ -0,0 +1,97 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ResizeObserverEntryBinding;
+use dom::bindings::codegen::Bindings::ResizeObserverEntryBinding::ResizeObserverEntryMethods;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::domrectreadonly::DOMRectReadOnly;
+use dom::element::Element;
+use dom::resizeobserversize::ResizeObserverSize;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::JSContext;
+use js::jsval::{JSVal, UndefinedValue};
+
+// https://drafts.csswg.org/resize-observer/#resize-observer-entry-interface
+#[dom_struct]
+pub struct ResizeObserverEntry {
+    reflector_: Reflector,
+    target: Dom<Element>,
+    content_rect: Dom<DOMRectReadOnly>,
+    border_box_size: Dom<ResizeObserverSize>,
+    content_box_size: Dom<ResizeObserverSize>,
+    device_pixel_content_box_size: Dom<ResizeObserverSize>,
+}
+
+impl ResizeObserverEntry {
+    fn new_inherited(target: &Element,
+                     content_rect: &DOMRectReadOnly,
+                     border_box_size: &ResizeObserverSize,
+                     content_box_size: &ResizeObserverSize,
+                     device_pixel_content_box_size: &ResizeObserverSize)
+                     -> ResizeObserverEntry {
+        ResizeObserverEntry {
+            reflector_: Reflector::new(),
+            target: Dom::from_ref(target),
+            content_rect: Dom::from_ref(content_rect),
+            border_box_size: Dom::from_ref(border_box_size),
+            content_box_size: Dom::from_ref(content_box_size),
+            device_pixel_content_box_size: Dom::from_ref(device_pixel_content_box_size),
+        }
+    }
+
+    pub fn new(window: &Window,
+               target: &Element,
+               content_rect: &DOMRectReadOnly,
+               border_box_size: &ResizeObserverSize,
+               content_box_size: &ResizeObserverSize,
+               device_pixel_content_box_size: &ResizeObserverSize)
+               -> DomRoot<ResizeObserverEntry> {
+        let entry = ResizeObserverEntry::new_inherited(target,
+                                                       content_rect,
+                                                       border_box_size,
+                                                       content_box_size,
+                                                       device_pixel_content_box_size);
+        reflect_dom_object(Box::new(entry), window, ResizeObserverEntryBinding::Wrap)
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe fn sizes_to_jsval(cx: *mut JSContext, size: &ResizeObserverSize) -> JSVal {
+    rooted!(in(cx) let mut sizes = UndefinedValue());
+    vec![DomRoot::from_ref(size)].to_jsval(cx, sizes.handle_mut());
+    sizes.get()
+}
+
+impl ResizeObserverEntryMethods for ResizeObserverEntry {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-target
+    fn Target(&self) -> DomRoot<Element> {
+        DomRoot::from_ref(&*self.target)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-contentrect
+    fn ContentRect(&self) -> DomRoot<DOMRectReadOnly> {
+        DomRoot::from_ref(&*self.content_rect)
+    }
+
+    #[allow(unsafe_code)]
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-borderboxsize
+    unsafe fn BorderBoxSize(&self, cx: *mut JSContext) -> JSVal {
+        sizes_to_jsval(cx, &self.border_box_size)
+    }
+
+    #[allow(unsafe_code)]
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-contentboxsize
+    unsafe fn ContentBoxSize(&self, cx: *mut JSContext) -> JSVal {
+        sizes_to_jsval(cx, &self.content_box_size)
+    }
+
+    #[allow(unsafe_code)]
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-devicepixelcontentboxsize
+    unsafe fn DevicePixelContentBoxSize(&self, cx: *mut JSContext) -> JSVal {
+        sizes_to_jsval(cx, &self.device_pixel_content_box_size)
+    }
+}
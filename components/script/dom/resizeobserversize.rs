@@ -0,0 +1,46 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ResizeObserverSizeBinding;
+use dom::bindings::codegen::Bindings::ResizeObserverSizeBinding::ResizeObserverSizeMethods;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+// https://drafts.csswg.org/resize-observer/#resizeobserversize
+#[dom_struct]
+pub struct ResizeObserverSize {
+    reflector_: Reflector,
+    inline_size: f64,
+    block_size: f64,
+}
+
+impl ResizeObserverSize {
+    fn new_inherited(inline_size: f64, block_size: f64) -> ResizeObserverSize {
+        ResizeObserverSize {
+            reflector_: Reflector::new(),
+            inline_size: inline_size,
+            block_size: block_size,
+        }
+    }
+
+    pub fn new(window: &Window, inline_size: f64, block_size: f64) -> DomRoot<ResizeObserverSize> {
+        reflect_dom_object(Box::new(ResizeObserverSize::new_inherited(inline_size, block_size)),
+                           window,
+                           ResizeObserverSizeBinding::Wrap)
+    }
+}
+
+impl ResizeObserverSizeMethods for ResizeObserverSize {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserversize-inlinesize
+    fn InlineSize(&self) -> f64 {
+        self.inline_size
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserversize-blocksize
+    fn BlockSize(&self) -> f64 {
+        self.block_size
+    }
+}
@@ -0,0 +1,488 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use dom::bindings::codegen::Bindings::SelectionBinding::{self, SelectionMethods};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::document::Document;
+use dom::editing;
+use dom::eventtarget::EventTarget;
+use dom::node::Node;
+use dom::range::Range;
+use dom::text::Text;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::iter;
+use task_source::TaskSource;
+
+/// <https://w3c.github.io/selection-api/#dfn-direction>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+enum Direction {
+    Forwards,
+    Backwards,
+    Directionless,
+}
+
+// https://w3c.github.io/selection-api/#selection-interface
+#[dom_struct]
+pub struct Selection {
+    reflector_: Reflector,
+    document: Dom<Document>,
+    range: MutNullableDom<Range>,
+    direction: Cell<Direction>,
+    /// The text nodes that are currently painted as selected.
+    highlighted_text: DomRefCell<Vec<Dom<Text>>>,
+    /// Whether a `selectionchange` event is already queued for the document.
+    selectionchange_task_queued: Cell<bool>,
+}
+
+impl Selection {
+    fn new_inherited(document: &Document) -> Selection {
+        Selection {
+            reflector_: Reflector::new(),
+            document: Dom::from_ref(document),
+            range: MutNullableDom::new(None),
+            direction: Cell::new(Direction::Directionless),
+            highlighted_text: DomRefCell::new(vec![]),
+            selectionchange_task_queued: Cell::new(false),
+        }
+    }
+
+    pub fn new(document: &Document) -> DomRoot<Selection> {
+        reflect_dom_object(Box::new(Selection::new_inherited(document)),
+                           document.window(),
+                           SelectionBinding::Wrap)
+    }
+
+    /// Whether the document associated with this selection is the
+    /// shadow-including root of `node`.
+    fn is_in_document(&self, node: &Node) -> bool {
+        node.is_in_doc() && *node.owner_doc() == *self.document
+    }
+
+    /// Replace the range of this selection, associating the new range with it.
+    fn set_range(&self, range: Option<&Range>, direction: Direction) {
+        if let Some(old_range) = self.range.get() {
+            old_range.set_associated_selection(None);
+        }
+        if let Some(range) = range {
+            if let Some(other_selection) = range.associated_selection() {
+                other_selection.range.set(None);
+                other_selection.range_changed();
+            }
+            range.set_associated_selection(Some(self));
+        }
+        self.range.set(range);
+        self.direction.set(direction);
+        self.range_changed();
+    }
+
+    /// Replace the range of this selection with a new range between the given
+    /// boundary points, which must be in document order.
+    fn set_new_range(&self,
+                     start: (&Node, u32),
+                     end: (&Node, u32),
+                     direction: Direction) {
+        let range = Range::new(&self.document, start.0, start.1, end.0, end.1);
+        self.set_range(Some(&range), direction);
+    }
+
+    /// Called whenever the range of this selection or its boundary points change.
+    pub fn range_changed(&self) {
+        self.update_highlighted_text();
+        self.queue_selectionchange_task();
+    }
+
+    /// <https://w3c.github.io/selection-api/#selectionchange-event>
+    fn queue_selectionchange_task(&self) {
+        if self.selectionchange_task_queued.get() {
+            return;
+        }
+        self.selectionchange_task_queued.set(true);
+        let this = Trusted::new(self);
+        let window = self.document.window();
+        let _ = window.user_interaction_task_source().queue(
+            task!(fire_selectionchange_event: move || {
+                let this = this.root();
+                this.selectionchange_task_queued.set(false);
+                this.document.upcast::<EventTarget>().fire_event(atom!("selectionchange"));
+            }),
+            window.upcast(),
+        );
+    }
+
+    /// Mark the text nodes covered by the range of this selection so that
+    /// layout paints them as selected, or paints the caret if the selection
+    /// is collapsed in focused editable content.
+    pub fn update_highlighted_text(&self) {
+        let mut highlighted_text = self.highlighted_text.borrow_mut();
+        for text in highlighted_text.drain(..) {
+            text.set_selected_range(None);
+        }
+
+        let range = match self.range.get() {
+            Some(range) => range,
+            None => return,
+        };
+
+        if range.Collapsed() {
+            let container = range.StartContainer();
+            if let Some(text) = container.downcast::<Text>() {
+                if editing::shows_caret(&self.document, &container) {
+                    let offset = range.StartOffset();
+                    text.set_selected_range(Some((offset, offset)));
+                    highlighted_text.push(Dom::from_ref(text));
+                }
+            }
+            return;
+        }
+
+        for (text, start, end) in range.text_nodes() {
+            text.set_selected_range(Some((start, end)));
+            highlighted_text.push(Dom::from_ref(&*text));
+        }
+    }
+
+    /// Collapse this selection at the given point, as when the user starts
+    /// selecting with the mouse.
+    pub fn collapse_at(&self, node: &Node, offset: u32) {
+        let _ = self.Collapse(Some(node), offset);
+    }
+
+    /// Move the focus of this selection to the given point, keeping its anchor,
+    /// as when the user drags the mouse.
+    pub fn extend_to(&self, node: &Node, offset: u32) {
+        if self.range.get().is_none() {
+            return self.collapse_at(node, offset);
+        }
+        let _ = self.Extend(node, offset);
+    }
+
+    /// Move the focus of this selection by a single character, as when the user
+    /// presses shift and an arrow key.
+    pub fn extend_by_character(&self, forward: bool) {
+        let focus_node = match self.GetFocusNode() {
+            Some(node) => node,
+            None => return,
+        };
+        let focus_offset = self.FocusOffset();
+
+        if focus_node.is::<Text>() {
+            if forward && focus_offset < focus_node.len() {
+                return self.extend_to(&focus_node, focus_offset + 1);
+            }
+            if !forward && focus_offset > 0 {
+                return self.extend_to(&focus_node, focus_offset - 1);
+            }
+        }
+
+        // Step into the adjacent non-empty text node, past its first or last character.
+        let root = self.document.upcast::<Node>();
+        let is_non_empty_text = |node: &DomRoot<Node>| node.is::<Text>() && node.len() > 0;
+        if forward {
+            let next = match focus_node.children().nth(focus_offset as usize) {
+                Some(child) => {
+                    iter::once(child.clone()).chain(child.following_nodes(root)).find(is_non_empty_text)
+                },
+                None => {
+                    let mut following = focus_node.following_nodes(root);
+                    following.next_skipping_children().into_iter().chain(following).find(is_non_empty_text)
+                },
+            };
+            if let Some(next) = next {
+                self.extend_to(&next, 1);
+            }
+        } else {
+            let previous_child = focus_offset.checked_sub(1)
+                .and_then(|index| focus_node.children().nth(index as usize));
+            let previous = match previous_child {
+                Some(child) => {
+                    let last = child.descending_last_children().last().unwrap_or(child);
+                    iter::once(last.clone()).chain(last.preceding_nodes(root)).find(is_non_empty_text)
+                },
+                None => focus_node.preceding_nodes(root).find(is_non_empty_text),
+            };
+            if let Some(previous) = previous {
+                let length = previous.len();
+                self.extend_to(&previous, length - 1);
+            }
+        }
+    }
+}
+
+impl SelectionMethods for Selection {
+    // https://w3c.github.io/selection-api/#dom-selection-anchornode
+    fn GetAnchorNode(&self) -> Option<DomRoot<Node>> {
+        self.range.get().map(|range| match self.direction.get() {
+            Direction::Backwards => range.EndContainer(),
+            _ => range.StartContainer(),
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-anchoroffset
+    fn AnchorOffset(&self) -> u32 {
+        self.range.get().map_or(0, |range| match self.direction.get() {
+            Direction::Backwards => range.EndOffset(),
+            _ => range.StartOffset(),
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusnode
+    fn GetFocusNode(&self) -> Option<DomRoot<Node>> {
+        self.range.get().map(|range| match self.direction.get() {
+            Direction::Backwards => range.StartContainer(),
+            _ => range.EndContainer(),
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusoffset
+    fn FocusOffset(&self) -> u32 {
+        self.range.get().map_or(0, |range| match self.direction.get() {
+            Direction::Backwards => range.StartOffset(),
+            _ => range.EndOffset(),
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-iscollapsed
+    fn IsCollapsed(&self) -> bool {
+        self.range.get().map_or(true, |range| range.Collapsed())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-rangecount
+    fn RangeCount(&self) -> u32 {
+        if self.range.get().is_some() { 1 } else { 0 }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-type
+    fn Type(&self) -> DOMString {
+        DOMString::from(match self.range.get() {
+            None => "None",
+            Some(ref range) if range.Collapsed() => "Caret",
+            Some(_) => "Range",
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-getrangeat
+    fn GetRangeAt(&self, index: u32) -> Fallible<DomRoot<Range>> {
+        match self.range.get() {
+            Some(range) if index == 0 => Ok(range),
+            _ => Err(Error::IndexSize),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-addrange
+    fn AddRange(&self, range: &Range) {
+        // Step 1.
+        if !self.is_in_document(&range.StartContainer()) {
+            return;
+        }
+        // Step 2.
+        if self.range.get().is_some() {
+            return;
+        }
+        // Step 3.
+        self.set_range(Some(range), Direction::Directionless);
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removerange
+    fn RemoveRange(&self, range: &Range) -> ErrorResult {
+        match self.range.get() {
+            Some(ref current) if &**current == range => {
+                self.set_range(None, Direction::Directionless);
+                Ok(())
+            },
+            _ => Err(Error::NotFound),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removeallranges
+    fn RemoveAllRanges(&self) {
+        if self.range.get().is_some() {
+            self.set_range(None, Direction::Directionless);
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-empty
+    fn Empty(&self) {
+        self.RemoveAllRanges();
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapse
+    fn Collapse(&self, node: Option<&Node>, offset: u32) -> ErrorResult {
+        // Step 1.
+        let node = match node {
+            Some(node) => node,
+            None => {
+                self.RemoveAllRanges();
+                return Ok(());
+            },
+        };
+        // Step 2.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        // Step 3.
+        if offset > node.len() {
+            return Err(Error::IndexSize);
+        }
+        // Step 4.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+        // Step 5-7.
+        self.set_new_range((node, offset), (node, offset), Direction::Directionless);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setposition
+    fn SetPosition(&self, node: Option<&Node>, offset: u32) -> ErrorResult {
+        self.Collapse(node, offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetostart
+    fn CollapseToStart(&self) -> ErrorResult {
+        // Step 1.
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        // Step 2-3.
+        let start = range.StartContainer();
+        self.set_new_range((&start, range.StartOffset()),
+                           (&start, range.StartOffset()),
+                           Direction::Directionless);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetoend
+    fn CollapseToEnd(&self) -> ErrorResult {
+        // Step 1.
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        // Step 2-3.
+        let end = range.EndContainer();
+        self.set_new_range((&end, range.EndOffset()),
+                           (&end, range.EndOffset()),
+                           Direction::Directionless);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-extend
+    fn Extend(&self, node: &Node, offset: u32) -> ErrorResult {
+        // Step 1.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+        // Step 2.
+        if self.range.get().is_none() {
+            return Err(Error::InvalidState);
+        }
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        if offset > node.len() {
+            return Err(Error::IndexSize);
+        }
+        // Step 3-4.
+        let anchor_node = self.GetAnchorNode().unwrap();
+        let anchor_offset = self.AnchorOffset();
+        // Step 5-8.
+        let anchor = Range::new(&self.document, &anchor_node, anchor_offset, &anchor_node, anchor_offset);
+        if anchor.ComparePoint(node, offset)? < 0 {
+            self.set_new_range((node, offset), (&anchor_node, anchor_offset), Direction::Backwards);
+        } else {
+            self.set_new_range((&anchor_node, anchor_offset), (node, offset), Direction::Forwards);
+        }
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setbaseandextent
+    fn SetBaseAndExtent(&self,
+                        anchor_node: &Node,
+                        anchor_offset: u32,
+                        focus_node: &Node,
+                        focus_offset: u32)
+                        -> ErrorResult {
+        // Step 1.
+        if anchor_offset > anchor_node.len() || focus_offset > focus_node.len() {
+            return Err(Error::IndexSize);
+        }
+        if anchor_node.is_doctype() || focus_node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        // Step 2.
+        if !self.is_in_document(anchor_node) || !self.is_in_document(focus_node) {
+            return Ok(());
+        }
+        // Step 3-6.
+        let anchor = Range::new(&self.document, anchor_node, anchor_offset, anchor_node, anchor_offset);
+        if anchor.ComparePoint(focus_node, focus_offset)? < 0 {
+            self.set_new_range((focus_node, focus_offset),
+                               (anchor_node, anchor_offset),
+                               Direction::Backwards);
+        } else {
+            self.set_new_range((anchor_node, anchor_offset),
+                               (focus_node, focus_offset),
+                               Direction::Forwards);
+        }
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-selectallchildren
+    fn SelectAllChildren(&self, node: &Node) -> ErrorResult {
+        // Step 1.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        // Step 2.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+        // Step 3-6.
+        let child_count = node.children_count();
+        self.set_new_range((node, 0), (node, child_count), Direction::Forwards);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-deletefromdocument
+    fn DeleteFromDocument(&self) -> ErrorResult {
+        match self.range.get() {
+            Some(range) => range.DeleteContents(),
+            None => Ok(()),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-containsnode
+    fn ContainsNode(&self, node: &Node, allow_partial_containment: bool) -> bool {
+        // Step 1.
+        if !self.is_in_document(node) {
+            return false;
+        }
+        let range = match self.range.get() {
+            Some(range) => range,
+            None => return false,
+        };
+        // Step 2.
+        let first = match range.ComparePoint(node, 0) {
+            Ok(position) => position,
+            Err(_) => return false,
+        };
+        let last = match range.ComparePoint(node, node.len()) {
+            Ok(position) => position,
+            Err(_) => return false,
+        };
+        if allow_partial_containment {
+            first <= 0 && last >= 0
+        } else {
+            first == 0 && last == 0
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-stringifier
+    fn Stringifier(&self) -> DOMString {
+        self.range.get().map_or(DOMString::new(), |range| range.Stringifier())
+    }
+}
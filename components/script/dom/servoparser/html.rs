@@ -17,7 +17,7 @@ use dom::htmltemplateelement::HTMLTemplateElement;
 use dom::node::Node;
 use dom::processinginstruction::ProcessingInstruction;
 use dom::servoparser::{ParsingAlgorithm, Sink};
-use html5ever::QualName;
+use html5ever::{LocalName, QualName};
 use html5ever::buffer_queue::BufferQueue;
 use html5ever::serialize::{AttrRef, Serialize, Serializer};
 use html5ever::serialize::TraversalScope;
@@ -48,6 +48,7 @@ impl Tokenizer {
             current_line: 1,
             script: Default::default(),
             parsing_algorithm: parsing_algorithm,
+            has_parse_error: false,
         };
 
         let options = TreeBuilderOpts {
@@ -120,12 +121,21 @@ unsafe impl JSTraceable for HtmlTokenizer<TreeBuilder<Dom<Node>, Sink>> {
 fn start_element<S: Serializer>(node: &Element, serializer: &mut S) -> io::Result<()> {
     let name = QualName::new(None, node.namespace().clone(),
                              node.local_name().clone());
-    let attrs = node.attrs().iter().map(|attr| {
+    let mut attrs = node.attrs().iter().map(|attr| {
         let qname = QualName::new(None, attr.namespace().clone(),
                                   attr.local_name().clone());
-        let value = attr.value().clone();
+        let value = String::from(&**attr.value());
         (qname, value)
     }).collect::<Vec<_>>();
+    // The is value of a customized built-in element created with
+    // `createElement(name, { is })` has no attribute backing it, but still
+    // needs to round-trip.
+    if let Some(is) = node.get_is() {
+        let is_name = LocalName::from("is");
+        if !node.has_attribute(&is_name) {
+            attrs.insert(0, (QualName::new(None, ns!(), is_name), String::from(&*is)));
+        }
+    }
     let attr_refs = attrs.iter().map(|&(ref qname, ref value)| {
         let ar: AttrRef = (&qname, &**value);
         ar
@@ -239,7 +249,7 @@ impl<'a> Serialize for &'a Node {
                             serializer.write_processing_instruction(&pi.target(), &data)?;
                         },
 
-                        NodeTypeId::DocumentFragment => {}
+                        NodeTypeId::DocumentFragment(_) => {}
 
                         NodeTypeId::Document(_) => panic!("Can't serialize Document node itself"),
                         NodeTypeId::Element(_) => panic!("Element shouldn't appear here"),
@@ -9,6 +9,7 @@ use dom::bindings::codegen::Bindings::HTMLImageElementBinding::HTMLImageElementM
 use dom::bindings::codegen::Bindings::HTMLTemplateElementBinding::HTMLTemplateElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::codegen::Bindings::ServoParserBinding;
+use dom::bindings::error::{Error, Fallible};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::{Reflector, reflect_dom_object};
@@ -26,12 +27,13 @@ use dom::htmlimageelement::HTMLImageElement;
 use dom::htmlscriptelement::{HTMLScriptElement, ScriptResult};
 use dom::htmltemplateelement::HTMLTemplateElement;
 use dom::node::Node;
+use dom::performanceresourcetiming::InitiatorType;
 use dom::processinginstruction::ProcessingInstruction;
 use dom::text::Text;
 use dom::virtualmethods::vtable_for;
 use dom_struct::dom_struct;
 use embedder_traits::resources::{self, Resource};
-use html5ever::{Attribute, ExpandedName, LocalName, QualName};
+use html5ever::{Attribute, ExpandedName, LocalName, Namespace, QualName};
 use html5ever::buffer_queue::BufferQueue;
 use html5ever::tendril::{StrTendril, ByteTendril, IncompleteUtf8};
 use html5ever::tree_builder::{NodeOrText, TreeSink, NextParserState, QuirksMode, ElementFlags};
@@ -40,7 +42,7 @@ use hyper::mime::{Mime, SubLevel, TopLevel};
 use hyper_serde::Serde;
 use msg::constellation_msg::PipelineId;
 use net_traits::{FetchMetadata, FetchResponseListener, Metadata, NetworkError};
-use network_listener::PreInvoke;
+use network_listener::{PreInvoke, ResourceTimingRecorder};
 use profile_traits::time::{TimerMetadata, TimerMetadataFrameType};
 use profile_traits::time::{TimerMetadataReflowType, ProfilerCategory, profile};
 use script_thread::ScriptThread;
@@ -54,7 +56,7 @@ use style::context::QuirksMode as ServoQuirksMode;
 
 mod async_html;
 mod html;
-mod xml;
+pub mod xml;
 
 #[dom_struct]
 /// The parser maintains two input streams: one for input from script through
@@ -194,6 +196,84 @@ impl ServoParser {
         }
     }
 
+    // https://w3c.github.io/DOM-Parsing/#dfn-xml-fragment-parsing-algorithm
+    pub fn parse_xml_fragment(context: &Element, input: DOMString) -> Fallible<impl Iterator<Item=DomRoot<Node>>> {
+        let context_node = context.upcast::<Node>();
+        let context_document = context_node.owner_doc();
+        let window = context_document.window();
+        let url = context_document.url();
+
+        let loader = DocumentLoader::new_with_threads(context_document.loader().resource_threads().clone(),
+                                                      Some(url.clone()));
+        let document = Document::new(window,
+                                     HasBrowsingContext::No,
+                                     Some(url.clone()),
+                                     context_document.origin().clone(),
+                                     IsHTMLDocument::NonHTMLDocument,
+                                     None,
+                                     None,
+                                     DocumentActivity::Inactive,
+                                     DocumentSource::FromParser,
+                                     loader,
+                                     None,
+                                     None,
+                                     Default::default());
+
+        // Step 2.
+        let mut markup = String::from("<root");
+        for (prefix, namespace) in in_scope_namespaces(context) {
+            markup.push_str(" xmlns");
+            if let Some(prefix) = prefix {
+                markup.push(':');
+                markup.push_str(&prefix);
+            }
+            markup.push_str("=\"");
+            for c in namespace.chars() {
+                match c {
+                    '&' => markup.push_str("&amp;"),
+                    '<' => markup.push_str("&lt;"),
+                    '"' => markup.push_str("&quot;"),
+                    c => markup.push(c),
+                }
+            }
+            markup.push('"');
+        }
+        markup.push('>');
+
+        // Step 3.
+        markup.push_str(&input);
+
+        // Step 4.
+        markup.push_str("</root>");
+
+        let parser = ServoParser::new(&document,
+                                      Tokenizer::Xml(self::xml::Tokenizer::new(&document,
+                                                                               url,
+                                                                               ParsingAlgorithm::Fragment)),
+                                      LastChunkState::Received,
+                                      ParserKind::Normal);
+        parser.parse_string_chunk(markup);
+
+        // Step 5.
+        let has_parse_error = match *parser.tokenizer.borrow() {
+            Tokenizer::Xml(ref tokenizer) => tokenizer.has_parse_error(),
+            _ => unreachable!(),
+        };
+        if has_parse_error {
+            return Err(Error::Syntax);
+        }
+
+        // Steps 6-7.
+        let root_element = document.GetDocumentElement().ok_or(Error::Syntax)?;
+        let root_node = root_element.upcast::<Node>();
+        if root_node.GetPreviousSibling().is_some() || root_node.GetNextSibling().is_some() {
+            return Err(Error::Syntax);
+        }
+        Ok(FragmentParsingResult {
+            inner: root_node.children(),
+        })
+    }
+
     pub fn parse_html_script_input(document: &Document, url: ServoUrl, type_: &str) {
         let parser = ServoParser::new(
             document,
@@ -215,7 +295,7 @@ impl ServoParser {
 
     pub fn parse_xml_document(document: &Document, input: DOMString, url: ServoUrl) {
         let parser = ServoParser::new(document,
-                                      Tokenizer::Xml(self::xml::Tokenizer::new(document, url)),
+                                      Tokenizer::Xml(self::xml::Tokenizer::new(document, url, ParsingAlgorithm::Normal)),
                                       LastChunkState::NotReceived,
                                       ParserKind::Normal);
         parser.parse_string_chunk(String::from(input));
@@ -599,6 +679,8 @@ pub struct ParserContext {
     id: PipelineId,
     /// The URL for this document.
     url: ServoUrl,
+    /// The timing of the fetch, handed to the document once it has been received.
+    resource_timing: ResourceTimingRecorder,
 }
 
 impl ParserContext {
@@ -607,7 +689,8 @@ impl ParserContext {
             parser: None,
             is_synthesized_document: false,
             id: id,
-            url: url,
+            url: url.clone(),
+            resource_timing: ResourceTimingRecorder::new(url, InitiatorType::Navigation),
         }
     }
 }
@@ -618,6 +701,7 @@ impl FetchResponseListener for ParserContext {
     fn process_request_eof(&mut self) {}
 
     fn process_response(&mut self, meta_result: Result<FetchMetadata, NetworkError>) {
+        self.resource_timing.record_response(&meta_result);
         let mut ssl_error = None;
         let mut network_error = None;
         let metadata = match meta_result {
@@ -713,6 +797,7 @@ impl FetchResponseListener for ParserContext {
     }
 
     fn process_response_chunk(&mut self, payload: Vec<u8>) {
+        self.resource_timing.record_chunk(&payload);
         if self.is_synthesized_document {
             return;
         }
@@ -740,6 +825,8 @@ impl FetchResponseListener for ParserContext {
             debug!("Failed to load page URL {}, error: {:?}", self.url, err);
         }
 
+        parser.document.set_navigation_fetch_timing(self.resource_timing.finish());
+
         parser.last_chunk_received.set(true);
         if !parser.suspended.get() {
             parser.parse_sync();
@@ -754,6 +841,36 @@ pub struct FragmentContext<'a> {
     pub form_elem: Option<&'a Node>,
 }
 
+/// The namespace declarations in scope on `context`, as `(prefix, namespace)`
+/// pairs with `None` standing for the default namespace.
+fn in_scope_namespaces(context: &Element) -> Vec<(Option<String>, Namespace)> {
+    let mut seen = vec![];
+    let mut declarations = vec![];
+    {
+        let mut declare = |prefix: Option<String>, namespace: Namespace| {
+            if seen.contains(&prefix) {
+                return;
+            }
+            seen.push(prefix.clone());
+            if prefix.as_ref().map_or(false, |p| p == "xml" || p == "xmlns") || namespace == ns!() {
+                return;
+            }
+            declarations.push((prefix, namespace));
+        };
+        for element in context.upcast::<Node>().inclusive_ancestors().filter_map(DomRoot::downcast::<Element>) {
+            declare(element.prefix().as_ref().map(|p| p.to_string()), element.namespace().clone());
+            for attr in element.attrs().iter() {
+                if *attr.namespace() != ns!(xmlns) {
+                    continue;
+                }
+                let prefix = attr.prefix().map(|_| attr.local_name().to_string());
+                declare(prefix, Namespace::from(&**attr.value()));
+            }
+        }
+    }
+    declarations
+}
+
 #[allow(unrooted_must_root)]
 fn insert(parent: &Node, reference_child: Option<&Node>, child: NodeOrText<Dom<Node>>) {
     match child {
@@ -784,6 +901,7 @@ pub struct Sink {
     current_line: u64,
     script: MutNullableDom<HTMLScriptElement>,
     parsing_algorithm: ParsingAlgorithm,
+    has_parse_error: bool,
 }
 
 impl Sink {
@@ -893,6 +1011,7 @@ impl TreeSink for Sink {
 
     fn parse_error(&mut self, msg: Cow<'static, str>) {
         debug!("Parse error: {}", msg);
+        self.has_parse_error = true;
     }
 
     fn set_quirks_mode(&mut self, mode: QuirksMode) {
@@ -4,15 +4,29 @@
 
 #![allow(unrooted_must_root)]
 
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::HTMLTemplateElementBinding::HTMLTemplateElementMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::{Castable, CharacterDataTypeId, NodeTypeId};
 use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
 use dom::bindings::trace::JSTraceable;
+use dom::bindings::xmlname::{XMLName, xml_name_type};
+use dom::characterdata::CharacterData;
 use dom::document::Document;
+use dom::documenttype::DocumentType;
+use dom::element::Element;
 use dom::htmlscriptelement::HTMLScriptElement;
+use dom::htmltemplateelement::HTMLTemplateElement;
 use dom::node::Node;
+use dom::processinginstruction::ProcessingInstruction;
 use dom::servoparser::{ParsingAlgorithm, Sink};
+use html5ever::Namespace;
 use js::jsapi::JSTracer;
 use servo_url::ServoUrl;
+use std::collections::HashMap;
 use xml5ever::buffer_queue::BufferQueue;
+use xml5ever::serialize::TraversalScope;
 use xml5ever::tokenizer::XmlTokenizer;
 use xml5ever::tree_builder::{Tracer as XmlTracer, XmlTreeBuilder};
 
@@ -24,13 +38,14 @@ pub struct Tokenizer {
 }
 
 impl Tokenizer {
-    pub fn new(document: &Document, url: ServoUrl) -> Self {
+    pub fn new(document: &Document, url: ServoUrl, parsing_algorithm: ParsingAlgorithm) -> Self {
         let sink = Sink {
             base_url: url,
             document: Dom::from_ref(document),
             current_line: 1,
             script: Default::default(),
-            parsing_algorithm: ParsingAlgorithm::Normal,
+            parsing_algorithm: parsing_algorithm,
+            has_parse_error: false,
         };
 
         let tb = XmlTreeBuilder::new(sink, Default::default());
@@ -65,6 +80,11 @@ impl Tokenizer {
     pub fn url(&self) -> &ServoUrl {
         &self.inner.sink.sink.base_url
     }
+
+    /// Whether a well-formedness error has been reported while parsing.
+    pub fn has_parse_error(&self) -> bool {
+        self.inner.sink.sink.has_parse_error
+    }
 }
 
 #[allow(unsafe_code)]
@@ -86,3 +106,436 @@ unsafe impl JSTraceable for XmlTokenizer<XmlTreeBuilder<Dom<Node>, Sink>> {
         tree_builder.sink.trace(trc);
     }
 }
+
+/// A namespace prefix map, associating each namespace with the list of
+/// prefixes that are bound to it.
+///
+/// <https://w3c.github.io/DOM-Parsing/#the-namespace-prefix-map>
+#[derive(Clone)]
+struct NamespacePrefixMap(HashMap<Namespace, Vec<String>>);
+
+impl NamespacePrefixMap {
+    fn new() -> NamespacePrefixMap {
+        let mut map = NamespacePrefixMap(HashMap::new());
+        map.add("xml", &ns!(xml));
+        map
+    }
+
+    /// <https://w3c.github.io/DOM-Parsing/#dfn-found>
+    fn found(&self, prefix: &str, namespace: &Namespace) -> bool {
+        self.0.get(namespace).map_or(false, |prefixes| prefixes.iter().any(|p| p == prefix))
+    }
+
+    /// <https://w3c.github.io/DOM-Parsing/#dfn-add>
+    fn add(&mut self, prefix: &str, namespace: &Namespace) {
+        self.0.entry(namespace.clone()).or_insert_with(Vec::new).push(prefix.to_owned());
+    }
+
+    /// <https://w3c.github.io/DOM-Parsing/#dfn-retrieving-a-preferred-prefix-string>
+    fn preferred_prefix(&self, preferred: Option<&str>, namespace: &Namespace) -> Option<String> {
+        let candidates = self.0.get(namespace)?;
+        if let Some(preferred) = preferred {
+            if candidates.iter().any(|p| p == preferred) {
+                return Some(preferred.to_owned());
+            }
+        }
+        candidates.last().cloned()
+    }
+}
+
+/// The state shared by a single run of the XML serialization algorithm.
+/// The well-formedness checks bail out with `Err(())`.
+///
+/// <https://w3c.github.io/DOM-Parsing/#dfn-xml-serialization>
+struct XmlSerializer {
+    markup: String,
+    require_well_formed: bool,
+    prefix_index: usize,
+}
+
+/// <https://w3c.github.io/DOM-Parsing/#dfn-producing-an-xml-serialization>
+pub fn serialize(node: &Node, traversal_scope: TraversalScope, require_well_formed: bool) -> Fallible<DOMString> {
+    let mut serializer = XmlSerializer {
+        markup: String::new(),
+        require_well_formed: require_well_formed,
+        prefix_index: 1,
+    };
+    let map = NamespacePrefixMap::new();
+    let result = match traversal_scope {
+        TraversalScope::IncludeNode => serializer.serialize_node(node, &ns!(), &map),
+        TraversalScope::ChildrenOnly(_) => serializer.serialize_children(node, &ns!(), &map),
+    };
+    match result {
+        Ok(()) => Ok(DOMString::from(serializer.markup)),
+        Err(()) => Err(Error::InvalidState),
+    }
+}
+
+impl XmlSerializer {
+    /// <https://w3c.github.io/DOM-Parsing/#dfn-xml-serialization-algorithm>
+    fn serialize_node(&mut self, node: &Node, namespace: &Namespace, map: &NamespacePrefixMap) -> Result<(), ()> {
+        match node.type_id() {
+            NodeTypeId::Element(_) => {
+                self.serialize_element(node.downcast::<Element>().unwrap(), namespace, map)
+            },
+            NodeTypeId::Document(_) => {
+                if self.require_well_formed && node.downcast::<Document>().unwrap().GetDocumentElement().is_none() {
+                    return Err(());
+                }
+                self.serialize_children(node, namespace, map)
+            },
+            NodeTypeId::DocumentFragment(_) => self.serialize_children(node, namespace, map),
+            NodeTypeId::CharacterData(CharacterDataTypeId::Comment) => {
+                let data = node.downcast::<CharacterData>().unwrap().data();
+                if self.require_well_formed &&
+                    (!data.chars().all(is_xml_char) || data.contains("--") || data.ends_with('-')) {
+                    return Err(());
+                }
+                self.markup.push_str("<!--");
+                self.markup.push_str(&data);
+                self.markup.push_str("-->");
+                Ok(())
+            },
+            NodeTypeId::CharacterData(CharacterDataTypeId::Text) => {
+                let data = node.downcast::<CharacterData>().unwrap().data();
+                if self.require_well_formed && !data.chars().all(is_xml_char) {
+                    return Err(());
+                }
+                escape(&mut self.markup, &data, false);
+                Ok(())
+            },
+            NodeTypeId::CharacterData(CharacterDataTypeId::ProcessingInstruction) => {
+                let pi = node.downcast::<ProcessingInstruction>().unwrap();
+                let target = pi.target();
+                let data = pi.upcast::<CharacterData>().data();
+                if self.require_well_formed {
+                    if target.contains(':') || target.eq_ignore_ascii_case("xml") {
+                        return Err(());
+                    }
+                    if !data.chars().all(is_xml_char) || data.contains("?>") {
+                        return Err(());
+                    }
+                }
+                self.markup.push_str("<?");
+                self.markup.push_str(&target);
+                self.markup.push(' ');
+                self.markup.push_str(&data);
+                self.markup.push_str("?>");
+                Ok(())
+            },
+            NodeTypeId::DocumentType => {
+                let doctype = node.downcast::<DocumentType>().unwrap();
+                let public_id = doctype.public_id();
+                let system_id = doctype.system_id();
+                if self.require_well_formed {
+                    if !public_id.chars().all(is_pubid_char) {
+                        return Err(());
+                    }
+                    if !system_id.chars().all(is_xml_char) ||
+                        (system_id.contains('"') && system_id.contains('\'')) {
+                        return Err(());
+                    }
+                }
+                self.markup.push_str("<!DOCTYPE ");
+                self.markup.push_str(&doctype.name());
+                if !public_id.is_empty() {
+                    self.markup.push_str(" PUBLIC \"");
+                    self.markup.push_str(&public_id);
+                    self.markup.push('"');
+                }
+                if !system_id.is_empty() {
+                    if public_id.is_empty() {
+                        self.markup.push_str(" SYSTEM");
+                    }
+                    self.markup.push_str(" \"");
+                    self.markup.push_str(&system_id);
+                    self.markup.push('"');
+                }
+                self.markup.push('>');
+                Ok(())
+            },
+        }
+    }
+
+    fn serialize_children(&mut self, node: &Node, namespace: &Namespace, map: &NamespacePrefixMap) -> Result<(), ()> {
+        // https://github.com/w3c/DOM-Parsing/issues/1
+        let node = match node.downcast::<HTMLTemplateElement>() {
+            Some(template) => DomRoot::upcast(template.Content()),
+            None => DomRoot::from_ref(node),
+        };
+        for child in node.children() {
+            self.serialize_node(&child, namespace, map)?;
+        }
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/DOM-Parsing/#xml-serializing-an-element-node>
+    fn serialize_element(&mut self, element: &Element, namespace: &Namespace, map: &NamespacePrefixMap) -> Result<(), ()> {
+        let local_name = element.local_name();
+
+        // Step 1.
+        if self.require_well_formed && !is_valid_local_name(local_name) {
+            return Err(());
+        }
+
+        // Steps 2-7.
+        self.markup.push('<');
+        let qualified_name;
+        let mut ignore_namespace_definition_attribute = false;
+        let mut map = map.clone();
+        let mut local_prefixes = HashMap::new();
+
+        // Step 8.
+        let local_default_namespace = record_namespace_information(element, &mut map, &mut local_prefixes);
+
+        // Steps 9-10.
+        let mut inherited_namespace = namespace.clone();
+        let element_namespace = element.namespace().clone();
+
+        if inherited_namespace == element_namespace {
+            // Step 11.
+            if local_default_namespace.is_some() {
+                ignore_namespace_definition_attribute = true;
+            }
+            qualified_name = if element_namespace == ns!(xml) {
+                format!("xml:{}", local_name)
+            } else {
+                local_name.to_string()
+            };
+            self.markup.push_str(&qualified_name);
+        } else {
+            // Step 12.1-12.2.
+            let mut prefix = element.prefix().as_ref().map(|prefix| prefix.to_string());
+            let mut candidate_prefix = map.preferred_prefix(prefix.as_ref().map(|p| &**p), &element_namespace);
+
+            // Step 12.3.
+            if prefix.as_ref().map_or(false, |p| p == "xmlns") {
+                if self.require_well_formed {
+                    return Err(());
+                }
+                candidate_prefix = prefix.clone();
+            }
+
+            let local_default_namespace = local_default_namespace.map(|ns| Namespace::from(&*ns));
+            if let Some(candidate_prefix) = candidate_prefix {
+                // Step 12.4.
+                qualified_name = format!("{}:{}", candidate_prefix, local_name);
+                if let Some(ref default) = local_default_namespace {
+                    if *default != ns!(xml) {
+                        inherited_namespace = default.clone();
+                    }
+                }
+                self.markup.push_str(&qualified_name);
+            } else if let Some(mut prefix) = prefix.take() {
+                // Step 12.5.
+                if local_prefixes.contains_key(&prefix) {
+                    prefix = self.generate_prefix(&mut map, &element_namespace);
+                } else {
+                    map.add(&prefix, &element_namespace);
+                }
+                qualified_name = format!("{}:{}", prefix, local_name);
+                self.markup.push_str(&qualified_name);
+                self.markup.push_str(" xmlns:");
+                self.markup.push_str(&prefix);
+                self.markup.push_str("=\"");
+                self.serialize_attribute_value(&element_namespace)?;
+                self.markup.push('"');
+                if let Some(default) = local_default_namespace {
+                    inherited_namespace = default;
+                }
+            } else if local_default_namespace.as_ref() != Some(&element_namespace) {
+                // Step 12.6.
+                ignore_namespace_definition_attribute = true;
+                qualified_name = local_name.to_string();
+                inherited_namespace = element_namespace.clone();
+                self.markup.push_str(&qualified_name);
+                self.markup.push_str(" xmlns=\"");
+                self.serialize_attribute_value(&element_namespace)?;
+                self.markup.push('"');
+            } else {
+                // Step 12.7.
+                qualified_name = local_name.to_string();
+                inherited_namespace = element_namespace.clone();
+                self.markup.push_str(&qualified_name);
+            }
+        }
+
+        // Step 13.
+        self.serialize_attributes(element, &mut map, &local_prefixes, ignore_namespace_definition_attribute)?;
+
+        // Steps 14-16.
+        let node = element.upcast::<Node>();
+        let is_empty = node.children_count() == 0;
+        if element_namespace == ns!(html) {
+            if is_empty && element.is_void() {
+                self.markup.push_str(" />");
+                return Ok(());
+            }
+        } else if is_empty {
+            self.markup.push_str("/>");
+            return Ok(());
+        }
+        self.markup.push('>');
+
+        // Steps 17-19.
+        self.serialize_children(node, &inherited_namespace, &map)?;
+
+        // Step 20.
+        self.markup.push_str("</");
+        self.markup.push_str(&qualified_name);
+        self.markup.push('>');
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/DOM-Parsing/#dfn-serializing-an-element-s-attributes>
+    fn serialize_attributes(&mut self,
+                            element: &Element,
+                            map: &mut NamespacePrefixMap,
+                            local_prefixes: &HashMap<String, Namespace>,
+                            ignore_namespace_definition_attribute: bool)
+                            -> Result<(), ()> {
+        let mut localname_set = vec![];
+        for attr in element.attrs().iter() {
+            let attr_namespace = attr.namespace();
+            let attr_local_name = attr.local_name();
+            let attr_prefix = attr.prefix().map(|prefix| &**prefix);
+            let attr_value = attr.value();
+
+            // Steps 3.1-3.2.
+            if self.require_well_formed &&
+                localname_set.iter().any(|&(ref ns, ref name)| ns == attr_namespace && name == attr_local_name) {
+                return Err(());
+            }
+            localname_set.push((attr_namespace.clone(), attr_local_name.clone()));
+
+            // Steps 3.3-3.5.
+            let mut candidate_prefix = None;
+            if *attr_namespace != ns!() {
+                candidate_prefix = map.preferred_prefix(attr_prefix, attr_namespace);
+                if *attr_namespace == ns!(xmlns) {
+                    let value = &**attr_value;
+                    let redundant_prefix = attr_prefix.is_some() &&
+                        local_prefixes.get(&**attr_local_name).map_or(true, |ns| &**ns != value);
+                    if value == &*ns!(xml) ||
+                        (attr_prefix.is_none() && ignore_namespace_definition_attribute) ||
+                        redundant_prefix {
+                        continue;
+                    }
+                    if self.require_well_formed && (value == &*ns!(xmlns) || value.is_empty()) {
+                        return Err(());
+                    }
+                    if attr_prefix == Some("xmlns") {
+                        candidate_prefix = Some("xmlns".to_owned());
+                    }
+                } else if candidate_prefix.is_none() {
+                    let prefix = self.generate_prefix(map, attr_namespace);
+                    self.markup.push_str(" xmlns:");
+                    self.markup.push_str(&prefix);
+                    self.markup.push_str("=\"");
+                    self.serialize_attribute_value(attr_namespace)?;
+                    self.markup.push('"');
+                    candidate_prefix = Some(prefix);
+                }
+            }
+
+            // Steps 3.6-3.9.
+            self.markup.push(' ');
+            if let Some(prefix) = candidate_prefix {
+                self.markup.push_str(&prefix);
+                self.markup.push(':');
+            }
+            if self.require_well_formed &&
+                (!is_valid_local_name(attr_local_name) ||
+                 (*attr_local_name == local_name!("xmlns") && *attr_namespace == ns!())) {
+                return Err(());
+            }
+            self.markup.push_str(attr_local_name);
+            self.markup.push_str("=\"");
+            self.serialize_attribute_value(&attr_value)?;
+            self.markup.push('"');
+        }
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/DOM-Parsing/#dfn-serializing-an-attribute-value>
+    fn serialize_attribute_value(&mut self, value: &str) -> Result<(), ()> {
+        if self.require_well_formed && !value.chars().all(is_xml_char) {
+            return Err(());
+        }
+        escape(&mut self.markup, value, true);
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/DOM-Parsing/#dfn-generating-a-prefix>
+    fn generate_prefix(&mut self, map: &mut NamespacePrefixMap, namespace: &Namespace) -> String {
+        let prefix = format!("ns{}", self.prefix_index);
+        self.prefix_index += 1;
+        map.add(&prefix, namespace);
+        prefix
+    }
+}
+
+/// <https://w3c.github.io/DOM-Parsing/#dfn-recording-the-namespace-information>
+fn record_namespace_information(element: &Element,
+                                map: &mut NamespacePrefixMap,
+                                local_prefixes: &mut HashMap<String, Namespace>)
+                                -> Option<String> {
+    let mut default_namespace_attr_value = None;
+    for attr in element.attrs().iter() {
+        if *attr.namespace() != ns!(xmlns) {
+            continue;
+        }
+        let value = attr.value();
+        if attr.prefix().is_none() {
+            default_namespace_attr_value = Some(String::from(&**value));
+            continue;
+        }
+        let prefix_definition = &**attr.local_name();
+        let namespace_definition = Namespace::from(&**value);
+        if namespace_definition == ns!(xml) || map.found(prefix_definition, &namespace_definition) {
+            continue;
+        }
+        map.add(prefix_definition, &namespace_definition);
+        local_prefixes.insert(prefix_definition.to_owned(), namespace_definition);
+    }
+    default_namespace_attr_value
+}
+
+fn escape(markup: &mut String, value: &str, attr_mode: bool) {
+    for c in value.chars() {
+        match c {
+            '&' => markup.push_str("&amp;"),
+            '<' => markup.push_str("&lt;"),
+            '>' => markup.push_str("&gt;"),
+            '"' if attr_mode => markup.push_str("&quot;"),
+            '\t' if attr_mode => markup.push_str("&#9;"),
+            '\n' if attr_mode => markup.push_str("&#10;"),
+            '\r' if attr_mode => markup.push_str("&#13;"),
+            c => markup.push(c),
+        }
+    }
+}
+
+fn is_valid_local_name(name: &str) -> bool {
+    !name.contains(':') && xml_name_type(name) != XMLName::InvalidXMLName
+}
+
+/// <https://www.w3.org/TR/xml/#NT-Char>
+fn is_xml_char(c: char) -> bool {
+    match c {
+        '\u{9}' | '\u{A}' | '\u{D}' |
+        '\u{20}'...'\u{D7FF}' |
+        '\u{E000}'...'\u{FFFD}' |
+        '\u{10000}'...'\u{10FFFF}' => true,
+        _ => false,
+    }
+}
+
+/// <https://www.w3.org/TR/xml/#NT-PubidChar>
+fn is_pubid_char(c: char) -> bool {
+    match c {
+        '\u{20}' | '\u{D}' | '\u{A}' |
+        'a'...'z' | 'A'...'Z' | '0'...'9' => true,
+        _ => "-'()+,./:=?;!*#@$_%".contains(c),
+    }
+}
@@ -0,0 +1,236 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ShadowRootBinding;
+use dom::bindings::codegen::Bindings::ShadowRootBinding::{ShadowRootMethods, ShadowRootMode};
+use dom::bindings::error::{ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::bindings::str::DOMString;
+use dom::document::{Document, StyleSheetInDocument};
+use dom::documentfragment::DocumentFragment;
+use dom::element::Element;
+use dom::node::{Node, NodeDamage, NodeFlags, document_from_node};
+use dom::servoparser::xml;
+use dom_struct::dom_struct;
+use html5ever::serialize::{SerializeOpts, serialize};
+use html5ever::serialize::TraversalScope::ChildrenOnly;
+use servo_arc::Arc;
+use style::author_styles::AuthorStyles;
+use style::context::QuirksMode;
+use style::dom::TElement;
+use style::media_queries::Device;
+use style::shared_lock::SharedRwLockReadGuard;
+use style::stylesheets::Stylesheet;
+use style::stylist::CascadeData;
+use xml5ever::serialize::TraversalScope::ChildrenOnly as XmlChildrenOnly;
+
+/// <https://dom.spec.whatwg.org/#interface-shadowroot>
+#[dom_struct]
+pub struct ShadowRoot {
+    document_fragment: DocumentFragment,
+    host: Dom<Element>,
+    mode: ShadowRootMode,
+    /// The stylesheets owned by style and link elements in this shadow tree,
+    /// in tree order, and the cascade data layout computes from them.
+    #[ignore_malloc_size_of = "Arc"]
+    author_styles: DomRefCell<AuthorStyles<StyleSheetInDocument>>,
+}
+
+impl ShadowRoot {
+    fn new_inherited(host: &Element, document: &Document, mode: ShadowRootMode) -> ShadowRoot {
+        let document_fragment = DocumentFragment::new_inherited(document);
+        {
+            let node = document_fragment.upcast::<Node>();
+            node.set_flag(NodeFlags::IS_IN_SHADOW_TREE, true);
+            node.set_flag(NodeFlags::IS_CONNECTED, host.upcast::<Node>().is_connected());
+        }
+        ShadowRoot {
+            document_fragment,
+            host: Dom::from_ref(host),
+            mode,
+            author_styles: DomRefCell::new(AuthorStyles::new()),
+        }
+    }
+
+    pub fn new(host: &Element, mode: ShadowRootMode) -> DomRoot<ShadowRoot> {
+        let document = document_from_node(host);
+        Node::reflect_node(Box::new(ShadowRoot::new_inherited(host, &document, mode)),
+                           &document,
+                           ShadowRootBinding::Wrap)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-documentfragment-host>
+    pub fn host(&self) -> DomRoot<Element> {
+        DomRoot::from_ref(&self.host)
+    }
+
+    pub fn mode(&self) -> ShadowRootMode {
+        self.mode
+    }
+
+    /// Updates the connectedness of this shadow tree, and the ones nested in
+    /// it, after its host was inserted into or removed from a document.
+    pub fn set_connected(&self, connected: bool) {
+        let document = document_from_node(self);
+        for node in self.upcast::<Node>().shadow_including_inclusive_descendants() {
+            node.set_flag(NodeFlags::IS_CONNECTED, connected);
+            node.set_flag(NodeFlags::HAS_DIRTY_DESCENDANTS, false);
+            if let Some(shadow_root) = node.downcast::<ShadowRoot>() {
+                if connected {
+                    document.register_shadow_root(shadow_root);
+                } else {
+                    document.unregister_shadow_root(shadow_root);
+                }
+            }
+        }
+        if !connected {
+            self.upcast::<Node>().teardown();
+        }
+    }
+
+    /// Add a stylesheet owned by `owner` to the list of stylesheets of this
+    /// shadow tree, in the correct tree position.
+    #[allow(unrooted_must_root)] // Owner needs to be rooted already necessarily.
+    pub fn add_stylesheet(&self, owner: &Element, sheet: Arc<Stylesheet>) {
+        let mut author_styles = self.author_styles.borrow_mut();
+        let insertion_point =
+            author_styles.stylesheets
+                .iter()
+                .find(|sheet_in_doc| {
+                    owner.upcast::<Node>().is_before(sheet_in_doc.owner.upcast())
+                }).cloned();
+
+        let document = document_from_node(self);
+        let lock = document.style_shared_lock();
+        let guard = lock.read();
+
+        let sheet = StyleSheetInDocument {
+            sheet,
+            owner: Dom::from_ref(owner),
+        };
+        match insertion_point {
+            Some(ip) => {
+                author_styles.stylesheets.insert_stylesheet_before(None, sheet, ip, &guard);
+            }
+            None => {
+                author_styles.stylesheets.append_stylesheet(None, sheet, &guard);
+            }
+        }
+        drop(author_styles);
+        self.restyle_shadow_tree();
+    }
+
+    /// Remove a stylesheet owned by `owner` from the list of stylesheets of
+    /// this shadow tree.
+    #[allow(unrooted_must_root)] // Owner needs to be rooted already necessarily.
+    pub fn remove_stylesheet(&self, owner: &Element, sheet: &Arc<Stylesheet>) {
+        let guard = sheet.shared_lock.read();
+        self.author_styles.borrow_mut().stylesheets.remove_stylesheet(
+            None,
+            StyleSheetInDocument {
+                sheet: sheet.clone(),
+                owner: Dom::from_ref(owner),
+            },
+            &guard,
+        );
+        self.restyle_shadow_tree();
+    }
+
+    /// Marks the stylesheets of this shadow tree as changed, for example
+    /// after one of them got modified through the CSSOM.
+    pub fn invalidate_stylesheets(&self) {
+        self.author_styles.borrow_mut().stylesheets.force_dirty();
+        self.restyle_shadow_tree();
+    }
+
+    /// The cascade data of this shadow tree is only rebuilt by layout, and
+    /// has no invalidation machinery of its own yet, so restyle the host and
+    /// the whole shadow tree.
+    fn restyle_shadow_tree(&self) {
+        if !self.upcast::<Node>().is_connected() {
+            return;
+        }
+        self.host.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        for node in self.upcast::<Node>().traverse_preorder() {
+            if let Some(element) = node.downcast::<Element>() {
+                element.restyle(NodeDamage::OtherNodeDamage);
+            }
+        }
+    }
+}
+
+impl ShadowRootMethods for ShadowRoot {
+    // https://dom.spec.whatwg.org/#dom-shadowroot-mode
+    fn Mode(&self) -> ShadowRootMode {
+        self.mode
+    }
+
+    // https://dom.spec.whatwg.org/#dom-shadowroot-host
+    fn Host(&self) -> DomRoot<Element> {
+        self.host()
+    }
+
+    // https://w3c.github.io/DOM-Parsing/#dom-innerhtml-innerhtml
+    fn GetInnerHTML(&self) -> Fallible<DOMString> {
+        let node = self.upcast::<Node>();
+        if !document_from_node(self).is_html_document() {
+            return xml::serialize(node, XmlChildrenOnly(None), true);
+        }
+        let mut writer = vec![];
+        match serialize(&mut writer, &node, SerializeOpts {
+            traversal_scope: ChildrenOnly(None),
+            ..Default::default()
+        }) {
+            Ok(()) => Ok(DOMString::from(String::from_utf8(writer).unwrap())),
+            Err(_) => panic!("Cannot serialize shadow root"),
+        }
+    }
+
+    // https://w3c.github.io/DOM-Parsing/#dom-innerhtml-innerhtml
+    fn SetInnerHTML(&self, value: DOMString) -> ErrorResult {
+        // Step 1. The context element is the shadow root's host.
+        let frag = self.host.parse_fragment(value)?;
+        // Step 2.
+        Node::replace_all(Some(frag.upcast()), self.upcast());
+        Ok(())
+    }
+}
+
+#[allow(unsafe_code)]
+pub trait LayoutShadowRootHelpers {
+    unsafe fn get_host_for_layout(&self) -> LayoutDom<Element>;
+    unsafe fn get_style_data_for_layout<'a>(&self) -> &'a CascadeData;
+    unsafe fn flush_stylesheets_for_layout<E: TElement>(&self,
+                                                        device: &Device,
+                                                        quirks_mode: QuirksMode,
+                                                        guard: &SharedRwLockReadGuard);
+}
+
+impl LayoutShadowRootHelpers for LayoutDom<ShadowRoot> {
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn get_host_for_layout(&self) -> LayoutDom<Element> {
+        (*self.unsafe_get()).host.to_layout()
+    }
+
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn get_style_data_for_layout<'a>(&self) -> &'a CascadeData {
+        &(*self.unsafe_get()).author_styles.borrow_for_layout().data
+    }
+
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn flush_stylesheets_for_layout<E: TElement>(&self,
+                                                        device: &Device,
+                                                        quirks_mode: QuirksMode,
+                                                        guard: &SharedRwLockReadGuard) {
+        let mut author_styles = (*self.unsafe_get()).author_styles.borrow_mut_for_layout();
+        if author_styles.stylesheets.dirty() {
+            author_styles.flush::<E>(device, quirks_mode, guard);
+        }
+    }
+}
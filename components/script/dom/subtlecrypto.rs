@@ -0,0 +1,1709 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use base64;
+use dom::bindings::codegen::Bindings::CryptoKeyBinding::{CryptoKeyPair, KeyType, KeyUsage};
+use dom::bindings::codegen::Bindings::SubtleCryptoBinding::{self, JsonWebKey, KeyFormat, SubtleCryptoMethods};
+use dom::bindings::codegen::UnionTypes::{ArrayBufferViewOrArrayBuffer, BufferSourceOrJsonWebKey};
+use dom::bindings::codegen::UnionTypes::ObjectOrString;
+use dom::bindings::conversions::{ConversionBehavior, ConversionResult, FromJSValConvertible};
+use dom::bindings::conversions::{StringificationBehavior, ToJSValConvertible, get_property, get_property_jsval};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::refcounted::TrustedPromise;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::bindings::utils::set_dictionary_property;
+use dom::cryptokey::{CryptoKey, Handle, KeyAlgorithm};
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom_struct::dom_struct;
+use js::jsapi::{JSContext, JSObject, JS_NewObject};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue};
+use js::typedarray::{ArrayBuffer, ArrayBufferView, CreateWith};
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+use openssl::ec::{self, EcGroup, EcKey, EcPoint};
+use openssl::hash::{MessageDigest, hash2};
+use openssl::memcmp;
+use openssl::nid::{self, Nid};
+use openssl::pkcs5::pbkdf2_hmac;
+use openssl::pkey::PKey;
+use openssl::rand::rand_bytes;
+use openssl::rsa::Rsa;
+use openssl::sign::{Signer, Verifier};
+use openssl::symm::{self, Cipher};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::ptr;
+use std::rc::Rc;
+use std::sync::Arc;
+use task_source::{TaskSource, TaskSourceName};
+use webcrypto::{self, der};
+
+/// The names of the algorithms this implementation recognizes.
+/// <https://w3c.github.io/webcrypto/#algorithm-overview>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum AlgorithmName {
+    AesCbc,
+    AesCtr,
+    AesGcm,
+    AesKw,
+    Ecdh,
+    Ecdsa,
+    Hkdf,
+    Hmac,
+    Pbkdf2,
+    RsaOaep,
+    RsaPss,
+    RsassaPkcs1v15,
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+const ALGORITHM_NAMES: &'static [(&'static str, AlgorithmName)] = &[
+    ("AES-CBC", AlgorithmName::AesCbc),
+    ("AES-CTR", AlgorithmName::AesCtr),
+    ("AES-GCM", AlgorithmName::AesGcm),
+    ("AES-KW", AlgorithmName::AesKw),
+    ("ECDH", AlgorithmName::Ecdh),
+    ("ECDSA", AlgorithmName::Ecdsa),
+    ("HKDF", AlgorithmName::Hkdf),
+    ("HMAC", AlgorithmName::Hmac),
+    ("PBKDF2", AlgorithmName::Pbkdf2),
+    ("RSA-OAEP", AlgorithmName::RsaOaep),
+    ("RSA-PSS", AlgorithmName::RsaPss),
+    ("RSASSA-PKCS1-v1_5", AlgorithmName::RsassaPkcs1v15),
+    ("SHA-1", AlgorithmName::Sha1),
+    ("SHA-256", AlgorithmName::Sha256),
+    ("SHA-384", AlgorithmName::Sha384),
+    ("SHA-512", AlgorithmName::Sha512),
+];
+
+impl AlgorithmName {
+    /// Algorithm names are matched case-insensitively.
+    /// <https://w3c.github.io/webcrypto/#algorithm-normalization-normalize-an-algorithm>
+    /// Step 6
+    fn from_name(name: &str) -> Fallible<AlgorithmName> {
+        ALGORITHM_NAMES.iter()
+            .find(|&&(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|&(_, algorithm)| algorithm)
+            .ok_or(Error::NotSupported)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        ALGORITHM_NAMES.iter().find(|&&(_, algorithm)| algorithm == *self).unwrap().0
+    }
+
+    fn is_aes(&self) -> bool {
+        match *self {
+            AlgorithmName::AesCbc | AlgorithmName::AesCtr | AlgorithmName::AesGcm | AlgorithmName::AesKw => true,
+            _ => false,
+        }
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#sha>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    fn from_algorithm(name: AlgorithmName) -> Fallible<DigestAlgorithm> {
+        match name {
+            AlgorithmName::Sha1 => Ok(DigestAlgorithm::Sha1),
+            AlgorithmName::Sha256 => Ok(DigestAlgorithm::Sha256),
+            AlgorithmName::Sha384 => Ok(DigestAlgorithm::Sha384),
+            AlgorithmName::Sha512 => Ok(DigestAlgorithm::Sha512),
+            _ => Err(Error::NotSupported),
+        }
+    }
+
+    fn algorithm_name(&self) -> AlgorithmName {
+        match *self {
+            DigestAlgorithm::Sha1 => AlgorithmName::Sha1,
+            DigestAlgorithm::Sha256 => AlgorithmName::Sha256,
+            DigestAlgorithm::Sha384 => AlgorithmName::Sha384,
+            DigestAlgorithm::Sha512 => AlgorithmName::Sha512,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            DigestAlgorithm::Sha1 => "SHA-1",
+            DigestAlgorithm::Sha256 => "SHA-256",
+            DigestAlgorithm::Sha384 => "SHA-384",
+            DigestAlgorithm::Sha512 => "SHA-512",
+        }
+    }
+
+    fn message_digest(&self) -> MessageDigest {
+        match *self {
+            DigestAlgorithm::Sha1 => MessageDigest::sha1(),
+            DigestAlgorithm::Sha256 => MessageDigest::sha256(),
+            DigestAlgorithm::Sha384 => MessageDigest::sha384(),
+            DigestAlgorithm::Sha512 => MessageDigest::sha512(),
+        }
+    }
+
+    /// The output length, in bytes.
+    fn output_len(&self) -> usize {
+        match *self {
+            DigestAlgorithm::Sha1 => 20,
+            DigestAlgorithm::Sha256 => 32,
+            DigestAlgorithm::Sha384 => 48,
+            DigestAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// The block size, in bytes.
+    fn block_len(&self) -> usize {
+        match *self {
+            DigestAlgorithm::Sha1 | DigestAlgorithm::Sha256 => 64,
+            DigestAlgorithm::Sha384 | DigestAlgorithm::Sha512 => 128,
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> Fallible<Vec<u8>> {
+        hash2(self.message_digest(), data).map(|digest| digest.to_vec()).map_err(|_| Error::Operation)
+    }
+
+    /// The suffix used in the JWK `alg` of HMAC, RSA-OAEP and RSA-PSS keys.
+    fn jwk_suffix(&self) -> &'static str {
+        match *self {
+            DigestAlgorithm::Sha1 => "1",
+            DigestAlgorithm::Sha256 => "256",
+            DigestAlgorithm::Sha384 => "384",
+            DigestAlgorithm::Sha512 => "512",
+        }
+    }
+}
+
+/// The elliptic curves keys can be generated or imported on.
+/// <https://w3c.github.io/webcrypto/#dfn-NamedCurve>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum NamedCurve {
+    P256,
+    P384,
+    P521,
+}
+
+impl NamedCurve {
+    fn from_name(name: &str) -> Fallible<NamedCurve> {
+        match name {
+            "P-256" => Ok(NamedCurve::P256),
+            "P-384" => Ok(NamedCurve::P384),
+            "P-521" => Ok(NamedCurve::P521),
+            _ => Err(Error::NotSupported),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            NamedCurve::P256 => "P-256",
+            NamedCurve::P384 => "P-384",
+            NamedCurve::P521 => "P-521",
+        }
+    }
+
+    fn nid(&self) -> Nid {
+        match *self {
+            NamedCurve::P256 => nid::X9_62_PRIME256V1,
+            NamedCurve::P384 => nid::SECP384R1,
+            NamedCurve::P521 => nid::SECP521R1,
+        }
+    }
+
+    /// The object identifier of the curve, as used in DER-encoded keys.
+    fn oid(&self) -> &'static [u8] {
+        match *self {
+            NamedCurve::P256 => der::OID_SECP256R1,
+            NamedCurve::P384 => der::OID_SECP384R1,
+            NamedCurve::P521 => der::OID_SECP521R1,
+        }
+    }
+
+    fn group(&self) -> Fallible<EcGroup> {
+        EcGroup::from_curve_name(self.nid()).map_err(|_| Error::Operation)
+    }
+
+    /// The length of a field element, in bytes.
+    fn coordinate_len(&self) -> usize {
+        match *self {
+            NamedCurve::P256 => 32,
+            NamedCurve::P384 => 48,
+            NamedCurve::P521 => 66,
+        }
+    }
+}
+
+/// The operations an algorithm can be normalized for.
+/// <https://w3c.github.io/webcrypto/#supported-operation>
+#[derive(Clone, Copy, PartialEq)]
+enum Operation {
+    Encrypt,
+    Decrypt,
+    Sign,
+    Verify,
+    Digest,
+    GenerateKey,
+    DeriveBits,
+    ImportKey,
+    GetKeyLength,
+}
+
+/// The result of normalizing an algorithm, holding the parameters from the
+/// dictionary type registered for the operation.
+/// <https://w3c.github.io/webcrypto/#dfn-normalized-algorithm>
+enum NormalizedAlgorithm {
+    /// <https://w3c.github.io/webcrypto/#dfn-Algorithm>
+    Plain(AlgorithmName),
+    Digest(DigestAlgorithm),
+    /// <https://w3c.github.io/webcrypto/#dfn-AesCbcParams>
+    AesCbc { iv: Vec<u8> },
+    /// <https://w3c.github.io/webcrypto/#dfn-AesCtrParams>
+    AesCtr { counter: Vec<u8>, length: u8 },
+    /// <https://w3c.github.io/webcrypto/#dfn-AesGcmParams>
+    ///
+    /// The tag length is kept wider than the `octet` of the dictionary, so
+    /// that lengths like 256 are rejected as unsupported tag lengths with an
+    /// `OperationError`.
+    AesGcm { iv: Vec<u8>, additional_data: Vec<u8>, tag_length: u32 },
+    /// <https://w3c.github.io/webcrypto/#dfn-AesKeyGenParams> and
+    /// <https://w3c.github.io/webcrypto/#dfn-AesDerivedKeyParams>
+    AesKey { name: AlgorithmName, length: u16 },
+    /// <https://w3c.github.io/webcrypto/#dfn-HmacKeyGenParams> and
+    /// <https://w3c.github.io/webcrypto/#dfn-HmacImportParams>
+    HmacKey { hash: DigestAlgorithm, length: Option<u32> },
+    /// <https://w3c.github.io/webcrypto/#dfn-EcKeyGenParams> and
+    /// <https://w3c.github.io/webcrypto/#dfn-EcKeyImportParams>
+    EcKey { name: AlgorithmName, named_curve: NamedCurve },
+    /// <https://w3c.github.io/webcrypto/#dfn-EcdsaParams>
+    Ecdsa { hash: DigestAlgorithm },
+    /// <https://w3c.github.io/webcrypto/#dfn-EcdhKeyDeriveParams>
+    Ecdh { public: OperationKey },
+    /// <https://w3c.github.io/webcrypto/#dfn-RsaHashedKeyGenParams>
+    RsaHashedKeyGen {
+        name: AlgorithmName,
+        modulus_length: u32,
+        public_exponent: Vec<u8>,
+        hash: DigestAlgorithm,
+    },
+    /// <https://w3c.github.io/webcrypto/#dfn-RsaHashedImportParams>
+    RsaHashedImport { name: AlgorithmName, hash: DigestAlgorithm },
+    /// <https://w3c.github.io/webcrypto/#dfn-RsaOaepParams>
+    RsaOaep { label: Vec<u8> },
+    /// <https://w3c.github.io/webcrypto/#dfn-RsaPssParams>
+    RsaPss { salt_length: u32 },
+    /// <https://w3c.github.io/webcrypto/#dfn-Pbkdf2Params>
+    Pbkdf2 { salt: Vec<u8>, iterations: u32, hash: DigestAlgorithm },
+    /// <https://w3c.github.io/webcrypto/#dfn-HkdfParams>
+    Hkdf { hash: DigestAlgorithm, salt: Vec<u8>, info: Vec<u8> },
+}
+
+impl NormalizedAlgorithm {
+    fn name(&self) -> AlgorithmName {
+        match *self {
+            NormalizedAlgorithm::Plain(name) => name,
+            NormalizedAlgorithm::Digest(digest) => digest.algorithm_name(),
+            NormalizedAlgorithm::AesCbc { .. } => AlgorithmName::AesCbc,
+            NormalizedAlgorithm::AesCtr { .. } => AlgorithmName::AesCtr,
+            NormalizedAlgorithm::AesGcm { .. } => AlgorithmName::AesGcm,
+            NormalizedAlgorithm::AesKey { name, .. } => name,
+            NormalizedAlgorithm::HmacKey { .. } => AlgorithmName::Hmac,
+            NormalizedAlgorithm::EcKey { name, .. } => name,
+            NormalizedAlgorithm::Ecdsa { .. } => AlgorithmName::Ecdsa,
+            NormalizedAlgorithm::Ecdh { .. } => AlgorithmName::Ecdh,
+            NormalizedAlgorithm::RsaHashedKeyGen { name, .. } => name,
+            NormalizedAlgorithm::RsaHashedImport { name, .. } => name,
+            NormalizedAlgorithm::RsaOaep { .. } => AlgorithmName::RsaOaep,
+            NormalizedAlgorithm::RsaPss { .. } => AlgorithmName::RsaPss,
+            NormalizedAlgorithm::Pbkdf2 { .. } => AlgorithmName::Pbkdf2,
+            NormalizedAlgorithm::Hkdf { .. } => AlgorithmName::Hkdf,
+        }
+    }
+}
+
+/// The parts of a `CryptoKey` that an operation running in parallel needs.
+struct OperationKey {
+    algorithm: KeyAlgorithm,
+    handle: Arc<Handle>,
+}
+
+impl OperationKey {
+    fn new(key: &CryptoKey) -> OperationKey {
+        OperationKey {
+            algorithm: key.algorithm().clone(),
+            handle: key.shared_handle(),
+        }
+    }
+}
+
+/// A key created by an operation, which becomes a `CryptoKey` when the
+/// promise of the operation is settled.
+struct NewKey {
+    key_type: KeyType,
+    extractable: bool,
+    algorithm: KeyAlgorithm,
+    usages: Vec<KeyUsage>,
+    handle: Handle,
+}
+
+impl NewKey {
+    fn reflect(self, global: &GlobalScope) -> DomRoot<CryptoKey> {
+        CryptoKey::new(global, self.key_type, self.extractable, self.algorithm, self.usages, self.handle)
+    }
+}
+
+/// The value a fulfilled promise is resolved with.
+enum CryptoResult {
+    Bytes(Vec<u8>),
+    Boolean(bool),
+    Key(NewKey),
+    KeyPair(NewKey, NewKey),
+    Jwk(JsonWebKey),
+}
+
+fn buffer_source_to_vec(source: &ArrayBufferViewOrArrayBuffer) -> Vec<u8> {
+    match *source {
+        ArrayBufferViewOrArrayBuffer::ArrayBufferView(ref view) => view.to_vec(),
+        ArrayBufferViewOrArrayBuffer::ArrayBuffer(ref buffer) => buffer.to_vec(),
+    }
+}
+
+fn missing_member(member: &str) -> Error {
+    Error::Type(format!("Missing required dictionary member \"{}\"", member))
+}
+
+/// Reads a `BufferSource` dictionary member.
+#[allow(unsafe_code)]
+unsafe fn get_buffer_source(cx: *mut JSContext, object: HandleObject, member: &str) -> Fallible<Option<Vec<u8>>> {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    get_property_jsval(cx, object, member, value.handle_mut())?;
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    if value.is_object() {
+        let object = value.to_object();
+        typedarray!(in(cx) let view: ArrayBufferView = object);
+        if let Ok(view) = view {
+            return Ok(Some(view.to_vec()));
+        }
+        typedarray!(in(cx) let buffer: ArrayBuffer = object);
+        if let Ok(buffer) = buffer {
+            return Ok(Some(buffer.to_vec()));
+        }
+    }
+    Err(Error::Type(format!("Dictionary member \"{}\" is not a BufferSource", member)))
+}
+
+#[allow(unsafe_code)]
+unsafe fn get_required_buffer_source(cx: *mut JSContext, object: HandleObject, member: &str) -> Fallible<Vec<u8>> {
+    get_buffer_source(cx, object, member)?.ok_or_else(|| missing_member(member))
+}
+
+#[allow(unsafe_code)]
+unsafe fn get_required_number<T>(cx: *mut JSContext, object: HandleObject, member: &str) -> Fallible<T>
+    where T: FromJSValConvertible<Config=ConversionBehavior>
+{
+    get_property(cx, object, member, ConversionBehavior::EnforceRange)?.ok_or_else(|| missing_member(member))
+}
+
+/// Reads the name of an `AlgorithmIdentifier`, which is either a string or
+/// an object with a `name` member.
+#[allow(unsafe_code)]
+unsafe fn get_algorithm_name(cx: *mut JSContext, value: HandleValue) -> Fallible<AlgorithmName> {
+    if value.is_object() {
+        rooted!(in(cx) let object = value.to_object());
+        let name: DOMString = get_property(cx, object.handle(), "name", ())?.ok_or_else(|| missing_member("name"))?;
+        AlgorithmName::from_name(&name)
+    } else {
+        AlgorithmName::from_name(&string_from_value(cx, value)?)
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe fn string_from_value(cx: *mut JSContext, value: HandleValue) -> Fallible<DOMString> {
+    match DOMString::from_jsval(cx, value, StringificationBehavior::Default) {
+        Ok(ConversionResult::Success(string)) => Ok(string),
+        Ok(ConversionResult::Failure(error)) => Err(Error::Type(error.into_owned())),
+        Err(()) => Err(Error::JSFailed),
+    }
+}
+
+/// Reads the `hash` member of a dictionary, normalizing it as a digest
+/// algorithm.
+#[allow(unsafe_code)]
+unsafe fn get_hash(cx: *mut JSContext, object: HandleObject) -> Fallible<DigestAlgorithm> {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    get_property_jsval(cx, object, "hash", value.handle_mut())?;
+    if value.is_undefined() {
+        return Err(missing_member("hash"));
+    }
+    DigestAlgorithm::from_algorithm(get_algorithm_name(cx, value.handle())?)
+}
+
+/// <https://w3c.github.io/webcrypto/#algorithm-normalization-normalize-an-algorithm>
+#[allow(unsafe_code)]
+unsafe fn normalize_algorithm(cx: *mut JSContext,
+                              algorithm: &ObjectOrString,
+                              operation: Operation)
+                              -> Fallible<NormalizedAlgorithm> {
+    // Step 1. A string is treated as a dictionary with just a name.
+    rooted!(in(cx) let mut object = ptr::null_mut::<JSObject>());
+    match *algorithm {
+        ObjectOrString::Object(ref algorithm) => object.set(algorithm.get()),
+        ObjectOrString::String(ref name) => {
+            object.set(JS_NewObject(cx, ptr::null()));
+            rooted!(in(cx) let mut name_value = UndefinedValue());
+            name.to_jsval(cx, name_value.handle_mut());
+            set_dictionary_property(cx, object.handle(), "name", name_value.handle())
+                .map_err(|_| Error::JSFailed)?;
+        },
+    }
+    let object = object.handle();
+
+    // Steps 2-6.
+    let name: DOMString = get_property(cx, object, "name", ())?.ok_or_else(|| missing_member("name"))?;
+    let name = AlgorithmName::from_name(&name)?;
+
+    // Steps 7-10.
+    let normalized = match (operation, name) {
+        (Operation::Digest, _) => NormalizedAlgorithm::Digest(DigestAlgorithm::from_algorithm(name)?),
+
+        (Operation::Encrypt, AlgorithmName::AesCbc) |
+        (Operation::Decrypt, AlgorithmName::AesCbc) => NormalizedAlgorithm::AesCbc {
+            iv: get_required_buffer_source(cx, object, "iv")?,
+        },
+        (Operation::Encrypt, AlgorithmName::AesCtr) |
+        (Operation::Decrypt, AlgorithmName::AesCtr) => NormalizedAlgorithm::AesCtr {
+            counter: get_required_buffer_source(cx, object, "counter")?,
+            length: get_required_number(cx, object, "length")?,
+        },
+        (Operation::Encrypt, AlgorithmName::AesGcm) |
+        (Operation::Decrypt, AlgorithmName::AesGcm) => NormalizedAlgorithm::AesGcm {
+            iv: get_required_buffer_source(cx, object, "iv")?,
+            additional_data: get_buffer_source(cx, object, "additionalData")?.unwrap_or_default(),
+            tag_length: get_property(cx, object, "tagLength", ConversionBehavior::EnforceRange)?.unwrap_or(128),
+        },
+        (Operation::Encrypt, AlgorithmName::RsaOaep) |
+        (Operation::Decrypt, AlgorithmName::RsaOaep) => NormalizedAlgorithm::RsaOaep {
+            label: get_buffer_source(cx, object, "label")?.unwrap_or_default(),
+        },
+
+        (Operation::Sign, AlgorithmName::Hmac) |
+        (Operation::Verify, AlgorithmName::Hmac) |
+        (Operation::Sign, AlgorithmName::RsassaPkcs1v15) |
+        (Operation::Verify, AlgorithmName::RsassaPkcs1v15) => NormalizedAlgorithm::Plain(name),
+        (Operation::Sign, AlgorithmName::Ecdsa) |
+        (Operation::Verify, AlgorithmName::Ecdsa) => NormalizedAlgorithm::Ecdsa {
+            hash: get_hash(cx, object)?,
+        },
+        (Operation::Sign, AlgorithmName::RsaPss) |
+        (Operation::Verify, AlgorithmName::RsaPss) => NormalizedAlgorithm::RsaPss {
+            salt_length: get_required_number(cx, object, "saltLength")?,
+        },
+
+        (Operation::GenerateKey, _) | (Operation::GetKeyLength, _) if name.is_aes() => NormalizedAlgorithm::AesKey {
+            name: name,
+            length: get_required_number(cx, object, "length")?,
+        },
+        (Operation::GenerateKey, AlgorithmName::Hmac) |
+        (Operation::ImportKey, AlgorithmName::Hmac) |
+        (Operation::GetKeyLength, AlgorithmName::Hmac) => NormalizedAlgorithm::HmacKey {
+            hash: get_hash(cx, object)?,
+            length: get_property(cx, object, "length", ConversionBehavior::EnforceRange)?,
+        },
+        (Operation::GenerateKey, AlgorithmName::Ecdsa) |
+        (Operation::GenerateKey, AlgorithmName::Ecdh) |
+        (Operation::ImportKey, AlgorithmName::Ecdsa) |
+        (Operation::ImportKey, AlgorithmName::Ecdh) => {
+            let named_curve: DOMString = get_property(cx, object, "namedCurve", ())?
+                .ok_or_else(|| missing_member("namedCurve"))?;
+            NormalizedAlgorithm::EcKey {
+                name: name,
+                named_curve: NamedCurve::from_name(&named_curve)?,
+            }
+        },
+        (Operation::GenerateKey, AlgorithmName::RsaOaep) |
+        (Operation::GenerateKey, AlgorithmName::RsaPss) |
+        (Operation::GenerateKey, AlgorithmName::RsassaPkcs1v15) => NormalizedAlgorithm::RsaHashedKeyGen {
+            name: name,
+            modulus_length: get_required_number(cx, object, "modulusLength")?,
+            public_exponent: get_required_buffer_source(cx, object, "publicExponent")?,
+            hash: get_hash(cx, object)?,
+        },
+        (Operation::ImportKey, AlgorithmName::RsaOaep) |
+        (Operation::ImportKey, AlgorithmName::RsaPss) |
+        (Operation::ImportKey, AlgorithmName::RsassaPkcs1v15) => NormalizedAlgorithm::RsaHashedImport {
+            name: name,
+            hash: get_hash(cx, object)?,
+        },
+        (Operation::ImportKey, AlgorithmName::Pbkdf2) |
+        (Operation::ImportKey, AlgorithmName::Hkdf) => NormalizedAlgorithm::Plain(name),
+        (Operation::ImportKey, _) if name.is_aes() => NormalizedAlgorithm::Plain(name),
+
+        (Operation::DeriveBits, AlgorithmName::Ecdh) => {
+            let public = get_property::<DomRoot<CryptoKey>>(cx, object, "public", ())?
+                .ok_or_else(|| missing_member("public"))?;
+            NormalizedAlgorithm::Ecdh { public: OperationKey::new(&public) }
+        },
+        (Operation::DeriveBits, AlgorithmName::Pbkdf2) => NormalizedAlgorithm::Pbkdf2 {
+            salt: get_required_buffer_source(cx, object, "salt")?,
+            iterations: get_required_number(cx, object, "iterations")?,
+            hash: get_hash(cx, object)?,
+        },
+        (Operation::DeriveBits, AlgorithmName::Hkdf) => NormalizedAlgorithm::Hkdf {
+            hash: get_hash(cx, object)?,
+            salt: get_required_buffer_source(cx, object, "salt")?,
+            info: get_required_buffer_source(cx, object, "info")?,
+        },
+
+        _ => return Err(Error::NotSupported),
+    };
+    Ok(normalized)
+}
+
+/// Checks that `usages` only contains usages from `allowed`.
+fn check_usages(usages: &[KeyUsage], allowed: &[KeyUsage]) -> Fallible<()> {
+    if usages.iter().all(|usage| allowed.contains(usage)) {
+        Ok(())
+    } else {
+        Err(Error::Syntax)
+    }
+}
+
+/// The usages out of `usages` that are in `allowed`.
+fn filter_usages(usages: &[KeyUsage], allowed: &[KeyUsage]) -> Vec<KeyUsage> {
+    usages.iter().cloned().filter(|usage| allowed.contains(usage)).collect()
+}
+
+fn random_bytes(len: usize) -> Fallible<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    rand_bytes(&mut bytes).map_err(|_| Error::Operation)?;
+    Ok(bytes)
+}
+
+/// Truncates `bits` to `length` bits, failing if there are not enough.
+fn truncate_bits(mut bits: Vec<u8>, length: u32) -> Fallible<Vec<u8>> {
+    let length = length as usize;
+    if length > bits.len() * 8 {
+        return Err(Error::Operation);
+    }
+    bits.truncate((length + 7) / 8);
+    if length % 8 != 0 {
+        let last = bits.len() - 1;
+        bits[last] &= 0xff << (8 - length % 8);
+    }
+    Ok(bits)
+}
+
+fn base64url_encode(bytes: &[u8]) -> DOMString {
+    DOMString::from(base64::encode_config(bytes, base64::URL_SAFE_NO_PAD))
+}
+
+fn base64url_decode(member: &Option<DOMString>) -> Fallible<Vec<u8>> {
+    let encoded = member.as_ref().ok_or(Error::Data)?;
+    base64::decode_config(&**encoded, base64::URL_SAFE_NO_PAD).map_err(|_| Error::Data)
+}
+
+fn bignum_from_jwk(member: &Option<DOMString>) -> Fallible<BigNum> {
+    BigNum::from_slice(&base64url_decode(member)?).map_err(|_| Error::Data)
+}
+
+fn aes_cipher(mode: AlgorithmName, key: &[u8]) -> Fallible<Cipher> {
+    match (mode, key.len()) {
+        (AlgorithmName::AesCbc, 16) => Ok(Cipher::aes_128_cbc()),
+        (AlgorithmName::AesCbc, 24) => Ok(webcrypto::aes_192_cbc()),
+        (AlgorithmName::AesCbc, 32) => Ok(Cipher::aes_256_cbc()),
+        (AlgorithmName::AesCtr, 16) => Ok(Cipher::aes_128_ctr()),
+        (AlgorithmName::AesCtr, 24) => Ok(webcrypto::aes_192_ctr()),
+        (AlgorithmName::AesCtr, 32) => Ok(Cipher::aes_256_ctr()),
+        (AlgorithmName::AesGcm, 16) => Ok(Cipher::aes_128_gcm()),
+        (AlgorithmName::AesGcm, 24) => Ok(webcrypto::aes_192_gcm()),
+        (AlgorithmName::AesGcm, 32) => Ok(Cipher::aes_256_gcm()),
+        _ => Err(Error::Operation),
+    }
+}
+
+fn check_aes_length(length: u16) -> Fallible<()> {
+    match length {
+        128 | 192 | 256 => Ok(()),
+        _ => Err(Error::Operation),
+    }
+}
+
+fn check_gcm_tag_length(tag_length: u32) -> Fallible<()> {
+    match tag_length {
+        32 | 64 | 96 | 104 | 112 | 120 | 128 => Ok(()),
+        _ => Err(Error::Operation),
+    }
+}
+
+fn secret(handle: &Handle) -> Fallible<&[u8]> {
+    match *handle {
+        Handle::Secret(ref bytes) => Ok(bytes),
+        _ => Err(Error::InvalidAccess),
+    }
+}
+
+fn public_pkey(handle: &Handle) -> Fallible<&PKey> {
+    match *handle {
+        Handle::Public(ref pkey) => Ok(pkey),
+        _ => Err(Error::InvalidAccess),
+    }
+}
+
+fn private_pkey(handle: &Handle) -> Fallible<&PKey> {
+    match *handle {
+        Handle::Private(ref pkey) => Ok(pkey),
+        _ => Err(Error::InvalidAccess),
+    }
+}
+
+fn key_hash(algorithm: &KeyAlgorithm) -> Fallible<DigestAlgorithm> {
+    match *algorithm {
+        KeyAlgorithm::Hmac { hash, .. } | KeyAlgorithm::RsaHashed { hash, .. } => Ok(hash),
+        _ => Err(Error::InvalidAccess),
+    }
+}
+
+fn key_curve(algorithm: &KeyAlgorithm) -> Fallible<NamedCurve> {
+    match *algorithm {
+        KeyAlgorithm::Ec { named_curve, .. } => Ok(named_curve),
+        _ => Err(Error::InvalidAccess),
+    }
+}
+
+/// Checks that `key` was created for `algorithm` and may be used for `usage`.
+fn check_key(key: &CryptoKey, algorithm: &NormalizedAlgorithm, usage: KeyUsage) -> Fallible<()> {
+    if key.algorithm().name() != algorithm.name() || !key.has_usage(usage) {
+        return Err(Error::InvalidAccess);
+    }
+    Ok(())
+}
+
+fn ec_public_point_bytes(curve: NamedCurve, ec: &EcKey) -> Fallible<Vec<u8>> {
+    let group = curve.group()?;
+    let mut context = BigNumContext::new().map_err(|_| Error::Operation)?;
+    let point = ec.public_key().ok_or(Error::Operation)?;
+    point.to_bytes(&group, ec::POINT_CONVERSION_UNCOMPRESSED, &mut context).map_err(|_| Error::Operation)
+}
+
+fn ec_public_key_from_bytes(curve: NamedCurve, bytes: &[u8]) -> Fallible<EcKey> {
+    let group = curve.group()?;
+    let mut context = BigNumContext::new().map_err(|_| Error::Operation)?;
+    let point = EcPoint::from_bytes(&group, bytes, &mut context).map_err(|_| Error::Data)?;
+    EcKey::from_public_key(&group, &point).map_err(|_| Error::Data)
+}
+
+/// Checks that an imported elliptic curve key is valid and lies on `curve`.
+fn check_ec_key(curve: NamedCurve, ec: &EcKey) -> Fallible<()> {
+    match ec.group().and_then(|group| group.curve_name()) {
+        Some(nid) if nid == curve.nid() => {},
+        _ => return Err(Error::Data),
+    }
+    ec.check_key().map_err(|_| Error::Data)
+}
+
+/// The `modulusLength` and `publicExponent` of an RSA key.
+fn rsa_parameters(rsa: &Rsa) -> Fallible<(u32, Vec<u8>)> {
+    match (rsa.n(), rsa.e()) {
+        (Some(n), Some(e)) => Ok((n.num_bits() as u32, e.to_vec())),
+        _ => Err(Error::Data),
+    }
+}
+
+/// Checks the parameters of a new RSA key, before generating it.
+fn check_rsa_key_gen(modulus_length: u32, public_exponent: &[u8]) -> Fallible<()> {
+    // FIXME: Only the default public exponent of 65537 is supported.
+    let start = public_exponent.iter().position(|&byte| byte != 0).unwrap_or(public_exponent.len());
+    if &public_exponent[start..] != &[0x01, 0x00, 0x01] || modulus_length < 256 {
+        return Err(Error::Operation);
+    }
+    Ok(())
+}
+
+fn base64url_bignum(value: &BigNumRef) -> DOMString {
+    base64url_encode(&value.to_vec())
+}
+
+/// Sets the `n` and `e` members of the JWK of an RSA key.
+fn set_rsa_public_jwk(jwk: &mut JsonWebKey, rsa: &Rsa) -> Fallible<()> {
+    jwk.n = Some(rsa.n().map(base64url_bignum).ok_or(Error::Operation)?);
+    jwk.e = Some(rsa.e().map(base64url_bignum).ok_or(Error::Operation)?);
+    Ok(())
+}
+
+/// The usages an AES key may have.
+fn aes_key_usages(name: AlgorithmName) -> &'static [KeyUsage] {
+    if name == AlgorithmName::AesKw { KEY_WRAPPING_USAGES } else { SECRET_KEY_USAGES }
+}
+
+/// The `alg` member of the JWK of an RSA key.
+fn rsa_jwk_alg(name: AlgorithmName, hash: DigestAlgorithm) -> String {
+    match (name, hash) {
+        (AlgorithmName::RsaOaep, DigestAlgorithm::Sha1) => "RSA-OAEP".to_owned(),
+        (AlgorithmName::RsaOaep, _) => format!("RSA-OAEP-{}", hash.jwk_suffix()),
+        (AlgorithmName::RsaPss, _) => format!("PS{}", hash.jwk_suffix()),
+        _ => format!("RS{}", hash.jwk_suffix()),
+    }
+}
+
+/// The usages that apply to the public and private halves of a key pair.
+fn key_pair_usages(name: AlgorithmName) -> (&'static [KeyUsage], &'static [KeyUsage]) {
+    match name {
+        AlgorithmName::RsaOaep => (&[KeyUsage::Encrypt, KeyUsage::WrapKey], &[KeyUsage::Decrypt, KeyUsage::UnwrapKey]),
+        AlgorithmName::Ecdh => (&[], &[KeyUsage::DeriveKey, KeyUsage::DeriveBits]),
+        _ => (&[KeyUsage::Verify], &[KeyUsage::Sign]),
+    }
+}
+
+const SECRET_KEY_USAGES: &'static [KeyUsage] = &[
+    KeyUsage::Encrypt, KeyUsage::Decrypt, KeyUsage::WrapKey, KeyUsage::UnwrapKey,
+];
+const KEY_WRAPPING_USAGES: &'static [KeyUsage] = &[KeyUsage::WrapKey, KeyUsage::UnwrapKey];
+const DERIVATION_KEY_USAGES: &'static [KeyUsage] = &[KeyUsage::DeriveKey, KeyUsage::DeriveBits];
+const SIGNING_KEY_USAGES: &'static [KeyUsage] = &[KeyUsage::Sign, KeyUsage::Verify];
+
+/// Settles `promise` with the outcome of an operation.
+#[allow(unsafe_code)]
+unsafe fn settle_promise(cx: *mut JSContext, promise: &Promise, result: Fallible<CryptoResult>) {
+    match result {
+        Ok(CryptoResult::Bytes(bytes)) => {
+            rooted!(in(cx) let mut buffer = ptr::null_mut::<JSObject>());
+            assert!(ArrayBuffer::create(cx, CreateWith::Slice(&bytes), buffer.handle_mut()).is_ok());
+            rooted!(in(cx) let value = ObjectValue(buffer.get()));
+            promise.resolve(cx, value.handle());
+        },
+        Ok(CryptoResult::Boolean(boolean)) => promise.resolve_native(&boolean),
+        Ok(CryptoResult::Key(key)) => promise.resolve_native(&key.reflect(&promise.global())),
+        Ok(CryptoResult::KeyPair(public_key, private_key)) => {
+            let global = promise.global();
+            let mut pair = CryptoKeyPair::empty();
+            pair.publicKey = Some(public_key.reflect(&global));
+            pair.privateKey = Some(private_key.reflect(&global));
+            promise.resolve_native(&pair);
+        },
+        Ok(CryptoResult::Jwk(jwk)) => promise.resolve_native(&jwk),
+        Err(error) => promise.reject_error(error),
+    }
+}
+
+// https://w3c.github.io/webcrypto/#subtlecrypto-interface
+/// How many operations run in parallel, for all the documents and workers
+/// of the process.
+const OPERATION_THREADS: usize = 4;
+
+lazy_static! {
+    /// The threads that run the operations of every `SubtleCrypto`.
+    static ref OPERATION_POOL: ThreadPool = ThreadPoolBuilder::new()
+        .num_threads(OPERATION_THREADS)
+        .thread_name(|index| format!("WebCryptoOperation#{}", index))
+        .build()
+        .expect("ThreadPool creation failed");
+}
+
+#[dom_struct]
+pub struct SubtleCrypto {
+    reflector_: Reflector,
+}
+
+impl SubtleCrypto {
+    fn new_inherited() -> SubtleCrypto {
+        SubtleCrypto {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<SubtleCrypto> {
+        reflect_dom_object(Box::new(SubtleCrypto::new_inherited()),
+                           global,
+                           SubtleCryptoBinding::Wrap)
+    }
+
+    /// Creates a promise settled with the outcome of an operation.
+    #[allow(unsafe_code)]
+    unsafe fn settle(&self, cx: *mut JSContext, result: Fallible<CryptoResult>) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        settle_promise(cx, &promise, result);
+        promise
+    }
+
+    /// Creates a promise, and runs `operation` in parallel on the operation
+    /// pool once the steps of the method before it have succeeded. The promise is settled with the
+    /// outcome of the operation from a networking task.
+    #[allow(unsafe_code)]
+    fn settle_in_parallel<F>(&self, operation: Fallible<F>) -> Rc<Promise>
+        where F: FnOnce() -> Fallible<CryptoResult> + Send + 'static
+    {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let operation = match operation {
+            Ok(operation) => operation,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let trusted_promise = TrustedPromise::new(promise.clone());
+        let task_source = global.networking_task_source();
+        let canceller = global.task_canceller(TaskSourceName::Networking);
+        OPERATION_POOL.spawn(move || {
+            let result = operation();
+            let _ = task_source.queue_with_canceller(
+                task!(settle_crypto_promise: move || {
+                    let promise = trusted_promise.root();
+                    unsafe { settle_promise(promise.global().get_cx(), &promise, result) };
+                }),
+                &canceller,
+            );
+        });
+        promise
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-encrypt>
+/// Step 10
+fn encrypt(algorithm: &NormalizedAlgorithm, key: &OperationKey, data: &[u8]) -> Fallible<Vec<u8>> {
+    match *algorithm {
+        NormalizedAlgorithm::AesCbc { ref iv } => {
+            if iv.len() != 16 {
+                return Err(Error::Operation);
+            }
+            let secret = secret(&key.handle)?;
+            symm::encrypt(aes_cipher(AlgorithmName::AesCbc, secret)?, secret, Some(iv), data)
+                .map_err(|_| Error::Operation)
+        },
+        NormalizedAlgorithm::AesCtr { ref counter, length } => {
+            let secret = secret(&key.handle)?;
+            webcrypto::aes_ctr(aes_cipher(AlgorithmName::AesCtr, secret)?, secret, counter, length as u32, data)
+                .map_err(|_| Error::Operation)
+        },
+        NormalizedAlgorithm::AesGcm { ref iv, ref additional_data, tag_length } => {
+            check_gcm_tag_length(tag_length)?;
+            let secret = secret(&key.handle)?;
+            let mut tag = vec![0; tag_length as usize / 8];
+            let mut ciphertext = symm::encrypt_aead(aes_cipher(AlgorithmName::AesGcm, secret)?,
+                                                    secret, Some(iv), additional_data, data, &mut tag)
+                .map_err(|_| Error::Operation)?;
+            ciphertext.extend(tag);
+            Ok(ciphertext)
+        },
+        NormalizedAlgorithm::RsaOaep { ref label } => {
+            let hash = key_hash(&key.algorithm)?;
+            webcrypto::rsa_oaep_encrypt(public_pkey(&key.handle)?, hash.message_digest(), label, data)
+                .map_err(|_| Error::Operation)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-decrypt>
+/// Step 10
+fn decrypt(algorithm: &NormalizedAlgorithm, key: &OperationKey, data: &[u8]) -> Fallible<Vec<u8>> {
+    match *algorithm {
+        NormalizedAlgorithm::AesCbc { ref iv } => {
+            if iv.len() != 16 {
+                return Err(Error::Operation);
+            }
+            let secret = secret(&key.handle)?;
+            symm::decrypt(aes_cipher(AlgorithmName::AesCbc, secret)?, secret, Some(iv), data)
+                .map_err(|_| Error::Operation)
+        },
+        NormalizedAlgorithm::AesCtr { ref counter, length } => {
+            let secret = secret(&key.handle)?;
+            webcrypto::aes_ctr(aes_cipher(AlgorithmName::AesCtr, secret)?, secret, counter, length as u32, data)
+                .map_err(|_| Error::Operation)
+        },
+        NormalizedAlgorithm::AesGcm { ref iv, ref additional_data, tag_length } => {
+            check_gcm_tag_length(tag_length)?;
+            let tag_len = tag_length as usize / 8;
+            if data.len() < tag_len {
+                return Err(Error::Operation);
+            }
+            let (ciphertext, tag) = data.split_at(data.len() - tag_len);
+            let secret = secret(&key.handle)?;
+            symm::decrypt_aead(aes_cipher(AlgorithmName::AesGcm, secret)?,
+                               secret, Some(iv), additional_data, ciphertext, tag)
+                .map_err(|_| Error::Operation)
+        },
+        NormalizedAlgorithm::RsaOaep { ref label } => {
+            let hash = key_hash(&key.algorithm)?;
+            webcrypto::rsa_oaep_decrypt(private_pkey(&key.handle)?, hash.message_digest(), label, data)
+                .map_err(|_| Error::Operation)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-sign>
+/// Step 10
+fn sign(algorithm: &NormalizedAlgorithm, key: &OperationKey, data: &[u8]) -> Fallible<Vec<u8>> {
+    match *algorithm {
+        NormalizedAlgorithm::Plain(AlgorithmName::Hmac) => {
+            let hash = key_hash(&key.algorithm)?;
+            webcrypto::hmac(hash.message_digest(), secret(&key.handle)?, data).map_err(|_| Error::Operation)
+        },
+        NormalizedAlgorithm::Ecdsa { hash } => {
+            let pkey = private_pkey(&key.handle)?;
+            let mut signer = Signer::new(hash.message_digest(), pkey).map_err(|_| Error::Operation)?;
+            signer.update(data).map_err(|_| Error::Operation)?;
+            let der = signer.sign_to_vec().map_err(|_| Error::Operation)?;
+            der::ecdsa_der_to_raw(&der, key_curve(&key.algorithm)?.coordinate_len()).ok_or(Error::Operation)
+        },
+        NormalizedAlgorithm::Plain(AlgorithmName::RsassaPkcs1v15) => {
+            let hash = key_hash(&key.algorithm)?;
+            let mut signer = Signer::new(hash.message_digest(), private_pkey(&key.handle)?)
+                .map_err(|_| Error::Operation)?;
+            signer.update(data).map_err(|_| Error::Operation)?;
+            signer.sign_to_vec().map_err(|_| Error::Operation)
+        },
+        NormalizedAlgorithm::RsaPss { salt_length } => {
+            let hash = key_hash(&key.algorithm)?;
+            webcrypto::rsa_pss_sign(private_pkey(&key.handle)?, hash.message_digest(), salt_length as usize, data)
+                .map_err(|_| Error::Operation)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-verify>
+/// Step 11
+fn verify(algorithm: &NormalizedAlgorithm, key: &OperationKey, signature: &[u8], data: &[u8]) -> Fallible<bool> {
+    match *algorithm {
+        NormalizedAlgorithm::Plain(AlgorithmName::Hmac) => {
+            let hash = key_hash(&key.algorithm)?;
+            let expected = webcrypto::hmac(hash.message_digest(), secret(&key.handle)?, data)
+                .map_err(|_| Error::Operation)?;
+            Ok(expected.len() == signature.len() && memcmp::eq(&expected, signature))
+        },
+        NormalizedAlgorithm::Ecdsa { hash } => {
+            let pkey = public_pkey(&key.handle)?;
+            if signature.len() != 2 * key_curve(&key.algorithm)?.coordinate_len() {
+                return Ok(false);
+            }
+            let mut verifier = Verifier::new(hash.message_digest(), pkey).map_err(|_| Error::Operation)?;
+            verifier.update(data).map_err(|_| Error::Operation)?;
+            Ok(verifier.verify(&der::ecdsa_raw_to_der(signature)).unwrap_or(false))
+        },
+        NormalizedAlgorithm::Plain(AlgorithmName::RsassaPkcs1v15) => {
+            let hash = key_hash(&key.algorithm)?;
+            let mut verifier = Verifier::new(hash.message_digest(), public_pkey(&key.handle)?)
+                .map_err(|_| Error::Operation)?;
+            verifier.update(data).map_err(|_| Error::Operation)?;
+            Ok(verifier.verify(signature).unwrap_or(false))
+        },
+        NormalizedAlgorithm::RsaPss { salt_length } => {
+            let hash = key_hash(&key.algorithm)?;
+            let pkey = public_pkey(&key.handle)?;
+            webcrypto::rsa_pss_verify(pkey, hash.message_digest(), salt_length as usize, data, signature)
+                .map_err(|_| Error::Operation)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-generateKey>
+/// Steps 8-10
+fn generate_key(algorithm: &NormalizedAlgorithm, extractable: bool, usages: &[KeyUsage]) -> Fallible<CryptoResult> {
+    let result = match *algorithm {
+        NormalizedAlgorithm::AesKey { name, length } => {
+            check_usages(usages, aes_key_usages(name))?;
+            check_aes_length(length)?;
+            CryptoResult::Key(NewKey {
+                key_type: KeyType::Secret,
+                extractable: extractable,
+                algorithm: KeyAlgorithm::Aes { name: name, length: length },
+                usages: usages.to_vec(),
+                handle: Handle::Secret(random_bytes(length as usize / 8)?),
+            })
+        },
+        NormalizedAlgorithm::HmacKey { hash, length } => {
+            check_usages(usages, SIGNING_KEY_USAGES)?;
+            let length = length.unwrap_or(8 * hash.block_len() as u32);
+            if length == 0 || length % 8 != 0 {
+                return Err(Error::Operation);
+            }
+            CryptoResult::Key(NewKey {
+                key_type: KeyType::Secret,
+                extractable: extractable,
+                algorithm: KeyAlgorithm::Hmac { hash: hash, length: length },
+                usages: usages.to_vec(),
+                handle: Handle::Secret(random_bytes(length as usize / 8)?),
+            })
+        },
+        NormalizedAlgorithm::EcKey { name, named_curve } => {
+            let allowed = if name == AlgorithmName::Ecdh { DERIVATION_KEY_USAGES } else { SIGNING_KEY_USAGES };
+            check_usages(usages, allowed)?;
+            let group = named_curve.group()?;
+            let private = EcKey::generate(&group).map_err(|_| Error::Operation)?;
+            let point = private.public_key().ok_or(Error::Operation)?;
+            let public = EcKey::from_public_key(&group, point).map_err(|_| Error::Operation)?;
+            let algorithm = KeyAlgorithm::Ec { name: name, named_curve: named_curve };
+            new_key_pair(algorithm, extractable, usages,
+                         PKey::from_ec_key(public).map_err(|_| Error::Operation)?,
+                         PKey::from_ec_key(private).map_err(|_| Error::Operation)?)?
+        },
+        NormalizedAlgorithm::RsaHashedKeyGen { name, modulus_length, ref public_exponent, hash } => {
+            let allowed = if name == AlgorithmName::RsaOaep { SECRET_KEY_USAGES } else { SIGNING_KEY_USAGES };
+            check_usages(usages, allowed)?;
+            check_rsa_key_gen(modulus_length, public_exponent)?;
+            let private = Rsa::generate(modulus_length).map_err(|_| Error::Operation)?;
+            let public = {
+                let n = private.n().ok_or(Error::Operation)?.to_owned().map_err(|_| Error::Operation)?;
+                let e = private.e().ok_or(Error::Operation)?.to_owned().map_err(|_| Error::Operation)?;
+                Rsa::from_public_components(n, e).map_err(|_| Error::Operation)?
+            };
+            let (modulus_length, public_exponent) = rsa_parameters(&private)?;
+            let algorithm = KeyAlgorithm::RsaHashed {
+                name: name,
+                modulus_length: modulus_length,
+                public_exponent: public_exponent,
+                hash: hash,
+            };
+            new_key_pair(algorithm, extractable, usages,
+                         PKey::from_rsa(public).map_err(|_| Error::Operation)?,
+                         PKey::from_rsa(private).map_err(|_| Error::Operation)?)?
+        },
+        _ => return Err(Error::NotSupported),
+    };
+
+    // Step 10.
+    if let CryptoResult::Key(ref key) = result {
+        if key.usages.is_empty() {
+            return Err(Error::Syntax);
+        }
+    }
+    Ok(result)
+}
+
+fn new_key_pair(algorithm: KeyAlgorithm,
+                extractable: bool,
+                usages: &[KeyUsage],
+                public: PKey,
+                private: PKey)
+                -> Fallible<CryptoResult> {
+    let (public_usages, private_usages) = key_pair_usages(algorithm.name());
+    let private_usages = filter_usages(usages, private_usages);
+    if private_usages.is_empty() {
+        return Err(Error::Syntax);
+    }
+    // Public keys are always extractable.
+    let public_key = NewKey {
+        key_type: KeyType::Public,
+        extractable: true,
+        algorithm: algorithm.clone(),
+        usages: filter_usages(usages, public_usages),
+        handle: Handle::Public(public),
+    };
+    let private_key = NewKey {
+        key_type: KeyType::Private,
+        extractable: extractable,
+        algorithm: algorithm,
+        usages: private_usages,
+        handle: Handle::Private(private),
+    };
+    Ok(CryptoResult::KeyPair(public_key, private_key))
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-deriveBits>
+/// Step 10
+fn derive_bits(algorithm: &NormalizedAlgorithm, key: &OperationKey, length: u32) -> Fallible<Vec<u8>> {
+    if key.algorithm.name() != algorithm.name() {
+        return Err(Error::InvalidAccess);
+    }
+    match *algorithm {
+        NormalizedAlgorithm::Ecdh { ref public } => {
+            let curve = key_curve(&key.algorithm)?;
+            if public.algorithm.name() != AlgorithmName::Ecdh || key_curve(&public.algorithm)? != curve {
+                return Err(Error::InvalidAccess);
+            }
+            let secret = webcrypto::ecdh(private_pkey(&key.handle)?, public_pkey(&public.handle)?)
+                .map_err(|_| Error::Operation)?;
+            truncate_bits(der::pad_to(secret, curve.coordinate_len()), length)
+        },
+        NormalizedAlgorithm::Pbkdf2 { ref salt, iterations, hash } => {
+            // The length comes from script. Like HKDF, it is limited to 255
+            // blocks of hash output, which is far more than any key needs.
+            let len = length as usize / 8;
+            if length == 0 || length % 8 != 0 || iterations == 0 || len > 255 * hash.output_len() {
+                return Err(Error::Operation);
+            }
+            let mut bits = vec![0; len];
+            pbkdf2_hmac(secret(&key.handle)?, salt, iterations as usize, hash.message_digest(), &mut bits)
+                .map_err(|_| Error::Operation)?;
+            Ok(bits)
+        },
+        NormalizedAlgorithm::Hkdf { hash, ref salt, ref info } => {
+            let len = length as usize / 8;
+            if length % 8 != 0 || len > 255 * hash.output_len() {
+                return Err(Error::Operation);
+            }
+            webcrypto::hkdf(hash.message_digest(), secret(&key.handle)?, salt, info, len)
+                .map_err(|_| Error::Operation)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-GetKeyLength>
+fn get_key_length(algorithm: &NormalizedAlgorithm) -> Fallible<u32> {
+    match *algorithm {
+        NormalizedAlgorithm::AesKey { length, .. } => {
+            check_aes_length(length)?;
+            Ok(length as u32)
+        },
+        NormalizedAlgorithm::HmacKey { hash, length } => match length {
+            Some(0) => Err(Error::Type("HMAC key length must not be zero".to_owned())),
+            Some(length) => Ok(length),
+            None => Ok(8 * hash.block_len() as u32),
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-importKey>
+/// Steps 8-10
+fn import_key(format: KeyFormat,
+              key_data: KeyData,
+              algorithm: &NormalizedAlgorithm,
+              extractable: bool,
+              usages: &[KeyUsage])
+              -> Fallible<NewKey> {
+    if let KeyData::Jwk(ref jwk) = key_data {
+        // https://w3c.github.io/webcrypto/#concept-parse-a-jwk
+        if jwk.ext == Some(false) && extractable {
+            return Err(Error::Data);
+        }
+        if let Some(ref key_ops) = jwk.key_ops {
+            if !usages.iter().all(|usage| key_ops.iter().any(|op| &**op == usage.as_str())) {
+                return Err(Error::Data);
+            }
+        }
+    }
+
+    let key = match *algorithm {
+        NormalizedAlgorithm::Plain(name) if name.is_aes() => {
+            check_usages(usages, aes_key_usages(name))?;
+            let secret = match (format, key_data) {
+                (KeyFormat::Raw, KeyData::Bytes(bytes)) => bytes,
+                (KeyFormat::Jwk, KeyData::Jwk(jwk)) => {
+                    if jwk.kty.as_ref().map(|kty| &**kty) != Some("oct") {
+                        return Err(Error::Data);
+                    }
+                    let secret = base64url_decode(&jwk.k)?;
+                    let mode = &name.as_str()[4..];
+                    let expected_alg = format!("A{}{}", secret.len() * 8, mode);
+                    if jwk.alg.as_ref().map_or(false, |alg| **alg != *expected_alg) {
+                        return Err(Error::Data);
+                    }
+                    if jwk.use_.as_ref().map_or(false, |use_| &**use_ != "enc") {
+                        return Err(Error::Data);
+                    }
+                    secret
+                },
+                _ => return Err(Error::NotSupported),
+            };
+            let length = secret.len() as u16 * 8;
+            check_aes_length(length).map_err(|_| Error::Data)?;
+            NewKey {
+                key_type: KeyType::Secret,
+                extractable: extractable,
+                algorithm: KeyAlgorithm::Aes { name: name, length: length },
+                usages: usages.to_vec(),
+                handle: Handle::Secret(secret),
+            }
+        },
+        NormalizedAlgorithm::HmacKey { hash, length } => {
+            check_usages(usages, SIGNING_KEY_USAGES)?;
+            let secret = match (format, key_data) {
+                (KeyFormat::Raw, KeyData::Bytes(bytes)) => bytes,
+                (KeyFormat::Jwk, KeyData::Jwk(jwk)) => {
+                    if jwk.kty.as_ref().map(|kty| &**kty) != Some("oct") {
+                        return Err(Error::Data);
+                    }
+                    let expected_alg = format!("HS{}", hash.jwk_suffix());
+                    if jwk.alg.as_ref().map_or(false, |alg| **alg != *expected_alg) {
+                        return Err(Error::Data);
+                    }
+                    if jwk.use_.as_ref().map_or(false, |use_| &**use_ != "sig") {
+                        return Err(Error::Data);
+                    }
+                    base64url_decode(&jwk.k)?
+                },
+                _ => return Err(Error::NotSupported),
+            };
+            let data_length = secret.len() as u32 * 8;
+            if data_length == 0 {
+                return Err(Error::Data);
+            }
+            let length = match length {
+                Some(length) if length > data_length || length <= data_length - 8 => return Err(Error::Data),
+                Some(length) => length,
+                None => data_length,
+            };
+            NewKey {
+                key_type: KeyType::Secret,
+                extractable: extractable,
+                algorithm: KeyAlgorithm::Hmac { hash: hash, length: length },
+                usages: usages.to_vec(),
+                handle: Handle::Secret(secret),
+            }
+        },
+        NormalizedAlgorithm::Plain(name) => {
+            // PBKDF2 and HKDF keys.
+            check_usages(usages, DERIVATION_KEY_USAGES)?;
+            if extractable {
+                return Err(Error::Syntax);
+            }
+            let secret = match (format, key_data) {
+                (KeyFormat::Raw, KeyData::Bytes(bytes)) => bytes,
+                _ => return Err(Error::NotSupported),
+            };
+            NewKey {
+                key_type: KeyType::Secret,
+                extractable: false,
+                algorithm: KeyAlgorithm::Plain(name),
+                usages: usages.to_vec(),
+                handle: Handle::Secret(secret),
+            }
+        },
+        NormalizedAlgorithm::EcKey { name, named_curve } => {
+            let (public_usages, private_usages) = key_pair_usages(name);
+            let handle = match (format, key_data) {
+                (KeyFormat::Raw, KeyData::Bytes(bytes)) => {
+                    let ec = ec_public_key_from_bytes(named_curve, &bytes)?;
+                    check_ec_key(named_curve, &ec)?;
+                    Handle::Public(PKey::from_ec_key(ec).map_err(|_| Error::Operation)?)
+                },
+                (KeyFormat::Spki, KeyData::Bytes(bytes)) => {
+                    let pkey = PKey::public_key_from_der(&bytes).map_err(|_| Error::Data)?;
+                    check_ec_key(named_curve, &pkey.ec_key().map_err(|_| Error::Data)?)?;
+                    Handle::Public(pkey)
+                },
+                (KeyFormat::Pkcs8, KeyData::Bytes(bytes)) => {
+                    let pkey = PKey::private_key_from_der(&bytes).map_err(|_| Error::Data)?;
+                    check_ec_key(named_curve, &pkey.ec_key().map_err(|_| Error::Data)?)?;
+                    Handle::Private(pkey)
+                },
+                (KeyFormat::Jwk, KeyData::Jwk(jwk)) => {
+                    if jwk.kty.as_ref().map(|kty| &**kty) != Some("EC") ||
+                       jwk.crv.as_ref().map(|crv| &**crv) != Some(named_curve.as_str()) {
+                        return Err(Error::Data);
+                    }
+                    let coordinate_len = named_curve.coordinate_len();
+                    let x = base64url_decode(&jwk.x)?;
+                    let y = base64url_decode(&jwk.y)?;
+                    if x.len() != coordinate_len || y.len() != coordinate_len {
+                        return Err(Error::Data);
+                    }
+                    let mut point = vec![0x04];
+                    point.extend(x);
+                    point.extend(y);
+                    let public = ec_public_key_from_bytes(named_curve, &point)?;
+                    if jwk.d.is_none() {
+                        check_ec_key(named_curve, &public)?;
+                        Handle::Public(PKey::from_ec_key(public).map_err(|_| Error::Operation)?)
+                    } else {
+                        let d = base64url_decode(&jwk.d)?;
+                        if d.len() != coordinate_len {
+                            return Err(Error::Data);
+                        }
+                        let der = der::ec_private_key(named_curve.oid(), &d, &point);
+                        let private = EcKey::private_key_from_der(&der).map_err(|_| Error::Data)?;
+                        // Checking the key also checks that `d` matches the public point.
+                        check_ec_key(named_curve, &private)?;
+                        Handle::Private(PKey::from_ec_key(private).map_err(|_| Error::Operation)?)
+                    }
+                },
+                _ => return Err(Error::NotSupported),
+            };
+            let key_type = handle.key_type();
+            let allowed = if key_type == KeyType::Public { public_usages } else { private_usages };
+            check_usages(usages, allowed)?;
+            NewKey {
+                key_type: key_type,
+                extractable: extractable,
+                algorithm: KeyAlgorithm::Ec { name: name, named_curve: named_curve },
+                usages: usages.to_vec(),
+                handle: handle,
+            }
+        },
+        NormalizedAlgorithm::RsaHashedImport { name, hash } => {
+            let (public_usages, private_usages) = key_pair_usages(name);
+            let (handle, (modulus_length, public_exponent)) = match (format, key_data) {
+                (KeyFormat::Spki, KeyData::Bytes(bytes)) => {
+                    let pkey = PKey::public_key_from_der(&bytes).map_err(|_| Error::Data)?;
+                    let parameters = rsa_parameters(&pkey.rsa().map_err(|_| Error::Data)?)?;
+                    (Handle::Public(pkey), parameters)
+                },
+                (KeyFormat::Pkcs8, KeyData::Bytes(bytes)) => {
+                    let pkey = PKey::private_key_from_der(&bytes).map_err(|_| Error::Data)?;
+                    let parameters = rsa_parameters(&pkey.rsa().map_err(|_| Error::Data)?)?;
+                    (Handle::Private(pkey), parameters)
+                },
+                (KeyFormat::Jwk, KeyData::Jwk(jwk)) => {
+                    if jwk.kty.as_ref().map(|kty| &**kty) != Some("RSA") {
+                        return Err(Error::Data);
+                    }
+                    let expected_alg = rsa_jwk_alg(name, hash);
+                    if jwk.alg.as_ref().map_or(false, |alg| **alg != *expected_alg) {
+                        return Err(Error::Data);
+                    }
+                    let n = bignum_from_jwk(&jwk.n)?;
+                    let e = bignum_from_jwk(&jwk.e)?;
+                    if jwk.d.is_none() {
+                        let rsa = Rsa::from_public_components(n, e).map_err(|_| Error::Data)?;
+                        let parameters = rsa_parameters(&rsa)?;
+                        (Handle::Public(PKey::from_rsa(rsa).map_err(|_| Error::Operation)?), parameters)
+                    } else {
+                        // FIXME: Keys with more than two primes are not supported.
+                        if jwk.oth.is_some() {
+                            return Err(Error::NotSupported);
+                        }
+                        let rsa = Rsa::from_private_components(n, e,
+                                                               bignum_from_jwk(&jwk.d)?,
+                                                               bignum_from_jwk(&jwk.p)?,
+                                                               bignum_from_jwk(&jwk.q)?,
+                                                               bignum_from_jwk(&jwk.dp)?,
+                                                               bignum_from_jwk(&jwk.dq)?,
+                                                               bignum_from_jwk(&jwk.qi)?)
+                            .map_err(|_| Error::Data)?;
+                        let parameters = rsa_parameters(&rsa)?;
+                        (Handle::Private(PKey::from_rsa(rsa).map_err(|_| Error::Operation)?), parameters)
+                    }
+                },
+                _ => return Err(Error::NotSupported),
+            };
+            let key_type = handle.key_type();
+            let allowed = if key_type == KeyType::Public { public_usages } else { private_usages };
+            check_usages(usages, allowed)?;
+            let algorithm = KeyAlgorithm::RsaHashed {
+                name: name,
+                modulus_length: modulus_length,
+                public_exponent: public_exponent,
+                hash: hash,
+            };
+            NewKey {
+                key_type: key_type,
+                extractable: extractable,
+                algorithm: algorithm,
+                usages: usages.to_vec(),
+                handle: handle,
+            }
+        },
+        _ => return Err(Error::NotSupported),
+    };
+
+    // Step 9.
+    if key.key_type != KeyType::Public && key.usages.is_empty() {
+        return Err(Error::Syntax);
+    }
+    Ok(key)
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-exportKey>
+/// Steps 5-6
+fn export_key(format: KeyFormat, key: &CryptoKey) -> Fallible<CryptoResult> {
+    if !key.extractable() {
+        return Err(Error::InvalidAccess);
+    }
+
+    let algorithm = key.algorithm();
+    match (format, algorithm) {
+        (KeyFormat::Raw, &KeyAlgorithm::Aes { .. }) |
+        (KeyFormat::Raw, &KeyAlgorithm::Hmac { .. }) => Ok(CryptoResult::Bytes(secret(key.handle())?.to_vec())),
+        (KeyFormat::Raw, &KeyAlgorithm::Ec { named_curve, .. }) => {
+            let ec = public_pkey(key.handle())?.ec_key().map_err(|_| Error::Operation)?;
+            Ok(CryptoResult::Bytes(ec_public_point_bytes(named_curve, &ec)?))
+        },
+        (KeyFormat::Spki, &KeyAlgorithm::Ec { .. }) |
+        (KeyFormat::Spki, &KeyAlgorithm::RsaHashed { .. }) => {
+            public_pkey(key.handle())?.public_key_to_der().map(CryptoResult::Bytes).map_err(|_| Error::Operation)
+        },
+        (KeyFormat::Pkcs8, &KeyAlgorithm::Ec { named_curve, .. }) => {
+            let ec = private_pkey(key.handle())?.ec_key().map_err(|_| Error::Operation)?;
+            let mut algorithm_identifier = der::element(der::OBJECT_IDENTIFIER, der::OID_EC_PUBLIC_KEY);
+            algorithm_identifier.extend(der::element(der::OBJECT_IDENTIFIER, named_curve.oid()));
+            let private_key = ec.private_key_to_der().map_err(|_| Error::Operation)?;
+            Ok(CryptoResult::Bytes(der::pkcs8_wrap(&algorithm_identifier, &private_key)))
+        },
+        (KeyFormat::Pkcs8, &KeyAlgorithm::RsaHashed { .. }) => {
+            let rsa = private_pkey(key.handle())?.rsa().map_err(|_| Error::Operation)?;
+            let mut algorithm_identifier = der::element(der::OBJECT_IDENTIFIER, der::OID_RSA_ENCRYPTION);
+            algorithm_identifier.extend(der::element(der::NULL, &[]));
+            let private_key = rsa.private_key_to_der().map_err(|_| Error::Operation)?;
+            Ok(CryptoResult::Bytes(der::pkcs8_wrap(&algorithm_identifier, &private_key)))
+        },
+        (KeyFormat::Jwk, _) => export_jwk(key).map(CryptoResult::Jwk),
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-exportKey>
+/// Step 6, for the "jwk" format.
+fn export_jwk(key: &CryptoKey) -> Fallible<JsonWebKey> {
+    let mut jwk = JsonWebKey::empty();
+    jwk.key_ops = Some(key.usages().iter().map(|usage| DOMString::from(usage.as_str())).collect());
+    jwk.ext = Some(key.extractable());
+
+    match *key.algorithm() {
+        KeyAlgorithm::Aes { name, length } => {
+            jwk.kty = Some(DOMString::from("oct"));
+            jwk.k = Some(base64url_encode(secret(key.handle())?));
+            jwk.alg = Some(DOMString::from(format!("A{}{}", length, &name.as_str()[4..])));
+        },
+        KeyAlgorithm::Hmac { hash, .. } => {
+            jwk.kty = Some(DOMString::from("oct"));
+            jwk.k = Some(base64url_encode(secret(key.handle())?));
+            jwk.alg = Some(DOMString::from(format!("HS{}", hash.jwk_suffix())));
+        },
+        KeyAlgorithm::Ec { named_curve, .. } => {
+            let coordinate_len = named_curve.coordinate_len();
+            let point = match *key.handle() {
+                Handle::Public(ref pkey) => {
+                    ec_public_point_bytes(named_curve, &pkey.ec_key().map_err(|_| Error::Operation)?)?
+                },
+                Handle::Private(ref pkey) => {
+                    let ec = pkey.ec_key().map_err(|_| Error::Operation)?;
+                    let d = ec.private_key().ok_or(Error::Operation)?.to_vec();
+                    jwk.d = Some(base64url_encode(&der::pad_to(d, coordinate_len)));
+                    ec_public_point_bytes(named_curve, &ec)?
+                },
+                Handle::Secret(_) => return Err(Error::Operation),
+            };
+            jwk.kty = Some(DOMString::from("EC"));
+            jwk.crv = Some(DOMString::from(named_curve.as_str()));
+            jwk.x = Some(base64url_encode(&point[1..1 + coordinate_len]));
+            jwk.y = Some(base64url_encode(&point[1 + coordinate_len..]));
+        },
+        KeyAlgorithm::RsaHashed { name, hash, .. } => {
+            jwk.kty = Some(DOMString::from("RSA"));
+            jwk.alg = Some(DOMString::from(rsa_jwk_alg(name, hash)));
+            match *key.handle() {
+                Handle::Public(ref pkey) => {
+                    set_rsa_public_jwk(&mut jwk, &pkey.rsa().map_err(|_| Error::Operation)?)?;
+                },
+                Handle::Private(ref pkey) => {
+                    let rsa = pkey.rsa().map_err(|_| Error::Operation)?;
+                    let component = |value: Option<&BigNumRef>| {
+                        value.map(base64url_bignum).ok_or(Error::Operation)
+                    };
+                    set_rsa_public_jwk(&mut jwk, &rsa)?;
+                    jwk.d = Some(component(rsa.d())?);
+                    jwk.p = Some(component(rsa.p())?);
+                    jwk.q = Some(component(rsa.q())?);
+                    jwk.dp = Some(component(rsa.dp())?);
+                    jwk.dq = Some(component(rsa.dq())?);
+                    jwk.qi = Some(component(rsa.qi())?);
+                },
+                Handle::Secret(_) => return Err(Error::Operation),
+            }
+        },
+        KeyAlgorithm::Plain(_) => return Err(Error::NotSupported),
+    }
+    Ok(jwk)
+}
+
+/// The `keyData` argument of `importKey()`.
+enum KeyData {
+    Bytes(Vec<u8>),
+    Jwk(JsonWebKey),
+}
+
+impl SubtleCryptoMethods for SubtleCrypto {
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-encrypt
+    unsafe fn Encrypt(&self,
+                      cx: *mut JSContext,
+                      algorithm: ObjectOrString,
+                      key: &CryptoKey,
+                      data: ArrayBufferViewOrArrayBuffer)
+                      -> Rc<Promise> {
+        let data = buffer_source_to_vec(&data);
+        let operation = normalize_algorithm(cx, &algorithm, Operation::Encrypt).and_then(|algorithm| {
+            // Step 9.
+            check_key(key, &algorithm, KeyUsage::Encrypt)?;
+            let key = OperationKey::new(key);
+            Ok(move || encrypt(&algorithm, &key, &data).map(CryptoResult::Bytes))
+        });
+        self.settle_in_parallel(operation)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-decrypt
+    unsafe fn Decrypt(&self,
+                      cx: *mut JSContext,
+                      algorithm: ObjectOrString,
+                      key: &CryptoKey,
+                      data: ArrayBufferViewOrArrayBuffer)
+                      -> Rc<Promise> {
+        let data = buffer_source_to_vec(&data);
+        let operation = normalize_algorithm(cx, &algorithm, Operation::Decrypt).and_then(|algorithm| {
+            // Step 9.
+            check_key(key, &algorithm, KeyUsage::Decrypt)?;
+            let key = OperationKey::new(key);
+            Ok(move || decrypt(&algorithm, &key, &data).map(CryptoResult::Bytes))
+        });
+        self.settle_in_parallel(operation)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-sign
+    unsafe fn Sign(&self,
+                   cx: *mut JSContext,
+                   algorithm: ObjectOrString,
+                   key: &CryptoKey,
+                   data: ArrayBufferViewOrArrayBuffer)
+                   -> Rc<Promise> {
+        let data = buffer_source_to_vec(&data);
+        let operation = normalize_algorithm(cx, &algorithm, Operation::Sign).and_then(|algorithm| {
+            // Step 9.
+            check_key(key, &algorithm, KeyUsage::Sign)?;
+            let key = OperationKey::new(key);
+            Ok(move || sign(&algorithm, &key, &data).map(CryptoResult::Bytes))
+        });
+        self.settle_in_parallel(operation)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-verify
+    unsafe fn Verify(&self,
+                     cx: *mut JSContext,
+                     algorithm: ObjectOrString,
+                     key: &CryptoKey,
+                     signature: ArrayBufferViewOrArrayBuffer,
+                     data: ArrayBufferViewOrArrayBuffer)
+                     -> Rc<Promise> {
+        let signature = buffer_source_to_vec(&signature);
+        let data = buffer_source_to_vec(&data);
+        let operation = normalize_algorithm(cx, &algorithm, Operation::Verify).and_then(|algorithm| {
+            // Step 10.
+            check_key(key, &algorithm, KeyUsage::Verify)?;
+            let key = OperationKey::new(key);
+            Ok(move || verify(&algorithm, &key, &signature, &data).map(CryptoResult::Boolean))
+        });
+        self.settle_in_parallel(operation)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-digest
+    unsafe fn Digest(&self,
+                     cx: *mut JSContext,
+                     algorithm: ObjectOrString,
+                     data: ArrayBufferViewOrArrayBuffer)
+                     -> Rc<Promise> {
+        let result = normalize_algorithm(cx, &algorithm, Operation::Digest).and_then(|algorithm| {
+            match algorithm {
+                NormalizedAlgorithm::Digest(digest) => digest.digest(&buffer_source_to_vec(&data)),
+                _ => Err(Error::NotSupported),
+            }
+        }).map(CryptoResult::Bytes);
+        self.settle(cx, result)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-generateKey
+    unsafe fn GenerateKey(&self,
+                          cx: *mut JSContext,
+                          algorithm: ObjectOrString,
+                          extractable: bool,
+                          key_usages: Vec<KeyUsage>)
+                          -> Rc<Promise> {
+        let operation = normalize_algorithm(cx, &algorithm, Operation::GenerateKey).map(|algorithm| {
+            move || generate_key(&algorithm, extractable, &key_usages)
+        });
+        self.settle_in_parallel(operation)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-deriveKey
+    unsafe fn DeriveKey(&self,
+                        cx: *mut JSContext,
+                        algorithm: ObjectOrString,
+                        base_key: &CryptoKey,
+                        derived_key_type: ObjectOrString,
+                        extractable: bool,
+                        key_usages: Vec<KeyUsage>)
+                        -> Rc<Promise> {
+        let operation = normalize_algorithm(cx, &algorithm, Operation::DeriveBits).and_then(|algorithm| {
+            // Steps 2-5.
+            let import = normalize_algorithm(cx, &derived_key_type, Operation::ImportKey)?;
+            let key_length = normalize_algorithm(cx, &derived_key_type, Operation::GetKeyLength)?;
+            // Step 11.
+            if !base_key.has_usage(KeyUsage::DeriveKey) {
+                return Err(Error::InvalidAccess);
+            }
+            let base_key = OperationKey::new(base_key);
+            Ok(move || {
+                // Steps 12-14.
+                let length = get_key_length(&key_length)?;
+                let secret = derive_bits(&algorithm, &base_key, length)?;
+                // Steps 15-16.
+                import_key(KeyFormat::Raw, KeyData::Bytes(secret), &import, extractable, &key_usages)
+                    .map(CryptoResult::Key)
+            })
+        });
+        self.settle_in_parallel(operation)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-deriveBits
+    unsafe fn DeriveBits(&self,
+                         cx: *mut JSContext,
+                         algorithm: ObjectOrString,
+                         base_key: &CryptoKey,
+                         length: u32)
+                         -> Rc<Promise> {
+        let operation = normalize_algorithm(cx, &algorithm, Operation::DeriveBits).and_then(|algorithm| {
+            // Step 9.
+            if !base_key.has_usage(KeyUsage::DeriveBits) {
+                return Err(Error::InvalidAccess);
+            }
+            let base_key = OperationKey::new(base_key);
+            Ok(move || derive_bits(&algorithm, &base_key, length).map(CryptoResult::Bytes))
+        });
+        self.settle_in_parallel(operation)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-importKey
+    unsafe fn ImportKey(&self,
+                        cx: *mut JSContext,
+                        format: KeyFormat,
+                        key_data: BufferSourceOrJsonWebKey,
+                        algorithm: ObjectOrString,
+                        extractable: bool,
+                        key_usages: Vec<KeyUsage>)
+                        -> Rc<Promise> {
+        // Steps 2-3.
+        let key_data = match (format, key_data) {
+            (KeyFormat::Jwk, BufferSourceOrJsonWebKey::JsonWebKey(jwk)) => Ok(KeyData::Jwk(jwk)),
+            (KeyFormat::Jwk, _) => Err(Error::Type("JWK key data must be a JsonWebKey dictionary".to_owned())),
+            (_, BufferSourceOrJsonWebKey::ArrayBufferView(view)) => Ok(KeyData::Bytes(view.to_vec())),
+            (_, BufferSourceOrJsonWebKey::ArrayBuffer(buffer)) => Ok(KeyData::Bytes(buffer.to_vec())),
+            (_, BufferSourceOrJsonWebKey::JsonWebKey(_)) => {
+                Err(Error::Type("Key data must be a BufferSource".to_owned()))
+            },
+        };
+        let result = key_data.and_then(|key_data| {
+            let algorithm = normalize_algorithm(cx, &algorithm, Operation::ImportKey)?;
+            import_key(format, key_data, &algorithm, extractable, &key_usages)
+        }).map(CryptoResult::Key);
+        self.settle(cx, result)
+    }
+
+    #[allow(unsafe_code)]
+    // https://w3c.github.io/webcrypto/#dfn-SubtleCrypto-method-exportKey
+    fn ExportKey(&self, format: KeyFormat, key: &CryptoKey) -> Rc<Promise> {
+        let result = export_key(format, key);
+        unsafe { self.settle(self.global().get_cx(), result) }
+    }
+}
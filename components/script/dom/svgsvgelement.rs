@@ -6,16 +6,19 @@ use dom::attr::Attr;
 use dom::bindings::codegen::Bindings::SVGSVGElementBinding;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::root::{DomRoot, LayoutDom};
-use dom::bindings::str::DOMString;
+use dom::characterdata::{CharacterData, LayoutCharacterDataHelpers};
 use dom::document::Document;
 use dom::element::{AttributeMutation, Element, RawLayoutElementHelpers};
-use dom::node::Node;
+use dom::node::{LayoutNodeHelpers, Node, NodeDamage};
 use dom::svggraphicselement::SVGGraphicsElement;
+use dom::text::Text;
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
 use script_layout_interface::SVGSVGData;
-use style::attr::AttrValue;
+use std::sync::Arc;
+use style::dom::OpaqueNode;
+use svg_renderer::{NodeId, SvgImage, Tree};
 
 const DEFAULT_WIDTH: u32 = 300;
 const DEFAULT_HEIGHT: u32 = 150;
@@ -45,6 +48,20 @@ impl SVGSVGElement {
     }
 }
 
+/// Damages the outermost `<svg>` element around a node of SVG content, which
+/// layout renders as a whole, after the content changed.
+pub fn dirty_outermost_svg_element(node: &Node) {
+    let outermost = node.inclusive_ancestors()
+        .take_while(|ancestor| {
+            ancestor.downcast::<Element>().map_or(false, |element| *element.namespace() == ns!(svg))
+        })
+        .filter(|ancestor| ancestor.is::<SVGSVGElement>())
+        .last();
+    if let Some(svg) = outermost {
+        svg.dirty(NodeDamage::OtherNodeDamage);
+    }
+}
+
 pub trait LayoutSVGSVGElementHelpers {
     fn data(&self) -> SVGSVGData;
 }
@@ -52,16 +69,58 @@ pub trait LayoutSVGSVGElementHelpers {
 impl LayoutSVGSVGElementHelpers for LayoutDom<SVGSVGElement> {
     #[allow(unsafe_code)]
     fn data(&self) -> SVGSVGData {
+        let mut tree = Tree::new();
+        let mut nodes = vec![];
         unsafe {
-            let SVG = &*self.unsafe_get();
+            append_to_tree(&mut tree, &mut nodes, None, self.upcast::<Node>());
+        }
+        let document = SvgImage::from_tree(tree).map(Arc::new);
+        let (width, height) = match document {
+            Some(ref document) => {
+                let size = document.default_size();
+                (size.width.round() as u32, size.height.round() as u32)
+            },
+            None => (DEFAULT_WIDTH, DEFAULT_HEIGHT),
+        };
+        SVGSVGData {
+            width: width,
+            height: height,
+            document: document,
+            nodes: Arc::new(nodes),
+        }
+    }
+}
+
+/// Appends an element to a tree, with its SVG descendants and their text,
+/// and records the DOM node of each node of the tree.
+#[allow(unsafe_code)]
+unsafe fn append_to_tree(tree: &mut Tree,
+                         nodes: &mut Vec<OpaqueNode>,
+                         parent: Option<NodeId>,
+                         node: LayoutDom<Node>) {
+    if let Some(element) = node.downcast::<Element>() {
+        let element = &*element.unsafe_get();
+        if *element.namespace() != ns!(svg) {
+            return;
+        }
+        // Attributes in other namespaces than XLink are ignored, like in
+        // documents used as images.
+        let attributes = element.get_attrs_for_layout().into_iter()
+            .filter(|&(namespace, _, _)| *namespace == ns!() || *namespace == ns!(xlink))
+            .map(|(_, name, value)| (name.to_string(), value.to_owned()))
+            .collect();
+        let id = tree.append_element(parent, element.local_name(), attributes);
+        nodes.push(node.opaque());
 
-            let width_attr = SVG.upcast::<Element>().get_attr_for_layout(&ns!(), &local_name!("width"));
-            let height_attr = SVG.upcast::<Element>().get_attr_for_layout(&ns!(), &local_name!("height"));
-            SVGSVGData {
-                width: width_attr.map_or(DEFAULT_WIDTH, |val| val.as_uint()),
-                height: height_attr.map_or(DEFAULT_HEIGHT, |val| val.as_uint()),
-            }
+        let mut child = node.first_child_ref();
+        while let Some(current) = child {
+            append_to_tree(tree, nodes, Some(id), current);
+            child = current.next_sibling_ref();
         }
+    } else if let (Some(parent), Some(text)) = (parent, node.downcast::<Text>()) {
+        let data = text.upcast::<CharacterData>().data_for_layout().to_owned();
+        tree.append_text(parent, data);
+        nodes.push(node.opaque());
     }
 }
 
@@ -73,12 +132,4 @@ impl VirtualMethods for SVGSVGElement {
     fn attribute_mutated(&self, attr: &Attr, mutation: AttributeMutation) {
         self.super_type().unwrap().attribute_mutated(attr, mutation);
     }
-
-    fn parse_plain_attribute(&self, name: &LocalName, value: DOMString) -> AttrValue {
-        match name {
-            &local_name!("width") => AttrValue::from_u32(value.into(), DEFAULT_WIDTH),
-            &local_name!("height") => AttrValue::from_u32(value.into(), DEFAULT_HEIGHT),
-            _ => self.super_type().unwrap().parse_plain_attribute(name, value),
-        }
-    }
 }
@@ -0,0 +1,58 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::TaskAttributionTimingBinding;
+use dom::bindings::codegen::Bindings::TaskAttributionTimingBinding::TaskAttributionTimingMethods;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::performanceentry::PerformanceEntry;
+use dom::window::Window;
+use dom_struct::dom_struct;
+
+#[dom_struct]
+pub struct TaskAttributionTiming {
+    entry: PerformanceEntry,
+    container_type: DOMString,
+}
+
+impl TaskAttributionTiming {
+    fn new_inherited(container_type: DOMString) -> TaskAttributionTiming {
+        TaskAttributionTiming {
+            entry: PerformanceEntry::new_inherited(DOMString::from("unknown"),
+                                                   DOMString::from("taskattribution"),
+                                                   0.,
+                                                   0.),
+            container_type,
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(window: &Window, container_type: DOMString) -> DomRoot<TaskAttributionTiming> {
+        let entry = TaskAttributionTiming::new_inherited(container_type);
+        reflect_dom_object(Box::new(entry), window, TaskAttributionTimingBinding::Wrap)
+    }
+}
+
+impl TaskAttributionTimingMethods for TaskAttributionTiming {
+    // https://w3c.github.io/longtasks/#dom-taskattributiontiming-containertype
+    fn ContainerType(&self) -> DOMString {
+        self.container_type.clone()
+    }
+
+    // https://w3c.github.io/longtasks/#dom-taskattributiontiming-containersrc
+    fn ContainerSrc(&self) -> DOMString {
+        DOMString::new()
+    }
+
+    // https://w3c.github.io/longtasks/#dom-taskattributiontiming-containerid
+    fn ContainerId(&self) -> DOMString {
+        DOMString::new()
+    }
+
+    // https://w3c.github.io/longtasks/#dom-taskattributiontiming-containername
+    fn ContainerName(&self) -> DOMString {
+        DOMString::new()
+    }
+}
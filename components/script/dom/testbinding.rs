@@ -804,6 +804,10 @@ impl TestBindingMethods for TestBinding {
 
     fn Panic(&self) { panic!("explicit panic from script") }
 
+    fn SetVisible(&self, visible: bool) {
+        self.global().as_window().alter_resource_utilization(visible);
+    }
+
     fn EntryGlobal(&self) -> DomRoot<GlobalScope> {
         GlobalScope::entry()
     }
@@ -9,24 +9,31 @@ use dom::bindings::codegen::Bindings::TextBinding::{self, TextMethods};
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::error::{Error, Fallible};
 use dom::bindings::inheritance::Castable;
-use dom::bindings::root::{DomRoot, RootedReference};
+use dom::bindings::root::{DomRoot, LayoutDom, RootedReference};
 use dom::bindings::str::DOMString;
-use dom::characterdata::CharacterData;
+use dom::characterdata::{CharacterData, LayoutCharacterDataHelpers};
 use dom::document::Document;
-use dom::node::Node;
+use dom::htmlslotelement::HTMLSlotElement;
+use dom::node::{Node, NodeDamage};
 use dom::window::Window;
 use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::ops::Range;
 
 /// An HTML text node.
 #[dom_struct]
 pub struct Text {
     characterdata: CharacterData,
+    /// The part of this node that is painted as selected by the document's
+    /// selection, in UTF-16 code units. An empty range is a caret.
+    selected_range: Cell<Option<(u32, u32)>>,
 }
 
 impl Text {
     fn new_inherited(text: DOMString, document: &Document) -> Text {
         Text {
-            characterdata: CharacterData::new_inherited(text, document)
+            characterdata: CharacterData::new_inherited(text, document),
+            selected_range: Cell::new(None),
         }
     }
 
@@ -39,6 +46,49 @@ impl Text {
         let document = window.Document();
         Ok(Text::new(text, &document))
     }
+
+    /// Set the part of this node that is highlighted by the document's selection,
+    /// and make sure layout repaints it.
+    pub fn set_selected_range(&self, range: Option<(u32, u32)>) {
+        if self.selected_range.get() == range {
+            return;
+        }
+        self.selected_range.set(range);
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+}
+
+pub trait LayoutTextHelpers {
+    unsafe fn selection_for_layout(&self) -> Option<Range<usize>>;
+}
+
+#[allow(unsafe_code)]
+impl LayoutTextHelpers for LayoutDom<Text> {
+    unsafe fn selection_for_layout(&self) -> Option<Range<usize>> {
+        let (start, end) = (*self.unsafe_get()).selected_range.get()?;
+        let characterdata = self.upcast::<CharacterData>();
+        let data = characterdata.data_for_layout();
+        let start = utf16_offset_to_byte_index(data, start);
+        let end = utf16_offset_to_byte_index(data, end);
+        // An empty range is painted as a caret.
+        if start > end {
+            return None;
+        }
+        Some(start..end)
+    }
+}
+
+/// The byte index in `s` of the given offset in UTF-16 code units, clamped to
+/// the length of `s`.
+fn utf16_offset_to_byte_index(s: &str, offset: u32) -> usize {
+    let mut code_units = 0;
+    for (i, c) in s.char_indices() {
+        if code_units >= offset {
+            return i;
+        }
+        code_units += c.len_utf16() as u32;
+    }
+    s.len()
 }
 
 impl TextMethods for Text {
@@ -90,4 +140,9 @@ impl TextMethods for Text {
         }
         DOMString::from(text)
     }
+
+    // https://dom.spec.whatwg.org/#dom-slotable-assignedslot
+    fn GetAssignedSlot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        HTMLSlotElement::find_a_slot(self.upcast(), true)
+    }
 }
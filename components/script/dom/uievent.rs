@@ -27,8 +27,11 @@ pub struct UIEvent {
 
 impl UIEvent {
     pub fn new_inherited() -> UIEvent {
+        let event = Event::new_inherited();
+        // UI events fired by the user agent cross shadow boundaries.
+        event.set_composed(true);
         UIEvent {
-            event: Event::new_inherited(),
+            event,
             view: Default::default(),
             detail: Cell::new(0),
         }
@@ -60,6 +63,7 @@ impl UIEvent {
                                  type_,
                                  bubbles, cancelable,
                                  init.view.r(), init.detail);
+        event.upcast::<Event>().set_composed(init.parent.composed);
         Ok(event)
     }
 }
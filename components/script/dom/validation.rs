@@ -5,5 +5,7 @@ use dom::validitystate::ValidationFlags;
 
 pub trait Validatable {
     fn is_instance_validatable(&self) -> bool { true }
+
+    /// Whether the element satisfies the constraints in `validate_flags`.
     fn validate(&self, _validate_flags: ValidationFlags) -> bool { true }
 }
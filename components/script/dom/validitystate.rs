@@ -28,6 +28,7 @@ pub enum ValidityStatus {
 }
 
 bitflags!{
+    #[derive(JSTraceable, MallocSizeOf)]
     pub struct ValidationFlags: u32 {
         const VALUE_MISSING    = 0b0000000001;
         const TYPE_MISMATCH    = 0b0000000010;
@@ -65,61 +66,66 @@ impl ValidityState {
                            window,
                            ValidityStateBinding::Wrap)
     }
+
+    /// Whether the element suffers from the validity states in `flags`.
+    fn suffers_from(&self, flags: ValidationFlags) -> bool {
+        self.element.as_maybe_validatable().map_or(false, |validatable| !validatable.validate(flags))
+    }
 }
 
 impl ValidityStateMethods for ValidityState {
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-valuemissing
     fn ValueMissing(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::VALUE_MISSING)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-typemismatch
     fn TypeMismatch(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::TYPE_MISMATCH)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-patternmismatch
     fn PatternMismatch(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::PATTERN_MISMATCH)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-toolong
     fn TooLong(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::TOO_LONG)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-tooshort
     fn TooShort(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::TOO_SHORT)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-rangeunderflow
     fn RangeUnderflow(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::RANGE_UNDERFLOW)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-rangeoverflow
     fn RangeOverflow(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::RANGE_OVERFLOW)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-stepmismatch
     fn StepMismatch(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::STEP_MISMATCH)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-badinput
     fn BadInput(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::BAD_INPUT)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-customerror
     fn CustomError(&self) -> bool {
-        false
+        self.suffers_from(ValidationFlags::CUSTOM_ERROR)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-valid
     fn Valid(&self) -> bool {
-        false
+        !self.suffers_from(ValidationFlags::all())
     }
 }
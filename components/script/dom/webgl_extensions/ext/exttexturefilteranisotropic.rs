@@ -0,0 +1,43 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::EXTTextureFilterAnisotropicBinding;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions};
+
+#[dom_struct]
+pub struct EXTTextureFilterAnisotropic {
+    reflector_: Reflector,
+}
+
+impl EXTTextureFilterAnisotropic {
+    fn new_inherited() -> EXTTextureFilterAnisotropic {
+        Self {
+            reflector_: Reflector::new(),
+        }
+    }
+}
+
+impl WebGLExtension for EXTTextureFilterAnisotropic {
+    fn name() -> &'static str {
+        "EXT_texture_filter_anisotropic"
+    }
+
+    fn is_supported(extensions: &WebGLExtensions) -> bool {
+        extensions.supports_gl_extension("GL_EXT_texture_filter_anisotropic")
+    }
+
+    fn new(context: &WebGLRenderingContext) -> DomRoot<Self> {
+        reflect_dom_object(Box::new(Self::new_inherited()),
+                           &*context.global(),
+                           EXTTextureFilterAnisotropicBinding::Wrap)
+    }
+
+    fn enable(extensions: &WebGLExtensions) {
+        extensions.enable_anisotropic_filtering();
+    }
+}
@@ -0,0 +1,44 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::OESElementIndexUintBinding;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions};
+
+#[dom_struct]
+pub struct OESElementIndexUint {
+    reflector_: Reflector,
+}
+
+impl OESElementIndexUint {
+    fn new_inherited() -> OESElementIndexUint {
+        Self {
+            reflector_: Reflector::new(),
+        }
+    }
+}
+
+impl WebGLExtension for OESElementIndexUint {
+    fn name() -> &'static str {
+        "OES_element_index_uint"
+    }
+
+    fn is_supported(extensions: &WebGLExtensions) -> bool {
+        // Desktop GL always accepts UNSIGNED_INT indices.
+        !extensions.is_gles() || extensions.supports_gl_extension("GL_OES_element_index_uint")
+    }
+
+    fn new(context: &WebGLRenderingContext) -> DomRoot<Self> {
+        reflect_dom_object(Box::new(Self::new_inherited()),
+                           &*context.global(),
+                           OESElementIndexUintBinding::Wrap)
+    }
+
+    fn enable(extensions: &WebGLExtensions) {
+        extensions.enable_element_index_uint();
+    }
+}
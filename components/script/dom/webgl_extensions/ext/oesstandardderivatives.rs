@@ -0,0 +1,44 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::OESStandardDerivativesBinding;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions};
+
+#[dom_struct]
+pub struct OESStandardDerivatives {
+    reflector_: Reflector,
+}
+
+impl OESStandardDerivatives {
+    fn new_inherited() -> OESStandardDerivatives {
+        Self {
+            reflector_: Reflector::new(),
+        }
+    }
+}
+
+impl WebGLExtension for OESStandardDerivatives {
+    fn name() -> &'static str {
+        "OES_standard_derivatives"
+    }
+
+    fn is_supported(extensions: &WebGLExtensions) -> bool {
+        // Derivatives are part of desktop GLSL.
+        !extensions.is_gles() || extensions.supports_gl_extension("GL_OES_standard_derivatives")
+    }
+
+    fn new(context: &WebGLRenderingContext) -> DomRoot<Self> {
+        reflect_dom_object(Box::new(Self::new_inherited()),
+                           &*context.global(),
+                           OESStandardDerivativesBinding::Wrap)
+    }
+
+    fn enable(extensions: &WebGLExtensions) {
+        extensions.enable_standard_derivatives();
+    }
+}
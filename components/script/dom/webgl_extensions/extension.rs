@@ -0,0 +1,26 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::reflector::DomObject;
+use dom::bindings::root::DomRoot;
+use dom::bindings::trace::JSTraceable;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use super::WebGLExtensions;
+
+/// Trait implemented by the DOM objects of WebGL extensions.
+pub trait WebGLExtension: DomObject + JSTraceable {
+    /// The name of the extension, as passed to `getExtension`.
+    fn name() -> &'static str;
+
+    /// Whether the GL context backing `extensions` can implement this
+    /// extension.
+    fn is_supported(extensions: &WebGLExtensions) -> bool;
+
+    /// Creates the object exposed to script for this extension.
+    fn new(context: &WebGLRenderingContext) -> DomRoot<Self>;
+
+    /// Enables the features of this extension, called the first time it is
+    /// requested.
+    fn enable(extensions: &WebGLExtensions);
+}
@@ -0,0 +1,138 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::reflector::DomObject;
+use dom::bindings::root::MutNullableDom;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use fnv::FnvHashSet;
+use js::jsapi::JSObject;
+use std::cell::Cell;
+use std::ptr::NonNull;
+use super::WebGLExtension;
+use super::ext::exttexturefilteranisotropic::EXTTextureFilterAnisotropic;
+use super::ext::oeselementindexuint::OESElementIndexUint;
+use super::ext::oesstandardderivatives::OESStandardDerivatives;
+
+/// The extensions of a WebGL context, and the features they enabled.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct WebGLExtensions {
+    /// The extensions supported by the GL context.
+    gl_extensions: DomRefCell<FnvHashSet<String>>,
+    /// Whether the GL context runs OpenGL ES, where features that desktop GL
+    /// always has need an extension.
+    is_gles: Cell<bool>,
+    ext_texture_filter_anisotropic: MutNullableDom<EXTTextureFilterAnisotropic>,
+    oes_element_index_uint: MutNullableDom<OESElementIndexUint>,
+    oes_standard_derivatives: MutNullableDom<OESStandardDerivatives>,
+    anisotropic_filtering_enabled: Cell<bool>,
+    element_index_uint_enabled: Cell<bool>,
+    standard_derivatives_enabled: Cell<bool>,
+}
+
+impl WebGLExtensions {
+    pub fn new() -> WebGLExtensions {
+        WebGLExtensions {
+            gl_extensions: DomRefCell::new(FnvHashSet::default()),
+            is_gles: Cell::new(false),
+            ext_texture_filter_anisotropic: Default::default(),
+            oes_element_index_uint: Default::default(),
+            oes_standard_derivatives: Default::default(),
+            anisotropic_filtering_enabled: Cell::new(false),
+            element_index_uint_enabled: Cell::new(false),
+            standard_derivatives_enabled: Cell::new(false),
+        }
+    }
+
+    /// Records the extensions of the GL context, as returned by
+    /// `glGetString(GL_EXTENSIONS)`.
+    pub fn init_once<F: FnOnce() -> String>(&self, is_gles: bool, get_gl_extensions: F) {
+        if !self.gl_extensions.borrow().is_empty() {
+            return;
+        }
+        self.is_gles.set(is_gles);
+        let gl_extensions = get_gl_extensions();
+        *self.gl_extensions.borrow_mut() = gl_extensions.split_whitespace().map(String::from).collect();
+    }
+
+    pub fn supports_gl_extension(&self, name: &str) -> bool {
+        self.gl_extensions.borrow().contains(name)
+    }
+
+    pub fn is_gles(&self) -> bool {
+        self.is_gles.get()
+    }
+
+    /// The names of the extensions that `getExtension` can return.
+    pub fn get_supported_extensions(&self) -> Vec<&'static str> {
+        let mut names = vec![];
+        if EXTTextureFilterAnisotropic::is_supported(self) {
+            names.push(EXTTextureFilterAnisotropic::name());
+        }
+        if OESElementIndexUint::is_supported(self) {
+            names.push(OESElementIndexUint::name());
+        }
+        if OESStandardDerivatives::is_supported(self) {
+            names.push(OESStandardDerivatives::name());
+        }
+        names
+    }
+
+    /// Returns the object of the extension named `name`, enabling it the
+    /// first time it is requested. Extension names are case-insensitive.
+    pub fn get_or_init_extension(&self,
+                                 name: &str,
+                                 context: &WebGLRenderingContext)
+                                 -> Option<NonNull<JSObject>> {
+        if name.eq_ignore_ascii_case(EXTTextureFilterAnisotropic::name()) {
+            self.get_or_init(&self.ext_texture_filter_anisotropic, context)
+        } else if name.eq_ignore_ascii_case(OESElementIndexUint::name()) {
+            self.get_or_init(&self.oes_element_index_uint, context)
+        } else if name.eq_ignore_ascii_case(OESStandardDerivatives::name()) {
+            self.get_or_init(&self.oes_standard_derivatives, context)
+        } else {
+            None
+        }
+    }
+
+    fn get_or_init<T: WebGLExtension>(&self,
+                                      extension: &MutNullableDom<T>,
+                                      context: &WebGLRenderingContext)
+                                      -> Option<NonNull<JSObject>> {
+        if !T::is_supported(self) {
+            return None;
+        }
+        let extension = extension.or_init(|| {
+            T::enable(self);
+            T::new(context)
+        });
+        // The extension object is kept alive by this registry.
+        NonNull::new(extension.reflector().get_jsobject().get())
+    }
+
+    pub fn enable_anisotropic_filtering(&self) {
+        self.anisotropic_filtering_enabled.set(true);
+    }
+
+    pub fn is_anisotropic_filtering_enabled(&self) -> bool {
+        self.anisotropic_filtering_enabled.get()
+    }
+
+    pub fn enable_element_index_uint(&self) {
+        self.element_index_uint_enabled.set(true);
+    }
+
+    pub fn is_element_index_uint_enabled(&self) -> bool {
+        self.element_index_uint_enabled.get()
+    }
+
+    pub fn enable_standard_derivatives(&self) {
+        self.standard_derivatives_enabled.set(true);
+    }
+
+    pub fn is_standard_derivatives_enabled(&self) -> bool {
+        self.standard_derivatives_enabled.get()
+    }
+}
@@ -0,0 +1,15 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! WebGL extensions, which are only exposed to script once they are
+//! requested with `getExtension`.
+//!
+//! <https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.14>
+
+pub mod ext;
+mod extension;
+mod extensions;
+
+pub use self::extension::WebGLExtension;
+pub use self::extensions::WebGLExtensions;
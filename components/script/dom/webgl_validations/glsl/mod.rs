@@ -0,0 +1,153 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Validation of WebGL shader sources, and their translation to the GLSL
+//! dialect of the underlying GL context.
+//!
+//! Sources are preprocessed and parsed against the grammar of OpenGL ES
+//! Shading Language 1.00, and must also follow the restrictions WebGL adds
+//! on top of it. The native compiler is only given the tokens that were
+//! parsed, never the source itself:
+//!
+//! <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>
+//! <https://www.khronos.org/registry/webgl/specs/latest/1.0/#6>
+
+mod parser;
+mod preprocessor;
+
+use self::preprocessor::{Token, TokenKind};
+
+/// The maximum length of an identifier.
+///
+/// <https://www.khronos.org/registry/webgl/specs/latest/1.0/#6.22>
+const MAX_IDENTIFIER_LENGTH: usize = 256;
+
+/// How deep expressions, statements and macro arguments may nest, which
+/// bounds the recursion of the parsers.
+const MAX_NESTING_DEPTH: usize = 128;
+
+/// The prefixes of identifiers reserved by WebGL.
+///
+/// <https://www.khronos.org/registry/webgl/specs/latest/1.0/#6.17>
+const RESERVED_PREFIXES: &'static [&'static str] = &["webgl_", "_webgl_"];
+
+const DERIVATIVE_FUNCTIONS: &'static [&'static str] = &["dFdx", "dFdy", "fwidth"];
+
+const STANDARD_DERIVATIVES: &'static str = "GL_OES_standard_derivatives";
+
+/// Operators made of two characters, which two tokens must not be merged
+/// into when written next to each other.
+const TWO_CHARACTER_OPERATORS: &'static [&'static str] = &[
+    "++", "--", "<=", ">=", "==", "!=", "&&", "||", "^^", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<", ">>",
+];
+
+#[derive(Clone, Copy, Debug, Eq, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum ShaderKind {
+    Vertex,
+    Fragment,
+}
+
+/// What the translated shader is compiled for.
+#[derive(Clone, Copy, Debug)]
+pub struct TranslationOptions {
+    pub kind: ShaderKind,
+    /// The GLSL version of the GL context, as in a `#version` directive,
+    /// 100 meaning that the context runs OpenGL ES.
+    pub glsl_version: u32,
+    /// Whether `OES_standard_derivatives` was enabled on the context.
+    pub standard_derivatives: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShaderError {
+    pub line: u32,
+    pub message: String,
+}
+
+impl ShaderError {
+    fn new(line: u32, message: String) -> ShaderError {
+        ShaderError { line, message }
+    }
+
+    /// Formats the error the way shader info logs usually are.
+    pub fn to_info_log(&self) -> String {
+        format!("ERROR: 0:{}: {}\n", self.line, self.message)
+    }
+}
+
+/// Validates a shader source and translates it for the native compiler.
+pub fn translate(source: &str, options: &TranslationOptions) -> Result<String, ShaderError> {
+    let preprocessed = preprocessor::preprocess(source, options)?;
+    for token in preprocessed.tokens.iter().filter(|token| token.kind == TokenKind::Identifier) {
+        check_identifier(token, options, preprocessed.derivatives_enabled)?;
+    }
+    let parsed = parser::parse(&preprocessed.tokens, options.kind)?;
+    if !parsed.has_main {
+        let line = preprocessed.tokens.last().map_or(1, |token| token.line);
+        return Err(ShaderError::new(line, "missing main function".to_owned()));
+    }
+
+    // Every token is written on the line it came from, to keep line numbers
+    // in the native compiler's messages meaningful. Precision qualifiers
+    // don't exist in desktop GLSL before 1.30.
+    let strip_precision = options.glsl_version != 100 && options.glsl_version < 130;
+    let mut lines = vec![String::new(); source.split('\n').count()];
+    for &(line, ref directive) in &preprocessed.directives {
+        lines[line as usize - 1] = directive.clone();
+    }
+    for (index, token) in preprocessed.tokens.iter().enumerate() {
+        if strip_precision && parsed.precision_tokens[index] {
+            continue;
+        }
+        let line = &mut lines[token.line as usize - 1];
+        if needs_space(line, token) {
+            line.push(' ');
+        }
+        line.push_str(&token.text);
+    }
+    Ok(format!("#version {}\n{}", options.glsl_version, lines.join("\n")))
+}
+
+/// Checks the restrictions WebGL puts on identifiers.
+fn check_identifier(token: &Token,
+                    options: &TranslationOptions,
+                    derivatives_enabled: bool)
+                    -> Result<(), ShaderError> {
+    let identifier = &*token.text;
+    if identifier.len() > MAX_IDENTIFIER_LENGTH {
+        return Err(ShaderError::new(
+            token.line,
+            format!("identifier is longer than {} characters", MAX_IDENTIFIER_LENGTH),
+        ));
+    }
+    if RESERVED_PREFIXES.iter().any(|prefix| identifier.starts_with(prefix)) {
+        return Err(ShaderError::new(
+            token.line,
+            format!("'{}' uses a prefix reserved by WebGL", identifier),
+        ));
+    }
+    if DERIVATIVE_FUNCTIONS.contains(&identifier) && (options.kind == ShaderKind::Vertex || !derivatives_enabled) {
+        return Err(ShaderError::new(
+            token.line,
+            format!("'{}' requires the {} extension", identifier, STANDARD_DERIVATIVES),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `token` must be separated from what precedes it on `line`,
+/// either because it was in the source or because the two would read as
+/// a different token otherwise.
+fn needs_space(line: &str, token: &Token) -> bool {
+    let last = match line.chars().last() {
+        Some(last) => last,
+        None => return false,
+    };
+    let first = token.text.chars().next().unwrap();
+    let is_word = |character: char| character.is_ascii_alphanumeric() || character == '_';
+    token.space_before ||
+        (is_word(last) && (is_word(first) || first == '.')) ||
+        (last == '.' && first.is_ascii_digit()) ||
+        TWO_CHARACTER_OPERATORS.iter().any(|operator| operator.starts_with(last) && operator.ends_with(first))
+}
@@ -0,0 +1,680 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Recursive descent parser of the GLSL ES 1.00 grammar, which rejects
+//! whatever the grammar doesn't describe.
+//!
+//! <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>, section 9
+
+use std::collections::HashMap;
+use super::{MAX_NESTING_DEPTH, ShaderError, ShaderKind};
+use super::preprocessor::{Token, TokenKind, parse_integer};
+
+const BUILTIN_TYPES: &'static [&'static str] = &[
+    "void", "bool", "int", "float", "vec2", "vec3", "vec4", "bvec2", "bvec3", "bvec4", "ivec2", "ivec3", "ivec4",
+    "mat2", "mat3", "mat4", "sampler2D", "samplerCube",
+];
+
+/// <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>, section 3.6
+const KEYWORDS: &'static [&'static str] = &[
+    "attribute", "const", "uniform", "varying", "break", "continue", "do", "for", "while", "if", "else", "in", "out",
+    "inout", "true", "false", "lowp", "mediump", "highp", "precision", "invariant", "discard", "return", "struct",
+];
+
+/// <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>, section 3.6
+const RESERVED_KEYWORDS: &'static [&'static str] = &[
+    "asm", "class", "union", "enum", "typedef", "template", "this", "packed", "goto", "switch", "default", "inline",
+    "noinline", "volatile", "public", "static", "extern", "external", "interface", "flat", "long", "short", "double",
+    "half", "fixed", "unsigned", "superp", "input", "output", "hvec2", "hvec3", "hvec4", "dvec2", "dvec3", "dvec4",
+    "fvec2", "fvec3", "fvec4", "sampler1D", "sampler3D", "sampler1DShadow", "sampler2DShadow", "sampler2DRect",
+    "sampler3DRect", "sampler2DRectShadow", "sizeof", "cast", "namespace", "using",
+];
+
+/// Operators reserved for future versions of the language.
+///
+/// <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>, section 5.1
+const RESERVED_OPERATORS: &'static [&'static str] = &[
+    "%", "~", "<<", ">>", "&", "|", "^", "%=", "<<=", ">>=", "&=", "^=", "|=",
+];
+
+const PRECISION_QUALIFIERS: &'static [&'static str] = &["lowp", "mediump", "highp"];
+
+const STORAGE_QUALIFIERS: &'static [&'static str] = &["const", "attribute", "varying", "uniform", "invariant"];
+
+/// What a shader was found to contain.
+pub struct Parsed {
+    pub has_main: bool,
+    /// Whether each token is part of a precision qualifier or declaration.
+    pub precision_tokens: Vec<bool>,
+}
+
+pub fn parse(tokens: &[Token], kind: ShaderKind) -> Result<Parsed, ShaderError> {
+    for token in tokens {
+        if token.kind == TokenKind::Identifier && RESERVED_KEYWORDS.contains(&&*token.text) {
+            return Err(ShaderError::new(token.line, format!("'{}' is a reserved keyword", token.text)));
+        }
+        if RESERVED_OPERATORS.contains(&&*token.text) {
+            return Err(ShaderError::new(token.line, format!("'{}' is a reserved operator", token.text)));
+        }
+    }
+
+    let mut parser = Parser {
+        tokens: tokens,
+        position: 0,
+        kind: kind,
+        scopes: vec![HashMap::new()],
+        depth: 0,
+        has_main: false,
+        precision_tokens: vec![false; tokens.len()],
+    };
+    while parser.position < tokens.len() {
+        parser.declaration(true)?;
+    }
+    Ok(Parsed {
+        has_main: parser.has_main,
+        precision_tokens: parser.precision_tokens,
+    })
+}
+
+/// The type of a declaration.
+struct TypeSpecifier {
+    is_void: bool,
+    defines_struct: bool,
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    kind: ShaderKind,
+    /// The names declared in each scope, mapped to whether they name a
+    /// structure type.
+    scopes: Vec<HashMap<String, bool>>,
+    depth: usize,
+    has_main: bool,
+    precision_tokens: Vec<bool>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_is(&self, offset: usize, text: &str) -> bool {
+        self.tokens.get(self.position + offset).map_or(false, |token| token.is(text))
+    }
+
+    fn next(&mut self) -> Result<&'a Token, ShaderError> {
+        let token = self.peek().ok_or_else(|| self.error("unexpected end of shader".to_owned()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn accept(&mut self, text: &str) -> bool {
+        let accepted = self.peek_is(0, text);
+        if accepted {
+            self.position += 1;
+        }
+        accepted
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), ShaderError> {
+        let token = self.next()?;
+        if !token.is(text) {
+            return Err(unexpected(token));
+        }
+        Ok(())
+    }
+
+    /// An error on the line of the current token.
+    fn error(&self, message: String) -> ShaderError {
+        let token = self.peek().or(self.tokens.last());
+        ShaderError::new(token.map_or(1, |token| token.line), message)
+    }
+
+    /// Enters an expression or statement, which nest too deeply past
+    /// `MAX_NESTING_DEPTH`.
+    fn enter(&mut self) -> Result<(), ShaderError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            return Err(self.error("shader is nested too deeply".to_owned()));
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn is_type(&self, name: &str) -> bool {
+        if BUILTIN_TYPES.contains(&name) {
+            return true;
+        }
+        self.scopes.iter().rev().filter_map(|scope| scope.get(name)).next() == Some(&true)
+    }
+
+    fn identifier(&mut self) -> Result<&'a Token, ShaderError> {
+        let token = self.next()?;
+        if token.kind != TokenKind::Identifier || is_keyword(&token.text) {
+            return Err(unexpected(token));
+        }
+        Ok(token)
+    }
+
+    fn declare(&mut self, name: &Token, is_type: bool) -> Result<(), ShaderError> {
+        if name.text.starts_with("gl_") {
+            return Err(ShaderError::new(name.line, format!("'{}' uses the reserved 'gl_' prefix", name.text)));
+        }
+        let scope = self.scopes.last_mut().unwrap();
+        let redeclared = match scope.get(&name.text) {
+            Some(&existing) => existing || is_type,
+            None => false,
+        };
+        if redeclared {
+            return Err(ShaderError::new(name.line, format!("'{}' is already declared", name.text)));
+        }
+        scope.insert(name.text.clone(), is_type);
+        Ok(())
+    }
+
+    /// Whether a declaration rather than an expression starts at the current
+    /// token, a type followed by a parenthesis being a constructor call.
+    fn starts_declaration(&self) -> bool {
+        let token = match self.peek() {
+            Some(token) => token,
+            None => return false,
+        };
+        let text = &*token.text;
+        STORAGE_QUALIFIERS.contains(&text) ||
+            PRECISION_QUALIFIERS.contains(&text) ||
+            text == "precision" ||
+            text == "struct" ||
+            (self.is_type(text) && !self.peek_is(1, "("))
+    }
+
+    /// Parses a declaration, or a function definition if `global`.
+    fn declaration(&mut self, global: bool) -> Result<(), ShaderError> {
+        let start = self.position;
+        if self.accept("precision") {
+            let qualifier = self.next()?;
+            if !PRECISION_QUALIFIERS.contains(&&*qualifier.text) {
+                return Err(unexpected(qualifier));
+            }
+            let type_ = self.next()?;
+            if !["int", "float", "sampler2D", "samplerCube"].contains(&&*type_.text) {
+                return Err(ShaderError::new(
+                    type_.line,
+                    format!("default precision can't be set for '{}'", type_.text),
+                ));
+            }
+            self.expect(";")?;
+            for is_precision in &mut self.precision_tokens[start..self.position] {
+                *is_precision = true;
+            }
+            return Ok(());
+        }
+
+        // Redeclarations of varyings as invariant.
+        if global && self.peek_is(0, "invariant") && !self.peek_is(1, "varying") {
+            self.position += 1;
+            loop {
+                self.identifier()?;
+                if !self.accept(",") {
+                    break;
+                }
+            }
+            return self.expect(";");
+        }
+
+        let qualifier = match self.peek() {
+            Some(token) if STORAGE_QUALIFIERS.contains(&&*token.text) => {
+                self.position += 1;
+                if token.is("invariant") {
+                    self.expect("varying")?;
+                }
+                let allowed = match &*token.text {
+                    "const" => true,
+                    "attribute" => global && self.kind == ShaderKind::Vertex,
+                    _ => global,
+                };
+                if !allowed {
+                    return Err(ShaderError::new(token.line, format!("'{}' can't be used here", token.text)));
+                }
+                Some(token)
+            },
+            _ => None,
+        };
+
+        let type_ = self.type_specifier()?;
+        if self.accept(";") {
+            return Ok(());
+        }
+        let name = self.identifier()?;
+        if self.peek_is(0, "(") {
+            if !global {
+                return Err(ShaderError::new(name.line, "functions can't be declared locally".to_owned()));
+            }
+            if let Some(qualifier) = qualifier {
+                return Err(ShaderError::new(
+                    qualifier.line,
+                    "function return types can't be qualified".to_owned(),
+                ));
+            }
+            return self.function(name, type_);
+        }
+        if type_.is_void {
+            return Err(ShaderError::new(name.line, format!("'{}' can't be void", name.text)));
+        }
+
+        let mut name = name;
+        loop {
+            self.declare(name, false)?;
+            if self.accept("[") {
+                self.conditional()?;
+                self.expect("]")?;
+                if self.peek_is(0, "=") {
+                    return Err(self.error("arrays can't be initialized".to_owned()));
+                }
+            }
+            if self.accept("=") {
+                self.assignment()?;
+            }
+            if !self.accept(",") {
+                break;
+            }
+            name = self.identifier()?;
+        }
+        self.expect(";")
+    }
+
+    /// Parses the parameters and body of a function, whose name was parsed.
+    fn function(&mut self, name: &Token, return_type: TypeSpecifier) -> Result<(), ShaderError> {
+        self.declare(name, false)?;
+        self.expect("(")?;
+        self.scopes.push(HashMap::new());
+        let mut parameter_count = 0;
+        if self.peek_is(0, "void") && self.peek_is(1, ")") {
+            self.position += 1;
+        } else if !self.peek_is(0, ")") {
+            loop {
+                self.parameter()?;
+                parameter_count += 1;
+                if !self.accept(",") {
+                    break;
+                }
+            }
+        }
+        self.expect(")")?;
+
+        if !self.accept(";") {
+            if name.is("main") {
+                if !return_type.is_void || parameter_count != 0 {
+                    return Err(ShaderError::new(
+                        name.line,
+                        "main function must be declared as 'void main()'".to_owned(),
+                    ));
+                }
+                self.has_main = true;
+            }
+            // The parameters and the body share a scope.
+            self.expect("{")?;
+            while !self.accept("}") {
+                self.statement()?;
+            }
+        }
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn parameter(&mut self) -> Result<(), ShaderError> {
+        self.accept("const");
+        let _ = self.accept("in") || self.accept("out") || self.accept("inout");
+        let type_ = self.type_specifier()?;
+        if type_.is_void || type_.defines_struct {
+            return Err(self.error("invalid parameter type".to_owned()));
+        }
+        let named = self.peek().map_or(false, |token| {
+            token.kind == TokenKind::Identifier && !is_keyword(&token.text)
+        });
+        if named {
+            let name = self.identifier()?;
+            self.declare(name, false)?;
+        }
+        if self.accept("[") {
+            self.conditional()?;
+            self.expect("]")?;
+        }
+        Ok(())
+    }
+
+    /// Parses a type, with its precision qualifier if any.
+    fn type_specifier(&mut self) -> Result<TypeSpecifier, ShaderError> {
+        if self.peek().map_or(false, |token| PRECISION_QUALIFIERS.contains(&&*token.text)) {
+            self.precision_tokens[self.position] = true;
+            self.position += 1;
+        }
+        let token = self.next()?;
+        if token.is("struct") {
+            self.struct_specifier()?;
+            return Ok(TypeSpecifier { is_void: false, defines_struct: true });
+        }
+        if !self.is_type(&token.text) {
+            return Err(unexpected(token));
+        }
+        Ok(TypeSpecifier { is_void: token.is("void"), defines_struct: false })
+    }
+
+    /// Parses a structure definition, after its `struct` keyword.
+    fn struct_specifier(&mut self) -> Result<(), ShaderError> {
+        let name = if self.peek_is(0, "{") { None } else { Some(self.identifier()?) };
+        self.expect("{")?;
+        loop {
+            let type_ = self.type_specifier()?;
+            if type_.defines_struct {
+                return Err(self.error("embedded structure definitions are not allowed".to_owned()));
+            }
+            if type_.is_void {
+                return Err(self.error("structure members can't be void".to_owned()));
+            }
+            loop {
+                self.identifier()?;
+                if self.accept("[") {
+                    self.conditional()?;
+                    self.expect("]")?;
+                }
+                if !self.accept(",") {
+                    break;
+                }
+            }
+            self.expect(";")?;
+            if self.accept("}") {
+                break;
+            }
+        }
+        if let Some(name) = name {
+            self.declare(name, true)?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), ShaderError> {
+        self.enter()?;
+        let token = match self.peek() {
+            Some(token) => token,
+            None => return Err(self.error("unexpected end of shader".to_owned())),
+        };
+        match &*token.text {
+            "{" => {
+                self.position += 1;
+                self.scopes.push(HashMap::new());
+                while !self.accept("}") {
+                    self.statement()?;
+                }
+                self.scopes.pop();
+            },
+            "if" => {
+                self.position += 1;
+                self.expect("(")?;
+                self.expression()?;
+                self.expect(")")?;
+                self.scoped_statement()?;
+                if self.accept("else") {
+                    self.scoped_statement()?;
+                }
+            },
+            "while" => {
+                self.position += 1;
+                self.expect("(")?;
+                self.scopes.push(HashMap::new());
+                self.condition()?;
+                self.expect(")")?;
+                self.statement()?;
+                self.scopes.pop();
+            },
+            "do" => {
+                self.position += 1;
+                self.scoped_statement()?;
+                self.expect("while")?;
+                self.expect("(")?;
+                self.expression()?;
+                self.expect(")")?;
+                self.expect(";")?;
+            },
+            "for" => {
+                self.position += 1;
+                self.expect("(")?;
+                self.scopes.push(HashMap::new());
+                if !self.accept(";") {
+                    self.simple_statement()?;
+                }
+                if !self.peek_is(0, ";") {
+                    self.condition()?;
+                }
+                self.expect(";")?;
+                if !self.peek_is(0, ")") {
+                    self.expression()?;
+                }
+                self.expect(")")?;
+                self.statement()?;
+                self.scopes.pop();
+            },
+            "discard" if self.kind == ShaderKind::Vertex => {
+                return Err(ShaderError::new(token.line, "'discard' can only be used in fragment shaders".to_owned()));
+            },
+            "continue" | "break" | "discard" => {
+                self.position += 1;
+                self.expect(";")?;
+            },
+            "return" => {
+                self.position += 1;
+                if !self.accept(";") {
+                    self.expression()?;
+                    self.expect(";")?;
+                }
+            },
+            ";" => self.position += 1,
+            _ => self.simple_statement()?,
+        }
+        self.leave();
+        Ok(())
+    }
+
+    fn scoped_statement(&mut self) -> Result<(), ShaderError> {
+        self.scopes.push(HashMap::new());
+        self.statement()?;
+        self.scopes.pop();
+        Ok(())
+    }
+
+    /// Parses a declaration or an expression statement.
+    fn simple_statement(&mut self) -> Result<(), ShaderError> {
+        if self.starts_declaration() {
+            return self.declaration(false);
+        }
+        self.expression()?;
+        self.expect(";")
+    }
+
+    /// Parses the condition of a loop, which may declare a variable.
+    fn condition(&mut self) -> Result<(), ShaderError> {
+        if !self.starts_declaration() {
+            return self.expression();
+        }
+        let type_ = self.type_specifier()?;
+        let name = self.identifier()?;
+        if type_.is_void || type_.defines_struct {
+            return Err(ShaderError::new(name.line, format!("'{}' has an invalid type", name.text)));
+        }
+        self.declare(name, false)?;
+        self.expect("=")?;
+        self.assignment()
+    }
+
+    fn expression(&mut self) -> Result<(), ShaderError> {
+        loop {
+            self.assignment()?;
+            if !self.accept(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn assignment(&mut self) -> Result<(), ShaderError> {
+        self.enter()?;
+        let is_unary = self.conditional()?;
+        let is_assignment = ["=", "+=", "-=", "*=", "/="].iter().any(|operator| self.peek_is(0, operator));
+        if is_assignment {
+            if !is_unary {
+                return Err(self.error("invalid left-hand side of assignment".to_owned()));
+            }
+            self.position += 1;
+            self.assignment()?;
+        }
+        self.leave();
+        Ok(())
+    }
+
+    /// Parses a conditional expression, returning whether it is a unary
+    /// expression, which only those can be assigned to.
+    fn conditional(&mut self) -> Result<bool, ShaderError> {
+        let is_unary = self.binary(1)?;
+        if !self.accept("?") {
+            return Ok(is_unary);
+        }
+        self.expression()?;
+        self.expect(":")?;
+        self.assignment()?;
+        Ok(false)
+    }
+
+    /// Parses binary operators of at least `min_precedence`.
+    fn binary(&mut self, min_precedence: u8) -> Result<bool, ShaderError> {
+        let mut is_unary = self.unary()?;
+        loop {
+            let precedence = match self.peek().map(|token| &*token.text) {
+                Some("||") => 1,
+                Some("^^") => 2,
+                Some("&&") => 3,
+                Some("==") | Some("!=") => 4,
+                Some("<") | Some(">") | Some("<=") | Some(">=") => 5,
+                Some("+") | Some("-") => 6,
+                Some("*") | Some("/") => 7,
+                _ => break,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+            self.binary(precedence + 1)?;
+            is_unary = false;
+        }
+        Ok(is_unary)
+    }
+
+    fn unary(&mut self) -> Result<bool, ShaderError> {
+        let is_prefix = ["++", "--", "+", "-", "!"].iter().any(|operator| self.peek_is(0, operator));
+        if is_prefix {
+            self.position += 1;
+            self.enter()?;
+            self.unary()?;
+            self.leave();
+            return Ok(true);
+        }
+
+        self.primary()?;
+        loop {
+            if self.accept("[") {
+                self.expression()?;
+                self.expect("]")?;
+            } else if self.accept(".") {
+                self.identifier()?;
+                if self.peek_is(0, "(") {
+                    return Err(self.error("methods are not supported".to_owned()));
+                }
+            } else if !(self.accept("++") || self.accept("--")) {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<(), ShaderError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Number => check_number(token),
+            TokenKind::Identifier if token.is("true") || token.is("false") => Ok(()),
+            TokenKind::Identifier if self.is_type(&token.text) => {
+                if !self.peek_is(0, "(") {
+                    return Err(unexpected(token));
+                }
+                self.arguments(Some(token))
+            },
+            TokenKind::Identifier if is_keyword(&token.text) => Err(unexpected(token)),
+            TokenKind::Identifier => {
+                if self.peek_is(0, "(") {
+                    self.arguments(None)?;
+                }
+                Ok(())
+            },
+            TokenKind::Punctuation if token.is("(") => {
+                self.expression()?;
+                self.expect(")")
+            },
+            TokenKind::Punctuation => Err(unexpected(token)),
+        }
+    }
+
+    /// Parses the arguments of a function call, or of a call to the
+    /// constructor of `constructed`.
+    fn arguments(&mut self, constructed: Option<&Token>) -> Result<(), ShaderError> {
+        self.expect("(")?;
+        let no_arguments = if self.peek_is(0, "void") && self.peek_is(1, ")") {
+            self.position += 2;
+            true
+        } else {
+            self.accept(")")
+        };
+        if no_arguments {
+            if let Some(type_) = constructed {
+                return Err(ShaderError::new(
+                    type_.line,
+                    format!("constructor of '{}' needs arguments", type_.text),
+                ));
+            }
+            return Ok(());
+        }
+        loop {
+            self.assignment()?;
+            if !self.accept(",") {
+                return self.expect(")");
+            }
+        }
+    }
+}
+
+fn is_keyword(text: &str) -> bool {
+    KEYWORDS.contains(&text) || BUILTIN_TYPES.contains(&text)
+}
+
+fn unexpected(token: &Token) -> ShaderError {
+    ShaderError::new(token.line, format!("unexpected '{}'", token.text))
+}
+
+/// Checks that a number is an integer or floating-point constant, the
+/// latter having no suffix.
+///
+/// <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>, section 4.1.3
+fn check_number(token: &Token) -> Result<(), ShaderError> {
+    let text = &*token.text;
+    let is_hexadecimal = text.starts_with("0x") || text.starts_with("0X");
+    let is_float = !is_hexadecimal && text.contains(&['.', 'e', 'E'][..]);
+    let valid = if is_float {
+        text.chars().all(|character| character.is_ascii_digit() || ".eE+-".contains(character)) &&
+            text.parse::<f64>().ok().map_or(false, f64::is_finite)
+    } else {
+        parse_integer(text).is_some()
+    };
+    if !valid {
+        return Err(ShaderError::new(token.line, format!("invalid constant '{}'", text)));
+    }
+    Ok(())
+}
@@ -0,0 +1,799 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Tokenizer and preprocessor of GLSL ES 1.00.
+//!
+//! Macros are expanded and conditional groups resolved here, so that the
+//! parser sees the exact tokens the native compiler will be given.
+//!
+//! <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>, section 3.4
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::mem;
+use super::{MAX_NESTING_DEPTH, STANDARD_DERIVATIVES, ShaderError, ShaderKind, TranslationOptions};
+
+/// How many tokens macros may expand to in a whole shader, so that macros
+/// expanding each other several times can't exhaust memory.
+const MAX_EXPANDED_TOKENS: usize = 1 << 18;
+
+/// Operators of more than one character, longest first.
+const OPERATORS: &'static [&'static str] = &[
+    "<<=", ">>=", "++", "--", "<=", ">=", "==", "!=", "&&", "||", "^^", "+=", "-=", "*=", "/=", "%=", "&=", "|=",
+    "^=", "<<", ">>",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    Identifier,
+    Number,
+    Punctuation,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    /// The line of the token, or of the macro invocation it comes from.
+    pub line: u32,
+    /// Whether whitespace or a comment precedes the token.
+    pub space_before: bool,
+    /// Whether the token is the first of its line, which makes a `#` start
+    /// a directive.
+    first_on_line: bool,
+    /// The macros whose expansion the token comes from, which are not
+    /// expanded again in it.
+    hide_set: Vec<String>,
+}
+
+impl Token {
+    pub fn is(&self, text: &str) -> bool {
+        self.text == text
+    }
+}
+
+/// A preprocessed shader.
+pub struct Preprocessed {
+    pub tokens: Vec<Token>,
+    /// The directives to pass on to the native compiler, with their line.
+    pub directives: Vec<(u32, String)>,
+    /// Whether the shader enables `OES_standard_derivatives`.
+    pub derivatives_enabled: bool,
+}
+
+pub fn preprocess(source: &str, options: &TranslationOptions) -> Result<Preprocessed, ShaderError> {
+    let tokens = tokenize(source)?;
+    let mut preprocessor = Preprocessor::new(options);
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        if !(token.is("#") && token.first_on_line) {
+            if preprocessor.is_active() {
+                preprocessor.pending.push(token.clone());
+            }
+            preprocessor.seen_code = true;
+            index += 1;
+            continue;
+        }
+        let end = tokens[index + 1..].iter().position(|token| token.first_on_line).map_or(tokens.len(), |end| {
+            index + 1 + end
+        });
+        preprocessor.flush()?;
+        preprocessor.directive(token, &tokens[index + 1..end])?;
+        preprocessor.seen_code = true;
+        index = end;
+    }
+    preprocessor.flush()?;
+    if let Some(conditional) = preprocessor.conditionals.last() {
+        return Err(ShaderError::new(conditional.line, "unterminated conditional directive".to_owned()));
+    }
+    Ok(Preprocessed {
+        tokens: preprocessor.output,
+        directives: preprocessor.directives,
+        derivatives_enabled: preprocessor.derivatives_enabled,
+    })
+}
+
+struct Macro {
+    /// The parameters of function-like macros.
+    parameters: Option<Vec<String>>,
+    body: Vec<Token>,
+}
+
+/// An `#if` group being processed.
+struct Conditional {
+    line: u32,
+    /// Whether the group containing this one is being compiled.
+    parent_active: bool,
+    /// Whether the current branch is being compiled.
+    active: bool,
+    /// Whether one of the branches seen so far was compiled.
+    taken: bool,
+    seen_else: bool,
+}
+
+struct Preprocessor<'a> {
+    options: &'a TranslationOptions,
+    macros: HashMap<String, Macro>,
+    conditionals: Vec<Conditional>,
+    /// Tokens of the source waiting for their macros to be expanded.
+    pending: Vec<Token>,
+    output: Vec<Token>,
+    directives: Vec<(u32, String)>,
+    derivatives_enabled: bool,
+    /// Whether anything but comments and whitespace was seen.
+    seen_code: bool,
+    expanded_tokens: Cell<usize>,
+}
+
+impl<'a> Preprocessor<'a> {
+    fn new(options: &'a TranslationOptions) -> Preprocessor<'a> {
+        let mut predefined = vec![("GL_ES", "1"), ("__VERSION__", "100")];
+        if options.kind == ShaderKind::Fragment {
+            predefined.push(("GL_FRAGMENT_PRECISION_HIGH", "1"));
+        }
+        if options.standard_derivatives {
+            predefined.push((STANDARD_DERIVATIVES, "1"));
+        }
+        let macros = predefined.into_iter().map(|(name, value)| {
+            (name.to_owned(), Macro { parameters: None, body: vec![number(value.to_owned(), 0)] })
+        }).collect();
+        Preprocessor {
+            options: options,
+            macros: macros,
+            conditionals: vec![],
+            pending: vec![],
+            output: vec![],
+            directives: vec![],
+            derivatives_enabled: false,
+            seen_code: false,
+            expanded_tokens: Cell::new(0),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.conditionals.last().map_or(true, |conditional| conditional.active)
+    }
+
+    /// Expands the macros of the pending tokens into the output.
+    fn flush(&mut self) -> Result<(), ShaderError> {
+        let pending = mem::replace(&mut self.pending, vec![]);
+        let expanded = self.expand(pending, 0)?;
+        self.output.extend(expanded);
+        Ok(())
+    }
+
+    /// <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>, section 3.4
+    fn directive(&mut self, hash: &Token, words: &[Token]) -> Result<(), ShaderError> {
+        let line = hash.line;
+        let name = match words.first() {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let arguments = &words[1..];
+        match &*name.text {
+            "if" | "ifdef" | "ifndef" => {
+                let parent_active = self.is_active();
+                let value = if !parent_active {
+                    false
+                } else if name.is("if") {
+                    self.evaluate(line, arguments)?
+                } else {
+                    let defined = self.macros.contains_key(&*single_identifier(line, arguments)?.text);
+                    defined == name.is("ifdef")
+                };
+                self.conditionals.push(Conditional {
+                    line: line,
+                    parent_active: parent_active,
+                    active: value,
+                    taken: value,
+                    seen_else: false,
+                });
+            },
+            "elif" => {
+                let evaluate = match self.conditionals.last() {
+                    Some(conditional) if conditional.seen_else => {
+                        return Err(ShaderError::new(line, "#elif after #else".to_owned()));
+                    },
+                    Some(conditional) => conditional.parent_active && !conditional.taken,
+                    None => return Err(ShaderError::new(line, "#elif without #if".to_owned())),
+                };
+                let value = if evaluate { self.evaluate(line, arguments)? } else { false };
+                let conditional = self.conditionals.last_mut().unwrap();
+                conditional.active = value;
+                conditional.taken |= value;
+            },
+            "else" => {
+                no_arguments(line, name, arguments)?;
+                let conditional = match self.conditionals.last_mut() {
+                    Some(conditional) => conditional,
+                    None => return Err(ShaderError::new(line, "#else without #if".to_owned())),
+                };
+                if conditional.seen_else {
+                    return Err(ShaderError::new(line, "#else after #else".to_owned()));
+                }
+                conditional.active = conditional.parent_active && !conditional.taken;
+                conditional.taken = true;
+                conditional.seen_else = true;
+            },
+            "endif" => {
+                no_arguments(line, name, arguments)?;
+                if self.conditionals.pop().is_none() {
+                    return Err(ShaderError::new(line, "#endif without #if".to_owned()));
+                }
+            },
+            // Other directives are ignored in groups that are skipped.
+            _ if !self.is_active() => {},
+            "define" => self.define(line, arguments)?,
+            "undef" => {
+                let name = single_identifier(line, arguments)?;
+                check_macro_name(name)?;
+                self.macros.remove(&name.text);
+            },
+            "error" => {
+                let message = arguments.iter().map(|word| &*word.text).collect::<Vec<_>>().join(" ");
+                return Err(ShaderError::new(line, format!("#error {}", message)));
+            },
+            "pragma" => {},
+            "extension" => self.extension(line, arguments)?,
+            "version" => {
+                if self.seen_code {
+                    return Err(ShaderError::new(
+                        line,
+                        "#version directive must occur before anything else".to_owned(),
+                    ));
+                }
+                if arguments.len() != 1 || !arguments[0].is("100") {
+                    return Err(ShaderError::new(line, "unsupported shader version".to_owned()));
+                }
+            },
+            "line" => {
+                let arguments = self.expand(arguments.to_vec(), 0)?;
+                let valid = (arguments.len() == 1 || arguments.len() == 2) &&
+                    arguments.iter().all(|argument| argument.kind == TokenKind::Number);
+                if !valid {
+                    return Err(ShaderError::new(line, "malformed #line directive".to_owned()));
+                }
+            },
+            _ => return Err(ShaderError::new(line, format!("invalid directive '{}'", name.text))),
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, line: u32, words: &[Token]) -> Result<(), ShaderError> {
+        let name = match words.first() {
+            Some(name) if name.kind == TokenKind::Identifier => name,
+            _ => return Err(ShaderError::new(line, "invalid macro name".to_owned())),
+        };
+        check_macro_name(name)?;
+
+        let mut body = &words[1..];
+        // A parenthesis right after the name starts the parameter list.
+        let parameters = match body.first() {
+            Some(open) if open.is("(") && !open.space_before => {
+                let close = body.iter().position(|word| word.is(")")).ok_or_else(|| {
+                    ShaderError::new(line, "unterminated macro parameter list".to_owned())
+                })?;
+                let mut parameters: Vec<String> = vec![];
+                for (index, word) in body[1..close].iter().enumerate() {
+                    let valid = if index % 2 == 0 {
+                        word.kind == TokenKind::Identifier && !parameters.contains(&word.text)
+                    } else {
+                        word.is(",")
+                    };
+                    if !valid {
+                        return Err(ShaderError::new(line, "invalid macro parameter list".to_owned()));
+                    }
+                    if index % 2 == 0 {
+                        parameters.push(word.text.clone());
+                    }
+                }
+                if close > 1 && close % 2 == 1 {
+                    return Err(ShaderError::new(line, "invalid macro parameter list".to_owned()));
+                }
+                body = &body[close + 1..];
+                Some(parameters)
+            },
+            _ => None,
+        };
+
+        let body = body.to_vec();
+        if let Some(existing) = self.macros.get(&name.text) {
+            let same = existing.parameters == parameters &&
+                existing.body.len() == body.len() &&
+                existing.body.iter().zip(&body).all(|(a, b)| a.text == b.text);
+            if !same {
+                return Err(ShaderError::new(line, format!("macro '{}' redefined", name.text)));
+            }
+        }
+        self.macros.insert(name.text.clone(), Macro { parameters, body });
+        Ok(())
+    }
+
+    /// <https://www.khronos.org/registry/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf>, section 3.4
+    fn extension(&mut self, line: u32, words: &[Token]) -> Result<(), ShaderError> {
+        let (name, behavior) = match words {
+            [ref name, ref colon, ref behavior] if colon.is(":") => (&*name.text, &*behavior.text),
+            _ => return Err(ShaderError::new(line, "malformed #extension directive".to_owned())),
+        };
+        let supported = name == STANDARD_DERIVATIVES && self.options.standard_derivatives;
+        match behavior {
+            "require" | "enable" if name == "all" => {
+                return Err(ShaderError::new(
+                    line,
+                    format!("extension 'all' cannot have '{}' behavior", behavior),
+                ));
+            },
+            "require" | "enable" if !supported => {
+                return Err(ShaderError::new(line, format!("extension '{}' is not supported", name)));
+            },
+            "require" | "enable" | "warn" => {
+                self.derivatives_enabled |= supported || (name == "all" && self.options.standard_derivatives);
+            },
+            "disable" => {
+                if supported || name == "all" {
+                    self.derivatives_enabled = false;
+                }
+            },
+            _ => {
+                return Err(ShaderError::new(
+                    line,
+                    format!("'{}' is not a valid extension behavior", behavior),
+                ));
+            },
+        }
+        // Desktop GLSL has derivatives built in.
+        if self.options.glsl_version == 100 {
+            self.directives.push((line, format!("#extension {} : {}", name, behavior)));
+        }
+        Ok(())
+    }
+
+    /// Evaluates the condition of an `#if` or `#elif` directive.
+    fn evaluate(&self, line: u32, words: &[Token]) -> Result<bool, ShaderError> {
+        // The operand of `defined` must not be expanded.
+        let mut replaced = vec![];
+        let mut index = 0;
+        while index < words.len() {
+            if !words[index].is("defined") {
+                replaced.push(words[index].clone());
+                index += 1;
+                continue;
+            }
+            let (name, next) = match (words.get(index + 1), words.get(index + 2), words.get(index + 3)) {
+                (Some(open), Some(name), Some(close)) if open.is("(") && close.is(")") => (name, index + 4),
+                (Some(name), _, _) => (name, index + 2),
+                _ => (&words[index], index + 1),
+            };
+            if name.kind != TokenKind::Identifier || name.is("defined") {
+                return Err(ShaderError::new(line, "invalid use of 'defined'".to_owned()));
+            }
+            let value = if self.macros.contains_key(&name.text) { "1" } else { "0" };
+            replaced.push(number(value.to_owned(), line));
+            index = next;
+        }
+
+        let tokens = self.expand(replaced, 0)?;
+        if tokens.is_empty() {
+            return Err(ShaderError::new(line, "missing expression in conditional directive".to_owned()));
+        }
+        let mut parser = ExpressionParser { tokens: &tokens, position: 0, line: line };
+        let value = parser.parse_binary(0, 0)?;
+        if let Some(token) = tokens.get(parser.position) {
+            return Err(ShaderError::new(line, format!("unexpected '{}' in conditional directive", token.text)));
+        }
+        Ok(value != 0)
+    }
+
+    /// Expands the macros in `tokens`, the result of each expansion being
+    /// scanned again along with the tokens that follow it.
+    fn expand(&self, tokens: Vec<Token>, depth: usize) -> Result<Vec<Token>, ShaderError> {
+        if depth > MAX_NESTING_DEPTH {
+            let line = tokens.first().map_or(0, |token| token.line);
+            return Err(ShaderError::new(line, "macro invocations are nested too deeply".to_owned()));
+        }
+        // The tokens left to scan, in reverse order.
+        let mut input: Vec<Token> = tokens.into_iter().rev().collect();
+        let mut output = vec![];
+        while let Some(token) = input.pop() {
+            if token.kind != TokenKind::Identifier || token.hide_set.contains(&token.text) {
+                output.push(token);
+                continue;
+            }
+            match &*token.text {
+                "__LINE__" => {
+                    output.push(number(token.line.to_string(), token.line));
+                    continue;
+                },
+                "__FILE__" => {
+                    output.push(number("0".to_owned(), token.line));
+                    continue;
+                },
+                _ => {},
+            }
+            let macro_ = match self.macros.get(&token.text) {
+                Some(macro_) => macro_,
+                None => {
+                    output.push(token);
+                    continue;
+                },
+            };
+
+            let replacement = match macro_.parameters {
+                None => macro_.body.clone(),
+                Some(ref parameters) => {
+                    // A function-like macro name alone is an identifier.
+                    if !input.last().map_or(false, |next| next.is("(")) {
+                        output.push(token);
+                        continue;
+                    }
+                    input.pop();
+                    let mut arguments = collect_arguments(&mut input, token.line)?;
+                    if parameters.is_empty() && arguments.len() == 1 && arguments[0].is_empty() {
+                        arguments.clear();
+                    }
+                    if arguments.len() != parameters.len() {
+                        return Err(ShaderError::new(
+                            token.line,
+                            format!("wrong number of arguments for macro '{}'", token.text),
+                        ));
+                    }
+                    let mut expanded_arguments = vec![];
+                    for argument in arguments {
+                        expanded_arguments.push(self.expand(argument, depth + 1)?);
+                    }
+                    let mut replacement = vec![];
+                    for word in &macro_.body {
+                        match parameters.iter().position(|parameter| *parameter == word.text) {
+                            Some(index) => {
+                                let start = replacement.len();
+                                replacement.extend(expanded_arguments[index].iter().cloned());
+                                if let Some(first) = replacement.get_mut(start) {
+                                    first.space_before = word.space_before;
+                                }
+                            },
+                            None => replacement.push(word.clone()),
+                        }
+                    }
+                    replacement
+                },
+            };
+
+            let expanded_tokens = self.expanded_tokens.get() + replacement.len();
+            if expanded_tokens > MAX_EXPANDED_TOKENS {
+                return Err(ShaderError::new(token.line, "macro expansion is too large".to_owned()));
+            }
+            self.expanded_tokens.set(expanded_tokens);
+            for (index, mut word) in replacement.into_iter().enumerate().rev() {
+                word.line = token.line;
+                word.first_on_line = false;
+                if index == 0 {
+                    word.space_before = token.space_before;
+                }
+                for name in token.hide_set.iter().chain(Some(&token.text)) {
+                    if !word.hide_set.contains(name) {
+                        word.hide_set.push(name.clone());
+                    }
+                }
+                input.push(word);
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Collects the arguments of a function-like macro invocation, whose
+/// opening parenthesis was consumed.
+fn collect_arguments(input: &mut Vec<Token>, line: u32) -> Result<Vec<Vec<Token>>, ShaderError> {
+    let mut arguments = vec![vec![]];
+    let mut depth = 0;
+    loop {
+        let token = input.pop().ok_or_else(|| {
+            ShaderError::new(line, "unterminated macro invocation".to_owned())
+        })?;
+        match &*token.text {
+            ")" if depth == 0 => return Ok(arguments),
+            "," if depth == 0 => {
+                arguments.push(vec![]);
+                continue;
+            },
+            "(" => depth += 1,
+            ")" => depth -= 1,
+            _ => {},
+        }
+        arguments.last_mut().unwrap().push(token);
+    }
+}
+
+fn number(text: String, line: u32) -> Token {
+    Token {
+        kind: TokenKind::Number,
+        text: text,
+        line: line,
+        space_before: false,
+        first_on_line: false,
+        hide_set: vec![],
+    }
+}
+
+fn single_identifier(line: u32, words: &[Token]) -> Result<&Token, ShaderError> {
+    match words {
+        [ref name] if name.kind == TokenKind::Identifier => Ok(name),
+        _ => Err(ShaderError::new(line, "expected a macro name".to_owned())),
+    }
+}
+
+fn no_arguments(line: u32, directive: &Token, words: &[Token]) -> Result<(), ShaderError> {
+    match words.first() {
+        Some(word) => Err(ShaderError::new(
+            line,
+            format!("unexpected '{}' after #{}", word.text, directive.text),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Checks that a macro may be defined or undefined.
+fn check_macro_name(name: &Token) -> Result<(), ShaderError> {
+    let reserved = name.text.starts_with("GL_") ||
+        ["defined", "__LINE__", "__FILE__", "__VERSION__"].contains(&&*name.text);
+    if reserved {
+        return Err(ShaderError::new(name.line, format!("macro name '{}' is reserved", name.text)));
+    }
+    Ok(())
+}
+
+/// Evaluates the integer expressions of conditional directives.
+struct ExpressionParser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    line: u32,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn error(&self) -> ShaderError {
+        let message = match self.tokens.get(self.position) {
+            Some(token) => format!("unexpected '{}' in conditional directive", token.text),
+            None => "unexpected end of conditional directive".to_owned(),
+        };
+        ShaderError::new(self.line, message)
+    }
+
+    /// Parses operators of at least `min_precedence`.
+    fn parse_binary(&mut self, min_precedence: u8, depth: usize) -> Result<i64, ShaderError> {
+        let mut left = self.parse_unary(depth)?;
+        loop {
+            let operator = match self.tokens.get(self.position) {
+                Some(token) => &*token.text,
+                None => break,
+            };
+            let precedence = match operator {
+                "||" => 1,
+                "&&" => 2,
+                "|" => 3,
+                "^" => 4,
+                "&" => 5,
+                "==" | "!=" => 6,
+                "<" | ">" | "<=" | ">=" => 7,
+                "<<" | ">>" => 8,
+                "+" | "-" => 9,
+                "*" | "/" | "%" => 10,
+                _ => break,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+            let right = self.parse_binary(precedence + 1, depth)?;
+            left = match operator {
+                "||" => ((left != 0) || (right != 0)) as i64,
+                "&&" => ((left != 0) && (right != 0)) as i64,
+                "|" => left | right,
+                "^" => left ^ right,
+                "&" => left & right,
+                "==" => (left == right) as i64,
+                "!=" => (left != right) as i64,
+                "<" => (left < right) as i64,
+                ">" => (left > right) as i64,
+                "<=" => (left <= right) as i64,
+                ">=" => (left >= right) as i64,
+                "<<" => left.wrapping_shl(right as u32),
+                ">>" => left.wrapping_shr(right as u32),
+                "+" => left.wrapping_add(right),
+                "-" => left.wrapping_sub(right),
+                "*" => left.wrapping_mul(right),
+                "/" | "%" if right == 0 => {
+                    let message = "division by zero in conditional directive".to_owned();
+                    return Err(ShaderError::new(self.line, message));
+                },
+                "/" => left.wrapping_div(right),
+                _ => left.wrapping_rem(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self, depth: usize) -> Result<i64, ShaderError> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(ShaderError::new(self.line, "conditional directive is nested too deeply".to_owned()));
+        }
+        let token = self.tokens.get(self.position).ok_or_else(|| self.error())?;
+        self.position += 1;
+        match &*token.text {
+            "+" => self.parse_unary(depth + 1),
+            "-" => Ok(self.parse_unary(depth + 1)?.wrapping_neg()),
+            "!" => Ok((self.parse_unary(depth + 1)? == 0) as i64),
+            "~" => Ok(!self.parse_unary(depth + 1)?),
+            "(" => {
+                let value = self.parse_binary(0, depth + 1)?;
+                match self.tokens.get(self.position) {
+                    Some(close) if close.is(")") => self.position += 1,
+                    _ => return Err(self.error()),
+                }
+                Ok(value)
+            },
+            _ if token.kind == TokenKind::Number => {
+                parse_integer(&token.text).map(|value| value as i64).ok_or_else(|| {
+                    ShaderError::new(self.line, format!("'{}' is not an integer", token.text))
+                })
+            },
+            _ => {
+                self.position -= 1;
+                Err(self.error())
+            },
+        }
+    }
+}
+
+/// Parses a decimal, octal or hexadecimal integer constant.
+pub fn parse_integer(text: &str) -> Option<u32> {
+    let (digits, radix) = if text.starts_with("0x") || text.starts_with("0X") {
+        (&text[2..], 16)
+    } else if text.starts_with('0') && text.len() > 1 {
+        (&text[1..], 8)
+    } else {
+        (text, 10)
+    };
+    if digits.is_empty() || !digits.chars().all(|digit| digit.is_digit(radix)) {
+        return None;
+    }
+    u32::from_str_radix(digits, radix).ok()
+}
+
+/// Splits the source into tokens, skipping whitespace and comments.
+///
+/// <https://www.khronos.org/registry/webgl/specs/latest/1.0/#6.18>
+fn tokenize(source: &str) -> Result<Vec<Token>, ShaderError> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut first_on_line = true;
+    let mut space_before = false;
+    let mut position = 0;
+    while position < bytes.len() {
+        let start = position;
+        let kind = match bytes[position] {
+            b'\n' => {
+                line += 1;
+                first_on_line = true;
+                space_before = true;
+                position += 1;
+                continue;
+            },
+            b' ' | b'\t' | b'\r' | 0x0B | 0x0C => {
+                space_before = true;
+                position += 1;
+                continue;
+            },
+            b'/' if bytes.get(position + 1) == Some(&b'/') => {
+                while position < bytes.len() && bytes[position] != b'\n' {
+                    position += 1;
+                }
+                space_before = true;
+                continue;
+            },
+            b'/' if bytes.get(position + 1) == Some(&b'*') => {
+                position += 2;
+                loop {
+                    match bytes.get(position) {
+                        None => {
+                            return Err(ShaderError::new(line, "unterminated comment".to_owned()));
+                        },
+                        Some(&b'*') if bytes.get(position + 1) == Some(&b'/') => {
+                            position += 2;
+                            break;
+                        },
+                        Some(&b'\n') => line += 1,
+                        Some(_) => {},
+                    }
+                    position += 1;
+                }
+                space_before = true;
+                continue;
+            },
+            b'a'...b'z' | b'A'...b'Z' | b'_' => {
+                while position < bytes.len() && is_identifier_byte(bytes[position]) {
+                    position += 1;
+                }
+                TokenKind::Identifier
+            },
+            b'0'...b'9' => {
+                position = scan_number(bytes, position);
+                TokenKind::Number
+            },
+            b'.' if bytes.get(position + 1).map_or(false, u8::is_ascii_digit) => {
+                position = scan_number(bytes, position);
+                TokenKind::Number
+            },
+            _ => {
+                check_character(source, position, line)?;
+                let rest = &source[position..];
+                position += OPERATORS.iter().find(|operator| rest.starts_with(*operator)).map_or(1, |operator| {
+                    operator.len()
+                });
+                TokenKind::Punctuation
+            },
+        };
+        tokens.push(Token {
+            kind: kind,
+            text: source[start..position].to_owned(),
+            line: line,
+            space_before: space_before,
+            first_on_line: first_on_line,
+            hide_set: vec![],
+        });
+        first_on_line = false;
+        space_before = false;
+    }
+    Ok(tokens)
+}
+
+/// Returns the end of the number starting at `position`. Letters that
+/// follow it are made part of it, for the parser to reject.
+fn scan_number(bytes: &[u8], mut position: usize) -> usize {
+    let is_digit = |position: usize| bytes.get(position).map_or(false, u8::is_ascii_digit);
+    if bytes[position] == b'0' && (bytes.get(position + 1) == Some(&b'x') || bytes.get(position + 1) == Some(&b'X')) {
+        position += 2;
+    } else {
+        while is_digit(position) {
+            position += 1;
+        }
+        if bytes.get(position) == Some(&b'.') {
+            position += 1;
+            while is_digit(position) {
+                position += 1;
+            }
+        }
+        if bytes.get(position) == Some(&b'e') || bytes.get(position) == Some(&b'E') {
+            let sign = bytes.get(position + 1) == Some(&b'+') || bytes.get(position + 1) == Some(&b'-');
+            let digits = if sign { position + 2 } else { position + 1 };
+            if is_digit(digits) {
+                position = digits;
+                while is_digit(position) {
+                    position += 1;
+                }
+            }
+        }
+    }
+    while position < bytes.len() && is_identifier_byte(bytes[position]) {
+        position += 1;
+    }
+    position
+}
+
+fn is_identifier_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Checks that the character at `position` is part of the character set of
+/// GLSL ES, which is only enforced outside of comments.
+fn check_character(source: &str, position: usize, line: u32) -> Result<(), ShaderError> {
+    let character = source[position..].chars().next().unwrap();
+    match character {
+        '"' | '$' | '\'' | '@' | '\\' | '`' => {},
+        ' '...'~' => return Ok(()),
+        _ => {},
+    }
+    Err(ShaderError::new(line, format!("invalid character {:?}", character)))
+}
@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
+use canvas_traits::webgl::{WebGLBufferId, WebGLCommand, WebGLError, WebGLMsgSender, WebGLResult};
+use canvas_traits::webgl::webgl_channel;
+use dom::bindings::codegen::Bindings::WebGLBufferBinding;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct WebGLBuffer {
+    webgl_object: WebGLObject,
+    id: WebGLBufferId,
+    /// The target to which this buffer was bound the first time.
+    target: Cell<Option<u32>>,
+    capacity: Cell<usize>,
+    usage: Cell<u32>,
+    is_deleted: Cell<bool>,
+    renderer: WebGLMsgSender,
+}
+
+impl WebGLBuffer {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLBufferId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            target: Cell::new(None),
+            capacity: Cell::new(0),
+            usage: Cell::new(constants::STATIC_DRAW),
+            is_deleted: Cell::new(false),
+            renderer: context.webgl_sender(),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateBuffer(sender));
+        receiver.recv().unwrap().map(|id| WebGLBuffer::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLBufferId) -> DomRoot<Self> {
+        reflect_dom_object(Box::new(WebGLBuffer::new_inherited(context, id)),
+                           &*context.global(),
+                           WebGLBufferBinding::Wrap)
+    }
+}
+
+impl WebGLBuffer {
+    pub fn id(&self) -> WebGLBufferId {
+        self.id
+    }
+
+    // NB: Only valid buffer targets come here
+    pub fn bind(&self, target: u32) -> WebGLResult<()> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        // A buffer can never change its target once it was bound to one.
+        if let Some(previous_target) = self.target.get() {
+            if target != previous_target {
+                return Err(WebGLError::InvalidOperation);
+            }
+        } else {
+            self.target.set(Some(target));
+        }
+        self.renderer.send(WebGLCommand::BindBuffer(target, Some(self.id))).unwrap();
+        Ok(())
+    }
+
+    pub fn buffer_data(&self, target: u32, data: Vec<u8>, usage: u32) -> WebGLResult<()> {
+        match usage {
+            constants::STREAM_DRAW |
+            constants::STATIC_DRAW |
+            constants::DYNAMIC_DRAW => (),
+            _ => return Err(WebGLError::InvalidEnum),
+        }
+
+        self.capacity.set(data.len());
+        self.usage.set(usage);
+        self.renderer.send(WebGLCommand::BufferData(target, data.into(), usage)).unwrap();
+        Ok(())
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity.get()
+    }
+
+    pub fn usage(&self) -> u32 {
+        self.usage.get()
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            let _ = self.renderer.send(WebGLCommand::DeleteBuffer(self.id));
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    pub fn target(&self) -> Option<u32> {
+        self.target.get()
+    }
+}
+
+impl Drop for WebGLBuffer {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
@@ -0,0 +1,386 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
+use canvas_traits::webgl::{ActiveAttribInfo, ActiveUniformInfo, WebGLCommand, WebGLError};
+use canvas_traits::webgl::{WebGLMsgSender, WebGLProgramId, WebGLResult, webgl_channel};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WebGLProgramBinding;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom::webglshader::WebGLShader;
+use dom::webgluniformlocation::WebGLUniformLocation;
+use dom_struct::dom_struct;
+use std::cell::{Cell, Ref};
+
+/// The maximum length of the names that can be passed to WebGL.
+///
+/// <https://www.khronos.org/registry/webgl/specs/latest/1.0/#6.22>
+pub const MAX_NAME_LENGTH: usize = 256;
+
+#[dom_struct]
+pub struct WebGLProgram {
+    webgl_object: WebGLObject,
+    id: WebGLProgramId,
+    marked_for_deletion: Cell<bool>,
+    is_deleted: Cell<bool>,
+    /// Whether this program is the current program of its context, in which
+    /// case its deletion is deferred until it is not anymore.
+    is_in_use: Cell<bool>,
+    link_called: Cell<bool>,
+    linked: Cell<bool>,
+    fragment_shader: MutNullableDom<WebGLShader>,
+    vertex_shader: MutNullableDom<WebGLShader>,
+    active_attribs: DomRefCell<Box<[ActiveAttribInfo]>>,
+    active_uniforms: DomRefCell<Box<[ActiveUniformInfo]>>,
+    renderer: WebGLMsgSender,
+}
+
+impl WebGLProgram {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLProgramId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            marked_for_deletion: Cell::new(false),
+            is_deleted: Cell::new(false),
+            is_in_use: Cell::new(false),
+            link_called: Cell::new(false),
+            linked: Cell::new(false),
+            fragment_shader: Default::default(),
+            vertex_shader: Default::default(),
+            active_attribs: DomRefCell::new(vec![].into()),
+            active_uniforms: DomRefCell::new(vec![].into()),
+            renderer: context.webgl_sender(),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateProgram(sender));
+        receiver.recv().unwrap().map(|id| WebGLProgram::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLProgramId) -> DomRoot<Self> {
+        reflect_dom_object(Box::new(WebGLProgram::new_inherited(context, id)),
+                           &*context.global(),
+                           WebGLProgramBinding::Wrap)
+    }
+}
+
+impl WebGLProgram {
+    pub fn id(&self) -> WebGLProgramId {
+        self.id
+    }
+
+    /// glDeleteProgram, the program being actually deleted once it is not
+    /// in use anymore.
+    pub fn mark_for_deletion(&self) {
+        if self.marked_for_deletion.get() {
+            return;
+        }
+        self.marked_for_deletion.set(true);
+        let _ = self.renderer.send(WebGLCommand::DeleteProgram(self.id));
+        if !self.is_in_use.get() {
+            self.detach_shaders();
+        }
+    }
+
+    pub fn in_use(&self, value: bool) {
+        if self.is_in_use.get() == value {
+            return;
+        }
+        self.is_in_use.set(value);
+        if !value && self.marked_for_deletion.get() {
+            self.detach_shaders();
+        }
+    }
+
+    fn detach_shaders(&self) {
+        self.is_deleted.set(true);
+        if let Some(shader) = self.fragment_shader.take() {
+            shader.decrement_attached_counter();
+        }
+        if let Some(shader) = self.vertex_shader.take() {
+            shader.decrement_attached_counter();
+        }
+    }
+
+    pub fn is_marked_for_deletion(&self) -> bool {
+        self.marked_for_deletion.get()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.linked.get()
+    }
+
+    /// glLinkProgram
+    pub fn link(&self) -> WebGLResult<()> {
+        self.linked.set(false);
+        self.link_called.set(true);
+        *self.active_attribs.borrow_mut() = vec![].into();
+        *self.active_uniforms.borrow_mut() = vec![].into();
+
+        // A program without both a compiled vertex and fragment shader, or
+        // with one that was not validated, never gets linked.
+        let shaders_compiled = [&self.fragment_shader, &self.vertex_shader].iter().all(|shader| {
+            shader.get().map_or(false, |shader| shader.successfully_compiled())
+        });
+        if !shaders_compiled {
+            return Ok(());
+        }
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.renderer.send(WebGLCommand::LinkProgram(self.id, sender)).unwrap();
+        let link_info = receiver.recv().unwrap();
+
+        // Attribute names reserved by WebGL make the link fail.
+        let uses_reserved_names = link_info.active_attribs.iter().any(|attrib| {
+            attrib.name.starts_with("webgl_") || attrib.name.starts_with("_webgl_")
+        });
+        if !link_info.linked || uses_reserved_names {
+            return Ok(());
+        }
+
+        self.linked.set(true);
+        *self.active_attribs.borrow_mut() = link_info.active_attribs;
+        *self.active_uniforms.borrow_mut() = link_info.active_uniforms;
+        Ok(())
+    }
+
+    pub fn active_attribs(&self) -> Ref<[ActiveAttribInfo]> {
+        Ref::map(self.active_attribs.borrow(), |attribs| &**attribs)
+    }
+
+    pub fn active_uniforms(&self) -> Ref<[ActiveUniformInfo]> {
+        Ref::map(self.active_uniforms.borrow(), |uniforms| &**uniforms)
+    }
+
+    /// glValidateProgram
+    pub fn validate(&self) -> WebGLResult<()> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.renderer.send(WebGLCommand::ValidateProgram(self.id)).unwrap();
+        Ok(())
+    }
+
+    /// glAttachShader
+    pub fn attach_shader(&self, shader: &WebGLShader) -> WebGLResult<()> {
+        if self.is_deleted() || shader.is_deleted() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let shader_slot = match shader.gl_type() {
+            constants::FRAGMENT_SHADER => &self.fragment_shader,
+            constants::VERTEX_SHADER => &self.vertex_shader,
+            _ => return Err(WebGLError::InvalidValue),
+        };
+
+        if shader_slot.get().is_some() {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        shader_slot.set(Some(shader));
+        shader.increment_attached_counter();
+
+        self.renderer.send(WebGLCommand::AttachShader(self.id, shader.id())).unwrap();
+
+        Ok(())
+    }
+
+    /// glDetachShader
+    pub fn detach_shader(&self, shader: &WebGLShader) -> WebGLResult<()> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let shader_slot = match shader.gl_type() {
+            constants::FRAGMENT_SHADER => &self.fragment_shader,
+            constants::VERTEX_SHADER => &self.vertex_shader,
+            _ => return Err(WebGLError::InvalidValue),
+        };
+
+        match shader_slot.get() {
+            Some(ref attached_shader) if attached_shader.id() != shader.id() =>
+                return Err(WebGLError::InvalidOperation),
+            None =>
+                return Err(WebGLError::InvalidOperation),
+            _ => {}
+        }
+
+        shader_slot.set(None);
+        shader.decrement_attached_counter();
+
+        self.renderer.send(WebGLCommand::DetachShader(self.id, shader.id())).unwrap();
+
+        Ok(())
+    }
+
+    /// glBindAttribLocation
+    pub fn bind_attrib_location(&self, index: u32, name: DOMString) -> WebGLResult<()> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        validate_name(&name)?;
+
+        // Check if the name is reserved
+        if name.starts_with("gl_") {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        self.renderer
+            .send(WebGLCommand::BindAttribLocation(self.id, index, String::from(name)))
+            .unwrap();
+        Ok(())
+    }
+
+    pub fn get_active_uniform(&self, index: u32) -> WebGLResult<(String, i32, u32)> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidValue);
+        }
+        let uniforms = self.active_uniforms.borrow();
+        let data = uniforms.get(index as usize).ok_or(WebGLError::InvalidValue)?;
+        Ok((data.name().into_owned(), data.size.unwrap_or(1), data.type_))
+    }
+
+    /// glGetActiveAttrib
+    pub fn get_active_attrib(&self, index: u32) -> WebGLResult<(String, i32, u32)> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidValue);
+        }
+        let attribs = self.active_attribs.borrow();
+        let data = attribs.get(index as usize).ok_or(WebGLError::InvalidValue)?;
+        Ok((data.name.clone(), data.size, data.type_))
+    }
+
+    /// glGetAttribLocation
+    pub fn get_attrib_location(&self, name: DOMString) -> WebGLResult<i32> {
+        if !self.is_linked() || self.is_deleted() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        validate_name(&name)?;
+
+        // Check if the name is reserved
+        if name.starts_with("gl_") || name.starts_with("webgl_") || name.starts_with("_webgl_") {
+            return Ok(-1);
+        }
+
+        let location = self.active_attribs
+            .borrow()
+            .iter()
+            .find(|attrib| attrib.name == &*name)
+            .map_or(-1, |attrib| attrib.location);
+        Ok(location)
+    }
+
+    /// glGetUniformLocation
+    pub fn get_uniform_location(&self, name: DOMString) -> WebGLResult<Option<DomRoot<WebGLUniformLocation>>> {
+        if !self.is_linked() || self.is_deleted() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        validate_name(&name)?;
+
+        // Check if the name is reserved
+        if name.starts_with("webgl_") || name.starts_with("_webgl_") {
+            return Ok(None);
+        }
+
+        // Only ask for the location of an active uniform, or of an element
+        // of an active uniform array, as GL returns -1 for anything else.
+        let (size, type_) = {
+            let (base_name, array_index) = match parse_array_index(&name) {
+                Some((base_name, index)) => (base_name, Some(index)),
+                None => (&*name, None),
+            };
+            let uniforms = self.active_uniforms.borrow();
+            let uniform = uniforms.iter().find(|uniform| &*uniform.base_name == base_name);
+            match (uniform, array_index) {
+                (Some(uniform), None) => (uniform.size, uniform.type_),
+                (Some(uniform), Some(index)) if index < uniform.size.unwrap_or(1) as u32 => {
+                    (uniform.size.map(|size| size - index as i32), uniform.type_)
+                },
+                _ => return Ok(None),
+            }
+        };
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.renderer
+            .send(WebGLCommand::GetUniformLocation(self.id, name.into(), sender))
+            .unwrap();
+        let location = receiver.recv().unwrap();
+
+        Ok(Some(WebGLUniformLocation::new(
+            &self.webgl_object.context(),
+            location,
+            self.id,
+            size,
+            type_,
+        )))
+    }
+
+    /// glGetProgramInfoLog
+    pub fn get_info_log(&self) -> WebGLResult<String> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        if self.link_called.get() {
+            let shaders_compiled = match (self.fragment_shader.get(), self.vertex_shader.get()) {
+                (Some(fs), Some(vs)) => fs.successfully_compiled() && vs.successfully_compiled(),
+                _ => false
+            };
+            if !shaders_compiled {
+                return Ok("One or more shaders failed to compile".to_string());
+            }
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.renderer.send(WebGLCommand::GetProgramInfoLog(self.id, sender)).unwrap();
+        Ok(receiver.recv().unwrap())
+    }
+
+    pub fn attached_shaders(&self) -> WebGLResult<Vec<DomRoot<WebGLShader>>> {
+        if self.marked_for_deletion.get() {
+            return Err(WebGLError::InvalidValue);
+        }
+        Ok(self.vertex_shader.get().into_iter().chain(self.fragment_shader.get()).collect())
+    }
+
+    pub fn validate_status(&self) -> bool {
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.renderer.send(WebGLCommand::GetProgramValidateStatus(self.id, sender)).unwrap();
+        receiver.recv().unwrap()
+    }
+}
+
+impl Drop for WebGLProgram {
+    fn drop(&mut self) {
+        // The attached shaders may already have been collected, so only the
+        // GL program gets deleted here.
+        if !self.marked_for_deletion.get() {
+            let _ = self.renderer.send(WebGLCommand::DeleteProgram(self.id));
+        }
+    }
+}
+
+fn validate_name(name: &str) -> WebGLResult<()> {
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(WebGLError::InvalidValue);
+    }
+    Ok(())
+}
+
+/// Splits a name like `foo[3]` into `foo` and the index `3`.
+fn parse_array_index(name: &str) -> Option<(&str, u32)> {
+    if !name.ends_with(']') {
+        return None;
+    }
+    let open_bracket = name.rfind('[')?;
+    let index = name[open_bracket + 1..name.len() - 1].parse().ok()?;
+    Some((&name[..open_bracket], index))
+}
@@ -0,0 +1,125 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
+use canvas_traits::webgl::{WebGLCommand, WebGLError, WebGLMsgSender, WebGLRenderbufferId, WebGLResult};
+use canvas_traits::webgl::webgl_channel;
+use dom::bindings::codegen::Bindings::WebGLRenderbufferBinding;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+/// The packed depth and stencil format of `OES_packed_depth_stencil`.
+const DEPTH24_STENCIL8: u32 = 0x88F0;
+
+#[dom_struct]
+pub struct WebGLRenderbuffer {
+    webgl_object: WebGLObject,
+    id: WebGLRenderbufferId,
+    ever_bound: Cell<bool>,
+    is_deleted: Cell<bool>,
+    size: Cell<Option<(i32, i32)>>,
+    internal_format: Cell<Option<u32>>,
+    renderer: WebGLMsgSender,
+}
+
+impl WebGLRenderbuffer {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLRenderbufferId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            ever_bound: Cell::new(false),
+            is_deleted: Cell::new(false),
+            size: Cell::new(None),
+            internal_format: Cell::new(None),
+            renderer: context.webgl_sender(),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateRenderbuffer(sender));
+        receiver.recv().unwrap().map(|id| WebGLRenderbuffer::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLRenderbufferId) -> DomRoot<Self> {
+        reflect_dom_object(Box::new(WebGLRenderbuffer::new_inherited(context, id)),
+                           &*context.global(),
+                           WebGLRenderbufferBinding::Wrap)
+    }
+}
+
+impl WebGLRenderbuffer {
+    pub fn id(&self) -> WebGLRenderbufferId {
+        self.id
+    }
+
+    pub fn size(&self) -> Option<(i32, i32)> {
+        self.size.get()
+    }
+
+    pub fn internal_format(&self) -> u32 {
+        self.internal_format.get().unwrap_or(constants::RGBA4)
+    }
+
+    pub fn bind(&self, target: u32) {
+        self.ever_bound.set(true);
+        self.renderer.send(WebGLCommand::BindRenderbuffer(target, Some(self.id))).unwrap();
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            let _ = self.renderer.send(WebGLCommand::DeleteRenderbuffer(self.id));
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    pub fn ever_bound(&self) -> bool {
+        self.ever_bound.get()
+    }
+
+    pub fn storage(&self, internal_format: u32, width: i32, height: i32) -> WebGLResult<()> {
+        // Validate the internal_format, and save it for completeness
+        // validation.
+        match internal_format {
+            constants::RGBA4 |
+            constants::DEPTH_STENCIL |
+            constants::DEPTH_COMPONENT16 |
+            constants::STENCIL_INDEX8 |
+            constants::RGB5_A1 |
+            constants::RGB565 => (),
+            _ => return Err(WebGLError::InvalidEnum),
+        };
+
+        // The DEPTH_STENCIL format has no sized equivalent in OpenGL ES 2.0,
+        // it is backed by a packed depth and stencil renderbuffer.
+        let native_format = match internal_format {
+            constants::DEPTH_STENCIL => DEPTH24_STENCIL8,
+            format => format,
+        };
+
+        self.internal_format.set(Some(internal_format));
+        self.size.set(Some((width, height)));
+
+        self.renderer.send(WebGLCommand::RenderbufferStorage(constants::RENDERBUFFER,
+                                                             native_format,
+                                                             width,
+                                                             height)).unwrap();
+        Ok(())
+    }
+}
+
+impl Drop for WebGLRenderbuffer {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
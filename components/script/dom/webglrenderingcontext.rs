@@ -0,0 +1,2957 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::byte_swap;
+use canvas_traits::webgl::{Parameter, TexParameter, WebGLCommand, WebGLContextShareMode, WebGLError};
+use canvas_traits::webgl::{WebGLFramebufferBindingRequest, WebGLMsg, WebGLMsgSender};
+use canvas_traits::webgl::{WebGLResult, WebGLVersion, webgl_channel};
+use canvas_traits::webgl::WebGLError::*;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLContextAttributes;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextMethods;
+use dom::bindings::codegen::UnionTypes::ArrayBufferViewOrArrayBuffer;
+use dom::bindings::codegen::UnionTypes::Float32ArrayOrUnrestrictedFloatSequence;
+use dom::bindings::codegen::UnionTypes::ImageBitmapOrImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement;
+use dom::bindings::codegen::UnionTypes::Int32ArrayOrLongSequence;
+use dom::bindings::conversions::{DerivedFrom, ToJSValConvertible};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom};
+use dom::bindings::str::DOMString;
+use dom::event::{Event, EventBubbles, EventCancelable};
+use dom::globalscope::GlobalScope;
+use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::imagebitmap::{premultiply, unpremultiply};
+use dom::node::{Node, NodeDamage, window_from_node};
+use dom::webgl_extensions::WebGLExtensions;
+use dom::webgl_validations::WebGLValidator;
+use dom::webgl_validations::tex_image_2d::{CommonTexImage2DValidator, CommonTexImage2DValidatorResult};
+use dom::webgl_validations::tex_image_2d::{TexImage2DValidator, TexImage2DValidatorResult};
+use dom::webgl_validations::types::{TexDataType, TexFormat, TexImageTarget};
+use dom::webglactiveinfo::WebGLActiveInfo;
+use dom::webglbuffer::WebGLBuffer;
+use dom::webglcontextevent::WebGLContextEvent;
+use dom::webglframebuffer::{WebGLFramebuffer, WebGLFramebufferAttachmentRoot};
+use dom::webglobject::WebGLObject;
+use dom::webglprogram::WebGLProgram;
+use dom::webglrenderbuffer::WebGLRenderbuffer;
+use dom::webglshader::WebGLShader;
+use dom::webglshaderprecisionformat::WebGLShaderPrecisionFormat;
+use dom::webgltexture::{TEXTURE_MAX_ANISOTROPY_EXT, TexParameterValue, WebGLTexture};
+use dom::webgluniformlocation::WebGLUniformLocation;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::Size2D;
+use js::jsapi::{JSContext, JSObject, Type};
+use js::jsval::{BooleanValue, DoubleValue, Int32Value, JSVal, NullValue, ObjectValue, UndefinedValue};
+use js::rust::CustomAutoRooterGuard;
+use js::typedarray::{ArrayBufferView, CreateWith, Float32Array, Int32Array, Uint32Array};
+use net_traits::image::base::PixelFormat;
+use offscreen_gl_context::{GLContextAttributes, GLLimits};
+use script_layout_interface::HTMLCanvasDataSource;
+use servo_config::prefs::PREFS;
+use std::cell::Cell;
+use std::cmp;
+use std::ptr::{self, NonNull};
+use webrender_api;
+
+type ImageSource = ImageBitmapOrImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement;
+
+/// Calls a `WebGLResult`-returning expression, recording the error on the
+/// context and evaluating to `$return_on_error` if it failed.
+macro_rules! handle_potential_webgl_error {
+    ($context:expr, $call:expr, $return_on_error:expr) => {
+        match $call {
+            Ok(ret) => ret,
+            Err(error) => {
+                $context.webgl_error(error.into());
+                $return_on_error
+            }
+        }
+    };
+    ($context:expr, $call:expr) => {
+        handle_potential_webgl_error!($context, $call, ());
+    };
+}
+
+bitflags! {
+    #[derive(JSTraceable, MallocSizeOf)]
+    struct TextureUnpacking: u8 {
+        const FLIP_Y_AXIS = 0x01;
+        const PREMULTIPLY_ALPHA = 0x02;
+        const CONVERT_COLORSPACE = 0x04;
+    }
+}
+
+bitflags! {
+    /// The capabilities toggled by `enable` and `disable`.
+    #[derive(JSTraceable, MallocSizeOf)]
+    struct Capabilities: u16 {
+        const BLEND = 0x0001;
+        const CULL_FACE = 0x0002;
+        const DEPTH_TEST = 0x0004;
+        const DITHER = 0x0008;
+        const POLYGON_OFFSET_FILL = 0x0010;
+        const SAMPLE_ALPHA_TO_COVERAGE = 0x0020;
+        const SAMPLE_COVERAGE = 0x0040;
+        const SCISSOR_TEST = 0x0080;
+        const STENCIL_TEST = 0x0100;
+    }
+}
+
+impl Capabilities {
+    fn from_gl_constant(cap: u32) -> WebGLResult<Self> {
+        Ok(match cap {
+            constants::BLEND => Capabilities::BLEND,
+            constants::CULL_FACE => Capabilities::CULL_FACE,
+            constants::DEPTH_TEST => Capabilities::DEPTH_TEST,
+            constants::DITHER => Capabilities::DITHER,
+            constants::POLYGON_OFFSET_FILL => Capabilities::POLYGON_OFFSET_FILL,
+            constants::SAMPLE_ALPHA_TO_COVERAGE => Capabilities::SAMPLE_ALPHA_TO_COVERAGE,
+            constants::SAMPLE_COVERAGE => Capabilities::SAMPLE_COVERAGE,
+            constants::SCISSOR_TEST => Capabilities::SCISSOR_TEST,
+            constants::STENCIL_TEST => Capabilities::STENCIL_TEST,
+            _ => return Err(InvalidEnum),
+        })
+    }
+}
+
+/// The textures bound to a texture unit.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct TextureUnit {
+    tex_2d: MutNullableDom<WebGLTexture>,
+    cube_map: MutNullableDom<WebGLTexture>,
+}
+
+/// The state of a vertex attribute array.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct VertexAttribData {
+    enabled: bool,
+    size: i32,
+    type_: u32,
+    normalized: bool,
+    stride: i32,
+    offset: u32,
+    buffer: Option<Dom<WebGLBuffer>>,
+}
+
+impl Default for VertexAttribData {
+    #[allow(unrooted_must_root)]
+    fn default() -> Self {
+        VertexAttribData {
+            enabled: false,
+            size: 4,
+            type_: constants::FLOAT,
+            normalized: false,
+            stride: 0,
+            offset: 0,
+            buffer: None,
+        }
+    }
+}
+
+#[dom_struct]
+pub struct WebGLRenderingContext {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "Channels are hard"]
+    webgl_sender: WebGLMsgSender,
+    #[ignore_malloc_size_of = "Defined in webrender"]
+    webrender_image: Cell<Option<webrender_api::ImageKey>>,
+    share_mode: WebGLContextShareMode,
+    /// The GLSL version shaders are translated to.
+    glsl_version: u32,
+    #[ignore_malloc_size_of = "Defined in offscreen_gl_context"]
+    limits: GLLimits,
+    canvas: Dom<HTMLCanvasElement>,
+    #[ignore_malloc_size_of = "Defined in canvas_traits"]
+    last_error: Cell<Option<WebGLError>>,
+    texture_unpacking_settings: Cell<TextureUnpacking>,
+    texture_unpacking_alignment: Cell<u32>,
+    bound_framebuffer: MutNullableDom<WebGLFramebuffer>,
+    bound_renderbuffer: MutNullableDom<WebGLRenderbuffer>,
+    bound_buffer_array: MutNullableDom<WebGLBuffer>,
+    bound_buffer_element_array: MutNullableDom<WebGLBuffer>,
+    texture_units: Box<[TextureUnit]>,
+    active_texture_unit: Cell<u32>,
+    vertex_attribs: DomRefCell<Box<[VertexAttribData]>>,
+    current_program: MutNullableDom<WebGLProgram>,
+    capabilities: Cell<Capabilities>,
+    extensions: WebGLExtensions,
+}
+
+impl WebGLRenderingContext {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(window: &Window,
+                     canvas: &HTMLCanvasElement,
+                     webgl_version: WebGLVersion,
+                     size: Size2D<i32>,
+                     attrs: GLContextAttributes)
+                     -> Result<WebGLRenderingContext, String> {
+        if let Some(true) = PREFS.get("webgl.testing.context_creation_error").as_boolean() {
+            return Err("WebGL context creation error forced by pref `webgl.testing.context_creation_error`".into());
+        }
+
+        let webgl_chan = match window.webgl_chan() {
+            Some(webgl_chan) => webgl_chan,
+            None => return Err("WebGL initialization failed early on".into()),
+        };
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        webgl_chan.send(WebGLMsg::CreateContext(webgl_version, size, attrs, sender)).unwrap();
+        let result = receiver.recv().unwrap()?;
+
+        // Shaders are translated to the GLSL version of the GL context,
+        // which is never required to be newer than GLSL 1.20 on desktop.
+        let glsl_version = if cfg!(target_os = "android") {
+            100
+        } else {
+            cmp::min(120, result.glsl_version.major * 100 + result.glsl_version.minor)
+        };
+
+        let limits = result.limits;
+        let texture_units = (0..limits.max_combined_texture_image_units).map(|_| {
+            TextureUnit {
+                tex_2d: Default::default(),
+                cube_map: Default::default(),
+            }
+        }).collect();
+        let vertex_attribs = (0..limits.max_vertex_attribs).map(|_| VertexAttribData::default()).collect();
+
+        Ok(WebGLRenderingContext {
+            reflector_: Reflector::new(),
+            webgl_sender: result.sender,
+            webrender_image: Cell::new(None),
+            share_mode: result.share_mode,
+            glsl_version,
+            limits,
+            canvas: Dom::from_ref(canvas),
+            last_error: Cell::new(None),
+            texture_unpacking_settings: Cell::new(TextureUnpacking::CONVERT_COLORSPACE),
+            texture_unpacking_alignment: Cell::new(4),
+            bound_framebuffer: MutNullableDom::new(None),
+            bound_renderbuffer: MutNullableDom::new(None),
+            bound_buffer_array: MutNullableDom::new(None),
+            bound_buffer_element_array: MutNullableDom::new(None),
+            texture_units,
+            active_texture_unit: Cell::new(0),
+            vertex_attribs: DomRefCell::new(vertex_attribs),
+            current_program: MutNullableDom::new(None),
+            capabilities: Cell::new(Capabilities::DITHER),
+            extensions: WebGLExtensions::new(),
+        })
+    }
+
+    /// Creates a WebGL context for `canvas`, firing a
+    /// `webglcontextcreationerror` event at it if that fails.
+    #[allow(unrooted_must_root)]
+    pub fn new(window: &Window,
+               canvas: &HTMLCanvasElement,
+               webgl_version: WebGLVersion,
+               size: Size2D<i32>,
+               attrs: GLContextAttributes)
+               -> Option<DomRoot<WebGLRenderingContext>> {
+        match WebGLRenderingContext::new_inherited(window, canvas, webgl_version, size, attrs) {
+            Ok(ctx) => Some(reflect_dom_object(Box::new(ctx), window, WebGLRenderingContextBinding::Wrap)),
+            Err(msg) => {
+                error!("Couldn't create WebGLRenderingContext: {}", msg);
+                let event = WebGLContextEvent::new(window,
+                                                   atom!("webglcontextcreationerror"),
+                                                   EventBubbles::DoesNotBubble,
+                                                   EventCancelable::Cancelable,
+                                                   DOMString::from(msg));
+                event.upcast::<Event>().fire(canvas.upcast());
+                None
+            }
+        }
+    }
+
+    pub fn limits(&self) -> &GLLimits {
+        &self.limits
+    }
+
+    pub fn webgl_sender(&self) -> WebGLMsgSender {
+        self.webgl_sender.clone()
+    }
+
+    #[inline]
+    pub fn send_command(&self, command: WebGLCommand) {
+        self.webgl_sender.send(command).unwrap();
+    }
+
+    /// Records `err` as the error returned by the next `getError` call,
+    /// unless an earlier error is still pending.
+    pub fn webgl_error(&self, err: WebGLError) {
+        warn!("WebGL error: {:?}, previous error was {:?}", err, self.last_error.get());
+
+        // If an error has been detected no further errors must be
+        // recorded until `getError` has been called
+        if self.last_error.get().is_none() {
+            self.last_error.set(Some(err));
+        }
+    }
+
+    /// Resizes the drawing buffer after the canvas changed size.
+    pub fn recreate(&self, size: Size2D<i32>) {
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.webgl_sender.send_resize(size, sender).unwrap();
+
+        if let Err(msg) = receiver.recv().unwrap() {
+            error!("Error resizing WebGLContext: {}", msg);
+            return;
+        };
+
+        // Resizing the drawing buffer rebinds it, so the objects bound by
+        // script need to be bound again.
+        let unit = self.active_texture_unit();
+        if let Some(texture) = unit.tex_2d.get() {
+            self.send_command(WebGLCommand::BindTexture(constants::TEXTURE_2D, Some(texture.id())));
+        }
+        if let Some(renderbuffer) = self.bound_renderbuffer.get() {
+            self.send_command(WebGLCommand::BindRenderbuffer(constants::RENDERBUFFER, Some(renderbuffer.id())));
+        }
+        if let Some(framebuffer) = self.bound_framebuffer.get() {
+            let request = WebGLFramebufferBindingRequest::Explicit(framebuffer.id());
+            self.send_command(WebGLCommand::BindFramebuffer(constants::FRAMEBUFFER, request));
+        }
+    }
+
+    /// Reads the drawing buffer back as BGRA pixels, top row first, whatever
+    /// framebuffer is bound by script.
+    pub fn get_image_data(&self, size: Size2D<i32>) -> Vec<u8> {
+        let framebuffer = self.bound_framebuffer.get();
+        if framebuffer.is_some() {
+            let request = WebGLFramebufferBindingRequest::Default;
+            self.send_command(WebGLCommand::BindFramebuffer(constants::FRAMEBUFFER, request));
+        }
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.send_command(WebGLCommand::ReadPixels(0, 0, size.width, size.height,
+                                                   constants::RGBA, constants::UNSIGNED_BYTE,
+                                                   sender));
+        let pixels: Vec<u8> = receiver.recv().unwrap().into();
+
+        if let Some(framebuffer) = framebuffer {
+            let request = WebGLFramebufferBindingRequest::Explicit(framebuffer.id());
+            self.send_command(WebGLCommand::BindFramebuffer(constants::FRAMEBUFFER, request));
+        }
+
+        // GL rows go from bottom to top.
+        let stride = size.width as usize * 4;
+        let mut data = Vec::with_capacity(pixels.len());
+        for row in pixels.chunks(stride).rev() {
+            data.extend_from_slice(row);
+        }
+        byte_swap(&mut data);
+        data
+    }
+
+    /// Returns the texture bound to `target` in the active texture unit.
+    pub fn bound_texture(&self, target: u32) -> Option<DomRoot<WebGLTexture>> {
+        let unit = self.active_texture_unit();
+        match target {
+            constants::TEXTURE_2D => unit.tex_2d.get(),
+            constants::TEXTURE_CUBE_MAP => unit.cube_map.get(),
+            _ => None,
+        }
+    }
+
+    fn active_texture_unit(&self) -> &TextureUnit {
+        &self.texture_units[self.active_texture_unit.get() as usize]
+    }
+
+    fn texture_slot(&self, target: u32) -> WebGLResult<&MutNullableDom<WebGLTexture>> {
+        let unit = self.active_texture_unit();
+        match target {
+            constants::TEXTURE_2D => Ok(&unit.tex_2d),
+            constants::TEXTURE_CUBE_MAP => Ok(&unit.cube_map),
+            _ => Err(InvalidEnum),
+        }
+    }
+
+    fn buffer_slot(&self, target: u32) -> WebGLResult<&MutNullableDom<WebGLBuffer>> {
+        match target {
+            constants::ARRAY_BUFFER => Ok(&self.bound_buffer_array),
+            constants::ELEMENT_ARRAY_BUFFER => Ok(&self.bound_buffer_element_array),
+            _ => Err(InvalidEnum),
+        }
+    }
+
+    fn bound_buffer(&self, target: u32) -> WebGLResult<DomRoot<WebGLBuffer>> {
+        self.buffer_slot(target)?.get().ok_or(InvalidOperation)
+    }
+
+    fn extensions(&self) -> &WebGLExtensions {
+        let sender = &self.webgl_sender;
+        self.extensions.init_once(cfg!(target_os = "android"), || {
+            let (tx, rx) = webgl_channel().unwrap();
+            sender.send(WebGLCommand::GetExtensions(tx)).unwrap();
+            rx.recv().unwrap()
+        });
+        &self.extensions
+    }
+
+    /// Checks that `object` was created by this context.
+    fn validate_ownership<T>(&self, object: &T) -> WebGLResult<()>
+        where T: DerivedFrom<WebGLObject>
+    {
+        if ptr::eq(&*object.upcast::<WebGLObject>().context(), self) {
+            Ok(())
+        } else {
+            Err(InvalidOperation)
+        }
+    }
+
+    fn validate_framebuffer_complete(&self) -> WebGLResult<()> {
+        match self.bound_framebuffer.get() {
+            Some(fb) if fb.check_status() != constants::FRAMEBUFFER_COMPLETE => {
+                Err(InvalidFramebufferOperation)
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn mark_as_dirty(&self) {
+        self.canvas.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    fn layout_handle(&self) -> webrender_api::ImageKey {
+        match self.share_mode {
+            WebGLContextShareMode::SharedTexture => {
+                // A shared texture only needs its image key to be created once.
+                self.webrender_image.get().unwrap_or_else(|| {
+                    let (sender, receiver) = webgl_channel().unwrap();
+                    self.webgl_sender.send_update_wr_image(sender).unwrap();
+                    let image_key = receiver.recv().unwrap();
+                    self.webrender_image.set(Some(image_key));
+                    image_key
+                })
+            },
+            WebGLContextShareMode::Readback => {
+                // The pixels need to be read back for every frame.
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.webgl_sender.send_update_wr_image(sender).unwrap();
+                receiver.recv().unwrap()
+            },
+        }
+    }
+
+    fn vertex_attrib(&self, index: u32, x: f32, y: f32, z: f32, w: f32) {
+        if index >= self.limits.max_vertex_attribs {
+            return self.webgl_error(InvalidValue);
+        }
+        self.send_command(WebGLCommand::VertexAttrib(index, x, y, z, w));
+    }
+
+    fn vertex_attrib_fv(&self, index: u32, values: Float32ArrayOrUnrestrictedFloatSequence, size: usize) {
+        let values = float32_list_to_vec(values);
+        if values.len() < size {
+            return self.webgl_error(InvalidValue);
+        }
+        let value = |i: usize| if i < size { values[i] } else if i == 3 { 1. } else { 0. };
+        self.vertex_attrib(index, value(0), value(1), value(2), value(3));
+    }
+
+    /// Checks that the vertex attribute arrays read by `program` have a
+    /// buffer bound, and that this buffer is large enough for
+    /// `vertex_count` vertices when it is known.
+    fn validate_vertex_attribs(&self, program: &WebGLProgram, vertex_count: Option<u32>) -> WebGLResult<()> {
+        let vertex_attribs = self.vertex_attribs.borrow();
+        for attrib in program.active_attribs().iter() {
+            if attrib.location < 0 {
+                continue;
+            }
+            let data = match vertex_attribs.get(attrib.location as usize) {
+                Some(data) if data.enabled => data,
+                _ => continue,
+            };
+            let buffer = match data.buffer {
+                Some(ref buffer) => buffer,
+                None => return Err(InvalidOperation),
+            };
+            let count = match vertex_count {
+                Some(count) if count > 0 => count as u64,
+                _ => continue,
+            };
+            let element_size = data.size as u64 * type_size(data.type_).unwrap_or(1) as u64;
+            let stride = if data.stride == 0 { element_size } else { data.stride as u64 };
+            let required = data.offset as u64 + stride * (count - 1) + element_size;
+            if required > buffer.capacity() as u64 {
+                return Err(InvalidOperation);
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_draw(&self, vertex_count: Option<u32>) -> WebGLResult<()> {
+        let program = self.current_program.get().ok_or(InvalidOperation)?;
+        if !program.is_linked() {
+            return Err(InvalidOperation);
+        }
+        self.validate_framebuffer_complete()?;
+        self.validate_vertex_attribs(&program, vertex_count)
+    }
+
+    /// Checks that `location` can be set by a uniform setter accepting the
+    /// `uniform_types`, with `values` holding whole vectors of
+    /// `vector_size` components. Returns `None` for a null location, which
+    /// is silently ignored.
+    fn validate_uniform_parameters<T>(&self,
+                                      location: Option<&WebGLUniformLocation>,
+                                      uniform_types: &[u32],
+                                      vector_size: usize,
+                                      values: &[T])
+                                      -> WebGLResult<Option<i32>> {
+        let location = match location {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+
+        if !location.is_from(self) {
+            return Err(InvalidOperation);
+        }
+
+        match self.current_program.get() {
+            Some(ref program) if program.id() == location.program_id() => {},
+            _ => return Err(InvalidOperation),
+        }
+
+        if !uniform_types.contains(&location.type_()) {
+            return Err(InvalidOperation);
+        }
+
+        if values.is_empty() || values.len() % vector_size != 0 {
+            return Err(InvalidValue);
+        }
+
+        // Only arrays can be set from more than one vector.
+        if values.len() > vector_size && location.size().is_none() {
+            return Err(InvalidOperation);
+        }
+
+        Ok(Some(location.id()))
+    }
+
+    fn validate_sampler_values(&self, location: Option<&WebGLUniformLocation>, values: &[i32]) -> WebGLResult<()> {
+        match location.map(|location| location.type_()) {
+            Some(constants::SAMPLER_2D) | Some(constants::SAMPLER_CUBE) => {},
+            _ => return Ok(()),
+        }
+        let units = self.limits.max_combined_texture_image_units as i32;
+        if values.iter().any(|&value| value < 0 || value >= units) {
+            return Err(InvalidValue);
+        }
+        Ok(())
+    }
+
+    fn uniform_matrix_fv(&self,
+                         location: Option<&WebGLUniformLocation>,
+                         transpose: bool,
+                         values: Float32ArrayOrUnrestrictedFloatSequence,
+                         uniform_type: u32,
+                         vector_size: usize)
+                         -> WebGLResult<Option<(i32, Vec<f32>)>> {
+        let values = float32_list_to_vec(values);
+        let location = self.validate_uniform_parameters(location, &[uniform_type], vector_size, &values)?;
+        if transpose {
+            return Err(InvalidValue);
+        }
+        Ok(location.map(|location| (location, values)))
+    }
+
+    /// Returns the pixels of `source` as RGBA along with its size, and
+    /// whether they are premultiplied, or `None` if there is nothing to
+    /// upload.
+    fn get_image_pixels(&self, source: ImageSource) -> Fallible<Option<(Vec<u8>, Size2D<u32>, bool)>> {
+        Ok(Some(match source {
+            ImageSource::ImageBitmap(bitmap) => {
+                if !bitmap.origin_is_clean() {
+                    return Err(Error::Security);
+                }
+                let (mut data, size) = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+                byte_swap(&mut data);
+                (data, size, bitmap.is_alpha_premultiplied())
+            },
+            ImageSource::ImageData(image_data) => {
+                let size = Size2D::new(image_data.Width(), image_data.Height());
+                (image_data.get_data_array(), size, false)
+            },
+            ImageSource::HTMLImageElement(image) => {
+                let origin = match image.get_origin() {
+                    Some(origin) => origin,
+                    None => return Ok(None),
+                };
+                if !origin.same_origin(GlobalScope::entry().origin()) {
+                    return Err(Error::Security);
+                }
+                let image = match image.get_image() {
+                    Some(image) => image,
+                    None => return Ok(None),
+                };
+                let mut data = match image.format {
+                    PixelFormat::BGRA8 => image.first_frame_bytes().to_vec(),
+                    format => unimplemented!("unsupported pixel format ({:?})", format),
+                };
+                byte_swap(&mut data);
+                (data, Size2D::new(image.width, image.height), false)
+            },
+            ImageSource::HTMLCanvasElement(canvas) => {
+                if !canvas.origin_is_clean() {
+                    return Err(Error::Security);
+                }
+                let (mut data, size) = match canvas.fetch_all_data() {
+                    Some(data) => data,
+                    None => return Ok(None),
+                };
+                byte_swap(&mut data);
+                (data, size.to_u32(), true)
+            },
+            // TODO: Support video elements once they can be decoded.
+            ImageSource::HTMLVideoElement(_) => return Ok(None),
+        }))
+    }
+
+    /// Converts RGBA pixels coming from a `TexImageSource` to tightly packed
+    /// texture data of the given format and type, following the unpacking
+    /// settings.
+    fn prepare_image_pixels(&self,
+                            pixels: Vec<u8>,
+                            size: Size2D<u32>,
+                            premultiplied: bool,
+                            format: TexFormat,
+                            data_type: TexDataType)
+                            -> Vec<u8> {
+        let settings = self.texture_unpacking_settings.get();
+        let mut pixels = pixels;
+        let premultiply_alpha = settings.contains(TextureUnpacking::PREMULTIPLY_ALPHA);
+        if premultiply_alpha && !premultiplied {
+            premultiply(&mut pixels);
+        } else if !premultiply_alpha && premultiplied {
+            unpremultiply(&mut pixels);
+        }
+
+        let mut pixels = rgba8_image_to_tex_image_data(format, data_type, pixels);
+
+        if settings.contains(TextureUnpacking::FLIP_Y_AXIS) {
+            let stride = bytes_per_pixel(format, data_type) as usize * size.width as usize;
+            flip_pixels_y(&mut pixels, stride, size.height as usize);
+        }
+
+        pixels
+    }
+
+    /// Uploads tightly packed pixels, overriding the unpack alignment set by
+    /// script for the duration of the upload.
+    fn tex_image_2d_unaligned(&self, texture: &WebGLTexture, validated: TexImage2DValidatorResult, pixels: Vec<u8>) {
+        self.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT, 1));
+        self.tex_image_2d(texture, validated, pixels);
+        self.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT,
+                                                    self.texture_unpacking_alignment.get() as i32));
+    }
+
+    fn tex_image_2d(&self, texture: &WebGLTexture, validated: TexImage2DValidatorResult, pixels: Vec<u8>) {
+        let TexImage2DValidatorResult { target, level, width, height, format, data_type, .. } = validated;
+
+        self.send_command(WebGLCommand::TexImage2D(target.as_gl_constant(),
+                                                   level as i32,
+                                                   format.as_gl_constant() as i32,
+                                                   width as i32,
+                                                   height as i32,
+                                                   format.as_gl_constant(),
+                                                   data_type.as_gl_constant(),
+                                                   pixels.into()));
+
+        texture.initialize(target, width, height, format, level, Some(data_type));
+
+        if let Some(fb) = self.bound_framebuffer.get() {
+            fb.invalidate_texture(texture);
+        }
+    }
+
+    /// Validates the arguments of `texSubImage2D`, returning the target,
+    /// level, format and type of the upload.
+    fn validate_tex_sub_image_2d(&self,
+                                 target: u32,
+                                 level: i32,
+                                 xoffset: i32,
+                                 yoffset: i32,
+                                 width: i32,
+                                 height: i32,
+                                 format: u32,
+                                 data_type: u32)
+                                 -> WebGLResult<(TexImageTarget, u32, TexFormat, TexDataType)> {
+        let validator = CommonTexImage2DValidator::new(self, target, level, width, height);
+        let CommonTexImage2DValidatorResult { texture, target, level, width, height } = validator.validate()?;
+
+        let format = TexFormat::from_gl_constant(format).ok_or(InvalidEnum)?;
+        let data_type = TexDataType::from_gl_constant(data_type).ok_or(InvalidEnum)?;
+
+        // GL_INVALID_VALUE is generated if xoffset or yoffset is negative, or
+        // if the updated region goes outside of the texture image.
+        if xoffset < 0 || yoffset < 0 {
+            return Err(InvalidValue);
+        }
+        let image_info = texture.image_info_for_target(&target, level);
+        if xoffset as u32 + width > image_info.width() || yoffset as u32 + height > image_info.height() {
+            return Err(InvalidValue);
+        }
+
+        // GL_INVALID_OPERATION is generated if format and type don't match
+        // those of the texture image.
+        if image_info.internal_format() != Some(format) || image_info.data_type() != Some(data_type) {
+            return Err(InvalidOperation);
+        }
+
+        Ok((target, level, format, data_type))
+    }
+
+    fn tex_parameter(&self, target: u32, name: u32, value: TexParameterValue) {
+        let texture = match target {
+            constants::TEXTURE_2D |
+            constants::TEXTURE_CUBE_MAP => self.bound_texture(target),
+            _ => return self.webgl_error(InvalidEnum),
+        };
+        let texture = match texture {
+            Some(texture) => texture,
+            None => return self.webgl_error(InvalidOperation),
+        };
+        if name == TEXTURE_MAX_ANISOTROPY_EXT && !self.extensions().is_anisotropic_filtering_enabled() {
+            return self.webgl_error(InvalidEnum);
+        }
+        handle_potential_webgl_error!(self, texture.tex_parameter(target, name, value));
+    }
+
+    fn get_gl_parameter(&self, cx: *mut JSContext, parameter: Parameter) -> JSVal {
+        match parameter {
+            Parameter::Bool(param) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetParameterBool(param, sender));
+                BooleanValue(receiver.recv().unwrap())
+            },
+            Parameter::Bool4(param) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetParameterBool4(param, sender));
+                unsafe { to_js_value(cx, receiver.recv().unwrap().to_vec()) }
+            },
+            Parameter::Int(param) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetParameterInt(param, sender));
+                Int32Value(receiver.recv().unwrap())
+            },
+            Parameter::Int2(param) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetParameterInt2(param, sender));
+                unsafe { int32_array_value(cx, &receiver.recv().unwrap()) }
+            },
+            Parameter::Int4(param) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetParameterInt4(param, sender));
+                unsafe { int32_array_value(cx, &receiver.recv().unwrap()) }
+            },
+            Parameter::Float(param) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetParameterFloat(param, sender));
+                DoubleValue(receiver.recv().unwrap() as f64)
+            },
+            Parameter::Float2(param) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetParameterFloat2(param, sender));
+                unsafe { float32_array_value(cx, &receiver.recv().unwrap()) }
+            },
+            Parameter::Float4(param) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetParameterFloat4(param, sender));
+                unsafe { float32_array_value(cx, &receiver.recv().unwrap()) }
+            },
+        }
+    }
+}
+
+impl Drop for WebGLRenderingContext {
+    fn drop(&mut self) {
+        let _ = self.webgl_sender.send_remove();
+    }
+}
+
+impl WebGLRenderingContextMethods for WebGLRenderingContext {
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.1
+    fn Canvas(&self) -> DomRoot<HTMLCanvasElement> {
+        DomRoot::from_ref(&*self.canvas)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.1
+    fn DrawingBufferWidth(&self) -> i32 {
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.send_command(WebGLCommand::DrawingBufferWidth(sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.1
+    fn DrawingBufferHeight(&self) -> i32 {
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.send_command(WebGLCommand::DrawingBufferHeight(sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.2
+    fn GetContextAttributes(&self) -> Option<WebGLContextAttributes> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.send_command(WebGLCommand::GetContextAttributes(sender));
+        let attrs = receiver.recv().unwrap();
+
+        Some(WebGLContextAttributes {
+            alpha: attrs.alpha,
+            antialias: attrs.antialias,
+            depth: attrs.depth,
+            failIfMajorPerformanceCaveat: false,
+            premultipliedAlpha: attrs.premultiplied_alpha,
+            preserveDrawingBuffer: attrs.preserve_drawing_buffer,
+            stencil: attrs.stencil,
+        })
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.13
+    fn IsContextLost(&self) -> bool {
+        false
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.14
+    fn GetSupportedExtensions(&self) -> Option<Vec<DOMString>> {
+        Some(self.extensions().get_supported_extensions().into_iter().map(DOMString::from).collect())
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.14
+    unsafe fn GetExtension(&self, _cx: *mut JSContext, name: DOMString) -> Option<NonNull<JSObject>> {
+        self.extensions().get_or_init_extension(&name, self)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn ActiveTexture(&self, texture: u32) {
+        let units = self.limits.max_combined_texture_image_units;
+        if texture < constants::TEXTURE0 || texture - constants::TEXTURE0 >= units {
+            return self.webgl_error(InvalidEnum);
+        }
+        self.active_texture_unit.set(texture - constants::TEXTURE0);
+        self.send_command(WebGLCommand::ActiveTexture(texture));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn AttachShader(&self, program: &WebGLProgram, shader: &WebGLShader) {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return);
+        handle_potential_webgl_error!(self, self.validate_ownership(shader), return);
+        handle_potential_webgl_error!(self, program.attach_shader(shader));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn BindAttribLocation(&self, program: &WebGLProgram, index: u32, name: DOMString) {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return);
+        if index >= self.limits.max_vertex_attribs {
+            return self.webgl_error(InvalidValue);
+        }
+        handle_potential_webgl_error!(self, program.bind_attrib_location(index, name));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
+    fn BindBuffer(&self, target: u32, buffer: Option<&WebGLBuffer>) {
+        let slot = handle_potential_webgl_error!(self, self.buffer_slot(target), return);
+        match buffer {
+            Some(buffer) => {
+                handle_potential_webgl_error!(self, self.validate_ownership(buffer), return);
+                handle_potential_webgl_error!(self, buffer.bind(target), return);
+            },
+            None => self.send_command(WebGLCommand::BindBuffer(target, None)),
+        }
+        slot.set(buffer);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
+    fn BindFramebuffer(&self, target: u32, framebuffer: Option<&WebGLFramebuffer>) {
+        if target != constants::FRAMEBUFFER {
+            return self.webgl_error(InvalidEnum);
+        }
+        match framebuffer {
+            Some(framebuffer) => {
+                handle_potential_webgl_error!(self, self.validate_ownership(framebuffer), return);
+                if framebuffer.is_deleted() {
+                    return self.webgl_error(InvalidOperation);
+                }
+                framebuffer.bind(target);
+            },
+            None => {
+                // Bind the default framebuffer, which is the drawing buffer
+                // of the canvas and not GL's framebuffer 0.
+                let request = WebGLFramebufferBindingRequest::Default;
+                self.send_command(WebGLCommand::BindFramebuffer(target, request));
+            },
+        }
+        self.bound_framebuffer.set(framebuffer);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
+    fn BindRenderbuffer(&self, target: u32, renderbuffer: Option<&WebGLRenderbuffer>) {
+        if target != constants::RENDERBUFFER {
+            return self.webgl_error(InvalidEnum);
+        }
+        match renderbuffer {
+            Some(renderbuffer) => {
+                handle_potential_webgl_error!(self, self.validate_ownership(renderbuffer), return);
+                if renderbuffer.is_deleted() {
+                    return self.webgl_error(InvalidOperation);
+                }
+                renderbuffer.bind(target);
+            },
+            None => self.send_command(WebGLCommand::BindRenderbuffer(target, None)),
+        }
+        self.bound_renderbuffer.set(renderbuffer);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn BindTexture(&self, target: u32, texture: Option<&WebGLTexture>) {
+        let slot = handle_potential_webgl_error!(self, self.texture_slot(target), return);
+        match texture {
+            Some(texture) => {
+                handle_potential_webgl_error!(self, self.validate_ownership(texture), return);
+                handle_potential_webgl_error!(self, texture.bind(target), return);
+            },
+            None => self.send_command(WebGLCommand::BindTexture(target, None)),
+        }
+        slot.set(texture);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn BlendColor(&self, r: f32, g: f32, b: f32, a: f32) {
+        self.send_command(WebGLCommand::BlendColor(r, g, b, a));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn BlendEquation(&self, mode: u32) {
+        handle_potential_webgl_error!(self, validate_blend_equation(mode), return);
+        self.send_command(WebGLCommand::BlendEquation(mode));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn BlendEquationSeparate(&self, mode_rgb: u32, mode_alpha: u32) {
+        handle_potential_webgl_error!(self, validate_blend_equation(mode_rgb), return);
+        handle_potential_webgl_error!(self, validate_blend_equation(mode_alpha), return);
+        self.send_command(WebGLCommand::BlendEquationSeparate(mode_rgb, mode_alpha));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn BlendFunc(&self, src_factor: u32, dest_factor: u32) {
+        handle_potential_webgl_error!(self, validate_blend_factors(src_factor, dest_factor), return);
+        self.send_command(WebGLCommand::BlendFunc(src_factor, dest_factor));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn BlendFuncSeparate(&self, src_rgb: u32, dest_rgb: u32, src_alpha: u32, dest_alpha: u32) {
+        handle_potential_webgl_error!(self, validate_blend_factors(src_rgb, dest_rgb), return);
+        handle_potential_webgl_error!(self, validate_blend_factors(src_alpha, dest_alpha), return);
+        self.send_command(WebGLCommand::BlendFuncSeparate(src_rgb, dest_rgb, src_alpha, dest_alpha));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
+    fn BufferData(&self, target: u32, data: Option<ArrayBufferViewOrArrayBuffer>, usage: u32) {
+        let buffer = handle_potential_webgl_error!(self, self.bound_buffer(target), return);
+        let data = match data {
+            Some(ArrayBufferViewOrArrayBuffer::ArrayBufferView(data)) => data.to_vec(),
+            Some(ArrayBufferViewOrArrayBuffer::ArrayBuffer(data)) => data.to_vec(),
+            None => return self.webgl_error(InvalidValue),
+        };
+        handle_potential_webgl_error!(self, buffer.buffer_data(target, data, usage));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
+    fn BufferData_(&self, target: u32, size: i64, usage: u32) {
+        let buffer = handle_potential_webgl_error!(self, self.bound_buffer(target), return);
+        if size < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        handle_potential_webgl_error!(self, buffer.buffer_data(target, vec![0; size as usize], usage));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
+    fn BufferSubData(&self, target: u32, offset: i64, data: ArrayBufferViewOrArrayBuffer) {
+        let buffer = handle_potential_webgl_error!(self, self.bound_buffer(target), return);
+        let data = match data {
+            ArrayBufferViewOrArrayBuffer::ArrayBufferView(data) => data.to_vec(),
+            ArrayBufferViewOrArrayBuffer::ArrayBuffer(data) => data.to_vec(),
+        };
+        if offset < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        if offset as u64 + data.len() as u64 > buffer.capacity() as u64 {
+            return self.webgl_error(InvalidValue);
+        }
+        self.send_command(WebGLCommand::BufferSubData(target, offset as isize, data.into()));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
+    fn CheckFramebufferStatus(&self, target: u32) -> u32 {
+        if target != constants::FRAMEBUFFER {
+            self.webgl_error(InvalidEnum);
+            return 0;
+        }
+        match self.bound_framebuffer.get() {
+            Some(fb) => fb.check_status(),
+            None => constants::FRAMEBUFFER_COMPLETE,
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
+    fn Clear(&self, mask: u32) {
+        let valid_bits = constants::DEPTH_BUFFER_BIT | constants::STENCIL_BUFFER_BIT | constants::COLOR_BUFFER_BIT;
+        if mask & !valid_bits != 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        handle_potential_webgl_error!(self, self.validate_framebuffer_complete(), return);
+        self.send_command(WebGLCommand::Clear(mask));
+        self.mark_as_dirty();
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn ClearColor(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.send_command(WebGLCommand::ClearColor(red, green, blue, alpha));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn ClearDepth(&self, depth: f32) {
+        self.send_command(WebGLCommand::ClearDepth(depth));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn ClearStencil(&self, stencil: i32) {
+        self.send_command(WebGLCommand::ClearStencil(stencil));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn ColorMask(&self, r: bool, g: bool, b: bool, a: bool) {
+        self.send_command(WebGLCommand::ColorMask(r, g, b, a));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn CompileShader(&self, shader: &WebGLShader) {
+        handle_potential_webgl_error!(self, self.validate_ownership(shader), return);
+        if shader.is_deleted() {
+            return self.webgl_error(InvalidValue);
+        }
+        shader.compile(self.glsl_version, self.extensions().is_standard_derivatives_enabled());
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn CompressedTexImage2D(&self, _target: u32, _level: i32, _internal_format: u32,
+                            _width: i32, _height: i32, _border: i32,
+                            _data: CustomAutoRooterGuard<ArrayBufferView>) {
+        // No compressed texture format is supported.
+        self.webgl_error(InvalidEnum);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn CompressedTexSubImage2D(&self, _target: u32, _level: i32, _xoffset: i32,
+                               _yoffset: i32, _width: i32, _height: i32, _format: u32,
+                               _data: CustomAutoRooterGuard<ArrayBufferView>) {
+        self.webgl_error(InvalidEnum);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn CopyTexImage2D(&self, target: u32, level: i32, internal_format: u32,
+                      x: i32, y: i32, width: i32, height: i32, border: i32) {
+        let validator = CommonTexImage2DValidator::new(self, target, level, width, height);
+        let CommonTexImage2DValidatorResult { texture, target, level, width, height } =
+            handle_potential_webgl_error!(self, validator.validate(), return);
+
+        let internal_format = match TexFormat::from_gl_constant(internal_format) {
+            Some(TexFormat::DepthComponent) | None => return self.webgl_error(InvalidEnum),
+            Some(format) => format,
+        };
+        if border != 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        if target.is_cubic() && width != height {
+            return self.webgl_error(InvalidValue);
+        }
+        if level > 0 && (!width.is_power_of_two() || !height.is_power_of_two()) {
+            return self.webgl_error(InvalidValue);
+        }
+        handle_potential_webgl_error!(self, self.validate_framebuffer_complete(), return);
+
+        self.send_command(WebGLCommand::CopyTexImage2D(target.as_gl_constant(),
+                                                       level as i32,
+                                                       internal_format.as_gl_constant(),
+                                                       x, y,
+                                                       width as i32, height as i32,
+                                                       border));
+
+        texture.initialize(target, width, height, internal_format, level, Some(TexDataType::UnsignedByte));
+        if let Some(fb) = self.bound_framebuffer.get() {
+            fb.invalidate_texture(&texture);
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn CopyTexSubImage2D(&self, target: u32, level: i32, xoffset: i32, yoffset: i32,
+                         x: i32, y: i32, width: i32, height: i32) {
+        let validator = CommonTexImage2DValidator::new(self, target, level, width, height);
+        let CommonTexImage2DValidatorResult { texture, target, level, width, height } =
+            handle_potential_webgl_error!(self, validator.validate(), return);
+
+        if xoffset < 0 || yoffset < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        let image_info = texture.image_info_for_target(&target, level);
+        if xoffset as u32 + width > image_info.width() || yoffset as u32 + height > image_info.height() {
+            return self.webgl_error(InvalidValue);
+        }
+        handle_potential_webgl_error!(self, self.validate_framebuffer_complete(), return);
+
+        self.send_command(WebGLCommand::CopyTexSubImage2D(target.as_gl_constant(),
+                                                          level as i32,
+                                                          xoffset, yoffset,
+                                                          x, y,
+                                                          width as i32, height as i32));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
+    fn CreateBuffer(&self) -> Option<DomRoot<WebGLBuffer>> {
+        WebGLBuffer::maybe_new(self)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
+    fn CreateFramebuffer(&self) -> Option<DomRoot<WebGLFramebuffer>> {
+        WebGLFramebuffer::maybe_new(self)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn CreateProgram(&self) -> Option<DomRoot<WebGLProgram>> {
+        WebGLProgram::maybe_new(self)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
+    fn CreateRenderbuffer(&self) -> Option<DomRoot<WebGLRenderbuffer>> {
+        WebGLRenderbuffer::maybe_new(self)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn CreateShader(&self, shader_type: u32) -> Option<DomRoot<WebGLShader>> {
+        match shader_type {
+            constants::VERTEX_SHADER | constants::FRAGMENT_SHADER => {},
+            _ => {
+                self.webgl_error(InvalidEnum);
+                return None;
+            },
+        }
+        WebGLShader::maybe_new(self, shader_type)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn CreateTexture(&self) -> Option<DomRoot<WebGLTexture>> {
+        WebGLTexture::maybe_new(self)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn CullFace(&self, mode: u32) {
+        match mode {
+            constants::FRONT | constants::BACK | constants::FRONT_AND_BACK => {
+                self.send_command(WebGLCommand::CullFace(mode))
+            },
+            _ => self.webgl_error(InvalidEnum),
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
+    fn DeleteBuffer(&self, buffer: Option<&WebGLBuffer>) {
+        let buffer = match buffer {
+            Some(buffer) => buffer,
+            None => return,
+        };
+        handle_potential_webgl_error!(self, self.validate_ownership(buffer), return);
+
+        // Deleting a buffer unbinds it from the context, and detaches it
+        // from the vertex attribute arrays.
+        for slot in &[&self.bound_buffer_array, &self.bound_buffer_element_array] {
+            if slot.get().map_or(false, |bound| bound.id() == buffer.id()) {
+                slot.set(None);
+            }
+        }
+        for attrib in self.vertex_attribs.borrow_mut().iter_mut() {
+            if attrib.buffer.as_ref().map_or(false, |attached| attached.id() == buffer.id()) {
+                attrib.buffer = None;
+            }
+        }
+        buffer.delete();
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
+    fn DeleteFramebuffer(&self, framebuffer: Option<&WebGLFramebuffer>) {
+        let framebuffer = match framebuffer {
+            Some(framebuffer) => framebuffer,
+            None => return,
+        };
+        handle_potential_webgl_error!(self, self.validate_ownership(framebuffer), return);
+
+        if self.bound_framebuffer.get().map_or(false, |bound| bound.id() == framebuffer.id()) {
+            self.bound_framebuffer.set(None);
+            let request = WebGLFramebufferBindingRequest::Default;
+            self.send_command(WebGLCommand::BindFramebuffer(constants::FRAMEBUFFER, request));
+        }
+        framebuffer.delete();
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn DeleteProgram(&self, program: Option<&WebGLProgram>) {
+        if let Some(program) = program {
+            handle_potential_webgl_error!(self, self.validate_ownership(program), return);
+            program.mark_for_deletion();
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
+    fn DeleteRenderbuffer(&self, renderbuffer: Option<&WebGLRenderbuffer>) {
+        let renderbuffer = match renderbuffer {
+            Some(renderbuffer) => renderbuffer,
+            None => return,
+        };
+        handle_potential_webgl_error!(self, self.validate_ownership(renderbuffer), return);
+
+        if self.bound_renderbuffer.get().map_or(false, |bound| bound.id() == renderbuffer.id()) {
+            self.bound_renderbuffer.set(None);
+        }
+        if let Some(fb) = self.bound_framebuffer.get() {
+            fb.detach_renderbuffer(renderbuffer);
+        }
+        renderbuffer.delete();
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn DeleteShader(&self, shader: Option<&WebGLShader>) {
+        if let Some(shader) = shader {
+            handle_potential_webgl_error!(self, self.validate_ownership(shader), return);
+            shader.mark_for_deletion();
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn DeleteTexture(&self, texture: Option<&WebGLTexture>) {
+        let texture = match texture {
+            Some(texture) => texture,
+            None => return,
+        };
+        handle_potential_webgl_error!(self, self.validate_ownership(texture), return);
+
+        for unit in self.texture_units.iter() {
+            for slot in &[&unit.tex_2d, &unit.cube_map] {
+                if slot.get().map_or(false, |bound| bound.id() == texture.id()) {
+                    slot.set(None);
+                }
+            }
+        }
+        if let Some(fb) = self.bound_framebuffer.get() {
+            fb.detach_texture(texture);
+        }
+        texture.delete();
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn DepthFunc(&self, func: u32) {
+        handle_potential_webgl_error!(self, validate_comparison_function(func), return);
+        self.send_command(WebGLCommand::DepthFunc(func));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn DepthMask(&self, flag: bool) {
+        self.send_command(WebGLCommand::DepthMask(flag));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn DepthRange(&self, near: f32, far: f32) {
+        // WebGL forbids a near plane further than the far plane.
+        if near > far {
+            return self.webgl_error(InvalidOperation);
+        }
+        self.send_command(WebGLCommand::DepthRange(near, far));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn DetachShader(&self, program: &WebGLProgram, shader: &WebGLShader) {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return);
+        handle_potential_webgl_error!(self, self.validate_ownership(shader), return);
+        handle_potential_webgl_error!(self, program.detach_shader(shader));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn Disable(&self, cap: u32) {
+        let capability = handle_potential_webgl_error!(self, Capabilities::from_gl_constant(cap), return);
+        let mut capabilities = self.capabilities.get();
+        capabilities.remove(capability);
+        self.capabilities.set(capabilities);
+        self.send_command(WebGLCommand::Disable(cap));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn DisableVertexAttribArray(&self, index: u32) {
+        if index >= self.limits.max_vertex_attribs {
+            return self.webgl_error(InvalidValue);
+        }
+        self.vertex_attribs.borrow_mut()[index as usize].enabled = false;
+        self.send_command(WebGLCommand::DisableVertexAttribArray(index));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
+    fn DrawArrays(&self, mode: u32, first: i32, count: i32) {
+        handle_potential_webgl_error!(self, validate_draw_mode(mode), return);
+        if first < 0 || count < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        let vertex_count = match (first as u32).checked_add(count as u32) {
+            Some(_) if count == 0 => 0,
+            Some(vertex_count) => vertex_count,
+            None => return self.webgl_error(InvalidOperation),
+        };
+        handle_potential_webgl_error!(self, self.validate_draw(Some(vertex_count)), return);
+        if count == 0 {
+            return;
+        }
+
+        self.send_command(WebGLCommand::DrawArrays { mode, first, count });
+        self.mark_as_dirty();
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
+    fn DrawElements(&self, mode: u32, count: i32, type_: u32, offset: i64) {
+        handle_potential_webgl_error!(self, validate_draw_mode(mode), return);
+        let type_size = match type_ {
+            constants::UNSIGNED_BYTE => 1,
+            constants::UNSIGNED_SHORT => 2,
+            constants::UNSIGNED_INT if self.extensions().is_element_index_uint_enabled() => 4,
+            _ => return self.webgl_error(InvalidEnum),
+        };
+        if count < 0 || offset < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        if offset % type_size != 0 {
+            return self.webgl_error(InvalidOperation);
+        }
+        let buffer = match self.bound_buffer_element_array.get() {
+            Some(buffer) => buffer,
+            None => return self.webgl_error(InvalidOperation),
+        };
+        if offset as u64 + count as u64 * type_size as u64 > buffer.capacity() as u64 {
+            return self.webgl_error(InvalidOperation);
+        }
+        handle_potential_webgl_error!(self, self.validate_draw(None), return);
+        if count == 0 {
+            return;
+        }
+
+        self.send_command(WebGLCommand::DrawElements { mode, count, type_, offset: offset as u32 });
+        self.mark_as_dirty();
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn Enable(&self, cap: u32) {
+        let capability = handle_potential_webgl_error!(self, Capabilities::from_gl_constant(cap), return);
+        let mut capabilities = self.capabilities.get();
+        capabilities.insert(capability);
+        self.capabilities.set(capabilities);
+        self.send_command(WebGLCommand::Enable(cap));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn EnableVertexAttribArray(&self, index: u32) {
+        if index >= self.limits.max_vertex_attribs {
+            return self.webgl_error(InvalidValue);
+        }
+        self.vertex_attribs.borrow_mut()[index as usize].enabled = true;
+        self.send_command(WebGLCommand::EnableVertexAttribArray(index));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
+    fn Finish(&self) {
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.send_command(WebGLCommand::Finish(sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
+    fn Flush(&self) {
+        self.send_command(WebGLCommand::Flush);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
+    fn FramebufferRenderbuffer(&self, target: u32, attachment: u32,
+                               renderbuffertarget: u32,
+                               rb: Option<&WebGLRenderbuffer>) {
+        if target != constants::FRAMEBUFFER || renderbuffertarget != constants::RENDERBUFFER {
+            return self.webgl_error(InvalidEnum);
+        }
+        let fb = match self.bound_framebuffer.get() {
+            Some(fb) => fb,
+            None => return self.webgl_error(InvalidOperation),
+        };
+        if let Some(rb) = rb {
+            handle_potential_webgl_error!(self, self.validate_ownership(rb), return);
+        }
+        handle_potential_webgl_error!(self, fb.renderbuffer(attachment, rb));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
+    fn FramebufferTexture2D(&self, target: u32, attachment: u32,
+                            textarget: u32, texture: Option<&WebGLTexture>,
+                            level: i32) {
+        if target != constants::FRAMEBUFFER {
+            return self.webgl_error(InvalidEnum);
+        }
+        let fb = match self.bound_framebuffer.get() {
+            Some(fb) => fb,
+            None => return self.webgl_error(InvalidOperation),
+        };
+        if let Some(texture) = texture {
+            handle_potential_webgl_error!(self, self.validate_ownership(texture), return);
+        }
+        handle_potential_webgl_error!(self, fb.texture2d(attachment, textarget, texture, level));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn FrontFace(&self, mode: u32) {
+        match mode {
+            constants::CW | constants::CCW => self.send_command(WebGLCommand::FrontFace(mode)),
+            _ => self.webgl_error(InvalidEnum),
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn GenerateMipmap(&self, target: u32) {
+        let slot = handle_potential_webgl_error!(self, self.texture_slot(target), return);
+        let texture = match slot.get() {
+            Some(texture) => texture,
+            None => return self.webgl_error(InvalidOperation),
+        };
+        handle_potential_webgl_error!(self, texture.generate_mipmap());
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn GetActiveAttrib(&self, program: &WebGLProgram, index: u32) -> Option<DomRoot<WebGLActiveInfo>> {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return None);
+        let (name, size, type_) = handle_potential_webgl_error!(self, program.get_active_attrib(index), return None);
+        Some(WebGLActiveInfo::new(&window_from_node(&*self.canvas), size, type_, name.into()))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn GetActiveUniform(&self, program: &WebGLProgram, index: u32) -> Option<DomRoot<WebGLActiveInfo>> {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return None);
+        let (name, size, type_) = handle_potential_webgl_error!(self, program.get_active_uniform(index), return None);
+        Some(WebGLActiveInfo::new(&window_from_node(&*self.canvas), size, type_, name.into()))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn GetAttachedShaders(&self, program: &WebGLProgram) -> Option<Vec<DomRoot<WebGLShader>>> {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return None);
+        Some(handle_potential_webgl_error!(self, program.attached_shaders(), return None))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn GetAttribLocation(&self, program: &WebGLProgram, name: DOMString) -> i32 {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return -1);
+        handle_potential_webgl_error!(self, program.get_attrib_location(name), -1)
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
+    unsafe fn GetBufferParameter(&self, _cx: *mut JSContext, target: u32, parameter: u32) -> JSVal {
+        let buffer = handle_potential_webgl_error!(self, self.bound_buffer(target), return NullValue());
+        match parameter {
+            constants::BUFFER_SIZE => Int32Value(buffer.capacity() as i32),
+            constants::BUFFER_USAGE => Int32Value(buffer.usage() as i32),
+            _ => {
+                self.webgl_error(InvalidEnum);
+                NullValue()
+            },
+        }
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    unsafe fn GetParameter(&self, cx: *mut JSContext, parameter: u32) -> JSVal {
+        match parameter {
+            constants::ARRAY_BUFFER_BINDING => {
+                return to_js_value(cx, self.bound_buffer_array.get());
+            },
+            constants::ELEMENT_ARRAY_BUFFER_BINDING => {
+                return to_js_value(cx, self.bound_buffer_element_array.get());
+            },
+            constants::FRAMEBUFFER_BINDING => {
+                return to_js_value(cx, self.bound_framebuffer.get());
+            },
+            constants::RENDERBUFFER_BINDING => {
+                return to_js_value(cx, self.bound_renderbuffer.get());
+            },
+            constants::TEXTURE_BINDING_2D => {
+                return to_js_value(cx, self.active_texture_unit().tex_2d.get());
+            },
+            constants::TEXTURE_BINDING_CUBE_MAP => {
+                return to_js_value(cx, self.active_texture_unit().cube_map.get());
+            },
+            constants::CURRENT_PROGRAM => {
+                return to_js_value(cx, self.current_program.get());
+            },
+            constants::COMPRESSED_TEXTURE_FORMATS => {
+                // No compressed texture format is supported.
+                rooted!(in(cx) let mut rval = ptr::null_mut::<JSObject>());
+                let _ = Uint32Array::create(cx, CreateWith::Slice(&[]), rval.handle_mut()).unwrap();
+                return ObjectValue(rval.get());
+            },
+            // readPixels only supports RGBA and UNSIGNED_BYTE.
+            constants::IMPLEMENTATION_COLOR_READ_FORMAT => return Int32Value(constants::RGBA as i32),
+            constants::IMPLEMENTATION_COLOR_READ_TYPE => return Int32Value(constants::UNSIGNED_BYTE as i32),
+            constants::VERSION => return to_js_value(cx, DOMString::from("WebGL 1.0")),
+            constants::RENDERER | constants::VENDOR => return to_js_value(cx, DOMString::from("Mozilla/Servo")),
+            constants::SHADING_LANGUAGE_VERSION => {
+                return to_js_value(cx, DOMString::from("WebGL GLSL ES 1.0"));
+            },
+            constants::UNPACK_FLIP_Y_WEBGL => {
+                let unpack = self.texture_unpacking_settings.get();
+                return BooleanValue(unpack.contains(TextureUnpacking::FLIP_Y_AXIS));
+            },
+            constants::UNPACK_PREMULTIPLY_ALPHA_WEBGL => {
+                let unpack = self.texture_unpacking_settings.get();
+                return BooleanValue(unpack.contains(TextureUnpacking::PREMULTIPLY_ALPHA));
+            },
+            constants::UNPACK_COLORSPACE_CONVERSION_WEBGL => {
+                let unpack = self.texture_unpacking_settings.get();
+                return Int32Value(if unpack.contains(TextureUnpacking::CONVERT_COLORSPACE) {
+                    constants::BROWSER_DEFAULT_WEBGL as i32
+                } else {
+                    constants::NONE as i32
+                });
+            },
+            constants::UNPACK_ALIGNMENT => return Int32Value(self.texture_unpacking_alignment.get() as i32),
+            constants::MAX_COMBINED_TEXTURE_IMAGE_UNITS => {
+                return Int32Value(self.limits.max_combined_texture_image_units as i32);
+            },
+            constants::MAX_CUBE_MAP_TEXTURE_SIZE => return Int32Value(self.limits.max_cube_map_tex_size as i32),
+            constants::MAX_FRAGMENT_UNIFORM_VECTORS => {
+                return Int32Value(self.limits.max_fragment_uniform_vectors as i32);
+            },
+            constants::MAX_RENDERBUFFER_SIZE => return Int32Value(self.limits.max_renderbuffer_size as i32),
+            constants::MAX_TEXTURE_IMAGE_UNITS => return Int32Value(self.limits.max_texture_image_units as i32),
+            constants::MAX_TEXTURE_SIZE => return Int32Value(self.limits.max_tex_size as i32),
+            constants::MAX_VARYING_VECTORS => return Int32Value(self.limits.max_varying_vectors as i32),
+            constants::MAX_VERTEX_ATTRIBS => return Int32Value(self.limits.max_vertex_attribs as i32),
+            constants::MAX_VERTEX_TEXTURE_IMAGE_UNITS => {
+                return Int32Value(self.limits.max_vertex_texture_image_units as i32);
+            },
+            constants::MAX_VERTEX_UNIFORM_VECTORS => {
+                return Int32Value(self.limits.max_vertex_uniform_vectors as i32);
+            },
+            _ => {},
+        }
+
+        if let Ok(capability) = Capabilities::from_gl_constant(parameter) {
+            return BooleanValue(self.capabilities.get().contains(capability));
+        }
+
+        // These parameters are only exposed by their extension.
+        let extensions = self.extensions();
+        let enabled = match parameter {
+            constants::MAX_TEXTURE_MAX_ANISOTROPY_EXT => extensions.is_anisotropic_filtering_enabled(),
+            constants::FRAGMENT_SHADER_DERIVATIVE_HINT_OES => extensions.is_standard_derivatives_enabled(),
+            _ => true,
+        };
+        if !enabled {
+            self.webgl_error(InvalidEnum);
+            return NullValue();
+        }
+
+        let parameter = handle_potential_webgl_error!(self, Parameter::from_u32(parameter), return NullValue());
+        self.get_gl_parameter(cx, parameter)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn GetError(&self) -> u32 {
+        let error_code = if let Some(error) = self.last_error.get() {
+            match error {
+                InvalidEnum => constants::INVALID_ENUM,
+                InvalidFramebufferOperation => constants::INVALID_FRAMEBUFFER_OPERATION,
+                InvalidValue => constants::INVALID_VALUE,
+                InvalidOperation => constants::INVALID_OPERATION,
+                OutOfMemory => constants::OUT_OF_MEMORY,
+                ContextLost => constants::CONTEXT_LOST_WEBGL,
+            }
+        } else {
+            constants::NO_ERROR
+        };
+        self.last_error.set(None);
+        error_code
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
+    unsafe fn GetFramebufferAttachmentParameter(&self, cx: *mut JSContext, target: u32,
+                                                attachment: u32, pname: u32) -> JSVal {
+        if target != constants::FRAMEBUFFER {
+            self.webgl_error(InvalidEnum);
+            return NullValue();
+        }
+        match attachment {
+            constants::COLOR_ATTACHMENT0 |
+            constants::DEPTH_ATTACHMENT |
+            constants::STENCIL_ATTACHMENT |
+            constants::DEPTH_STENCIL_ATTACHMENT => {},
+            _ => {
+                self.webgl_error(InvalidEnum);
+                return NullValue();
+            },
+        }
+        match pname {
+            constants::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE |
+            constants::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME |
+            constants::FRAMEBUFFER_ATTACHMENT_TEXTURE_LEVEL |
+            constants::FRAMEBUFFER_ATTACHMENT_TEXTURE_CUBE_MAP_FACE => {},
+            _ => {
+                self.webgl_error(InvalidEnum);
+                return NullValue();
+            },
+        }
+
+        // Querying the default framebuffer is not allowed in WebGL 1.
+        let fb = match self.bound_framebuffer.get() {
+            Some(fb) => fb,
+            None => {
+                self.webgl_error(InvalidOperation);
+                return NullValue();
+            },
+        };
+
+        match (fb.attachment(attachment), pname) {
+            (None, constants::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE) => Int32Value(constants::NONE as i32),
+            (Some(WebGLFramebufferAttachmentRoot::Renderbuffer(_)),
+             constants::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE) => Int32Value(constants::RENDERBUFFER as i32),
+            (Some(WebGLFramebufferAttachmentRoot::Texture(_)),
+             constants::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE) => Int32Value(constants::TEXTURE as i32),
+            (Some(WebGLFramebufferAttachmentRoot::Renderbuffer(rb)),
+             constants::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME) => to_js_value(cx, rb),
+            (Some(WebGLFramebufferAttachmentRoot::Texture(texture)),
+             constants::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME) => to_js_value(cx, texture),
+            (Some(WebGLFramebufferAttachmentRoot::Texture(_)), _) => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetFramebufferAttachmentParameter(target, attachment, pname, sender));
+                Int32Value(receiver.recv().unwrap())
+            },
+            // The level and face of an attachment only exist for textures.
+            _ => {
+                self.webgl_error(InvalidEnum);
+                NullValue()
+            },
+        }
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    unsafe fn GetProgramParameter(&self, _cx: *mut JSContext, program: &WebGLProgram, param: u32) -> JSVal {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return NullValue());
+        if program.is_deleted() {
+            self.webgl_error(InvalidValue);
+            return NullValue();
+        }
+        match param {
+            constants::DELETE_STATUS => BooleanValue(program.is_marked_for_deletion()),
+            constants::LINK_STATUS => BooleanValue(program.is_linked()),
+            constants::VALIDATE_STATUS => BooleanValue(program.validate_status()),
+            constants::ATTACHED_SHADERS => {
+                Int32Value(program.attached_shaders().map_or(0, |shaders| shaders.len() as i32))
+            },
+            constants::ACTIVE_ATTRIBUTES => Int32Value(program.active_attribs().len() as i32),
+            constants::ACTIVE_UNIFORMS => Int32Value(program.active_uniforms().len() as i32),
+            _ => {
+                self.webgl_error(InvalidEnum);
+                NullValue()
+            },
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn GetProgramInfoLog(&self, program: &WebGLProgram) -> Option<DOMString> {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return None);
+        let info_log = handle_potential_webgl_error!(self, program.get_info_log(), return None);
+        Some(DOMString::from(info_log))
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
+    unsafe fn GetRenderbufferParameter(&self, _cx: *mut JSContext, target: u32, pname: u32) -> JSVal {
+        if target != constants::RENDERBUFFER {
+            self.webgl_error(InvalidEnum);
+            return NullValue();
+        }
+        let rb = match self.bound_renderbuffer.get() {
+            Some(rb) => rb,
+            None => {
+                self.webgl_error(InvalidOperation);
+                return NullValue();
+            },
+        };
+        match pname {
+            // The internal format is the one requested by script, which may
+            // not be the one of the GL renderbuffer.
+            constants::RENDERBUFFER_INTERNAL_FORMAT => Int32Value(rb.internal_format() as i32),
+            constants::RENDERBUFFER_WIDTH |
+            constants::RENDERBUFFER_HEIGHT |
+            constants::RENDERBUFFER_RED_SIZE |
+            constants::RENDERBUFFER_GREEN_SIZE |
+            constants::RENDERBUFFER_BLUE_SIZE |
+            constants::RENDERBUFFER_ALPHA_SIZE |
+            constants::RENDERBUFFER_DEPTH_SIZE |
+            constants::RENDERBUFFER_STENCIL_SIZE => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetRenderbufferParameter(target, pname, sender));
+                Int32Value(receiver.recv().unwrap())
+            },
+            _ => {
+                self.webgl_error(InvalidEnum);
+                NullValue()
+            },
+        }
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    unsafe fn GetShaderParameter(&self, _cx: *mut JSContext, shader: &WebGLShader, param: u32) -> JSVal {
+        handle_potential_webgl_error!(self, self.validate_ownership(shader), return NullValue());
+        if shader.is_deleted() {
+            self.webgl_error(InvalidValue);
+            return NullValue();
+        }
+        match param {
+            constants::SHADER_TYPE => Int32Value(shader.gl_type() as i32),
+            constants::DELETE_STATUS => BooleanValue(shader.is_marked_for_deletion()),
+            constants::COMPILE_STATUS => BooleanValue(shader.successfully_compiled()),
+            _ => {
+                self.webgl_error(InvalidEnum);
+                NullValue()
+            },
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn GetShaderPrecisionFormat(&self,
+                                shader_type: u32,
+                                precision_type: u32)
+                                -> Option<DomRoot<WebGLShaderPrecisionFormat>> {
+        match shader_type {
+            constants::FRAGMENT_SHADER |
+            constants::VERTEX_SHADER => {},
+            _ => {
+                self.webgl_error(InvalidEnum);
+                return None;
+            },
+        }
+        match precision_type {
+            constants::LOW_FLOAT |
+            constants::MEDIUM_FLOAT |
+            constants::HIGH_FLOAT |
+            constants::LOW_INT |
+            constants::MEDIUM_INT |
+            constants::HIGH_INT => {},
+            _ => {
+                self.webgl_error(InvalidEnum);
+                return None;
+            },
+        }
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.send_command(WebGLCommand::GetShaderPrecisionFormat(shader_type, precision_type, sender));
+        let (range_min, range_max, precision) = receiver.recv().unwrap();
+        Some(WebGLShaderPrecisionFormat::new(&window_from_node(&*self.canvas), range_min, range_max, precision))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn GetShaderInfoLog(&self, shader: &WebGLShader) -> Option<DOMString> {
+        handle_potential_webgl_error!(self, self.validate_ownership(shader), return None);
+        Some(shader.info_log())
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn GetShaderSource(&self, shader: &WebGLShader) -> Option<DOMString> {
+        handle_potential_webgl_error!(self, self.validate_ownership(shader), return None);
+        Some(shader.source())
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    unsafe fn GetTexParameter(&self, _cx: *mut JSContext, target: u32, pname: u32) -> JSVal {
+        let slot = handle_potential_webgl_error!(self, self.texture_slot(target), return NullValue());
+        let texture = match slot.get() {
+            Some(texture) => texture,
+            None => {
+                self.webgl_error(InvalidOperation);
+                return NullValue();
+            },
+        };
+        match pname {
+            constants::TEXTURE_MIN_FILTER => Int32Value(texture.min_filter() as i32),
+            constants::TEXTURE_MAG_FILTER => Int32Value(texture.mag_filter() as i32),
+            _ => match TexParameter::from_u32(pname) {
+                Ok(TexParameter::Int(param)) => {
+                    let (sender, receiver) = webgl_channel().unwrap();
+                    self.send_command(WebGLCommand::GetTexParameterInt(target, param, sender));
+                    Int32Value(receiver.recv().unwrap())
+                },
+                Ok(TexParameter::Float(param)) if self.extensions().is_anisotropic_filtering_enabled() => {
+                    let (sender, receiver) = webgl_channel().unwrap();
+                    self.send_command(WebGLCommand::GetTexParameterFloat(target, param, sender));
+                    DoubleValue(receiver.recv().unwrap() as f64)
+                },
+                _ => {
+                    self.webgl_error(InvalidEnum);
+                    NullValue()
+                },
+            },
+        }
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    unsafe fn GetUniform(&self,
+                         cx: *mut JSContext,
+                         program: &WebGLProgram,
+                         location: &WebGLUniformLocation) -> JSVal {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return NullValue());
+        if !location.is_from(self) || !program.is_linked() || program.is_deleted() ||
+           location.program_id() != program.id() {
+            self.webgl_error(InvalidOperation);
+            return NullValue();
+        }
+
+        let program_id = program.id();
+        let id = location.id();
+        macro_rules! get_uniform {
+            ($command:ident) => {{
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::$command(program_id, id, sender));
+                receiver.recv().unwrap()
+            }}
+        }
+
+        match location.type_() {
+            constants::BOOL => BooleanValue(get_uniform!(GetUniformBool)),
+            constants::BOOL_VEC2 => to_js_value(cx, get_uniform!(GetUniformBool2).to_vec()),
+            constants::BOOL_VEC3 => to_js_value(cx, get_uniform!(GetUniformBool3).to_vec()),
+            constants::BOOL_VEC4 => to_js_value(cx, get_uniform!(GetUniformBool4).to_vec()),
+            constants::INT |
+            constants::SAMPLER_2D |
+            constants::SAMPLER_CUBE => Int32Value(get_uniform!(GetUniformInt)),
+            constants::INT_VEC2 => int32_array_value(cx, &get_uniform!(GetUniformInt2)),
+            constants::INT_VEC3 => int32_array_value(cx, &get_uniform!(GetUniformInt3)),
+            constants::INT_VEC4 => int32_array_value(cx, &get_uniform!(GetUniformInt4)),
+            constants::FLOAT => DoubleValue(get_uniform!(GetUniformFloat) as f64),
+            constants::FLOAT_VEC2 => float32_array_value(cx, &get_uniform!(GetUniformFloat2)),
+            constants::FLOAT_VEC3 => float32_array_value(cx, &get_uniform!(GetUniformFloat3)),
+            constants::FLOAT_VEC4 |
+            constants::FLOAT_MAT2 => float32_array_value(cx, &get_uniform!(GetUniformFloat4)),
+            constants::FLOAT_MAT3 => float32_array_value(cx, &get_uniform!(GetUniformFloat9)),
+            constants::FLOAT_MAT4 => float32_array_value(cx, &get_uniform!(GetUniformFloat16)),
+            _ => panic!("Wrong uniform type"),
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn GetUniformLocation(&self, program: &WebGLProgram, name: DOMString) -> Option<DomRoot<WebGLUniformLocation>> {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return None);
+        handle_potential_webgl_error!(self, program.get_uniform_location(name), None)
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    unsafe fn GetVertexAttrib(&self, cx: *mut JSContext, index: u32, pname: u32) -> JSVal {
+        if index >= self.limits.max_vertex_attribs {
+            self.webgl_error(InvalidValue);
+            return NullValue();
+        }
+
+        if pname == constants::CURRENT_VERTEX_ATTRIB {
+            let (sender, receiver) = webgl_channel().unwrap();
+            self.send_command(WebGLCommand::GetCurrentVertexAttrib(index, sender));
+            return float32_array_value(cx, &receiver.recv().unwrap());
+        }
+
+        let vertex_attribs = self.vertex_attribs.borrow();
+        let data = &vertex_attribs[index as usize];
+        match pname {
+            constants::VERTEX_ATTRIB_ARRAY_ENABLED => BooleanValue(data.enabled),
+            constants::VERTEX_ATTRIB_ARRAY_SIZE => Int32Value(data.size),
+            constants::VERTEX_ATTRIB_ARRAY_TYPE => Int32Value(data.type_ as i32),
+            constants::VERTEX_ATTRIB_ARRAY_NORMALIZED => BooleanValue(data.normalized),
+            constants::VERTEX_ATTRIB_ARRAY_STRIDE => Int32Value(data.stride),
+            constants::VERTEX_ATTRIB_ARRAY_BUFFER_BINDING => {
+                to_js_value(cx, data.buffer.as_ref().map(|buffer| DomRoot::from_ref(&**buffer)))
+            },
+            _ => {
+                self.webgl_error(InvalidEnum);
+                NullValue()
+            },
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn GetVertexAttribOffset(&self, index: u32, pname: u32) -> i64 {
+        if pname != constants::VERTEX_ATTRIB_ARRAY_POINTER {
+            self.webgl_error(InvalidEnum);
+            return 0;
+        }
+        match self.vertex_attribs.borrow().get(index as usize) {
+            Some(data) => data.offset as i64,
+            None => {
+                self.webgl_error(InvalidValue);
+                0
+            },
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn Hint(&self, target: u32, mode: u32) {
+        match target {
+            constants::GENERATE_MIPMAP_HINT => {},
+            constants::FRAGMENT_SHADER_DERIVATIVE_HINT_OES
+                if self.extensions().is_standard_derivatives_enabled() => {},
+            _ => return self.webgl_error(InvalidEnum),
+        }
+        match mode {
+            constants::FASTEST |
+            constants::NICEST |
+            constants::DONT_CARE => {},
+            _ => return self.webgl_error(InvalidEnum),
+        }
+        self.send_command(WebGLCommand::Hint(target, mode));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
+    fn IsBuffer(&self, buffer: Option<&WebGLBuffer>) -> bool {
+        buffer.map_or(false, |buffer| {
+            self.validate_ownership(buffer).is_ok() && buffer.target().is_some() && !buffer.is_deleted()
+        })
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn IsEnabled(&self, cap: u32) -> bool {
+        let capability = handle_potential_webgl_error!(self, Capabilities::from_gl_constant(cap), return false);
+        self.capabilities.get().contains(capability)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
+    fn IsFramebuffer(&self, framebuffer: Option<&WebGLFramebuffer>) -> bool {
+        framebuffer.map_or(false, |framebuffer| {
+            self.validate_ownership(framebuffer).is_ok() && framebuffer.target().is_some() &&
+            !framebuffer.is_deleted()
+        })
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn IsProgram(&self, program: Option<&WebGLProgram>) -> bool {
+        program.map_or(false, |program| {
+            self.validate_ownership(program).is_ok() && !program.is_deleted()
+        })
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
+    fn IsRenderbuffer(&self, renderbuffer: Option<&WebGLRenderbuffer>) -> bool {
+        renderbuffer.map_or(false, |renderbuffer| {
+            self.validate_ownership(renderbuffer).is_ok() && renderbuffer.ever_bound() &&
+            !renderbuffer.is_deleted()
+        })
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn IsShader(&self, shader: Option<&WebGLShader>) -> bool {
+        shader.map_or(false, |shader| {
+            self.validate_ownership(shader).is_ok() && !shader.is_deleted()
+        })
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn IsTexture(&self, texture: Option<&WebGLTexture>) -> bool {
+        texture.map_or(false, |texture| {
+            self.validate_ownership(texture).is_ok() && texture.target().is_some() && !texture.is_deleted()
+        })
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn LineWidth(&self, width: f32) {
+        if width.is_nan() || width <= 0. {
+            return self.webgl_error(InvalidValue);
+        }
+        self.send_command(WebGLCommand::LineWidth(width))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn LinkProgram(&self, program: &WebGLProgram) {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return);
+        handle_potential_webgl_error!(self, program.link());
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.2
+    fn PixelStorei(&self, param_name: u32, param_value: i32) {
+        let mut texture_settings = self.texture_unpacking_settings.get();
+        match param_name {
+            constants::UNPACK_FLIP_Y_WEBGL => {
+                texture_settings.set(TextureUnpacking::FLIP_Y_AXIS, param_value != 0);
+            },
+            constants::UNPACK_PREMULTIPLY_ALPHA_WEBGL => {
+                texture_settings.set(TextureUnpacking::PREMULTIPLY_ALPHA, param_value != 0);
+            },
+            constants::UNPACK_COLORSPACE_CONVERSION_WEBGL => {
+                match param_value as u32 {
+                    constants::BROWSER_DEFAULT_WEBGL => texture_settings.insert(TextureUnpacking::CONVERT_COLORSPACE),
+                    constants::NONE => texture_settings.remove(TextureUnpacking::CONVERT_COLORSPACE),
+                    _ => return self.webgl_error(InvalidEnum),
+                }
+            },
+            constants::UNPACK_ALIGNMENT |
+            constants::PACK_ALIGNMENT => {
+                match param_value {
+                    1 | 2 | 4 | 8 => (),
+                    _ => return self.webgl_error(InvalidValue),
+                }
+                // The unpack alignment is needed to compute the size of the
+                // data passed to texImage2D.
+                if param_name == constants::UNPACK_ALIGNMENT {
+                    self.texture_unpacking_alignment.set(param_value as u32);
+                }
+                return self.send_command(WebGLCommand::PixelStorei(param_name, param_value));
+            },
+            _ => return self.webgl_error(InvalidEnum),
+        }
+        self.texture_unpacking_settings.set(texture_settings);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn PolygonOffset(&self, factor: f32, units: f32) {
+        self.send_command(WebGLCommand::PolygonOffset(factor, units))
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.12
+    fn ReadPixels(&self, x: i32, y: i32, width: i32, height: i32, format: u32, pixel_type: u32,
+                  mut pixels: CustomAutoRooterGuard<Option<ArrayBufferView>>) {
+        let pixels = match *pixels {
+            Some(ref mut pixels) => pixels,
+            None => return self.webgl_error(InvalidValue),
+        };
+        if width < 0 || height < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        match (format, pixel_type) {
+            (constants::RGBA, constants::UNSIGNED_BYTE) => {},
+            (constants::ALPHA, _) | (constants::RGB, _) | (constants::RGBA, _) => {
+                return self.webgl_error(InvalidOperation);
+            },
+            _ => return self.webgl_error(InvalidEnum),
+        }
+        match pixels.get_array_type() {
+            Type::Uint8 => {},
+            _ => return self.webgl_error(InvalidOperation),
+        }
+        handle_potential_webgl_error!(self, self.validate_framebuffer_complete(), return);
+
+        let dest = unsafe { pixels.as_mut_slice() };
+        let row_length = width as usize * 4;
+        if dest.len() < row_length * height as usize {
+            return self.webgl_error(InvalidOperation);
+        }
+
+        // Pixels outside of the framebuffer are left untouched, so only its
+        // intersection with the requested rectangle is read.
+        let fb_size = match self.bound_framebuffer.get() {
+            Some(fb) => fb.size().unwrap_or((0, 0)),
+            None => (self.DrawingBufferWidth(), self.DrawingBufferHeight()),
+        };
+        let src_x = cmp::max(x, 0);
+        let src_y = cmp::max(y, 0);
+        let src_width = cmp::min(x.saturating_add(width), fb_size.0) - src_x;
+        let src_height = cmp::min(y.saturating_add(height), fb_size.1) - src_y;
+        if src_width <= 0 || src_height <= 0 {
+            return;
+        }
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.send_command(WebGLCommand::ReadPixels(src_x, src_y, src_width, src_height,
+                                                   format, pixel_type, sender));
+        let result: Vec<u8> = receiver.recv().unwrap().into();
+
+        let src_row_length = src_width as usize * 4;
+        let dest_x = (src_x - x) as usize * 4;
+        for row in 0..src_height as usize {
+            let src_start = row * src_row_length;
+            let dest_start = (row + (src_y - y) as usize) * row_length + dest_x;
+            dest[dest_start..dest_start + src_row_length]
+                .copy_from_slice(&result[src_start..src_start + src_row_length]);
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
+    fn RenderbufferStorage(&self, target: u32, internal_format: u32, width: i32, height: i32) {
+        if target != constants::RENDERBUFFER {
+            return self.webgl_error(InvalidEnum);
+        }
+        let max_size = self.limits.max_renderbuffer_size as i32;
+        if width < 0 || height < 0 || width > max_size || height > max_size {
+            return self.webgl_error(InvalidValue);
+        }
+        let rb = match self.bound_renderbuffer.get() {
+            Some(rb) => rb,
+            None => return self.webgl_error(InvalidOperation),
+        };
+        handle_potential_webgl_error!(self, rb.storage(internal_format, width, height), return);
+        if let Some(fb) = self.bound_framebuffer.get() {
+            fb.invalidate_renderbuffer(&rb);
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn SampleCoverage(&self, value: f32, invert: bool) {
+        self.send_command(WebGLCommand::SampleCoverage(value, invert));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.4
+    fn Scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        if width < 0 || height < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        self.send_command(WebGLCommand::Scissor(x, y, width, height))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn ShaderSource(&self, shader: &WebGLShader, source: DOMString) {
+        handle_potential_webgl_error!(self, self.validate_ownership(shader), return);
+        shader.set_source(source)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn StencilFunc(&self, func: u32, ref_: i32, mask: u32) {
+        handle_potential_webgl_error!(self, validate_comparison_function(func), return);
+        self.send_command(WebGLCommand::StencilFunc(func, ref_, mask))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn StencilFuncSeparate(&self, face: u32, func: u32, ref_: i32, mask: u32) {
+        handle_potential_webgl_error!(self, validate_face(face), return);
+        handle_potential_webgl_error!(self, validate_comparison_function(func), return);
+        self.send_command(WebGLCommand::StencilFuncSeparate(face, func, ref_, mask))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn StencilMask(&self, mask: u32) {
+        self.send_command(WebGLCommand::StencilMask(mask))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn StencilMaskSeparate(&self, face: u32, mask: u32) {
+        handle_potential_webgl_error!(self, validate_face(face), return);
+        self.send_command(WebGLCommand::StencilMaskSeparate(face, mask))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn StencilOp(&self, fail: u32, zfail: u32, zpass: u32) {
+        handle_potential_webgl_error!(self, validate_stencil_actions(&[fail, zfail, zpass]), return);
+        self.send_command(WebGLCommand::StencilOp(fail, zfail, zpass))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
+    fn StencilOpSeparate(&self, face: u32, fail: u32, zfail: u32, zpass: u32) {
+        handle_potential_webgl_error!(self, validate_face(face), return);
+        handle_potential_webgl_error!(self, validate_stencil_actions(&[fail, zfail, zpass]), return);
+        self.send_command(WebGLCommand::StencilOpSeparate(face, fail, zfail, zpass))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn TexImage2D(&self,
+                  target: u32,
+                  level: i32,
+                  internal_format: u32,
+                  width: i32,
+                  height: i32,
+                  border: i32,
+                  format: u32,
+                  data_type: u32,
+                  pixels: CustomAutoRooterGuard<Option<ArrayBufferView>>) -> ErrorResult {
+        let validator = TexImage2DValidator::new(self, target, level, internal_format,
+                                                 width, height, border, format, data_type);
+        let validated = handle_potential_webgl_error!(self, validator.validate(), return Ok(()));
+        let (format, data_type) = (validated.format, validated.data_type);
+        let (width, height) = (validated.width as usize, validated.height as usize);
+
+        let row_length = width * bytes_per_pixel(format, data_type) as usize;
+        let alignment = self.texture_unpacking_alignment.get() as usize;
+        let stride = (row_length + alignment - 1) / alignment * alignment;
+        let expected_byte_length = if height == 0 { 0 } else { stride * (height - 1) + row_length };
+
+        let mut buff = match *pixels {
+            Some(ref pixels) => {
+                // The type of the array must match the type of the data, and
+                // it must be large enough.
+                let array_type_matches = match (pixels.get_array_type(), data_type) {
+                    (Type::Uint8, TexDataType::UnsignedByte) |
+                    (Type::Uint8Clamped, TexDataType::UnsignedByte) => true,
+                    (Type::Uint16, TexDataType::UnsignedShort4444) |
+                    (Type::Uint16, TexDataType::UnsignedShort5551) |
+                    (Type::Uint16, TexDataType::UnsignedShort565) => true,
+                    _ => false,
+                };
+                if !array_type_matches {
+                    self.webgl_error(InvalidOperation);
+                    return Ok(());
+                }
+                let data = pixels.to_vec();
+                if data.len() < expected_byte_length {
+                    self.webgl_error(InvalidOperation);
+                    return Ok(());
+                }
+                data
+            },
+            None => vec![0u8; expected_byte_length],
+        };
+
+        // Make the data cover whole rows so that it can be flipped.
+        buff.resize(stride * height, 0);
+        let settings = self.texture_unpacking_settings.get();
+        if settings.contains(TextureUnpacking::PREMULTIPLY_ALPHA) {
+            premultiply_pixels(format, data_type, &mut buff, width, stride);
+        }
+        if settings.contains(TextureUnpacking::FLIP_Y_AXIS) {
+            flip_pixels_y(&mut buff, stride, height);
+        }
+
+        let texture = validated.texture.clone();
+        self.tex_image_2d(&texture, validated, buff);
+        Ok(())
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn TexImage2D_(&self,
+                   target: u32,
+                   level: i32,
+                   internal_format: u32,
+                   format: u32,
+                   data_type: u32,
+                   source: ImageSource) -> ErrorResult {
+        let (pixels, size, premultiplied) = match self.get_image_pixels(source)? {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+
+        let validator = TexImage2DValidator::new(self, target, level, internal_format,
+                                                 size.width as i32, size.height as i32,
+                                                 0, format, data_type);
+        let validated = handle_potential_webgl_error!(self, validator.validate(), return Ok(()));
+
+        let pixels = self.prepare_image_pixels(pixels, size, premultiplied, validated.format, validated.data_type);
+        let texture = validated.texture.clone();
+        self.tex_image_2d_unaligned(&texture, validated, pixels);
+        Ok(())
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn TexParameterf(&self, target: u32, name: u32, value: f32) {
+        self.tex_parameter(target, name, TexParameterValue::Float(value))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn TexParameteri(&self, target: u32, name: u32, value: i32) {
+        self.tex_parameter(target, name, TexParameterValue::Int(value))
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn TexSubImage2D(&self,
+                     target: u32,
+                     level: i32,
+                     xoffset: i32,
+                     yoffset: i32,
+                     width: i32,
+                     height: i32,
+                     format: u32,
+                     data_type: u32,
+                     pixels: CustomAutoRooterGuard<Option<ArrayBufferView>>) -> ErrorResult {
+        let (target, level, format, data_type) = handle_potential_webgl_error!(
+            self,
+            self.validate_tex_sub_image_2d(target, level, xoffset, yoffset, width, height, format, data_type),
+            return Ok(())
+        );
+        let (width, height) = (width as usize, height as usize);
+
+        let row_length = width * bytes_per_pixel(format, data_type) as usize;
+        let alignment = self.texture_unpacking_alignment.get() as usize;
+        let stride = (row_length + alignment - 1) / alignment * alignment;
+        let expected_byte_length = if height == 0 { 0 } else { stride * (height - 1) + row_length };
+
+        // Unlike texImage2D, the data can't be null.
+        let mut buff = match *pixels {
+            Some(ref pixels) => pixels.to_vec(),
+            None => {
+                self.webgl_error(InvalidValue);
+                return Ok(());
+            },
+        };
+        if buff.len() < expected_byte_length {
+            self.webgl_error(InvalidOperation);
+            return Ok(());
+        }
+
+        buff.resize(stride * height, 0);
+        let settings = self.texture_unpacking_settings.get();
+        if settings.contains(TextureUnpacking::PREMULTIPLY_ALPHA) {
+            premultiply_pixels(format, data_type, &mut buff, width, stride);
+        }
+        if settings.contains(TextureUnpacking::FLIP_Y_AXIS) {
+            flip_pixels_y(&mut buff, stride, height);
+        }
+
+        self.send_command(WebGLCommand::TexSubImage2D(target.as_gl_constant(),
+                                                      level as i32,
+                                                      xoffset,
+                                                      yoffset,
+                                                      width as i32,
+                                                      height as i32,
+                                                      format.as_gl_constant(),
+                                                      data_type.as_gl_constant(),
+                                                      buff.into()));
+        Ok(())
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn TexSubImage2D_(&self,
+                      target: u32,
+                      level: i32,
+                      xoffset: i32,
+                      yoffset: i32,
+                      format: u32,
+                      data_type: u32,
+                      source: ImageSource) -> ErrorResult {
+        let (pixels, size, premultiplied) = match self.get_image_pixels(source)? {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+
+        let (target, level, format, data_type) = handle_potential_webgl_error!(
+            self,
+            self.validate_tex_sub_image_2d(target, level, xoffset, yoffset,
+                                           size.width as i32, size.height as i32,
+                                           format, data_type),
+            return Ok(())
+        );
+
+        let pixels = self.prepare_image_pixels(pixels, size, premultiplied, format, data_type);
+
+        self.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT, 1));
+        self.send_command(WebGLCommand::TexSubImage2D(target.as_gl_constant(),
+                                                      level as i32,
+                                                      xoffset,
+                                                      yoffset,
+                                                      size.width as i32,
+                                                      size.height as i32,
+                                                      format.as_gl_constant(),
+                                                      data_type.as_gl_constant(),
+                                                      pixels.into()));
+        self.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT,
+                                                    self.texture_unpacking_alignment.get() as i32));
+        Ok(())
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform1f(&self, location: Option<&WebGLUniformLocation>, x: f32) {
+        let types = [constants::FLOAT, constants::BOOL];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 1, &[x]), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform1f(location, x));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform2f(&self, location: Option<&WebGLUniformLocation>, x: f32, y: f32) {
+        let types = [constants::FLOAT_VEC2, constants::BOOL_VEC2];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 2, &[x, y]), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform2f(location, x, y));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform3f(&self, location: Option<&WebGLUniformLocation>, x: f32, y: f32, z: f32) {
+        let types = [constants::FLOAT_VEC3, constants::BOOL_VEC3];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 3, &[x, y, z]), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform3f(location, x, y, z));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform4f(&self, location: Option<&WebGLUniformLocation>, x: f32, y: f32, z: f32, w: f32) {
+        let types = [constants::FLOAT_VEC4, constants::BOOL_VEC4];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 4, &[x, y, z, w]), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform4f(location, x, y, z, w));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform1i(&self, location: Option<&WebGLUniformLocation>, x: i32) {
+        let types = [constants::INT, constants::BOOL, constants::SAMPLER_2D, constants::SAMPLER_CUBE];
+        let id = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 1, &[x]), return);
+        handle_potential_webgl_error!(self, self.validate_sampler_values(location, &[x]), return);
+        if let Some(id) = id {
+            self.send_command(WebGLCommand::Uniform1i(id, x));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform2i(&self, location: Option<&WebGLUniformLocation>, x: i32, y: i32) {
+        let types = [constants::INT_VEC2, constants::BOOL_VEC2];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 2, &[x, y]), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform2i(location, x, y));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform3i(&self, location: Option<&WebGLUniformLocation>, x: i32, y: i32, z: i32) {
+        let types = [constants::INT_VEC3, constants::BOOL_VEC3];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 3, &[x, y, z]), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform3i(location, x, y, z));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform4i(&self, location: Option<&WebGLUniformLocation>, x: i32, y: i32, z: i32, w: i32) {
+        let types = [constants::INT_VEC4, constants::BOOL_VEC4];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 4, &[x, y, z, w]), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform4i(location, x, y, z, w));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform1fv(&self, location: Option<&WebGLUniformLocation>, v: Float32ArrayOrUnrestrictedFloatSequence) {
+        let v = float32_list_to_vec(v);
+        let types = [constants::FLOAT, constants::BOOL];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 1, &v), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform1fv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform2fv(&self, location: Option<&WebGLUniformLocation>, v: Float32ArrayOrUnrestrictedFloatSequence) {
+        let v = float32_list_to_vec(v);
+        let types = [constants::FLOAT_VEC2, constants::BOOL_VEC2];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 2, &v), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform2fv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform3fv(&self, location: Option<&WebGLUniformLocation>, v: Float32ArrayOrUnrestrictedFloatSequence) {
+        let v = float32_list_to_vec(v);
+        let types = [constants::FLOAT_VEC3, constants::BOOL_VEC3];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 3, &v), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform3fv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform4fv(&self, location: Option<&WebGLUniformLocation>, v: Float32ArrayOrUnrestrictedFloatSequence) {
+        let v = float32_list_to_vec(v);
+        let types = [constants::FLOAT_VEC4, constants::BOOL_VEC4];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 4, &v), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform4fv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform1iv(&self, location: Option<&WebGLUniformLocation>, v: Int32ArrayOrLongSequence) {
+        let v = int32_list_to_vec(v);
+        let types = [constants::INT, constants::BOOL, constants::SAMPLER_2D, constants::SAMPLER_CUBE];
+        let id = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 1, &v), return);
+        handle_potential_webgl_error!(self, self.validate_sampler_values(location, &v), return);
+        if let Some(id) = id {
+            self.send_command(WebGLCommand::Uniform1iv(id, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform2iv(&self, location: Option<&WebGLUniformLocation>, v: Int32ArrayOrLongSequence) {
+        let v = int32_list_to_vec(v);
+        let types = [constants::INT_VEC2, constants::BOOL_VEC2];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 2, &v), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform2iv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform3iv(&self, location: Option<&WebGLUniformLocation>, v: Int32ArrayOrLongSequence) {
+        let v = int32_list_to_vec(v);
+        let types = [constants::INT_VEC3, constants::BOOL_VEC3];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 3, &v), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform3iv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn Uniform4iv(&self, location: Option<&WebGLUniformLocation>, v: Int32ArrayOrLongSequence) {
+        let v = int32_list_to_vec(v);
+        let types = [constants::INT_VEC4, constants::BOOL_VEC4];
+        let location = handle_potential_webgl_error!(self, self.validate_uniform_parameters(location, &types, 4, &v), return);
+        if let Some(location) = location {
+            self.send_command(WebGLCommand::Uniform4iv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn UniformMatrix2fv(&self,
+                        location: Option<&WebGLUniformLocation>,
+                        transpose: bool,
+                        v: Float32ArrayOrUnrestrictedFloatSequence) {
+        let result = self.uniform_matrix_fv(location, transpose, v, constants::FLOAT_MAT2, 4);
+        if let Some((location, v)) = handle_potential_webgl_error!(self, result, return) {
+            self.send_command(WebGLCommand::UniformMatrix2fv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn UniformMatrix3fv(&self,
+                        location: Option<&WebGLUniformLocation>,
+                        transpose: bool,
+                        v: Float32ArrayOrUnrestrictedFloatSequence) {
+        let result = self.uniform_matrix_fv(location, transpose, v, constants::FLOAT_MAT3, 9);
+        if let Some((location, v)) = handle_potential_webgl_error!(self, result, return) {
+            self.send_command(WebGLCommand::UniformMatrix3fv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn UniformMatrix4fv(&self,
+                        location: Option<&WebGLUniformLocation>,
+                        transpose: bool,
+                        v: Float32ArrayOrUnrestrictedFloatSequence) {
+        let result = self.uniform_matrix_fv(location, transpose, v, constants::FLOAT_MAT4, 16);
+        if let Some((location, v)) = handle_potential_webgl_error!(self, result, return) {
+            self.send_command(WebGLCommand::UniformMatrix4fv(location, v));
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn UseProgram(&self, program: Option<&WebGLProgram>) {
+        if let Some(program) = program {
+            handle_potential_webgl_error!(self, self.validate_ownership(program), return);
+            if program.is_deleted() || !program.is_linked() {
+                return self.webgl_error(InvalidOperation);
+            }
+        }
+
+        // The previous program gets actually deleted if it was marked for
+        // deletion while in use.
+        if let Some(previous) = self.current_program.get() {
+            previous.in_use(false);
+        }
+        if let Some(program) = program {
+            program.in_use(true);
+        }
+        self.send_command(WebGLCommand::UseProgram(program.map(|program| program.id())));
+        self.current_program.set(program);
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
+    fn ValidateProgram(&self, program: &WebGLProgram) {
+        handle_potential_webgl_error!(self, self.validate_ownership(program), return);
+        handle_potential_webgl_error!(self, program.validate());
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttrib1f(&self, indx: u32, x: f32) {
+        self.vertex_attrib(indx, x, 0., 0., 1.)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttrib1fv(&self, indx: u32, v: Float32ArrayOrUnrestrictedFloatSequence) {
+        self.vertex_attrib_fv(indx, v, 1)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttrib2f(&self, indx: u32, x: f32, y: f32) {
+        self.vertex_attrib(indx, x, y, 0., 1.)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttrib2fv(&self, indx: u32, v: Float32ArrayOrUnrestrictedFloatSequence) {
+        self.vertex_attrib_fv(indx, v, 2)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttrib3f(&self, indx: u32, x: f32, y: f32, z: f32) {
+        self.vertex_attrib(indx, x, y, z, 1.)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttrib3fv(&self, indx: u32, v: Float32ArrayOrUnrestrictedFloatSequence) {
+        self.vertex_attrib_fv(indx, v, 3)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttrib4f(&self, indx: u32, x: f32, y: f32, z: f32, w: f32) {
+        self.vertex_attrib(indx, x, y, z, w)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttrib4fv(&self, indx: u32, v: Float32ArrayOrUnrestrictedFloatSequence) {
+        self.vertex_attrib_fv(indx, v, 4)
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
+    fn VertexAttribPointer(&self, indx: u32, size: i32, type_: u32,
+                           normalized: bool, stride: i32, offset: i64) {
+        if indx >= self.limits.max_vertex_attribs {
+            return self.webgl_error(InvalidValue);
+        }
+        if size < 1 || size > 4 {
+            return self.webgl_error(InvalidValue);
+        }
+        let type_size = match type_size(type_) {
+            Some(type_size) => type_size as i64,
+            None => return self.webgl_error(InvalidEnum),
+        };
+        // WebGL limits the stride to 255 bytes, and requires offsets and
+        // strides to be multiples of the size of the type.
+        if stride < 0 || stride > 255 || offset < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        if offset % type_size != 0 || stride as i64 % type_size != 0 {
+            return self.webgl_error(InvalidOperation);
+        }
+        let buffer = match self.bound_buffer_array.get() {
+            Some(buffer) => buffer,
+            None => return self.webgl_error(InvalidOperation),
+        };
+
+        {
+            let mut vertex_attribs = self.vertex_attribs.borrow_mut();
+            let data = &mut vertex_attribs[indx as usize];
+            data.size = size;
+            data.type_ = type_;
+            data.normalized = normalized;
+            data.stride = stride;
+            data.offset = offset as u32;
+            data.buffer = Some(Dom::from_ref(&*buffer));
+        }
+
+        self.send_command(WebGLCommand::VertexAttribPointer(indx, size, type_, normalized, stride, offset as u32));
+    }
+
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.4
+    fn Viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        if width < 0 || height < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+        self.send_command(WebGLCommand::SetViewport(x, y, width, height))
+    }
+}
+
+pub trait LayoutCanvasWebGLRenderingContextHelpers {
+    #[allow(unsafe_code)]
+    unsafe fn canvas_data_source(&self) -> HTMLCanvasDataSource;
+}
+
+impl LayoutCanvasWebGLRenderingContextHelpers for LayoutDom<WebGLRenderingContext> {
+    #[allow(unsafe_code)]
+    unsafe fn canvas_data_source(&self) -> HTMLCanvasDataSource {
+        HTMLCanvasDataSource::WebGL((*self.unsafe_get()).layout_handle())
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe fn to_js_value<T: ToJSValConvertible>(cx: *mut JSContext, value: T) -> JSVal {
+    rooted!(in(cx) let mut rval = UndefinedValue());
+    value.to_jsval(cx, rval.handle_mut());
+    rval.get()
+}
+
+#[allow(unsafe_code)]
+unsafe fn float32_array_value(cx: *mut JSContext, values: &[f32]) -> JSVal {
+    rooted!(in(cx) let mut rval = ptr::null_mut::<JSObject>());
+    let _ = Float32Array::create(cx, CreateWith::Slice(values), rval.handle_mut()).unwrap();
+    ObjectValue(rval.get())
+}
+
+#[allow(unsafe_code)]
+unsafe fn int32_array_value(cx: *mut JSContext, values: &[i32]) -> JSVal {
+    rooted!(in(cx) let mut rval = ptr::null_mut::<JSObject>());
+    let _ = Int32Array::create(cx, CreateWith::Slice(values), rval.handle_mut()).unwrap();
+    ObjectValue(rval.get())
+}
+
+fn float32_list_to_vec(list: Float32ArrayOrUnrestrictedFloatSequence) -> Vec<f32> {
+    match list {
+        Float32ArrayOrUnrestrictedFloatSequence::Float32Array(array) => array.to_vec(),
+        Float32ArrayOrUnrestrictedFloatSequence::UnrestrictedFloatSequence(sequence) => sequence,
+    }
+}
+
+fn int32_list_to_vec(list: Int32ArrayOrLongSequence) -> Vec<i32> {
+    match list {
+        Int32ArrayOrLongSequence::Int32Array(array) => array.to_vec(),
+        Int32ArrayOrLongSequence::LongSequence(sequence) => sequence,
+    }
+}
+
+/// The size in bytes of a component of a vertex attribute of type `type_`.
+fn type_size(type_: u32) -> Option<u32> {
+    match type_ {
+        constants::BYTE | constants::UNSIGNED_BYTE => Some(1),
+        constants::SHORT | constants::UNSIGNED_SHORT => Some(2),
+        constants::FLOAT => Some(4),
+        _ => None,
+    }
+}
+
+fn bytes_per_pixel(format: TexFormat, data_type: TexDataType) -> u32 {
+    data_type.element_size() * format.components() / data_type.components_per_element()
+}
+
+fn validate_draw_mode(mode: u32) -> WebGLResult<()> {
+    match mode {
+        constants::POINTS |
+        constants::LINE_STRIP |
+        constants::LINE_LOOP |
+        constants::LINES |
+        constants::TRIANGLE_STRIP |
+        constants::TRIANGLE_FAN |
+        constants::TRIANGLES => Ok(()),
+        _ => Err(InvalidEnum),
+    }
+}
+
+fn validate_blend_equation(mode: u32) -> WebGLResult<()> {
+    match mode {
+        constants::FUNC_ADD |
+        constants::FUNC_SUBTRACT |
+        constants::FUNC_REVERSE_SUBTRACT => Ok(()),
+        _ => Err(InvalidEnum),
+    }
+}
+
+fn validate_blend_factors(src_factor: u32, dest_factor: u32) -> WebGLResult<()> {
+    fn is_valid(factor: u32) -> bool {
+        match factor {
+            constants::ZERO |
+            constants::ONE |
+            constants::SRC_COLOR |
+            constants::ONE_MINUS_SRC_COLOR |
+            constants::DST_COLOR |
+            constants::ONE_MINUS_DST_COLOR |
+            constants::SRC_ALPHA |
+            constants::ONE_MINUS_SRC_ALPHA |
+            constants::DST_ALPHA |
+            constants::ONE_MINUS_DST_ALPHA |
+            constants::CONSTANT_COLOR |
+            constants::ONE_MINUS_CONSTANT_COLOR |
+            constants::CONSTANT_ALPHA |
+            constants::ONE_MINUS_CONSTANT_ALPHA => true,
+            _ => false,
+        }
+    }
+
+    if !(is_valid(src_factor) || src_factor == constants::SRC_ALPHA_SATURATE) || !is_valid(dest_factor) {
+        return Err(InvalidEnum);
+    }
+
+    // WebGL forbids using a constant color and a constant alpha together.
+    let is_constant_color = |factor| {
+        factor == constants::CONSTANT_COLOR || factor == constants::ONE_MINUS_CONSTANT_COLOR
+    };
+    let is_constant_alpha = |factor| {
+        factor == constants::CONSTANT_ALPHA || factor == constants::ONE_MINUS_CONSTANT_ALPHA
+    };
+    if (is_constant_color(src_factor) && is_constant_alpha(dest_factor)) ||
+       (is_constant_alpha(src_factor) && is_constant_color(dest_factor)) {
+        return Err(InvalidOperation);
+    }
+    Ok(())
+}
+
+fn validate_comparison_function(func: u32) -> WebGLResult<()> {
+    match func {
+        constants::NEVER |
+        constants::LESS |
+        constants::EQUAL |
+        constants::LEQUAL |
+        constants::GREATER |
+        constants::NOTEQUAL |
+        constants::GEQUAL |
+        constants::ALWAYS => Ok(()),
+        _ => Err(InvalidEnum),
+    }
+}
+
+fn validate_face(face: u32) -> WebGLResult<()> {
+    match face {
+        constants::FRONT | constants::BACK | constants::FRONT_AND_BACK => Ok(()),
+        _ => Err(InvalidEnum),
+    }
+}
+
+fn validate_stencil_actions(actions: &[u32]) -> WebGLResult<()> {
+    for &action in actions {
+        match action {
+            constants::KEEP |
+            constants::ZERO |
+            constants::REPLACE |
+            constants::INCR |
+            constants::INCR_WRAP |
+            constants::DECR |
+            constants::DECR_WRAP |
+            constants::INVERT => {},
+            _ => return Err(InvalidEnum),
+        }
+    }
+    Ok(())
+}
+
+/// Flips the rows of an image whose rows are `stride` bytes apart.
+fn flip_pixels_y(pixels: &mut [u8], stride: usize, height: usize) {
+    for y in 0..height / 2 {
+        let (top, bottom) = pixels.split_at_mut((height - y - 1) * stride);
+        top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+    }
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    if cfg!(target_endian = "little") {
+        bytes[0] as u16 | (bytes[1] as u16) << 8
+    } else {
+        (bytes[0] as u16) << 8 | bytes[1] as u16
+    }
+}
+
+/// GL reads packed 16-bit pixels in the native byte order.
+fn write_u16(bytes: &mut [u8], value: u16) {
+    if cfg!(target_endian = "little") {
+        bytes[0] = value as u8;
+        bytes[1] = (value >> 8) as u8;
+    } else {
+        bytes[0] = (value >> 8) as u8;
+        bytes[1] = value as u8;
+    }
+}
+
+fn push_u16(pixels: &mut Vec<u8>, value: u16) {
+    let mut bytes = [0; 2];
+    write_u16(&mut bytes, value);
+    pixels.extend_from_slice(&bytes);
+}
+
+/// Premultiplies the color components of texture data by its alpha, for
+/// formats that have an alpha channel.
+fn premultiply_pixels(format: TexFormat, data_type: TexDataType, pixels: &mut [u8], width: usize, stride: usize) {
+    let premultiply_component = |value: u16, alpha: u16, max: u16| value * alpha / max;
+
+    for row in pixels.chunks_mut(stride) {
+        match (format, data_type) {
+            (TexFormat::RGBA, TexDataType::UnsignedByte) => premultiply(&mut row[..width * 4]),
+            (TexFormat::LuminanceAlpha, TexDataType::UnsignedByte) => {
+                for pixel in row[..width * 2].chunks_mut(2) {
+                    pixel[0] = premultiply_component(pixel[0] as u16, pixel[1] as u16, 255) as u8;
+                }
+            },
+            (TexFormat::RGBA, TexDataType::UnsignedShort4444) => {
+                for pixel in row[..width * 2].chunks_mut(2) {
+                    let value = read_u16(pixel);
+                    let alpha = value & 0xf;
+                    let r = premultiply_component(value >> 12, alpha, 0xf);
+                    let g = premultiply_component((value >> 8) & 0xf, alpha, 0xf);
+                    let b = premultiply_component((value >> 4) & 0xf, alpha, 0xf);
+                    write_u16(pixel, r << 12 | g << 8 | b << 4 | alpha);
+                }
+            },
+            (TexFormat::RGBA, TexDataType::UnsignedShort5551) => {
+                for pixel in row[..width * 2].chunks_mut(2) {
+                    // A pixel is either opaque or fully transparent.
+                    if read_u16(pixel) & 1 == 0 {
+                        write_u16(pixel, 0);
+                    }
+                }
+            },
+            _ => return,
+        }
+    }
+}
+
+/// Converts RGBA8 pixels to tightly packed texture data of the given format
+/// and type, which the validators guarantee to be a valid combination.
+fn rgba8_image_to_tex_image_data(format: TexFormat, data_type: TexDataType, pixels: Vec<u8>) -> Vec<u8> {
+    let pixel_count = pixels.len() / 4;
+    match (format, data_type) {
+        (TexFormat::RGBA, TexDataType::UnsignedByte) => pixels,
+        (TexFormat::RGB, TexDataType::UnsignedByte) => {
+            let mut rgb = Vec::with_capacity(pixel_count * 3);
+            for pixel in pixels.chunks(4) {
+                rgb.extend_from_slice(&pixel[..3]);
+            }
+            rgb
+        },
+        (TexFormat::LuminanceAlpha, TexDataType::UnsignedByte) => {
+            let mut luminance_alpha = Vec::with_capacity(pixel_count * 2);
+            for pixel in pixels.chunks(4) {
+                luminance_alpha.push(pixel[0]);
+                luminance_alpha.push(pixel[3]);
+            }
+            luminance_alpha
+        },
+        (TexFormat::Luminance, TexDataType::UnsignedByte) => {
+            pixels.chunks(4).map(|pixel| pixel[0]).collect()
+        },
+        (TexFormat::Alpha, TexDataType::UnsignedByte) => {
+            pixels.chunks(4).map(|pixel| pixel[3]).collect()
+        },
+        (TexFormat::RGBA, TexDataType::UnsignedShort4444) => {
+            let mut rgba4 = Vec::with_capacity(pixel_count * 2);
+            for pixel in pixels.chunks(4) {
+                push_u16(&mut rgba4,
+                         (pixel[0] as u16 & 0xf0) << 8 |
+                         (pixel[1] as u16 & 0xf0) << 4 |
+                         (pixel[2] as u16 & 0xf0) |
+                         (pixel[3] as u16 & 0xf0) >> 4);
+            }
+            rgba4
+        },
+        (TexFormat::RGBA, TexDataType::UnsignedShort5551) => {
+            let mut rgb5a1 = Vec::with_capacity(pixel_count * 2);
+            for pixel in pixels.chunks(4) {
+                push_u16(&mut rgb5a1,
+                         (pixel[0] as u16 & 0xf8) << 8 |
+                         (pixel[1] as u16 & 0xf8) << 3 |
+                         (pixel[2] as u16 & 0xf8) >> 2 |
+                         (pixel[3] as u16) >> 7);
+            }
+            rgb5a1
+        },
+        (TexFormat::RGB, TexDataType::UnsignedShort565) => {
+            let mut rgb565 = Vec::with_capacity(pixel_count * 2);
+            for pixel in pixels.chunks(4) {
+                push_u16(&mut rgb565,
+                         (pixel[0] as u16 & 0xf8) << 8 |
+                         (pixel[1] as u16 & 0xfc) << 3 |
+                         (pixel[2] as u16 & 0xf8) >> 3);
+            }
+            rgb565
+        },
+        _ => unreachable!("invalid format and type combination"),
+    }
+}
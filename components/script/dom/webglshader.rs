@@ -0,0 +1,169 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
+use canvas_traits::webgl::{WebGLCommand, WebGLMsgSender, WebGLShaderId, webgl_channel};
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::codegen::Bindings::WebGLShaderBinding;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::webgl_validations::glsl::{self, ShaderKind, TranslationOptions};
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum ShaderCompilationStatus {
+    NotCompiled,
+    Succeeded,
+    Failed,
+}
+
+#[dom_struct]
+pub struct WebGLShader {
+    webgl_object: WebGLObject,
+    id: WebGLShaderId,
+    gl_type: u32,
+    source: DomRefCell<DOMString>,
+    info_log: DomRefCell<DOMString>,
+    marked_for_deletion: Cell<bool>,
+    /// The number of programs this shader is attached to, it is only
+    /// actually deleted once it is detached from all of them.
+    attached_counter: Cell<u32>,
+    compilation_status: Cell<ShaderCompilationStatus>,
+    renderer: WebGLMsgSender,
+}
+
+impl WebGLShader {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLShaderId, shader_type: u32) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            gl_type: shader_type,
+            source: Default::default(),
+            info_log: Default::default(),
+            marked_for_deletion: Cell::new(false),
+            attached_counter: Cell::new(0),
+            compilation_status: Cell::new(ShaderCompilationStatus::NotCompiled),
+            renderer: context.webgl_sender(),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext, shader_type: u32) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateShader(shader_type, sender));
+        receiver.recv().unwrap().map(|id| WebGLShader::new(context, id, shader_type))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLShaderId, shader_type: u32) -> DomRoot<Self> {
+        reflect_dom_object(Box::new(WebGLShader::new_inherited(context, id, shader_type)),
+                           &*context.global(),
+                           WebGLShaderBinding::Wrap)
+    }
+}
+
+impl WebGLShader {
+    pub fn id(&self) -> WebGLShaderId {
+        self.id
+    }
+
+    pub fn gl_type(&self) -> u32 {
+        self.gl_type
+    }
+
+    /// Validates the source against the WebGL restrictions, and compiles its
+    /// translation for the GL context if it is valid.
+    pub fn compile(&self, glsl_version: u32, standard_derivatives: bool) {
+        let kind = match self.gl_type {
+            constants::VERTEX_SHADER => ShaderKind::Vertex,
+            _ => ShaderKind::Fragment,
+        };
+        let options = TranslationOptions { kind, glsl_version, standard_derivatives };
+
+        let translated = match glsl::translate(&self.source.borrow(), &options) {
+            Ok(translated) => translated,
+            Err(error) => {
+                debug!("Shader {} failed to validate: {}", self.id, error.message);
+                *self.info_log.borrow_mut() = error.to_info_log().into();
+                self.compilation_status.set(ShaderCompilationStatus::Failed);
+                return;
+            },
+        };
+
+        self.renderer.send(WebGLCommand::CompileShader(self.id, translated)).unwrap();
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.renderer.send(WebGLCommand::GetShaderCompileStatus(self.id, sender)).unwrap();
+        let compiled = receiver.recv().unwrap();
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.renderer.send(WebGLCommand::GetShaderInfoLog(self.id, sender)).unwrap();
+        *self.info_log.borrow_mut() = receiver.recv().unwrap().into();
+
+        self.compilation_status.set(if compiled {
+            ShaderCompilationStatus::Succeeded
+        } else {
+            ShaderCompilationStatus::Failed
+        });
+    }
+
+    /// Mark this shader as deleted (if it wasn't previously)
+    /// and delete it as if calling glDeleteShader, GL itself only
+    /// deleting it once it is detached from all programs.
+    pub fn mark_for_deletion(&self) {
+        if !self.marked_for_deletion.get() {
+            self.marked_for_deletion.set(true);
+            let _ = self.renderer.send(WebGLCommand::DeleteShader(self.id));
+        }
+    }
+
+    pub fn is_marked_for_deletion(&self) -> bool {
+        self.marked_for_deletion.get()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.marked_for_deletion.get() && !self.is_attached()
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.attached_counter.get() > 0
+    }
+
+    pub fn increment_attached_counter(&self) {
+        self.attached_counter.set(self.attached_counter.get() + 1);
+    }
+
+    pub fn decrement_attached_counter(&self) {
+        assert!(self.attached_counter.get() > 0);
+        self.attached_counter.set(self.attached_counter.get() - 1);
+    }
+
+    /// glGetShaderInfoLog
+    pub fn info_log(&self) -> DOMString {
+        self.info_log.borrow().clone()
+    }
+
+    /// Get the shader source
+    pub fn source(&self) -> DOMString {
+        self.source.borrow().clone()
+    }
+
+    /// glShaderSource
+    pub fn set_source(&self, source: DOMString) {
+        *self.source.borrow_mut() = source;
+    }
+
+    pub fn successfully_compiled(&self) -> bool {
+        self.compilation_status.get() == ShaderCompilationStatus::Succeeded
+    }
+}
+
+impl Drop for WebGLShader {
+    fn drop(&mut self) {
+        self.mark_for_deletion();
+    }
+}
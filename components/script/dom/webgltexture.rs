@@ -0,0 +1,377 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
+use canvas_traits::webgl::{WebGLCommand, WebGLError, WebGLMsgSender, WebGLResult, WebGLTextureId};
+use canvas_traits::webgl::webgl_channel;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::codegen::Bindings::WebGLTextureBinding;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webgl_validations::types::{TexDataType, TexFormat, TexImageTarget};
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::cmp;
+
+/// The `TEXTURE_MAX_ANISOTROPY_EXT` parameter of
+/// `EXT_texture_filter_anisotropic`.
+pub const TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FE;
+
+const MAX_LEVEL_COUNT: usize = 31;
+const MAX_FACE_COUNT: usize = 6;
+
+pub enum TexParameterValue {
+    Float(f32),
+    Int(i32),
+}
+
+#[dom_struct]
+pub struct WebGLTexture {
+    webgl_object: WebGLObject,
+    id: WebGLTextureId,
+    /// The target to which this texture was bound the first time
+    target: Cell<Option<u32>>,
+    is_deleted: Cell<bool>,
+    /// Stores information about mipmap levels and cubemap faces.
+    image_info_array: DomRefCell<Vec<ImageInfo>>,
+    /// Face count can only be 1 or 6
+    face_count: Cell<u8>,
+    min_filter: Cell<u32>,
+    mag_filter: Cell<u32>,
+    renderer: WebGLMsgSender,
+}
+
+impl WebGLTexture {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLTextureId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id,
+            target: Cell::new(None),
+            is_deleted: Cell::new(false),
+            image_info_array: DomRefCell::new(vec![ImageInfo::new(); MAX_LEVEL_COUNT * MAX_FACE_COUNT]),
+            face_count: Cell::new(0),
+            min_filter: Cell::new(constants::NEAREST_MIPMAP_LINEAR),
+            mag_filter: Cell::new(constants::LINEAR),
+            renderer: context.webgl_sender(),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateTexture(sender));
+        receiver.recv().unwrap().map(|id| WebGLTexture::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLTextureId) -> DomRoot<Self> {
+        reflect_dom_object(Box::new(WebGLTexture::new_inherited(context, id)),
+                           &*context.global(),
+                           WebGLTextureBinding::Wrap)
+    }
+}
+
+impl WebGLTexture {
+    pub fn id(&self) -> WebGLTextureId {
+        self.id
+    }
+
+    // NB: Only valid texture targets come here
+    pub fn bind(&self, target: u32) -> WebGLResult<()> {
+        if self.is_deleted.get() {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        if let Some(previous_target) = self.target.get() {
+            if target != previous_target {
+                return Err(WebGLError::InvalidOperation);
+            }
+        } else {
+            // This is the first time that we bind this texture
+            self.face_count.set(match target {
+                constants::TEXTURE_2D => 1,
+                constants::TEXTURE_CUBE_MAP => 6,
+                _ => return Err(WebGLError::InvalidEnum),
+            });
+            self.target.set(Some(target));
+        }
+
+        self.renderer.send(WebGLCommand::BindTexture(target, Some(self.id))).unwrap();
+        Ok(())
+    }
+
+    /// Records the size and format of the image uploaded to a face and level.
+    pub fn initialize(&self,
+                      target: TexImageTarget,
+                      width: u32,
+                      height: u32,
+                      internal_format: TexFormat,
+                      level: u32,
+                      data_type: Option<TexDataType>) {
+        let image_info = ImageInfo {
+            width,
+            height,
+            internal_format: Some(internal_format),
+            data_type,
+        };
+        self.set_image_infos_at_level_and_face(level, target.face_index(), image_info);
+    }
+
+    pub fn generate_mipmap(&self) -> WebGLResult<()> {
+        let target = match self.target.get() {
+            Some(target) => target,
+            None => {
+                error!("Cannot generate mipmap on texture that has no target!");
+                return Err(WebGLError::InvalidOperation);
+            }
+        };
+
+        let base_image_info = self.base_image_info().unwrap();
+        if !base_image_info.is_initialized() {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        let is_cubic = target == constants::TEXTURE_CUBE_MAP;
+        if is_cubic && !self.is_cube_complete() {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        if !base_image_info.is_power_of_two() {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        self.renderer.send(WebGLCommand::GenerateMipmap(target)).unwrap();
+
+        let last_level = base_image_info.get_max_mipmap_levels() - 1;
+        self.populate_mip_chain(0, last_level)
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            let _ = self.renderer.send(WebGLCommand::DeleteTexture(self.id));
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    pub fn target(&self) -> Option<u32> {
+        self.target.get()
+    }
+
+    /// We have to follow the conversion rules for GLES 2.0. See:
+    ///   https://www.khronos.org/webgl/public-mailing-list/archives/1008/msg00014.html
+    pub fn tex_parameter(&self, target: u32, name: u32, value: TexParameterValue) -> WebGLResult<()> {
+        let (int_value, float_value) = match value {
+            TexParameterValue::Int(int_value) => (int_value, int_value as f32),
+            TexParameterValue::Float(float_value) => (float_value as i32, float_value),
+        };
+
+        match name {
+            constants::TEXTURE_MIN_FILTER => {
+                match int_value as u32 {
+                    constants::NEAREST |
+                    constants::LINEAR |
+                    constants::NEAREST_MIPMAP_NEAREST |
+                    constants::LINEAR_MIPMAP_NEAREST |
+                    constants::NEAREST_MIPMAP_LINEAR |
+                    constants::LINEAR_MIPMAP_LINEAR => {
+                        self.min_filter.set(int_value as u32);
+                        self.renderer.send(WebGLCommand::TexParameteri(target, name, int_value)).unwrap();
+                        Ok(())
+                    },
+                    _ => Err(WebGLError::InvalidEnum),
+                }
+            },
+            constants::TEXTURE_MAG_FILTER => {
+                match int_value as u32 {
+                    constants::NEAREST |
+                    constants::LINEAR => {
+                        self.mag_filter.set(int_value as u32);
+                        self.renderer.send(WebGLCommand::TexParameteri(target, name, int_value)).unwrap();
+                        Ok(())
+                    },
+                    _ => Err(WebGLError::InvalidEnum),
+                }
+            },
+            constants::TEXTURE_WRAP_S |
+            constants::TEXTURE_WRAP_T => {
+                match int_value as u32 {
+                    constants::CLAMP_TO_EDGE |
+                    constants::MIRRORED_REPEAT |
+                    constants::REPEAT => {
+                        self.renderer.send(WebGLCommand::TexParameteri(target, name, int_value)).unwrap();
+                        Ok(())
+                    },
+                    _ => Err(WebGLError::InvalidEnum),
+                }
+            },
+            // Only reached when EXT_texture_filter_anisotropic is enabled.
+            TEXTURE_MAX_ANISOTROPY_EXT => {
+                if float_value < 1. {
+                    return Err(WebGLError::InvalidValue);
+                }
+                self.renderer.send(WebGLCommand::TexParameterf(target, name, float_value)).unwrap();
+                Ok(())
+            },
+            _ => Err(WebGLError::InvalidEnum),
+        }
+    }
+
+    pub fn min_filter(&self) -> u32 {
+        self.min_filter.get()
+    }
+
+    pub fn mag_filter(&self) -> u32 {
+        self.mag_filter.get()
+    }
+
+    fn populate_mip_chain(&self, first_level: u32, last_level: u32) -> WebGLResult<()> {
+        let base_image_info = self.image_info_at_face(0, first_level);
+        if !base_image_info.is_initialized() {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        let mut ref_width = base_image_info.width;
+        let mut ref_height = base_image_info.height;
+
+        if ref_width == 0 || ref_height == 0 {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        for level in (first_level + 1)..last_level + 1 {
+            if ref_width == 1 && ref_height == 1 {
+                break;
+            }
+
+            ref_width = cmp::max(1, ref_width / 2);
+            ref_height = cmp::max(1, ref_height / 2);
+
+            let image_info = ImageInfo {
+                width: ref_width,
+                height: ref_height,
+                internal_format: base_image_info.internal_format,
+                data_type: base_image_info.data_type,
+            };
+
+            self.set_image_infos_at_level(level, image_info);
+        }
+        Ok(())
+    }
+
+    fn is_cube_complete(&self) -> bool {
+        debug_assert_eq!(self.face_count.get(), 6);
+
+        let image_info = self.base_image_info().unwrap();
+        if !image_info.is_initialized() {
+            return false;
+        }
+
+        let ref_width = image_info.width;
+        let ref_format = image_info.internal_format;
+
+        for face in 0..self.face_count.get() {
+            let current_image_info = self.image_info_at_face(face, 0);
+            if !current_image_info.is_initialized() {
+                return false;
+            }
+
+            // Compares height with width to enforce square dimensions
+            if current_image_info.internal_format != ref_format ||
+               current_image_info.width != ref_width ||
+               current_image_info.height != ref_width {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn image_info_for_target(&self, target: &TexImageTarget, level: u32) -> ImageInfo {
+        self.image_info_at_face(target.face_index() as u8, level)
+    }
+
+    fn image_info_at_face(&self, face: u8, level: u32) -> ImageInfo {
+        let pos = (level * self.face_count.get() as u32) + face as u32;
+        self.image_info_array.borrow()[pos as usize]
+    }
+
+    fn set_image_infos_at_level(&self, level: u32, image_info: ImageInfo) {
+        for face in 0..self.face_count.get() {
+            self.set_image_infos_at_level_and_face(level, face as usize, image_info);
+        }
+    }
+
+    fn set_image_infos_at_level_and_face(&self, level: u32, face: usize, image_info: ImageInfo) {
+        debug_assert!(face < self.face_count.get() as usize);
+        let pos = (level * self.face_count.get() as u32) as usize + face;
+        self.image_info_array.borrow_mut()[pos] = image_info;
+    }
+
+    fn base_image_info(&self) -> Option<ImageInfo> {
+        assert!((self.face_count.get() as usize) <= MAX_FACE_COUNT);
+        self.image_info_array.borrow().get(0).cloned()
+    }
+}
+
+impl Drop for WebGLTexture {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
+
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub struct ImageInfo {
+    width: u32,
+    height: u32,
+    internal_format: Option<TexFormat>,
+    data_type: Option<TexDataType>,
+}
+
+impl ImageInfo {
+    fn new() -> ImageInfo {
+        ImageInfo {
+            width: 0,
+            height: 0,
+            internal_format: None,
+            data_type: None,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn internal_format(&self) -> Option<TexFormat> {
+        self.internal_format
+    }
+
+    pub fn data_type(&self) -> Option<TexDataType> {
+        self.data_type
+    }
+
+    fn is_power_of_two(&self) -> bool {
+        self.width.is_power_of_two() && self.height.is_power_of_two()
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.internal_format.is_some()
+    }
+
+    fn get_max_mipmap_levels(&self) -> u32 {
+        let largest = cmp::max(self.width, self.height);
+        if largest == 0 {
+            return 0;
+        }
+        // FloorLog2(largest) + 1
+        (largest as f64).log2() as u32 + 1
+    }
+}
@@ -0,0 +1,73 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
+use canvas_traits::webgl::WebGLProgramId;
+use dom::bindings::codegen::Bindings::WebGLUniformLocationBinding;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+
+#[dom_struct]
+pub struct WebGLUniformLocation {
+    reflector_: Reflector,
+    context: Dom<WebGLRenderingContext>,
+    id: i32,
+    program_id: WebGLProgramId,
+    /// The number of elements of the array this location points into, if
+    /// the uniform is an array.
+    size: Option<i32>,
+    type_: u32,
+}
+
+impl WebGLUniformLocation {
+    fn new_inherited(context: &WebGLRenderingContext,
+                     id: i32,
+                     program_id: WebGLProgramId,
+                     size: Option<i32>,
+                     type_: u32)
+                     -> Self {
+        Self {
+            reflector_: Reflector::new(),
+            context: Dom::from_ref(context),
+            id,
+            program_id,
+            size,
+            type_,
+        }
+    }
+
+    pub fn new(context: &WebGLRenderingContext,
+               id: i32,
+               program_id: WebGLProgramId,
+               size: Option<i32>,
+               type_: u32)
+               -> DomRoot<Self> {
+        reflect_dom_object(Box::new(Self::new_inherited(context, id, program_id, size, type_)),
+                           &*context.global(),
+                           WebGLUniformLocationBinding::Wrap)
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn program_id(&self) -> WebGLProgramId {
+        self.program_id
+    }
+
+    /// Whether this location was returned by `context`.
+    pub fn is_from(&self, context: &WebGLRenderingContext) -> bool {
+        &*self.context as *const WebGLRenderingContext == context as *const WebGLRenderingContext
+    }
+
+    pub fn size(&self) -> Option<i32> {
+        self.size
+    }
+
+    pub fn type_(&self) -> u32 {
+        self.type_
+    }
+}
@@ -4,14 +4,20 @@
 
 use app_units::Au;
 use base64;
+use canvas_traits::webgl::WebGLChan;
 use cssparser::{Parser, ParserInput};
 use devtools_traits::{ScriptToDevtoolsControlMsg, TimelineMarker, TimelineMarkerType};
+use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
-use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState};
+use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState, DocumentVisibilityState};
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use dom::bindings::codegen::Bindings::HistoryBinding::HistoryBinding::HistoryMethods;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapOptions, ImageBitmapSource};
 use dom::bindings::codegen::Bindings::MediaQueryListBinding::MediaQueryListBinding::MediaQueryListMethods;
+use dom::bindings::codegen::Bindings::MessagePortBinding::Transferable;
+use dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::{self, FrameRequestCallback, WindowMethods};
+use dom::bindings::codegen::Bindings::WindowBinding::{IdleRequestCallback, IdleRequestOptions};
 use dom::bindings::codegen::Bindings::WindowBinding::{ScrollBehavior, ScrollToOptions};
 use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::Castable;
@@ -23,6 +29,7 @@ use dom::bindings::str::{DOMString, USVString};
 use dom::bindings::structuredclone::StructuredCloneData;
 use dom::bindings::utils::{GlobalStaticData, WindowProxyHandler};
 use dom::bindings::weakref::DOMTracker;
+use dom::crypto::Crypto;
 use dom::cssstyledeclaration::{CSSModificationAccess, CSSStyleDeclaration, CSSStyleOwner};
 use dom::customelementregistry::CustomElementRegistry;
 use dom::document::{AnimationFrameCallback, Document};
@@ -32,13 +39,17 @@ use dom::eventtarget::EventTarget;
 use dom::globalscope::GlobalScope;
 use dom::hashchangeevent::HashChangeEvent;
 use dom::history::History;
+use dom::idledeadline::IdleDeadline;
+use dom::imagebitmap::ImageBitmap;
 use dom::location::Location;
 use dom::mediaquerylist::{MediaQueryList, MediaQueryListMatchState};
 use dom::mediaquerylistevent::MediaQueryListEvent;
 use dom::messageevent::MessageEvent;
 use dom::node::{Node, NodeDamage, document_from_node, from_untrusted_node_address};
 use dom::performance::Performance;
+use dom::promise::Promise;
 use dom::screen::Screen;
+use dom::selection::Selection;
 use dom::storage::Storage;
 use dom::windowproxy::WindowProxy;
 use dom::worklet::Worklet;
@@ -66,14 +77,17 @@ use profile_traits::time::ProfilerChan as TimeProfilerChan;
 use script_layout_interface::{TrustedNodeAddress, PendingImageState};
 use script_layout_interface::message::{Msg, Reflow, QueryMsg, ReflowGoal, ScriptReflow};
 use script_layout_interface::reporter::CSSErrorReporter;
-use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
-use script_layout_interface::rpc::{NodeScrollIdResponse, ResolvedStyleResponse, TextIndexResponse};
+use script_layout_interface::rpc::{BoxSizes, BoxSizesResponse, ContentBoxResponse, ContentBoxesResponse};
+use script_layout_interface::rpc::LayoutRPC;
+use script_layout_interface::rpc::{NodeScrollIdResponse, ResolvedStyleResponse, SvgHitTestResponse};
+use script_layout_interface::rpc::TextIndexResponse;
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptPort, ScriptThreadEventCategory, Runtime};
 use script_thread::{ImageCacheMsg, MainThreadScriptChan, MainThreadScriptMsg};
 use script_thread::{ScriptThread, SendableMainThreadScriptChan};
-use script_traits::{ConstellationControlMsg, DocumentState, LoadData};
+use script_traits::{ConstellationControlMsg, DocumentState, LoadData, MsDuration};
 use script_traits::{ScriptToConstellationChan, ScriptMsg, ScrollState, TimerEvent, TimerEventId};
-use script_traits::{TimerSchedulerMsg, UntrustedNodeAddress, WindowSizeData, WindowSizeType};
+use script_traits::{TimerSchedulerMsg, TransferredPort, UntrustedNodeAddress, WindowSizeData};
+use script_traits::WindowSizeType;
 use script_traits::webdriver_msg::{WebDriverJSError, WebDriverJSResult};
 use selectors::attr::CaseSensitivity;
 use servo_arc;
@@ -98,24 +112,33 @@ use style::error_reporting::ParseErrorReporter;
 use style::media_queries;
 use style::parser::ParserContext as CssParserContext;
 use style::properties::{ComputedValues, PropertyId};
+use style::properties::style_structs::Font;
 use style::selector_parser::PseudoElement;
 use style::str::HTML_SPACE_CHARACTERS;
 use style::stylesheets::CssRuleType;
 use style_traits::{CSSPixel, DevicePixel, ParsingMode};
 use task::TaskCanceller;
-use task_source::TaskSourceName;
+use task_source::{TaskSource, TaskSourceName};
 use task_source::dom_manipulation::DOMManipulationTaskSource;
 use task_source::history_traversal::HistoryTraversalTaskSource;
+use task_source::idle::IdleTaskSource;
+use task_source::intersection_observer::IntersectionObserverTaskSource;
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
+use task_source::port_message::PortMessageQueue;
 use task_source::remote_event::RemoteEventTaskSource;
 use task_source::user_interaction::UserInteractionTaskSource;
 use time;
-use timers::{IsInterval, TimerCallback};
+use timers::{IsInterval, OneshotTimerCallback, TimerCallback};
 use url::Position;
 use webdriver_handlers::jsval_to_webdriver;
 use webrender_api::{ExternalScrollId, DeviceIntPoint, DeviceUintSize, DocumentId};
 
+/// The longest an idle period can last, in milliseconds.
+///
+/// <https://w3c.github.io/requestidlecallback/#why50>
+const MAX_IDLE_PERIOD: f64 = 50.;
+
 /// Current state of the window object
 #[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
 enum WindowState {
@@ -164,6 +187,12 @@ pub struct Window {
     #[ignore_malloc_size_of = "task sources are hard"]
     performance_timeline_task_source: PerformanceTimelineTaskSource,
     #[ignore_malloc_size_of = "task sources are hard"]
+    port_message_queue: PortMessageQueue,
+    #[ignore_malloc_size_of = "task sources are hard"]
+    intersection_observer_task_source: IntersectionObserverTaskSource,
+    #[ignore_malloc_size_of = "task sources are hard"]
+    idle_task_source: IdleTaskSource,
+    #[ignore_malloc_size_of = "task sources are hard"]
     remote_event_task_source: RemoteEventTaskSource,
     #[ignore_malloc_size_of = "Arc"]
     image_cache: Arc<ImageCache>,
@@ -175,6 +204,7 @@ pub struct Window {
     history: MutNullableDom<History>,
     custom_element_registry: MutNullableDom<CustomElementRegistry>,
     performance: MutNullableDom<Performance>,
+    crypto: MutNullableDom<Crypto>,
     navigation_start: Cell<u64>,
     navigation_start_precise: Cell<u64>,
     screen: MutNullableDom<Screen>,
@@ -259,15 +289,117 @@ pub struct Window {
     test_worklet: MutNullableDom<Worklet>,
     /// <https://drafts.css-houdini.org/css-paint-api-1/#paint-worklet>
     paint_worklet: MutNullableDom<Worklet>,
+    /// A handle to the WebGL thread, if WebGL is available.
+    #[ignore_malloc_size_of = "channels are hard"]
+    webgl_chan: Option<WebGLChan>,
     /// The Webrender Document id associated with this window.
     #[ignore_malloc_size_of = "defined in webrender_api"]
     webrender_document: DocumentId,
 
     /// Flag to identify whether mutation observers are present(true)/absent(false)
     exists_mut_observer: Cell<bool>,
+
+    /// <https://w3c.github.io/requestidlecallback/#dfn-idle-callback-identifier>
+    idle_callback_identifier: Cell<u32>,
+    /// <https://w3c.github.io/requestidlecallback/#dfn-list-of-idle-request-callbacks>
+    #[ignore_malloc_size_of = "Rc is hard"]
+    idle_request_callbacks: DomRefCell<Vec<(u32, Rc<IdleRequestCallback>)>>,
+    /// <https://w3c.github.io/requestidlecallback/#dfn-list-of-runnable-idle-callbacks>
+    #[ignore_malloc_size_of = "Rc is hard"]
+    runnable_idle_callbacks: DomRefCell<Vec<(u32, Rc<IdleRequestCallback>)>>,
+    /// Whether a task to invoke the runnable idle callbacks is queued.
+    idle_callbacks_task_queued: Cell<bool>,
 }
 
 impl Window {
+    /// Remove the callback with the given handle from the lists of idle
+    /// request callbacks and of runnable idle callbacks.
+    fn take_idle_callback(&self, handle: u32) -> Option<Rc<IdleRequestCallback>> {
+        for list in &[&self.idle_request_callbacks, &self.runnable_idle_callbacks] {
+            let mut list = list.borrow_mut();
+            if let Some(index) = list.iter().position(|&(h, _)| h == handle) {
+                return Some(list.remove(index).1);
+            }
+        }
+        None
+    }
+
+    /// <https://w3c.github.io/requestidlecallback/#start-an-idle-period-algorithm>
+    pub fn start_an_idle_period(&self) {
+        if self.idle_callbacks_task_queued.get() {
+            return;
+        }
+
+        // Steps 3-4. Callbacks requested during an idle period wait for the next one.
+        if self.runnable_idle_callbacks.borrow().is_empty() {
+            let mut pending = self.idle_request_callbacks.borrow_mut();
+            self.runnable_idle_callbacks.borrow_mut().extend(pending.drain(..));
+        }
+        if self.runnable_idle_callbacks.borrow().is_empty() {
+            return;
+        }
+
+        // Step 1. The idle period ends at the next timer or animation frame,
+        // and lasts at most 50ms.
+        let now = *self.Performance().Now();
+        let mut deadline = now + MAX_IDLE_PERIOD;
+        if let Some(duration) = self.upcast::<GlobalScope>().time_until_next_timer() {
+            deadline = deadline.min(now + duration.get() as f64);
+        }
+        if let Some(next_frame) = self.Document().next_animation_frame_time() {
+            deadline = deadline.min(next_frame);
+        }
+
+        // The timer or animation frame that ends this idle period is due, and
+        // the event loop will try again once it has run.
+        if deadline <= now {
+            return;
+        }
+
+        // Step 5.
+        self.idle_callbacks_task_queued.set(true);
+        let this = Trusted::new(self);
+        let _ = self.idle_task_source().queue(
+            task!(invoke_idle_callbacks: move || {
+                this.root().invoke_idle_callbacks(deadline);
+            }),
+            self.upcast(),
+        );
+    }
+
+    /// <https://w3c.github.io/requestidlecallback/#invoke-idle-callbacks-algorithm>
+    ///
+    /// This runs a single callback. The event loop starts another idle period
+    /// for the remaining ones after checking for more urgent work.
+    fn invoke_idle_callbacks(&self, deadline: f64) {
+        self.idle_callbacks_task_queued.set(false);
+
+        // Steps 2-3.
+        if *self.Performance().Now() >= deadline {
+            return;
+        }
+        let callback = {
+            let mut runnable = self.runnable_idle_callbacks.borrow_mut();
+            if runnable.is_empty() {
+                return;
+            }
+            runnable.remove(0).1
+        };
+        let deadline = IdleDeadline::new(self, deadline, false);
+        let _ = callback.Call__(&deadline, ExceptionHandling::Report);
+    }
+
+    /// <https://w3c.github.io/requestidlecallback/#invoke-idle-callback-timeout-algorithm>
+    fn invoke_idle_callback_timeout(&self, handle: u32) {
+        // Steps 1-2.
+        if let Some(callback) = self.take_idle_callback(handle) {
+            // Steps 3-4.
+            let now = *self.Performance().Now();
+            let deadline = IdleDeadline::new(self, now, true);
+            let _ = callback.Call__(&deadline, ExceptionHandling::Report);
+        }
+    }
+
     pub fn get_exists_mut_observer(&self) -> bool {
         self.exists_mut_observer.get()
     }
@@ -327,6 +459,18 @@ impl Window {
         self.performance_timeline_task_source.clone()
     }
 
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        self.port_message_queue.clone()
+    }
+
+    pub fn intersection_observer_task_source(&self) -> IntersectionObserverTaskSource {
+        self.intersection_observer_task_source.clone()
+    }
+
+    pub fn idle_task_source(&self) -> IdleTaskSource {
+        self.idle_task_source.clone()
+    }
+
     pub fn remote_event_task_source(&self) -> RemoteEventTaskSource {
         self.remote_event_task_source.clone()
     }
@@ -542,6 +686,11 @@ impl WindowMethods for Window {
         self.document.get().expect("Document accessed before initialization.")
     }
 
+    // https://w3c.github.io/selection-api/#dom-window-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        self.Document().GetSelection()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-history
     fn History(&self) -> DomRoot<History> {
         self.history.or_init(|| History::new(self))
@@ -682,6 +831,11 @@ impl WindowMethods for Window {
         Some(DomRoot::from_ref(window_proxy.top()))
     }
 
+    // https://w3c.github.io/webcrypto/#dom-globalcrypto-crypto
+    fn Crypto(&self) -> DomRoot<Crypto> {
+        self.crypto.or_init(|| Crypto::new(self.upcast()))
+    }
+
     // https://dvcs.w3.org/hg/webperf/raw-file/tip/specs/
     // NavigationTiming/Overview.html#sec-window.performance-attribute
     fn Performance(&self) -> DomRoot<Performance> {
@@ -713,6 +867,26 @@ impl WindowMethods for Window {
         base64_atob(atob)
     }
 
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap(&self, image: ImageBitmapSource, options: &ImageBitmapOptions) -> Rc<Promise> {
+        ImageBitmap::create(self.upcast(), image, None, options)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap_(&self,
+                          image: ImageBitmapSource,
+                          sx: i32,
+                          sy: i32,
+                          sw: i32,
+                          sh: i32,
+                          options: &ImageBitmapOptions)
+                          -> Rc<Promise> {
+        let crop = Rect::new(Point2D::new(sx, sy), Size2D::new(sw, sh));
+        ImageBitmap::create(self.upcast(), image, Some(crop), options)
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#dom-window-requestanimationframe>
     fn RequestAnimationFrame(&self, callback: Rc<FrameRequestCallback>) -> u32 {
         self.Document()
@@ -725,12 +899,43 @@ impl WindowMethods for Window {
         doc.cancel_animation_frame(ident);
     }
 
+    // https://w3c.github.io/requestidlecallback/#the-requestidlecallback-method
+    fn RequestIdleCallback(&self, callback: Rc<IdleRequestCallback>, options: &IdleRequestOptions) -> u32 {
+        // Steps 2-4.
+        let handle = self.idle_callback_identifier.get() + 1;
+        self.idle_callback_identifier.set(handle);
+
+        // Step 5.
+        self.idle_request_callbacks.borrow_mut().push((handle, callback));
+
+        // Step 6.
+        if options.timeout > 0 {
+            let callback = IdleCallbackTimeout {
+                window: Trusted::new(self),
+                handle,
+            };
+            self.upcast::<GlobalScope>().schedule_callback(
+                OneshotTimerCallback::IdleCallbackTimeout(callback),
+                MsDuration::new(options.timeout as u64),
+            );
+        }
+
+        // Step 7.
+        handle
+    }
+
+    // https://w3c.github.io/requestidlecallback/#the-cancelidlecallback-method
+    fn CancelIdleCallback(&self, handle: u32) {
+        self.take_idle_callback(handle);
+    }
+
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage
     unsafe fn PostMessage(&self,
                    cx: *mut JSContext,
                    message: HandleValue,
-                   origin: DOMString)
+                   origin: DOMString,
+                   transfer: Option<Vec<Transferable>>)
                    -> ErrorResult {
         // Step 3-5.
         let origin = match &origin[..] {
@@ -746,12 +951,20 @@ impl WindowMethods for Window {
             }
         };
 
+        // Step 7.1. This window is the target, so a mismatching origin is
+        // known now, and the message is dropped before transferring anything.
+        if let Some(ref origin) = origin {
+            if !origin.same_origin(self.Document().origin()) {
+                return Ok(());
+            }
+        }
+
         // Step 1-2, 6-8.
-        // TODO(#12717): Should implement the `transfer` argument.
-        let data = StructuredCloneData::write(cx, message)?;
+        let transfer = transfer.unwrap_or_default();
+        let (data, ports) = StructuredCloneData::write_with_transfer(cx, message, &transfer)?;
 
         // Step 9.
-        self.post_message(origin, data);
+        self.post_message(origin, data, ports);
         Ok(())
     }
 
@@ -1065,6 +1278,9 @@ impl Window {
             custom_elements.teardown();
         }
 
+        // Disentangle the ports of this window and close its broadcast channels.
+        self.upcast::<GlobalScope>().remove_message_port_router();
+
         // The above code may not catch all DOM objects (e.g. DOM
         // objects removed from the tree that haven't been collected
         // yet). There should not be any such DOM nodes with layout
@@ -1394,6 +1610,14 @@ impl Window {
         rects
     }
 
+    pub fn box_sizes_query(&self, node: TrustedNodeAddress) -> Option<BoxSizes> {
+        if !self.layout_reflow(QueryMsg::BoxSizesQuery(node)) {
+            return None;
+        }
+        let BoxSizesResponse(sizes) = self.layout_rpc.box_sizes();
+        sizes
+    }
+
     pub fn client_rect_query(&self, node_geometry_request: TrustedNodeAddress) -> Rect<i32> {
         if !self.layout_reflow(QueryMsg::NodeGeometryQuery(node_geometry_request)) {
             return Rect::zero();
@@ -1456,6 +1680,16 @@ impl Window {
         DOMString::from(resolved)
     }
 
+    pub fn resolved_font_style_query(&self,
+                                     node: &Node,
+                                     value: String) -> Option<servo_arc::Arc<Font>> {
+        let query = QueryMsg::ResolvedFontStyleQuery(node.to_trusted_node_address(), value);
+        if !self.layout_reflow(query) {
+            return None;
+        }
+        self.layout_rpc.resolved_font_style()
+    }
+
     #[allow(unsafe_code)]
     pub fn offset_parent_query(&self, node: TrustedNodeAddress) -> (Option<DomRoot<Element>>, Rect<Au>) {
         if !self.layout_reflow(QueryMsg::OffsetParentQuery(node)) {
@@ -1482,14 +1716,31 @@ impl Window {
     pub fn text_index_query(
         &self,
         node: TrustedNodeAddress,
-        point_in_node: Point2D<f32>
+        point_in_node: Point2D<f32>,
+        client_point: Point2D<f32>
     ) -> TextIndexResponse {
-        if !self.layout_reflow(QueryMsg::TextIndexQuery(node, point_in_node)) {
+        let scroll_offset = self.current_viewport.get().origin;
+        let point_in_page = Point2D::new(
+            client_point.x + scroll_offset.x.to_f32_px(),
+            client_point.y + scroll_offset.y.to_f32_px(),
+        );
+        if !self.layout_reflow(QueryMsg::TextIndexQuery(node, point_in_node, point_in_page)) {
             return TextIndexResponse(None);
         }
         self.layout_rpc.text_index()
     }
 
+    pub fn svg_hit_test_query(
+        &self,
+        node: TrustedNodeAddress,
+        point_in_node: Point2D<f32>
+    ) -> SvgHitTestResponse {
+        if !self.layout_reflow(QueryMsg::SvgHitTestQuery(node, point_in_node)) {
+            return SvgHitTestResponse(None);
+        }
+        self.layout_rpc.svg_hit_test()
+    }
+
     #[allow(unsafe_code)]
     pub fn init_window_proxy(&self, window_proxy: &WindowProxy) {
         assert!(self.window_proxy.get().is_none());
@@ -1738,12 +1989,28 @@ impl Window {
         }
     }
 
-    /// Slow down/speed up timers based on visibility.
+    /// Throttle the timers and animation frames of hidden windows, and pause
+    /// their animated images.
     pub fn alter_resource_utilization(&self, visible: bool) {
+        let visibility_state = self.throttle_resources(visible);
+        self.Document().update_visibility_state(visibility_state);
+    }
+
+    /// Throttle a window that is hidden from the start, without firing a
+    /// `visibilitychange` event at its new document.
+    pub fn start_hidden(&self) {
+        let visibility_state = self.throttle_resources(false);
+        self.Document().set_initial_visibility_state(visibility_state);
+    }
+
+    fn throttle_resources(&self, visible: bool) -> DocumentVisibilityState {
+        self.layout_chan.send(Msg::SetDocumentVisibility(visible)).unwrap();
         if visible {
             self.upcast::<GlobalScope>().speed_up_timers();
+            DocumentVisibilityState::Visible
         } else {
             self.upcast::<GlobalScope>().slow_down_timers();
+            DocumentVisibilityState::Hidden
         }
     }
 
@@ -1772,6 +2039,10 @@ impl Window {
     pub fn webrender_document(&self) -> DocumentId {
         self.webrender_document
     }
+
+    pub fn webgl_chan(&self) -> Option<WebGLChan> {
+        self.webgl_chan.clone()
+    }
 }
 
 impl Window {
@@ -1784,6 +2055,9 @@ impl Window {
         networking_task_source: NetworkingTaskSource,
         history_traversal_task_source: HistoryTraversalTaskSource,
         performance_timeline_task_source: PerformanceTimelineTaskSource,
+        port_message_queue: PortMessageQueue,
+        intersection_observer_task_source: IntersectionObserverTaskSource,
+        idle_task_source: IdleTaskSource,
         remote_event_task_source: RemoteEventTaskSource,
         image_cache_chan: Sender<ImageCacheMsg>,
         image_cache: Arc<ImageCache>,
@@ -1803,6 +2077,7 @@ impl Window {
         navigation_start: u64,
         navigation_start_precise: u64,
         microtask_queue: Rc<MicrotaskQueue>,
+        webgl_chan: Option<WebGLChan>,
         webrender_document: DocumentId,
     ) -> DomRoot<Self> {
         let layout_rpc: Box<LayoutRPC + Send> = {
@@ -1833,6 +2108,9 @@ impl Window {
             networking_task_source,
             history_traversal_task_source,
             performance_timeline_task_source,
+            port_message_queue,
+            intersection_observer_task_source,
+            idle_task_source,
             remote_event_task_source,
             image_cache_chan,
             image_cache,
@@ -1842,6 +2120,7 @@ impl Window {
             window_proxy: Default::default(),
             document: Default::default(),
             performance: Default::default(),
+            crypto: Default::default(),
             navigation_start: Cell::new(navigation_start),
             navigation_start_precise: Cell::new(navigation_start_precise),
             screen: Default::default(),
@@ -1871,8 +2150,13 @@ impl Window {
             unminified_js_dir: Default::default(),
             test_worklet: Default::default(),
             paint_worklet: Default::default(),
+            webgl_chan,
             webrender_document,
             exists_mut_observer: Cell::new(false),
+            idle_callback_identifier: Cell::new(0),
+            idle_request_callbacks: Default::default(),
+            runnable_idle_callbacks: Default::default(),
+            idle_callbacks_task_queued: Cell::new(false),
         });
 
         unsafe {
@@ -1911,14 +2195,17 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
         ReflowGoal::LayoutQuery(ref query_msg, _) => match query_msg {
             &QueryMsg::ContentBoxQuery(_n) => "\tContentBoxQuery",
             &QueryMsg::ContentBoxesQuery(_n) => "\tContentBoxesQuery",
+            &QueryMsg::BoxSizesQuery(_n) => "\tBoxSizesQuery",
             &QueryMsg::NodesFromPointQuery(..) => "\tNodesFromPointQuery",
             &QueryMsg::NodeGeometryQuery(_n) => "\tNodeGeometryQuery",
             &QueryMsg::NodeScrollGeometryQuery(_n) => "\tNodeScrollGeometryQuery",
             &QueryMsg::NodeScrollIdQuery(_n) => "\tNodeScrollIdQuery",
             &QueryMsg::ResolvedStyleQuery(_, _, _) => "\tResolvedStyleQuery",
+            &QueryMsg::ResolvedFontStyleQuery(..) => "\tResolvedFontStyleQuery",
             &QueryMsg::OffsetParentQuery(_n) => "\tOffsetParentQuery",
             &QueryMsg::StyleQuery(_n) => "\tStyleQuery",
             &QueryMsg::TextIndexQuery(..) => "\tTextIndexQuery",
+            &QueryMsg::SvgHitTestQuery(..) => "\tSvgHitTestQuery",
             &QueryMsg::ElementInnerTextQuery(_) => "\tElementInnerTextQuery",
         },
     });
@@ -1955,6 +2242,7 @@ impl Window {
         &self,
         target_origin: Option<ImmutableOrigin>,
         serialize_with_transfer_result: StructuredCloneData,
+        ports: Vec<TransferredPort>,
     ) {
         let this = Trusted::new(self);
         let task = task!(post_serialised_message: move || {
@@ -1963,7 +2251,7 @@ impl Window {
             // Step 7.1.
             if let Some(target_origin) = target_origin {
                 if !target_origin.same_origin(this.Document().origin()) {
-                    return;
+                    return this.upcast::<GlobalScope>().discard_transferred_ports(ports);
                 }
             }
 
@@ -1972,29 +2260,38 @@ impl Window {
             let obj = this.reflector().get_jsobject();
             let _ac = JSAutoCompartment::new(cx, obj.get());
             rooted!(in(cx) let mut message_clone = UndefinedValue());
-            serialize_with_transfer_result.read(
+            // Step 7.6.
+            let new_ports = serialize_with_transfer_result.read_with_ports(
                 this.upcast(),
+                ports,
                 message_clone.handle_mut(),
             );
 
-            // Step 7.6.
-            // TODO: MessagePort array.
-
             // Step 7.7.
             // TODO(#12719): Set the other attributes.
             MessageEvent::dispatch_jsval(
                 this.upcast(),
                 this.upcast(),
                 message_clone.handle(),
+                None,
+                new_ports,
             );
         });
         // FIXME(nox): Why are errors silenced here?
-        // TODO(#12718): Use the "posted message task source".
-        // TODO: When switching to the right task source, update the task_canceller call too.
-        let _ = self.script_chan.send(CommonScriptMsg::Task(
-            ScriptThreadEventCategory::DomEvent,
-            Box::new(self.task_canceller(TaskSourceName::DOMManipulation).wrap_task(task)),
-            self.pipeline_id()
-        ));
+        let _ = self.port_message_queue.queue(task, self.upcast());
+    }
+}
+
+/// A timer that runs an idle callback when its `timeout` expires.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct IdleCallbackTimeout {
+    #[ignore_malloc_size_of = "non-owning"]
+    window: Trusted<Window>,
+    handle: u32,
+}
+
+impl IdleCallbackTimeout {
+    pub fn invoke(self) {
+        self.window.root().invoke_idle_callback_timeout(self.handle);
     }
 }
@@ -5,6 +5,7 @@
 use devtools_traits::{DevtoolsPageInfo, ScriptToDevtoolsControlMsg};
 use dom::abstractworker::{SharedRt, SimpleWorkerErrorHandler};
 use dom::abstractworker::WorkerScriptMsg;
+use dom::bindings::codegen::Bindings::MessagePortBinding::Transferable;
 use dom::bindings::codegen::Bindings::WorkerBinding;
 use dom::bindings::codegen::Bindings::WorkerBinding::WorkerMethods;
 use dom::bindings::error::{Error, ErrorResult, Fallible};
@@ -24,7 +25,7 @@ use ipc_channel::ipc;
 use js::jsapi::{JSAutoCompartment, JSContext};
 use js::jsval::UndefinedValue;
 use js::rust::HandleValue;
-use script_traits::WorkerScriptLoadOrigin;
+use script_traits::{TransferredPort, WorkerScriptLoadOrigin};
 use std::cell::Cell;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -121,7 +122,8 @@ impl Worker {
     }
 
     pub fn handle_message(address: TrustedWorkerAddress,
-                          data: StructuredCloneData) {
+                          data: StructuredCloneData,
+                          ports: Vec<TransferredPort>) {
         let worker = address.root();
 
         if worker.is_terminated() {
@@ -132,8 +134,8 @@ impl Worker {
         let target = worker.upcast();
         let _ac = JSAutoCompartment::new(global.get_cx(), target.reflector().get_jsobject().get());
         rooted!(in(global.get_cx()) let mut message = UndefinedValue());
-        data.read(&global, message.handle_mut());
-        MessageEvent::dispatch_jsval(target, &global, message.handle());
+        let new_ports = data.read_with_ports(&global, ports, message.handle_mut());
+        MessageEvent::dispatch_jsval(target, &global, message.handle(), None, new_ports);
     }
 
     pub fn dispatch_simple_error(address: TrustedWorkerAddress) {
@@ -145,13 +147,18 @@ impl Worker {
 impl WorkerMethods for Worker {
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-worker-postmessage
-    unsafe fn PostMessage(&self, cx: *mut JSContext, message: HandleValue) -> ErrorResult {
-        let data = StructuredCloneData::write(cx, message)?;
+    unsafe fn PostMessage(&self,
+                          cx: *mut JSContext,
+                          message: HandleValue,
+                          transfer: Option<Vec<Transferable>>)
+                          -> ErrorResult {
+        let transfer = transfer.unwrap_or_default();
+        let (data, ports) = StructuredCloneData::write_with_transfer(cx, message, &transfer)?;
         let address = Trusted::new(self);
 
         // NOTE: step 9 of https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage
         // indicates that a nonexistent communication channel should result in a silent error.
-        let _ = self.sender.send((address, WorkerScriptMsg::DOMMessage(data)));
+        let _ = self.sender.send((address, WorkerScriptMsg::DOMMessage(data, ports)));
         Ok(())
     }
 
@@ -4,6 +4,7 @@
 
 use devtools_traits::{DevtoolScriptControlMsg, WorkerId};
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapOptions, ImageBitmapSource};
 use dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
 use dom::bindings::error::{Error, ErrorResult, Fallible, report_pending_exception};
 use dom::bindings::inheritance::Castable;
@@ -11,12 +12,16 @@ use dom::bindings::reflector::DomObject;
 use dom::bindings::root::{DomRoot, MutNullableDom};
 use dom::bindings::settings_stack::AutoEntryScript;
 use dom::bindings::str::{DOMString, USVString};
+use dom::crypto::Crypto;
 use dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use dom::globalscope::GlobalScope;
+use dom::imagebitmap::ImageBitmap;
 use dom::performance::Performance;
+use dom::promise::Promise;
 use dom::window::{base64_atob, base64_btoa};
 use dom::workerlocation::WorkerLocation;
 use dom_struct::dom_struct;
+use euclid::{Point2D, Rect, Size2D};
 use ipc_channel::ipc::IpcSender;
 use js::jsapi::{JSAutoCompartment, JSContext, JSRuntime};
 use js::jsval::UndefinedValue;
@@ -37,6 +42,7 @@ use std::sync::mpsc::Receiver;
 use task::TaskCanceller;
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
+use task_source::port_message::PortMessageQueue;
 use task_source::remote_event::RemoteEventTaskSource;
 use time::precise_time_ns;
 use timers::{IsInterval, TimerCallback};
@@ -84,6 +90,7 @@ pub struct WorkerGlobalScope {
 
     navigation_start_precise: u64,
     performance: MutNullableDom<Performance>,
+    crypto: MutNullableDom<Crypto>,
 }
 
 impl WorkerGlobalScope {
@@ -117,6 +124,7 @@ impl WorkerGlobalScope {
             from_devtools_receiver,
             navigation_start_precise: precise_time_ns(),
             performance: Default::default(),
+            crypto: Default::default(),
         }
     }
 
@@ -235,6 +243,26 @@ impl WorkerGlobalScopeMethods for WorkerGlobalScope {
         base64_atob(atob)
     }
 
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap(&self, image: ImageBitmapSource, options: &ImageBitmapOptions) -> Rc<Promise> {
+        ImageBitmap::create(self.upcast(), image, None, options)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap_(&self,
+                          image: ImageBitmapSource,
+                          sx: i32,
+                          sy: i32,
+                          sw: i32,
+                          sh: i32,
+                          options: &ImageBitmapOptions)
+                          -> Rc<Promise> {
+        let crop = Rect::new(Point2D::new(sx, sy), Size2D::new(sw, sh));
+        ImageBitmap::create(self.upcast(), image, Some(crop), options)
+    }
+
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-windowtimers-settimeout
     unsafe fn SetTimeout(&self, _cx: *mut JSContext, callback: Rc<Function>,
@@ -289,6 +317,11 @@ impl WorkerGlobalScopeMethods for WorkerGlobalScope {
         self.ClearTimeout(handle);
     }
 
+    // https://w3c.github.io/webcrypto/#dom-globalcrypto-crypto
+    fn Crypto(&self) -> DomRoot<Crypto> {
+        self.crypto.or_init(|| Crypto::new(self.upcast()))
+    }
+
     // https://w3c.github.io/hr-time/#the-performance-attribute
     fn Performance(&self) -> DomRoot<Performance> {
         self.performance.or_init(|| {
@@ -347,6 +380,10 @@ impl WorkerGlobalScope {
         PerformanceTimelineTaskSource(self.script_chan(), self.pipeline_id())
     }
 
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        PortMessageQueue(self.script_chan(), self.pipeline_id())
+    }
+
     pub fn remote_event_task_source(&self) -> RemoteEventTaskSource {
         RemoteEventTaskSource(self.script_chan(), self.pipeline_id())
     }
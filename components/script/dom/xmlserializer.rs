@@ -0,0 +1,46 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::XMLSerializerBinding;
+use dom::bindings::codegen::Bindings::XMLSerializerBinding::XMLSerializerMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::node::Node;
+use dom::servoparser::xml;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use xml5ever::serialize::TraversalScope;
+
+#[dom_struct]
+pub struct XMLSerializer {
+    reflector_: Reflector,
+}
+
+impl XMLSerializer {
+    fn new_inherited() -> XMLSerializer {
+        XMLSerializer {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<XMLSerializer> {
+        reflect_dom_object(Box::new(XMLSerializer::new_inherited()),
+                           window,
+                           XMLSerializerBinding::Wrap)
+    }
+
+    pub fn Constructor(window: &Window) -> Fallible<DomRoot<XMLSerializer>> {
+        Ok(XMLSerializer::new(window))
+    }
+}
+
+impl XMLSerializerMethods for XMLSerializer {
+    // https://w3c.github.io/DOM-Parsing/#dom-xmlserializer-serializetostring
+    fn SerializeToString(&self, root: &Node) -> DOMString {
+        xml::serialize(root, TraversalScope::IncludeNode, false)
+            .expect("XML serialization only fails when well-formedness is required")
+    }
+}
@@ -0,0 +1,79 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::XPathEvaluatorBinding;
+use dom::bindings::codegen::Bindings::XPathEvaluatorBinding::XPathEvaluatorMethods;
+use dom::bindings::codegen::Bindings::XPathExpressionBinding::XPathExpressionMethods;
+use dom::bindings::codegen::Bindings::XPathNSResolverBinding::XPathNSResolver;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::node::Node;
+use dom::window::Window;
+use dom::xpathexpression::XPathExpression;
+use dom::xpathresult::XPathResult;
+use dom_struct::dom_struct;
+use std::rc::Rc;
+
+#[dom_struct]
+pub struct XPathEvaluator {
+    reflector_: Reflector,
+    window: Dom<Window>,
+}
+
+impl XPathEvaluator {
+    fn new_inherited(window: &Window) -> XPathEvaluator {
+        XPathEvaluator {
+            reflector_: Reflector::new(),
+            window: Dom::from_ref(window),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<XPathEvaluator> {
+        reflect_dom_object(Box::new(XPathEvaluator::new_inherited(window)),
+                           window,
+                           XPathEvaluatorBinding::Wrap)
+    }
+
+    pub fn Constructor(window: &Window) -> Fallible<DomRoot<XPathEvaluator>> {
+        Ok(XPathEvaluator::new(window))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-xpathevaluatorbase-evaluate>
+    pub fn evaluate(window: &Window,
+                    expression: DOMString,
+                    context_node: &Node,
+                    resolver: Option<Rc<XPathNSResolver>>,
+                    type_: u16)
+                    -> Fallible<DomRoot<XPathResult>> {
+        XPathExpression::create(window, expression, resolver)?.Evaluate(context_node, type_, None)
+    }
+}
+
+impl XPathEvaluatorMethods for XPathEvaluator {
+    // https://dom.spec.whatwg.org/#dom-xpathevaluatorbase-createexpression
+    fn CreateExpression(&self,
+                        expression: DOMString,
+                        resolver: Option<Rc<XPathNSResolver>>)
+                        -> Fallible<DomRoot<XPathExpression>> {
+        XPathExpression::create(&self.window, expression, resolver)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathevaluatorbase-creatensresolver
+    fn CreateNSResolver(&self, node_resolver: &Node) -> DomRoot<Node> {
+        DomRoot::from_ref(node_resolver)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathevaluatorbase-evaluate
+    fn Evaluate(&self,
+                expression: DOMString,
+                context_node: &Node,
+                resolver: Option<Rc<XPathNSResolver>>,
+                type_: u16,
+                _result: Option<&XPathResult>)
+                -> Fallible<DomRoot<XPathResult>> {
+        XPathEvaluator::evaluate(&self.window, expression, context_node, resolver, type_)
+    }
+}
@@ -0,0 +1,91 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::callback::ExceptionHandling::Rethrow;
+use dom::bindings::codegen::Bindings::XPathExpressionBinding;
+use dom::bindings::codegen::Bindings::XPathExpressionBinding::XPathExpressionMethods;
+use dom::bindings::codegen::Bindings::XPathNSResolverBinding::XPathNSResolver;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::bindings::xmlname::namespace_from_domstring;
+use dom::node::Node;
+use dom::window::Window;
+use dom::xpathresult::XPathResult;
+use dom_struct::dom_struct;
+use html5ever::Namespace;
+use std::collections::HashMap;
+use std::rc::Rc;
+use xpath::{self, EvaluationError, Expr};
+
+#[dom_struct]
+pub struct XPathExpression {
+    reflector_: Reflector,
+    window: Dom<Window>,
+    expr: Expr,
+    /// The namespaces of the prefixes used by the expression, as given by the
+    /// resolver when the expression was created.
+    namespaces: HashMap<String, Namespace>,
+}
+
+impl XPathExpression {
+    fn new_inherited(window: &Window, expr: Expr, namespaces: HashMap<String, Namespace>) -> XPathExpression {
+        XPathExpression {
+            reflector_: Reflector::new(),
+            window: Dom::from_ref(window),
+            expr,
+            namespaces,
+        }
+    }
+
+    /// Parse an expression and resolve the namespace prefixes it uses.
+    ///
+    /// <https://dom.spec.whatwg.org/#dom-xpathevaluatorbase-createexpression>
+    pub fn create(window: &Window,
+                  expression: DOMString,
+                  resolver: Option<Rc<XPathNSResolver>>)
+                  -> Fallible<DomRoot<XPathExpression>> {
+        let expr = xpath::parse(&expression).map_err(|error| {
+            debug!("Invalid XPath expression {:?}: {:?}", expression, error);
+            Error::Syntax
+        })?;
+
+        let mut namespaces = HashMap::new();
+        for prefix in expr.prefixes() {
+            let namespace = match resolver {
+                Some(ref resolver) => resolver.LookupNamespaceURI__(Some(DOMString::from(&*prefix)), Rethrow)?,
+                None => None,
+            };
+            match namespace_from_domstring(namespace) {
+                ns!() => return Err(Error::Namespace),
+                namespace => {
+                    namespaces.insert(prefix, namespace);
+                },
+            }
+        }
+
+        Ok(reflect_dom_object(Box::new(XPathExpression::new_inherited(window, expr, namespaces)),
+                              window,
+                              XPathExpressionBinding::Wrap))
+    }
+}
+
+impl XPathExpressionMethods for XPathExpression {
+    // https://dom.spec.whatwg.org/#dom-xpathexpression-evaluate
+    fn Evaluate(&self,
+                context_node: &Node,
+                type_: u16,
+                _result: Option<&XPathResult>)
+                -> Fallible<DomRoot<XPathResult>> {
+        let value = xpath::evaluate(&self.expr, context_node, &self.namespaces).map_err(|error| {
+            match error {
+                EvaluationError::UnresolvedPrefix(_) => Error::Namespace,
+                error => Error::Type(format!("Cannot evaluate XPath expression: {:?}", error)),
+            }
+        })?;
+        // Results are never reused, which the specification allows.
+        XPathResult::new(&self.window, context_node, type_, value)
+    }
+}
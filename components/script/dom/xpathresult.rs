@@ -0,0 +1,205 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::XPathResultBinding;
+use dom::bindings::codegen::Bindings::XPathResultBinding::XPathResultConstants;
+use dom::bindings::codegen::Bindings::XPathResultBinding::XPathResultMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::document::Document;
+use dom::node::Node;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+use xpath::{Value, XPathNode};
+
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+enum XPathResultValue {
+    Boolean(bool),
+    Number(f64),
+    String(DOMString),
+    Nodes(Vec<Dom<Node>>),
+}
+
+#[dom_struct]
+pub struct XPathResult {
+    reflector_: Reflector,
+    result_type: u16,
+    value: XPathResultValue,
+    /// The index of the next node returned by `iterateNext`.
+    iterator_index: Cell<usize>,
+    /// The document of the context node, whose mutations invalidate iterators.
+    document: Dom<Document>,
+    document_version: u64,
+}
+
+impl XPathResult {
+    fn new_inherited(document: &Document, result_type: u16, value: XPathResultValue) -> XPathResult {
+        XPathResult {
+            reflector_: Reflector::new(),
+            result_type,
+            value,
+            iterator_index: Cell::new(0),
+            document: Dom::from_ref(document),
+            document_version: document.upcast::<Node>().inclusive_descendants_version(),
+        }
+    }
+
+    /// Convert the value of an expression to the requested result type.
+    ///
+    /// <https://dom.spec.whatwg.org/#dom-xpathexpression-evaluate>
+    #[allow(unrooted_must_root)]
+    pub fn new(window: &Window,
+               context_node: &Node,
+               result_type: u16,
+               value: Value)
+               -> Fallible<DomRoot<XPathResult>> {
+        let result_type = match (result_type, &value) {
+            (XPathResultConstants::ANY_TYPE, &Value::Boolean(_)) => XPathResultConstants::BOOLEAN_TYPE,
+            (XPathResultConstants::ANY_TYPE, &Value::Number(_)) => XPathResultConstants::NUMBER_TYPE,
+            (XPathResultConstants::ANY_TYPE, &Value::String(_)) => XPathResultConstants::STRING_TYPE,
+            (XPathResultConstants::ANY_TYPE, &Value::Nodes(_)) => {
+                XPathResultConstants::UNORDERED_NODE_ITERATOR_TYPE
+            },
+            (result_type, _) if result_type > XPathResultConstants::FIRST_ORDERED_NODE_TYPE => {
+                return Err(Error::NotSupported);
+            },
+            (result_type, _) => result_type,
+        };
+
+        let value = match result_type {
+            XPathResultConstants::BOOLEAN_TYPE => XPathResultValue::Boolean(value.boolean()),
+            XPathResultConstants::NUMBER_TYPE => XPathResultValue::Number(value.number()),
+            XPathResultConstants::STRING_TYPE => XPathResultValue::String(DOMString::from(value.string())),
+            _ => {
+                let nodes = match value {
+                    Value::Nodes(nodes) => nodes,
+                    _ => return Err(Error::Type(String::from("The expression does not evaluate to a node-set"))),
+                };
+                let mut result = vec![];
+                for node in nodes {
+                    match node {
+                        XPathNode::Node(node) => result.push(Dom::from_ref(&*node)),
+                        // Attributes are not nodes in the DOM, so they can't
+                        // be returned as such.
+                        XPathNode::Attribute(_) => return Err(Error::NotSupported),
+                    }
+                }
+                if result_type == XPathResultConstants::ANY_UNORDERED_NODE_TYPE ||
+                    result_type == XPathResultConstants::FIRST_ORDERED_NODE_TYPE {
+                    result.truncate(1);
+                }
+                XPathResultValue::Nodes(result)
+            },
+        };
+
+        let document = context_node.owner_doc();
+        Ok(reflect_dom_object(Box::new(XPathResult::new_inherited(&document, result_type, value)),
+                              window,
+                              XPathResultBinding::Wrap))
+    }
+
+    fn is_iterator(&self) -> bool {
+        self.result_type == XPathResultConstants::UNORDERED_NODE_ITERATOR_TYPE ||
+            self.result_type == XPathResultConstants::ORDERED_NODE_ITERATOR_TYPE
+    }
+
+    fn is_snapshot(&self) -> bool {
+        self.result_type == XPathResultConstants::UNORDERED_NODE_SNAPSHOT_TYPE ||
+            self.result_type == XPathResultConstants::ORDERED_NODE_SNAPSHOT_TYPE
+    }
+
+    fn nodes(&self) -> &[Dom<Node>] {
+        match self.value {
+            XPathResultValue::Nodes(ref nodes) => nodes,
+            _ => &[],
+        }
+    }
+
+    fn wrong_type() -> Error {
+        Error::Type(String::from("The result type does not allow this operation"))
+    }
+}
+
+impl XPathResultMethods for XPathResult {
+    // https://dom.spec.whatwg.org/#dom-xpathresult-resulttype
+    fn ResultType(&self) -> u16 {
+        self.result_type
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathresult-numbervalue
+    fn GetNumberValue(&self) -> Fallible<f64> {
+        match self.value {
+            XPathResultValue::Number(value) => Ok(value),
+            _ => Err(XPathResult::wrong_type()),
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathresult-stringvalue
+    fn GetStringValue(&self) -> Fallible<DOMString> {
+        match self.value {
+            XPathResultValue::String(ref value) => Ok(value.clone()),
+            _ => Err(XPathResult::wrong_type()),
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathresult-booleanvalue
+    fn GetBooleanValue(&self) -> Fallible<bool> {
+        match self.value {
+            XPathResultValue::Boolean(value) => Ok(value),
+            _ => Err(XPathResult::wrong_type()),
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathresult-singlenodevalue
+    fn GetSingleNodeValue(&self) -> Fallible<Option<DomRoot<Node>>> {
+        if self.result_type != XPathResultConstants::ANY_UNORDERED_NODE_TYPE &&
+            self.result_type != XPathResultConstants::FIRST_ORDERED_NODE_TYPE {
+            return Err(XPathResult::wrong_type());
+        }
+        Ok(self.nodes().first().map(|node| DomRoot::from_ref(&**node)))
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathresult-invaliditeratorstate
+    fn InvalidIteratorState(&self) -> bool {
+        self.is_iterator() &&
+            self.document.upcast::<Node>().inclusive_descendants_version() != self.document_version
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathresult-snapshotlength
+    fn GetSnapshotLength(&self) -> Fallible<u32> {
+        if !self.is_snapshot() {
+            return Err(XPathResult::wrong_type());
+        }
+        Ok(self.nodes().len() as u32)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathresult-iteratenext
+    fn IterateNext(&self) -> Fallible<Option<DomRoot<Node>>> {
+        if !self.is_iterator() {
+            return Err(XPathResult::wrong_type());
+        }
+        if self.InvalidIteratorState() {
+            return Err(Error::InvalidState);
+        }
+        let index = self.iterator_index.get();
+        let node = self.nodes().get(index).map(|node| DomRoot::from_ref(&**node));
+        if node.is_some() {
+            self.iterator_index.set(index + 1);
+        }
+        Ok(node)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-xpathresult-snapshotitem
+    fn SnapshotItem(&self, index: u32) -> Fallible<Option<DomRoot<Node>>> {
+        if !self.is_snapshot() {
+            return Err(XPathResult::wrong_type());
+        }
+        Ok(self.nodes().get(index as usize).map(|node| DomRoot::from_ref(&**node)))
+    }
+}
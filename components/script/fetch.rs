@@ -2,7 +2,11 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use ipc_channel::ipc;
+use dom::abortsignal::{AbortAlgorithm, AbortSignal};
+use dom::globalscope::GlobalScope;
+use ipc_channel::ipc::{self, IpcSender};
+use net_traits::{CoreResourceMsg, FetchChannels, FetchResponseMsg};
+use net_traits::request::RequestInit;
 
 /// RAII fetch canceller object. By default initialized to not having a canceller
 /// in it, however you can ask it for a cancellation receiver to send to Fetch
@@ -55,3 +59,25 @@ impl Drop for FetchCanceller {
         self.cancel()
     }
 }
+
+/// Initiate a fetch that is cancelled when the given signal is aborted.
+///
+/// Returns `false` without starting the fetch if the signal is already
+/// aborted, in which case the caller should abort the fetch right away.
+///
+/// <https://fetch.spec.whatwg.org/#dom-global-fetch>
+pub fn fetch_with_signal(global: &GlobalScope,
+                         request: RequestInit,
+                         fetch_target: IpcSender<FetchResponseMsg>,
+                         signal: &AbortSignal)
+                         -> bool {
+    if signal.aborted() {
+        return false;
+    }
+    let mut canceller = FetchCanceller::new();
+    let cancel_receiver = canceller.initialize();
+    signal.add_abort_algorithm(AbortAlgorithm::Fetch(canceller));
+    global.core_resource_thread().send(
+        CoreResourceMsg::Fetch(request, FetchChannels::ResponseMsg(fetch_target, Some(cancel_receiver)))).unwrap();
+    true
+}
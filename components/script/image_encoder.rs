@@ -0,0 +1,354 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Serialization of canvas bitmaps to image files, as used by `toDataURL`,
+//! `toBlob` and `convertToBlob`.
+//!
+//! <https://html.spec.whatwg.org/multipage/#a-serialisation-of-the-bitmap-as-a-file>
+
+use canvas_traits::canvas::byte_swap;
+use euclid::Size2D;
+use image::ColorType;
+use image::bmp::BMPEncoder;
+use image::jpeg::JPEGEncoder;
+use image::png::PNGEncoder;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use unpremultiplytable::UNPREMULTIPLY_TABLE;
+
+/// The quality used for JPEG images when none, or an invalid one, is given.
+const DEFAULT_JPEG_QUALITY: f64 = 0.92;
+
+/// The largest width and height of a WebP image.
+const MAX_WEBP_SIZE: u32 = 1 << 14;
+
+/// The file formats a bitmap can be serialized to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageType {
+    Png,
+    Jpeg,
+    Webp,
+    Bmp,
+}
+
+impl ImageType {
+    /// Returns the format to use for `mime_type` and a bitmap of the given
+    /// size, which is PNG when the format is unsupported or cannot hold a
+    /// bitmap that large.
+    pub fn new(mime_type: &str, size: Size2D<u32>) -> ImageType {
+        match &*mime_type.to_ascii_lowercase() {
+            "image/jpeg" => ImageType::Jpeg,
+            "image/webp" if size.width <= MAX_WEBP_SIZE && size.height <= MAX_WEBP_SIZE => ImageType::Webp,
+            "image/bmp" => ImageType::Bmp,
+            _ => ImageType::Png,
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match *self {
+            ImageType::Png => "image/png",
+            ImageType::Jpeg => "image/jpeg",
+            ImageType::Webp => "image/webp",
+            ImageType::Bmp => "image/bmp",
+        }
+    }
+
+    /// Encodes premultiplied BGRA pixels, as sent by the canvas paint thread.
+    /// `quality` is only used by lossy formats, and ignored unless it is
+    /// between 0 and 1.
+    pub fn encode(&self, mut data: Vec<u8>, size: Size2D<u32>, quality: Option<f64>) -> Vec<u8> {
+        byte_swap(&mut data);
+        let mut encoded = vec![];
+        match *self {
+            ImageType::Png => {
+                unpremultiply(&mut data);
+                PNGEncoder::new(&mut encoded)
+                    .encode(&data, size.width, size.height, ColorType::RGBA(8))
+                    .unwrap();
+            },
+            // Formats without an alpha channel drop it, premultiplied pixels
+            // being what compositing them onto a black background gives.
+            ImageType::Jpeg => {
+                let quality = quality.filter(|quality| *quality >= 0. && *quality <= 1.)
+                    .unwrap_or(DEFAULT_JPEG_QUALITY);
+                let rgb: Vec<u8> = data.chunks(4).flat_map(|pixel| pixel[..3].iter().cloned()).collect();
+                JPEGEncoder::new_with_quality(&mut encoded, (quality * 100.).round().max(1.) as u8)
+                    .encode(&rgb, size.width, size.height, ColorType::RGB(8))
+                    .unwrap();
+            },
+            ImageType::Bmp => {
+                unpremultiply(&mut data);
+                BMPEncoder::new(&mut encoded)
+                    .encode(&data, size.width, size.height, ColorType::RGBA(8))
+                    .unwrap();
+            },
+            ImageType::Webp => {
+                unpremultiply(&mut data);
+                encoded = encode_webp(&data, size);
+            },
+        }
+        encoded
+    }
+}
+
+fn unpremultiply(data: &mut [u8]) {
+    for chunk in data.chunks_mut(4) {
+        let alpha = chunk[3] as usize;
+        chunk[0] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[0] as usize];
+        chunk[1] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[1] as usize];
+        chunk[2] = UNPREMULTIPLY_TABLE[256 * alpha + chunk[2] as usize];
+    }
+}
+
+/// The order in which the lengths of the code length code are written.
+const CODE_LENGTH_CODE_ORDER: [usize; 19] = [17, 18, 0, 1, 2, 3, 4, 5, 16, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+const SUBTRACT_GREEN_TRANSFORM: u32 = 2;
+
+/// Encodes non-premultiplied RGBA pixels as a lossless WebP image. Only the
+/// subtract green transform and entropy coding are used, without backward
+/// references.
+///
+/// <https://developers.google.com/speed/webp/docs/webp_lossless_bitstream_specification>
+fn encode_webp(data: &[u8], size: Size2D<u32>) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+
+    // The header: signature, size, whether alpha is used, and version.
+    writer.write_bits(0x2f, 8);
+    writer.write_bits(size.width - 1, 14);
+    writer.write_bits(size.height - 1, 14);
+    writer.write_bits(data.chunks(4).any(|pixel| pixel[3] != 0xff) as u32, 1);
+    writer.write_bits(0, 3);
+
+    writer.write_bits(1, 1);
+    writer.write_bits(SUBTRACT_GREEN_TRANSFORM, 2);
+    writer.write_bits(0, 1);
+
+    // The green, red, blue and alpha symbols of each pixel, in the order they
+    // are written.
+    let pixels: Vec<[u8; 4]> = data.chunks(4).map(|pixel| {
+        [pixel[1], pixel[0].wrapping_sub(pixel[1]), pixel[2].wrapping_sub(pixel[1]), pixel[3]]
+    }).collect();
+
+    // Neither a color cache nor meta prefix codes.
+    writer.write_bits(0, 1);
+    writer.write_bits(0, 1);
+
+    // The green alphabet also has the 24 length prefixes of backward
+    // references, which are never used.
+    let mut histograms = [vec![0; 256 + 24], vec![0; 256], vec![0; 256], vec![0; 256]];
+    for pixel in &pixels {
+        for (histogram, &symbol) in histograms.iter_mut().zip(pixel.iter()) {
+            histogram[symbol as usize] += 1;
+        }
+    }
+    let codes: Vec<_> = histograms.iter().map(|histogram| PrefixCode::new(histogram)).collect();
+    for code in &codes {
+        code.write(&mut writer);
+    }
+    PrefixCode::new(&[0; 40]).write(&mut writer);
+
+    for pixel in &pixels {
+        for (code, &symbol) in codes.iter().zip(pixel.iter()) {
+            code.write_symbol(&mut writer, symbol as usize);
+        }
+    }
+
+    let bitstream = writer.finish();
+    let padding = bitstream.len() % 2;
+    let mut encoded = Vec::with_capacity(20 + bitstream.len() + padding);
+    encoded.extend_from_slice(b"RIFF");
+    encoded.extend_from_slice(&u32_to_le_bytes((12 + bitstream.len() + padding) as u32));
+    encoded.extend_from_slice(b"WEBPVP8L");
+    encoded.extend_from_slice(&u32_to_le_bytes(bitstream.len() as u32));
+    encoded.extend_from_slice(&bitstream);
+    if padding != 0 {
+        encoded.push(0);
+    }
+    encoded
+}
+
+fn u32_to_le_bytes(value: u32) -> [u8; 4] {
+    [value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+}
+
+/// Writes values least significant bit first, as WebP reads them.
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    used: u32,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter { bytes: vec![], buffer: 0, used: 0 }
+    }
+
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.buffer |= (value as u64) << self.used;
+        self.used += count;
+        while self.used >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.used -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+enum PrefixCode {
+    /// A code for one or two symbols lower than 256, which take zero or one
+    /// bit respectively.
+    Simple(Vec<usize>),
+    /// A canonical prefix code, with the length and code of each symbol.
+    Normal(Vec<u32>, Vec<u32>),
+}
+
+impl PrefixCode {
+    fn new(histogram: &[u32]) -> PrefixCode {
+        let used: Vec<_> = (0..histogram.len()).filter(|&symbol| histogram[symbol] != 0).collect();
+        if used.len() <= 2 && used.iter().all(|&symbol| symbol < 256) {
+            return PrefixCode::Simple(used);
+        }
+        let lengths = huffman_lengths(histogram, 15);
+        let codes = canonical_codes(&lengths);
+        PrefixCode::Normal(lengths, codes)
+    }
+
+    fn write(&self, writer: &mut BitWriter) {
+        match *self {
+            PrefixCode::Simple(ref symbols) => {
+                writer.write_bits(1, 1);
+                let first = symbols.get(0).cloned().unwrap_or(0);
+                writer.write_bits(symbols.len().saturating_sub(1) as u32, 1);
+                if first < 2 {
+                    writer.write_bits(0, 1);
+                    writer.write_bits(first as u32, 1);
+                } else {
+                    writer.write_bits(1, 1);
+                    writer.write_bits(first as u32, 8);
+                }
+                if let Some(&second) = symbols.get(1) {
+                    writer.write_bits(second as u32, 8);
+                }
+            },
+            PrefixCode::Normal(ref lengths, _) => {
+                writer.write_bits(0, 1);
+
+                // The lengths are themselves written with a prefix code.
+                let mut histogram = [0; 19];
+                for &length in lengths {
+                    histogram[length as usize] += 1;
+                }
+                let code_lengths = huffman_lengths(&histogram, 7);
+                let code_length_code = PrefixCode::Normal(code_lengths.clone(), canonical_codes(&code_lengths));
+                let count = CODE_LENGTH_CODE_ORDER.iter()
+                    .rposition(|&symbol| code_lengths[symbol] != 0)
+                    .map_or(4, |index| (index + 1).max(4));
+                writer.write_bits(count as u32 - 4, 4);
+                for &symbol in &CODE_LENGTH_CODE_ORDER[..count] {
+                    writer.write_bits(code_lengths[symbol], 3);
+                }
+
+                // The lengths of every symbol of the alphabet follow.
+                writer.write_bits(0, 1);
+                for &length in lengths {
+                    code_length_code.write_symbol(writer, length as usize);
+                }
+            },
+        }
+    }
+
+    fn write_symbol(&self, writer: &mut BitWriter, symbol: usize) {
+        match *self {
+            PrefixCode::Simple(ref symbols) => {
+                if symbols.len() == 2 {
+                    writer.write_bits((symbols[1] == symbol) as u32, 1);
+                }
+            },
+            // Codes are read one bit at a time from their most significant
+            // bit, so they are written reversed.
+            PrefixCode::Normal(ref lengths, ref codes) => {
+                let length = lengths[symbol];
+                let reversed = (0..length).fold(0, |reversed, bit| (reversed << 1) | (codes[symbol] >> bit) & 1);
+                writer.write_bits(reversed, length);
+            },
+        }
+    }
+}
+
+/// Returns the length of the code of each symbol of a Huffman code for the
+/// given histogram, none of them being longer than `max_length`. At least two
+/// symbols get a code, so that the code is complete.
+fn huffman_lengths(histogram: &[u32], max_length: u32) -> Vec<u32> {
+    let mut symbols: Vec<_> = (0..histogram.len()).filter(|&symbol| histogram[symbol] != 0).collect();
+    for symbol in 0..2 {
+        if symbols.len() < 2 && !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+
+    // Small counts get rounded up until the tree is shallow enough.
+    let mut min_count = 1;
+    loop {
+        // The leaves come first and each node comes before its parent, the
+        // root being the last one.
+        let mut parents = vec![0; symbols.len()];
+        let mut heap: BinaryHeap<_> = symbols.iter().enumerate().map(|(node, &symbol)| {
+            Reverse((histogram[symbol].max(min_count) as u64, node))
+        }).collect();
+        while heap.len() > 1 {
+            let Reverse((first_weight, first)) = heap.pop().unwrap();
+            let Reverse((second_weight, second)) = heap.pop().unwrap();
+            let node = parents.len();
+            parents[first] = node;
+            parents[second] = node;
+            parents.push(0);
+            heap.push(Reverse((first_weight + second_weight, node)));
+        }
+
+        let mut depths = vec![0; parents.len()];
+        for node in (0..parents.len() - 1).rev() {
+            depths[node] = depths[parents[node]] + 1;
+        }
+        if depths[..symbols.len()].iter().all(|&depth| depth <= max_length) {
+            let mut lengths = vec![0; histogram.len()];
+            for (node, &symbol) in symbols.iter().enumerate() {
+                lengths[symbol] = depths[node];
+            }
+            return lengths;
+        }
+        min_count *= 2;
+    }
+}
+
+/// Assigns codes to symbols with the given lengths, shorter codes coming
+/// first and symbols with codes of the same length being in order.
+fn canonical_codes(lengths: &[u32]) -> Vec<u32> {
+    let max_length = lengths.iter().cloned().max().unwrap_or(0) as usize;
+    let mut length_counts = vec![0; max_length + 1];
+    for &length in lengths.iter().filter(|&&length| length != 0) {
+        length_counts[length as usize] += 1;
+    }
+    let mut next_codes = vec![0; max_length + 1];
+    let mut code = 0;
+    for length in 1..max_length + 1 {
+        code = (code + length_counts[length - 1]) << 1;
+        next_codes[length] = code;
+    }
+    lengths.iter().map(|&length| {
+        if length == 0 {
+            return 0;
+        }
+        let code = next_codes[length as usize];
+        next_codes[length as usize] += 1;
+        code
+    }).collect()
+}
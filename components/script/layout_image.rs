@@ -7,14 +7,18 @@
 //! no guarantee that the responsible nodes will still exist in the future if the
 //! layout thread holds on to them during asynchronous operations.
 
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::DomObject;
+use dom::document::Document;
 use dom::node::{Node, document_from_node};
+use dom::performanceresourcetiming::InitiatorType;
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use net_traits::{FetchResponseMsg, FetchResponseListener, FetchMetadata, NetworkError};
 use net_traits::image_cache::{ImageCache, PendingImageId};
 use net_traits::request::{Destination, RequestInit as FetchRequestInit};
-use network_listener::{NetworkListener, PreInvoke};
+use network_listener::{NetworkListener, PreInvoke, ResourceTimingRecorder};
 use servo_url::ServoUrl;
 use std::sync::{Arc, Mutex};
 use task_source::TaskSourceName;
@@ -22,24 +26,29 @@ use task_source::TaskSourceName;
 struct LayoutImageContext {
     id: PendingImageId,
     cache: Arc<ImageCache>,
+    document: Trusted<Document>,
+    resource_timing: ResourceTimingRecorder,
 }
 
 impl FetchResponseListener for LayoutImageContext {
     fn process_request_body(&mut self) {}
     fn process_request_eof(&mut self) {}
     fn process_response(&mut self, metadata: Result<FetchMetadata, NetworkError>) {
+        self.resource_timing.record_response(&metadata);
         self.cache.notify_pending_response(
             self.id,
             FetchResponseMsg::ProcessResponse(metadata));
     }
 
     fn process_response_chunk(&mut self, payload: Vec<u8>) {
+        self.resource_timing.record_chunk(&payload);
         self.cache.notify_pending_response(
             self.id,
             FetchResponseMsg::ProcessResponseChunk(payload));
     }
 
     fn process_response_eof(&mut self, response: Result<(), NetworkError>) {
+        self.resource_timing.submit(self.document.root().window().upcast());
         self.cache.notify_pending_response(self.id,
                                            FetchResponseMsg::ProcessResponseEOF(response));
     }
@@ -51,14 +60,17 @@ pub fn fetch_image_for_layout(url: ServoUrl,
                               node: &Node,
                               id: PendingImageId,
                               cache: Arc<ImageCache>) {
+    let document = document_from_node(node);
+    let window = document.window();
+
     let context = Arc::new(Mutex::new(LayoutImageContext {
         id: id,
         cache: cache,
+        document: Trusted::new(&*document),
+        // Images needed by layout are the ones referenced from stylesheets.
+        resource_timing: ResourceTimingRecorder::new(url.clone(), InitiatorType::Css),
     }));
 
-    let document = document_from_node(node);
-    let window = document.window();
-
     let (action_sender, action_receiver) = ipc::channel().unwrap();
     let listener = NetworkListener {
         context: context,
@@ -62,10 +62,14 @@ extern crate mozjs as js;
 extern crate msg;
 extern crate net_traits;
 extern crate num_traits;
+extern crate offscreen_gl_context;
+extern crate openssl;
+extern crate openssl_sys;
 extern crate parking_lot;
 extern crate phf;
 #[macro_use]
 extern crate profile_traits;
+extern crate rayon;
 extern crate ref_filter_map;
 extern crate ref_slice;
 extern crate script_layout_interface;
@@ -84,6 +88,7 @@ extern crate smallvec;
 #[macro_use]
 extern crate style;
 extern crate style_traits;
+extern crate svg_renderer;
 extern crate swapper;
 extern crate time;
 #[cfg(target_os = "linux")]
@@ -102,6 +107,7 @@ pub mod document_loader;
 #[macro_use]
 mod dom;
 pub mod fetch;
+mod image_encoder;
 mod layout_image;
 mod mem;
 mod microtask;
@@ -115,7 +121,9 @@ pub mod test;
 pub mod textinput;
 mod timers;
 mod unpremultiplytable;
+mod webcrypto;
 mod webdriver_handlers;
+mod xpath;
 
 /// A module with everything layout can use from script.
 ///
@@ -129,8 +137,10 @@ pub mod layout_exports {
     pub use dom::characterdata::LayoutCharacterDataHelpers;
     pub use dom::document::{Document, LayoutDocumentHelpers, PendingRestyle};
     pub use dom::element::{Element, LayoutElementHelpers, RawLayoutElementHelpers};
+    pub use dom::htmlslotelement::{HTMLSlotElement, LayoutHTMLSlotElementHelpers};
     pub use dom::node::NodeFlags;
     pub use dom::node::{LayoutNodeHelpers, Node};
+    pub use dom::shadowroot::{LayoutShadowRootHelpers, ShadowRoot};
     pub use dom::text::Text;
 }
 
@@ -2,11 +2,19 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use net_traits::{Action, FetchResponseListener, FetchResponseMsg};
+use dom::bindings::inheritance::Castable;
+use dom::globalscope::GlobalScope;
+use dom::performanceentry::PerformanceEntry;
+use dom::performanceresourcetiming::{InitiatorType, PerformanceResourceTiming};
+use net_traits::{Action, FetchMetadata, FetchResponseListener, FetchResponseMsg};
+use net_traits::{FilteredMetadata, NetworkError, ResourceFetchTiming};
+use servo_url::{ImmutableOrigin, ServoUrl};
+use std::str;
 use std::sync::{Arc, Mutex};
 use task::{TaskCanceller, TaskOnce};
 use task_source::TaskSource;
 use task_source::networking::NetworkingTaskSource;
+use time::precise_time_ns;
 
 /// An off-thread sink for async network event tasks. All such events are forwarded to
 /// a target thread, where they are invoked on the provided context object.
@@ -67,3 +75,119 @@ where
         }
     }
 }
+
+/// Records the timing of a fetch made on behalf of a `FetchResponseListener`,
+/// to be reported as a [resource timing](https://w3c.github.io/resource-timing/)
+/// entry once the response has been fully received.
+#[derive(JSTraceable)]
+pub struct ResourceTimingRecorder {
+    url: ServoUrl,
+    initiator_type: InitiatorType,
+    timing: ResourceFetchTiming,
+    /// The final URL of the response, if one was received.
+    response_url: Option<ServoUrl>,
+    /// The origins listed in the `Timing-Allow-Origin` header of the response.
+    timing_allow_origins: Vec<String>,
+    /// Whether the response is opaque, in which case its sizes may not be
+    /// exposed even if it passes the timing allow check.
+    opaque: bool,
+}
+
+impl ResourceTimingRecorder {
+    pub fn new(url: ServoUrl, initiator_type: InitiatorType) -> ResourceTimingRecorder {
+        ResourceTimingRecorder {
+            url: url,
+            initiator_type: initiator_type,
+            timing: ResourceFetchTiming {
+                fetch_start: precise_time_ns(),
+                ..ResourceFetchTiming::default()
+            },
+            response_url: None,
+            timing_allow_origins: vec![],
+            opaque: false,
+        }
+    }
+
+    /// Take the timings measured by the network stack from the response metadata.
+    pub fn record_response(&mut self, metadata: &Result<FetchMetadata, NetworkError>) {
+        // The timing allow check looks at the internal response, whatever
+        // the filter script gets to see.
+        let metadata = match *metadata {
+            Ok(FetchMetadata::Unfiltered(ref metadata)) => metadata,
+            Ok(FetchMetadata::Filtered { ref filtered, ref unsafe_ }) => {
+                match *filtered {
+                    FilteredMetadata::Basic(_) | FilteredMetadata::Cors(_) => {},
+                    FilteredMetadata::Opaque | FilteredMetadata::OpaqueRedirect => self.opaque = true,
+                }
+                unsafe_
+            },
+            Err(_) => return,
+        };
+        self.response_url = Some(metadata.final_url.clone());
+        if let Some(ref headers) = metadata.headers {
+            if let Some(values) = headers.get_raw("Timing-Allow-Origin") {
+                self.timing_allow_origins = values.iter()
+                    .filter_map(|value| str::from_utf8(value).ok())
+                    .flat_map(|value| value.split(','))
+                    .map(|origin| origin.trim().to_owned())
+                    .collect();
+            }
+        }
+        if let Some(timing) = metadata.timing {
+            self.timing = ResourceFetchTiming {
+                fetch_start: self.timing.fetch_start,
+                ..timing
+            };
+        }
+    }
+
+    pub fn record_chunk(&mut self, chunk: &[u8]) {
+        self.timing.decoded_body_size += chunk.len() as u64;
+    }
+
+    /// Mark the end of the response, and return everything recorded about it.
+    pub fn finish(&mut self) -> ResourceFetchTiming {
+        self.timing.response_end = precise_time_ns();
+        // Without a Content-Length header, the network stack could only account
+        // for the response headers, and the body size is only known now.
+        if self.timing.encoded_body_size == 0 && self.timing.transfer_size != 0 {
+            self.timing.encoded_body_size = self.timing.decoded_body_size;
+            self.timing.transfer_size += self.timing.decoded_body_size;
+        }
+        self.timing
+    }
+
+    /// <https://w3c.github.io/resource-timing/#dfn-timing-allow-check>
+    fn timing_allow_check(&self, origin: &ImmutableOrigin) -> bool {
+        let response_url = match self.response_url {
+            Some(ref url) => url,
+            None => return false,
+        };
+        if response_url.origin() == *origin {
+            return true;
+        }
+        let serialized_origin = origin.ascii_serialization();
+        self.timing_allow_origins.iter().any(|allowed| *allowed == "*" || *allowed == serialized_origin)
+    }
+
+    /// Queue a `PerformanceResourceTiming` entry for this fetch on the given global.
+    pub fn submit(&mut self, global: &GlobalScope) {
+        let mut timing = self.finish();
+        if !self.timing_allow_check(global.origin().immutable()) {
+            // Only the start and end of a fetch that fails the timing allow
+            // check are exposed.
+            timing = ResourceFetchTiming {
+                fetch_start: timing.fetch_start,
+                response_end: timing.response_end,
+                ..ResourceFetchTiming::default()
+            };
+        } else if self.opaque {
+            timing.transfer_size = 0;
+            timing.encoded_body_size = 0;
+            timing.decoded_body_size = 0;
+        }
+        let entry = PerformanceResourceTiming::new(global, &self.url, self.initiator_type, &timing);
+        global.performance().queue_entry(entry.upcast::<PerformanceEntry>(),
+                                         true /* buffer performance entry */);
+    }
+}
@@ -97,6 +97,9 @@ pub enum ScriptThreadEventCategory {
     ExitFullscreen,
     WebVREvent,
     PerformanceTimelineTask,
+    PortMessage,
+    IntersectionObserverTask,
+    IdleTask,
 }
 
 /// An interface for receiving ScriptMsg values in an event loop. Used for synchronous DOM
@@ -19,6 +19,7 @@
 
 extern crate itertools;
 
+use canvas_traits::webgl::WebGLPipeline;
 use devtools;
 use devtools_traits::{DevtoolScriptControlMsg, DevtoolsPageInfo};
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
@@ -46,6 +47,9 @@ use dom::event::{Event, EventBubbles, EventCancelable};
 use dom::globalscope::GlobalScope;
 use dom::htmlanchorelement::HTMLAnchorElement;
 use dom::htmliframeelement::{HTMLIFrameElement, NavigationType};
+use dom::htmlslotelement::HTMLSlotElement;
+use dom::largestcontentfulpaint::LargestContentfulPaint;
+use dom::layoutshift::LayoutShift;
 use dom::mutationobserver::MutationObserver;
 use dom::node::{Node, NodeDamage, window_from_node, from_untrusted_node_address};
 use dom::performanceentry::PerformanceEntry;
@@ -71,7 +75,7 @@ use js::glue::GetWindowProxyClass;
 use js::jsapi::{JSAutoCompartment, JSContext, JS_SetWrapObjectCallbacks};
 use js::jsapi::{JSTracer, SetWindowProxyClass};
 use js::jsval::UndefinedValue;
-use metrics::{MAX_TASK_NS, PaintTimeMetrics};
+use metrics::{MAX_TASK_NS, PaintTimeMetrics, ToMs};
 use microtask::{MicrotaskQueue, Microtask};
 use msg::constellation_msg::{BrowsingContextId, HistoryStateId, PipelineId};
 use msg::constellation_msg::{PipelineNamespace, TopLevelBrowsingContextId};
@@ -90,7 +94,7 @@ use script_traits::{InitialScriptState, JsEvalResult, LayoutMsg, LoadData};
 use script_traits::{MouseButton, MouseEventType, NewLayoutInfo};
 use script_traits::{ProgressiveWebMetricType, Painter, ScriptMsg, ScriptThreadFactory};
 use script_traits::{ScriptToConstellationChan, TimerEvent, TimerSchedulerMsg};
-use script_traits::{TimerSource, TouchEventType, TouchId, UntrustedNodeAddress};
+use script_traits::{TimerSource, TouchEventType, TouchId, TransferredPort, UntrustedNodeAddress};
 use script_traits::{UpdatePipelineIdReason, WindowSizeData, WindowSizeType};
 use script_traits::GuiApplication;
 use script_traits::CompositorEvent::{KeyEvent, MouseButtonEvent, MouseMoveEvent, ResizeEvent, TouchEvent};
@@ -102,6 +106,7 @@ use std::cell::Cell;
 use std::cell::RefCell;
 use std::collections::{hash_map, HashMap, HashSet};
 use std::default::Default;
+use std::mem;
 use std::ops::Deref;
 use std::option::Option;
 use std::ptr;
@@ -113,8 +118,11 @@ use std::thread;
 use style::thread_state::{self, ThreadState};
 use task_source::dom_manipulation::DOMManipulationTaskSource;
 use task_source::history_traversal::HistoryTraversalTaskSource;
+use task_source::idle::IdleTaskSource;
+use task_source::intersection_observer::IntersectionObserverTaskSource;
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
+use task_source::port_message::PortMessageQueue;
 use task_source::remote_event::RemoteEventTaskSource;
 use task_source::user_interaction::UserInteractionTaskSource;
 use time::{get_time, precise_time_ns, Tm};
@@ -419,6 +427,12 @@ pub struct ScriptThread {
 
     performance_timeline_task_sender: Box<ScriptChan>,
 
+    port_message_sender: Box<ScriptChan>,
+
+    intersection_observer_task_sender: Box<ScriptChan>,
+
+    idle_task_sender: Box<ScriptChan>,
+
     remote_event_task_sender: Box<ScriptChan>,
 
     /// A channel to hand out to threads that need to respond to a message from the script thread.
@@ -476,6 +490,9 @@ pub struct ScriptThread {
     /// The unit of related similar-origin browsing contexts' list of MutationObserver objects
     mutation_observers: DomRefCell<Vec<Dom<MutationObserver>>>,
 
+    /// <https://dom.spec.whatwg.org/#signal-slot-list>
+    signal_slots: DomRefCell<Vec<Dom<HTMLSlotElement>>>,
+
     /// The worklet thread pool
     worklet_thread_pool: DomRefCell<Option<Rc<WorkletThreadPool>>>,
 
@@ -490,6 +507,9 @@ pub struct ScriptThread {
     /// <https://html.spec.whatwg.org/multipage/#custom-element-reactions-stack>
     custom_element_reaction_stack: CustomElementReactionStack,
 
+    /// A handle to the WebGL thread
+    webgl_chan: Option<WebGLPipeline>,
+
     /// The Webrender Document ID associated with this thread.
     webrender_document: DocumentId,
 
@@ -621,6 +641,24 @@ impl ScriptThread {
         })
     }
 
+    pub fn add_signal_slot(slot: &HTMLSlotElement) {
+        SCRIPT_THREAD_ROOT.with(|root| {
+            let script_thread = unsafe { &*root.get().unwrap() };
+            let mut signal_slots = script_thread.signal_slots.borrow_mut();
+            if !signal_slots.iter().any(|s| &**s == slot) {
+                signal_slots.push(Dom::from_ref(slot));
+            }
+        })
+    }
+
+    pub fn take_signal_slots() -> Vec<DomRoot<HTMLSlotElement>> {
+        SCRIPT_THREAD_ROOT.with(|root| {
+            let script_thread = unsafe { &*root.get().unwrap() };
+            let signal_slots = mem::replace(&mut *script_thread.signal_slots.borrow_mut(), vec![]);
+            signal_slots.iter().map(|s| DomRoot::from_ref(&**s)).collect()
+        })
+    }
+
     pub fn mark_document_with_no_blocked_loads(doc: &Document) {
         SCRIPT_THREAD_ROOT.with(|root| {
             let script_thread = unsafe { &*root.get().unwrap() };
@@ -820,6 +858,9 @@ impl ScriptThread {
             networking_task_sender: boxed_script_sender.clone(),
             file_reading_task_sender: boxed_script_sender.clone(),
             performance_timeline_task_sender: boxed_script_sender.clone(),
+            port_message_sender: boxed_script_sender.clone(),
+            intersection_observer_task_sender: boxed_script_sender.clone(),
+            idle_task_sender: boxed_script_sender.clone(),
             remote_event_task_sender: boxed_script_sender.clone(),
 
             history_traversal_task_source: HistoryTraversalTaskSource(chan),
@@ -850,6 +891,8 @@ impl ScriptThread {
 
             mutation_observers: Default::default(),
 
+            signal_slots: Default::default(),
+
             layout_to_constellation_chan: state.layout_to_constellation_chan,
 
             worklet_thread_pool: Default::default(),
@@ -860,6 +903,8 @@ impl ScriptThread {
 
             custom_element_reaction_stack: CustomElementReactionStack::new(),
 
+            webgl_chan: state.webgl_chan,
+
             webrender_document: state.webrender_document,
 
             gui_application: RefCell::new(state.gui_application.unwrap()),
@@ -1077,6 +1122,15 @@ impl ScriptThread {
 
         // https://html.spec.whatwg.org/multipage/#event-loop-processing-model step 7.12
 
+        // https://drafts.csswg.org/resize-observer/#html-event-loop
+        let documents: Vec<DomRoot<Document>> =
+            self.documents.borrow().iter().map(|(_, document)| document).collect();
+        for document in documents {
+            if document.is_fully_active() {
+                document.run_the_resize_observers();
+            }
+        }
+
         // Issue batched reflows on any pages that require it (e.g. if images loaded)
         // TODO(gw): In the future we could probably batch other types of reflows
         // into this loop too, but for now it's only images.
@@ -1095,6 +1149,20 @@ impl ScriptThread {
             }
         }
 
+        // https://w3c.github.io/IntersectionObserver/#event-loop
+        for (_, document) in self.documents.borrow().iter() {
+            if document.is_fully_active() {
+                document.update_intersection_observations();
+            }
+        }
+
+        // https://w3c.github.io/requestidlecallback/#start-an-idle-period-algorithm
+        for (_, document) in self.documents.borrow().iter() {
+            if document.is_fully_active() {
+                document.window().start_an_idle_period();
+            }
+        }
+
         true
     }
 
@@ -1220,6 +1288,10 @@ impl ScriptThread {
                 ScriptThreadEventCategory::EnterFullscreen => ProfilerCategory::ScriptEnterFullscreen,
                 ScriptThreadEventCategory::ExitFullscreen => ProfilerCategory::ScriptExitFullscreen,
                 ScriptThreadEventCategory::PerformanceTimelineTask => ProfilerCategory::ScriptPerformanceEvent,
+                ScriptThreadEventCategory::PortMessage => ProfilerCategory::ScriptPortMessage,
+                ScriptThreadEventCategory::IntersectionObserverTask =>
+                    ProfilerCategory::ScriptIntersectionObserver,
+                ScriptThreadEventCategory::IdleTask => ProfilerCategory::ScriptIdleCallback,
             };
             profile(profiler_cat, None, self.time_profiler_chan.clone(), f)
         } else {
@@ -1233,6 +1305,7 @@ impl ScriptThread {
                         println!("Task took longer than max allowed ({:?}) {:?}", category, end - start);
                     }
                     doc.start_tti();
+                    doc.window().Performance().queue_long_task_entry(start, end);
                 }
             }
             doc.record_tti_if_necessary();
@@ -1259,8 +1332,8 @@ impl ScriptThread {
                 self.handle_visibility_change_msg(pipeline_id, visible),
             ConstellationControlMsg::NotifyVisibilityChange(parent_pipeline_id, browsing_context_id, visible) =>
                 self.handle_visibility_change_complete_msg(parent_pipeline_id, browsing_context_id, visible),
-            ConstellationControlMsg::PostMessage(pipeline_id, origin, data) =>
-                self.handle_post_message_msg(pipeline_id, origin, data),
+            ConstellationControlMsg::PostMessage(pipeline_id, origin, data, ports) =>
+                self.handle_post_message_msg(pipeline_id, origin, data, ports),
             ConstellationControlMsg::UpdatePipelineId(parent_pipeline_id,
                                                       browsing_context_id,
                                                       new_pipeline_id,
@@ -1417,6 +1490,10 @@ impl ScriptThread {
                 webdriver_handlers::handle_find_element_css(&*documents, pipeline_id, selector, reply),
             WebDriverScriptCommand::FindElementsCSS(selector, reply) =>
                 webdriver_handlers::handle_find_elements_css(&*documents, pipeline_id, selector, reply),
+            WebDriverScriptCommand::FindElementXPath(selector, reply) =>
+                webdriver_handlers::handle_find_element_xpath(&*documents, pipeline_id, selector, reply),
+            WebDriverScriptCommand::FindElementsXPath(selector, reply) =>
+                webdriver_handlers::handle_find_elements_xpath(&*documents, pipeline_id, selector, reply),
             WebDriverScriptCommand::FocusElement(element_id, reply) =>
                 webdriver_handlers::handle_focus_element(&*documents, pipeline_id, element_id, reply),
             WebDriverScriptCommand::GetActiveElement(reply) =>
@@ -1634,10 +1711,14 @@ impl ScriptThread {
         }
     }
 
-    fn handle_post_message_msg(&self, pipeline_id: PipelineId, origin: Option<ImmutableOrigin>, data: Vec<u8>) {
+    fn handle_post_message_msg(&self,
+                               pipeline_id: PipelineId,
+                               origin: Option<ImmutableOrigin>,
+                               data: Vec<u8>,
+                               ports: Vec<TransferredPort>) {
         match { self.documents.borrow().find_window(pipeline_id) } {
             None => return warn!("postMessage after pipeline {} closed.", pipeline_id),
-            Some(window) => window.post_message(origin, StructuredCloneData::Vector(data)),
+            Some(window) => window.post_message(origin, StructuredCloneData::Vector(data), ports),
         }
     }
 
@@ -1731,6 +1812,18 @@ impl ScriptThread {
         NetworkingTaskSource(self.networking_task_sender.clone(), pipeline_id)
     }
 
+    pub fn port_message_queue(&self, pipeline_id: PipelineId) -> PortMessageQueue {
+        PortMessageQueue(self.port_message_sender.clone(), pipeline_id)
+    }
+
+    pub fn intersection_observer_task_source(&self, pipeline_id: PipelineId) -> IntersectionObserverTaskSource {
+        IntersectionObserverTaskSource(self.intersection_observer_task_sender.clone(), pipeline_id)
+    }
+
+    pub fn idle_task_source(&self, pipeline_id: PipelineId) -> IdleTaskSource {
+        IdleTaskSource(self.idle_task_sender.clone(), pipeline_id)
+    }
+
     pub fn remote_event_task_source(&self, pipeline_id: PipelineId) -> RemoteEventTaskSource {
         RemoteEventTaskSource(self.remote_event_task_sender.clone(), pipeline_id)
     }
@@ -1865,6 +1958,7 @@ impl ScriptThread {
             parent: EventInit {
                 bubbles: true,
                 cancelable: false,
+                composed: false,
             },
             propertyName: DOMString::from(name),
             elapsedTime: Finite::new(duration as f32).unwrap(),
@@ -2042,6 +2136,9 @@ impl ScriptThread {
             self.networking_task_source(incomplete.pipeline_id),
             HistoryTraversalTaskSource(history_sender.clone()),
             self.performance_timeline_task_source(incomplete.pipeline_id).clone(),
+            self.port_message_queue(incomplete.pipeline_id),
+            self.intersection_observer_task_source(incomplete.pipeline_id),
+            self.idle_task_source(incomplete.pipeline_id),
             self.remote_event_task_source(incomplete.pipeline_id),
             self.image_cache_channel.clone(),
             self.image_cache.clone(),
@@ -2061,6 +2158,7 @@ impl ScriptThread {
             incomplete.navigation_start,
             incomplete.navigation_start_precise,
             self.microtask_queue.clone(),
+            self.webgl_chan.as_ref().map(|chan| chan.channel()),
             self.webrender_document,
         );
 
@@ -2122,6 +2220,10 @@ impl ScriptThread {
 
         window.init_document(&document);
 
+        if !incomplete.is_visible {
+            window.start_hidden();
+        }
+
         self.script_sender
             .send((incomplete.pipeline_id, ScriptMsg::ActivateDocument))
             .unwrap();
@@ -2146,10 +2248,6 @@ impl ScriptThread {
             window.suspend();
         }
 
-        if !incomplete.is_visible {
-            window.alter_resource_utilization(false);
-        }
-
         document.get_current_parser().unwrap()
     }
 
@@ -2193,7 +2291,7 @@ impl ScriptThread {
                 );
             }
 
-            MouseMoveEvent(point, node_address) => {
+            MouseMoveEvent(point, node_address, point_in_node) => {
                 let document = match { self.documents.borrow().find_document(pipeline_id) } {
                     Some(document) => document,
                     None => return warn!("Message sent to closed pipeline {}.", pipeline_id),
@@ -2205,7 +2303,8 @@ impl ScriptThread {
 
                 document.handle_mouse_move_event(self.js_runtime.rt(), point,
                                                  &self.topmost_mouse_over_target,
-                                                 node_address);
+                                                 node_address,
+                                                 point_in_node);
 
                 // Short-circuit if nothing changed
                 if self.topmost_mouse_over_target.get() == prev_mouse_over_target {
@@ -2586,10 +2685,24 @@ impl ScriptThread {
                            metric_value: u64) {
         let window = self.documents.borrow().find_window(pipeline_id);
         if let Some(window) = window {
-            let entry = PerformancePaintTiming::new(&window.upcast::<GlobalScope>(),
-                                                    metric_type, metric_value);
-            window.Performance().queue_entry(&entry.upcast::<PerformanceEntry>(),
-                                             true /* buffer performance entry */);
+            let entry = match metric_type {
+                ProgressiveWebMetricType::FirstPaint |
+                ProgressiveWebMetricType::FirstContentfulPaint => {
+                    DomRoot::upcast::<PerformanceEntry>(
+                        PerformancePaintTiming::new(&window.upcast::<GlobalScope>(),
+                                                    metric_type, metric_value))
+                },
+                ProgressiveWebMetricType::LargestContentfulPaint(size) => {
+                    DomRoot::upcast::<PerformanceEntry>(
+                        LargestContentfulPaint::new(&window, metric_value.to_ms(), size as u32))
+                },
+                ProgressiveWebMetricType::LayoutShift(value) => {
+                    DomRoot::upcast::<PerformanceEntry>(
+                        LayoutShift::new(&window, metric_value.to_ms(), value))
+                },
+                ProgressiveWebMetricType::TimeToInteractive => return,
+            };
+            window.Performance().queue_entry(&entry, true /* buffer performance entry */);
         }
     }
 
@@ -13,6 +13,7 @@ use dom::eventtarget::EventTarget;
 use dom::htmlelement::HTMLElement;
 use dom::htmllinkelement::{RequestGenerationId, HTMLLinkElement};
 use dom::node::{document_from_node, window_from_node};
+use dom::performanceresourcetiming::InitiatorType;
 use encoding_rs::UTF_8;
 use hyper::header::ContentType;
 use hyper::mime::{Mime, TopLevel, SubLevel};
@@ -21,7 +22,7 @@ use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use net_traits::{FetchResponseListener, FetchMetadata, FilteredMetadata, Metadata, NetworkError, ReferrerPolicy};
 use net_traits::request::{CorsSettings, CredentialsMode, Destination, RequestInit, RequestMode};
-use network_listener::{NetworkListener, PreInvoke};
+use network_listener::{NetworkListener, PreInvoke, ResourceTimingRecorder};
 use parking_lot::RwLock;
 use servo_arc::Arc;
 use servo_url::ServoUrl;
@@ -78,6 +79,8 @@ pub struct StylesheetContext {
     /// A token which must match the generation id of the `HTMLLinkElement` for it to load the stylesheet.
     /// This is ignored for `HTMLStyleElement` and imports.
     request_generation_id: Option<RequestGenerationId>,
+    /// The timing of the fetch, reported once the stylesheet has been received.
+    resource_timing: ResourceTimingRecorder,
 }
 
 impl PreInvoke for StylesheetContext {}
@@ -89,6 +92,7 @@ impl FetchResponseListener for StylesheetContext {
 
     fn process_response(&mut self,
                         metadata: Result<FetchMetadata, NetworkError>) {
+        self.resource_timing.record_response(&metadata);
         if let Ok(FetchMetadata::Filtered { ref filtered, .. }) = metadata {
             match *filtered {
                 FilteredMetadata::Opaque |
@@ -108,6 +112,7 @@ impl FetchResponseListener for StylesheetContext {
     }
 
     fn process_response_chunk(&mut self, mut payload: Vec<u8>) {
+        self.resource_timing.record_chunk(&payload);
         self.data.append(&mut payload);
     }
 
@@ -115,6 +120,7 @@ impl FetchResponseListener for StylesheetContext {
         let elem = self.elem.root();
         let document = self.document.root();
         let mut successful = false;
+        self.resource_timing.submit(document.window().upcast());
 
         if status.is_ok() {
             let metadata = match self.metadata.take() {
@@ -214,6 +220,10 @@ impl<'a> StylesheetLoader<'a> {
         let document = document_from_node(self.elem);
         let gen = self.elem.downcast::<HTMLLinkElement>()
                            .map(HTMLLinkElement::get_request_generation_id);
+        let initiator_type = match source {
+            StylesheetContextSource::LinkElement { .. } => InitiatorType::Link,
+            StylesheetContextSource::Import(..) => InitiatorType::Css,
+        };
         let context = ::std::sync::Arc::new(Mutex::new(StylesheetContext {
             elem: Trusted::new(&*self.elem),
             source: source,
@@ -223,6 +233,7 @@ impl<'a> StylesheetLoader<'a> {
             document: Trusted::new(&*document),
             origin_clean: true,
             request_generation_id: gen,
+            resource_timing: ResourceTimingRecorder::new(url.clone(), initiator_type),
         }));
 
         let (action_sender, action_receiver) = ipc::channel().unwrap();
@@ -0,0 +1,45 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use msg::constellation_msg::PipelineId;
+use script_runtime::{CommonScriptMsg, ScriptChan, ScriptThreadEventCategory};
+use std::fmt;
+use task::{TaskCanceller, TaskOnce};
+use task_source::{TaskSource, TaskSourceName};
+
+/// <https://w3c.github.io/requestidlecallback/#the-requestidlecallback-method>
+#[derive(JSTraceable)]
+pub struct IdleTaskSource(pub Box<ScriptChan + Send + 'static>, pub PipelineId);
+
+impl Clone for IdleTaskSource {
+    fn clone(&self) -> IdleTaskSource {
+        IdleTaskSource(self.0.clone(), self.1.clone())
+    }
+}
+
+impl fmt::Debug for IdleTaskSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IdleTaskSource(...)")
+    }
+}
+
+impl TaskSource for IdleTaskSource {
+    const NAME: TaskSourceName = TaskSourceName::Idle;
+
+    fn queue_with_canceller<T>(
+        &self,
+        task: T,
+        canceller: &TaskCanceller,
+    ) -> Result<(), ()>
+    where
+        T: TaskOnce + 'static,
+    {
+        let msg = CommonScriptMsg::Task(
+            ScriptThreadEventCategory::IdleTask,
+            Box::new(canceller.wrap_task(task)),
+            Some(self.1),
+        );
+        self.0.send(msg).map_err(|_| ())
+    }
+}
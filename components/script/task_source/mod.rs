@@ -5,8 +5,11 @@
 
 pub mod dom_manipulation;
 pub mod history_traversal;
+pub mod idle;
+pub mod intersection_observer;
 pub mod networking;
 pub mod performance_timeline;
+pub mod port_message;
 pub mod remote_event;
 pub mod user_interaction;
 
@@ -24,8 +27,11 @@ pub enum TaskSourceName {
     DOMManipulation,
     FileReading,
     HistoryTraversal,
+    Idle,
+    IntersectionObserver,
     Networking,
     PerformanceTimeline,
+    PortMessage,
     UserInteraction,
     RemoteEvent
 }
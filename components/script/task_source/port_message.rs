@@ -0,0 +1,45 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use msg::constellation_msg::PipelineId;
+use script_runtime::{CommonScriptMsg, ScriptChan, ScriptThreadEventCategory};
+use std::fmt;
+use task::{TaskCanceller, TaskOnce};
+use task_source::{TaskSource, TaskSourceName};
+
+/// <https://html.spec.whatwg.org/multipage/#posted-message-task-source>
+#[derive(JSTraceable)]
+pub struct PortMessageQueue(pub Box<ScriptChan + Send + 'static>, pub PipelineId);
+
+impl Clone for PortMessageQueue {
+    fn clone(&self) -> PortMessageQueue {
+        PortMessageQueue(self.0.clone(), self.1.clone())
+    }
+}
+
+impl fmt::Debug for PortMessageQueue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PortMessageQueue(...)")
+    }
+}
+
+impl TaskSource for PortMessageQueue {
+    const NAME: TaskSourceName = TaskSourceName::PortMessage;
+
+    fn queue_with_canceller<T>(
+        &self,
+        task: T,
+        canceller: &TaskCanceller,
+    ) -> Result<(), ()>
+    where
+        T: TaskOnce + 'static,
+    {
+        let msg = CommonScriptMsg::Task(
+            ScriptThreadEventCategory::PortMessage,
+            Box::new(canceller.wrap_task(task)),
+            Some(self.1),
+        );
+        self.0.send(msg).map_err(|_| ())
+    }
+}
@@ -14,6 +14,28 @@ pub mod area {
     pub use dom::htmlareaelement::{Area, Shape};
 }
 
+pub mod glsl {
+    pub use dom::webgl_validations::glsl::{ShaderError, ShaderKind, TranslationOptions, translate};
+}
+
+pub mod intersectionobserver {
+    pub use dom::intersectionobserver::RootMargin;
+}
+
+pub mod path2d {
+    pub use canvas_traits::canvas::PathSegment;
+    pub use dom::path2d::PathData;
+}
+
+pub mod webcrypto {
+    pub use webcrypto::{aes_192_cbc, aes_ctr, der, hkdf, hmac};
+    pub use webcrypto::{rsa_oaep_decrypt, rsa_oaep_encrypt, rsa_pss_sign, rsa_pss_verify};
+}
+
+pub mod xpath {
+    pub use xpath::{Axis, BinaryOperator, Expr, KindTest, NodeTest, ParseError, PathStart, QName, Step, parse};
+}
+
 pub mod size_of {
     use dom::characterdata::CharacterData;
     use dom::element::Element;
@@ -10,6 +10,7 @@ use dom::bindings::str::DOMString;
 use dom::document::FakeRequestAnimationFrameCallback;
 use dom::globalscope::GlobalScope;
 use dom::testbinding::TestBindingCallback;
+use dom::window::IdleCallbackTimeout;
 use euclid::Length;
 use ipc_channel::ipc::IpcSender;
 use js::jsapi::Heap;
@@ -68,6 +69,7 @@ pub enum OneshotTimerCallback {
     JsTimer(JsTimerTask),
     TestBindingCallback(TestBindingCallback),
     FakeRequestAnimationFrame(FakeRequestAnimationFrameCallback),
+    IdleCallbackTimeout(IdleCallbackTimeout),
 }
 
 impl OneshotTimerCallback {
@@ -76,6 +78,7 @@ impl OneshotTimerCallback {
             OneshotTimerCallback::JsTimer(task) => task.invoke(this, js_timers),
             OneshotTimerCallback::TestBindingCallback(callback) => callback.invoke(),
             OneshotTimerCallback::FakeRequestAnimationFrame(callback) => callback.invoke(),
+            OneshotTimerCallback::IdleCallbackTimeout(callback) => callback.invoke(),
         }
     }
 }
@@ -205,6 +208,15 @@ impl OneshotTimers {
         self.schedule_timer_call();
     }
 
+    /// How long until the next timer is due, if there is one and timers aren't suspended.
+    pub fn time_until_next_timer(&self) -> Option<MsDuration> {
+        if self.suspended_since.get().is_some() {
+            return None;
+        }
+        let base_time = self.base_time().get();
+        self.timers.borrow().last().map(|timer| Length::new(timer.scheduled_for.get().saturating_sub(base_time)))
+    }
+
     fn base_time(&self) -> MsDuration {
         let offset = self.suspension_offset.get();
 
@@ -0,0 +1,137 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The bits of DER that OpenSSL doesn't produce in the shape Web Crypto
+//! expects: PKCS #8 private keys and raw ECDSA signatures, and the EC
+//! private keys it can't build from their components.
+
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const NULL: u8 = 0x05;
+pub const OBJECT_IDENTIFIER: u8 = 0x06;
+pub const SEQUENCE: u8 = 0x30;
+/// The explicit tags of the optional ECPrivateKey fields.
+const EC_PARAMETERS: u8 = 0xa0;
+const EC_PUBLIC_KEY: u8 = 0xa1;
+
+/// rsaEncryption, 1.2.840.113549.1.1.1
+pub const OID_RSA_ENCRYPTION: &'static [u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+/// id-ecPublicKey, 1.2.840.10045.2.1
+pub const OID_EC_PUBLIC_KEY: &'static [u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+/// secp256r1, 1.2.840.10045.3.1.7
+pub const OID_SECP256R1: &'static [u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// secp384r1, 1.3.132.0.34
+pub const OID_SECP384R1: &'static [u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+/// secp521r1, 1.3.132.0.35
+pub const OID_SECP521R1: &'static [u8] = &[0x2b, 0x81, 0x04, 0x00, 0x23];
+
+fn length(len: usize) -> Vec<u8> {
+    if len < 0x80 {
+        return vec![len as u8];
+    }
+    let mut bytes = Vec::new();
+    let mut remaining = len;
+    while remaining > 0 {
+        bytes.insert(0, remaining as u8);
+        remaining >>= 8;
+    }
+    bytes.insert(0, 0x80 | bytes.len() as u8);
+    bytes
+}
+
+/// Encodes an element with the given tag and contents.
+pub fn element(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut element = vec![tag];
+    element.extend(length(contents.len()));
+    element.extend_from_slice(contents);
+    element
+}
+
+/// Splits an element off the front of `input`, returning its tag, contents
+/// and whatever follows it.
+pub fn split(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    if input.len() < 2 {
+        return None;
+    }
+    let (len, header_len) = if input[1] < 0x80 {
+        (input[1] as usize, 2)
+    } else {
+        let len_bytes = (input[1] & 0x7f) as usize;
+        if len_bytes == 0 || len_bytes > 4 || input.len() < 2 + len_bytes {
+            return None;
+        }
+        let len = input[2..2 + len_bytes].iter().fold(0, |len, &byte| (len << 8) | byte as usize);
+        (len, 2 + len_bytes)
+    };
+    if input.len() - header_len < len {
+        return None;
+    }
+    Some((input[0], &input[header_len..header_len + len], &input[header_len + len..]))
+}
+
+/// Left-pads a big-endian integer to `len` bytes.
+pub fn pad_to(mut bytes: Vec<u8>, len: usize) -> Vec<u8> {
+    while bytes.len() < len {
+        bytes.insert(0, 0);
+    }
+    bytes
+}
+
+/// Wraps a DER-encoded RSAPrivateKey or ECPrivateKey in a PKCS #8
+/// PrivateKeyInfo structure.
+/// <https://tools.ietf.org/html/rfc5208#section-5>
+pub fn pkcs8_wrap(algorithm_identifier: &[u8], private_key: &[u8]) -> Vec<u8> {
+    let mut contents = element(INTEGER, &[0]);
+    contents.extend(element(SEQUENCE, algorithm_identifier));
+    contents.extend(element(OCTET_STRING, private_key));
+    element(SEQUENCE, &contents)
+}
+
+/// Encodes an ECPrivateKey structure for the private scalar `private`, the
+/// uncompressed point `public` and the curve with the given OID.
+/// <https://tools.ietf.org/html/rfc5915#section-3>
+pub fn ec_private_key(curve: &[u8], private: &[u8], public: &[u8]) -> Vec<u8> {
+    let mut public_key = vec![0];
+    public_key.extend_from_slice(public);
+    let mut contents = element(INTEGER, &[1]);
+    contents.extend(element(OCTET_STRING, private));
+    contents.extend(element(EC_PARAMETERS, &element(OBJECT_IDENTIFIER, curve)));
+    contents.extend(element(EC_PUBLIC_KEY, &element(BIT_STRING, &public_key)));
+    element(SEQUENCE, &contents)
+}
+
+/// Converts a DER-encoded ECDSA signature into the concatenation of `r`
+/// and `s` that Web Crypto uses.
+/// <https://w3c.github.io/webcrypto/#ecdsa-operations>
+pub fn ecdsa_der_to_raw(der: &[u8], coordinate_len: usize) -> Option<Vec<u8>> {
+    let (_, sequence, _) = split(der)?;
+    let (_, r, rest) = split(sequence)?;
+    let (_, s, _) = split(rest)?;
+    let mut raw = Vec::with_capacity(2 * coordinate_len);
+    for integer in &[r, s] {
+        let start = integer.iter().position(|&byte| byte != 0).unwrap_or(integer.len());
+        let integer = &integer[start..];
+        if integer.len() > coordinate_len {
+            return None;
+        }
+        raw.extend(pad_to(integer.to_vec(), coordinate_len));
+    }
+    Some(raw)
+}
+
+/// The inverse of `ecdsa_der_to_raw`.
+pub fn ecdsa_raw_to_der(raw: &[u8]) -> Vec<u8> {
+    let (r, s) = raw.split_at(raw.len() / 2);
+    let mut contents = Vec::new();
+    for integer in &[r, s] {
+        let start = integer.iter().position(|&byte| byte != 0).unwrap_or(integer.len() - 1);
+        let mut integer = integer[start..].to_vec();
+        if integer[0] & 0x80 != 0 {
+            integer.insert(0, 0);
+        }
+        contents.extend(element(INTEGER, &integer));
+    }
+    element(SEQUENCE, &contents)
+}
@@ -0,0 +1,336 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The cryptographic operations behind `SubtleCrypto`, configured the way
+//! [Web Crypto](https://w3c.github.io/webcrypto/) requires.
+//!
+//! The `openssl` 0.9 bindings only expose the paddings OpenSSL uses by
+//! default, so OAEP and PSS are encoded here on top of raw RSA. They also
+//! lack the AES-192 ciphers, which are looked up through `openssl-sys`.
+
+pub mod der;
+
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::EcPoint;
+use openssl::error::ErrorStack;
+use openssl::hash::{MessageDigest, hash2};
+use openssl::memcmp;
+use openssl::pkey::{PKey, PKeyRef};
+use openssl::rand::rand_bytes;
+use openssl::rsa::{NO_PADDING, RsaRef};
+use openssl::sign::Signer;
+use openssl::symm::{self, Cipher};
+use openssl_sys::EVP_CIPHER;
+
+extern "C" {
+    fn EVP_aes_192_cbc() -> *const EVP_CIPHER;
+    fn EVP_aes_192_ctr() -> *const EVP_CIPHER;
+    fn EVP_aes_192_gcm() -> *const EVP_CIPHER;
+}
+
+#[allow(unsafe_code)]
+pub fn aes_192_cbc() -> Cipher {
+    unsafe { Cipher::from_ptr(EVP_aes_192_cbc()) }
+}
+
+#[allow(unsafe_code)]
+pub fn aes_192_ctr() -> Cipher {
+    unsafe { Cipher::from_ptr(EVP_aes_192_ctr()) }
+}
+
+#[allow(unsafe_code)]
+pub fn aes_192_gcm() -> Cipher {
+    unsafe { Cipher::from_ptr(EVP_aes_192_gcm()) }
+}
+
+fn digest(digest: MessageDigest, data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    hash2(digest, data).map(|bytes| bytes.to_vec())
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, ErrorStack> {
+    let mut bytes = vec![0; len];
+    rand_bytes(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn xor_in_place(target: &mut [u8], mask: &[u8]) {
+    for (byte, mask) in target.iter_mut().zip(mask) {
+        *byte ^= *mask;
+    }
+}
+
+/// The error reported for inputs that don't fit the key, or ciphertexts and
+/// signatures that don't decode.
+fn invalid_input() -> ErrorStack {
+    ErrorStack::get()
+}
+
+/// <https://tools.ietf.org/html/rfc2104>
+pub fn hmac(digest: MessageDigest, key: &[u8], data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(digest, &key)?;
+    signer.update(data)?;
+    signer.sign_to_vec()
+}
+
+/// AES in counter mode, where only the rightmost `length` bits of the
+/// 16-byte `counter` block are incremented, wrapping around to zero.
+/// Encryption and decryption are the same operation.
+///
+/// OpenSSL increments the whole block, so the data is split where the
+/// counter bits wrap around. Data needing more blocks than there are
+/// counter values is rejected rather than reusing a counter.
+/// <https://w3c.github.io/webcrypto/#aes-ctr-operations>
+pub fn aes_ctr(cipher: Cipher, key: &[u8], counter: &[u8], length: u32, data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    if counter.len() != 16 || length == 0 || length > 128 {
+        return Err(invalid_input());
+    }
+    let mask = if length == 128 { !0 } else { (1u128 << length) - 1 };
+    let blocks = |len: usize| (len as u128 + 15) / 16;
+    if length < 128 && blocks(data.len()) > mask + 1 {
+        return Err(invalid_input());
+    }
+
+    let mut counter = counter.iter().fold(0u128, |value, &byte| value << 8 | byte as u128);
+    let mut output = Vec::with_capacity(data.len());
+    let mut remaining = data;
+    while !remaining.is_empty() {
+        // The number of blocks until the counter bits wrap around, which
+        // overflows when all 128 bits are zero.
+        let segment_len = match (mask - (counter & mask)).checked_add(1) {
+            Some(blocks_left) if blocks_left < blocks(remaining.len()) => blocks_left as usize * 16,
+            _ => remaining.len(),
+        };
+        let (segment, rest) = remaining.split_at(segment_len);
+        let block: Vec<u8> = (0..16).rev().map(|index| (counter >> (index * 8)) as u8).collect();
+        output.extend(symm::encrypt(cipher, key, Some(&block), segment)?);
+        counter &= !mask;
+        remaining = rest;
+    }
+    Ok(output)
+}
+
+/// <https://tools.ietf.org/html/rfc5869#section-2>
+///
+/// Panics if `len` is more than 255 times the output size of `digest`.
+pub fn hkdf(digest: MessageDigest,
+            key: &[u8],
+            salt: &[u8],
+            info: &[u8],
+            len: usize)
+            -> Result<Vec<u8>, ErrorStack> {
+    // A missing salt stands for a string of zeros as long as the hash output,
+    // which HMAC pads the same way as an empty key.
+    let pseudorandom_key = hmac(digest, salt, key)?;
+    let hash_len = pseudorandom_key.len();
+    let blocks = (len + hash_len - 1) / hash_len;
+    assert!(blocks <= 255, "HKDF output too long");
+
+    let mut output = Vec::with_capacity(blocks * hash_len);
+    let mut block = Vec::new();
+    for counter in 1..(blocks + 1) {
+        let mut input = block;
+        input.extend_from_slice(info);
+        input.push(counter as u8);
+        block = hmac(digest, &pseudorandom_key, &input)?;
+        output.extend_from_slice(&block);
+    }
+    output.truncate(len);
+    Ok(output)
+}
+
+/// <https://tools.ietf.org/html/rfc8017#appendix-B.2.1>
+fn mgf1(hash: MessageDigest, seed: &[u8], len: usize) -> Result<Vec<u8>, ErrorStack> {
+    let mut mask = Vec::with_capacity(len);
+    let mut counter = 0u32;
+    while mask.len() < len {
+        let mut input = seed.to_vec();
+        input.extend_from_slice(&[(counter >> 24) as u8, (counter >> 16) as u8, (counter >> 8) as u8, counter as u8]);
+        mask.extend(digest(hash, &input)?);
+        counter += 1;
+    }
+    mask.truncate(len);
+    Ok(mask)
+}
+
+/// <https://tools.ietf.org/html/rfc8017#section-7.1.1>
+pub fn rsa_oaep_encrypt(key: &PKeyRef,
+                        hash: MessageDigest,
+                        label: &[u8],
+                        message: &[u8])
+                        -> Result<Vec<u8>, ErrorStack> {
+    let rsa = key.rsa()?;
+    let k = rsa.size() as usize;
+    let label_hash = digest(hash, label)?;
+    let h_len = label_hash.len();
+    if k < 2 * h_len + 2 || message.len() > k - 2 * h_len - 2 {
+        return Err(invalid_input());
+    }
+
+    // EME-OAEP encoding, step 2.
+    let mut db = label_hash;
+    db.resize(k - message.len() - h_len - 2, 0);
+    db.push(0x01);
+    db.extend_from_slice(message);
+    let seed = random_bytes(h_len)?;
+    xor_in_place(&mut db, &mgf1(hash, &seed, k - h_len - 1)?);
+    let mut masked_seed = seed;
+    xor_in_place(&mut masked_seed, &mgf1(hash, &db, h_len)?);
+    let mut encoded = vec![0];
+    encoded.extend(masked_seed);
+    encoded.extend(db);
+
+    // RSA encryption, step 3.
+    let mut ciphertext = vec![0; k];
+    rsa.public_encrypt(&encoded, &mut ciphertext, NO_PADDING)?;
+    Ok(ciphertext)
+}
+
+/// <https://tools.ietf.org/html/rfc8017#section-7.1.2>
+pub fn rsa_oaep_decrypt(key: &PKeyRef,
+                        hash: MessageDigest,
+                        label: &[u8],
+                        ciphertext: &[u8])
+                        -> Result<Vec<u8>, ErrorStack> {
+    let rsa = key.rsa()?;
+    let k = rsa.size() as usize;
+    let label_hash = digest(hash, label)?;
+    let h_len = label_hash.len();
+    if ciphertext.len() != k || k < 2 * h_len + 2 {
+        return Err(invalid_input());
+    }
+
+    // RSA decryption, step 2.
+    let mut encoded = vec![0; k];
+    rsa.private_decrypt(ciphertext, &mut encoded, NO_PADDING)?;
+
+    // EME-OAEP decoding, step 3.
+    let (masked_seed, masked_db) = encoded[1..].split_at(h_len);
+    let mut seed = masked_seed.to_vec();
+    xor_in_place(&mut seed, &mgf1(hash, masked_db, h_len)?);
+    let mut db = masked_db.to_vec();
+    xor_in_place(&mut db, &mgf1(hash, &seed, k - h_len - 1)?);
+
+    let separator = db[h_len..].iter().position(|&byte| byte != 0).map(|index| index + h_len);
+    match separator {
+        Some(index) if encoded[0] == 0 && db[index] == 0x01 && memcmp::eq(&db[..h_len], &label_hash) => {
+            Ok(db[index + 1..].to_vec())
+        },
+        _ => Err(invalid_input()),
+    }
+}
+
+/// The length of the PSS encoded message, in bits, for the given key.
+fn pss_encoded_bits(rsa: &RsaRef) -> Result<usize, ErrorStack> {
+    let modulus = rsa.n().ok_or_else(invalid_input)?;
+    Ok(modulus.num_bits() as usize - 1)
+}
+
+/// The `M'` whose hash EMSA-PSS encodes.
+fn pss_prefixed_hash(hash: MessageDigest, message: &[u8], salt: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let mut prefixed = vec![0; 8];
+    prefixed.extend(digest(hash, message)?);
+    prefixed.extend_from_slice(salt);
+    digest(hash, &prefixed)
+}
+
+/// <https://tools.ietf.org/html/rfc8017#section-8.1.1>
+pub fn rsa_pss_sign(key: &PKeyRef,
+                    hash: MessageDigest,
+                    salt_len: usize,
+                    message: &[u8])
+                    -> Result<Vec<u8>, ErrorStack> {
+    let rsa = key.rsa()?;
+    let k = rsa.size() as usize;
+    let em_bits = pss_encoded_bits(&rsa)?;
+    let em_len = (em_bits + 7) / 8;
+
+    // EMSA-PSS encoding, https://tools.ietf.org/html/rfc8017#section-9.1.1
+    // The salt length comes from script, so it is checked before the salt
+    // is generated.
+    let h_len = digest(hash, &[])?.len();
+    if em_len < h_len + salt_len + 2 {
+        return Err(invalid_input());
+    }
+    let salt = random_bytes(salt_len)?;
+    let h = pss_prefixed_hash(hash, message, &salt)?;
+    let mut db = vec![0; em_len - salt_len - h_len - 2];
+    db.push(0x01);
+    db.extend(salt);
+    xor_in_place(&mut db, &mgf1(hash, &h, em_len - h_len - 1)?);
+    db[0] &= 0xff >> (8 * em_len - em_bits);
+    let mut encoded = db;
+    encoded.extend(h);
+    encoded.push(0xbc);
+
+    let mut signature = vec![0; k];
+    rsa.private_encrypt(&der::pad_to(encoded, k), &mut signature, NO_PADDING)?;
+    Ok(signature)
+}
+
+/// <https://tools.ietf.org/html/rfc8017#section-8.1.2>
+pub fn rsa_pss_verify(key: &PKeyRef,
+                      hash: MessageDigest,
+                      salt_len: usize,
+                      message: &[u8],
+                      signature: &[u8])
+                      -> Result<bool, ErrorStack> {
+    let rsa = key.rsa()?;
+    let k = rsa.size() as usize;
+    let em_bits = pss_encoded_bits(&rsa)?;
+    let em_len = (em_bits + 7) / 8;
+    let h_len = digest(hash, &[])?.len();
+    if signature.len() != k || em_len < h_len + salt_len + 2 {
+        return Ok(false);
+    }
+
+    // Malformed signatures are reported as mismatches rather than errors.
+    let mut decrypted = vec![0; k];
+    if rsa.public_decrypt(signature, &mut decrypted, NO_PADDING).is_err() {
+        return Ok(false);
+    }
+    if decrypted[..k - em_len].iter().any(|&byte| byte != 0) {
+        return Ok(false);
+    }
+
+    // EMSA-PSS verification, https://tools.ietf.org/html/rfc8017#section-9.1.2
+    let encoded = &decrypted[k - em_len..];
+    if encoded[em_len - 1] != 0xbc {
+        return Ok(false);
+    }
+    let (masked_db, h) = encoded[..em_len - 1].split_at(em_len - h_len - 1);
+    let top_bits_mask = !(0xff >> (8 * em_len - em_bits)) as u8;
+    if masked_db[0] & top_bits_mask != 0 {
+        return Ok(false);
+    }
+    let mut db = masked_db.to_vec();
+    xor_in_place(&mut db, &mgf1(hash, h, em_len - h_len - 1)?);
+    db[0] &= !top_bits_mask;
+    let padding_len = em_len - h_len - salt_len - 2;
+    if db[..padding_len].iter().any(|&byte| byte != 0) || db[padding_len] != 0x01 {
+        return Ok(false);
+    }
+
+    let expected = pss_prefixed_hash(hash, message, &db[db.len() - salt_len..])?;
+    Ok(memcmp::eq(&expected, h))
+}
+
+/// Computes the shared secret of an ECDH key agreement, which is the
+/// x-coordinate of the shared point without its leading zeros.
+/// <https://w3c.github.io/webcrypto/#ecdh-operations>
+pub fn ecdh(private: &PKeyRef, public: &PKeyRef) -> Result<Vec<u8>, ErrorStack> {
+    let private = private.ec_key()?;
+    let public = public.ec_key()?;
+    let group = private.group().ok_or_else(invalid_input)?;
+    let mut context = BigNumContext::new()?;
+    let mut shared = EcPoint::new(group)?;
+    shared.mul(group,
+               public.public_key().ok_or_else(invalid_input)?,
+               private.private_key().ok_or_else(invalid_input)?,
+               &mut context)?;
+    let mut x = BigNum::new()?;
+    let mut y = BigNum::new()?;
+    shared.affine_coordinates_gfp(group, &mut x, &mut y, &mut context)?;
+    Ok(x.to_vec())
+}
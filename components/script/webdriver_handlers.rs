@@ -11,10 +11,13 @@ use dom::bindings::codegen::Bindings::HTMLInputElementBinding::HTMLInputElementM
 use dom::bindings::codegen::Bindings::HTMLOptionElementBinding::HTMLOptionElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::codegen::Bindings::XPathResultBinding::XPathResultConstants;
+use dom::bindings::codegen::Bindings::XPathResultBinding::XPathResultMethods;
 use dom::bindings::conversions::{ConversionResult, FromJSValConvertible, StringificationBehavior};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::root::DomRoot;
 use dom::bindings::str::DOMString;
+use dom::document::Document;
 use dom::element::Element;
 use dom::globalscope::GlobalScope;
 use dom::htmlelement::HTMLElement;
@@ -153,6 +156,45 @@ pub fn handle_find_elements_css(documents: &Documents,
     reply.send(node_ids).unwrap();
 }
 
+/// <https://w3c.github.io/webdriver/webdriver-spec.html#xpath>
+fn find_elements_xpath(document: &Document, selector: String) -> Result<Vec<DomRoot<Node>>, ()> {
+    let result = document.Evaluate(DOMString::from(selector),
+                                   document.upcast::<Node>(),
+                                   None,
+                                   XPathResultConstants::ORDERED_NODE_SNAPSHOT_TYPE,
+                                   None).map_err(|_| ())?;
+    let length = result.GetSnapshotLength().map_err(|_| ())?;
+    let mut elements = vec![];
+    for index in 0..length {
+        match result.SnapshotItem(index) {
+            // Only elements can be returned.
+            Ok(Some(ref node)) if node.is::<Element>() => elements.push(node.clone()),
+            _ => return Err(()),
+        }
+    }
+    Ok(elements)
+}
+
+pub fn handle_find_element_xpath(documents: &Documents, pipeline: PipelineId, selector: String,
+                                 reply: IpcSender<Result<Option<String>, ()>>) {
+    let node_id = documents.find_document(pipeline)
+        .ok_or(())
+        .and_then(|doc| find_elements_xpath(&doc, selector))
+        .map(|nodes| nodes.first().map(|x| x.unique_id()));
+    reply.send(node_id).unwrap();
+}
+
+pub fn handle_find_elements_xpath(documents: &Documents,
+                                  pipeline: PipelineId,
+                                  selector: String,
+                                  reply: IpcSender<Result<Vec<String>, ()>>) {
+    let node_ids = documents.find_document(pipeline)
+        .ok_or(())
+        .and_then(|doc| find_elements_xpath(&doc, selector))
+        .map(|nodes| nodes.iter().map(|x| x.unique_id()).collect());
+    reply.send(node_ids).unwrap();
+}
+
 pub fn handle_focus_element(documents: &Documents,
                             pipeline: PipelineId,
                             element_id: String,
@@ -0,0 +1,767 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Evaluation of XPath 1.0 expressions against DOM trees.
+//!
+//! <https://www.w3.org/TR/1999/REC-xpath-19991116/#section-Expressions>
+
+use dom::attr::Attr;
+use dom::bindings::codegen::Bindings::AttrBinding::AttrMethods;
+use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::root::DomRoot;
+use dom::characterdata::CharacterData;
+use dom::comment::Comment;
+use dom::element::Element;
+use dom::node::Node;
+use dom::processinginstruction::ProcessingInstruction;
+use dom::text::Text;
+use html5ever::{LocalName, Namespace};
+use std::collections::HashMap;
+use std::f64;
+use xpath::parser::{Axis, BinaryOperator, Expr, KindTest, NodeTest, PathStart, QName, Step};
+
+/// A node of the XPath data model. Attributes are nodes in XPath, but not in
+/// the DOM.
+#[derive(Clone, PartialEq)]
+pub enum XPathNode {
+    Node(DomRoot<Node>),
+    Attribute(DomRoot<Attr>),
+}
+
+impl XPathNode {
+    /// The DOM node this node is, or belongs to for attributes.
+    fn node(&self) -> Option<DomRoot<Node>> {
+        match *self {
+            XPathNode::Node(ref node) => Some(node.clone()),
+            XPathNode::Attribute(ref attr) => attr.owner().map(DomRoot::upcast),
+        }
+    }
+
+    /// <https://www.w3.org/TR/1999/REC-xpath-19991116/#dt-string-value>
+    pub fn string_value(&self) -> String {
+        match *self {
+            XPathNode::Attribute(ref attr) => String::from(&**attr.value()),
+            XPathNode::Node(ref node) => {
+                if let Some(data) = node.downcast::<CharacterData>() {
+                    return String::from(data.data().clone());
+                }
+                if node.is::<Element>() || node.children_count() > 0 {
+                    return String::from(Node::collect_text_contents(node.traverse_preorder()));
+                }
+                String::new()
+            },
+        }
+    }
+}
+
+pub enum Value {
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    /// A node-set, in document order and without duplicates.
+    Nodes(Vec<XPathNode>),
+}
+
+impl Value {
+    /// <https://www.w3.org/TR/1999/REC-xpath-19991116/#function-boolean>
+    pub fn boolean(&self) -> bool {
+        match *self {
+            Value::Boolean(value) => value,
+            Value::Number(value) => value != 0. && !value.is_nan(),
+            Value::String(ref value) => !value.is_empty(),
+            Value::Nodes(ref nodes) => !nodes.is_empty(),
+        }
+    }
+
+    /// <https://www.w3.org/TR/1999/REC-xpath-19991116/#function-number>
+    pub fn number(&self) -> f64 {
+        match *self {
+            Value::Boolean(value) => if value { 1. } else { 0. },
+            Value::Number(value) => value,
+            Value::String(ref value) => string_to_number(value),
+            Value::Nodes(_) => string_to_number(&self.string()),
+        }
+    }
+
+    /// <https://www.w3.org/TR/1999/REC-xpath-19991116/#function-string>
+    pub fn string(&self) -> String {
+        match *self {
+            Value::Boolean(value) => String::from(if value { "true" } else { "false" }),
+            Value::Number(value) => number_to_string(value),
+            Value::String(ref value) => value.clone(),
+            Value::Nodes(ref nodes) => nodes.first().map_or(String::new(), |node| node.string_value()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EvaluationError {
+    /// An operand which must be a node-set is not.
+    NotANodeSet,
+    UnknownFunction(String),
+    UnknownVariable(String),
+    UnresolvedPrefix(String),
+}
+
+pub type EvaluationResult<T> = Result<T, EvaluationError>;
+
+struct Context<'a> {
+    node: XPathNode,
+    position: usize,
+    size: usize,
+    namespaces: &'a HashMap<String, Namespace>,
+    /// Whether the context node is from an HTML document, which changes how
+    /// unprefixed names are matched.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#interactions-with-xpath-and-xslt>
+    is_html: bool,
+}
+
+impl<'a> Context<'a> {
+    fn with_node(&self, node: XPathNode, position: usize, size: usize) -> Context<'a> {
+        Context {
+            node,
+            position,
+            size,
+            namespaces: self.namespaces,
+            is_html: self.is_html,
+        }
+    }
+
+    fn resolve_prefix(&self, prefix: &str) -> EvaluationResult<Namespace> {
+        self.namespaces.get(prefix).cloned().ok_or(EvaluationError::UnresolvedPrefix(String::from(prefix)))
+    }
+}
+
+/// Evaluate an expression with the given node as context node. Namespace
+/// prefixes used by the expression are looked up in `namespaces`.
+pub fn evaluate(expr: &Expr,
+                context_node: &Node,
+                namespaces: &HashMap<String, Namespace>)
+                -> EvaluationResult<Value> {
+    let context = Context {
+        node: XPathNode::Node(DomRoot::from_ref(context_node)),
+        position: 1,
+        size: 1,
+        namespaces,
+        is_html: context_node.owner_doc().is_html_document(),
+    };
+    evaluate_expr(expr, &context)
+}
+
+fn evaluate_expr(expr: &Expr, context: &Context) -> EvaluationResult<Value> {
+    match *expr {
+        Expr::Binary(ref left, operator, ref right) => evaluate_binary(left, operator, right, context),
+        Expr::Negate(ref expr) => Ok(Value::Number(-evaluate_expr(expr, context)?.number())),
+        Expr::Literal(ref literal) => Ok(Value::String(literal.clone())),
+        Expr::Number(number) => Ok(Value::Number(number)),
+        // The DOM API has no way to bind variables.
+        Expr::Variable(ref name) => Err(EvaluationError::UnknownVariable(name.local_name.clone())),
+        Expr::Function(ref name, ref arguments) => evaluate_function(name, arguments, context),
+        Expr::Filter(ref primary, ref predicates) => {
+            let mut nodes = match evaluate_expr(primary, context)? {
+                Value::Nodes(nodes) => nodes,
+                _ => return Err(EvaluationError::NotANodeSet),
+            };
+            for predicate in predicates {
+                nodes = apply_predicate(predicate, nodes, context)?;
+            }
+            Ok(Value::Nodes(nodes))
+        },
+        Expr::Path(ref start, ref steps) => {
+            let mut nodes = match *start {
+                PathStart::Root => {
+                    context.node.node().and_then(|node| node.inclusive_ancestors().last())
+                        .map(XPathNode::Node).into_iter().collect()
+                },
+                PathStart::Context => vec![context.node.clone()],
+                PathStart::Expr(ref expr) => {
+                    match evaluate_expr(expr, context)? {
+                        Value::Nodes(nodes) => nodes,
+                        _ => return Err(EvaluationError::NotANodeSet),
+                    }
+                },
+            };
+            for step in steps {
+                nodes = evaluate_step(step, &nodes, context)?;
+            }
+            Ok(Value::Nodes(nodes))
+        },
+    }
+}
+
+fn evaluate_binary(left: &Expr,
+                   operator: BinaryOperator,
+                   right: &Expr,
+                   context: &Context)
+                   -> EvaluationResult<Value> {
+    // The boolean operators don't evaluate their right operand if the left one
+    // already determines the result.
+    match operator {
+        BinaryOperator::Or => {
+            return Ok(Value::Boolean(evaluate_expr(left, context)?.boolean() ||
+                                     evaluate_expr(right, context)?.boolean()));
+        },
+        BinaryOperator::And => {
+            return Ok(Value::Boolean(evaluate_expr(left, context)?.boolean() &&
+                                     evaluate_expr(right, context)?.boolean()));
+        },
+        _ => {},
+    }
+
+    let left = evaluate_expr(left, context)?;
+    let right = evaluate_expr(right, context)?;
+    Ok(match operator {
+        BinaryOperator::Or | BinaryOperator::And => unreachable!(),
+        BinaryOperator::Equal | BinaryOperator::NotEqual | BinaryOperator::Less |
+        BinaryOperator::LessOrEqual | BinaryOperator::Greater | BinaryOperator::GreaterOrEqual => {
+            Value::Boolean(compare(operator, &left, &right))
+        },
+        BinaryOperator::Add => Value::Number(left.number() + right.number()),
+        BinaryOperator::Subtract => Value::Number(left.number() - right.number()),
+        BinaryOperator::Multiply => Value::Number(left.number() * right.number()),
+        BinaryOperator::Divide => Value::Number(left.number() / right.number()),
+        BinaryOperator::Modulo => Value::Number(left.number() % right.number()),
+        BinaryOperator::Union => {
+            match (left, right) {
+                (Value::Nodes(mut left), Value::Nodes(right)) => {
+                    left.extend(right);
+                    sort_in_document_order(&mut left);
+                    Value::Nodes(left)
+                },
+                _ => return Err(EvaluationError::NotANodeSet),
+            }
+        },
+    })
+}
+
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#booleans>
+fn compare(operator: BinaryOperator, left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (&Value::Nodes(ref left), &Value::Nodes(ref right)) => {
+            let right: Vec<Value> = right.iter().map(|node| Value::String(node.string_value())).collect();
+            left.iter().any(|node| {
+                let left = Value::String(node.string_value());
+                right.iter().any(|right| compare_objects(operator, &left, right))
+            })
+        },
+        (&Value::Nodes(ref nodes), &Value::Boolean(_)) => {
+            compare_objects(operator, &Value::Boolean(!nodes.is_empty()), right)
+        },
+        (&Value::Boolean(_), &Value::Nodes(ref nodes)) => {
+            compare_objects(operator, left, &Value::Boolean(!nodes.is_empty()))
+        },
+        (&Value::Nodes(ref nodes), _) => {
+            nodes.iter().any(|node| compare_objects(operator, &Value::String(node.string_value()), right))
+        },
+        (_, &Value::Nodes(ref nodes)) => {
+            nodes.iter().any(|node| compare_objects(operator, left, &Value::String(node.string_value())))
+        },
+        _ => compare_objects(operator, left, right),
+    }
+}
+
+/// Compare two values which are not node-sets.
+fn compare_objects(operator: BinaryOperator, left: &Value, right: &Value) -> bool {
+    match operator {
+        BinaryOperator::Equal | BinaryOperator::NotEqual => {
+            let equal = match (left, right) {
+                (&Value::Boolean(_), _) | (_, &Value::Boolean(_)) => left.boolean() == right.boolean(),
+                (&Value::Number(_), _) | (_, &Value::Number(_)) => left.number() == right.number(),
+                _ => left.string() == right.string(),
+            };
+            equal == (operator == BinaryOperator::Equal)
+        },
+        BinaryOperator::Less => left.number() < right.number(),
+        BinaryOperator::LessOrEqual => left.number() <= right.number(),
+        BinaryOperator::Greater => left.number() > right.number(),
+        BinaryOperator::GreaterOrEqual => left.number() >= right.number(),
+        _ => unreachable!(),
+    }
+}
+
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#location-paths>
+fn evaluate_step(step: &Step, nodes: &[XPathNode], context: &Context) -> EvaluationResult<Vec<XPathNode>> {
+    let mut result = vec![];
+    for node in nodes {
+        let mut selected = vec![];
+        for candidate in axis_nodes(step.axis, node) {
+            if node_test_matches(&step.node_test, step.axis, &candidate, context)? {
+                selected.push(candidate);
+            }
+        }
+        // Proximity positions follow the direction of the axis.
+        for predicate in &step.predicates {
+            selected = apply_predicate(predicate, selected, context)?;
+        }
+        if step.axis.is_reverse() {
+            selected.reverse();
+        }
+        result.extend(selected);
+    }
+    if nodes.len() > 1 {
+        sort_in_document_order(&mut result);
+    }
+    Ok(result)
+}
+
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#predicates>
+fn apply_predicate(predicate: &Expr, nodes: Vec<XPathNode>, context: &Context) -> EvaluationResult<Vec<XPathNode>> {
+    let size = nodes.len();
+    let mut result = vec![];
+    for (index, node) in nodes.into_iter().enumerate() {
+        let keep = match evaluate_expr(predicate, &context.with_node(node.clone(), index + 1, size))? {
+            Value::Number(position) => position == (index + 1) as f64,
+            value => value.boolean(),
+        };
+        if keep {
+            result.push(node);
+        }
+    }
+    Ok(result)
+}
+
+/// Returns the nodes of an axis, in the order of the axis: reverse axes list
+/// the nodes in reverse document order.
+fn axis_nodes(axis: Axis, node: &XPathNode) -> Vec<XPathNode> {
+    fn nodes<I: Iterator<Item=DomRoot<Node>>>(iterator: I) -> Vec<XPathNode> {
+        iterator.map(XPathNode::Node).collect()
+    }
+
+    fn following(node: &Node) -> Vec<XPathNode> {
+        nodes(node.inclusive_ancestors()
+            .flat_map(|ancestor| ancestor.following_siblings())
+            .flat_map(|sibling| sibling.traverse_preorder()))
+    }
+
+    fn preceding(node: &Node) -> Vec<XPathNode> {
+        let mut result = vec![];
+        for ancestor in node.inclusive_ancestors() {
+            for sibling in ancestor.preceding_siblings() {
+                let mut subtree = nodes(sibling.traverse_preorder());
+                subtree.reverse();
+                result.extend(subtree);
+            }
+        }
+        result
+    }
+
+    let attr = match *node {
+        XPathNode::Node(ref node) => {
+            return match axis {
+                Axis::Ancestor => nodes(node.ancestors()),
+                Axis::AncestorOrSelf => nodes(node.inclusive_ancestors()),
+                Axis::Attribute => {
+                    match node.downcast::<Element>() {
+                        Some(element) => {
+                            // Namespace declarations are not attributes in XPath.
+                            element.attrs().iter()
+                                .filter(|attr| *attr.namespace() != ns!(xmlns))
+                                .map(|attr| XPathNode::Attribute(DomRoot::from_ref(&**attr)))
+                                .collect()
+                        },
+                        None => vec![],
+                    }
+                },
+                Axis::Child => nodes(node.children()),
+                Axis::Descendant => nodes(node.traverse_preorder().skip(1)),
+                Axis::DescendantOrSelf => nodes(node.traverse_preorder()),
+                Axis::Following => following(node),
+                Axis::FollowingSibling => nodes(node.following_siblings()),
+                // Namespace nodes are not supported.
+                Axis::Namespace => vec![],
+                Axis::Parent => nodes(node.GetParentNode().into_iter()),
+                Axis::Preceding => preceding(node),
+                Axis::PrecedingSibling => nodes(node.preceding_siblings()),
+                Axis::Self_ => vec![XPathNode::Node(node.clone())],
+            };
+        },
+        XPathNode::Attribute(ref attr) => attr,
+    };
+
+    // The parent of an attribute is its element, but the attribute is not a
+    // child of the element.
+    let owner = match attr.owner() {
+        Some(owner) => DomRoot::upcast::<Node>(owner),
+        None => {
+            return match axis {
+                Axis::AncestorOrSelf | Axis::Self_ => vec![node.clone()],
+                _ => vec![],
+            };
+        },
+    };
+    match axis {
+        Axis::Ancestor => nodes(owner.inclusive_ancestors()),
+        Axis::AncestorOrSelf => {
+            let mut result = vec![node.clone()];
+            result.extend(nodes(owner.inclusive_ancestors()));
+            result
+        },
+        Axis::Following => {
+            let mut result = nodes(owner.traverse_preorder().skip(1));
+            result.extend(following(&owner));
+            result
+        },
+        Axis::Parent => vec![XPathNode::Node(owner)],
+        Axis::Preceding => preceding(&owner),
+        Axis::Self_ => vec![node.clone()],
+        Axis::Attribute | Axis::Child | Axis::Descendant | Axis::DescendantOrSelf |
+        Axis::FollowingSibling | Axis::Namespace | Axis::PrecedingSibling => vec![],
+    }
+}
+
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#node-tests>
+fn node_test_matches(test: &NodeTest, axis: Axis, node: &XPathNode, context: &Context) -> EvaluationResult<bool> {
+    let dom_node = match *node {
+        XPathNode::Node(ref node) => node,
+        XPathNode::Attribute(ref attr) => {
+            // Attributes are only found on the attribute axis, whose principal
+            // node type is attribute.
+            return Ok(match *test {
+                NodeTest::Kind(KindTest::Node) => true,
+                NodeTest::Kind(_) => false,
+                NodeTest::Wildcard(None) => true,
+                NodeTest::Wildcard(Some(ref prefix)) => *attr.namespace() == context.resolve_prefix(prefix)?,
+                NodeTest::Name(ref name) => {
+                    let namespace = match name.prefix {
+                        Some(ref prefix) => context.resolve_prefix(prefix)?,
+                        None => ns!(),
+                    };
+                    let is_html_element = attr.owner().map_or(false, |owner| owner.is_html_element());
+                    *attr.namespace() == namespace &&
+                        name_matches(attr.local_name(), &name.local_name, context.is_html && is_html_element)
+                },
+            });
+        },
+    };
+
+    match *test {
+        NodeTest::Kind(KindTest::Node) => Ok(true),
+        NodeTest::Kind(KindTest::Text) => Ok(dom_node.is::<Text>()),
+        NodeTest::Kind(KindTest::Comment) => Ok(dom_node.is::<Comment>()),
+        NodeTest::Kind(KindTest::ProcessingInstruction(ref target)) => {
+            Ok(match dom_node.downcast::<ProcessingInstruction>() {
+                Some(pi) => target.as_ref().map_or(true, |target| **pi.target() == **target),
+                None => false,
+            })
+        },
+        _ if axis == Axis::Attribute || axis == Axis::Namespace => Ok(false),
+        NodeTest::Wildcard(ref prefix) => {
+            let element = match dom_node.downcast::<Element>() {
+                Some(element) => element,
+                None => return Ok(false),
+            };
+            match *prefix {
+                Some(ref prefix) => Ok(*element.namespace() == context.resolve_prefix(prefix)?),
+                None => Ok(true),
+            }
+        },
+        NodeTest::Name(ref name) => {
+            let element = match dom_node.downcast::<Element>() {
+                Some(element) => element,
+                None => return Ok(false),
+            };
+            match name.prefix {
+                Some(ref prefix) => {
+                    Ok(*element.namespace() == context.resolve_prefix(prefix)? &&
+                       **element.local_name() == *name.local_name)
+                },
+                // In HTML documents, unprefixed names match HTML elements
+                // ignoring case, as the default element namespace is the HTML
+                // namespace.
+                None if context.is_html && *element.namespace() == ns!(html) => {
+                    Ok(name_matches(element.local_name(), &name.local_name, true))
+                },
+                None => Ok(*element.namespace() == ns!() && **element.local_name() == *name.local_name),
+            }
+        },
+    }
+}
+
+fn name_matches(local_name: &LocalName, name: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        local_name.eq_ignore_ascii_case(name)
+    } else {
+        **local_name == *name
+    }
+}
+
+/// Sort nodes in document order and remove duplicates.
+fn sort_in_document_order(nodes: &mut Vec<XPathNode>) {
+    if nodes.len() < 2 {
+        return;
+    }
+
+    // Number the nodes of every tree in tree order, which is cheaper than
+    // comparing the position of each pair of nodes.
+    let mut indices = HashMap::new();
+    let mut next_index = 0;
+    let mut key = |node: &XPathNode| -> (usize, usize) {
+        let dom_node = match node.node() {
+            Some(dom_node) => dom_node,
+            None => return (usize::max_value(), 0),
+        };
+        let address = &*dom_node as *const Node;
+        if !indices.contains_key(&address) {
+            for descendant in dom_node.inclusive_ancestors().last().unwrap().traverse_preorder() {
+                indices.insert(&*descendant as *const Node, next_index);
+                next_index += 1;
+            }
+        }
+        // Attributes follow their element, in the order of its attribute list.
+        let attribute_index = match *node {
+            XPathNode::Node(_) => 0,
+            XPathNode::Attribute(ref attr) => {
+                dom_node.downcast::<Element>().unwrap().attrs().iter()
+                    .position(|candidate| &**candidate as *const Attr == &**attr as *const Attr)
+                    .map_or(0, |position| position + 1)
+            },
+        };
+        (indices[&address], attribute_index)
+    };
+
+    let mut keyed: Vec<((usize, usize), XPathNode)> = nodes.drain(..).map(|node| (key(&node), node)).collect();
+    keyed.sort_by_key(|&(key, _)| key);
+    keyed.dedup_by(|a, b| a.1 == b.1);
+    nodes.extend(keyed.into_iter().map(|(_, node)| node));
+}
+
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#corelib>
+fn evaluate_function(name: &QName, arguments: &[Expr], context: &Context) -> EvaluationResult<Value> {
+    if name.prefix.is_some() {
+        return Err(EvaluationError::UnknownFunction(name.local_name.clone()));
+    }
+
+    let argument = |index: usize| -> EvaluationResult<Value> {
+        evaluate_expr(&arguments[index], context)
+    };
+    let string_argument = |index: usize| -> EvaluationResult<String> {
+        if index < arguments.len() {
+            Ok(argument(index)?.string())
+        } else {
+            Ok(context.node.string_value())
+        }
+    };
+    let node_argument = |index: usize| -> EvaluationResult<Option<XPathNode>> {
+        if index < arguments.len() {
+            match argument(index)? {
+                Value::Nodes(nodes) => Ok(nodes.into_iter().next()),
+                _ => Err(EvaluationError::NotANodeSet),
+            }
+        } else {
+            Ok(Some(context.node.clone()))
+        }
+    };
+
+    Ok(match &*name.local_name {
+        // Node set functions.
+        "last" => Value::Number(context.size as f64),
+        "position" => Value::Number(context.position as f64),
+        "count" => {
+            match argument(0)? {
+                Value::Nodes(nodes) => Value::Number(nodes.len() as f64),
+                _ => return Err(EvaluationError::NotANodeSet),
+            }
+        },
+        "id" => {
+            let ids = match argument(0)? {
+                Value::Nodes(nodes) => nodes.iter().map(|node| node.string_value()).collect::<Vec<_>>().join(" "),
+                value => value.string(),
+            };
+            let root = match context.node.node().and_then(|node| node.inclusive_ancestors().last()) {
+                Some(root) => root,
+                None => return Ok(Value::Nodes(vec![])),
+            };
+            let ids: Vec<&str> = ids.split(is_xml_whitespace).filter(|id| !id.is_empty()).collect();
+            let mut found: Vec<String> = vec![];
+            let mut nodes = vec![];
+            for element in root.traverse_preorder().filter_map(DomRoot::downcast::<Element>) {
+                let id = String::from(element.Id());
+                if ids.contains(&&*id) && !found.contains(&id) {
+                    found.push(id);
+                    nodes.push(XPathNode::Node(DomRoot::upcast(element)));
+                }
+            }
+            Value::Nodes(nodes)
+        },
+        "local-name" => {
+            Value::String(match node_argument(0)? {
+                Some(XPathNode::Node(node)) => {
+                    if let Some(element) = node.downcast::<Element>() {
+                        String::from(&**element.local_name())
+                    } else if let Some(pi) = node.downcast::<ProcessingInstruction>() {
+                        String::from(pi.target().clone())
+                    } else {
+                        String::new()
+                    }
+                },
+                Some(XPathNode::Attribute(attr)) => String::from(&**attr.local_name()),
+                None => String::new(),
+            })
+        },
+        "namespace-uri" => {
+            Value::String(match node_argument(0)? {
+                Some(XPathNode::Node(node)) => {
+                    node.downcast::<Element>().map_or(String::new(), |element| String::from(&**element.namespace()))
+                },
+                Some(XPathNode::Attribute(attr)) => String::from(&**attr.namespace()),
+                None => String::new(),
+            })
+        },
+        "name" => {
+            Value::String(match node_argument(0)? {
+                Some(XPathNode::Node(node)) => {
+                    if let Some(element) = node.downcast::<Element>() {
+                        match *element.prefix() {
+                            Some(ref prefix) => format!("{}:{}", &**prefix, &**element.local_name()),
+                            None => String::from(&**element.local_name()),
+                        }
+                    } else if let Some(pi) = node.downcast::<ProcessingInstruction>() {
+                        String::from(pi.target().clone())
+                    } else {
+                        String::new()
+                    }
+                },
+                Some(XPathNode::Attribute(attr)) => String::from(attr.Name()),
+                None => String::new(),
+            })
+        },
+
+        // String functions.
+        "string" => Value::String(string_argument(0)?),
+        "concat" => {
+            let mut result = String::new();
+            for index in 0..arguments.len() {
+                result.push_str(&string_argument(index)?);
+            }
+            Value::String(result)
+        },
+        "starts-with" => Value::Boolean(string_argument(0)?.starts_with(&*string_argument(1)?)),
+        "contains" => Value::Boolean(string_argument(0)?.contains(&*string_argument(1)?)),
+        "substring-before" => {
+            let (string, pattern) = (string_argument(0)?, string_argument(1)?);
+            Value::String(string.find(&*pattern).map_or(String::new(), |index| String::from(&string[..index])))
+        },
+        "substring-after" => {
+            let (string, pattern) = (string_argument(0)?, string_argument(1)?);
+            Value::String(string.find(&*pattern)
+                .map_or(String::new(), |index| String::from(&string[index + pattern.len()..])))
+        },
+        "substring" => {
+            let string = string_argument(0)?;
+            let start = round(argument(1)?.number());
+            let end = if arguments.len() > 2 {
+                start + round(argument(2)?.number())
+            } else {
+                f64::INFINITY
+            };
+            Value::String(string.chars().enumerate().filter(|&(index, _)| {
+                let position = (index + 1) as f64;
+                position >= start && position < end
+            }).map(|(_, c)| c).collect())
+        },
+        "string-length" => Value::Number(string_argument(0)?.chars().count() as f64),
+        "normalize-space" => {
+            Value::String(string_argument(0)?.split(is_xml_whitespace)
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "))
+        },
+        "translate" => {
+            let string = string_argument(0)?;
+            let from: Vec<char> = string_argument(1)?.chars().collect();
+            let to: Vec<char> = string_argument(2)?.chars().collect();
+            Value::String(string.chars().filter_map(|c| {
+                match from.iter().position(|&from| from == c) {
+                    Some(index) => to.get(index).cloned(),
+                    None => Some(c),
+                }
+            }).collect())
+        },
+
+        // Boolean functions.
+        "boolean" => Value::Boolean(argument(0)?.boolean()),
+        "not" => Value::Boolean(!argument(0)?.boolean()),
+        "true" => Value::Boolean(true),
+        "false" => Value::Boolean(false),
+        "lang" => {
+            let lang = argument(0)?.string().to_ascii_lowercase();
+            let language = context.node.node().and_then(|node| {
+                node.inclusive_ancestors()
+                    .filter_map(DomRoot::downcast::<Element>)
+                    .filter_map(|element| element.get_attribute(&ns!(xml), &local_name!("lang")))
+                    .next()
+            }).map(|attr| attr.value().to_ascii_lowercase());
+            Value::Boolean(language.map_or(false, |language| {
+                language == lang || language.starts_with(&format!("{}-", lang))
+            }))
+        },
+
+        // Number functions.
+        "number" => {
+            if arguments.is_empty() {
+                Value::Number(string_to_number(&context.node.string_value()))
+            } else {
+                Value::Number(argument(0)?.number())
+            }
+        },
+        "sum" => {
+            match argument(0)? {
+                Value::Nodes(nodes) => {
+                    Value::Number(nodes.iter().map(|node| string_to_number(&node.string_value())).sum())
+                },
+                _ => return Err(EvaluationError::NotANodeSet),
+            }
+        },
+        "floor" => Value::Number(argument(0)?.number().floor()),
+        "ceiling" => Value::Number(argument(0)?.number().ceil()),
+        "round" => Value::Number(round(argument(0)?.number())),
+        _ => return Err(EvaluationError::UnknownFunction(name.local_name.clone())),
+    })
+}
+
+fn is_xml_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\r' || c == '\n'
+}
+
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#function-round>
+fn round(number: f64) -> f64 {
+    if number.is_nan() || number.is_infinite() || number == 0. {
+        return number;
+    }
+    // Numbers in [-0.5, 0) round to negative zero.
+    if number < 0. && number >= -0.5 {
+        return -0.;
+    }
+    (number + 0.5).floor()
+}
+
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#function-number>
+fn string_to_number(string: &str) -> f64 {
+    let string = string.trim_matches(is_xml_whitespace);
+    let digits = if string.starts_with('-') { &string[1..] } else { string };
+    let valid = !digits.is_empty() && digits != "." &&
+        digits.chars().all(|c| c.is_digit(10) || c == '.') &&
+        digits.matches('.').count() <= 1;
+    if !valid {
+        return f64::NAN;
+    }
+    string.parse().unwrap_or(f64::NAN)
+}
+
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#function-string>
+fn number_to_string(number: f64) -> String {
+    if number.is_nan() {
+        String::from("NaN")
+    } else if number == 0. {
+        // Both positive and negative zero.
+        String::from("0")
+    } else if number.is_infinite() {
+        String::from(if number > 0. { "Infinity" } else { "-Infinity" })
+    } else {
+        format!("{}", number)
+    }
+}
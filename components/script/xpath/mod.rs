@@ -0,0 +1,12 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! An implementation of [XPath 1.0](https://www.w3.org/TR/1999/REC-xpath-19991116/)
+//! operating on DOM trees, used by `document.evaluate` and WebDriver.
+
+mod eval;
+mod parser;
+
+pub use self::eval::{EvaluationError, Value, XPathNode, evaluate};
+pub use self::parser::{Axis, BinaryOperator, Expr, KindTest, NodeTest, ParseError, PathStart, QName, Step, parse};
@@ -0,0 +1,767 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Tokenizer and recursive descent parser for XPath 1.0 expressions.
+//!
+//! <https://www.w3.org/TR/1999/REC-xpath-19991116/#exprlex>
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Clone, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum Expr {
+    Binary(Box<Expr>, BinaryOperator, Box<Expr>),
+    Negate(Box<Expr>),
+    Literal(String),
+    Number(f64),
+    Variable(QName),
+    Function(QName, Vec<Expr>),
+    /// A primary expression followed by predicates.
+    Filter(Box<Expr>, Vec<Expr>),
+    Path(PathStart, Vec<Step>),
+}
+
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Union,
+}
+
+#[derive(Clone, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum PathStart {
+    /// `/`: the root of the tree containing the context node.
+    Root,
+    /// A relative location path.
+    Context,
+    /// A filter expression followed by `/` or `//`.
+    Expr(Box<Expr>),
+}
+
+#[derive(Clone, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub struct Step {
+    pub axis: Axis,
+    pub node_test: NodeTest,
+    pub predicates: Vec<Expr>,
+}
+
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum Axis {
+    Ancestor,
+    AncestorOrSelf,
+    Attribute,
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Following,
+    FollowingSibling,
+    Namespace,
+    Parent,
+    Preceding,
+    PrecedingSibling,
+    Self_,
+}
+
+impl Axis {
+    fn from_name(name: &str) -> Option<Axis> {
+        Some(match name {
+            "ancestor" => Axis::Ancestor,
+            "ancestor-or-self" => Axis::AncestorOrSelf,
+            "attribute" => Axis::Attribute,
+            "child" => Axis::Child,
+            "descendant" => Axis::Descendant,
+            "descendant-or-self" => Axis::DescendantOrSelf,
+            "following" => Axis::Following,
+            "following-sibling" => Axis::FollowingSibling,
+            "namespace" => Axis::Namespace,
+            "parent" => Axis::Parent,
+            "preceding" => Axis::Preceding,
+            "preceding-sibling" => Axis::PrecedingSibling,
+            "self" => Axis::Self_,
+            _ => return None,
+        })
+    }
+
+    /// <https://www.w3.org/TR/1999/REC-xpath-19991116/#predicates>
+    pub fn is_reverse(&self) -> bool {
+        match *self {
+            Axis::Ancestor | Axis::AncestorOrSelf | Axis::Preceding | Axis::PrecedingSibling => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum NodeTest {
+    /// A QName, matching nodes of the principal node type of the axis.
+    Name(QName),
+    /// `*` or `prefix:*`.
+    Wildcard(Option<String>),
+    Kind(KindTest),
+}
+
+#[derive(Clone, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum KindTest {
+    Node,
+    Text,
+    Comment,
+    ProcessingInstruction(Option<String>),
+}
+
+#[derive(Clone, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub struct QName {
+    pub prefix: Option<String>,
+    pub local_name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    UnexpectedCharacter(char),
+    UnterminatedLiteral,
+    UnexpectedToken,
+    UnexpectedEnd,
+    UnknownAxis(String),
+    UnknownOperator(String),
+    UnknownFunction(String),
+    WrongArgumentCount(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Dot,
+    DotDot,
+    At,
+    Comma,
+    DoubleColon,
+    Slash,
+    DoubleSlash,
+    Pipe,
+    Plus,
+    Minus,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Multiply,
+    And,
+    Or,
+    Mod,
+    Div,
+    Literal(String),
+    Number(f64),
+    Variable(QName),
+    NodeType(String),
+    FunctionName(QName),
+    AxisName(Axis),
+    NameTest(NodeTest),
+}
+
+impl Token {
+    /// Whether a `*` or a name following this token must be read as an
+    /// operator.
+    ///
+    /// <https://www.w3.org/TR/1999/REC-xpath-19991116/#exprlex>
+    fn precedes_operator(&self) -> bool {
+        match *self {
+            Token::At | Token::DoubleColon | Token::LeftParen | Token::LeftBracket | Token::Comma |
+            Token::Slash | Token::DoubleSlash | Token::Pipe | Token::Plus | Token::Minus |
+            Token::Equal | Token::NotEqual | Token::Less | Token::LessOrEqual | Token::Greater |
+            Token::GreaterOrEqual | Token::Multiply | Token::And | Token::Or | Token::Mod |
+            Token::Div => false,
+            _ => true,
+        }
+    }
+}
+
+fn is_xml_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\r' || c == '\n'
+}
+
+fn is_name_start_char(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '.' || c == '-' || c == '_' || c == '\u{B7}'
+}
+
+struct Tokenizer<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    tokens: Vec<Token>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn peek_char(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    /// Returns the next character which isn't whitespace, without consuming it.
+    fn peek_non_whitespace(&mut self) -> Option<char> {
+        self.remaining().chars().find(|&c| !is_xml_whitespace(c))
+    }
+
+    fn remaining(&mut self) -> &'a str {
+        let position = self.chars.peek().map_or(self.input.len(), |&(i, _)| i);
+        &self.input[position..]
+    }
+
+    fn next_is(&mut self, c: char) -> bool {
+        if self.peek_char() == Some(c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn read_ncname(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek_char() {
+            if !is_name_char(c) {
+                break;
+            }
+            name.push(c);
+            self.chars.next();
+        }
+        name
+    }
+
+    fn read_qname(&mut self) -> Result<QName, ParseError> {
+        match self.peek_char() {
+            Some(c) if is_name_start_char(c) => {},
+            Some(c) => return Err(ParseError::UnexpectedCharacter(c)),
+            None => return Err(ParseError::UnexpectedEnd),
+        }
+        let first = self.read_ncname();
+        if self.remaining().starts_with(':') && !self.remaining().starts_with("::") {
+            self.chars.next();
+            match self.peek_char() {
+                Some(c) if is_name_start_char(c) => {},
+                Some(c) => return Err(ParseError::UnexpectedCharacter(c)),
+                None => return Err(ParseError::UnexpectedEnd),
+            }
+            return Ok(QName { prefix: Some(first), local_name: self.read_ncname() });
+        }
+        Ok(QName { prefix: None, local_name: first })
+    }
+
+    fn read_number(&mut self) -> Token {
+        let mut number = String::from("0");
+        while let Some(c) = self.peek_char() {
+            if !c.is_digit(10) && c != '.' {
+                break;
+            }
+            if c == '.' && number.contains('.') {
+                break;
+            }
+            number.push(c);
+            self.chars.next();
+        }
+        Token::Number(number.parse().unwrap_or(0.))
+    }
+
+    fn read_literal(&mut self, quote: char) -> Result<Token, ParseError> {
+        let mut literal = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, c)) if c == quote => return Ok(Token::Literal(literal)),
+                Some((_, c)) => literal.push(c),
+                None => return Err(ParseError::UnterminatedLiteral),
+            }
+        }
+    }
+
+    fn read_name(&mut self) -> Result<Token, ParseError> {
+        let operator_expected = self.tokens.last().map_or(false, |token| token.precedes_operator());
+        let first = self.read_ncname();
+
+        if operator_expected {
+            return match &*first {
+                "and" => Ok(Token::And),
+                "or" => Ok(Token::Or),
+                "mod" => Ok(Token::Mod),
+                "div" => Ok(Token::Div),
+                _ => Err(ParseError::UnknownOperator(first)),
+            };
+        }
+
+        if self.remaining().trim_left_matches(is_xml_whitespace).starts_with("::") {
+            return Axis::from_name(&first)
+                .map(Token::AxisName)
+                .ok_or(ParseError::UnknownAxis(first));
+        }
+
+        let name = if self.remaining().starts_with(':') {
+            self.chars.next();
+            if self.next_is('*') {
+                return Ok(Token::NameTest(NodeTest::Wildcard(Some(first))));
+            }
+            match self.peek_char() {
+                Some(c) if is_name_start_char(c) => {},
+                Some(c) => return Err(ParseError::UnexpectedCharacter(c)),
+                None => return Err(ParseError::UnexpectedEnd),
+            }
+            QName { prefix: Some(first), local_name: self.read_ncname() }
+        } else {
+            QName { prefix: None, local_name: first }
+        };
+
+        if self.peek_non_whitespace() == Some('(') {
+            if name.prefix.is_none() {
+                match &*name.local_name {
+                    "comment" | "text" | "processing-instruction" | "node" => {
+                        return Ok(Token::NodeType(name.local_name));
+                    },
+                    _ => {},
+                }
+            }
+            return Ok(Token::FunctionName(name));
+        }
+
+        Ok(Token::NameTest(NodeTest::Name(name)))
+    }
+
+    fn next_token(&mut self, c: char) -> Result<Token, ParseError> {
+        if is_name_start_char(c) {
+            return self.read_name();
+        }
+        if c.is_digit(10) || (c == '.' && self.remaining()[1..].starts_with(|d: char| d.is_digit(10))) {
+            return Ok(self.read_number());
+        }
+
+        self.chars.next();
+        Ok(match c {
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            '@' => Token::At,
+            ',' => Token::Comma,
+            '|' => Token::Pipe,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '=' => Token::Equal,
+            '"' | '\'' => return self.read_literal(c),
+            '$' => return self.read_qname().map(Token::Variable),
+            '.' if self.next_is('.') => Token::DotDot,
+            '.' => Token::Dot,
+            ':' if self.next_is(':') => Token::DoubleColon,
+            '/' if self.next_is('/') => Token::DoubleSlash,
+            '/' => Token::Slash,
+            '!' if self.next_is('=') => Token::NotEqual,
+            '<' if self.next_is('=') => Token::LessOrEqual,
+            '<' => Token::Less,
+            '>' if self.next_is('=') => Token::GreaterOrEqual,
+            '>' => Token::Greater,
+            '*' => {
+                if self.tokens.last().map_or(false, |token| token.precedes_operator()) {
+                    Token::Multiply
+                } else {
+                    Token::NameTest(NodeTest::Wildcard(None))
+                }
+            },
+            _ => return Err(ParseError::UnexpectedCharacter(c)),
+        })
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>, ParseError> {
+        while let Some(c) = self.peek_char() {
+            if is_xml_whitespace(c) {
+                self.chars.next();
+                continue;
+            }
+            let token = self.next_token(c)?;
+            self.tokens.push(token);
+        }
+        Ok(self.tokens)
+    }
+}
+
+/// The arity of each function of the core function library, as the minimum
+/// and maximum number of arguments.
+///
+/// <https://www.w3.org/TR/1999/REC-xpath-19991116/#corelib>
+fn function_arity(name: &str) -> Option<(usize, usize)> {
+    Some(match name {
+        "last" | "position" | "true" | "false" => (0, 0),
+        "count" | "id" | "sum" | "not" | "boolean" | "floor" | "ceiling" | "round" | "lang" => (1, 1),
+        "local-name" | "namespace-uri" | "name" | "string" | "string-length" |
+        "normalize-space" | "number" => (0, 1),
+        "starts-with" | "contains" | "substring-before" | "substring-after" => (2, 2),
+        "substring" => (2, 3),
+        "translate" => (3, 3),
+        "concat" => (2, usize::max_value()),
+        _ => return None,
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_is(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), ParseError> {
+        match self.next() {
+            Some(ref next) if next == token => Ok(()),
+            Some(_) => Err(ParseError::UnexpectedToken),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    /// Parse a left-associative sequence of operands separated by the given
+    /// operators.
+    fn parse_binary<F>(&mut self,
+                       operators: &[(Token, BinaryOperator)],
+                       parse_operand: F)
+                       -> Result<Expr, ParseError>
+        where F: Fn(&mut Parser) -> Result<Expr, ParseError>
+    {
+        let mut expr = parse_operand(self)?;
+        'operands: loop {
+            for &(ref token, operator) in operators {
+                if self.next_is(token) {
+                    let right = parse_operand(self)?;
+                    expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
+                    continue 'operands;
+                }
+            }
+            return Ok(expr);
+        }
+    }
+
+    fn parse_or_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&[(Token::Or, BinaryOperator::Or)], Parser::parse_and_expr)
+    }
+
+    fn parse_and_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&[(Token::And, BinaryOperator::And)], Parser::parse_equality_expr)
+    }
+
+    fn parse_equality_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&[
+            (Token::Equal, BinaryOperator::Equal),
+            (Token::NotEqual, BinaryOperator::NotEqual),
+        ], Parser::parse_relational_expr)
+    }
+
+    fn parse_relational_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&[
+            (Token::Less, BinaryOperator::Less),
+            (Token::LessOrEqual, BinaryOperator::LessOrEqual),
+            (Token::Greater, BinaryOperator::Greater),
+            (Token::GreaterOrEqual, BinaryOperator::GreaterOrEqual),
+        ], Parser::parse_additive_expr)
+    }
+
+    fn parse_additive_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&[
+            (Token::Plus, BinaryOperator::Add),
+            (Token::Minus, BinaryOperator::Subtract),
+        ], Parser::parse_multiplicative_expr)
+    }
+
+    fn parse_multiplicative_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&[
+            (Token::Multiply, BinaryOperator::Multiply),
+            (Token::Div, BinaryOperator::Divide),
+            (Token::Mod, BinaryOperator::Modulo),
+        ], Parser::parse_unary_expr)
+    }
+
+    fn parse_unary_expr(&mut self) -> Result<Expr, ParseError> {
+        if self.next_is(&Token::Minus) {
+            return Ok(Expr::Negate(Box::new(self.parse_unary_expr()?)));
+        }
+        self.parse_union_expr()
+    }
+
+    fn parse_union_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&[(Token::Pipe, BinaryOperator::Union)], Parser::parse_path_expr)
+    }
+
+    fn parse_path_expr(&mut self) -> Result<Expr, ParseError> {
+        let starts_filter_expr = match self.peek() {
+            Some(&Token::Variable(_)) | Some(&Token::LeftParen) | Some(&Token::Literal(_)) |
+            Some(&Token::Number(_)) | Some(&Token::FunctionName(_)) => true,
+            _ => false,
+        };
+        if !starts_filter_expr {
+            return self.parse_location_path();
+        }
+
+        let primary = self.parse_primary_expr()?;
+        let predicates = self.parse_predicates()?;
+        let filter = if predicates.is_empty() {
+            primary
+        } else {
+            Expr::Filter(Box::new(primary), predicates)
+        };
+
+        let mut steps = vec![];
+        if self.next_is(&Token::DoubleSlash) {
+            steps.push(descendant_or_self_step());
+        } else if !self.next_is(&Token::Slash) {
+            return Ok(filter);
+        }
+        self.parse_relative_location_path(&mut steps)?;
+        Ok(Expr::Path(PathStart::Expr(Box::new(filter)), steps))
+    }
+
+    fn parse_location_path(&mut self) -> Result<Expr, ParseError> {
+        let mut steps = vec![];
+        if self.next_is(&Token::Slash) {
+            if self.starts_step() {
+                self.parse_relative_location_path(&mut steps)?;
+            }
+            return Ok(Expr::Path(PathStart::Root, steps));
+        }
+        if self.next_is(&Token::DoubleSlash) {
+            steps.push(descendant_or_self_step());
+            self.parse_relative_location_path(&mut steps)?;
+            return Ok(Expr::Path(PathStart::Root, steps));
+        }
+        self.parse_relative_location_path(&mut steps)?;
+        Ok(Expr::Path(PathStart::Context, steps))
+    }
+
+    fn starts_step(&self) -> bool {
+        match self.peek() {
+            Some(&Token::Dot) | Some(&Token::DotDot) | Some(&Token::At) | Some(&Token::AxisName(_)) |
+            Some(&Token::NodeType(_)) | Some(&Token::NameTest(_)) => true,
+            _ => false,
+        }
+    }
+
+    fn parse_relative_location_path(&mut self, steps: &mut Vec<Step>) -> Result<(), ParseError> {
+        loop {
+            steps.push(self.parse_step()?);
+            if self.next_is(&Token::DoubleSlash) {
+                steps.push(descendant_or_self_step());
+            } else if !self.next_is(&Token::Slash) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_step(&mut self) -> Result<Step, ParseError> {
+        if self.next_is(&Token::Dot) {
+            return Ok(Step { axis: Axis::Self_, node_test: NodeTest::Kind(KindTest::Node), predicates: vec![] });
+        }
+        if self.next_is(&Token::DotDot) {
+            return Ok(Step { axis: Axis::Parent, node_test: NodeTest::Kind(KindTest::Node), predicates: vec![] });
+        }
+
+        let axis = match self.peek().cloned() {
+            Some(Token::At) => {
+                self.position += 1;
+                Axis::Attribute
+            },
+            Some(Token::AxisName(axis)) => {
+                self.position += 1;
+                self.expect(&Token::DoubleColon)?;
+                axis
+            },
+            _ => Axis::Child,
+        };
+
+        let node_test = match self.next() {
+            Some(Token::NameTest(test)) => test,
+            Some(Token::NodeType(node_type)) => {
+                self.expect(&Token::LeftParen)?;
+                let test = match &*node_type {
+                    "comment" => KindTest::Comment,
+                    "text" => KindTest::Text,
+                    "node" => KindTest::Node,
+                    _ => {
+                        match self.peek().cloned() {
+                            Some(Token::Literal(target)) => {
+                                self.position += 1;
+                                KindTest::ProcessingInstruction(Some(target))
+                            },
+                            _ => KindTest::ProcessingInstruction(None),
+                        }
+                    },
+                };
+                self.expect(&Token::RightParen)?;
+                NodeTest::Kind(test)
+            },
+            Some(_) => return Err(ParseError::UnexpectedToken),
+            None => return Err(ParseError::UnexpectedEnd),
+        };
+
+        let predicates = self.parse_predicates()?;
+        Ok(Step { axis, node_test, predicates })
+    }
+
+    fn parse_predicates(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut predicates = vec![];
+        while self.next_is(&Token::LeftBracket) {
+            predicates.push(self.parse_or_expr()?);
+            self.expect(&Token::RightBracket)?;
+        }
+        Ok(predicates)
+    }
+
+    fn parse_primary_expr(&mut self) -> Result<Expr, ParseError> {
+        match self.next() {
+            Some(Token::Variable(name)) => Ok(Expr::Variable(name)),
+            Some(Token::Literal(literal)) => Ok(Expr::Literal(literal)),
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::LeftParen) => {
+                let expr = self.parse_or_expr()?;
+                self.expect(&Token::RightParen)?;
+                Ok(expr)
+            },
+            Some(Token::FunctionName(name)) => {
+                self.expect(&Token::LeftParen)?;
+                let mut arguments = vec![];
+                if !self.next_is(&Token::RightParen) {
+                    loop {
+                        arguments.push(self.parse_or_expr()?);
+                        if self.next_is(&Token::RightParen) {
+                            break;
+                        }
+                        self.expect(&Token::Comma)?;
+                    }
+                }
+
+                let qualified_name = match name.prefix {
+                    Some(ref prefix) => format!("{}:{}", prefix, name.local_name),
+                    None => name.local_name.clone(),
+                };
+                let (min, max) = match name.prefix {
+                    None => function_arity(&name.local_name),
+                    // There are no extension functions.
+                    Some(_) => None,
+                }.ok_or(ParseError::UnknownFunction(qualified_name.clone()))?;
+                if arguments.len() < min || arguments.len() > max {
+                    return Err(ParseError::WrongArgumentCount(qualified_name));
+                }
+                Ok(Expr::Function(name, arguments))
+            },
+            Some(_) => Err(ParseError::UnexpectedToken),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+}
+
+/// The step `//` abbreviates.
+fn descendant_or_self_step() -> Step {
+    Step {
+        axis: Axis::DescendantOrSelf,
+        node_test: NodeTest::Kind(KindTest::Node),
+        predicates: vec![],
+    }
+}
+
+/// Parse an XPath 1.0 expression.
+pub fn parse(input: &str) -> Result<Expr, ParseError> {
+    let tokenizer = Tokenizer {
+        input,
+        chars: input.char_indices().peekable(),
+        tokens: vec![],
+    };
+    let mut parser = Parser {
+        tokens: tokenizer.tokenize()?,
+        position: 0,
+    };
+    let expr = parser.parse_or_expr()?;
+    if parser.peek().is_some() {
+        return Err(ParseError::UnexpectedToken);
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /// Returns the namespace prefixes used by the name tests of this
+    /// expression, which need to be resolved before evaluating it.
+    pub fn prefixes(&self) -> Vec<String> {
+        fn collect_from_steps(steps: &[Step], prefixes: &mut Vec<String>) {
+            for step in steps {
+                match step.node_test {
+                    NodeTest::Name(QName { prefix: Some(ref prefix), .. }) |
+                    NodeTest::Wildcard(Some(ref prefix)) => {
+                        if !prefixes.contains(prefix) {
+                            prefixes.push(prefix.clone());
+                        }
+                    },
+                    _ => {},
+                }
+                for predicate in &step.predicates {
+                    collect(predicate, prefixes);
+                }
+            }
+        }
+
+        fn collect(expr: &Expr, prefixes: &mut Vec<String>) {
+            match *expr {
+                Expr::Binary(ref left, _, ref right) => {
+                    collect(left, prefixes);
+                    collect(right, prefixes);
+                },
+                Expr::Negate(ref expr) => collect(expr, prefixes),
+                Expr::Function(_, ref arguments) => {
+                    for argument in arguments {
+                        collect(argument, prefixes);
+                    }
+                },
+                Expr::Filter(ref primary, ref predicates) => {
+                    collect(primary, prefixes);
+                    for predicate in predicates {
+                        collect(predicate, prefixes);
+                    }
+                },
+                Expr::Path(ref start, ref steps) => {
+                    if let PathStart::Expr(ref expr) = *start {
+                        collect(expr, prefixes);
+                    }
+                    collect_from_steps(steps, prefixes);
+                },
+                Expr::Literal(_) | Expr::Number(_) | Expr::Variable(_) => {},
+            }
+        }
+
+        let mut prefixes = vec![];
+        collect(self, &mut prefixes);
+        prefixes
+    }
+}
@@ -33,6 +33,7 @@ extern crate servo_arc;
 extern crate servo_atoms;
 extern crate servo_url;
 extern crate style;
+extern crate svg_renderer;
 extern crate webrender_api;
 
 pub mod message;
@@ -48,8 +49,11 @@ use net_traits::image_cache::PendingImageId;
 use script_traits::UntrustedNodeAddress;
 use servo_url::ServoUrl;
 use std::ptr::NonNull;
+use std::sync::Arc;
 use std::sync::atomic::AtomicIsize;
 use style::data::ElementData;
+use style::dom::OpaqueNode;
+use svg_renderer::SvgImage;
 
 #[repr(C)]
 pub struct StyleData {
@@ -139,6 +143,10 @@ pub struct HTMLCanvasData {
 pub struct SVGSVGData {
     pub width: u32,
     pub height: u32,
+    /// The document made of the element and its SVG descendants.
+    pub document: Option<Arc<SvgImage>>,
+    /// The DOM node of each node of the document, by `NodeId`.
+    pub nodes: Arc<Vec<OpaqueNode>>,
 }
 
 /// The address of a node known to be valid. These are sent from script to layout.
@@ -98,6 +98,10 @@ pub enum Msg {
 
     /// Send to layout the precise time when the navigation started.
     SetNavigationStart(u64),
+
+    /// Tells layout whether the document is visible. Image animations are
+    /// paused while it is hidden.
+    SetDocumentVisibility(bool),
 }
 
 #[derive(Debug, PartialEq)]
@@ -110,13 +114,16 @@ pub enum NodesFromPointQueryType {
 pub enum QueryMsg {
     ContentBoxQuery(TrustedNodeAddress),
     ContentBoxesQuery(TrustedNodeAddress),
+    BoxSizesQuery(TrustedNodeAddress),
     NodeScrollIdQuery(TrustedNodeAddress),
     NodeGeometryQuery(TrustedNodeAddress),
     NodeScrollGeometryQuery(TrustedNodeAddress),
     ResolvedStyleQuery(TrustedNodeAddress, Option<PseudoElement>, PropertyId),
+    ResolvedFontStyleQuery(TrustedNodeAddress, String),
     OffsetParentQuery(TrustedNodeAddress),
     StyleQuery(TrustedNodeAddress),
-    TextIndexQuery(TrustedNodeAddress, Point2D<f32>),
+    TextIndexQuery(TrustedNodeAddress, Point2D<f32>, Point2D<f32>),
+    SvgHitTestQuery(TrustedNodeAddress, Point2D<f32>),
     NodesFromPointQuery(Point2D<f32>, NodesFromPointQueryType),
     ElementInnerTextQuery(TrustedNodeAddress),
 }
@@ -138,13 +145,16 @@ impl ReflowGoal {
             ReflowGoal::LayoutQuery(ref querymsg, _) => match querymsg {
                 &QueryMsg::NodesFromPointQuery(..) |
                 &QueryMsg::TextIndexQuery(..) |
+                &QueryMsg::SvgHitTestQuery(..) |
                 &QueryMsg::ElementInnerTextQuery(_) => true,
                 &QueryMsg::ContentBoxQuery(_) |
                 &QueryMsg::ContentBoxesQuery(_) |
+                &QueryMsg::BoxSizesQuery(_) |
                 &QueryMsg::NodeGeometryQuery(_) |
                 &QueryMsg::NodeScrollGeometryQuery(_) |
                 &QueryMsg::NodeScrollIdQuery(_) |
                 &QueryMsg::ResolvedStyleQuery(..) |
+                &QueryMsg::ResolvedFontStyleQuery(..) |
                 &QueryMsg::OffsetParentQuery(_) |
                 &QueryMsg::StyleQuery(_) => false,
             },
@@ -159,13 +169,16 @@ impl ReflowGoal {
             ReflowGoal::LayoutQuery(ref querymsg, _) => match querymsg {
                 &QueryMsg::NodesFromPointQuery(..) |
                 &QueryMsg::TextIndexQuery(..) |
+                &QueryMsg::SvgHitTestQuery(..) |
                 &QueryMsg::ElementInnerTextQuery(_) => true,
                 &QueryMsg::ContentBoxQuery(_) |
                 &QueryMsg::ContentBoxesQuery(_) |
+                &QueryMsg::BoxSizesQuery(_) |
                 &QueryMsg::NodeGeometryQuery(_) |
                 &QueryMsg::NodeScrollGeometryQuery(_) |
                 &QueryMsg::NodeScrollIdQuery(_) |
                 &QueryMsg::ResolvedStyleQuery(..) |
+                &QueryMsg::ResolvedFontStyleQuery(..) |
                 &QueryMsg::OffsetParentQuery(_) |
                 &QueryMsg::StyleQuery(_) => false,
             },
@@ -3,10 +3,11 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use app_units::Au;
-use euclid::{Point2D, Rect};
+use euclid::{Point2D, Rect, Size2D};
 use script_traits::UntrustedNodeAddress;
 use servo_arc::Arc;
 use style::properties::ComputedValues;
+use style::properties::style_structs::Font;
 use style::properties::longhands::overflow_x;
 use webrender_api::ExternalScrollId;
 
@@ -23,6 +24,8 @@ pub trait LayoutRPC {
     fn content_box(&self) -> ContentBoxResponse;
     /// Requests the dimensions of all the content boxes, as in the `getClientRects()` call.
     fn content_boxes(&self) -> ContentBoxesResponse;
+    /// Requests the sizes of the boxes of this node. Used by `ResizeObserver`.
+    fn box_sizes(&self) -> BoxSizesResponse;
     /// Requests the geometry of this node. Used by APIs such as `clientTop`.
     fn node_geometry(&self) -> NodeGeometryResponse;
     /// Requests the scroll geometry of this node. Used by APIs such as `scrollTop`.
@@ -31,11 +34,16 @@ pub trait LayoutRPC {
     fn node_scroll_id(&self) -> NodeScrollIdResponse;
     /// Query layout for the resolved value of a given CSS property
     fn resolved_style(&self) -> ResolvedStyleResponse;
+    /// Query layout for the computed font of a `font` shorthand value resolved against an
+    /// element, as used by the canvas `font` attribute.
+    fn resolved_font_style(&self) -> Option<Arc<Font>>;
     fn offset_parent(&self) -> OffsetParentResponse;
     /// Requests the styles for an element. Contains a `None` value if the element is in a `display:
     /// none` subtree.
     fn style(&self) -> StyleResponse;
     fn text_index(&self) -> TextIndexResponse;
+    /// Requests the element of an inline `<svg>` element painted at a point.
+    fn svg_hit_test(&self) -> SvgHitTestResponse;
     /// Requests the list of nodes from the given point.
     fn nodes_from_point_response(&self) -> Vec<UntrustedNodeAddress>;
     /// Query layout to get the inner text for a given element.
@@ -46,6 +54,20 @@ pub struct ContentBoxResponse(pub Option<Rect<Au>>);
 
 pub struct ContentBoxesResponse(pub Vec<Rect<Au>>);
 
+/// The sizes of the boxes of the first fragment of a node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoxSizes {
+    /// The size of the border box.
+    pub border_box: Size2D<Au>,
+    /// The content box, relative to the origin of the padding box.
+    pub content_box: Rect<Au>,
+    /// Whether the writing mode is vertical, in which case the inline size
+    /// of the boxes is their height.
+    pub vertical: bool,
+}
+
+pub struct BoxSizesResponse(pub Option<BoxSizes>);
+
 pub struct NodeGeometryResponse {
     pub client_rect: Rect<i32>,
 }
@@ -76,3 +98,6 @@ pub struct StyleResponse(pub Option<Arc<ComputedValues>>);
 
 #[derive(Clone)]
 pub struct TextIndexResponse(pub Option<usize>);
+
+#[derive(Clone)]
+pub struct SvgHitTestResponse(pub Option<UntrustedNodeAddress>);
@@ -172,6 +172,9 @@ pub trait ThreadSafeLayoutNode: Clone + Copy + Debug + GetLayoutData + NodeInfo
     /// the parent until all the children have been processed.
     fn parent_style(&self) -> Arc<ComputedValues>;
 
+    /// Returns the `::selection` style of the parent element, falling back to its primary style.
+    fn parent_selected_style(&self) -> Arc<ComputedValues>;
+
     fn get_before_pseudo(&self) -> Option<Self> {
         self.as_element().and_then(|el| el.get_before_pseudo()).map(|el| el.as_node())
     }
@@ -220,8 +223,7 @@ pub trait ThreadSafeLayoutNode: Clone + Copy + Debug + GetLayoutData + NodeInfo
             el.selected_style()
         } else {
             debug_assert!(self.is_text_node());
-            // TODO(stshine): What should the selected style be for text?
-            self.parent_style()
+            self.parent_selected_style()
         }
     }
 
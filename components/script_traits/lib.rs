@@ -33,6 +33,7 @@ extern crate servo_atoms;
 extern crate servo_url;
 extern crate style_traits;
 extern crate time;
+extern crate uuid;
 extern crate webrender_api;
 extern crate webvr_traits;
 extern crate mime;
@@ -77,6 +78,8 @@ use webvr_traits::{WebVREvent, WebVRMsg};
 
 pub use script_msg::{LayoutMsg, ScriptMsg, EventResult, LogEntry};
 pub use script_msg::{ServiceWorkerMsg, ScopeThings, SWManagerMsg, SWManagerSenders, DOMMessage};
+pub use script_msg::{BroadcastMsg, MessagePortId, MessagePortMsg, MessagePortRouterId};
+pub use script_msg::{PortMessageTask, TransferredPort};
 
 /// The address of a node. Layout sends these back. They must be validated via
 /// `from_untrusted_node_address` before they can be used, because we do not trust layout.
@@ -240,6 +243,10 @@ pub enum ProgressiveWebMetricType {
     FirstPaint,
     /// Time to first contentful paint
     FirstContentfulPaint,
+    /// Largest contentful paint, with the area of the largest content in pixels
+    LargestContentfulPaint(u64),
+    /// A layout shift, with its layout shift score
+    LayoutShift(f64),
     /// Time to interactive
     TimeToInteractive,
 }
@@ -290,7 +297,7 @@ pub enum ConstellationControlMsg {
     /// PipelineId is for the parent, BrowsingContextId is for the nested browsing context
     Navigate(PipelineId, BrowsingContextId, LoadData, bool),
     /// Post a message to a given window.
-    PostMessage(PipelineId, Option<ImmutableOrigin>, Vec<u8>),
+    PostMessage(PipelineId, Option<ImmutableOrigin>, Vec<u8>, Vec<TransferredPort>),
     /// Updates the current pipeline ID of a given iframe.
     /// First PipelineId is for the parent, second is the new PipelineId for the frame.
     UpdatePipelineId(PipelineId, BrowsingContextId, PipelineId, UpdatePipelineIdReason),
@@ -449,7 +456,7 @@ pub enum CompositorEvent {
         Option<Point2D<f32>>
     ),
     /// The mouse was moved over a point (or was moved out of the recognizable region).
-    MouseMoveEvent(Option<Point2D<f32>>, Option<UntrustedNodeAddress>),
+    MouseMoveEvent(Option<Point2D<f32>>, Option<UntrustedNodeAddress>, Option<Point2D<f32>>),
     /// A touch event was generated with a touch ID and location.
     TouchEvent(TouchEventType, TouchId, Point2D<f32>, Option<UntrustedNodeAddress>),
     /// A key was pressed.
@@ -727,6 +734,8 @@ pub enum ConstellationMsg {
     SendError(Option<TopLevelBrowsingContextId>, String),
     /// Make browser visible.
     SelectBrowser(TopLevelBrowsingContextId),
+    /// Show or hide the active browser, when the window is restored or minimized.
+    WindowVisibility(bool),
     /// Forward an event to the script task of the given pipeline.
     ForwardEvent(PipelineId, CompositorEvent),
     /// Requesting a change to the onscreen cursor.
@@ -755,6 +764,7 @@ impl fmt::Debug for ConstellationMsg {
             CloseBrowser(..) => "CloseBrowser",
             SendError(..) => "SendError",
             SelectBrowser(..) => "SelectBrowser",
+            WindowVisibility(..) => "WindowVisibility",
             ForwardEvent(..) => "ForwardEvent",
             SetCursor(..) => "SetCursor",
         };
@@ -26,6 +26,7 @@ use std::fmt;
 use style_traits::CSSPixel;
 use style_traits::cursor::CursorKind;
 use style_traits::viewport::ViewportConstraints;
+use uuid::Uuid;
 use webrender_api::{DeviceIntPoint, DeviceUintSize};
 
 /// Messages from the layout to the constellation.
@@ -115,7 +116,28 @@ pub enum ScriptMsg {
     /// Abort loading after sending a LoadUrl message.
     AbortLoadUrl,
     /// Post a message to the currently active window of a given browsing context.
-    PostMessage(BrowsingContextId, Option<ImmutableOrigin>, Vec<u8>),
+    PostMessage(BrowsingContextId, Option<ImmutableOrigin>, Vec<u8>, Vec<TransferredPort>),
+    /// A global created its message-port router.
+    NewMessagePortRouter(MessagePortRouterId, IpcSender<MessagePortMsg>),
+    /// A global is going away, along with its message-port router.
+    RemoveMessagePortRouter(MessagePortRouterId),
+    /// A port was created, or was transferred into a global, and is now owned by the given router.
+    NewMessagePort(MessagePortRouterId, MessagePortId),
+    /// A port is being transferred out of its global. Messages for it are
+    /// buffered until another router claims it.
+    MessagePortShipped(MessagePortId),
+    /// Deliver a message to the given port, wherever it currently lives.
+    PostPortMessage(MessagePortId, PortMessageTask),
+    /// A port was closed, or its global went away.
+    RemoveMessagePort(MessagePortId),
+    /// The port entangled with the given one was closed.
+    DisentangleMessagePort(MessagePortId),
+    /// A global started listening to the broadcast channel with the given name and origin.
+    NewBroadcastChannelNameInRouter(MessagePortRouterId, String, ImmutableOrigin),
+    /// A global stopped listening to the broadcast channel with the given name and origin.
+    RemoveBroadcastChannelNameInRouter(MessagePortRouterId, String, ImmutableOrigin),
+    /// Broadcast a message to every other global listening to the same channel.
+    ScheduleBroadcast(MessagePortRouterId, BroadcastMsg),
     /// Inform the constellation that a fragment was navigated to and whether or not it was a replacement navigation.
     NavigatedToFragment(ServoUrl, bool),
     /// HTMLIFrameElement Forward or Back traversal.
@@ -186,6 +208,16 @@ impl fmt::Debug for ScriptMsg {
             LoadUrl(..) => "LoadUrl",
             AbortLoadUrl => "AbortLoadUrl",
             PostMessage(..) => "PostMessage",
+            NewMessagePortRouter(..) => "NewMessagePortRouter",
+            RemoveMessagePortRouter(..) => "RemoveMessagePortRouter",
+            NewMessagePort(..) => "NewMessagePort",
+            MessagePortShipped(..) => "MessagePortShipped",
+            PostPortMessage(..) => "PostPortMessage",
+            RemoveMessagePort(..) => "RemoveMessagePort",
+            DisentangleMessagePort(..) => "DisentangleMessagePort",
+            NewBroadcastChannelNameInRouter(..) => "NewBroadcastChannelNameInRouter",
+            RemoveBroadcastChannelNameInRouter(..) => "RemoveBroadcastChannelNameInRouter",
+            ScheduleBroadcast(..) => "ScheduleBroadcast",
             NavigatedToFragment(..) => "NavigatedToFragment",
             TraverseHistory(..) => "TraverseHistory",
             PushHistoryState(..) => "PushHistoryState",
@@ -230,6 +262,75 @@ pub struct ScopeThings {
     pub worker_id: WorkerId,
 }
 
+/// The identifier of a `MessagePort`, unique across all event loops.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct MessagePortId(pub Uuid);
+
+impl MessagePortId {
+    /// Generate a new, unique, message port id.
+    pub fn new() -> MessagePortId {
+        MessagePortId(Uuid::new_v4())
+    }
+}
+
+malloc_size_of_is_0!(MessagePortId);
+
+/// The identifier of the message-port router of a global, through which the
+/// constellation delivers port messages and broadcasts to that global.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct MessagePortRouterId(pub Uuid);
+
+impl MessagePortRouterId {
+    /// Generate a new, unique, router id.
+    pub fn new() -> MessagePortRouterId {
+        MessagePortRouterId(Uuid::new_v4())
+    }
+}
+
+malloc_size_of_is_0!(MessagePortRouterId);
+
+/// A message posted on a `MessagePort`, on its way to the entangled port.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct PortMessageTask {
+    /// The structured clone of the message.
+    pub data: Vec<u8>,
+    /// The ports transferred along with the message.
+    pub ports: Vec<TransferredPort>,
+}
+
+/// A `MessagePort` transferred from one global to another.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct TransferredPort {
+    /// The id of the transferred port.
+    pub port_id: MessagePortId,
+    /// The id of the port it is entangled with, if any.
+    pub entangled_port: Option<MessagePortId>,
+    /// The messages that had been received, but not yet dispatched, by the previous owner.
+    pub queued_messages: Vec<PortMessageTask>,
+}
+
+/// A message sent on a `BroadcastChannel`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BroadcastMsg {
+    /// The origin of the global that sent the message.
+    pub origin: ImmutableOrigin,
+    /// The name of the channel.
+    pub channel_name: String,
+    /// The structured clone of the message.
+    pub data: Vec<u8>,
+}
+
+/// Messages sent from the constellation to the message-port router of a global.
+#[derive(Deserialize, Serialize)]
+pub enum MessagePortMsg {
+    /// A message was posted to a port owned by the global.
+    NewTask(MessagePortId, PortMessageTask),
+    /// A message was sent on a broadcast channel the global listens to.
+    Broadcast(BroadcastMsg),
+    /// The port entangled with a port owned by the global was closed.
+    Disentangle(MessagePortId),
+}
+
 /// Message that gets passed to service worker scope on postMessage
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct DOMMessage(pub Vec<u8>);
@@ -310,7 +310,7 @@ where
                     builder,
                 );
             }
-            Component::PseudoElement(..) | Component::LocalName(..) => {
+            Component::PseudoElement(..) | Component::LocalName(..) | Component::Part(..) => {
                 specificity.element_selectors += 1
             },
             Component::Slotted(ref selector) => {
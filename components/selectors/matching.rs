@@ -264,7 +264,8 @@ where
             (from_offset != selector.len() &&
                 matches!(
                     selector.combinator_at_parse_order(from_offset),
-                    Combinator::SlotAssignment | Combinator::PseudoElement
+                    Combinator::SlotAssignment | Combinator::PseudoElement |
+                        Combinator::Part
                 )),
         "Got the math wrong: {:?} | {:?} | {} {}",
         selector,
@@ -457,6 +458,7 @@ where
             );
             element.assigned_slot()
         },
+        Combinator::Part => element.containing_shadow_host(),
         Combinator::PseudoElement => element.pseudo_element_originating_element(),
     }
 }
@@ -509,6 +511,7 @@ where
         Combinator::Child |
         Combinator::Descendant |
         Combinator::SlotAssignment |
+        Combinator::Part |
         Combinator::PseudoElement => SelectorMatchingResult::NotMatchedGlobally,
     };
 
@@ -548,7 +551,7 @@ where
 
             // Upgrade the failure status to
             // NotMatchedAndRestartFromClosestDescendant.
-            (_, Combinator::PseudoElement) | (_, Combinator::Child) => {
+            (_, Combinator::PseudoElement) | (_, Combinator::Part) | (_, Combinator::Child) => {
                 return SelectorMatchingResult::NotMatchedAndRestartFromClosestDescendant;
             },
 
@@ -668,6 +671,7 @@ where
                     matches_complex_selector(selector.iter(), element, context, flags_setter)
                 })
         },
+        Component::Part(ref name) => element.is_part(name),
         Component::PseudoElement(ref pseudo) => {
             element.match_pseudo_element(pseudo, context.shared)
         },
@@ -152,6 +152,11 @@ pub trait Parser<'i> {
         false
     }
 
+    /// Whether to parse the `::part()` pseudo-element.
+    fn parse_part(&self) -> bool {
+        false
+    }
+
     /// This function can return an "Err" pseudo-element in order to support CSS2.1
     /// pseudo-elements.
     fn parse_non_ts_pseudo_class(
@@ -512,6 +517,16 @@ impl<Impl: SelectorImpl> Selector<Impl> {
         self.0.header.header.is_slotted()
     }
 
+    /// Whether this selector ends in a `::part()` pseudo-element, and thus
+    /// needs to be matched against the elements exposed by a shadow tree.
+    ///
+    /// There are no spare bits in the specificity header, so we just look at
+    /// the rightmost compound selector.
+    #[inline]
+    pub fn is_part(&self) -> bool {
+        self.iter().any(|c| matches!(*c, Component::Part(..)))
+    }
+
     #[inline]
     pub fn pseudo_element(&self) -> Option<&Impl::PseudoElement> {
         if !self.has_pseudo_element() {
@@ -777,6 +792,9 @@ pub enum Combinator {
     /// Another combinator used for ::slotted(), which represent the jump from
     /// a node to its assigned slot.
     SlotAssignment,
+    /// Another combinator used for `::part()`, which represents the jump from
+    /// the part to the containing shadow host.
+    Part,
 }
 
 impl Combinator {
@@ -786,7 +804,7 @@ impl Combinator {
         matches!(
             *self,
             Combinator::Child | Combinator::Descendant | Combinator::PseudoElement |
-                Combinator::SlotAssignment
+                Combinator::SlotAssignment | Combinator::Part
         )
     }
 
@@ -885,6 +903,13 @@ pub enum Component<Impl: SelectorImpl> {
     ///
     /// See https://github.com/w3c/csswg-drafts/issues/2158
     Host(Option<Selector<Impl>>),
+    /// The `::part()` pseudo-element:
+    ///
+    /// https://drafts.csswg.org/css-shadow-parts/#part
+    ///
+    /// Only a single part name is supported, which is what other engines do
+    /// as well.
+    Part(Impl::Identifier),
     PseudoElement(Impl::PseudoElement),
 }
 
@@ -1047,10 +1072,11 @@ impl<Impl: SelectorImpl> ToCss for Selector<Impl> {
                     // the one simple selector. Technically this compound
                     // selector contains the pseudo element selector as well
                     // -- Combinator::PseudoElement, just like
-                    // Combinator::SlotAssignment, don't exist in the
-                    // spec.
+                    // Combinator::SlotAssignment and Combinator::Part, don't
+                    // exist in the spec.
                     (Some(Combinator::PseudoElement), _) |
-                    (Some(Combinator::SlotAssignment), _) => (),
+                    (Some(Combinator::SlotAssignment), _) |
+                    (Some(Combinator::Part), _) => (),
                     (_, &Component::ExplicitUniversalType) => {
                         // Iterate over everything so we serialize the namespace
                         // too.
@@ -1121,6 +1147,7 @@ impl ToCss for Combinator {
             Combinator::LaterSibling => dest.write_str(" ~ "),
             Combinator::PseudoElement => Ok(()),
             Combinator::SlotAssignment => Ok(()),
+            Combinator::Part => Ok(()),
         }
     }
 }
@@ -1159,6 +1186,11 @@ impl<Impl: SelectorImpl> ToCss for Component<Impl> {
                 selector.to_css(dest)?;
                 dest.write_char(')')
             },
+            Part(ref name) => {
+                dest.write_str("::part(")?;
+                display_to_css_identifier(name, dest)?;
+                dest.write_char(')')
+            },
             PseudoElement(ref p) => p.to_css(dest),
             ID(ref s) => {
                 dest.write_char('#')?;
@@ -1330,12 +1362,14 @@ where
 
     let mut has_pseudo_element;
     let mut slotted;
+    let mut part;
     'outer_loop: loop {
         // Parse a sequence of simple selectors.
         match parse_compound_selector(parser, input, &mut builder)? {
-            Some((has_pseudo, slot)) => {
+            Some((has_pseudo, slot, has_part)) => {
                 has_pseudo_element = has_pseudo;
                 slotted = slot;
+                part = has_part;
             },
             None => {
                 return Err(input.new_custom_error(if builder.has_combinators() {
@@ -1346,7 +1380,7 @@ where
             },
         };
 
-        if has_pseudo_element || slotted {
+        if has_pseudo_element || slotted || part {
             break;
         }
 
@@ -1478,6 +1512,7 @@ enum SimpleSelectorParseResult<Impl: SelectorImpl> {
     SimpleSelector(Component<Impl>),
     PseudoElement(Impl::PseudoElement),
     SlottedPseudo(Selector<Impl>),
+    PartPseudo(Impl::Identifier),
 }
 
 #[derive(Debug)]
@@ -1790,7 +1825,8 @@ where
                 return Err(input.new_custom_error(SelectorParseErrorKind::EmptyNegation));
             },
             Some(SimpleSelectorParseResult::PseudoElement(_)) |
-            Some(SimpleSelectorParseResult::SlottedPseudo(_)) => {
+            Some(SimpleSelectorParseResult::SlottedPseudo(_)) |
+            Some(SimpleSelectorParseResult::PartPseudo(_)) => {
                 let e = SelectorParseErrorKind::NonSimpleSelectorInNegation;
                 return Err(input.new_custom_error(e));
             },
@@ -1808,13 +1844,14 @@ where
 /// `Err(())` means invalid selector.
 /// `Ok(None)` is an empty selector
 ///
-/// The booleans represent whether a pseudo-element has been parsed, and whether
-/// ::slotted() has been parsed, respectively.
+/// The booleans represent whether a pseudo-element has been parsed, whether
+/// ::slotted() has been parsed, and whether ::part() has been parsed,
+/// respectively.
 fn parse_compound_selector<'i, 't, P, Impl>(
     parser: &P,
     input: &mut CssParser<'i, 't>,
     builder: &mut SelectorBuilder<Impl>,
-) -> Result<Option<(bool, bool)>, ParseError<'i, P::Error>>
+) -> Result<Option<(bool, bool, bool)>, ParseError<'i, P::Error>>
 where
     P: Parser<'i, Impl = Impl>,
     Impl: SelectorImpl,
@@ -1835,6 +1872,7 @@ where
     }
 
     let mut pseudo = false;
+    let mut part = false;
     loop {
         let parse_result =
             match parse_one_simple_selector(parser, input, /* inside_negation = */ false)? {
@@ -1910,13 +1948,24 @@ where
                 // push more type selectors either.
                 break;
             },
+            SimpleSelectorParseResult::PartPseudo(name) => {
+                empty = false;
+                part = true;
+                if !builder.is_empty() {
+                    builder.push_combinator(Combinator::Part);
+                }
+                builder.push_simple_selector(Component::Part(name));
+                // FIXME: ::part() should allow pseudo-classes and
+                // pseudo-elements to its right, but we don't support that yet.
+                break;
+            },
         }
     }
     if empty {
         // An empty selector is invalid.
         Ok(None)
     } else {
-        Ok(Some((pseudo, slot)))
+        Ok(Some((pseudo, slot, part)))
     }
 }
 
@@ -2033,6 +2082,12 @@ where
                             parse_inner_compound_selector(parser, input)
                         })?;
                         SimpleSelectorParseResult::SlottedPseudo(selector)
+                    } else if P::parse_part(parser) && name.eq_ignore_ascii_case("part") {
+                        let name = input.parse_nested_block(|input| {
+                            let name = input.expect_ident()?;
+                            Ok::<_, ParseError<'i, P::Error>>(name.as_ref().into())
+                        })?;
+                        SimpleSelectorParseResult::PartPseudo(name)
                     } else {
                         let selector = input.parse_nested_block(|input| {
                             P::parse_functional_pseudo_element(parser, name, input)
@@ -2237,6 +2292,10 @@ pub mod tests {
             true
         }
 
+        fn parse_part(&self) -> bool {
+            true
+        }
+
         fn parse_non_ts_pseudo_class(
             &self,
             location: SourceLocation,
@@ -2882,6 +2941,31 @@ pub mod tests {
         // TODO
         assert!(parse("::slotted(div)::before").is_err());
         assert!(parse("slot::slotted(div,foo)").is_err());
+
+        assert!(parse("::part()").is_err());
+        assert!(parse("::part(42)").is_err());
+        assert!(parse("::part(foo bar)").is_err());
+        assert!(parse("::part(foo)").is_ok());
+        assert!(parse("x-foo::part(foo)").is_ok());
+        assert!(parse("div x-foo::part(foo)").is_ok());
+        assert!(parse("x-foo::part(foo) div").is_err());
+        assert!(parse("x-foo::part(foo).bar").is_err());
+        assert!(parse("::slotted(::part(foo))").is_err());
+        assert!(parse("x-foo::part(foo)").unwrap().0[0].is_part());
+        assert!(!parse("x-foo").unwrap().0[0].is_part());
+    }
+
+    #[test]
+    fn test_part_iter() {
+        let selector = &parse("x-foo::part(foo)").unwrap().0[0];
+        assert_eq!(selector.to_css_string(), "x-foo::part(foo)");
+        let mut iter = selector.iter();
+        assert_eq!(iter.next(), Some(&Component::Part(DummyAtom::from("foo"))));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_sequence(), Some(Combinator::Part));
+        assert!(matches!(iter.next(), Some(&Component::LocalName(..))));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_sequence(), None);
     }
 
     #[test]
@@ -105,6 +105,14 @@ pub trait Element: Sized + Clone + Debug {
         case_sensitivity: CaseSensitivity,
     ) -> bool;
 
+    /// Returns whether this element has the given name in its `part`
+    /// attribute.
+    ///
+    /// Necessary for the `::part` pseudo-element.
+    fn is_part(&self, _name: &<Self::Impl as SelectorImpl>::Identifier) -> bool {
+        false
+    }
+
     /// Returns whether this element matches `:empty`.
     ///
     /// That is, whether it does not contain any child element or any non-zero-length text node.
@@ -342,6 +342,13 @@ impl<Window> Servo<Window> where Window: WindowMethods + 'static {
                 }
             }
 
+            WindowEvent::Visible(visible) => {
+                let msg = ConstellationMsg::WindowVisibility(visible);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending WindowVisibility message to constellation failed ({}).", e);
+                }
+            }
+
             WindowEvent::CloseBrowser(ctx) => {
                 let msg = ConstellationMsg::CloseBrowser(ctx);
                 if let Err(e) = self.constellation_chan.send(msg) {
@@ -774,6 +774,12 @@ pub trait TElement:
     /// The shadow root which roots the subtree this element is contained in.
     fn containing_shadow(&self) -> Option<<Self::ConcreteNode as TNode>::ConcreteShadowRoot>;
 
+    /// Whether this element has a non-empty `part` attribute, and thus may
+    /// match `::part()` rules from outside its shadow tree.
+    fn has_part_attr(&self) -> bool {
+        false
+    }
+
     /// XBL hack for style sharing. :(
     fn has_same_xbl_proto_binding_as(&self, _other: Self) -> bool {
         true
@@ -343,6 +343,10 @@ where
         }
     }
 
+    fn is_part(&self, name: &Atom) -> bool {
+        self.element.is_part(name)
+    }
+
     fn is_empty(&self) -> bool {
         self.element.is_empty()
     }
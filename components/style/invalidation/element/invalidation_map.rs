@@ -102,7 +102,7 @@ impl Dependency {
     pub fn invalidation_kind(&self) -> DependencyInvalidationKind {
         match self.combinator() {
             None => DependencyInvalidationKind::Element,
-            Some(Combinator::Child) | Some(Combinator::Descendant) => {
+            Some(Combinator::Child) | Some(Combinator::Descendant) | Some(Combinator::Part) => {
                 DependencyInvalidationKind::Descendants
             },
             Some(Combinator::LaterSibling) | Some(Combinator::NextSibling) => {
@@ -157,7 +157,10 @@ impl<'a> Invalidation<'a> {
         //
         // We should be able to do better here!
         match self.selector.combinator_at_parse_order(self.offset - 1) {
-            Combinator::Descendant | Combinator::LaterSibling | Combinator::PseudoElement => true,
+            Combinator::Descendant |
+            Combinator::LaterSibling |
+            Combinator::PseudoElement |
+            Combinator::Part => true,
             Combinator::SlotAssignment | Combinator::NextSibling | Combinator::Child => false,
         }
     }
@@ -168,9 +171,10 @@ impl<'a> Invalidation<'a> {
         }
 
         match self.selector.combinator_at_parse_order(self.offset - 1) {
-            Combinator::Child | Combinator::Descendant | Combinator::PseudoElement => {
-                InvalidationKind::Descendant(DescendantInvalidationKind::Dom)
-            },
+            Combinator::Child |
+            Combinator::Descendant |
+            Combinator::PseudoElement |
+            Combinator::Part => InvalidationKind::Descendant(DescendantInvalidationKind::Dom),
             Combinator::SlotAssignment => {
                 InvalidationKind::Descendant(DescendantInvalidationKind::Slotted)
             },
@@ -760,6 +760,13 @@ impl<E: TElement> StyleSharingCache<E> {
             return None;
         }
 
+        // Elements exposed as parts can match ::part() rules from the outer
+        // tree, which we don't account for in the revalidation selectors.
+        if target.element.has_part_attr() || candidate.element.has_part_attr() {
+            trace!("Miss: Part");
+            return None;
+        }
+
         if target.matches_user_and_author_rules() !=
             candidate.element.matches_user_and_author_rules()
         {
@@ -1297,6 +1297,32 @@ impl Stylist {
 
                 match_document_author_rules = host_is_svg_use;
             }
+
+            // ::part() rules come from the tree the shadow host is in, so they
+            // go after the rules of the shadow tree the part lives in.
+            if rule_hash_target.has_part_attr() {
+                if let Some(containing_shadow) = rule_hash_target.containing_shadow() {
+                    let host = containing_shadow.host();
+                    let (cascade_data, outer_host) = match host.containing_shadow() {
+                        Some(shadow) => (shadow.style_data(), Some(shadow.host())),
+                        None => (&self.cascade_data.author, None),
+                    };
+                    if let Some(map) = cascade_data.part_rules(pseudo_element) {
+                        context.with_shadow_host(outer_host, |context| {
+                            map.get_all_matching_rules(
+                                element,
+                                rule_hash_target,
+                                applicable_declarations,
+                                context,
+                                flags_setter,
+                                CascadeLevel::SameTreeAuthorNormal,
+                                shadow_cascade_order,
+                            );
+                        });
+                        shadow_cascade_order += 1;
+                    }
+                }
+            }
         }
 
         // FIXME(emilio): This doesn't account for the author_styles_enabled
@@ -1947,6 +1973,13 @@ pub struct CascadeData {
     /// containing style scopes starting from the closest assigned slot.
     slotted_rules: Option<Box<ElementAndPseudoRules>>,
 
+    /// The data coming from ::part() pseudo-element rules.
+    ///
+    /// These never match elements in the tree the stylesheet applies to, but
+    /// elements in the shadow trees of the hosts in that tree, so they're
+    /// stored separately as well.
+    part_rules: Option<Box<ElementAndPseudoRules>>,
+
     /// The invalidation map for these rules.
     invalidation_map: InvalidationMap,
 
@@ -2016,6 +2049,7 @@ impl CascadeData {
             normal_rules: ElementAndPseudoRules::default(),
             host_rules: None,
             slotted_rules: None,
+            part_rules: None,
             invalidation_map: InvalidationMap::new(),
             attribute_dependencies: NonCountingBloomFilter::new(),
             style_attribute_dependency: false,
@@ -2108,6 +2142,11 @@ impl CascadeData {
         self.slotted_rules.as_ref().and_then(|d| d.rules(pseudo))
     }
 
+    #[inline]
+    fn part_rules(&self, pseudo: Option<&PseudoElement>) -> Option<&SelectorMap<Rule>> {
+        self.part_rules.as_ref().and_then(|d| d.rules(pseudo))
+    }
+
     /// Collects all the applicable media query results into `results`.
     ///
     /// This duplicates part of the logic in `add_stylesheet`, which is
@@ -2238,7 +2277,10 @@ impl CascadeData {
                         // NOTE(emilio): It's fine to look at :host and then at
                         // ::slotted(..), since :host::slotted(..) could never
                         // possibly match, as <slot> is not a valid shadow host.
-                        let rules = if selector.is_featureless_host_selector_or_pseudo_element() {
+                        let rules = if selector.is_part() {
+                            self.part_rules
+                                .get_or_insert_with(|| Box::new(Default::default()))
+                        } else if selector.is_featureless_host_selector_or_pseudo_element() {
                             self.host_rules
                                 .get_or_insert_with(|| Box::new(Default::default()))
                         } else if selector.is_slotted() {
@@ -2415,6 +2457,9 @@ impl CascadeData {
         if let Some(ref mut slotted_rules) = self.slotted_rules {
             slotted_rules.clear();
         }
+        if let Some(ref mut part_rules) = self.part_rules {
+            part_rules.clear();
+        }
         self.animations.clear();
         self.extra_data.clear();
         self.rules_source_order = 0;
@@ -2441,6 +2486,9 @@ impl CascadeData {
         if let Some(ref slotted_rules) = self.slotted_rules {
             slotted_rules.add_size_of(ops, sizes);
         }
+        if let Some(ref part_rules) = self.part_rules {
+            part_rules.add_size_of(ops, sizes);
+        }
         sizes.mInvalidationMap += self.invalidation_map.size_of(ops);
         sizes.mRevalidationSelectors += self.selectors_for_cache_revalidation.size_of(ops);
         sizes.mOther += self.animations.size_of(ops);
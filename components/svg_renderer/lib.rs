@@ -0,0 +1,258 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A renderer for SVG documents used as images.
+//!
+//! Documents run in the secure static mode of images: scripts, animations,
+//! links and any resource outside the document are ignored, so rendering
+//! never loads anything.
+//! https://www.w3.org/TR/SVG2/conform.html#secure-static-mode
+
+#![deny(unsafe_code)]
+
+extern crate azure;
+extern crate cssparser;
+extern crate euclid;
+#[macro_use]
+extern crate log;
+extern crate xml;
+
+mod path;
+mod render;
+mod style;
+mod tree;
+
+use azure::azure_hl::{BackendType, DrawTarget, SurfaceFormat};
+use cssparser::RGBA;
+use euclid::{Point2D, Size2D};
+pub use path::Segment;
+use render::Renderer;
+use style::{Axis, Length, Stylesheet, Unit};
+pub use tree::{NodeId, NodeKind, Tree, SVG_NAMESPACE};
+
+/// The size of replaced elements that don't have one of their own.
+/// https://drafts.csswg.org/css-images-3/#default-object-size
+const DEFAULT_WIDTH: f32 = 300.;
+const DEFAULT_HEIGHT: f32 = 150.;
+
+/// The largest width or height, in pixels, that a document is rasterized
+/// at. Larger rasterizations are scaled down to fit.
+const MAX_RASTER_DIMENSION: f32 = 8192.;
+
+/// Whether some bytes look like an SVG document: an `<svg>` root element,
+/// maybe after an XML declaration, comments and a doctype.
+pub fn is_svg(bytes: &[u8]) -> bool {
+    let mut rest = if bytes.starts_with(b"\xEF\xBB\xBF") { &bytes[3..] } else { bytes };
+    loop {
+        while rest.first().map_or(false, |byte| byte.is_ascii_whitespace()) {
+            rest = &rest[1..];
+        }
+        let end = if rest.starts_with(b"<?") {
+            find(rest, b"?>").map(|end| end + 2)
+        } else if rest.starts_with(b"<!--") {
+            find(rest, b"-->").map(|end| end + 3)
+        } else if rest.starts_with(b"<!DOCTYPE") || rest.starts_with(b"<!doctype") {
+            // The doctype may have an internal subset in brackets.
+            let subset_end = match (find(rest, b"["), find(rest, b">")) {
+                (Some(open), Some(close)) if open < close => find(rest, b"]").unwrap_or(open),
+                _ => 0,
+            };
+            find(&rest[subset_end..], b">").map(|end| subset_end + end + 1)
+        } else {
+            break;
+        };
+        match end {
+            Some(end) => rest = &rest[end..],
+            None => return false,
+        }
+    }
+
+    // The root may use a namespace prefix, as in `<svg:svg>`.
+    let name_end = rest.iter().position(|&byte| byte.is_ascii_whitespace() || byte == b'>' || byte == b'/');
+    let name = match name_end {
+        Some(end) if rest.starts_with(b"<") => &rest[1..end],
+        _ => return false,
+    };
+    name == b"svg" || name.ends_with(b":svg")
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Pixels of a rasterized document, in BGRA.
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: Vec<u8>,
+}
+
+/// The font of a `<text>` element.
+pub struct Font<'a> {
+    /// The value of `font-family`, as written.
+    pub family: &'a str,
+    pub size: f32,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// Lays out text, which the renderer has no fonts for.
+pub trait TextOutliner {
+    /// The outline of a line of text, with its start on the origin of its
+    /// baseline, and its advance width. Both are in pixels, with y down.
+    fn outline(&self, text: &str, font: &Font) -> (Vec<Segment>, f32);
+}
+
+/// What a document inherits from where it is rendered: documents used as
+/// images are isolated, but inline `<svg>` elements are part of a page.
+pub struct Environment<'a> {
+    /// The `color` of the root, for `currentColor`.
+    pub color: RGBA,
+    /// The `font-family` of the root, as written in CSS.
+    pub font_family: &'a str,
+    /// The `font-size` of the root, in pixels.
+    pub font_size: f32,
+    /// Lays out text, which isn't painted without one.
+    pub text_outliner: Option<&'a TextOutliner>,
+}
+
+impl<'a> Default for Environment<'a> {
+    fn default() -> Environment<'a> {
+        Environment {
+            color: RGBA::new(0, 0, 0, 255),
+            font_family: "serif",
+            font_size: 16.,
+            text_outliner: None,
+        }
+    }
+}
+
+/// A parsed SVG document, ready to be painted at any size.
+pub struct SvgImage {
+    tree: Tree,
+    stylesheet: Stylesheet,
+}
+
+impl SvgImage {
+    pub fn parse(bytes: &[u8]) -> Option<SvgImage> {
+        SvgImage::from_tree(Tree::parse(bytes)?)
+    }
+
+    /// Wraps a tree whose first node is an `<svg>` element.
+    pub fn from_tree(tree: Tree) -> Option<SvgImage> {
+        tree.root()?;
+        let stylesheet = Stylesheet::from_tree(&tree);
+        Some(SvgImage {
+            tree: tree,
+            stylesheet: stylesheet,
+        })
+    }
+
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// The size the document asks for with the `width` and `height` of its
+    /// root, each of which is `None` if it is missing or a percentage.
+    pub fn intrinsic_dimensions(&self) -> (Option<f32>, Option<f32>) {
+        let root = match self.tree.root() {
+            Some(root) => root,
+            None => return (None, None),
+        };
+        let dimension = |name: &str, axis: Axis| {
+            match self.tree.attribute(root, name).and_then(Length::parse) {
+                Some(Length { unit: Unit::Percent, .. }) | None => None,
+                Some(length) => Some(length.resolve((0., 0.), 16., axis).max(0.)),
+            }
+        };
+        (dimension("width", Axis::Horizontal), dimension("height", Axis::Vertical))
+    }
+
+    /// The aspect ratio of the `viewBox` of the root, if it has one.
+    pub fn intrinsic_ratio(&self) -> Option<f32> {
+        let view_box = self.tree.attribute(self.tree.root()?, "viewBox").and_then(render::parse_view_box)?;
+        if view_box.size.width <= 0. || view_box.size.height <= 0. {
+            return None;
+        }
+        Some(view_box.size.width / view_box.size.height)
+    }
+
+    /// The size to paint the document at when nothing else sizes it: its
+    /// intrinsic dimensions, completed with its aspect ratio, or else the
+    /// largest size with that ratio that fits the default object size.
+    /// https://drafts.csswg.org/css-images-3/#default-sizing
+    pub fn default_size(&self) -> Size2D<f32> {
+        let ratio = self.intrinsic_ratio();
+        let (width, height) = match (self.intrinsic_dimensions(), ratio) {
+            ((Some(width), Some(height)), _) => (width, height),
+            ((Some(width), None), Some(ratio)) => (width, width / ratio),
+            ((None, Some(height)), Some(ratio)) => (height * ratio, height),
+            ((Some(width), None), None) => (width, DEFAULT_HEIGHT),
+            ((None, Some(height)), None) => (DEFAULT_WIDTH, height),
+            ((None, None), Some(ratio)) => {
+                if ratio > DEFAULT_WIDTH / DEFAULT_HEIGHT {
+                    (DEFAULT_WIDTH, DEFAULT_WIDTH / ratio)
+                } else {
+                    (DEFAULT_HEIGHT * ratio, DEFAULT_HEIGHT)
+                }
+            },
+            ((None, None), None) => (DEFAULT_WIDTH, DEFAULT_HEIGHT),
+        };
+        Size2D::new(width, height)
+    }
+
+    /// Paints the document into a viewport of `viewport` CSS pixels, at
+    /// `scale` device pixels per CSS pixel, in non-premultiplied BGRA.
+    pub fn rasterize(&self, viewport: Size2D<f32>, scale: f32) -> Option<Raster> {
+        let mut raster = self.paint(viewport, scale, &Environment::default())?;
+        unpremultiply(&mut raster.bytes);
+        Some(raster)
+    }
+
+    /// Paints the document like `rasterize`, in an environment, and leaves
+    /// the pixels premultiplied.
+    pub fn paint(&self, viewport: Size2D<f32>, scale: f32, environment: &Environment) -> Option<Raster> {
+        if !(viewport.width > 0. && viewport.height > 0. && scale > 0.) {
+            return None;
+        }
+        let largest = viewport.width.max(viewport.height) * scale;
+        let scale = if largest > MAX_RASTER_DIMENSION { scale * MAX_RASTER_DIMENSION / largest } else { scale };
+        let width = (viewport.width * scale).ceil().max(1.);
+        let height = (viewport.height * scale).ceil().max(1.);
+        let size = Size2D::new(width as i32, height as i32);
+
+        let draw_target = DrawTarget::new(BackendType::Skia, size, SurfaceFormat::B8G8R8A8);
+        Renderer::new(&self.tree, &self.stylesheet, environment).paint(&draw_target, viewport, scale);
+
+        let mut bytes = vec![];
+        draw_target.snapshot().get_data_surface().with_data(|data| bytes = data.to_vec());
+        Some(Raster {
+            width: size.width as u32,
+            height: size.height as u32,
+            bytes: bytes,
+        })
+    }
+
+    /// The element painted topmost at a point of a viewport, in CSS pixels.
+    pub fn hit_test(&self, viewport: Size2D<f32>, point: Point2D<f32>, environment: &Environment)
+                    -> Option<NodeId> {
+        if !(viewport.width > 0. && viewport.height > 0.) {
+            return None;
+        }
+        let draw_target = DrawTarget::new(BackendType::Skia, Size2D::new(1, 1), SurfaceFormat::B8G8R8A8);
+        Renderer::new(&self.tree, &self.stylesheet, environment).hit_test(&draw_target, viewport, point)
+    }
+}
+
+fn unpremultiply(data: &mut [u8]) {
+    for pixel in data.chunks_mut(4) {
+        let alpha = pixel[3] as u32;
+        if alpha == 0 || alpha == 255 {
+            continue;
+        }
+        for channel in &mut pixel[0..3] {
+            *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+        }
+    }
+}
@@ -0,0 +1,404 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Path data and the outlines of basic shapes.
+//!
+//! https://www.w3.org/TR/SVG2/paths.html
+
+use euclid::{Point2D, Rect, Size2D, Vector2D};
+use std::f32::consts::PI;
+use style::parse_number_prefix;
+
+/// A path segment in absolute coordinates. Arcs are approximated with
+/// cubic curves, since that's all the backends can draw.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segment {
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    QuadTo(Point2D<f32>, Point2D<f32>),
+    CubicTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    Close,
+}
+
+impl Segment {
+    pub fn translate(self, offset: Vector2D<f32>) -> Segment {
+        match self {
+            Segment::MoveTo(point) => Segment::MoveTo(point + offset),
+            Segment::LineTo(point) => Segment::LineTo(point + offset),
+            Segment::QuadTo(control, point) => Segment::QuadTo(control + offset, point + offset),
+            Segment::CubicTo(control1, control2, point) => {
+                Segment::CubicTo(control1 + offset, control2 + offset, point + offset)
+            },
+            Segment::Close => Segment::Close,
+        }
+    }
+}
+
+/// Reads the arguments of path commands out of path data.
+struct Arguments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Arguments<'a> {
+    fn skip_separators(&mut self) {
+        self.rest = self.rest.trim_left_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let (number, length) = parse_number_prefix(self.rest)?;
+        self.rest = &self.rest[length..];
+        Some(number)
+    }
+
+    fn point(&mut self) -> Option<Point2D<f32>> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Some(Point2D::new(x, y))
+    }
+
+    /// Arc flags are single digits, which don't need to be separated from
+    /// what follows them.
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.rest.chars().next()? {
+            '0' => false,
+            '1' => true,
+            _ => return None,
+        };
+        self.rest = &self.rest[1..];
+        Some(flag)
+    }
+}
+
+/// Parses path data. As the specification requires, everything up to the
+/// first error is kept.
+pub fn parse_path_data(data: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut arguments = Arguments { rest: data };
+    let mut current = Point2D::zero();
+    let mut subpath_start = Point2D::zero();
+    // The reflected control point of the previous curve, for the smooth
+    // curve commands.
+    let mut last_cubic_control: Option<Point2D<f32>> = None;
+    let mut last_quad_control: Option<Point2D<f32>> = None;
+    let mut command = None;
+
+    loop {
+        arguments.skip_separators();
+        if arguments.rest.is_empty() {
+            break;
+        }
+        let next = arguments.rest.chars().next().unwrap();
+        if next.is_ascii_alphabetic() {
+            arguments.rest = &arguments.rest[1..];
+            command = Some(next);
+        } else {
+            // Repeated arguments repeat the command, with moves becoming
+            // lines.
+            command = match command {
+                Some('M') => Some('L'),
+                Some('m') => Some('l'),
+                Some('Z') | Some('z') | None => break,
+                command => command,
+            };
+        }
+        let command = command.unwrap();
+        // Path data has to start with a move.
+        if segments.is_empty() && command != 'M' && command != 'm' {
+            break;
+        }
+
+        let relative = command.is_ascii_lowercase();
+        let origin = current.to_vector();
+        let offset = move |point: Point2D<f32>| if relative { point + origin } else { point };
+        let segment = match command.to_ascii_uppercase() {
+            'M' => match arguments.point() {
+                Some(point) => {
+                    subpath_start = offset(point);
+                    Segment::MoveTo(subpath_start)
+                },
+                None => break,
+            },
+            'L' => match arguments.point() {
+                Some(point) => Segment::LineTo(offset(point)),
+                None => break,
+            },
+            'H' => match arguments.number() {
+                Some(x) => Segment::LineTo(Point2D::new(if relative { current.x + x } else { x }, current.y)),
+                None => break,
+            },
+            'V' => match arguments.number() {
+                Some(y) => Segment::LineTo(Point2D::new(current.x, if relative { current.y + y } else { y })),
+                None => break,
+            },
+            'C' => match (arguments.point(), arguments.point(), arguments.point()) {
+                (Some(control1), Some(control2), Some(point)) => {
+                    Segment::CubicTo(offset(control1), offset(control2), offset(point))
+                },
+                _ => break,
+            },
+            'S' => match (arguments.point(), arguments.point()) {
+                (Some(control2), Some(point)) => {
+                    let control1 = last_cubic_control.map_or(current, |control| reflect(control, current));
+                    Segment::CubicTo(control1, offset(control2), offset(point))
+                },
+                _ => break,
+            },
+            'Q' => match (arguments.point(), arguments.point()) {
+                (Some(control), Some(point)) => Segment::QuadTo(offset(control), offset(point)),
+                _ => break,
+            },
+            'T' => match arguments.point() {
+                Some(point) => {
+                    let control = last_quad_control.map_or(current, |control| reflect(control, current));
+                    Segment::QuadTo(control, offset(point))
+                },
+                None => break,
+            },
+            'A' => {
+                let radii = (arguments.number(), arguments.number(), arguments.number());
+                let flags = (arguments.flag(), arguments.flag());
+                match (radii, flags, arguments.point()) {
+                    ((Some(rx), Some(ry), Some(angle)), (Some(large_arc), Some(sweep)), Some(point)) => {
+                        let point = offset(point);
+                        arc_to_curves(current, rx, ry, angle, large_arc, sweep, point, &mut segments);
+                        current = point;
+                        last_cubic_control = None;
+                        last_quad_control = None;
+                        continue;
+                    },
+                    _ => break,
+                }
+            },
+            'Z' => Segment::Close,
+            _ => break,
+        };
+
+        last_cubic_control = None;
+        last_quad_control = None;
+        match segment {
+            Segment::MoveTo(point) | Segment::LineTo(point) => current = point,
+            Segment::QuadTo(control, point) => {
+                last_quad_control = Some(control);
+                current = point;
+            },
+            Segment::CubicTo(_, control2, point) => {
+                last_cubic_control = Some(control2);
+                current = point;
+            },
+            Segment::Close => current = subpath_start,
+        }
+        segments.push(segment);
+    }
+    segments
+}
+
+fn reflect(control: Point2D<f32>, around: Point2D<f32>) -> Point2D<f32> {
+    around + (around - control)
+}
+
+/// Approximates an elliptical arc with cubic curves of at most a quarter
+/// turn each.
+/// https://www.w3.org/TR/SVG2/implnote.html#ArcImplementationNotes
+fn arc_to_curves(from: Point2D<f32>,
+                 rx: f32,
+                 ry: f32,
+                 angle: f32,
+                 large_arc: bool,
+                 sweep: bool,
+                 to: Point2D<f32>,
+                 segments: &mut Vec<Segment>) {
+    if from == to {
+        return;
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0. || ry == 0. {
+        segments.push(Segment::LineTo(to));
+        return;
+    }
+
+    let (sin, cos) = (angle * PI / 180.).sin_cos();
+    let half = (from - to) / 2.;
+    let x1 = cos * half.x + sin * half.y;
+    let y1 = -sin * half.x + cos * half.y;
+
+    // Scale up radii that are too small to reach the end point.
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1. {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coefficient = (numerator / denominator).max(0.).sqrt();
+    if large_arc == sweep {
+        coefficient = -coefficient;
+    }
+    let cx1 = coefficient * rx * y1 / ry;
+    let cy1 = -coefficient * ry * x1 / rx;
+    let middle = (from.to_vector() + to.to_vector()) / 2.;
+    let center = Point2D::new(cos * cx1 - sin * cy1 + middle.x, sin * cx1 + cos * cy1 + middle.y);
+
+    let start_angle = vector_angle(Vector2D::new(1., 0.), Vector2D::new((x1 - cx1) / rx, (y1 - cy1) / ry));
+    let mut sweep_angle = vector_angle(Vector2D::new((x1 - cx1) / rx, (y1 - cy1) / ry),
+                                       Vector2D::new((-x1 - cx1) / rx, (-y1 - cy1) / ry));
+    if !sweep && sweep_angle > 0. {
+        sweep_angle -= 2. * PI;
+    } else if sweep && sweep_angle < 0. {
+        sweep_angle += 2. * PI;
+    }
+
+    let pieces = (sweep_angle.abs() / (PI / 2.)).ceil().max(1.) as usize;
+    let step = sweep_angle / pieces as f32;
+    let handle = 4. / 3. * (step / 4.).tan();
+    let point_at = |theta: f32| {
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        (Point2D::new(center.x + rx * cos * cos_theta - ry * sin * sin_theta,
+                      center.y + rx * sin * cos_theta + ry * cos * sin_theta),
+         Vector2D::new(-rx * cos * sin_theta - ry * sin * cos_theta,
+                       -rx * sin * sin_theta + ry * cos * cos_theta))
+    };
+    let mut theta = start_angle;
+    for piece in 0..pieces {
+        let (start, start_tangent) = point_at(theta);
+        let (end, end_tangent) = point_at(theta + step);
+        // Land exactly on the end point, whatever the rounding.
+        let end = if piece == pieces - 1 { to } else { end };
+        segments.push(Segment::CubicTo(start + start_tangent * handle, end - end_tangent * handle, end));
+        theta += step;
+    }
+}
+
+fn vector_angle(u: Vector2D<f32>, v: Vector2D<f32>) -> f32 {
+    let angle = (u.dot(v) / (u.length() * v.length())).max(-1.).min(1.).acos();
+    if u.x * v.y - u.y * v.x < 0. { -angle } else { angle }
+}
+
+/// The outline of a rectangle, whose corners are rounded if it has radii.
+pub fn rect(rect: Rect<f32>, rx: f32, ry: f32) -> Vec<Segment> {
+    let (x, y, width, height) = (rect.origin.x, rect.origin.y, rect.size.width, rect.size.height);
+    let rx = rx.min(width / 2.);
+    let ry = ry.min(height / 2.);
+    if rx <= 0. || ry <= 0. {
+        return vec![
+            Segment::MoveTo(Point2D::new(x, y)),
+            Segment::LineTo(Point2D::new(x + width, y)),
+            Segment::LineTo(Point2D::new(x + width, y + height)),
+            Segment::LineTo(Point2D::new(x, y + height)),
+            Segment::Close,
+        ];
+    }
+
+    let mut segments = vec![Segment::MoveTo(Point2D::new(x + rx, y))];
+    let corners = [
+        (Point2D::new(x + width - rx, y), Point2D::new(x + width, y + ry)),
+        (Point2D::new(x + width, y + height - ry), Point2D::new(x + width - rx, y + height)),
+        (Point2D::new(x + rx, y + height), Point2D::new(x, y + height - ry)),
+        (Point2D::new(x, y + ry), Point2D::new(x + rx, y)),
+    ];
+    for &(line_end, corner_end) in &corners {
+        segments.push(Segment::LineTo(line_end));
+        arc_to_curves(line_end, rx, ry, 0., false, true, corner_end, &mut segments);
+    }
+    segments.push(Segment::Close);
+    segments
+}
+
+pub fn ellipse(center: Point2D<f32>, rx: f32, ry: f32) -> Vec<Segment> {
+    if rx <= 0. || ry <= 0. {
+        return vec![];
+    }
+    let start = Point2D::new(center.x + rx, center.y);
+    let opposite = Point2D::new(center.x - rx, center.y);
+    let mut segments = vec![Segment::MoveTo(start)];
+    arc_to_curves(start, rx, ry, 0., false, true, opposite, &mut segments);
+    arc_to_curves(opposite, rx, ry, 0., false, true, start, &mut segments);
+    segments.push(Segment::Close);
+    segments
+}
+
+/// The outline of a `<polyline>`, or of a `<polygon>` if `closed`.
+pub fn polyline(points: &[f32], closed: bool) -> Vec<Segment> {
+    let mut segments: Vec<_> = points.chunks(2).filter(|point| point.len() == 2).enumerate().map(|(index, point)| {
+        let point = Point2D::new(point[0], point[1]);
+        if index == 0 { Segment::MoveTo(point) } else { Segment::LineTo(point) }
+    }).collect();
+    if closed && !segments.is_empty() {
+        segments.push(Segment::Close);
+    }
+    segments
+}
+
+/// The bounding box of the geometry of a path, without its stroke.
+pub fn bounding_box(segments: &[Segment]) -> Option<Rect<f32>> {
+    let mut points = vec![];
+    let mut current = Point2D::zero();
+    let mut subpath_start = Point2D::zero();
+    for segment in segments {
+        match *segment {
+            Segment::MoveTo(point) => {
+                subpath_start = point;
+                points.push(point);
+                current = point;
+            },
+            Segment::LineTo(point) => {
+                points.push(point);
+                current = point;
+            },
+            Segment::QuadTo(control, point) => {
+                // A quadratic curve is a cubic one with these control points.
+                let control1 = current + (control - current) * (2. / 3.);
+                let control2 = point + (control - point) * (2. / 3.);
+                cubic_extrema(current, control1, control2, point, &mut points);
+                current = point;
+            },
+            Segment::CubicTo(control1, control2, point) => {
+                cubic_extrema(current, control1, control2, point, &mut points);
+                current = point;
+            },
+            Segment::Close => current = subpath_start,
+        }
+    }
+
+    let first = *points.first()?;
+    let (min, max) = points.iter().fold((first, first), |(min, max), point| {
+        (Point2D::new(min.x.min(point.x), min.y.min(point.y)),
+         Point2D::new(max.x.max(point.x), max.y.max(point.y)))
+    });
+    Some(Rect::new(min, Size2D::new(max.x - min.x, max.y - min.y)))
+}
+
+/// Adds the end point of a cubic curve and the points where it turns along
+/// either axis.
+fn cubic_extrema(p0: Point2D<f32>, p1: Point2D<f32>, p2: Point2D<f32>, p3: Point2D<f32>,
+                 points: &mut Vec<Point2D<f32>>) {
+    points.push(p3);
+    let at = |t: f32| {
+        let u = 1. - t;
+        (p0.to_vector() * (u * u * u) + p1.to_vector() * (3. * u * u * t) +
+         p2.to_vector() * (3. * u * t * t) + p3.to_vector() * (t * t * t)).to_point()
+    };
+    for &(a, b, c, d) in &[(p0.x, p1.x, p2.x, p3.x), (p0.y, p1.y, p2.y, p3.y)] {
+        // The derivative, divided by three, is a quadratic polynomial.
+        let qa = -a + 3. * b - 3. * c + d;
+        let qb = 2. * (a - 2. * b + c);
+        let qc = b - a;
+        let mut roots = vec![];
+        if qa.abs() < 1e-6 {
+            if qb.abs() > 1e-6 {
+                roots.push(-qc / qb);
+            }
+        } else {
+            let discriminant = qb * qb - 4. * qa * qc;
+            if discriminant >= 0. {
+                let root = discriminant.sqrt();
+                roots.push((-qb + root) / (2. * qa));
+                roots.push((-qb - root) / (2. * qa));
+            }
+        }
+        points.extend(roots.into_iter().filter(|&t| t > 0. && t < 1.).map(&at));
+    }
+}
@@ -0,0 +1,740 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Painting and hit-testing an SVG document with Azure.
+//!
+//! Shapes, groups, nested viewports, `<use>` and `<symbol>`, and linear and
+//! radial gradients are painted. Text is painted as a single line at the
+//! first position of its `<text>` element, when the environment can lay it
+//! out. Images, clipping paths, masks, patterns, markers and filters are
+//! not, nor is anything outside the document. Azure can't honour `fill-rule` or dash offsets, so every fill
+//! is non-zero and dashes start at the beginning of the path.
+
+use azure::azure::AzFloat;
+use azure::azure_hl::{AntialiasMode, CapStyle, Color, ColorPattern, CompositionOp};
+use azure::azure_hl::{DrawOptions, DrawSurfaceOptions, DrawTarget, ExtendMode, Filter};
+use azure::azure_hl::{GradientStop, JoinStyle, LinearGradientPattern, Path, Pattern};
+use azure::azure_hl::{RadialGradientPattern, StrokeOptions};
+use cssparser::RGBA;
+use euclid::{Point2D, Rect, Size2D, Transform2D, Vector2D};
+use path::{self, Segment};
+use style::{self, Axis, Length, LineCap, LineJoin, Paint, Style, Stylesheet, TextAnchor, Unit};
+use tree::{NodeId, Tree};
+use {Environment, Font};
+
+/// How deeply `<use>` elements and gradient references may nest, which
+/// also stops reference cycles.
+const MAX_REFERENCE_DEPTH: usize = 16;
+
+/// The user space of an element: how it maps to pixels, and the size of
+/// its nearest viewport for percentages.
+#[derive(Clone, Copy)]
+struct Space {
+    transform: Transform2D<f32>,
+    viewport: (f32, f32),
+}
+
+/// What a walk over the document does with its shapes.
+enum Mode<'a> {
+    Paint(&'a DrawTarget),
+    /// Finds the last shape painted over a point in device space, along with
+    /// the element it was instantiated by if it came from a `<use>`.
+    HitTest {
+        draw_target: &'a DrawTarget,
+        point: Point2D<f32>,
+        hit: Option<NodeId>,
+    },
+}
+
+impl<'a> Mode<'a> {
+    fn draw_target(&self) -> &'a DrawTarget {
+        match *self {
+            Mode::Paint(draw_target) | Mode::HitTest { draw_target, .. } => draw_target,
+        }
+    }
+}
+
+pub struct Renderer<'a> {
+    tree: &'a Tree,
+    stylesheet: &'a Stylesheet,
+    environment: &'a Environment<'a>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(tree: &'a Tree, stylesheet: &'a Stylesheet, environment: &'a Environment<'a>) -> Renderer<'a> {
+        Renderer {
+            tree: tree,
+            stylesheet: stylesheet,
+            environment: environment,
+        }
+    }
+
+    /// Paints the document into a viewport of the given size, in CSS pixels,
+    /// on a draw target that is `scale` times larger.
+    pub fn paint(&self, draw_target: &DrawTarget, viewport: Size2D<f32>, scale: f32) {
+        if let Some(root) = self.tree.root() {
+            let mut mode = Mode::Paint(draw_target);
+            self.walk_root(&mut mode, root, viewport, scale);
+        }
+    }
+
+    /// The element that is painted topmost at a point of the viewport, if
+    /// any. Content instantiated by a `<use>` element reports that element.
+    pub fn hit_test(&self, draw_target: &DrawTarget, viewport: Size2D<f32>, point: Point2D<f32>)
+                    -> Option<NodeId> {
+        let root = self.tree.root()?;
+        let mut mode = Mode::HitTest {
+            draw_target: draw_target,
+            point: point,
+            hit: None,
+        };
+        self.walk_root(&mut mode, root, viewport, 1.);
+        match mode {
+            Mode::HitTest { hit, .. } => hit,
+            Mode::Paint(_) => None,
+        }
+    }
+
+    fn walk_root(&self, mode: &mut Mode, root: NodeId, viewport: Size2D<f32>, scale: f32) {
+        let style = style::cascade(self.tree, self.stylesheet, root, &self.initial_style());
+        if !style.display {
+            return;
+        }
+        let space = Space {
+            transform: Transform2D::create_scale(scale, scale),
+            viewport: (viewport.width, viewport.height),
+        };
+        let viewport_rect = Rect::new(Point2D::zero(), viewport);
+        let space = self.establish_viewport(root, viewport_rect, space);
+        self.walk_group(mode, root, &style, space, &mut vec![]);
+    }
+
+    /// The user space inside an `<svg>` or `<symbol>` element laid out into
+    /// a viewport.
+    fn establish_viewport(&self, element: NodeId, viewport: Rect<f32>, space: Space) -> Space {
+        let mut transform = Transform2D::create_translation(viewport.origin.x, viewport.origin.y);
+        let mut user_viewport = (viewport.size.width, viewport.size.height);
+        if let Some(view_box) = self.tree.attribute(element, "viewBox").and_then(parse_view_box) {
+            let aspect_ratio = self.tree.attribute(element, "preserveAspectRatio").unwrap_or("");
+            transform = view_box_transform(view_box, aspect_ratio, viewport.size).post_mul(&transform);
+            user_viewport = (view_box.size.width, view_box.size.height);
+        }
+        Space {
+            transform: transform.post_mul(&space.transform),
+            viewport: user_viewport,
+        }
+    }
+
+    fn walk_children(&self, mode: &mut Mode, node: NodeId, style: &Style, space: Space,
+                     uses: &mut Vec<NodeId>) {
+        for &child in &self.tree.node(node).children {
+            self.walk_element(mode, child, style, space, uses);
+        }
+    }
+
+    /// Walks the children of a container, painting them into a layer first
+    /// if the container is translucent.
+    fn walk_group(&self, mode: &mut Mode, node: NodeId, style: &Style, space: Space,
+                  uses: &mut Vec<NodeId>) {
+        if style.opacity <= 0. {
+            return;
+        }
+        match *mode {
+            Mode::Paint(draw_target) if style.opacity < 1. => {
+                let layer = create_layer(draw_target);
+                self.walk_children(&mut Mode::Paint(&layer), node, style, space, uses);
+                composite_layer(draw_target, &layer, style.opacity);
+            },
+            _ => self.walk_children(mode, node, style, space, uses),
+        }
+    }
+
+    fn walk_element(&self, mode: &mut Mode, node: NodeId, parent_style: &Style, space: Space,
+                    uses: &mut Vec<NodeId>) {
+        let name = match self.tree.name(node) {
+            Some(name) => name,
+            None => return,
+        };
+        let style = style::cascade(self.tree, self.stylesheet, node, parent_style);
+        if !style.display {
+            return;
+        }
+        let mut space = space;
+        if name != "svg" {
+            if let Some(transform) = self.tree.attribute(node, "transform") {
+                match style::parse_transform(transform) {
+                    Some(transform) => space.transform = transform.post_mul(&space.transform),
+                    // An invalid transform is ignored, as if it wasn't set.
+                    None => {},
+                }
+            }
+        }
+
+        match name {
+            "g" | "a" | "switch" => self.walk_group(mode, node, &style, space, uses),
+            "svg" => {
+                let viewport = self.viewport_rect(node, &style, space, None);
+                if viewport.size.width <= 0. || viewport.size.height <= 0. {
+                    return;
+                }
+                let inner = self.establish_viewport(node, viewport, space);
+                self.clipped(mode, viewport, space, |mode| self.walk_group(mode, node, &style, inner, uses));
+            },
+            "use" => self.walk_use(mode, node, &style, space, uses),
+            "path" | "rect" | "circle" | "ellipse" | "line" | "polyline" | "polygon" | "text" => {
+                let segments = if name == "text" {
+                    self.text_segments(node, &style, space)
+                } else {
+                    self.shape_segments(node, name, &style, space)
+                };
+                if segments.is_empty() {
+                    return;
+                }
+                match *mode {
+                    Mode::Paint(draw_target) => {
+                        if style.opacity < 1. {
+                            let layer = create_layer(draw_target);
+                            self.paint_shape(&layer, &segments, &style, space);
+                            composite_layer(draw_target, &layer, style.opacity);
+                        } else if style.opacity > 0. {
+                            self.paint_shape(draw_target, &segments, &style, space);
+                        }
+                    },
+                    Mode::HitTest { draw_target, point, ref mut hit } => {
+                        if self.shape_contains_point(draw_target, &segments, &style, space, point) {
+                            *hit = Some(uses.first().cloned().unwrap_or(node));
+                        }
+                    },
+                }
+            },
+            // Everything else is either not rendered by itself, such as
+            // `<defs>` and gradients, or not supported.
+            _ => {},
+        }
+    }
+
+    /// The rectangle of a nested viewport, from its `x`, `y`, `width` and
+    /// `height`, where a `<use>` element may override the size.
+    fn viewport_rect(&self, node: NodeId, style: &Style, space: Space, size_from: Option<NodeId>)
+                     -> Rect<f32> {
+        let length = |element: NodeId, name: &str, default: Length, axis: Axis| {
+            self.tree.attribute(element, name)
+                .and_then(Length::parse)
+                .unwrap_or(default)
+                .resolve(space.viewport, style.font_size, axis)
+        };
+        let full = Length { value: 100., unit: Unit::Percent };
+        let size_element = |name: &str| {
+            match size_from {
+                Some(element) if self.tree.attribute(element, name).is_some() => element,
+                _ => node,
+            }
+        };
+        Rect::new(Point2D::new(length(node, "x", Length::new(0.), Axis::Horizontal),
+                               length(node, "y", Length::new(0.), Axis::Vertical)),
+                  Size2D::new(length(size_element("width"), "width", full, Axis::Horizontal),
+                              length(size_element("height"), "height", full, Axis::Vertical)))
+    }
+
+    /// Clips what `walk` paints to a viewport, as nested viewports don't let
+    /// their content overflow.
+    fn clipped<F>(&self, mode: &mut Mode, viewport: Rect<f32>, space: Space, walk: F)
+        where F: FnOnce(&mut Mode)
+    {
+        let draw_target = mode.draw_target();
+        let clip = build_path(draw_target, &path::rect(viewport, 0., 0.));
+        draw_target.set_transform(&space.transform);
+        draw_target.push_clip(&clip);
+        walk(mode);
+        draw_target.pop_clip();
+    }
+
+    /// Instantiates the element a `<use>` element references.
+    /// https://www.w3.org/TR/SVG2/struct.html#UseElement
+    fn walk_use(&self, mode: &mut Mode, node: NodeId, style: &Style, space: Space,
+                uses: &mut Vec<NodeId>) {
+        let target = match self.tree.attribute(node, "href").and_then(|href| self.tree.resolve_reference(href)) {
+            Some(target) => target,
+            None => return,
+        };
+        // Referencing an ancestor of a `<use>` being instantiated would
+        // recurse forever.
+        if uses.len() >= MAX_REFERENCE_DEPTH ||
+           uses.iter().chain(Some(&node)).any(|&user| self.is_inclusive_ancestor(target, user)) {
+            return;
+        }
+
+        let offset = Vector2D::new(
+            self.tree.attribute(node, "x").and_then(Length::parse).map_or(0., |x| {
+                x.resolve(space.viewport, style.font_size, Axis::Horizontal)
+            }),
+            self.tree.attribute(node, "y").and_then(Length::parse).map_or(0., |y| {
+                y.resolve(space.viewport, style.font_size, Axis::Vertical)
+            }));
+        let space = Space {
+            transform: space.transform.pre_translate(offset),
+            viewport: space.viewport,
+        };
+
+        uses.push(node);
+        match *mode {
+            Mode::Paint(draw_target) if style.opacity < 1. => {
+                if style.opacity > 0. {
+                    let layer = create_layer(draw_target);
+                    self.instantiate(&mut Mode::Paint(&layer), node, target, style, space, uses);
+                    composite_layer(draw_target, &layer, style.opacity);
+                }
+            },
+            _ => self.instantiate(mode, node, target, style, space, uses),
+        }
+        uses.pop();
+    }
+
+    fn instantiate(&self, mode: &mut Mode, node: NodeId, target: NodeId, style: &Style, space: Space,
+                   uses: &mut Vec<NodeId>) {
+        if self.tree.name(target) != Some("symbol") {
+            return self.walk_element(mode, target, style, space, uses);
+        }
+        // A symbol is a viewport at the origin of the `<use>`, which may
+        // size it.
+        let target_style = style::cascade(self.tree, self.stylesheet, target, style);
+        if !target_style.display {
+            return;
+        }
+        let mut viewport = self.viewport_rect(target, &target_style, space, Some(node));
+        viewport.origin = Point2D::zero();
+        if viewport.size.width <= 0. || viewport.size.height <= 0. {
+            return;
+        }
+        let inner = self.establish_viewport(target, viewport, space);
+        self.clipped(mode, viewport, space, |mode| self.walk_group(mode, target, &target_style, inner, uses));
+    }
+
+    fn is_inclusive_ancestor(&self, ancestor: NodeId, node: NodeId) -> bool {
+        let mut current = Some(node);
+        while let Some(node) = current {
+            if node == ancestor {
+                return true;
+            }
+            current = self.tree.node(node).parent;
+        }
+        false
+    }
+
+    /// The outline of a shape, in user space.
+    fn shape_segments(&self, node: NodeId, name: &str, style: &Style, space: Space) -> Vec<Segment> {
+        let length = |attribute: &str, axis: Axis| {
+            self.tree.attribute(node, attribute)
+                .and_then(Length::parse)
+                .map(|length| length.resolve(space.viewport, style.font_size, axis))
+        };
+        let number_list = |attribute: &str| {
+            self.tree.attribute(node, attribute).and_then(style::parse_number_list).unwrap_or(vec![])
+        };
+        match name {
+            "path" => path::parse_path_data(self.tree.attribute(node, "d").unwrap_or("")),
+            "rect" => {
+                let width = length("width", Axis::Horizontal).unwrap_or(0.);
+                let height = length("height", Axis::Vertical).unwrap_or(0.);
+                if width <= 0. || height <= 0. {
+                    return vec![];
+                }
+                // A missing radius takes the value of the other one.
+                let rx = length("rx", Axis::Horizontal).filter(|&rx| rx >= 0.);
+                let ry = length("ry", Axis::Vertical).filter(|&ry| ry >= 0.);
+                let (rx, ry) = match (rx, ry) {
+                    (Some(rx), Some(ry)) => (rx, ry),
+                    (Some(radius), None) | (None, Some(radius)) => (radius, radius),
+                    (None, None) => (0., 0.),
+                };
+                let origin = Point2D::new(length("x", Axis::Horizontal).unwrap_or(0.),
+                                          length("y", Axis::Vertical).unwrap_or(0.));
+                path::rect(Rect::new(origin, Size2D::new(width, height)), rx, ry)
+            },
+            "circle" | "ellipse" => {
+                let center = Point2D::new(length("cx", Axis::Horizontal).unwrap_or(0.),
+                                          length("cy", Axis::Vertical).unwrap_or(0.));
+                let (rx, ry) = if name == "circle" {
+                    let r = length("r", Axis::Other).unwrap_or(0.);
+                    (r, r)
+                } else {
+                    (length("rx", Axis::Horizontal).unwrap_or(0.), length("ry", Axis::Vertical).unwrap_or(0.))
+                };
+                path::ellipse(center, rx, ry)
+            },
+            "line" => vec![
+                Segment::MoveTo(Point2D::new(length("x1", Axis::Horizontal).unwrap_or(0.),
+                                             length("y1", Axis::Vertical).unwrap_or(0.))),
+                Segment::LineTo(Point2D::new(length("x2", Axis::Horizontal).unwrap_or(0.),
+                                             length("y2", Axis::Vertical).unwrap_or(0.))),
+            ],
+            "polyline" => path::polyline(&number_list("points"), false),
+            "polygon" => path::polyline(&number_list("points"), true),
+            _ => vec![],
+        }
+    }
+
+    /// The outlines of the glyphs of a `<text>` element, in user space.
+    /// https://www.w3.org/TR/SVG2/text.html#TextLayout
+    fn text_segments(&self, node: NodeId, style: &Style, space: Space) -> Vec<Segment> {
+        let outliner = match self.environment.text_outliner {
+            Some(outliner) => outliner,
+            None => return vec![],
+        };
+        let text = self.tree.text_content(node).split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return vec![];
+        }
+        // Only the first of a list of positions is used, for the whole line.
+        let position = |attribute: &str, axis: Axis| {
+            self.tree.attribute(node, attribute)
+                .and_then(|list| style::split_list(list).next())
+                .and_then(Length::parse)
+                .map_or(0., |length| length.resolve(space.viewport, style.font_size, axis))
+        };
+        let font = Font {
+            family: &style.font_family,
+            size: style.font_size,
+            bold: style.font_bold,
+            italic: style.font_italic,
+        };
+        let (segments, advance) = outliner.outline(&text, &font);
+        let anchor_offset = match style.text_anchor {
+            TextAnchor::Start => 0.,
+            TextAnchor::Middle => advance / 2.,
+            TextAnchor::End => advance,
+        };
+        let offset = Vector2D::new(position("x", Axis::Horizontal) - anchor_offset, position("y", Axis::Vertical));
+        segments.into_iter().map(|segment| segment.translate(offset)).collect()
+    }
+
+    fn paint_shape(&self, draw_target: &DrawTarget, segments: &[Segment], style: &Style, space: Space) {
+        if !style.visible {
+            return;
+        }
+        let outline = build_path(draw_target, segments);
+        draw_target.set_transform(&space.transform);
+
+        if let Some(fill) = self.pattern(draw_target, &style.resolve_paint(&style.fill), segments, space, 0) {
+            let options = DrawOptions::new(style.fill_opacity, CompositionOp::Over, AntialiasMode::Default);
+            draw_target.fill(&outline, fill.to_pattern_ref(), &options);
+        }
+
+        let stroke = match self.pattern(draw_target, &style.resolve_paint(&style.stroke), segments, space, 0) {
+            Some(stroke) => stroke,
+            None => return,
+        };
+        let width = style.stroke_width.resolve(space.viewport, style.font_size, Axis::Other);
+        if width <= 0. {
+            return;
+        }
+        let dashes = dash_pattern(style, space);
+        let stroke_options = stroke_options(style, width, &dashes);
+        let options = DrawOptions::new(style.stroke_opacity, CompositionOp::Over, AntialiasMode::Default);
+        draw_target.stroke(&outline, stroke.to_pattern_ref(), &stroke_options, &options);
+    }
+
+    /// Whether a visible shape paints a point with its fill or stroke, with
+    /// the `visiblePainted` behaviour of `pointer-events`.
+    fn shape_contains_point(&self, draw_target: &DrawTarget, segments: &[Segment], style: &Style,
+                            space: Space, point: Point2D<f32>) -> bool {
+        if !style.visible || style.opacity <= 0. {
+            return false;
+        }
+        let outline = build_path(draw_target, segments);
+        if style.fill != Paint::None &&
+           outline.contains_point(point.x as f64, point.y as f64, &space.transform) {
+            return true;
+        }
+        let width = style.stroke_width.resolve(space.viewport, style.font_size, Axis::Other);
+        if style.stroke == Paint::None || width <= 0. {
+            return false;
+        }
+
+        // Azure can't hit-test strokes, so this strokes the outline onto a
+        // single pixel at the point.
+        let pixel = draw_target.create_similar_draw_target(&Size2D::new(1, 1), draw_target.get_format());
+        pixel.set_transform(&space.transform.post_translate(Vector2D::new(0.5 - point.x, 0.5 - point.y)));
+        let dashes = dash_pattern(style, space);
+        let black = Pattern::Color(ColorPattern::new(Color::black()));
+        pixel.stroke(&outline,
+                     black.to_pattern_ref(),
+                     &stroke_options(style, width, &dashes),
+                     &DrawOptions::new(1., CompositionOp::Over, AntialiasMode::None));
+        let mut painted = false;
+        pixel.snapshot().get_data_surface().with_data(|data| painted = data[3] != 0);
+        painted
+    }
+
+    /// The Azure pattern for a paint, or `None` if nothing is painted.
+    fn pattern(&self, draw_target: &DrawTarget, paint: &Paint, segments: &[Segment], space: Space,
+               depth: usize)
+               -> Option<Pattern> {
+        match *paint {
+            Paint::None | Paint::CurrentColor => None,
+            Paint::Color(color) => Some(color_pattern(to_azure_color(color, 1.))),
+            Paint::Server(ref reference, ref fallback) => {
+                let server = self.tree.resolve_reference(reference);
+                match server.and_then(|server| self.tree.name(server)) {
+                    Some("linearGradient") | Some("radialGradient") => {
+                        self.gradient_pattern(draw_target, server.unwrap(), segments, space)
+                    },
+                    _ if depth == 0 => self.pattern(draw_target, fallback, segments, space, depth + 1),
+                    _ => None,
+                }
+            },
+        }
+    }
+
+    /// The gradient an element such as `<linearGradient>` describes, with
+    /// the attributes and stops it inherits through `href`.
+    /// https://www.w3.org/TR/SVG2/pservers.html
+    fn gradient_pattern(&self, draw_target: &DrawTarget, gradient: NodeId, segments: &[Segment],
+                        space: Space) -> Option<Pattern> {
+        let mut chain = vec![gradient];
+        while chain.len() < MAX_REFERENCE_DEPTH {
+            let next = self.tree.attribute(*chain.last().unwrap(), "href")
+                .and_then(|href| self.tree.resolve_reference(href))
+                .filter(|next| !chain.contains(next))
+                .filter(|&next| {
+                    let name = self.tree.name(next);
+                    name == Some("linearGradient") || name == Some("radialGradient")
+                });
+            match next {
+                Some(next) => chain.push(next),
+                None => break,
+            }
+        }
+        let attribute = |name: &str| chain.iter().filter_map(|&element| self.tree.attribute(element, name)).next();
+
+        let stop_element = chain.iter().cloned().find(|&element| {
+            self.tree.node(element).children.iter().any(|&child| self.tree.name(child) == Some("stop"))
+        });
+        let stops = match stop_element {
+            Some(element) => self.gradient_stops(element),
+            None => vec![],
+        };
+        match stops.len() {
+            0 => return None,
+            1 => return Some(color_pattern(stops[0].color)),
+            _ => {},
+        }
+
+        let bounding_box_units = attribute("gradientUnits") != Some("userSpaceOnUse");
+        let mut matrix = attribute("gradientTransform")
+            .and_then(style::parse_transform)
+            .unwrap_or(Transform2D::identity());
+        let viewport = if bounding_box_units {
+            let bounds = path::bounding_box(segments)?;
+            if bounds.size.width <= 0. || bounds.size.height <= 0. {
+                return None;
+            }
+            matrix = matrix.post_mul(&Transform2D::create_scale(bounds.size.width, bounds.size.height)
+                .post_translate(bounds.origin.to_vector()));
+            // Fractions and percentages are of the bounding box, which is
+            // the unit square here.
+            (1., 1.)
+        } else {
+            space.viewport
+        };
+        let font_size = self.computed_style(gradient).font_size;
+        let coordinate = |name: &str, default: Length, axis: Axis| {
+            let length = attribute(name).and_then(Length::parse).unwrap_or(default);
+            match (bounding_box_units, length.unit) {
+                (true, Unit::Percent) => length.value / 100.,
+                (true, _) => length.value,
+                (false, _) => length.resolve(viewport, font_size, axis),
+            }
+        };
+        let percent = |value: f32| Length { value: value, unit: Unit::Percent };
+
+        let extend_mode = match attribute("spreadMethod") {
+            Some("reflect") => ExtendMode::Reflect,
+            Some("repeat") => ExtendMode::Repeat,
+            _ => ExtendMode::Clamp,
+        };
+        let stops = draw_target.create_gradient_stops(&stops, extend_mode);
+        if self.tree.name(gradient) == Some("linearGradient") {
+            let start = Point2D::new(coordinate("x1", percent(0.), Axis::Horizontal),
+                                     coordinate("y1", percent(0.), Axis::Vertical));
+            let end = Point2D::new(coordinate("x2", percent(100.), Axis::Horizontal),
+                                   coordinate("y2", percent(0.), Axis::Vertical));
+            Some(Pattern::LinearGradient(LinearGradientPattern::new(&start, &end, stops, &matrix)))
+        } else {
+            let center = Point2D::new(coordinate("cx", percent(50.), Axis::Horizontal),
+                                      coordinate("cy", percent(50.), Axis::Vertical));
+            let radius = coordinate("r", percent(50.), Axis::Other);
+            if radius <= 0. {
+                return None;
+            }
+            let focus = Point2D::new(
+                attribute("fx").map_or(center.x, |_| coordinate("fx", percent(50.), Axis::Horizontal)),
+                attribute("fy").map_or(center.y, |_| coordinate("fy", percent(50.), Axis::Vertical)));
+            let focal_radius = coordinate("fr", percent(0.), Axis::Other).max(0.);
+            Some(Pattern::RadialGradient(RadialGradientPattern::new(&focus, &center,
+                                                                    focal_radius, radius,
+                                                                    stops, &matrix)))
+        }
+    }
+
+    /// The stops of a gradient element, with offsets made increasing.
+    fn gradient_stops(&self, gradient: NodeId) -> Vec<GradientStop> {
+        let gradient_style = self.computed_style(gradient);
+        let mut last_offset = 0.;
+        self.tree.node(gradient).children.iter().filter(|&&child| self.tree.name(child) == Some("stop")).map(|&stop| {
+            let style = style::cascade(self.tree, self.stylesheet, stop, &gradient_style);
+            let offset = match self.tree.attribute(stop, "offset").and_then(Length::parse) {
+                Some(Length { value, unit: Unit::Percent }) => value / 100.,
+                Some(Length { value, unit: Unit::None }) => value,
+                _ => 0.,
+            };
+            last_offset = offset.max(0.).min(1.).max(last_offset);
+            GradientStop {
+                offset: last_offset,
+                color: to_azure_color(style.stop_color, style.stop_opacity),
+            }
+        }).collect()
+    }
+
+    /// The style of an element outside of any `<use>`, such as a gradient.
+    fn computed_style(&self, node: NodeId) -> Style {
+        let parent_style = match self.tree.node(node).parent {
+            Some(parent) => self.computed_style(parent),
+            None => self.initial_style(),
+        };
+        style::cascade(self.tree, self.stylesheet, node, &parent_style)
+    }
+
+    /// The style the root inherits from its environment.
+    fn initial_style(&self) -> Style {
+        Style {
+            color: self.environment.color,
+            font_family: self.environment.font_family.to_owned(),
+            font_size: self.environment.font_size,
+            ..Style::initial()
+        }
+    }
+}
+
+/// Parses a `viewBox` attribute, which is ignored if its size is negative
+/// and disables rendering if it is zero.
+pub fn parse_view_box(value: &str) -> Option<Rect<f32>> {
+    let numbers = style::parse_number_list(value)?;
+    match numbers[..] {
+        [x, y, width, height] if width >= 0. && height >= 0. => {
+            Some(Rect::new(Point2D::new(x, y), Size2D::new(width, height)))
+        },
+        _ => None,
+    }
+}
+
+/// Maps a `viewBox` onto a viewport.
+/// https://www.w3.org/TR/SVG2/coords.html#ComputingAViewportsTransform
+pub fn view_box_transform(view_box: Rect<f32>, aspect_ratio: &str, viewport: Size2D<f32>)
+                          -> Transform2D<f32> {
+    if view_box.size.width <= 0. || view_box.size.height <= 0. {
+        return Transform2D::create_scale(0., 0.);
+    }
+    let mut scale_x = viewport.width / view_box.size.width;
+    let mut scale_y = viewport.height / view_box.size.height;
+
+    let mut values = aspect_ratio.split_whitespace();
+    let align = values.next().unwrap_or("xMidYMid");
+    let slice = values.next() == Some("slice");
+    if align != "none" {
+        let scale = if slice { scale_x.max(scale_y) } else { scale_x.min(scale_y) };
+        scale_x = scale;
+        scale_y = scale;
+    }
+    let free = Vector2D::new(viewport.width - view_box.size.width * scale_x,
+                             viewport.height - view_box.size.height * scale_y);
+    let fraction = |position: &str| match position {
+        "Min" => 0.,
+        "Max" => 1.,
+        _ => 0.5,
+    };
+    let (align_x, align_y) = if align.len() == 8 && align.starts_with('x') {
+        (fraction(&align[1..4]), fraction(&align[5..8]))
+    } else {
+        (0.5, 0.5)
+    };
+    let align_x = if align == "none" { 0. } else { align_x };
+    let align_y = if align == "none" { 0. } else { align_y };
+
+    Transform2D::create_translation(-view_box.origin.x, -view_box.origin.y)
+        .post_scale(scale_x, scale_y)
+        .post_translate(Vector2D::new(free.x * align_x, free.y * align_y))
+}
+
+fn build_path(draw_target: &DrawTarget, segments: &[Segment]) -> Path {
+    let path_builder = draw_target.create_path_builder();
+    for segment in segments {
+        match *segment {
+            Segment::MoveTo(point) => path_builder.move_to(point),
+            Segment::LineTo(point) => path_builder.line_to(point),
+            Segment::QuadTo(ref control, ref point) => path_builder.quadratic_curve_to(control, point),
+            Segment::CubicTo(ref control1, ref control2, ref point) => {
+                path_builder.bezier_curve_to(control1, control2, point)
+            },
+            Segment::Close => path_builder.close(),
+        }
+    }
+    path_builder.finish()
+}
+
+/// The dash lengths, in user units. A list of odd length is repeated, and
+/// one that adds up to nothing draws a solid line.
+fn dash_pattern(style: &Style, space: Space) -> Vec<AzFloat> {
+    let mut dashes: Vec<AzFloat> = style.stroke_dasharray.iter()
+        .map(|dash| dash.resolve(space.viewport, style.font_size, Axis::Other))
+        .collect();
+    if dashes.iter().sum::<AzFloat>() <= 0. {
+        return vec![];
+    }
+    if dashes.len() % 2 == 1 {
+        let copy = dashes.clone();
+        dashes.extend(copy);
+    }
+    dashes
+}
+
+fn stroke_options<'a>(style: &Style, width: f32, dashes: &'a [AzFloat]) -> StrokeOptions<'a> {
+    let join = match style.stroke_linejoin {
+        LineJoin::Miter => JoinStyle::MiterOrBevel,
+        LineJoin::Round => JoinStyle::Round,
+        LineJoin::Bevel => JoinStyle::Bevel,
+    };
+    let cap = match style.stroke_linecap {
+        LineCap::Butt => CapStyle::Butt,
+        LineCap::Round => CapStyle::Round,
+        LineCap::Square => CapStyle::Square,
+    };
+    StrokeOptions::new(width, join, cap, style.stroke_miterlimit, dashes)
+}
+
+fn to_azure_color(color: RGBA, opacity: f32) -> Color {
+    Color::rgba(color.red_f32(), color.green_f32(), color.blue_f32(), color.alpha_f32() * opacity)
+}
+
+fn color_pattern(color: Color) -> Pattern {
+    Pattern::Color(ColorPattern::new(color))
+}
+
+/// A transparent draw target the size of another, for painting a group
+/// that is composited with some opacity.
+fn create_layer(draw_target: &DrawTarget) -> DrawTarget {
+    let size = draw_target.get_size();
+    draw_target.create_similar_draw_target(&Size2D::new(size.width, size.height), draw_target.get_format())
+}
+
+fn composite_layer(draw_target: &DrawTarget, layer: &DrawTarget, opacity: f32) {
+    let size = layer.get_size();
+    let bounds = Rect::new(Point2D::zero(), Size2D::new(size.width as AzFloat, size.height as AzFloat));
+    draw_target.set_transform(&Transform2D::identity());
+    draw_target.draw_surface(layer.snapshot(),
+                             bounds,
+                             bounds,
+                             DrawSurfaceOptions::new(Filter::Linear, true),
+                             DrawOptions::new(opacity, CompositionOp::Over, AntialiasMode::None));
+}
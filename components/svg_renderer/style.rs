@@ -0,0 +1,667 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Computed styles of SVG elements.
+//!
+//! Declarations come from presentation attributes, then from the rules of
+//! `<style>` elements whose selectors are a single compound selector, and
+//! last from the `style` attribute.
+//!
+//! https://www.w3.org/TR/SVG2/styling.html
+
+use cssparser::{Color as CSSColor, Parser, ParserInput, RGBA};
+use euclid::{Transform2D, Vector2D};
+use std::f32::consts::PI;
+use tree::{NodeId, Tree};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    None,
+    Em,
+    Ex,
+    Percent,
+    In,
+    Cm,
+    Mm,
+    Pt,
+    Pc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Length {
+    pub value: f32,
+    pub unit: Unit,
+}
+
+/// Which dimension of the viewport a percentage refers to.
+#[derive(Clone, Copy)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+    /// The normalized diagonal, for lengths such as radii and stroke widths.
+    Other,
+}
+
+impl Length {
+    pub fn new(value: f32) -> Length {
+        Length { value: value, unit: Unit::None }
+    }
+
+    pub fn parse(value: &str) -> Option<Length> {
+        let value = value.trim();
+        let split = value.find(|c: char| c.is_ascii_alphabetic() || c == '%')
+            .filter(|&index| {
+                // An exponent is part of the number.
+                let rest = &value[index..];
+                !(rest.starts_with('e') || rest.starts_with('E')) ||
+                    rest[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+            })
+            .unwrap_or(value.len());
+        let number = value[..split].parse::<f32>().ok().filter(|number| number.is_finite())?;
+        let unit = match &*value[split..].to_ascii_lowercase() {
+            "" | "px" => Unit::None,
+            "em" => Unit::Em,
+            "ex" => Unit::Ex,
+            "%" => Unit::Percent,
+            "in" => Unit::In,
+            "cm" => Unit::Cm,
+            "mm" => Unit::Mm,
+            "pt" => Unit::Pt,
+            "pc" => Unit::Pc,
+            _ => return None,
+        };
+        Some(Length { value: number, unit: unit })
+    }
+
+    /// The length in user units.
+    pub fn resolve(&self, viewport: (f32, f32), font_size: f32, axis: Axis) -> f32 {
+        match self.unit {
+            Unit::None => self.value,
+            Unit::Em => self.value * font_size,
+            Unit::Ex => self.value * font_size / 2.,
+            Unit::In => self.value * 96.,
+            Unit::Cm => self.value * 96. / 2.54,
+            Unit::Mm => self.value * 96. / 25.4,
+            Unit::Pt => self.value * 4. / 3.,
+            Unit::Pc => self.value * 16.,
+            Unit::Percent => {
+                let (width, height) = viewport;
+                let reference = match axis {
+                    Axis::Horizontal => width,
+                    Axis::Vertical => height,
+                    Axis::Other => ((width * width + height * height) / 2.).sqrt(),
+                };
+                self.value * reference / 100.
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    None,
+    Color(RGBA),
+    CurrentColor,
+    /// A reference to a paint server such as a gradient, with the paint to
+    /// use if it doesn't resolve.
+    Server(String, Box<Paint>),
+}
+
+impl Paint {
+    fn parse(value: &str) -> Option<Paint> {
+        let value = value.trim();
+        if value == "none" {
+            return Some(Paint::None);
+        }
+        if value.starts_with("url(") {
+            let end = value.find(')')?;
+            let fallback = match value[end + 1..].trim() {
+                "" => Paint::None,
+                fallback => Paint::parse(fallback)?,
+            };
+            return Some(Paint::Server(value[..end + 1].to_owned(), Box::new(fallback)));
+        }
+        match parse_color(value)? {
+            CSSColor::RGBA(rgba) => Some(Paint::Color(rgba)),
+            CSSColor::CurrentColor => Some(Paint::CurrentColor),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAnchor {
+    Start,
+    Middle,
+    End,
+}
+
+#[derive(Clone, Debug)]
+pub struct Style {
+    // Inherited properties.
+    pub color: RGBA,
+    pub fill: Paint,
+    pub fill_opacity: f32,
+    pub stroke: Paint,
+    pub stroke_width: Length,
+    pub stroke_opacity: f32,
+    pub stroke_linecap: LineCap,
+    pub stroke_linejoin: LineJoin,
+    pub stroke_miterlimit: f32,
+    pub stroke_dasharray: Vec<Length>,
+    pub visible: bool,
+    pub font_family: String,
+    pub font_size: f32,
+    pub font_bold: bool,
+    pub font_italic: bool,
+    pub text_anchor: TextAnchor,
+
+    // Properties that aren't inherited.
+    pub display: bool,
+    pub opacity: f32,
+    pub stop_color: RGBA,
+    pub stop_opacity: f32,
+}
+
+impl Style {
+    pub fn initial() -> Style {
+        Style {
+            color: RGBA::new(0, 0, 0, 255),
+            fill: Paint::Color(RGBA::new(0, 0, 0, 255)),
+            fill_opacity: 1.,
+            stroke: Paint::None,
+            stroke_width: Length::new(1.),
+            stroke_opacity: 1.,
+            stroke_linecap: LineCap::Butt,
+            stroke_linejoin: LineJoin::Miter,
+            stroke_miterlimit: 4.,
+            stroke_dasharray: vec![],
+            visible: true,
+            font_family: "serif".to_owned(),
+            font_size: 16.,
+            font_bold: false,
+            font_italic: false,
+            text_anchor: TextAnchor::Start,
+            display: true,
+            opacity: 1.,
+            stop_color: RGBA::new(0, 0, 0, 255),
+            stop_opacity: 1.,
+        }
+    }
+
+    /// The style of a child of an element with this style, before any of
+    /// its own declarations apply.
+    fn inherit(&self) -> Style {
+        let initial = Style::initial();
+        Style {
+            display: initial.display,
+            opacity: initial.opacity,
+            stop_color: initial.stop_color,
+            stop_opacity: initial.stop_opacity,
+            ..self.clone()
+        }
+    }
+
+    /// Applies a declaration, ignoring it if the property isn't supported
+    /// or the value is invalid.
+    pub fn apply(&mut self, parent: &Style, property: &str, value: &str) {
+        let value = value.trim();
+        if value == "inherit" {
+            return self.inherit_property(parent, property);
+        }
+        let _ = self.apply_value(property, value);
+    }
+
+    fn inherit_property(&mut self, parent: &Style, property: &str) {
+        match property {
+            "display" => self.display = parent.display,
+            "opacity" => self.opacity = parent.opacity,
+            "stop-color" => self.stop_color = parent.stop_color,
+            "stop-opacity" => self.stop_opacity = parent.stop_opacity,
+            "color" => self.color = parent.color,
+            "fill" => self.fill = parent.fill.clone(),
+            "fill-opacity" => self.fill_opacity = parent.fill_opacity,
+            "stroke" => self.stroke = parent.stroke.clone(),
+            "stroke-width" => self.stroke_width = parent.stroke_width,
+            "stroke-opacity" => self.stroke_opacity = parent.stroke_opacity,
+            "stroke-linecap" => self.stroke_linecap = parent.stroke_linecap,
+            "stroke-linejoin" => self.stroke_linejoin = parent.stroke_linejoin,
+            "stroke-miterlimit" => self.stroke_miterlimit = parent.stroke_miterlimit,
+            "stroke-dasharray" => self.stroke_dasharray = parent.stroke_dasharray.clone(),
+            "visibility" => self.visible = parent.visible,
+            "font-family" => self.font_family = parent.font_family.clone(),
+            "font-size" => self.font_size = parent.font_size,
+            "font-weight" => self.font_bold = parent.font_bold,
+            "font-style" => self.font_italic = parent.font_italic,
+            "text-anchor" => self.text_anchor = parent.text_anchor,
+            _ => {},
+        }
+    }
+
+    fn apply_value(&mut self, property: &str, value: &str) -> Option<()> {
+        match property {
+            "color" => match parse_color(value)? {
+                CSSColor::RGBA(rgba) => self.color = rgba,
+                CSSColor::CurrentColor => {},
+            },
+            "fill" => self.fill = Paint::parse(value)?,
+            "fill-opacity" => self.fill_opacity = parse_opacity(value)?,
+            "stroke" => self.stroke = Paint::parse(value)?,
+            "stroke-width" => {
+                let width = Length::parse(value)?;
+                if width.value < 0. {
+                    return None;
+                }
+                self.stroke_width = width;
+            },
+            "stroke-opacity" => self.stroke_opacity = parse_opacity(value)?,
+            "stroke-linecap" => self.stroke_linecap = match value {
+                "butt" => LineCap::Butt,
+                "round" => LineCap::Round,
+                "square" => LineCap::Square,
+                _ => return None,
+            },
+            "stroke-linejoin" => self.stroke_linejoin = match value {
+                "miter" | "miter-clip" | "arcs" => LineJoin::Miter,
+                "round" => LineJoin::Round,
+                "bevel" => LineJoin::Bevel,
+                _ => return None,
+            },
+            "stroke-miterlimit" => {
+                let limit = value.parse::<f32>().ok()?;
+                if !(limit >= 1.) {
+                    return None;
+                }
+                self.stroke_miterlimit = limit;
+            },
+            "stroke-dasharray" => {
+                if value == "none" {
+                    self.stroke_dasharray = vec![];
+                } else {
+                    let dashes = split_list(value)
+                        .map(Length::parse)
+                        .collect::<Option<Vec<_>>>()?;
+                    if dashes.iter().any(|dash| dash.value < 0.) {
+                        return None;
+                    }
+                    self.stroke_dasharray = dashes;
+                }
+            },
+            "visibility" => self.visible = match value {
+                "visible" => true,
+                "hidden" | "collapse" => false,
+                _ => return None,
+            },
+            "display" => self.display = value != "none",
+            "opacity" => self.opacity = parse_opacity(value)?,
+            "stop-color" => match parse_color(value)? {
+                CSSColor::RGBA(rgba) => self.stop_color = rgba,
+                CSSColor::CurrentColor => self.stop_color = self.color,
+            },
+            "stop-opacity" => self.stop_opacity = parse_opacity(value)?,
+            "font-family" => self.font_family = value.to_owned(),
+            "font-size" => {
+                let size = Length::parse(value)?;
+                self.font_size = match size.unit {
+                    Unit::Em => size.value * self.font_size,
+                    Unit::Ex => size.value * self.font_size / 2.,
+                    Unit::Percent => size.value * self.font_size / 100.,
+                    _ => size.resolve((0., 0.), self.font_size, Axis::Other),
+                };
+            },
+            "font-weight" => self.font_bold = match value {
+                "normal" | "lighter" => false,
+                "bold" | "bolder" => true,
+                _ => value.parse::<u32>().ok()? >= 600,
+            },
+            "font-style" => self.font_italic = value != "normal",
+            "text-anchor" => self.text_anchor = match value {
+                "start" => TextAnchor::Start,
+                "middle" => TextAnchor::Middle,
+                "end" => TextAnchor::End,
+                _ => return None,
+            },
+            _ => {},
+        }
+        Some(())
+    }
+
+    /// The paint to use for a `fill` or `stroke` value with `currentColor`
+    /// resolved.
+    pub fn resolve_paint(&self, paint: &Paint) -> Paint {
+        match *paint {
+            Paint::CurrentColor => Paint::Color(self.color),
+            ref paint => paint.clone(),
+        }
+    }
+}
+
+/// The presentation attributes, which set the property of the same name.
+const PRESENTATION_ATTRIBUTES: &'static [&'static str] = &[
+    "color", "display", "fill", "fill-opacity", "font-family", "font-size", "font-style",
+    "font-weight", "opacity", "stop-color", "stop-opacity", "stroke", "stroke-dasharray",
+    "stroke-linecap", "stroke-linejoin", "stroke-miterlimit",
+    "stroke-opacity", "stroke-width", "text-anchor", "visibility",
+];
+
+/// Computes the style of an element from that of its parent.
+pub fn cascade(tree: &Tree, stylesheet: &Stylesheet, node: NodeId, parent: &Style) -> Style {
+    let mut style = parent.inherit();
+    for &(ref name, ref value) in tree.attributes(node) {
+        if PRESENTATION_ATTRIBUTES.contains(&&**name) {
+            style.apply(parent, name, value);
+        }
+    }
+    for rule in stylesheet.matching_rules(tree, node) {
+        for &(ref property, ref value) in &rule.declarations {
+            style.apply(parent, property, value);
+        }
+    }
+    if let Some(declarations) = tree.attribute(node, "style") {
+        for (property, value) in parse_declarations(declarations) {
+            style.apply(parent, &property, &value);
+        }
+    }
+    style
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Selector {
+    local_name: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+impl Selector {
+    /// Parses a compound selector made of a type or universal selector, ids
+    /// and classes. Anything else isn't supported.
+    fn parse(text: &str) -> Option<Selector> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let mut selector = Selector::default();
+        let mut rest = text;
+        if rest.starts_with('*') {
+            rest = &rest[1..];
+        } else if rest.starts_with(is_name_char) {
+            let end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+            selector.local_name = Some(rest[..end].to_owned());
+            rest = &rest[end..];
+        }
+        while !rest.is_empty() {
+            let prefix = rest.chars().next()?;
+            let end = rest[1..].find(|c| !is_name_char(c)).map_or(rest.len(), |end| end + 1);
+            let name = &rest[1..end];
+            if name.is_empty() {
+                return None;
+            }
+            match prefix {
+                '#' => selector.id = Some(name.to_owned()),
+                '.' => selector.classes.push(name.to_owned()),
+                _ => return None,
+            }
+            rest = &rest[end..];
+        }
+        Some(selector)
+    }
+
+    fn specificity(&self) -> (usize, usize, usize) {
+        (self.id.is_some() as usize, self.classes.len(), self.local_name.is_some() as usize)
+    }
+
+    fn matches(&self, tree: &Tree, node: NodeId) -> bool {
+        if let Some(ref local_name) = self.local_name {
+            if tree.name(node) != Some(local_name) {
+                return false;
+            }
+        }
+        if let Some(ref id) = self.id {
+            if tree.attribute(node, "id") != Some(id) {
+                return false;
+            }
+        }
+        let classes = tree.attribute(node, "class").unwrap_or("");
+        self.classes.iter().all(|class| classes.split_whitespace().any(|c| c == class))
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+struct Rule {
+    selector: Selector,
+    declarations: Vec<(String, String)>,
+}
+
+/// The rules of the `<style>` elements of a document.
+#[derive(Default)]
+pub struct Stylesheet {
+    rules: Vec<Rule>,
+}
+
+impl Stylesheet {
+    pub fn from_tree(tree: &Tree) -> Stylesheet {
+        let mut stylesheet = Stylesheet::default();
+        for node in 0..tree.len() {
+            if tree.name(node) != Some("style") {
+                continue;
+            }
+            let css = tree.attribute(node, "type").map_or(true, |kind| kind.is_empty() || kind == "text/css");
+            if css {
+                stylesheet.add_rules(&tree.text_content(node));
+            }
+        }
+        // Later rules win among those of the same specificity, which the
+        // sort keeps in order.
+        stylesheet.rules.sort_by_key(|rule| rule.selector.specificity());
+        stylesheet
+    }
+
+    fn add_rules(&mut self, css: &str) {
+        let css = strip_comments(css);
+        let mut rest = &*css;
+        while let Some(open) = rest.find('{') {
+            let prelude = rest[..open].trim();
+            let (block, after) = match matching_brace(&rest[open + 1..]) {
+                Some(close) => (&rest[open + 1..open + 1 + close], &rest[open + close + 2..]),
+                None => (&rest[open + 1..], ""),
+            };
+            rest = after;
+            // At-rules, such as media queries and font faces, are skipped.
+            if prelude.starts_with('@') {
+                continue;
+            }
+            let declarations = parse_declarations(block);
+            for selector in prelude.split(',').filter_map(Selector::parse) {
+                self.rules.push(Rule {
+                    selector: selector,
+                    declarations: declarations.clone(),
+                });
+            }
+        }
+    }
+
+    fn matching_rules<'a>(&'a self, tree: &'a Tree, node: NodeId) -> impl Iterator<Item = &'a Rule> + 'a {
+        self.rules.iter().filter(move |rule| rule.selector.matches(tree, node))
+    }
+}
+
+/// The offset of the brace closing a block, given the text after the one
+/// opening it.
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(index),
+            '}' => depth -= 1,
+            _ => {},
+        }
+    }
+    None
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut result = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        result.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + end + 4..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Splits a declaration block into properties and values. Importance is
+/// ignored.
+pub fn parse_declarations(block: &str) -> Vec<(String, String)> {
+    block.split(';').filter_map(|declaration| {
+        let colon = declaration.find(':')?;
+        let property = declaration[..colon].trim().to_ascii_lowercase();
+        let value = declaration[colon + 1..].trim();
+        let value = value.trim_right_matches("!important").trim();
+        if property.is_empty() || value.is_empty() {
+            return None;
+        }
+        Some((property, value.to_owned()))
+    }).collect()
+}
+
+pub fn parse_color(value: &str) -> Option<CSSColor> {
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    let color = CSSColor::parse(&mut parser).ok()?;
+    if !parser.is_exhausted() {
+        return None;
+    }
+    Some(color)
+}
+
+fn parse_opacity(value: &str) -> Option<f32> {
+    let opacity = match Length::parse(value)? {
+        Length { value, unit: Unit::None } => value,
+        Length { value, unit: Unit::Percent } => value / 100.,
+        _ => return None,
+    };
+    Some(opacity.max(0.).min(1.))
+}
+
+/// Splits a list separated by commas, whitespace or both.
+pub fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(|c: char| c == ',' || c.is_whitespace()).filter(|item| !item.is_empty())
+}
+
+/// Parses the numbers of a list such as `points` or `viewBox`.
+pub fn parse_number_list(value: &str) -> Option<Vec<f32>> {
+    let mut numbers = vec![];
+    let mut rest = value;
+    loop {
+        rest = rest.trim_left_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            return Some(numbers);
+        }
+        let (number, length) = parse_number_prefix(rest)?;
+        numbers.push(number);
+        rest = &rest[length..];
+    }
+}
+
+/// Parses the number at the start of a string, such as the coordinates of
+/// path data, which don't need separators between them as in `1.5.5-2`.
+pub fn parse_number_prefix(text: &str) -> Option<(f32, usize)> {
+    let bytes = text.as_bytes();
+    let mut end = 0;
+    if end < bytes.len() && (bytes[end] == b'+' || bytes[end] == b'-') {
+        end += 1;
+    }
+    let digits_start = end;
+    while end < bytes.len() && bytes[end].is_ascii_digit() {
+        end += 1;
+    }
+    if end < bytes.len() && bytes[end] == b'.' {
+        end += 1;
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end += 1;
+        }
+    }
+    if end == digits_start || &text[digits_start..end] == "." {
+        return None;
+    }
+    if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+        let mut exponent_end = end + 1;
+        if exponent_end < bytes.len() && (bytes[exponent_end] == b'+' || bytes[exponent_end] == b'-') {
+            exponent_end += 1;
+        }
+        let exponent_digits = exponent_end;
+        while exponent_end < bytes.len() && bytes[exponent_end].is_ascii_digit() {
+            exponent_end += 1;
+        }
+        if exponent_end > exponent_digits {
+            end = exponent_end;
+        }
+    }
+    let number = text[..end].parse::<f32>().ok().filter(|number| number.is_finite())?;
+    Some((number, end))
+}
+
+/// Parses the value of a `transform` attribute.
+/// https://www.w3.org/TR/css-transforms-1/#svg-syntax
+pub fn parse_transform(value: &str) -> Option<Transform2D<f32>> {
+    let mut transform = Transform2D::identity();
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let open = rest.find('(')?;
+        let close = rest.find(')')?;
+        if close < open {
+            return None;
+        }
+        let name = rest[..open].trim();
+        let arguments = parse_number_list(&rest[open + 1..close])?;
+        let next = match (name, &*arguments) {
+            ("matrix", &[a, b, c, d, e, f]) => Transform2D::row_major(a, b, c, d, e, f),
+            ("translate", &[x]) => Transform2D::create_translation(x, 0.),
+            ("translate", &[x, y]) => Transform2D::create_translation(x, y),
+            ("scale", &[x]) => Transform2D::create_scale(x, x),
+            ("scale", &[x, y]) => Transform2D::create_scale(x, y),
+            ("rotate", &[angle]) => rotation(angle),
+            ("rotate", &[angle, x, y]) => {
+                Transform2D::create_translation(-x, -y)
+                    .post_mul(&rotation(angle))
+                    .post_translate(Vector2D::new(x, y))
+            },
+            ("skewX", &[angle]) => Transform2D::row_major(1., 0., (angle * PI / 180.).tan(), 1., 0., 0.),
+            ("skewY", &[angle]) => Transform2D::row_major(1., (angle * PI / 180.).tan(), 0., 1., 0., 0.),
+            _ => return None,
+        };
+        // Each function applies in the coordinate system of the ones after
+        // it.
+        transform = next.post_mul(&transform);
+        rest = rest[close + 1..].trim_left_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    Some(transform)
+}
+
+fn rotation(degrees: f32) -> Transform2D<f32> {
+    let radians = degrees * PI / 180.;
+    let (sin, cos) = radians.sin_cos();
+    Transform2D::row_major(cos, sin, -sin, cos, 0., 0.)
+}
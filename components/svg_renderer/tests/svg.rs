@@ -0,0 +1,209 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate cssparser;
+extern crate euclid;
+extern crate svg_renderer;
+
+use cssparser::RGBA;
+use euclid::{Point2D, Size2D};
+use svg_renderer::{Environment, Font, Raster, Segment, SvgImage, TextOutliner, is_svg};
+
+fn parse(source: &str) -> SvgImage {
+    SvgImage::parse(source.as_bytes()).expect("document should parse")
+}
+
+/// The BGRA pixel at a point of a rasterization.
+fn pixel(raster: &Raster, x: u32, y: u32) -> [u8; 4] {
+    let offset = ((y * raster.width + x) * 4) as usize;
+    [raster.bytes[offset], raster.bytes[offset + 1], raster.bytes[offset + 2], raster.bytes[offset + 3]]
+}
+
+#[test]
+fn test_is_svg() {
+    assert!(is_svg(b"<svg xmlns='http://www.w3.org/2000/svg'/>"));
+    assert!(is_svg(b"\xEF\xBB\xBF<?xml version='1.0'?>\n<!-- logo -->\n<svg>"));
+    assert!(is_svg(b"<!DOCTYPE svg PUBLIC '-//W3C//DTD SVG 1.1//EN' [<!ENTITY a 'b'>]><svg>"));
+    assert!(is_svg(b"<svg:svg xmlns:svg='http://www.w3.org/2000/svg'>"));
+    assert!(!is_svg(b"<svgfoo>"));
+    assert!(!is_svg(b"<html><svg>"));
+    assert!(!is_svg(b"<?xml version='1.0'"));
+    assert!(!is_svg(&[0x89, 0x50, 0x4E, 0x47]));
+}
+
+#[test]
+fn test_parse_requires_svg_root() {
+    assert!(SvgImage::parse(b"<svg xmlns='http://www.w3.org/2000/svg'/>").is_some());
+    // Without the namespace, the root is not an SVG element.
+    assert!(SvgImage::parse(b"<svg/>").is_none());
+    assert!(SvgImage::parse(b"<svg xmlns='http://www.w3.org/2000/svg'>").is_none());
+    assert!(SvgImage::parse(b"<html xmlns='http://www.w3.org/1999/xhtml'/>").is_none());
+}
+
+#[test]
+fn test_foreign_content_is_dropped() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink'>\
+                         <foreignObject><div xmlns='http://www.w3.org/1999/xhtml'><p/></div></foreignObject>\
+                         <use xlink:href='#a'/>\
+                       </svg>");
+    let tree = image.tree();
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree.name(1), Some("foreignObject"));
+    assert!(tree.node(1).children.is_empty());
+    assert_eq!(tree.attribute(2, "href"), Some("#a"));
+}
+
+#[test]
+fn test_default_size() {
+    let size = |source: &str| parse(source).default_size();
+    assert_eq!(size("<svg xmlns='http://www.w3.org/2000/svg' width='20' height='1in'/>"),
+               Size2D::new(20., 96.));
+    assert_eq!(size("<svg xmlns='http://www.w3.org/2000/svg' width='40' viewBox='0 0 4 1'/>"),
+               Size2D::new(40., 10.));
+    assert_eq!(size("<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 1 1'/>"),
+               Size2D::new(150., 150.));
+    assert_eq!(size("<svg xmlns='http://www.w3.org/2000/svg' width='50%'/>"),
+               Size2D::new(300., 150.));
+}
+
+#[test]
+fn test_rasterize_at_scale() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'>\
+                         <rect width='5' height='10' fill='#00f'/>\
+                         <rect x='5' width='5' height='10' style='fill: lime; fill-opacity: 0.5'/>\
+                       </svg>");
+    let raster = image.rasterize(Size2D::new(10., 10.), 2.).unwrap();
+    assert_eq!((raster.width, raster.height), (20, 20));
+    assert_eq!(raster.bytes.len(), 20 * 20 * 4);
+    assert_eq!(pixel(&raster, 2, 10), [255, 0, 0, 255]);
+    let translucent = pixel(&raster, 18, 10);
+    assert_eq!(translucent[1], 255);
+    assert!(translucent[3] > 120 && translucent[3] < 136);
+}
+
+#[test]
+fn test_rasterize_uses_stylesheet_and_use() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' \
+                            width='20' height='10'>\
+                         <style>.red { fill: red } #hidden { display: none }</style>\
+                         <defs><rect id='square' width='10' height='10'/></defs>\
+                         <use xlink:href='#square' class='red'/>\
+                         <use href='#square' x='10' id='hidden'/>\
+                       </svg>");
+    let raster = image.rasterize(image.default_size(), 1.).unwrap();
+    assert_eq!(pixel(&raster, 5, 5), [0, 0, 255, 255]);
+    assert_eq!(pixel(&raster, 15, 5), [0, 0, 0, 0]);
+}
+
+#[test]
+fn test_use_cycles_terminate() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg'>\
+                         <g id='a'><use href='#b'/></g>\
+                         <g id='b'><use href='#a'/></g>\
+                         <use id='c' href='#c'/>\
+                       </svg>");
+    assert!(image.rasterize(Size2D::new(10., 10.), 1.).is_some());
+}
+
+#[test]
+fn test_rasterize_rejects_empty_viewport() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg'/>");
+    assert!(image.rasterize(Size2D::new(0., 10.), 1.).is_none());
+    assert!(image.rasterize(Size2D::new(10., 10.), 0.).is_none());
+}
+
+#[test]
+fn test_hit_test() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>\
+                         <circle cx='25' cy='25' r='20'/>\
+                         <rect x='50' width='50' height='50' fill='none' stroke='black' stroke-width='4'/>\
+                         <rect y='50' width='50' height='50' visibility='hidden'/>\
+                       </svg>");
+    let viewport = Size2D::new(200., 200.);
+    let hit = |x, y| image.hit_test(viewport, Point2D::new(x, y), &Environment::default());
+    assert_eq!(hit(50., 50.), Some(1));
+    // Only the stroke of the unfilled rectangle is painted.
+    assert_eq!(hit(101., 50.), Some(2));
+    assert_eq!(hit(150., 50.), None);
+    assert_eq!(hit(50., 150.), None);
+}
+
+#[test]
+fn test_path_data() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>\
+                         <path d='M10,10h20v20H10z'/>\
+                         <path d='M50 20m-10 0a10 10 0 1 0 20 0a10 10 0 1 0-20 0'/>\
+                         <path d='M70 10l10 0 0 10-10 0zM70 30 L 80 30 80 40 70 40 z'/>\
+                         <path d='M10 60 L 20 60 20 70 10 70 Z X 90 90'/>\
+                       </svg>");
+    let viewport = Size2D::new(100., 100.);
+    let hit = |x, y| image.hit_test(viewport, Point2D::new(x, y), &Environment::default());
+    assert_eq!(hit(20., 20.), Some(1));
+    assert_eq!(hit(35., 20.), None);
+    assert_eq!(hit(50., 25.), Some(2));
+    assert_eq!(hit(50., 32.), None);
+    assert_eq!(hit(75., 15.), Some(3));
+    assert_eq!(hit(75., 35.), Some(3));
+    // Path data is rendered up to its first error.
+    assert_eq!(hit(15., 65.), Some(4));
+}
+
+/// Lays text out in a font whose glyphs are squares half the font size wide.
+struct SquareOutliner;
+
+impl TextOutliner for SquareOutliner {
+    fn outline(&self, text: &str, font: &Font) -> (Vec<Segment>, f32) {
+        let width = font.size / 2.;
+        let mut segments = vec![];
+        for index in 0..text.chars().count() {
+            let left = index as f32 * width;
+            segments.extend_from_slice(&[
+                Segment::MoveTo(Point2D::new(left, -width)),
+                Segment::LineTo(Point2D::new(left + width, -width)),
+                Segment::LineTo(Point2D::new(left + width, 0.)),
+                Segment::LineTo(Point2D::new(left, 0.)),
+                Segment::Close,
+            ]);
+        }
+        (segments, text.chars().count() as f32 * width)
+    }
+}
+
+#[test]
+fn test_text() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>\
+                         <text x='10 90' y='20'>  ab\n c </text>\
+                         <text x='50' y='60' style='text-anchor: middle; font-size: 10px'>abcd</text>\
+                       </svg>");
+    let viewport = Size2D::new(100., 100.);
+    let environment = Environment {
+        font_size: 20.,
+        text_outliner: Some(&SquareOutliner),
+        ..Environment::default()
+    };
+    let hit = |x, y| image.hit_test(viewport, Point2D::new(x, y), &environment);
+    // Whitespace collapses to "ab c", four glyphs 10 pixels wide in the
+    // font size of the environment.
+    assert_eq!(hit(15., 15.), Some(1));
+    assert_eq!(hit(45., 15.), Some(1));
+    assert_eq!(hit(55., 15.), None);
+    assert_eq!(hit(15., 25.), None);
+    // The second element, after the text of the first, is centered on its
+    // position.
+    assert_eq!(hit(41., 58.), Some(3));
+    assert_eq!(hit(59., 58.), Some(3));
+    assert_eq!(hit(61., 58.), None);
+    // Without an outliner, text isn't painted.
+    assert_eq!(image.hit_test(viewport, Point2D::new(15., 15.), &Environment::default()), None);
+}
+
+#[test]
+fn test_environment_color() {
+    let image = parse("<svg xmlns='http://www.w3.org/2000/svg' width='10' height='10'>\
+                         <rect width='10' height='10' fill='currentColor'/>\
+                       </svg>");
+    let environment = Environment { color: RGBA::new(0, 128, 0, 255), ..Environment::default() };
+    let raster = image.paint(image.default_size(), 1., &environment).unwrap();
+    assert_eq!(pixel(&raster, 5, 5), [0, 128, 0, 255]);
+}
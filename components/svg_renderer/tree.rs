@@ -0,0 +1,227 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The element tree of an SVG document.
+//!
+//! Only elements in the SVG namespace are kept; anything else, such as the
+//! XHTML content of a `<foreignObject>`, is dropped along with its subtree.
+//! Attribute names lose their namespace, so `xlink:href` becomes `href`.
+
+use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
+use xml::reader::{ParserConfig, XmlEvent};
+
+pub const SVG_NAMESPACE: &'static str = "http://www.w3.org/2000/svg";
+const XLINK_NAMESPACE: &'static str = "http://www.w3.org/1999/xlink";
+
+/// The index of a node in its `Tree`.
+pub type NodeId = usize;
+
+pub enum NodeKind {
+    Element {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    Text(String),
+}
+
+pub struct Node {
+    pub kind: NodeKind,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+}
+
+/// An SVG document, whose root element is the first node.
+pub struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    pub fn new() -> Tree {
+        Tree { nodes: vec![] }
+    }
+
+    /// Parses a standalone SVG document. External entities and DTDs are
+    /// never fetched.
+    pub fn parse(bytes: &[u8]) -> Option<Tree> {
+        let reader = ParserConfig::new()
+            .cdata_to_characters(true)
+            .ignore_comments(true)
+            .coalesce_characters(true)
+            .create_reader(bytes);
+
+        let mut tree = Tree::new();
+        let mut open_elements: Vec<NodeId> = vec![];
+        // The depth of the element being skipped, with its subtree.
+        let mut skipped_depth = 0;
+        for event in reader {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    debug!("SVG document is not well-formed: {}", e);
+                    return None;
+                },
+            };
+            match event {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    if skipped_depth > 0 || !is_svg_name(&name) {
+                        if open_elements.is_empty() {
+                            debug!("Root element {} is not an SVG element", name);
+                            return None;
+                        }
+                        skipped_depth += 1;
+                        continue;
+                    }
+                    let parent = open_elements.last().cloned();
+                    let id = tree.append_element(parent, &name.local_name, convert_attributes(attributes));
+                    open_elements.push(id);
+                },
+                XmlEvent::EndElement { .. } => {
+                    if skipped_depth > 0 {
+                        skipped_depth -= 1;
+                    } else {
+                        open_elements.pop();
+                    }
+                },
+                XmlEvent::Characters(text) | XmlEvent::Whitespace(text) => {
+                    if skipped_depth == 0 {
+                        if let Some(&parent) = open_elements.last() {
+                            tree.append_text(parent, text);
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        if tree.root().is_none() {
+            return None;
+        }
+        Some(tree)
+    }
+
+    /// The outermost `<svg>` element.
+    pub fn root(&self) -> Option<NodeId> {
+        match self.nodes.first() {
+            Some(&Node { kind: NodeKind::Element { ref name, .. }, .. }) if name == "svg" => Some(0),
+            _ => None,
+        }
+    }
+
+    pub fn append_element(&mut self,
+                          parent: Option<NodeId>,
+                          name: &str,
+                          attributes: Vec<(String, String)>)
+                          -> NodeId {
+        let kind = NodeKind::Element {
+            name: name.to_owned(),
+            attributes: attributes,
+        };
+        self.append(parent, kind)
+    }
+
+    pub fn append_text(&mut self, parent: NodeId, text: String) -> NodeId {
+        self.append(Some(parent), NodeKind::Text(text))
+    }
+
+    fn append(&mut self, parent: Option<NodeId>, kind: NodeKind) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            kind: kind,
+            parent: parent,
+            children: vec![],
+        });
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(id);
+        }
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The local name of an element.
+    pub fn name(&self, id: NodeId) -> Option<&str> {
+        match self.nodes[id].kind {
+            NodeKind::Element { ref name, .. } => Some(name),
+            NodeKind::Text(_) => None,
+        }
+    }
+
+    pub fn attribute(&self, id: NodeId, attribute: &str) -> Option<&str> {
+        match self.nodes[id].kind {
+            NodeKind::Element { ref attributes, .. } => {
+                attributes.iter().find(|&&(ref name, _)| name == attribute).map(|&(_, ref value)| &**value)
+            },
+            NodeKind::Text(_) => None,
+        }
+    }
+
+    pub fn attributes(&self, id: NodeId) -> &[(String, String)] {
+        match self.nodes[id].kind {
+            NodeKind::Element { ref attributes, .. } => attributes,
+            NodeKind::Text(_) => &[],
+        }
+    }
+
+    /// The first element in tree order with the given id.
+    pub fn element_by_id(&self, element_id: &str) -> Option<NodeId> {
+        (0..self.nodes.len()).find(|&id| self.attribute(id, "id") == Some(element_id))
+    }
+
+    /// The element that a same-document reference such as `#shape` or
+    /// `url(#gradient)` points to. Anything else is an external reference,
+    /// which is never followed.
+    pub fn resolve_reference(&self, reference: &str) -> Option<NodeId> {
+        let reference = reference.trim();
+        let reference = if reference.starts_with("url(") && reference.ends_with(')') {
+            reference[4..reference.len() - 1].trim().trim_matches(|c| c == '"' || c == '\'')
+        } else {
+            reference
+        };
+        if !reference.starts_with('#') {
+            return None;
+        }
+        self.element_by_id(&reference[1..])
+    }
+
+    /// The concatenated text of the descendants of a node.
+    pub fn text_content(&self, id: NodeId) -> String {
+        let mut text = String::new();
+        self.collect_text(id, &mut text);
+        text
+    }
+
+    fn collect_text(&self, id: NodeId, text: &mut String) {
+        match self.nodes[id].kind {
+            NodeKind::Text(ref data) => text.push_str(data),
+            NodeKind::Element { .. } => {
+                for &child in &self.nodes[id].children {
+                    self.collect_text(child, text);
+                }
+            },
+        }
+    }
+}
+
+fn is_svg_name(name: &OwnedName) -> bool {
+    name.namespace.as_ref().map_or(false, |namespace| namespace == SVG_NAMESPACE)
+}
+
+fn convert_attributes(attributes: Vec<OwnedAttribute>) -> Vec<(String, String)> {
+    attributes.into_iter().filter_map(|attribute| {
+        match attribute.name.namespace {
+            None => Some((attribute.name.local_name, attribute.value)),
+            Some(ref namespace) if namespace == XLINK_NAMESPACE => {
+                Some((attribute.name.local_name, attribute.value))
+            },
+            Some(_) => None,
+        }
+    }).collect()
+}
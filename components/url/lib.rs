@@ -88,6 +88,27 @@ impl ServoUrl {
         scheme == "https" || scheme == "wss"
     }
 
+    /// <https://w3c.github.io/webappsec-secure-contexts/#is-url-trustworthy>
+    pub fn is_potentially_trustworthy(&self) -> bool {
+        // Step 1.
+        if self.as_str() == "about:blank" || self.as_str() == "about:srcdoc" {
+            return true;
+        }
+        // Step 2.
+        if self.scheme() == "data" {
+            return true;
+        }
+        // Step 3, from https://w3c.github.io/webappsec-secure-contexts/#is-origin-trustworthy
+        match self.host() {
+            _ if self.is_secure_scheme() || self.scheme() == "file" => true,
+            _ if !self.origin().is_tuple() => false,
+            Some(Host::Ipv4(address)) => address.is_loopback(),
+            Some(Host::Ipv6(address)) => address.is_loopback(),
+            Some(Host::Domain(domain)) => domain == "localhost" || domain.ends_with(".localhost"),
+            None => false,
+        }
+    }
+
     pub fn is_chrome(&self) -> bool {
         self.scheme() == "chrome"
     }
@@ -515,14 +515,14 @@ impl Handler {
     }
 
     fn handle_find_element(&self, parameters: &LocatorParameters) -> WebDriverResult<WebDriverResponse> {
-        if parameters.using != LocatorStrategy::CSSSelector {
-            return Err(WebDriverError::new(ErrorStatus::UnsupportedOperation,
-                                           "Unsupported locator strategy"))
-        }
-
         let (sender, receiver) = ipc::channel().unwrap();
 
-        let cmd = WebDriverScriptCommand::FindElementCSS(parameters.value.clone(), sender);
+        let cmd = match parameters.using {
+            LocatorStrategy::CSSSelector => WebDriverScriptCommand::FindElementCSS(parameters.value.clone(), sender),
+            LocatorStrategy::XPath => WebDriverScriptCommand::FindElementXPath(parameters.value.clone(), sender),
+            _ => return Err(WebDriverError::new(ErrorStatus::UnsupportedOperation,
+                                                "Unsupported locator strategy")),
+        };
         self.browsing_context_script_command(cmd)?;
 
         match receiver.recv().unwrap() {
@@ -574,13 +574,13 @@ impl Handler {
 
 
     fn handle_find_elements(&self, parameters: &LocatorParameters) -> WebDriverResult<WebDriverResponse> {
-        if parameters.using != LocatorStrategy::CSSSelector {
-            return Err(WebDriverError::new(ErrorStatus::UnsupportedOperation,
-                                           "Unsupported locator strategy"))
-        }
-
         let (sender, receiver) = ipc::channel().unwrap();
-        let cmd = WebDriverScriptCommand::FindElementsCSS(parameters.value.clone(), sender);
+        let cmd = match parameters.using {
+            LocatorStrategy::CSSSelector => WebDriverScriptCommand::FindElementsCSS(parameters.value.clone(), sender),
+            LocatorStrategy::XPath => WebDriverScriptCommand::FindElementsXPath(parameters.value.clone(), sender),
+            _ => return Err(WebDriverError::new(ErrorStatus::UnsupportedOperation,
+                                                "Unsupported locator strategy")),
+        };
         self.browsing_context_script_command(cmd)?;
         match receiver.recv().unwrap() {
             Ok(value) => {
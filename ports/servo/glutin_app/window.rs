@@ -551,6 +551,12 @@ impl Window {
                 // window.set_inner_size() takes DeviceIndependentPixel.
                 let (width, height) = size.into();
                 let new_size = TypedSize2D::new(width, height);
+                // Minimized windows are resized to nothing on some platforms.
+                let was_minimized = self.inner_size.get().area() == 0;
+                let is_minimized = new_size.area() == 0;
+                if was_minimized != is_minimized {
+                    self.event_queue.borrow_mut().push(WindowEvent::Visible(!is_minimized));
+                }
                 if self.inner_size.get() != new_size {
                     self.inner_size.set(new_size);
                     self.event_queue.borrow_mut().push(WindowEvent::Resize);
@@ -558,6 +564,7 @@ impl Window {
             }
             Event::Suspended(suspended) => {
                 self.suspended.set(suspended);
+                self.event_queue.borrow_mut().push(WindowEvent::Visible(!suspended));
                 if !suspended {
                     self.event_queue.borrow_mut().push(WindowEvent::Idle);
                 }
@@ -25,7 +25,7 @@ from mach.decorators import (
 )
 from mach.registrar import Registrar
 
-from servo.command_base import CommandBase, cd, call, check_call, BIN_SUFFIX
+from servo.command_base import CommandBase, cd, call, check_call, BIN_SUFFIX, is_windows
 from servo.util import host_triple
 
 
@@ -253,6 +253,10 @@ class MachCommands(CommandBase):
         if debug_mozjs:
             features += ["debugmozjs"]
 
+        # libwebp is installed by the Linux and macOS setup instructions only.
+        if not android and not is_windows():
+            features += ["webp"]
+
         if features:
             opts += ["--features", "%s" % ' '.join(features)]
 
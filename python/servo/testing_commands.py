@@ -253,6 +253,7 @@ class MachCommands(CommandBase):
             "selectors",
             "servo_config",
             "servo_remutex",
+            "svg_renderer",
         ]
         if not packages:
             packages = set(os.listdir(path.join(self.context.topdir, "tests", "unit"))) - set(['.DS_Store'])
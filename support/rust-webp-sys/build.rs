@@ -0,0 +1,23 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate cc;
+extern crate pkg_config;
+
+fn main() {
+    let mut build = cc::Build::new();
+    build.file("src/webp_decoder.c");
+    // The animation decoder was added to libwebpdemux in 0.5.0.
+    let library = pkg_config::Config::new()
+        .atleast_version("0.5.0")
+        .probe("libwebpdemux")
+        .unwrap_or_else(|error| {
+            panic!("WebP support needs libwebpdemux 0.5.0 or later (install libwebp-dev, \
+                    or build Servo without the `webp` feature): {}", error)
+        });
+    for path in library.include_paths {
+        build.include(path);
+    }
+    build.compile("libwebp_decoder.a");
+}
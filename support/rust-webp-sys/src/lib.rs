@@ -0,0 +1,37 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Bindings to the animation decoder of libwebpdemux.
+//!
+//! The decoder is driven through a small C shim compiled against the
+//! installed headers, which keeps the option structs and ABI version out of
+//! Rust.
+
+#![crate_name = "webp_sys"]
+#![crate_type = "rlib"]
+
+use std::os::raw::c_int;
+
+pub enum WebPAnimDecoder {}
+
+extern "C" {
+    /// Creates a decoder producing non-premultiplied BGRA canvases, and
+    /// stores the canvas size and frame count. Returns null on failure.
+    ///
+    /// `bytes` must outlive the decoder.
+    pub fn servo_webp_decoder_new(bytes: *const u8,
+                                  size: usize,
+                                  width: *mut u32,
+                                  height: *mut u32,
+                                  frame_count: *mut u32)
+                                  -> *mut WebPAnimDecoder;
+    /// Decodes the next frame. Returns 1 and sets `pixels` and the end
+    /// `timestamp` of the frame in milliseconds, 0 when there are no more
+    /// frames, or -1 on error. The pixels are owned by the decoder.
+    pub fn servo_webp_decoder_next_frame(decoder: *mut WebPAnimDecoder,
+                                         pixels: *mut *mut u8,
+                                         timestamp: *mut c_int)
+                                         -> c_int;
+    pub fn servo_webp_decoder_delete(decoder: *mut WebPAnimDecoder);
+}
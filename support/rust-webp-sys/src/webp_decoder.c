@@ -0,0 +1,56 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include <webp/demux.h>
+
+/* The option and info structs, and the ABI version passed along with them,
+ * come from the installed headers, so they always match the library. */
+WebPAnimDecoder*
+servo_webp_decoder_new(const uint8_t* bytes, size_t size,
+                       uint32_t* width, uint32_t* height, uint32_t* frame_count)
+{
+    WebPData data;
+    WebPAnimDecoderOptions options;
+    WebPAnimInfo info;
+    WebPAnimDecoder* decoder;
+
+    if (!WebPAnimDecoderOptionsInit(&options)) {
+        return NULL;
+    }
+    options.color_mode = MODE_BGRA;
+    options.use_threads = 0;
+
+    data.bytes = bytes;
+    data.size = size;
+    decoder = WebPAnimDecoderNew(&data, &options);
+    if (decoder == NULL) {
+        return NULL;
+    }
+    if (!WebPAnimDecoderGetInfo(decoder, &info)) {
+        WebPAnimDecoderDelete(decoder);
+        return NULL;
+    }
+    *width = info.canvas_width;
+    *height = info.canvas_height;
+    *frame_count = info.frame_count;
+    return decoder;
+}
+
+int
+servo_webp_decoder_next_frame(WebPAnimDecoder* decoder, uint8_t** pixels, int* timestamp)
+{
+    if (!WebPAnimDecoderHasMoreFrames(decoder)) {
+        return 0;
+    }
+    return WebPAnimDecoderGetNext(decoder, pixels, timestamp) ? 1 : -1;
+}
+
+void
+servo_webp_decoder_delete(WebPAnimDecoder* decoder)
+{
+    WebPAnimDecoderDelete(decoder);
+}
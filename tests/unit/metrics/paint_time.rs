@@ -2,15 +2,15 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use gfx_traits::Epoch;
+use gfx_traits::{ContentRect, Epoch};
 use ipc_channel::ipc;
-use layout::display_list::items::{BaseDisplayItem, DisplayItem, DisplayList, ImageDisplayItem};
+use layout::display_list::items::{BaseDisplayItem, DisplayItem, DisplayList, ImageDisplayItem, OpaqueNode};
 use metrics::{PaintTimeMetrics, ProfilerMetadataFactory, ProgressiveWebMetric};
 use msg::constellation_msg::TEST_PIPELINE_ID;
-use profile_traits::time::{ProfilerChan, TimerMetadata};
+use profile_traits::time::{ProfilerCategory, ProfilerChan, ProfilerMsg, TimerMetadata};
 use servo_url::ServoUrl;
 use time;
-use webrender_api::{ImageKey, ImageRendering, LayoutSize};
+use webrender_api::{ImageKey, ImageRendering, LayoutPoint, LayoutRect, LayoutSize};
 
 struct DummyProfilerMetadataFactory {}
 impl ProfilerMetadataFactory for DummyProfilerMetadataFactory {
@@ -67,6 +67,7 @@ fn test_common(display_list: &DisplayList, epoch: Epoch) -> PaintTimeMetrics {
         &dummy_profiler_metadata_factory,
         epoch,
         &*display_list,
+        &ContentRect::new(0., 0., 800., 600.),
     );
 
     // Should not set any metric until navigation start is set.
@@ -140,3 +141,167 @@ fn test_first_contentful_paint_setter() {
         "first paint is set"
     );
 }
+
+fn image_display_list(node: usize, x: f32, y: f32, width: f32, height: f32) -> DisplayList {
+    let mut base = BaseDisplayItem::empty();
+    base.bounds = LayoutRect::new(LayoutPoint::new(x, y), LayoutSize::new(width, height));
+    base.metadata.node = OpaqueNode(node);
+    let image = DisplayItem::Image(Box::new(ImageDisplayItem {
+        base: base,
+        id: ImageKey::DUMMY,
+        stretch_size: LayoutSize::new(width, height),
+        tile_spacing: LayoutSize::zero(),
+        image_rendering: ImageRendering::Auto,
+    }));
+    DisplayList {
+        list: vec![image],
+        clip_scroll_nodes: Vec::new(),
+    }
+}
+
+#[test]
+fn test_largest_contentful_paint_setter() {
+    let viewport = ContentRect::new(0., 0., 800., 600.);
+    let dummy_profiler_metadata_factory = DummyProfilerMetadataFactory {};
+    let epoch = Epoch(0);
+    let paint_time_metrics = test_common(&image_display_list(1, 0., 0., 100., 100.), epoch);
+    paint_time_metrics.maybe_set_metric(epoch, time::precise_time_ns());
+    assert_eq!(
+        paint_time_metrics.get_largest_contentful_paint(),
+        None,
+        "content painted before navigation start is not a candidate"
+    );
+
+    let epoch = Epoch(1);
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        epoch,
+        &image_display_list(1, 0., 0., 100., 100.),
+        &viewport,
+    );
+    paint_time_metrics.maybe_set_metric(epoch, time::precise_time_ns());
+    let first_candidate = paint_time_metrics.get_largest_contentful_paint();
+    assert!(first_candidate.is_some(), "largest contentful paint is set");
+
+    // Smaller content is not a new candidate.
+    let epoch = Epoch(2);
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        epoch,
+        &image_display_list(1, 0., 0., 50., 50.),
+        &viewport,
+    );
+    paint_time_metrics.maybe_set_metric(epoch, time::precise_time_ns());
+    assert_eq!(
+        paint_time_metrics.get_largest_contentful_paint(),
+        first_candidate,
+        "largest contentful paint is unchanged"
+    );
+
+    // Larger content is, but only its visible area counts.
+    let epoch = Epoch(3);
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        epoch,
+        &image_display_list(1, 0., 500., 1000., 1000.),
+        &viewport,
+    );
+    let now = time::precise_time_ns();
+    paint_time_metrics.maybe_set_metric(epoch, now);
+    assert_eq!(
+        paint_time_metrics.get_largest_contentful_paint(),
+        Some(now - paint_time_metrics.get_navigation_start().unwrap()),
+        "largest contentful paint is updated"
+    );
+}
+
+#[test]
+fn test_cumulative_layout_shift() {
+    let viewport = ContentRect::new(0., 0., 800., 600.);
+    let dummy_profiler_metadata_factory = DummyProfilerMetadataFactory {};
+    let paint_time_metrics = test_common(&image_display_list(1, 0., 0., 400., 300.), Epoch(0));
+
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        Epoch(1),
+        &image_display_list(1, 0., 0., 400., 300.),
+        &viewport,
+    );
+    assert_eq!(
+        paint_time_metrics.get_cumulative_layout_shift(),
+        0.,
+        "first layout has no shift"
+    );
+
+    // Moving the image down by 100px makes it cover 400x400px over both frames,
+    // a third of the viewport, and moves it an eighth of the viewport width.
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        Epoch(2),
+        &image_display_list(1, 0., 100., 400., 300.),
+        &viewport,
+    );
+    let expected = (1. / 3.) * (1. / 8.);
+    assert!(
+        (paint_time_metrics.get_cumulative_layout_shift() - expected).abs() < 1e-6,
+        "layout shift is reported"
+    );
+
+    // Unmoved and new nodes don't shift.
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        Epoch(3),
+        &image_display_list(1, 0., 100., 400., 300.),
+        &viewport,
+    );
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        Epoch(4),
+        &image_display_list(2, 0., 0., 400., 300.),
+        &viewport,
+    );
+    assert!(
+        (paint_time_metrics.get_cumulative_layout_shift() - expected).abs() < 1e-6,
+        "layout shift is unchanged"
+    );
+}
+
+#[test]
+fn test_cumulative_layout_shift_profiler_report() {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let profiler_chan = ProfilerChan(sender);
+    let (layout_sender, _) = ipc::channel().unwrap();
+    let (script_sender, _) = ipc::channel().unwrap();
+    let mut paint_time_metrics = PaintTimeMetrics::new(
+        TEST_PIPELINE_ID,
+        profiler_chan,
+        layout_sender,
+        script_sender,
+        ServoUrl::parse("about:blank").unwrap(),
+    );
+    paint_time_metrics.set_navigation_start(time::precise_time_ns());
+    let viewport = ContentRect::new(0., 0., 800., 600.);
+    let dummy_profiler_metadata_factory = DummyProfilerMetadataFactory {};
+
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        Epoch(0),
+        &image_display_list(1, 0., 0., 400., 300.),
+        &viewport,
+    );
+    paint_time_metrics.maybe_observe_paint_time(
+        &dummy_profiler_metadata_factory,
+        Epoch(1),
+        &image_display_list(1, 0., 100., 400., 300.),
+        &viewport,
+    );
+
+    // The score is reported as a duration of that many milliseconds.
+    let expected = ((1. / 3.) * (1. / 8.) * 1000000.) as u64;
+    match receiver.try_recv() {
+        Ok(ProfilerMsg::Time((ProfilerCategory::CumulativeLayoutShift, None), (0, duration), _)) => {
+            assert!((duration as i64 - expected as i64).abs() <= 1, "cumulative layout shift is reported");
+        },
+        _ => panic!("cumulative layout shift is not reported to the time profiler"),
+    }
+}
@@ -0,0 +1,158 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use script::test::glsl::{ShaderError, ShaderKind, TranslationOptions, translate};
+
+fn options(kind: ShaderKind, glsl_version: u32) -> TranslationOptions {
+    TranslationOptions { kind, glsl_version, standard_derivatives: false }
+}
+
+fn fragment(source: &str) -> Result<String, ShaderError> {
+    translate(source, &options(ShaderKind::Fragment, 120))
+}
+
+#[test]
+fn test_translation_strips_precision_for_desktop_glsl() {
+    let source = "precision mediump float;\nvoid main() { lowp vec4 c = vec4(1.0); gl_FragColor = c; }\n";
+    let translated = fragment(source).unwrap();
+    assert!(translated.starts_with("#version 120\n"));
+    assert!(!translated.contains("precision"));
+    assert!(!translated.contains("lowp"));
+    // Line numbers are kept.
+    assert_eq!(translated.lines().count(), source.lines().count() + 1);
+}
+
+#[test]
+fn test_translation_keeps_precision_for_glsl_es() {
+    let source = "precision mediump float;\nvoid main() { gl_FragColor = vec4(1.0); }\n";
+    let translated = translate(source, &options(ShaderKind::Fragment, 100)).unwrap();
+    assert!(translated.starts_with("#version 100\n"));
+    assert!(translated.contains("precision mediump float;"));
+}
+
+#[test]
+fn test_version_directive() {
+    assert!(fragment("#version 100\nvoid main() {}").is_ok());
+    assert_eq!(fragment("#version 300 es\nvoid main() {}").unwrap_err().line, 1);
+    assert_eq!(fragment("void f() {}\n#version 100\nvoid main() {}").unwrap_err().line, 2);
+}
+
+#[test]
+fn test_missing_main() {
+    assert!(fragment("void notmain() {}").is_err());
+    assert!(fragment("// void main() {}").is_err());
+}
+
+#[test]
+fn test_reserved_identifiers() {
+    assert!(fragment("uniform float webgl_foo;\nvoid main() {}").is_err());
+    assert!(fragment("uniform float _webgl_foo;\nvoid main() {}").is_err());
+    assert!(fragment("uniform float mywebgl_foo;\nvoid main() {}").is_ok());
+}
+
+#[test]
+fn test_identifier_length() {
+    let long = "a".repeat(257);
+    assert!(fragment(&format!("float {};\nvoid main() {{}}", long)).is_err());
+    let max = "a".repeat(256);
+    assert!(fragment(&format!("float {};\nvoid main() {{}}", max)).is_ok());
+}
+
+#[test]
+fn test_invalid_characters() {
+    assert_eq!(fragment("void main() {}\n$").unwrap_err().line, 2);
+    assert!(fragment("void main() { float \u{e9} = 1.0; }").is_err());
+    // Comments may contain anything.
+    assert!(fragment("// caf\u{e9} $\n/* \"quoted\" */\nvoid main() {}").is_ok());
+}
+
+#[test]
+fn test_standard_derivatives() {
+    let source = "#extension GL_OES_standard_derivatives : enable\n\
+                  void main() { gl_FragColor = vec4(dFdx(1.0)); }";
+    assert!(fragment(source).is_err());
+
+    let enabled = TranslationOptions {
+        kind: ShaderKind::Fragment,
+        glsl_version: 120,
+        standard_derivatives: true,
+    };
+    let translated = translate(source, &enabled).unwrap();
+    assert!(!translated.contains("#extension"));
+
+    let without_directive = "void main() { gl_FragColor = vec4(fwidth(1.0)); }";
+    assert!(translate(without_directive, &enabled).is_err());
+
+    let vertex = TranslationOptions { kind: ShaderKind::Vertex, ..enabled };
+    assert!(translate(source, &vertex).is_err());
+}
+
+#[test]
+fn test_unknown_extension() {
+    assert!(fragment("#extension GL_EXT_foo : require\nvoid main() {}").is_err());
+    assert!(fragment("#extension GL_EXT_foo : warn\nvoid main() {}").is_ok());
+    assert!(fragment("#extension all : enable\nvoid main() {}").is_err());
+}
+
+#[test]
+fn test_info_log() {
+    let error = fragment("void f() {}").unwrap_err();
+    assert_eq!(error.to_info_log(), format!("ERROR: 0:{}: {}\n", error.line, error.message));
+}
+
+#[test]
+fn test_grammar_errors() {
+    assert_eq!(fragment("void main() {\n    float x = 1.0 +;\n}").unwrap_err().line, 2);
+    assert!(fragment("void main() { float x = 1.0f; }").is_err());
+    assert!(fragment("void main() { int x = 1 % 2; }").is_err());
+    assert!(fragment("void main() { switch (1) {} }").is_err());
+    assert!(fragment("void main() { vec4 v = vec4(); }").is_err());
+    assert!(fragment("void main() { void f() {} }").is_err());
+    assert!(fragment("attribute vec4 position;\nvoid main() {}").is_err());
+    assert!(fragment("void main() {}\nvoid main() {} }").is_err());
+}
+
+#[test]
+fn test_valid_shaders() {
+    let source = "precision mediump float;\n\
+                  struct Light { vec3 position; lowp vec4 color[2]; };\n\
+                  uniform Light lights[4];\n\
+                  varying vec2 uv;\n\
+                  float attenuation(in Light light, const float d) { return 1.0 / (1.0 + d * d); }\n\
+                  void main() {\n\
+                      vec4 color = vec4(0.0);\n\
+                      for (int i = 0; i < 4; i++) {\n\
+                          if (lights[i].position.z > 0.5 ^^ uv.x < .5) continue;\n\
+                          color += lights[i].color[0] * attenuation(lights[i], length(lights[i].position));\n\
+                      }\n\
+                      gl_FragColor = color.a > 0.0 ? color : vec4(1e-3, 0x10, 010, -1.);\n\
+                  }";
+    assert!(fragment(source).is_ok());
+}
+
+#[test]
+fn test_macros() {
+    let source = "#define SCALE(x) ((x) * 2.0)\n\
+                  #ifdef GL_ES\n\
+                  precision highp float;\n\
+                  #endif\n\
+                  void main() { gl_FragColor = vec4(SCALE(0.25)); }";
+    let translated = fragment(source).unwrap();
+    assert!(!translated.contains("SCALE"));
+    assert!(translated.contains("((0.25) * 2.0)"));
+
+    let source = "#if UNDEFINED_MACRO\n#endif\nvoid main() {}";
+    assert!(fragment(source).is_err());
+    let source = "#if defined(GL_ES) && __VERSION__ == 100\nvoid main() {}\n#else\n#error unreachable\n#endif";
+    assert!(fragment(source).is_ok());
+    assert!(fragment("#ifdef GL_ES\nvoid main() {}").is_err());
+}
+
+#[test]
+fn test_nesting_depth() {
+    let nested = format!("void main() {{ float x = {}1.0{}; }}", "(".repeat(1000), ")".repeat(1000));
+    assert!(fragment(&nested).is_err());
+    let nested = format!("void main() {} {}", "{".repeat(1000), "}".repeat(1000));
+    assert!(fragment(&nested).is_err());
+}
@@ -0,0 +1,52 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use app_units::Au;
+use euclid::{Point2D, Rect, Size2D};
+use script::test::intersectionobserver::RootMargin;
+
+fn serialize(value: &str) -> String {
+    RootMargin::parse(value).ok().expect("root margin should parse").serialize()
+}
+
+fn rect(x: i32, y: i32, width: i32, height: i32) -> Rect<Au> {
+    Rect::new(Point2D::new(Au::from_px(x), Au::from_px(y)),
+              Size2D::new(Au::from_px(width), Au::from_px(height)))
+}
+
+#[test]
+fn test_parse_root_margin() {
+    assert_eq!(serialize(""), "0px 0px 0px 0px");
+    assert_eq!(serialize("  "), "0px 0px 0px 0px");
+    assert_eq!(serialize("0"), "0px 0px 0px 0px");
+    assert_eq!(serialize("10px"), "10px 10px 10px 10px");
+    assert_eq!(serialize("10px 5%"), "10px 5% 10px 5%");
+    assert_eq!(serialize("1px 2px 3px"), "1px 2px 3px 2px");
+    assert_eq!(serialize(" 1px\t2px 3px  4px "), "1px 2px 3px 4px");
+    assert_eq!(serialize("-5px"), "-5px -5px -5px -5px");
+}
+
+#[test]
+fn test_parse_invalid_root_margin() {
+    assert!(RootMargin::parse("1em").is_err());
+    assert!(RootMargin::parse("10").is_err());
+    assert!(RootMargin::parse("px").is_err());
+    assert!(RootMargin::parse("1px 2px 3px 4px 5px").is_err());
+    assert!(RootMargin::parse("calc(1px)").is_err());
+}
+
+#[test]
+fn test_apply_root_margin() {
+    // A fixed-size 800x600 viewport, as in headless windows.
+    let viewport = rect(0, 0, 800, 600);
+    let apply = |value: &str| RootMargin::parse(value).ok().unwrap().apply(viewport);
+    assert_eq!(apply(""), viewport);
+    assert_eq!(apply("10px 20px"), rect(-20, -10, 840, 620));
+    // Percentages resolve against the width horizontally and the height
+    // vertically.
+    assert_eq!(apply("10% 50%"), rect(-400, -60, 1600, 720));
+    // Negative margins shrink the rectangle, down to nothing.
+    assert_eq!(apply("-100px"), rect(100, 100, 600, 400));
+    assert_eq!(apply("-50%").size, Size2D::new(Au(0), Au(0)));
+}
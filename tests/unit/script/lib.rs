@@ -2,17 +2,24 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+#[cfg(test)] extern crate app_units;
 #[cfg(test)] extern crate euclid;
 #[cfg(test)] extern crate msg;
+#[cfg(test)] extern crate openssl;
 #[cfg(test)] extern crate script;
 #[cfg(test)] extern crate servo_url;
 
 #[cfg(test)] mod origin;
 #[cfg(all(test, target_pointer_width = "64"))] mod size_of;
 #[cfg(test)] mod textinput;
+#[cfg(test)] mod glsl;
 #[cfg(test)] mod headers;
 #[cfg(test)] mod htmlareaelement;
 #[cfg(test)] mod htmlimageelement;
+#[cfg(test)] mod intersectionobserver;
+#[cfg(test)] mod path2d;
+#[cfg(test)] mod webcrypto;
+#[cfg(test)] mod xpath;
 
 /**
 ```compile_fail,E0277
@@ -0,0 +1,109 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use euclid::Point2D;
+use script::test::path2d::{PathData, PathSegment};
+
+fn parse(data: &str) -> Vec<PathSegment> {
+    PathData::parse_svg_path_data(data).segments().to_vec()
+}
+
+fn point(x: f32, y: f32) -> Point2D<f32> {
+    Point2D::new(x, y)
+}
+
+fn end_point(segment: &PathSegment) -> Point2D<f32> {
+    match *segment {
+        PathSegment::MoveTo(point) |
+        PathSegment::LineTo(point) |
+        PathSegment::QuadraticCurveTo(_, point) |
+        PathSegment::BezierCurveTo(_, _, point) => point,
+        PathSegment::ClosePath => panic!("closepath has no end point"),
+    }
+}
+
+fn assert_close(actual: Point2D<f32>, expected: Point2D<f32>) {
+    assert!((actual.x - expected.x).abs() < 1e-3 && (actual.y - expected.y).abs() < 1e-3,
+            "expected {:?}, got {:?}", expected, actual);
+}
+
+#[test]
+fn test_absolute_commands() {
+    assert_eq!(parse("M 10 20 L 30 40 Z"), vec![
+        PathSegment::MoveTo(point(10., 20.)),
+        PathSegment::LineTo(point(30., 40.)),
+        PathSegment::ClosePath,
+    ]);
+    assert_eq!(parse("M0,0 Q10,0 10,10 C20,10 20,20 30,20"), vec![
+        PathSegment::MoveTo(point(0., 0.)),
+        PathSegment::QuadraticCurveTo(point(10., 0.), point(10., 10.)),
+        PathSegment::BezierCurveTo(point(20., 10.), point(20., 20.), point(30., 20.)),
+    ]);
+}
+
+#[test]
+fn test_relative_and_implicit_commands() {
+    assert_eq!(parse("m10,10 20,0 0,20z"), vec![
+        PathSegment::MoveTo(point(10., 10.)),
+        PathSegment::LineTo(point(30., 10.)),
+        PathSegment::LineTo(point(30., 30.)),
+        PathSegment::ClosePath,
+    ]);
+    assert_eq!(parse("M0 0H10V5h-5v-5"), vec![
+        PathSegment::MoveTo(point(0., 0.)),
+        PathSegment::LineTo(point(10., 0.)),
+        PathSegment::LineTo(point(10., 5.)),
+        PathSegment::LineTo(point(5., 5.)),
+        PathSegment::LineTo(point(5., 0.)),
+    ]);
+}
+
+#[test]
+fn test_compact_numbers() {
+    assert_eq!(parse("M1.5.5-1e1 2"), vec![
+        PathSegment::MoveTo(point(1.5, 0.5)),
+        PathSegment::LineTo(point(-10., 2.)),
+    ]);
+}
+
+#[test]
+fn test_smooth_curves() {
+    assert_eq!(parse("M0 0C10 0 20 10 20 20S30 40 40 40"), vec![
+        PathSegment::MoveTo(point(0., 0.)),
+        PathSegment::BezierCurveTo(point(10., 0.), point(20., 10.), point(20., 20.)),
+        PathSegment::BezierCurveTo(point(20., 30.), point(30., 40.), point(40., 40.)),
+    ]);
+    assert_eq!(parse("M0 0T10 10"), vec![
+        PathSegment::MoveTo(point(0., 0.)),
+        PathSegment::QuadraticCurveTo(point(0., 0.), point(10., 10.)),
+    ]);
+}
+
+#[test]
+fn test_errors_keep_preceding_segments() {
+    assert_eq!(parse("L 10 10"), vec![]);
+    assert_eq!(parse("M 0 0 L 10 10 L 20"), vec![
+        PathSegment::MoveTo(point(0., 0.)),
+        PathSegment::LineTo(point(10., 10.)),
+    ]);
+    assert_eq!(parse("M 0 0 X 10 10"), vec![PathSegment::MoveTo(point(0., 0.))]);
+}
+
+#[test]
+fn test_arcs() {
+    let segments = parse("M 0 0 A 10 10 0 0 1 20 0");
+    assert_eq!(segments.len(), 3);
+    assert_close(end_point(&segments[1]), point(10., -10.));
+    assert_close(end_point(&segments[2]), point(20., 0.));
+
+    // Radii that are too small get scaled up to reach the end point.
+    let segments = parse("M 0 0 A 1 1 0 0 0 20 0");
+    assert_close(end_point(&segments[1]), point(10., 10.));
+    assert_close(end_point(segments.last().unwrap()), point(20., 0.));
+
+    assert_eq!(parse("M 0 0 A 0 10 0 0 1 20 0"), vec![
+        PathSegment::MoveTo(point(0., 0.)),
+        PathSegment::LineTo(point(20., 0.)),
+    ]);
+}
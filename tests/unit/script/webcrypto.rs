@@ -0,0 +1,177 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use openssl::bn::BigNumContext;
+use openssl::ec::{self, EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::symm::{self, Cipher};
+use script::test::webcrypto::{aes_192_cbc, aes_ctr, der, hkdf, hmac};
+use script::test::webcrypto::{rsa_oaep_decrypt, rsa_oaep_encrypt, rsa_pss_sign, rsa_pss_verify};
+
+fn hex(string: &str) -> Vec<u8> {
+    (0..string.len()).step_by(2).map(|i| u8::from_str_radix(&string[i..i + 2], 16).unwrap()).collect()
+}
+
+#[test]
+fn test_hmac() {
+    // https://tools.ietf.org/html/rfc4231#section-4.3
+    assert_eq!(hmac(MessageDigest::sha256(), b"Jefe", b"what do ya want for nothing?").unwrap(),
+               hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"));
+}
+
+#[test]
+fn test_aes_192() {
+    // https://csrc.nist.gov/publications/detail/fips/197/final, appendix C.2
+    let key = hex("000102030405060708090a0b0c0d0e0f1011121314151617");
+    let ciphertext = symm::encrypt(aes_192_cbc(), &key, Some(&[0; 16]), &hex("00112233445566778899aabbccddeeff"))
+        .unwrap();
+    assert_eq!(&ciphertext[..16], &hex("dda97ca4864cdfe06eaf70a0ec0d7191")[..]);
+}
+
+#[test]
+fn test_aes_ctr_counter_wrap() {
+    let key = hex("2b7e151628aed2a6abf7158809cf4f3c");
+    let counter = hex("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+    // With zero plaintext, the output is the encryption of the counter blocks.
+    let keystream = |block: &str| {
+        symm::encrypt(Cipher::aes_128_ecb(), &key, None, &hex(block)).unwrap()[..16].to_vec()
+    };
+
+    // Only the rightmost byte counts, so it wraps around to 00 and the
+    // byte to its left stays fe.
+    let output = aes_ctr(Cipher::aes_128_ctr(), &key, &counter, 8, &[0; 32]).unwrap();
+    assert_eq!(&output[..16], &keystream("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff")[..]);
+    assert_eq!(&output[16..], &keystream("f0f1f2f3f4f5f6f7f8f9fafbfcfdfe00")[..]);
+
+    // A 128-bit counter carries into the other bytes.
+    let output = aes_ctr(Cipher::aes_128_ctr(), &key, &counter, 128, &[0; 32]).unwrap();
+    assert_eq!(&output[16..], &keystream("f0f1f2f3f4f5f6f7f8f9fafbfcfdff00")[..]);
+
+    // Decryption is the same operation.
+    let ciphertext = aes_ctr(Cipher::aes_128_ctr(), &key, &counter, 8, b"a message over two blocks").unwrap();
+    assert_eq!(aes_ctr(Cipher::aes_128_ctr(), &key, &counter, 8, &ciphertext).unwrap(),
+               &b"a message over two blocks"[..]);
+
+    // A 1-bit counter has two blocks before it would repeat.
+    assert!(aes_ctr(Cipher::aes_128_ctr(), &key, &counter, 1, &[0; 33]).is_err());
+}
+
+#[test]
+fn test_hkdf() {
+    // https://tools.ietf.org/html/rfc5869#appendix-A.1
+    let key = vec![0x0b; 22];
+    assert_eq!(hkdf(MessageDigest::sha256(), &key, &hex("000102030405060708090a0b0c"),
+                    &hex("f0f1f2f3f4f5f6f7f8f9"), 42).unwrap(),
+               hex("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"));
+
+    // https://tools.ietf.org/html/rfc5869#appendix-A.3
+    assert_eq!(hkdf(MessageDigest::sha256(), &key, &[], &[], 42).unwrap(),
+               hex("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"));
+}
+
+#[test]
+fn test_hkdf_longest_output() {
+    let output = hkdf(MessageDigest::sha1(), b"key", b"salt", b"info", 255 * 20).unwrap();
+    assert_eq!(output.len(), 255 * 20);
+    let shorter = hkdf(MessageDigest::sha1(), b"key", b"salt", b"info", 30).unwrap();
+    assert_eq!(&output[..30], &shorter[..]);
+}
+
+#[test]
+fn test_der_lengths() {
+    let short = der::element(der::OCTET_STRING, &[1; 0x7f]);
+    assert_eq!(&short[..2], &[der::OCTET_STRING, 0x7f]);
+    let long = der::element(der::OCTET_STRING, &[1; 0x123]);
+    assert_eq!(&long[..4], &[der::OCTET_STRING, 0x82, 0x01, 0x23]);
+
+    let mut input = long.clone();
+    input.extend_from_slice(&[0xff]);
+    let (tag, contents, rest) = der::split(&input).unwrap();
+    assert_eq!(tag, der::OCTET_STRING);
+    assert_eq!(contents, &[1; 0x123][..]);
+    assert_eq!(rest, &[0xff]);
+
+    assert_eq!(der::split(&long[..long.len() - 1]), None);
+    assert_eq!(der::split(&[der::SEQUENCE, 0x80]), None);
+    assert_eq!(der::split(&[der::SEQUENCE, 0x85, 0, 0, 0, 0, 1]), None);
+}
+
+#[test]
+fn test_pkcs8_wrap() {
+    let wrapped = der::pkcs8_wrap(&der::element(der::NULL, &[]), &[0xaa, 0xbb]);
+    assert_eq!(wrapped, vec![der::SEQUENCE, 11,
+                             der::INTEGER, 1, 0,
+                             der::SEQUENCE, 2, der::NULL, 0,
+                             der::OCTET_STRING, 2, 0xaa, 0xbb]);
+}
+
+#[test]
+fn test_ec_private_key() {
+    let group = EcGroup::from_curve_name(nid::X9_62_PRIME256V1).unwrap();
+    let key = EcKey::generate(&group).unwrap();
+    let mut context = BigNumContext::new().unwrap();
+    let point = key.public_key().unwrap()
+                   .to_bytes(&group, ec::POINT_CONVERSION_UNCOMPRESSED, &mut context).unwrap();
+    let private = key.private_key().unwrap().to_vec();
+
+    let der = der::ec_private_key(der::OID_SECP256R1, &private, &point);
+    let parsed = EcKey::private_key_from_der(&der).unwrap();
+    assert!(parsed.check_key().is_ok());
+    assert_eq!(parsed.private_key().unwrap().to_vec(), private);
+}
+
+#[test]
+fn test_ecdsa_signature_conversion() {
+    let raw = vec![0x80, 0x01, 0x02, 0x03, 0x00, 0x00, 0x7f, 0x01];
+
+    // Integers are minimal, and get a leading zero when their top bit is set.
+    let der = der::ecdsa_raw_to_der(&raw);
+    assert_eq!(der, vec![der::SEQUENCE, 9,
+                         der::INTEGER, 5, 0x00, 0x80, 0x01, 0x02, 0x03,
+                         der::INTEGER, 2, 0x7f, 0x01]);
+    assert_eq!(der::ecdsa_der_to_raw(&der, 4), Some(raw));
+
+    // Integers which don't fit in a coordinate are rejected.
+    assert_eq!(der::ecdsa_der_to_raw(&der, 3), None);
+    assert_eq!(der::ecdsa_der_to_raw(&der[..der.len() - 1], 4), None);
+}
+
+#[test]
+fn test_rsa_oaep_round_trip() {
+    let rsa = Rsa::generate(1024).unwrap();
+    let public = PKey::from_rsa(Rsa::from_public_components(rsa.n().unwrap().to_owned().unwrap(),
+                                                            rsa.e().unwrap().to_owned().unwrap()).unwrap()).unwrap();
+    let private = PKey::from_rsa(rsa).unwrap();
+    let digest = MessageDigest::sha256();
+
+    let ciphertext = rsa_oaep_encrypt(&public, digest, b"label", b"message").unwrap();
+    assert_eq!(ciphertext.len(), 128);
+    assert_eq!(rsa_oaep_decrypt(&private, digest, b"label", &ciphertext).unwrap(), b"message");
+    assert!(rsa_oaep_decrypt(&private, digest, b"other label", &ciphertext).is_err());
+    assert!(rsa_oaep_decrypt(&private, MessageDigest::sha1(), b"label", &ciphertext).is_err());
+}
+
+#[test]
+fn test_rsa_pss_round_trip() {
+    let rsa = Rsa::generate(1024).unwrap();
+    let public = PKey::from_rsa(Rsa::from_public_components(rsa.n().unwrap().to_owned().unwrap(),
+                                                            rsa.e().unwrap().to_owned().unwrap()).unwrap()).unwrap();
+    let private = PKey::from_rsa(rsa).unwrap();
+    let digest = MessageDigest::sha384();
+
+    let signature = rsa_pss_sign(&private, digest, 32, b"message").unwrap();
+    assert!(rsa_pss_verify(&public, digest, 32, b"message", &signature).unwrap());
+    assert!(!rsa_pss_verify(&public, digest, 32, b"other message", &signature).unwrap());
+    assert!(!rsa_pss_verify(&public, digest, 20, b"message", &signature).unwrap());
+    assert!(!rsa_pss_verify(&public, digest, 32, b"message", &signature[1..]).unwrap());
+}
+
+#[test]
+fn test_rsa_pss_salt_too_long() {
+    let private = PKey::from_rsa(Rsa::generate(1024).unwrap()).unwrap();
+    assert!(rsa_pss_sign(&private, MessageDigest::sha256(), u32::max_value() as usize, b"message").is_err());
+}
@@ -0,0 +1,131 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use script::test::xpath::{Axis, BinaryOperator, Expr, KindTest, NodeTest, ParseError, PathStart, QName, Step};
+use script::test::xpath::parse;
+
+fn name(local_name: &str) -> NodeTest {
+    NodeTest::Name(QName { prefix: None, local_name: String::from(local_name) })
+}
+
+fn step(axis: Axis, node_test: NodeTest) -> Step {
+    Step { axis, node_test, predicates: vec![] }
+}
+
+fn descendant_or_self() -> Step {
+    step(Axis::DescendantOrSelf, NodeTest::Kind(KindTest::Node))
+}
+
+#[test]
+fn test_abbreviated_location_paths() {
+    assert_eq!(parse("//div/p"), Ok(Expr::Path(PathStart::Root, vec![
+        descendant_or_self(),
+        step(Axis::Child, name("div")),
+        step(Axis::Child, name("p")),
+    ])));
+    assert_eq!(parse("../@id"), Ok(Expr::Path(PathStart::Context, vec![
+        step(Axis::Parent, NodeTest::Kind(KindTest::Node)),
+        step(Axis::Attribute, name("id")),
+    ])));
+    assert_eq!(parse("/"), Ok(Expr::Path(PathStart::Root, vec![])));
+}
+
+#[test]
+fn test_axes_and_node_tests() {
+    assert_eq!(parse("ancestor-or-self::svg:*"), Ok(Expr::Path(PathStart::Context, vec![
+        step(Axis::AncestorOrSelf, NodeTest::Wildcard(Some(String::from("svg")))),
+    ])));
+    assert_eq!(parse("following-sibling :: processing-instruction('foo')"), Ok(Expr::Path(PathStart::Context, vec![
+        step(Axis::FollowingSibling, NodeTest::Kind(KindTest::ProcessingInstruction(Some(String::from("foo"))))),
+    ])));
+    assert_eq!(parse("sideways::node()"), Err(ParseError::UnknownAxis(String::from("sideways"))));
+}
+
+#[test]
+fn test_predicates() {
+    let mut item = step(Axis::Child, name("li"));
+    item.predicates = vec![
+        Expr::Number(2.),
+        Expr::Function(QName { prefix: None, local_name: String::from("last") }, vec![]),
+    ];
+    assert_eq!(parse("li[2][last()]"), Ok(Expr::Path(PathStart::Context, vec![item])));
+}
+
+#[test]
+fn test_operator_disambiguation() {
+    // A `*` or a name which follows an operand is an operator.
+    let multiply = parse("* * *").unwrap();
+    assert_eq!(multiply, Expr::Binary(
+        Box::new(Expr::Path(PathStart::Context, vec![step(Axis::Child, NodeTest::Wildcard(None))])),
+        BinaryOperator::Multiply,
+        Box::new(Expr::Path(PathStart::Context, vec![step(Axis::Child, NodeTest::Wildcard(None))])),
+    ));
+    assert_eq!(parse("div div div"), Ok(Expr::Binary(
+        Box::new(Expr::Path(PathStart::Context, vec![step(Axis::Child, name("div"))])),
+        BinaryOperator::Divide,
+        Box::new(Expr::Path(PathStart::Context, vec![step(Axis::Child, name("div"))])),
+    )));
+    assert_eq!(parse("a b"), Err(ParseError::UnknownOperator(String::from("b"))));
+}
+
+#[test]
+fn test_operator_precedence() {
+    assert_eq!(parse("1 + 2 * 3 = 7 or false()"), Ok(Expr::Binary(
+        Box::new(Expr::Binary(
+            Box::new(Expr::Binary(
+                Box::new(Expr::Number(1.)),
+                BinaryOperator::Add,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Number(2.)),
+                    BinaryOperator::Multiply,
+                    Box::new(Expr::Number(3.)),
+                )),
+            )),
+            BinaryOperator::Equal,
+            Box::new(Expr::Number(7.)),
+        )),
+        BinaryOperator::Or,
+        Box::new(Expr::Function(QName { prefix: None, local_name: String::from("false") }, vec![])),
+    )));
+    assert_eq!(parse("-.5 - -1"), Ok(Expr::Binary(
+        Box::new(Expr::Negate(Box::new(Expr::Number(0.5)))),
+        BinaryOperator::Subtract,
+        Box::new(Expr::Negate(Box::new(Expr::Number(1.)))),
+    )));
+}
+
+#[test]
+fn test_filter_expressions() {
+    assert_eq!(parse("(//a)[1]/@href"), Ok(Expr::Path(
+        PathStart::Expr(Box::new(Expr::Filter(
+            Box::new(Expr::Path(PathStart::Root, vec![descendant_or_self(), step(Axis::Child, name("a"))])),
+            vec![Expr::Number(1.)],
+        ))),
+        vec![step(Axis::Attribute, name("href"))],
+    )));
+}
+
+#[test]
+fn test_functions() {
+    assert_eq!(parse("concat('a', \"b\")"), Ok(Expr::Function(
+        QName { prefix: None, local_name: String::from("concat") },
+        vec![Expr::Literal(String::from("a")), Expr::Literal(String::from("b"))],
+    )));
+    assert_eq!(parse("frobnicate()"), Err(ParseError::UnknownFunction(String::from("frobnicate"))));
+    assert_eq!(parse("concat('a')"), Err(ParseError::WrongArgumentCount(String::from("concat"))));
+}
+
+#[test]
+fn test_invalid_expressions() {
+    assert_eq!(parse("'unterminated"), Err(ParseError::UnterminatedLiteral));
+    assert_eq!(parse("//"), Err(ParseError::UnexpectedEnd));
+    assert_eq!(parse("a]"), Err(ParseError::UnexpectedToken));
+    assert_eq!(parse("a # b"), Err(ParseError::UnexpectedCharacter('#')));
+}
+
+#[test]
+fn test_prefixes() {
+    let expr = parse("//svg:svg[xlink:href]/svg:*").unwrap();
+    assert_eq!(expr.prefixes(), vec![String::from("svg"), String::from("xlink")]);
+}
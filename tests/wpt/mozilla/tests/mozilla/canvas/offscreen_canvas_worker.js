@@ -0,0 +1,8 @@
+onmessage = function() {
+  var canvas = new OffscreenCanvas(20, 10);
+  var ctx = canvas.getContext('2d');
+  ctx.fillStyle = '#0f0';
+  ctx.fillRect(0, 0, 10, 10);
+  var bitmap = canvas.transferToImageBitmap();
+  postMessage(bitmap, [bitmap]);
+};
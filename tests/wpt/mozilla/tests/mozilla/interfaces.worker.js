@@ -7,8 +7,14 @@ importScripts("interfaces.js");
 
 // IMPORTANT: Do not change the list below without review from a DOM peer!
 test_interfaces([
+  "AbortController",
+  "AbortSignal",
   "Blob",
+  "BroadcastChannel",
+  "CanvasGradient",
+  "CanvasPattern",
   "CloseEvent",
+  "Crypto",
   "DOMMatrix",
   "DOMMatrixReadOnly",
   "DOMPoint",
@@ -30,8 +36,14 @@ test_interfaces([
   "FormData",
   "Headers",
   "History",
+  "ImageBitmap",
   "ImageData",
+  "MessageChannel",
   "MessageEvent",
+  "MessagePort",
+  "OffscreenCanvas",
+  "OffscreenCanvasRenderingContext2D",
+  "Path2D",
   "Performance",
   "PerformanceEntry",
   "PerformanceMark",
@@ -39,11 +51,13 @@ test_interfaces([
   "PerformanceObserver",
   "PerformanceObserverEntryList",
   "PerformancePaintTiming",
+  "PerformanceResourceTiming",
   "ProgressEvent",
   "Request",
   "Response",
   "TextDecoder",
   "TextEncoder",
+  "TextMetrics",
   "URL",
   "URLSearchParams",
   "WebSocket",
@@ -0,0 +1,10 @@
+onmessage = function(e) {
+  var port = e.ports[0];
+  port.onmessage = function(e) {
+    if (e.data == "close") {
+      port.close();
+    } else {
+      port.postMessage("echo: " + e.data);
+    }
+  };
+};